@@ -53,7 +53,7 @@ fn xaynet_sdk(py: Python, m: &PyModule) -> PyResult<()> {
 }
 
 #[pyclass]
-#[text_signature = "(url, scalar, /)"]
+#[text_signature = "(url, scalar, state, clipping_norm, noise_multiplier, /)"]
 struct Participant {
     inner: Option<xaynet_mobile::Participant>,
 }
@@ -61,7 +61,13 @@ struct Participant {
 #[pymethods]
 impl Participant {
     #[new]
-    pub fn new(url: String, scalar: f64, state: Option<Vec<u8>>) -> PyResult<Self> {
+    pub fn new(
+        url: String,
+        scalar: f64,
+        state: Option<Vec<u8>>,
+        clipping_norm: Option<f64>,
+        noise_multiplier: Option<f64>,
+    ) -> PyResult<Self> {
         sodiumoxide::init()
             .map_err(|_| CryptoInit::new_err("failed to initialize crypto library"))?;
 
@@ -76,6 +82,16 @@ impl Participant {
             settings.set_keys(xaynet_core::crypto::SigningKeyPair::generate());
             settings.set_url(url);
             settings.set_scalar(scalar);
+            if let Some(clipping_norm) = clipping_norm {
+                let local_privacy = xaynet_mobile::LocalPrivacy::new(
+                    clipping_norm,
+                    noise_multiplier.unwrap_or(0.0),
+                )
+                .map_err(|err| {
+                    ParticipantInit::new_err(format!("invalid local privacy settings: {}", err))
+                })?;
+                settings.set_local_privacy(local_privacy);
+            }
 
             xaynet_mobile::Participant::new(settings).map_err(|err| {
                 ParticipantInit::new_err(format!("failed to initialize participant: {}", err))
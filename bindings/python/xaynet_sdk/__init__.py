@@ -12,6 +12,8 @@ def spawn_participant(
     kwargs: dict = {},
     state: Optional[List[int]] = None,
     scalar: float = 1.0,
+    clipping_norm: Optional[float] = None,
+    noise_multiplier: float = 0.0,
 ):
     """
     Spawns a `InternalParticipant` in a separate thread and returns a participant handle.
@@ -25,6 +27,11 @@ def spawn_participant(
         kwargs: The kwargs that get passed to the constructor of the `participant` class.
         state: A serialized participant state. Defaults to `None`.
         scalar: The scalar used for masking. Defaults to `1.0`.
+        clipping_norm: If set, the local model is clipped to this L2 norm before it is
+            masked. Defaults to `None`.
+        noise_multiplier: If a `clipping_norm` is set, Gaussian noise with a standard
+            deviation of `clipping_norm * noise_multiplier` is added to each weight of the
+            clipped local model. Defaults to `0.0`.
 
     Returns:
         The `InternalParticipant`.
@@ -32,13 +39,20 @@ def spawn_participant(
     Raises:
         CryptoInit: If the initialization of the underling crypto library has failed.
         ParticipantInit: If the participant cannot be initialized. This is most
-            likely caused by an invalid `coordinator_url`.
+            likely caused by an invalid `coordinator_url` or invalid local privacy settings.
         ParticipantRestore: If the participant cannot be restored due to invalid
             serialized state. This exception can never be thrown if the `state` is `None`.
         Exception: Any exception that can be thrown during the instantiation of `participant`.
     """
     internal_participant = InternalParticipant(
-        coordinator_url, participant, args, kwargs, state, scalar
+        coordinator_url,
+        participant,
+        args,
+        kwargs,
+        state,
+        scalar,
+        clipping_norm,
+        noise_multiplier,
     )
     # spawns the internal participant in a thread.
     # `start` calls the `run` method of `InternalParticipant`
@@ -49,7 +63,11 @@ def spawn_participant(
 
 
 def spawn_async_participant(
-    coordinator_url: str, state: Optional[List[int]] = None, scalar: float = 1.0
+    coordinator_url: str,
+    state: Optional[List[int]] = None,
+    scalar: float = 1.0,
+    clipping_norm: Optional[float] = None,
+    noise_multiplier: float = 0.0,
 ) -> (AsyncParticipant, threading.Event):
     """
     Spawns a `AsyncParticipant` in a separate thread and returns a participant handle
@@ -60,6 +78,11 @@ def spawn_async_participant(
         coordinator_url: The url of the coordinator.
         state: A serialized participant state. Defaults to `None`.
         scalar: The scalar used for masking. Defaults to `1.0`.
+        clipping_norm: If set, the local model is clipped to this L2 norm before it is
+            masked. Defaults to `None`.
+        noise_multiplier: If a `clipping_norm` is set, Gaussian noise with a standard
+            deviation of `clipping_norm * noise_multiplier` is added to each weight of the
+            clipped local model. Defaults to `0.0`.
 
     Returns:
         A tuple which consists of an `AsyncParticipant` and a global model notifier.
@@ -67,11 +90,13 @@ def spawn_async_participant(
     Raises:
         CryptoInit: If the initialization of the underling crypto library has failed.
         ParticipantInit: If the participant cannot be initialized. This is most
-            likely caused by an invalid `coordinator_url`.
+            likely caused by an invalid `coordinator_url` or invalid local privacy settings.
         ParticipantRestore: If the participant cannot be restored due to invalid
             serialized state. This exception can never be thrown if the `state` is `None`.
     """
     notifier = threading.Event()
-    async_participant = AsyncParticipant(coordinator_url, notifier, state, scalar)
+    async_participant = AsyncParticipant(
+        coordinator_url, notifier, state, scalar, clipping_norm, noise_multiplier
+    )
     async_participant.start()
     return (async_participant, notifier)
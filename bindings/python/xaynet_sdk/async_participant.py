@@ -19,10 +19,12 @@ class AsyncParticipant(threading.Thread):
         notifier,
         state,
         scalar,
+        clipping_norm,
+        noise_multiplier,
     ):
         # xaynet rust participant
         self._xaynet_participant = xaynet_sdk.Participant(
-            coordinator_url, scalar, state
+            coordinator_url, scalar, state, clipping_norm, noise_multiplier
         )
 
         self._exit_event = threading.Event()
@@ -119,10 +119,12 @@ class InternalParticipant(threading.Thread):
         p_kwargs,
         state,
         scalar,
+        clipping_norm,
+        noise_multiplier,
     ):
         # xaynet rust participant
         self._xaynet_participant = xaynet_sdk.Participant(
-            coordinator_url, scalar, state
+            coordinator_url, scalar, state, clipping_norm, noise_multiplier
         )
 
         # https://github.com/python/cpython/blob/3.9/Lib/multiprocessing/process.py#L80
@@ -0,0 +1,109 @@
+use std::iter;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rayon::prelude::*;
+use xaynet_core::mask::{
+    Aggregation,
+    BoundType,
+    DataType,
+    FromPrimitives,
+    GroupType,
+    MaskConfig,
+    MaskConfigPair,
+    MaskObject,
+    Masker,
+    Model,
+    ModelType,
+    QuantizationType,
+    SparsityType,
+};
+
+const MODEL_LENGTH: usize = 100_000;
+const NB_MODELS: usize = 10;
+
+fn config(group_type: GroupType) -> MaskConfigPair {
+    MaskConfig {
+        group_type,
+        data_type: DataType::F32,
+        bound_type: BoundType::B0,
+        model_type: ModelType::M3,
+        sparsity_type: SparsityType::S0,
+        quantization_type: QuantizationType::Qmax,
+    }
+    .into()
+}
+
+fn masked_models(config: MaskConfigPair) -> Vec<MaskObject> {
+    let model = Model::from_primitives(iter::repeat(0.5_f32).take(MODEL_LENGTH)).unwrap();
+    iter::repeat_with(|| Masker::new(config).mask(1.0, &model).1)
+        .take(NB_MODELS)
+        .collect()
+}
+
+/// Aggregates the masked models sequentially into a single aggregator.
+fn aggregate(config: MaskConfigPair, objects: Vec<MaskObject>) -> Aggregation {
+    let mut aggregation = Aggregation::new(config, MODEL_LENGTH);
+    for object in objects {
+        aggregation.aggregate(object);
+    }
+    aggregation
+}
+
+/// Aggregates the masked models in parallel into one aggregator per range of the vector.
+fn aggregate_sharded(config: MaskConfigPair, objects: Vec<MaskObject>) -> Aggregation {
+    let mut shards = Aggregation::new(config, MODEL_LENGTH)
+        .into_shards(rayon::current_num_threads())
+        .into_iter()
+        .map(|shard| (shard, Vec::with_capacity(NB_MODELS)))
+        .collect::<Vec<_>>();
+    let lengths = shards
+        .iter()
+        .map(|(shard, _)| shard.len())
+        .collect::<Vec<_>>();
+    for object in objects {
+        for ((_, objects), object) in shards.iter_mut().zip(object.split(&lengths)) {
+            objects.push(object);
+        }
+    }
+
+    let shards = shards
+        .into_par_iter()
+        .map(|(mut shard, objects)| {
+            for object in objects {
+                shard.aggregate(object);
+            }
+            shard
+        })
+        .collect();
+    Aggregation::merge(shards).unwrap()
+}
+
+pub fn aggregate_models(c: &mut Criterion) {
+    let mut bench = c.benchmark_group("aggregate_models");
+    for (name, group_type) in [("prime", GroupType::Prime), ("power2", GroupType::Power2)].iter() {
+        let config = config(*group_type);
+        let objects = masked_models(config);
+
+        bench.bench_function(format!("sequential {}", name), |b| {
+            b.iter_batched(
+                || objects.clone(),
+                |objects| aggregate(config, black_box(objects)),
+                BatchSize::LargeInput,
+            )
+        });
+
+        bench.bench_function(format!("sharded {}", name), |b| {
+            b.iter_batched(
+                || objects.clone(),
+                |objects| aggregate_sharded(config, black_box(objects)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+criterion_group!(name = benches;
+                 // aggregating the models takes a while, hence fewer samples suffice
+                 config = Criterion::default().sample_size(10);
+                 targets = aggregate_models);
+criterion_main!(benches);
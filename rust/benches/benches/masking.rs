@@ -0,0 +1,91 @@
+use std::iter;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use xaynet_core::{
+    mask::{
+        Aggregation,
+        BoundType,
+        DataType,
+        FromPrimitives,
+        GroupType,
+        MaskConfig,
+        MaskConfigPair,
+        MaskVect,
+        Masker,
+        Model,
+        ModelType,
+        QuantizationType,
+        SparsityType,
+    },
+    message::{FromBytes, ToBytes},
+};
+
+const MODEL_LENGTH: usize = 100_000;
+
+fn config(group_type: GroupType) -> MaskConfigPair {
+    MaskConfig {
+        group_type,
+        data_type: DataType::F32,
+        bound_type: BoundType::B0,
+        model_type: ModelType::M3,
+        sparsity_type: SparsityType::S0,
+        quantization_type: QuantizationType::Qmax,
+    }
+    .into()
+}
+
+fn model() -> Model {
+    Model::from_primitives(iter::repeat(0.5_f32).take(MODEL_LENGTH)).unwrap()
+}
+
+pub fn mask_model(c: &mut Criterion) {
+    let mut bench = c.benchmark_group("mask_model");
+    for (name, group_type) in [("prime", GroupType::Prime), ("power2", GroupType::Power2)].iter() {
+        let config = config(*group_type);
+        let model = model();
+
+        bench.bench_function(*name, |b| {
+            b.iter(|| Masker::new(config).mask(1.0, black_box(&model)))
+        });
+    }
+}
+
+pub fn serialize_masked_model(c: &mut Criterion) {
+    let mut bench = c.benchmark_group("serialize_masked_model");
+    for (name, group_type) in [("prime", GroupType::Prime), ("power2", GroupType::Power2)].iter() {
+        let (_, masked) = Masker::new(config(*group_type)).mask(1.0, &model());
+        let mut buffer = vec![0; masked.vect.buffer_length()];
+
+        bench.bench_function(format!("to_bytes {}", name), |b| {
+            b.iter(|| black_box(&masked.vect).to_bytes(&mut buffer))
+        });
+
+        bench.bench_function(format!("from_byte_slice {}", name), |b| {
+            b.iter(|| MaskVect::from_byte_slice(&black_box(&buffer)).unwrap())
+        });
+    }
+}
+
+pub fn unmask_model(c: &mut Criterion) {
+    let mut bench = c.benchmark_group("unmask_model");
+    for (name, group_type) in [("prime", GroupType::Prime), ("power2", GroupType::Power2)].iter() {
+        let config = config(*group_type);
+        let (seed, masked) = Masker::new(config).mask(1.0, &model());
+        let mask = seed.derive_mask(MODEL_LENGTH, config);
+        let aggregation = Aggregation::from(masked);
+
+        bench.bench_function(*name, |b| {
+            b.iter_batched(
+                || (aggregation.clone(), mask.clone()),
+                |(aggregation, mask)| aggregation.unmask(black_box(mask)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+criterion_group!(name = benches;
+                 // masking and unmasking the models takes a while, hence fewer samples suffice
+                 config = Criterion::default().sample_size(10);
+                 targets = mask_model, serialize_masked_model, unmask_model);
+criterion_main!(benches);
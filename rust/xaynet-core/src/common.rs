@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
 use sodiumoxide::{self, crypto::box_};
+use thiserror::Error;
 
-use crate::{crypto::ByteObject, mask::MaskConfigPair, CoordinatorPublicKey};
+use crate::{
+    crypto::ByteObject,
+    mask::{MaskConfigPair, MaskSelection},
+    CoordinatorPublicKey,
+};
 
 /// The round parameters.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -18,6 +23,38 @@ pub struct RoundParameters {
     pub mask_config: MaskConfigPair,
     /// The length of the model.
     pub model_length: usize,
+    /// Fraction of sum participants whose shares are required to reconstruct a mask seed. If
+    /// `None`, the mask seeds are not secret-shared and every sum participant sends the same
+    /// aggregated mask. Otherwise, every sum participant sends its part of the aggregated mask and
+    /// the parts of the sum participants who dropped out are reconstructed from the shares of
+    /// the others.
+    pub seed_sharing_threshold: Option<f64>,
+    /// Whether the participants mask the difference between their local model and the global
+    /// model of the previous round instead of their local model. A missing global model or one of
+    /// another length counts as a model of zeros.
+    pub model_delta: bool,
+}
+
+impl RoundParameters {
+    /// Gets the number of masked weights per model, which is smaller than the model length for
+    /// sparse masking configurations.
+    pub fn mask_length(&self) -> usize {
+        self.mask_config
+            .vect
+            .sparsity_type
+            .nb_selected(self.model_length)
+    }
+
+    /// Selects the masked weights of the models of this round.
+    ///
+    /// Returns `None` if all weights are masked.
+    pub fn mask_selection(&self) -> Option<MaskSelection> {
+        MaskSelection::new(
+            &self.seed,
+            self.model_length,
+            self.mask_config.vect.sparsity_type,
+        )
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -45,3 +82,120 @@ impl ByteObject for RoundSeed {
         self.0.as_ref()
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// The kind of error why the coordinator failed to handle a PET message.
+pub enum MessageErrorKind {
+    /// The message could not be decrypted with the coordinator secret key.
+    Decrypt,
+    /// The message could not be parsed.
+    Parsing,
+    /// The message signature is invalid.
+    InvalidSignature,
+    /// The message is encrypted for another coordinator public key, e.g. of a previous round.
+    InvalidCoordinatorPublicKey,
+    /// The message is not expected in the current phase.
+    UnexpectedMessage,
+    /// The participant is not eligible for the task of the message.
+    NotEligible,
+    /// The message was discarded, e.g. because the phase doesn't accept any more messages.
+    Discarded,
+    /// The message was rejected, e.g. because the participant sent it already.
+    Rejected,
+    /// The message could not be handled due to an internal error of the coordinator.
+    Internal,
+    /// The message exceeds the maximum size of its kind.
+    TooLarge,
+    /// The participant sent too many messages, either in a short time or in parallel.
+    RateLimited,
+    /// The participant is neither allowed nor presented a valid credential.
+    NotAdmitted,
+}
+
+impl MessageErrorKind {
+    /// Checks whether sending the same message again may succeed.
+    ///
+    /// All errors except for internal errors and exceeded rate limits are caused by the message
+    /// itself or by the current phase and will occur again.
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::Internal | Self::RateLimited)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Error)]
+#[error("{kind:?}: {description}")]
+/// The error body of a PET message which the coordinator failed to handle.
+pub struct MessageError {
+    /// The machine-readable kind of the error.
+    pub kind: MessageErrorKind,
+    /// A human-readable description of the error.
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A notification which the coordinator pushes to participants about the progress of a round.
+///
+/// Participants can wait for these events instead of polling the coordinator.
+pub enum RoundEvent {
+    /// A new round started with new round parameters.
+    NewRound { round_id: u64 },
+    /// The coordinator moved to another phase.
+    Phase { round_id: u64, phase: String },
+    /// The sum dictionary is available.
+    SumDict { round_id: u64 },
+    /// The seed dictionary is available.
+    SeedDict { round_id: u64 },
+    /// A new global model is available.
+    Model { round_id: u64 },
+}
+
+impl RoundEvent {
+    /// Gets the name of the event.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NewRound { .. } => "new_round",
+            Self::Phase { .. } => "phase",
+            Self::SumDict { .. } => "sum_dict",
+            Self::SeedDict { .. } => "seed_dict",
+            Self::Model { .. } => "model",
+        }
+    }
+
+    /// Gets the ID of the round in which the event occurred.
+    pub fn round_id(&self) -> u64 {
+        match self {
+            Self::NewRound { round_id }
+            | Self::Phase { round_id, .. }
+            | Self::SumDict { round_id }
+            | Self::SeedDict { round_id }
+            | Self::Model { round_id } => *round_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_error_display() {
+        let error = MessageError {
+            kind: MessageErrorKind::NotEligible,
+            description: "participant is not eligible for sum task".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "NotEligible: participant is not eligible for sum task"
+        );
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(MessageErrorKind::Internal.is_retryable());
+        assert!(MessageErrorKind::RateLimited.is_retryable());
+        assert!(!MessageErrorKind::TooLarge.is_retryable());
+        assert!(!MessageErrorKind::Discarded.is_retryable());
+        assert!(!MessageErrorKind::NotEligible.is_retryable());
+        assert!(!MessageErrorKind::NotAdmitted.is_retryable());
+    }
+}
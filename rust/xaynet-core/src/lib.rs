@@ -77,7 +77,8 @@ pub type ParticipantTaskSignature = Signature;
 pub type SumDict = HashMap<SumParticipantPublicKey, SumParticipantEphemeralPublicKey>;
 
 /// Local seed dictionaries are sent by update participants. They contain the participant's masking
-/// seed, encrypted with the ephemeral public key of each sum participant.
+/// seed, encrypted with the ephemeral public key of each sum participant. If the mask seeds are
+/// secret-shared, they contain the masking seeds and seed shares of each sum participant instead.
 pub type LocalSeedDict = HashMap<SumParticipantPublicKey, mask::seed::EncryptedMaskSeed>;
 
 /// A dictionary created during the update phase of the protocol. The global seed dictionary is
@@ -87,3 +88,12 @@ pub type SeedDict = HashMap<SumParticipantPublicKey, UpdateSeedDict>;
 
 /// Values of [`SeedDict`]. Sent to sum participants.
 pub type UpdateSeedDict = HashMap<UpdateParticipantPublicKey, mask::seed::EncryptedMaskSeed>;
+
+/// Seed share dictionaries are sent by sum participants if the mask seeds are secret-shared and some
+/// sum participants dropped out. They map each sum participant who dropped out to the decrypted
+/// shares of its masking seeds.
+pub type SeedShareDict = HashMap<SumParticipantPublicKey, UpdateSeedShareDict>;
+
+/// Values of [`SeedShareDict`]. They contain the decrypted share of the masking seed of every update
+/// participant.
+pub type UpdateSeedShareDict = HashMap<UpdateParticipantPublicKey, mask::seed::MaskSeed>;
@@ -6,22 +6,56 @@
 
 pub(crate) mod serialization;
 
-use std::convert::TryFrom;
+use std::{
+    collections::HashMap,
+    convert::{TryFrom, TryInto},
+    fmt,
+    sync::{Mutex, PoisonError},
+};
 
 use num::{
     bigint::{BigInt, BigUint},
     rational::Ratio,
-    traits::{pow::Pow, Num},
+    traits::{pow::Pow, Num, One, Signed, ToPrimitive},
+};
+use once_cell::sync::Lazy;
+use serde::{
+    de::{self, Unexpected, Visitor},
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
 };
-use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::mask::model::Model;
+
 // target dependent maximum bytes per mask object element
 #[cfg(target_pointer_width = "16")]
 const MAX_BPN: u64 = u16::MAX as u64;
 #[cfg(target_pointer_width = "32")]
 const MAX_BPN: u64 = u32::MAX as u64;
 
+/// The byte representation of the custom variants of the bound, model and quantization types.
+pub(crate) const CUSTOM_TAG: u8 = 254;
+
+/// The maximum number of decimal places of custom quantization types, which is the precision of
+/// bounded [`DataType::F64`] values.
+pub const MAX_DECIMAL_PLACES: u8 = 20;
+
+// the bases for the Miller-Rabin primality test, which is deterministic for numbers smaller than
+// `3.3 * 10^24` with the first 13 bases
+const PRIME_BASES: [u8; 20] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71,
+];
+
+// the maximum number of cached group orders of uncatalogued masking configurations, the cache is
+// cleared when it is full since the configurations of received messages may be arbitrary
+const ORDER_CACHE_CAPACITY: usize = 64;
+
+/// The cached group orders of the uncatalogued masking configurations.
+static COMPUTED_ORDERS: Lazy<Mutex<HashMap<MaskConfig, BigUint>>> = Lazy::new(Default::default);
+
 #[derive(Debug, Error)]
 /// Errors related to invalid masking configurations.
 pub enum InvalidMaskConfigError {
@@ -33,6 +67,10 @@ pub enum InvalidMaskConfigError {
     BoundType,
     #[error("invalid model type")]
     ModelType,
+    #[error("invalid sparsity type")]
+    SparsityType,
+    #[error("invalid quantization type")]
+    QuantizationType,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -88,24 +126,109 @@ impl TryFrom<u8> for DataType {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[repr(u8)]
+impl DataType {
+    /// Gets the absolute maximum value of the primitive data type.
+    fn max_abs(&self) -> Ratio<BigInt> {
+        match self {
+            // safe unwraps: all numbers are finite
+            DataType::F32 => Ratio::from_float(f32::MAX).unwrap(),
+            DataType::F64 => Ratio::from_float(f64::MAX).unwrap(),
+            DataType::I32 => Ratio::from_integer(-BigInt::from(i32::MIN)),
+            DataType::I64 => Ratio::from_integer(-BigInt::from(i64::MIN)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 /// The bounds of the numerical values.
 ///
 /// For a value `v` to be absolutely bounded by another value `b`, it has to hold that
 /// `-b <= v <= b` or equivalently `|v| <= b`.
 pub enum BoundType {
     /// Numerical values absolutely bounded by 1.
-    B0 = 0,
+    B0,
     /// Numerical values absolutely bounded by 100.
-    B2 = 2,
+    B2,
     /// Numerical values absolutely bounded by 10_000.
-    B4 = 4,
+    B4,
     /// Numerical values absolutely bounded by 1_000_000.
-    B6 = 6,
+    B6,
     /// Numerical values absolutely bounded by their original primitive data type's maximum absolute
     /// value.
-    Bmax = 255,
+    Bmax,
+    /// Numerical values absolutely bounded by a custom positive integer.
+    Custom(u64),
+}
+
+impl BoundType {
+    /// Gets the bound type for numerical values absolutely bounded by `bound`.
+    ///
+    /// The catalogued variants are preferred over custom ones for equal bounds. A bound of zero
+    /// is raised to one.
+    pub fn from_bound(bound: u64) -> Self {
+        match bound {
+            0 | 1 => BoundType::B0,
+            100 => BoundType::B2,
+            10_000 => BoundType::B4,
+            1_000_000 => BoundType::B6,
+            _ => BoundType::Custom(bound),
+        }
+    }
+
+    /// Gets the smallest bound type for numerical values of the given `data_type` which absolutely
+    /// bounds all weights of the `model`.
+    pub fn fitting(model: &Model, data_type: DataType) -> Self {
+        let bound = model
+            .iter()
+            .map(|weight| weight.abs().ceil().to_integer())
+            .max()
+            .unwrap_or_else(BigInt::one);
+        match bound.to_u64() {
+            Some(bound) if Ratio::from_integer(BigInt::from(bound)) < data_type.max_abs() => {
+                Self::from_bound(bound)
+            }
+            _ => BoundType::Bmax,
+        }
+    }
+
+    /// Gets the absolute bound of the numerical values.
+    ///
+    /// The bound depends on the data type for [`BoundType::Bmax`], hence it is not available.
+    pub fn bound(&self) -> Option<u64> {
+        match self {
+            BoundType::B0 => Some(1),
+            BoundType::B2 => Some(100),
+            BoundType::B4 => Some(10_000),
+            BoundType::B6 => Some(1_000_000),
+            BoundType::Bmax => None,
+            BoundType::Custom(bound) => Some(*bound),
+        }
+    }
+
+    /// Gets the byte representation of the bound type.
+    pub fn tag(&self) -> u8 {
+        match self {
+            BoundType::B0 => 0,
+            BoundType::B2 => 2,
+            BoundType::B4 => 4,
+            BoundType::B6 => 6,
+            BoundType::Bmax => 255,
+            BoundType::Custom(_) => CUSTOM_TAG,
+        }
+    }
+
+    /// Gets the bound type from its byte representation, where the `value` is the bound of a
+    /// custom bound type and ignored otherwise.
+    ///
+    /// # Errors
+    /// Fails if the `tag` is unknown or if the custom bound is zero.
+    pub fn from_tag(tag: u8, value: u64) -> Result<Self, InvalidMaskConfigError> {
+        match tag {
+            CUSTOM_TAG if value > 0 => Ok(BoundType::Custom(value)),
+            CUSTOM_TAG => Err(InvalidMaskConfigError::BoundType),
+            _ => Self::try_from(tag),
+        }
+    }
 }
 
 impl TryFrom<u8> for BoundType {
@@ -118,29 +241,85 @@ impl TryFrom<u8> for BoundType {
             4 => Ok(BoundType::B4),
             6 => Ok(BoundType::B6),
             255 => Ok(BoundType::Bmax),
-            _ => Err(InvalidMaskConfigError::ModelType),
+            _ => Err(InvalidMaskConfigError::BoundType),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 /// The maximum number of models to be aggregated.
 pub enum ModelType {
     /// At most 1_000 models to be aggregated.
-    M3 = 3,
+    M3,
     /// At most 1_000_000 models to be aggregated.
-    M6 = 6,
+    M6,
     /// At most 1_000_000_000 models to be aggregated.
-    M9 = 9,
+    M9,
     /// At most 1_000_000_000_000 models to be aggregated.
-    M12 = 12,
+    M12,
+    /// At most a custom positive number of models to be aggregated.
+    Custom(u64),
 }
 
 impl ModelType {
+    /// Gets the model type for at most `nb_models` models to be aggregated.
+    ///
+    /// The catalogued variants are preferred over custom ones for equal numbers of models. A
+    /// number of zero is raised to one.
+    pub fn from_nb_models(nb_models: u64) -> Self {
+        match nb_models {
+            0 => ModelType::Custom(1),
+            1_000 => ModelType::M3,
+            1_000_000 => ModelType::M6,
+            1_000_000_000 => ModelType::M9,
+            1_000_000_000_000 => ModelType::M12,
+            _ => ModelType::Custom(nb_models),
+        }
+    }
+
     /// Gets the maximum number of models that can be aggregated for this model type.
+    ///
+    /// The number is saturated at [`usize::MAX`] on targets smaller than 64 bits.
     pub fn max_nb_models(&self) -> usize {
-        10_usize.pow(*self as u8 as u32)
+        match self {
+            ModelType::M3 => 1_000,
+            ModelType::M6 => 1_000_000,
+            ModelType::M9 => 1_000_000_000,
+            ModelType::M12 => 1_000_000_000_000_u64.try_into().unwrap_or(usize::MAX),
+            ModelType::Custom(nb_models) => (*nb_models).try_into().unwrap_or(usize::MAX),
+        }
+    }
+
+    /// Gets the byte representation of the model type.
+    pub fn tag(&self) -> u8 {
+        match self {
+            ModelType::M3 => 3,
+            ModelType::M6 => 6,
+            ModelType::M9 => 9,
+            ModelType::M12 => 12,
+            ModelType::Custom(_) => CUSTOM_TAG,
+        }
+    }
+
+    /// Gets the model type from its byte representation, where the `value` is the maximum number
+    /// of models of a custom model type and ignored otherwise.
+    ///
+    /// # Errors
+    /// Fails if the `tag` is unknown or if the custom maximum number of models is zero.
+    pub fn from_tag(tag: u8, value: u64) -> Result<Self, InvalidMaskConfigError> {
+        match tag {
+            CUSTOM_TAG if value > 0 => Ok(ModelType::Custom(value)),
+            CUSTOM_TAG => Err(InvalidMaskConfigError::ModelType),
+            _ => Self::try_from(tag),
+        }
+    }
+
+    /// Gets the maximum number of models as a big integer.
+    fn max_nb_models_big(&self) -> BigUint {
+        match self {
+            ModelType::Custom(nb_models) => BigUint::from(*nb_models),
+            _ => BigUint::from(10_u8).pow(self.tag()),
+        }
     }
 }
 
@@ -158,6 +337,234 @@ impl TryFrom<u8> for ModelType {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+/// The fraction of the weights of a model to be masked.
+///
+/// The masked weights are a random subset of the model weights which is agreed upon by all
+/// participants of a round, because it is derived from the round seed. The other weights are
+/// neither masked nor sent to the coordinator.
+pub enum SparsityType {
+    /// All weights are masked.
+    S0 = 0,
+    /// One in 10 weights is masked.
+    S1 = 1,
+    /// One in 100 weights is masked.
+    S2 = 2,
+    /// One in 1_000 weights is masked.
+    S3 = 3,
+}
+
+impl SparsityType {
+    /// Gets the number of weights to be masked of a model with `len` weights.
+    ///
+    /// At least one weight is masked for non-empty models.
+    pub fn nb_selected(&self, len: usize) -> usize {
+        let fraction = 10_usize.pow(*self as u8 as u32);
+        len / fraction + (len % fraction != 0) as usize
+    }
+}
+
+impl Default for SparsityType {
+    fn default() -> Self {
+        SparsityType::S0
+    }
+}
+
+impl TryFrom<u8> for SparsityType {
+    type Error = InvalidMaskConfigError;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            0 => Ok(SparsityType::S0),
+            1 => Ok(SparsityType::S1),
+            2 => Ok(SparsityType::S2),
+            3 => Ok(SparsityType::S3),
+            _ => Err(InvalidMaskConfigError::SparsityType),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// The precision of the numerical values to be masked.
+///
+/// Quantized values are stochastically rounded to the chosen number of decimal places before
+/// masking, which shrinks the finite group and hence the size of the masked models. Quantization
+/// only applies to bounded values, it is ignored for [`BoundType::Bmax`].
+pub enum QuantizationType {
+    /// Numerical values quantized to 2 decimal places.
+    Q2,
+    /// Numerical values quantized to 4 decimal places.
+    Q4,
+    /// Numerical values with the full precision of their original primitive data type.
+    Qmax,
+    /// Numerical values quantized to a custom number of decimal places, which is at most
+    /// [`MAX_DECIMAL_PLACES`].
+    Custom(u8),
+}
+
+impl QuantizationType {
+    /// Gets the quantization type for numerical values quantized to `decimal_places`.
+    ///
+    /// The catalogued variants are preferred over custom ones for equal decimal places.
+    pub fn from_decimal_places(decimal_places: u8) -> Self {
+        match decimal_places {
+            2 => QuantizationType::Q2,
+            4 => QuantizationType::Q4,
+            _ => QuantizationType::Custom(decimal_places),
+        }
+    }
+
+    /// Gets the number of decimal places of the quantized numerical values.
+    ///
+    /// The precision depends on the data type for [`QuantizationType::Qmax`], hence it is not
+    /// available.
+    pub fn decimal_places(&self) -> Option<u8> {
+        match self {
+            QuantizationType::Q2 => Some(2),
+            QuantizationType::Q4 => Some(4),
+            QuantizationType::Qmax => None,
+            QuantizationType::Custom(decimal_places) => Some(*decimal_places),
+        }
+    }
+
+    /// Gets the byte representation of the quantization type.
+    pub fn tag(&self) -> u8 {
+        match self {
+            QuantizationType::Q2 => 2,
+            QuantizationType::Q4 => 4,
+            QuantizationType::Qmax => 255,
+            QuantizationType::Custom(_) => CUSTOM_TAG,
+        }
+    }
+
+    /// Gets the quantization type from its byte representation, where the `value` is the number
+    /// of decimal places of a custom quantization type and ignored otherwise.
+    ///
+    /// # Errors
+    /// Fails if the `tag` is unknown or if the custom decimal places exceed
+    /// [`MAX_DECIMAL_PLACES`].
+    pub fn from_tag(tag: u8, value: u64) -> Result<Self, InvalidMaskConfigError> {
+        match tag {
+            CUSTOM_TAG if value <= MAX_DECIMAL_PLACES as u64 => {
+                Ok(QuantizationType::Custom(value as u8))
+            }
+            CUSTOM_TAG => Err(InvalidMaskConfigError::QuantizationType),
+            _ => Self::try_from(tag),
+        }
+    }
+}
+
+impl Default for QuantizationType {
+    fn default() -> Self {
+        QuantizationType::Qmax
+    }
+}
+
+impl TryFrom<u8> for QuantizationType {
+    type Error = InvalidMaskConfigError;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            2 => Ok(QuantizationType::Q2),
+            4 => Ok(QuantizationType::Q4),
+            255 => Ok(QuantizationType::Qmax),
+            _ => Err(InvalidMaskConfigError::QuantizationType),
+        }
+    }
+}
+
+/// Implements the serde representation for the types with custom variants.
+///
+/// Human-readable formats represent the catalogued variants by their names and the custom variants
+/// by their values, e.g. `bound_type = "B2"` or `bound_type = 250` in TOML, where the values may
+/// be given as strings as well, e.g. for environment variables. Other formats represent the
+/// variants by their byte representations and values.
+macro_rules! impl_serde_with_custom {
+    ($type:ident, $expecting:literal, [$($variant:ident),+]) => {
+        impl $type {
+            /// Gets the value of a custom variant.
+            fn custom_value(&self) -> Option<u64> {
+                match self {
+                    $type::Custom(value) => Some(u64::from(*value)),
+                    _ => None,
+                }
+            }
+        }
+
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if serializer.is_human_readable() {
+                    match self {
+                        $($type::$variant => serializer.serialize_str(stringify!($variant)),)+
+                        $type::Custom(value) => serializer.serialize_u64(u64::from(*value)),
+                    }
+                } else {
+                    (self.tag(), self.custom_value().unwrap_or_default()).serialize(serializer)
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct TypeVisitor;
+
+                impl<'de> Visitor<'de> for TypeVisitor {
+                    type Value = $type;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str($expecting)
+                    }
+
+                    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                        $type::from_tag(CUSTOM_TAG, value)
+                            .map_err(|_| E::invalid_value(Unexpected::Unsigned(value), &self))
+                    }
+
+                    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                        u64::try_from(value)
+                            .map_err(|_| E::invalid_value(Unexpected::Signed(value), &self))
+                            .and_then(|value| self.visit_u64(value))
+                    }
+
+                    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                        match value {
+                            $(stringify!($variant) => Ok($type::$variant),)+
+                            _ => value
+                                .parse()
+                                .map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+                                .and_then(|value| self.visit_u64(value)),
+                        }
+                    }
+                }
+
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_any(TypeVisitor)
+                } else {
+                    let (tag, value) = <(u8, u64)>::deserialize(deserializer)?;
+                    $type::from_tag(tag, value).map_err(de::Error::custom)
+                }
+            }
+        }
+    };
+}
+
+impl_serde_with_custom!(
+    BoundType,
+    "one of B0, B2, B4, B6, Bmax or a positive integer",
+    [B0, B2, B4, B6, Bmax]
+);
+impl_serde_with_custom!(
+    ModelType,
+    "one of M3, M6, M9, M12 or a positive integer",
+    [M3, M6, M9, M12]
+);
+impl_serde_with_custom!(
+    QuantizationType,
+    "one of Q2, Q4, Qmax or a number of decimal places",
+    [Q2, Q4, Qmax]
+);
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A masking configuration.
 ///
@@ -171,6 +578,12 @@ pub struct MaskConfig {
     pub bound_type: BoundType,
     /// The maximum number of models to be aggregated.
     pub model_type: ModelType,
+    /// The fraction of the weights of a model to be masked.
+    #[serde(default)]
+    pub sparsity_type: SparsityType,
+    /// The precision of the numerical values to be masked.
+    #[serde(default)]
+    pub quantization_type: QuantizationType,
 }
 
 impl MaskConfig {
@@ -194,173 +607,267 @@ impl MaskConfig {
 
     /// Gets the additional shift value for masking/unmasking.
     pub fn add_shift(&self) -> Ratio<BigInt> {
-        use BoundType::{Bmax, B0, B2, B4, B6};
-        use DataType::{F32, F64, I32, I64};
-
-        match self.bound_type {
-            B0 => Ratio::from_integer(BigInt::from(1)),
-            B2 => Ratio::from_integer(BigInt::from(100)),
-            B4 => Ratio::from_integer(BigInt::from(10_000)),
-            B6 => Ratio::from_integer(BigInt::from(1_000_000)),
-            Bmax => match self.data_type {
-                // safe unwraps: all numbers are finite
-                F32 => Ratio::from_float(f32::MAX).unwrap(),
-                F64 => Ratio::from_float(f64::MAX).unwrap(),
-                I32 => Ratio::from_integer(-BigInt::from(i32::MIN)),
-                I64 => Ratio::from_integer(-BigInt::from(i64::MIN)),
-            },
+        match self.bound_type.bound() {
+            Some(bound) => Ratio::from_integer(BigInt::from(bound)),
+            None => self.data_type.max_abs(),
         }
     }
 
+    /// Checks whether the numerical values are quantized.
+    ///
+    /// Quantization only applies to bounded numerical values.
+    pub fn is_quantized(&self) -> bool {
+        self.quantization_type != QuantizationType::Qmax && self.bound_type != BoundType::Bmax
+    }
+
     /// Gets the exponential shift value for masking/unmasking.
     pub fn exp_shift(&self) -> BigInt {
-        use BoundType::{Bmax, B0, B2, B4, B6};
+        use BoundType::{Bmax, Custom, B0, B2, B4, B6};
         use DataType::{F32, F64, I32, I64};
 
+        if self.is_quantized() {
+            // safe unwrap: quantized values have a number of decimal places
+            return BigInt::from(10).pow(self.quantization_type.decimal_places().unwrap());
+        }
+
         match self.data_type {
             F32 => match self.bound_type {
-                B0 | B2 | B4 | B6 => BigInt::from(10).pow(10_u8),
+                B0 | B2 | B4 | B6 | Custom(_) => BigInt::from(10).pow(10_u8),
                 Bmax => BigInt::from(10).pow(45_u8),
             },
             F64 => match self.bound_type {
-                B0 | B2 | B4 | B6 => BigInt::from(10).pow(20_u8),
+                B0 | B2 | B4 | B6 | Custom(_) => BigInt::from(10).pow(20_u8),
                 Bmax => BigInt::from(10).pow(324_u16),
             },
             I32 | I64 => BigInt::from(10).pow(10_u8),
         }
     }
 
+    /// Validates the custom variants of the masking configuration.
+    ///
+    /// # Errors
+    /// Fails if a custom bound or maximum number of models is zero, if custom decimal places
+    /// exceed [`MAX_DECIMAL_PLACES`] or if a custom maximum number of models is combined with
+    /// [`BoundType::Bmax`], whose group orders are too large to be searched for primes.
+    pub fn validate(&self) -> Result<(), InvalidMaskConfigError> {
+        if self.bound_type == BoundType::Custom(0) {
+            return Err(InvalidMaskConfigError::BoundType);
+        }
+
+        match self.model_type {
+            ModelType::Custom(0) => return Err(InvalidMaskConfigError::ModelType),
+            ModelType::Custom(_) if self.bound_type == BoundType::Bmax => {
+                return Err(InvalidMaskConfigError::ModelType);
+            }
+            _ => {}
+        }
+
+        match self.quantization_type {
+            QuantizationType::Custom(decimal_places) if decimal_places > MAX_DECIMAL_PLACES => {
+                Err(InvalidMaskConfigError::QuantizationType)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Recommends the smallest masking configuration for models like the given `model`, which are
+    /// aggregated from at most `nb_models` participants.
+    ///
+    /// The bound type is the smallest one which absolutely bounds the weights of the `model`, see
+    /// [`BoundType::fitting()`], and the model type is the smallest one for `nb_models`. The
+    /// masked models can be further reduced by sparsity and quantization, hence those are left at
+    /// their defaults to be adjusted as needed.
+    ///
+    /// # Errors
+    /// Fails if the weights of the `model` are only bounded by [`BoundType::Bmax`] and
+    /// `nb_models` exceeds the largest catalogued model type.
+    pub fn recommend(
+        group_type: GroupType,
+        data_type: DataType,
+        model: &Model,
+        nb_models: u64,
+    ) -> Result<Self, InvalidMaskConfigError> {
+        let bound_type = BoundType::fitting(model, data_type);
+        let model_type = if bound_type == BoundType::Bmax {
+            // custom model types are invalid for unbounded values
+            let nb_models = BigUint::from(nb_models);
+            [ModelType::M3, ModelType::M6, ModelType::M9, ModelType::M12]
+                .iter()
+                .copied()
+                .find(|model_type| model_type.max_nb_models_big() >= nb_models)
+                .ok_or(InvalidMaskConfigError::ModelType)?
+        } else {
+            ModelType::from_nb_models(nb_models)
+        };
+
+        Ok(Self {
+            group_type,
+            data_type,
+            bound_type,
+            model_type,
+            sparsity_type: SparsityType::default(),
+            quantization_type: QuantizationType::default(),
+        })
+    }
+
+    /// Gets the bound and model types of the masking configuration catalogue.
+    ///
+    /// Returns `None` if any of them is custom.
+    fn catalogued(&self) -> Option<(CatalogueBound, CatalogueModel)> {
+        let bound_type = match self.bound_type {
+            BoundType::B0 => CatalogueBound::B0,
+            BoundType::B2 => CatalogueBound::B2,
+            BoundType::B4 => CatalogueBound::B4,
+            BoundType::B6 => CatalogueBound::B6,
+            BoundType::Bmax => CatalogueBound::Bmax,
+            BoundType::Custom(_) => return None,
+        };
+        let model_type = match self.model_type {
+            ModelType::M3 => CatalogueModel::M3,
+            ModelType::M6 => CatalogueModel::M6,
+            ModelType::M9 => CatalogueModel::M9,
+            ModelType::M12 => CatalogueModel::M12,
+            ModelType::Custom(_) => return None,
+        };
+        Some((bound_type, model_type))
+    }
+
     /// Gets the finite group order value for masking/unmasking.
     pub fn order(&self) -> BigUint {
-        use BoundType::{Bmax, B0, B2, B4, B6};
+        use CatalogueBound::{Bmax, B0, B2, B4, B6};
+        use CatalogueModel::{M12, M3, M6, M9};
         use DataType::{F32, F64, I32, I64};
         use GroupType::{Integer, Power2, Prime};
-        use ModelType::{M12, M3, M6, M9};
+
+        let (bound_type, model_type) = match self.catalogued() {
+            Some(types) if !self.is_quantized() => types,
+            _ => return self.computed_order(),
+        };
 
         let order_str = match self.group_type {
             Integer => match self.data_type {
-                F32 => match self.bound_type {
-                    B0 => match self.model_type {
+                F32 => match bound_type {
+                    B0 => match model_type {
                         M3 => "20_000_000_000_001",
                         M6 => "20_000_000_000_000_001",
                         M9 => "20_000_000_000_000_000_001",
                         M12 => "20_000_000_000_000_000_000_001",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "2_000_000_000_000_001",
                         M6 => "2_000_000_000_000_000_001",
                         M9 => "2_000_000_000_000_000_000_001",
                         M12 => "2_000_000_000_000_000_000_000_001",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "200_000_000_000_000_001",
                         M6 => "200_000_000_000_000_000_001",
                         M9 => "200_000_000_000_000_000_000_001",
                         M12 => "200_000_000_000_000_000_000_000_001",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "20_000_000_000_000_000_001",
                         M6 => "20_000_000_000_000_000_000_001",
                         M9 => "20_000_000_000_000_000_000_000_001",
                         M12 => "20_000_000_000_000_000_000_000_000_001",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "680_564_700_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_001",
                         M6 => "680_564_700_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_001",
                         M9 => "680_564_700_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_001",
                         M12 => "680_564_700_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_001",
                     }
                 }
-                F64 => match self.bound_type {
-                    B0 => match self.model_type {
+                F64 => match bound_type {
+                    B0 => match model_type {
                         M3 => "200_000_000_000_000_000_000_001",
                         M6 => "200_000_000_000_000_000_000_000_001",
                         M9 => "200_000_000_000_000_000_000_000_000_001",
                         M12 => "200_000_000_000_000_000_000_000_000_000_001",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "20_000_000_000_000_000_000_000_001",
                         M6 => "20_000_000_000_000_000_000_000_000_001",
                         M9 => "20_000_000_000_000_000_000_000_000_000_001",
                         M12 => "20_000_000_000_000_000_000_000_000_000_000_001",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "2_000_000_000_000_000_000_000_000_001",
                         M6 => "2_000_000_000_000_000_000_000_000_000_001",
                         M9 => "2_000_000_000_000_000_000_000_000_000_000_001",
                         M12 => "2_000_000_000_000_000_000_000_000_000_000_000_001",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "200_000_000_000_000_000_000_000_000_001",
                         M6 => "200_000_000_000_000_000_000_000_000_000_001",
                         M9 => "200_000_000_000_000_000_000_000_000_000_000_001",
                         M12 => "200_000_000_000_000_000_000_000_000_000_000_000_001",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "359_538_626_972_463_100_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_001",
                         M6 => "359_538_626_972_463_100_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_001",
                         M9 => "359_538_626_972_463_100_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_001",
                         M12 => "359_538_626_972_463_100_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_001",
                     }
                 }
-                I32 => match self.bound_type {
-                    B0 => match self.model_type {
+                I32 => match bound_type {
+                    B0 => match model_type {
                         M3 => "20_000_000_000_001",
                         M6 => "20_000_000_000_000_001",
                         M9 => "20_000_000_000_000_000_001",
                         M12 => "20_000_000_000_000_000_000_001",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "2_000_000_000_000_001",
                         M6 => "2_000_000_000_000_000_001",
                         M9 => "2_000_000_000_000_000_000_001",
                         M12 => "2_000_000_000_000_000_000_000_001",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "200_000_000_000_000_001",
                         M6 => "200_000_000_000_000_000_001",
                         M9 => "200_000_000_000_000_000_000_001",
                         M12 => "200_000_000_000_000_000_000_000_001",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "20_000_000_000_000_000_001",
                         M6 => "20_000_000_000_000_000_000_001",
                         M9 => "20_000_000_000_000_000_000_000_001",
                         M12 => "20_000_000_000_000_000_000_000_000_001",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "42_949_672_950_000_000_000_001",
                         M6 => "42_949_672_950_000_000_000_000_001",
                         M9 => "42_949_672_950_000_000_000_000_000_001",
                         M12 => "42_949_672_950_000_000_000_000_000_000_001",
                     }
                 }
-                I64 => match self.bound_type {
-                    B0 => match self.model_type {
+                I64 => match bound_type {
+                    B0 => match model_type {
                         M3 => "20_000_000_000_001",
                         M6 => "20_000_000_000_000_001",
                         M9 => "20_000_000_000_000_000_001",
                         M12 => "20_000_000_000_000_000_000_001",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "2_000_000_000_000_001",
                         M6 => "2_000_000_000_000_000_001",
                         M9 => "2_000_000_000_000_000_000_001",
                         M12 => "2_000_000_000_000_000_000_000_001",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "200_000_000_000_000_001",
                         M6 => "200_000_000_000_000_000_001",
                         M9 => "200_000_000_000_000_000_000_001",
                         M12 => "200_000_000_000_000_000_000_000_001",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "20_000_000_000_000_000_001",
                         M6 => "20_000_000_000_000_000_000_001",
                         M9 => "20_000_000_000_000_000_000_000_001",
                         M12 => "20_000_000_000_000_000_000_000_000_001",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "184_467_440_737_095_516_150_000_000_000_001",
                         M6 => "184_467_440_737_095_516_150_000_000_000_000_001",
                         M9 => "184_467_440_737_095_516_150_000_000_000_000_000_001",
@@ -369,128 +876,128 @@ impl MaskConfig {
                 }
             }
             Prime => match self.data_type {
-                F32 => match self.bound_type {
-                    B0 => match self.model_type {
+                F32 => match bound_type {
+                    B0 => match model_type {
                         M3 => "20_000_000_000_021",
                         M6 => "20_000_000_000_000_003",
                         M9 => "20_000_000_000_000_000_011",
                         M12 => "20_000_000_000_000_000_000_003",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "2_000_000_000_000_021",
                         M6 => "2_000_000_000_000_000_057",
                         M9 => "2_000_000_000_000_000_000_069",
                         M12 => "2_000_000_000_000_000_000_000_003",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "200_000_000_000_000_003",
                         M6 => "200_000_000_000_000_000_089",
                         M9 => "200_000_000_000_000_000_000_069",
                         M12 => "200_000_000_000_000_000_000_000_027",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "20_000_000_000_000_000_011",
                         M6 => "20_000_000_000_000_000_000_003",
                         M9 => "20_000_000_000_000_000_000_000_009",
                         M12 => "20_000_000_000_000_000_000_000_000_131",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "680_564_700_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_281",
                         M6 => "680_564_700_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_323",
                         M9 => "680_564_700_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_191",
                         M12 => "680_564_700_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_000_083",
                     }
                 }
-                F64 => match self.bound_type {
-                    B0 => match self.model_type {
+                F64 => match bound_type {
+                    B0 => match model_type {
                         M3 => "200_000_000_000_000_000_000_069",
                         M6 => "200_000_000_000_000_000_000_000_027",
                         M9 => "200_000_000_000_000_000_000_000_000_017",
                         M12 => "200_000_000_000_000_000_000_000_000_000_159",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "20_000_000_000_000_000_000_000_009",
                         M6 => "20_000_000_000_000_000_000_000_000_131",
                         M9 => "20_000_000_000_000_000_000_000_000_000_047",
                         M12 => "20_000_000_000_000_000_000_000_000_000_000_203",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "2_000_000_000_000_000_000_000_000_039",
                         M6 => "2_000_000_000_000_000_000_000_000_000_071",
                         M9 => "2_000_000_000_000_000_000_000_000_000_000_017",
                         M12 => "2_000_000_000_000_000_000_000_000_000_000_000_041",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "200_000_000_000_000_000_000_000_000_017",
                         M6 => "200_000_000_000_000_000_000_000_000_000_159",
                         M9 => "200_000_000_000_000_000_000_000_000_000_000_003",
                         M12 => "200_000_000_000_000_000_000_000_000_000_000_000_023",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "359_538_626_972_463_140_000_000_000_000_000_000_000_593_874_019_667_231_666_067_439_096_529_924_969_333_439_983_391_110_599_943_465_644_007_133_099_721_551_828_263_813_044_710_323_667_390_405_279_670_626_898_022_875_314_671_948_577_301_533_414_396_469_719_048_504_306_012_596_386_638_859_340_084_030_210_314_832_025_518_258_115_226_051_894_034_477_843_584_650_149_420_090_374_373_134_876_775_786_923_748_346_298_936_467_612_015_276_401_624_887_654_050_299_443_392_510_555_689_981_501_608_709_494_004_423_956_258_647_440_955_320_257_123_787_935_493_476_104_132_776_728_548_437_783_283_112_428_445_450_269_488_453_346_610_914_359_272_368_862_786_051_728_965_455_746_393_095_846_720_860_347_644_662_201_994_241_194_193_316_457_656_284_847_050_135_299_403_149_697_261_199_957_835_824_000_531_233_031_619_352_921_347_101_423_914_861_961_738_035_659_301",
                         M6 => "359_538_626_972_463_139_999_999_999_999_999_999_999_903_622_106_309_601_840_402_558_296_261_360_055_843_460_163_714_984_640_183_652_353_129_826_112_739_444_431_322_400_938_984_152_600_575_421_591_212_739_537_896_016_542_591_595_727_264_024_538_428_559_469_178_136_611_680_881_710_150_818_089_794_351_154_869_285_409_959_876_691_068_635_451_827_253_162_844_058_791_343_487_286_852_635_234_799_336_668_682_655_217_329_655_102_622_197_942_194_212_857_658_834_043_465_713_831_143_523_811_067_060_369_640_438_677_832_007_091_511_212_788_398_470_391_285_320_720_769_417_737_628_120_102_221_909_739_846_753_580_817_462_645_602_854_496_103_866_327_474_145_187_363_329_320_852_679_912_679_009_543_036_760_757_409_720_574_191_338_832_841_104_183_169_976_025_577_743_061_881_721_861_634_977_765_641_182_996_194_573_448_626_763_720_938_201_976_656_541_039_724_303",
                         M9 => "359_538_626_972_463_139_999_999_999_999_999_999_999_904_930_781_891_526_077_660_862_016_966_437_766_478_934_820_885_791_914_528_679_207_262_530_042_483_798_832_910_003_057_874_958_310_694_484_517_139_841_166_977_272_287_522_418_122_134_527_125_053_808_273_636_647_181_903_383_717_418_169_782_215_585_647_900_802_728_035_567_327_931_187_710_919_458_230_957_036_511_507_150_288_137_858_111_024_099_126_399_746_768_695_036_546_643_813_753_385_062_385_762_652_380_150_346_615_796_407_577_297_605_069_883_839_431_646_689_072_072_214_687_584_099_356_273_959_025_519_093_953_786_032_481_175_596_842_406_101_871_239_892_163_505_527_137_519_569_046_747_947_203_065_300_865_116_331_411_924_515_285_552_096_042_635_874_474_960_733_445_241_451_746_509_870_642_272_026_256_695_499_704_624_475_309_137_281_644_358_183_373_160_068_523_639_023_207_643_484_888_657_559_597",
                         M12 => "359_538_626_972_463_139_999_999_999_999_999_999_999_904_931_540_467_867_407_238_817_633_447_114_203_759_664_620_787_471_913_925_990_313_859_370_016_783_101_785_327_523_046_787_247_090_978_931_042_236_128_228_564_142_680_745_383_377_953_776_024_143_512_065_781_667_978_525_748_300_241_659_425_164_472_387_573_470_260_831_720_974_578_793_447_369_507_661_739_490_218_806_790_001_765_109_117_055_431_552_295_585_457_639_803_896_262_637_528_011_897_242_316_426_079_400_392_728_240_523_639_775_219_294_589_603_009_325_941_759_217_573_340_626_063_716_838_671_315_192_395_974_939_441_284_468_885_927_433_422_082_497_928_190_254_190_935_717_337_452_741_850_223_510_814_859_331_413_287_559_285_438_144_477_756_395_583_878_761_313_295_130_567_342_888_620_541_025_745_968_373_350_261_259_032_809_052_052_475_301_496_416_128_372_300_050_762_773_363_722_300_553_930_211_649",
                     }
                 }
-                I32 => match self.bound_type {
-                    B0 => match self.model_type {
+                I32 => match bound_type {
+                    B0 => match model_type {
                         M3 => "20_000_000_000_021",
                         M6 => "20_000_000_000_000_003",
                         M9 => "20_000_000_000_000_000_011",
                         M12 => "20_000_000_000_000_000_000_003",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "2_000_000_000_000_021",
                         M6 => "2_000_000_000_000_000_057",
                         M9 => "2_000_000_000_000_000_000_069",
                         M12 => "2_000_000_000_000_000_000_000_003",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "200_000_000_000_000_003",
                         M6 => "200_000_000_000_000_000_089",
                         M9 => "200_000_000_000_000_000_000_069",
                         M12 => "200_000_000_000_000_000_000_000_027",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "20_000_000_000_000_000_011",
                         M6 => "20_000_000_000_000_000_000_003",
                         M9 => "20_000_000_000_000_000_000_000_009",
                         M12 => "20_000_000_000_000_000_000_000_000_131",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "42_949_672_950_000_000_000_029",
                         M6 => "42_949_672_950_000_000_000_000_049",
                         M9 => "42_949_672_950_000_000_000_000_000_043",
                         M12 => "42_949_672_950_000_000_000_000_000_000_109",
                     }
                 }
-                I64 => match self.bound_type {
-                    B0 => match self.model_type {
+                I64 => match bound_type {
+                    B0 => match model_type {
                         M3 => "20_000_000_000_021",
                         M6 => "20_000_000_000_000_003",
                         M9 => "20_000_000_000_000_000_011",
                         M12 => "20_000_000_000_000_000_000_003",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "2_000_000_000_000_021",
                         M6 => "2_000_000_000_000_000_057",
                         M9 => "2_000_000_000_000_000_000_069",
                         M12 => "2_000_000_000_000_000_000_000_003",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "200_000_000_000_000_003",
                         M6 => "200_000_000_000_000_000_089",
                         M9 => "200_000_000_000_000_000_000_069",
                         M12 => "200_000_000_000_000_000_000_000_027",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "20_000_000_000_000_000_011",
                         M6 => "20_000_000_000_000_000_000_003",
                         M9 => "20_000_000_000_000_000_000_000_009",
                         M12 => "20_000_000_000_000_000_000_000_000_131",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "184_467_440_737_095_516_150_000_000_000_073",
                         M6 => "184_467_440_737_095_516_150_000_000_000_000_013",
                         M9 => "184_467_440_737_095_516_150_000_000_000_000_000_167",
@@ -499,128 +1006,128 @@ impl MaskConfig {
                 }
             },
             Power2 => match self.data_type {
-                F32 => match self.bound_type {
-                    B0 => match self.model_type {
+                F32 => match bound_type {
+                    B0 => match model_type {
                         M3 => "35_184_372_088_832",
                         M6 => "36_028_797_018_963_968",
                         M9 => "36_893_488_147_419_103_232",
                         M12 => "37_778_931_862_957_161_709_568",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "2_251_799_813_685_248",
                         M6 => "2_305_843_009_213_693_952",
                         M9 => "2_361_183_241_434_822_606_848",
                         M12 => "2_417_851_639_229_258_349_412_352",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "288_230_376_151_711_744",
                         M6 => "295_147_905_179_352_825_856",
                         M9 => "302_231_454_903_657_293_676_544",
                         M12 => "309_485_009_821_345_068_724_781_056",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "36_893_488_147_419_103_232",
                         M6 => "37_778_931_862_957_161_709_568",
                         M9 => "38_685_626_227_668_133_590_597_632",
                         M12 => "39_614_081_257_132_168_796_771_975_168",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "994_646_472_819_573_284_310_764_496_293_641_680_200_912_301_594_695_434_880_927_953_786_318_994_025_066_751_066_112",
                         M6 => "1_018_517_988_167_243_043_134_222_844_204_689_080_525_734_196_832_968_125_318_070_224_677_190_649_881_668_353_091_698_688",
                         M9 => "1_042_962_419_883_256_876_169_444_192_465_601_618_458_351_817_556_959_360_325_703_910_069_443_225_478_828_393_565_899_456_512",
                         M12 => "1_067_993_517_960_455_041_197_510_853_084_776_057_301_352_261_178_326_384_973_520_803_911_109_862_890_320_275_011_481_043_468_288",
                     }
                 }
-                F64 => match self.bound_type {
-                    B0 => match self.model_type {
+                F64 => match bound_type {
+                    B0 => match model_type {
                         M3 => "302_231_454_903_657_293_676_544",
                         M6 => "309_485_009_821_345_068_724_781_056",
                         M9 => "316_912_650_057_057_350_374_175_801_344",
                         M12 => "324_518_553_658_426_726_783_156_020_576_256",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "38_685_626_227_668_133_590_597_632",
                         M6 => "39_614_081_257_132_168_796_771_975_168",
                         M9 => "20_282_409_603_651_670_423_947_251_286_016",
                         M12 => "20_769_187_434_139_310_514_121_985_316_880_384",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "2_475_880_078_570_760_549_798_248_448",
                         M6 => "2_535_301_200_456_458_802_993_406_410_752",
                         M9 => "2_596_148_429_267_413_814_265_248_164_610_048",
                         M12 => "2_658_455_991_569_831_745_807_614_120_560_689_152",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "316_912_650_057_057_350_374_175_801_344",
                         M6 => "324_518_553_658_426_726_783_156_020_576_256",
                         M9 => "332_306_998_946_228_968_225_951_765_070_086_144",
                         M12 => "340_282_366_920_938_463_463_374_607_431_768_211_456",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "596_143_540_225_991_923_146_302_416_688_458_341_289_203_474_674_553_062_792_993_127_033_853_365_765_018_588_197_722_567_551_977_295_508_215_323_031_793_155_057_153_946_025_631_943_349_443_566_464_703_583_960_364_782_216_884_718_655_637_955_371_883_889_285_523_680_681_542_682_622_992_485_998_454_422_254_346_205_188_269_982_058_330_848_165_814_218_528_432_304_958_458_516_472_675_321_199_923_576_436_128_746_194_040_030_388_187_813_654_706_961_312_852_788_047_760_914_640_519_973_439_182_188_222_756_017_424_664_821_230_981_616_162_111_762_973_371_192_278_908_910_941_031_147_045_555_738_506_834_254_728_517_124_812_756_790_583_181_174_762_115_337_827_697_771_072_593_076_558_961_853_936_203_969_690_859_453_400_618_497_370_766_001_868_317_217_344_149_071_638_768_630_396_860_838_478_405_181_466_899_321_747_678_290_733_613_480_879_657_473_540_096",
                         M6 => "610_450_985_191_415_729_301_813_674_688_981_341_480_144_358_066_742_336_300_024_962_082_665_846_543_379_034_314_467_909_173_224_750_600_412_490_784_556_190_778_525_640_730_247_109_989_830_212_059_856_469_975_413_536_990_089_951_903_373_266_300_809_102_628_376_249_017_899_707_005_944_305_662_417_328_388_450_514_112_788_461_627_730_788_521_793_759_773_114_680_277_461_520_868_019_528_908_721_742_270_595_836_102_696_991_117_504_321_182_419_928_384_361_254_960_907_176_591_892_452_801_722_560_740_102_161_842_856_776_940_525_174_950_002_445_284_732_100_893_602_724_803_615_894_574_649_076_230_998_276_842_001_535_808_262_953_557_177_522_956_406_105_935_562_517_578_335_310_396_376_938_430_672_864_963_440_080_282_233_341_307_664_385_913_156_830_560_408_649_358_099_077_526_385_498_601_886_905_822_104_905_469_622_569_711_220_204_420_769_252_905_058_304",
                         M9 => "625_101_808_836_009_706_805_057_202_881_516_893_675_667_822_660_344_152_371_225_561_172_649_826_860_420_131_138_015_138_993_382_144_614_822_390_563_385_539_357_210_256_107_773_040_629_586_137_149_293_025_254_823_461_877_852_110_749_054_224_692_028_521_091_457_278_994_329_299_974_086_968_998_315_344_269_773_326_451_495_384_706_796_327_446_316_810_007_669_432_604_120_597_368_851_997_602_531_064_085_090_136_169_161_718_904_324_424_890_798_006_665_585_925_079_968_948_830_097_871_668_963_902_197_864_613_727_085_339_587_097_779_148_802_503_971_565_671_315_049_190_198_902_676_044_440_654_060_542_235_486_209_572_667_661_264_442_549_783_507_359_852_478_016_018_000_215_357_845_889_984_953_009_013_722_562_642_209_006_941_499_048_331_175_072_594_493_858_456_942_693_455_387_018_750_568_332_191_561_835_423_200_893_511_384_289_489_326_867_714_974_779_703_296",
                         M12 => "640_104_252_248_073_939_768_378_575_750_673_299_123_883_850_404_192_412_028_134_974_640_793_422_705_070_214_285_327_502_329_223_316_085_578_127_936_906_792_301_783_302_254_359_593_604_696_204_440_876_057_860_939_224_962_920_561_407_031_526_084_637_205_597_652_253_690_193_203_173_465_056_254_274_912_532_247_886_286_331_273_939_759_439_305_028_413_447_853_498_986_619_491_705_704_445_544_991_809_623_132_299_437_221_600_158_028_211_088_177_158_825_559_987_281_888_203_602_020_220_589_019_035_850_613_364_456_535_387_737_188_125_848_373_764_066_883_247_426_610_370_763_676_340_269_507_229_757_995_249_137_878_602_411_685_134_789_170_978_311_536_488_937_488_402_432_220_526_434_191_344_591_881_230_051_904_145_622_023_108_095_025_491_123_274_336_761_711_059_909_318_098_316_307_200_581_972_164_159_319_473_357_714_955_657_512_437_070_712_540_134_174_416_175_104",
                     }
                 }
-                I32 => match self.bound_type {
-                    B0 => match self.model_type {
+                I32 => match bound_type {
+                    B0 => match model_type {
                         M3 => "35_184_372_088_832",
                         M6 => "36_028_797_018_963_968",
                         M9 => "36_893_488_147_419_103_232",
                         M12 => "37_778_931_862_957_161_709_568",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "2_251_799_813_685_248",
                         M6 => "2_305_843_009_213_693_952",
                         M9 => "2_361_183_241_434_822_606_848",
                         M12 => "2_417_851_639_229_258_349_412_352",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "288_230_376_151_711_744",
                         M6 => "295_147_905_179_352_825_856",
                         M9 => "302_231_454_903_657_293_676_544",
                         M12 => "309_485_009_821_345_068_724_781_056",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "36_893_488_147_419_103_232",
                         M6 => "37_778_931_862_957_161_709_568",
                         M9 => "38_685_626_227_668_133_590_597_632",
                         M12 => "39_614_081_257_132_168_796_771_975_168",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "75_557_863_725_914_323_419_136",
                         M6 => "77_371_252_455_336_267_181_195_264",
                         M9 => "79_228_162_514_264_337_593_543_950_336",
                         M12 => "81_129_638_414_606_681_695_789_005_144_064",
                     }
                 }
-                I64 => match self.bound_type {
-                    B0 => match self.model_type {
+                I64 => match bound_type {
+                    B0 => match model_type {
                         M3 => "35_184_372_088_832",
                         M6 => "36_028_797_018_963_968",
                         M9 => "36_893_488_147_419_103_232",
                         M12 => "37_778_931_862_957_161_709_568",
                     }
-                    B2 => match self.model_type {
+                    B2 => match model_type {
                         M3 => "2_251_799_813_685_248",
                         M6 => "2_305_843_009_213_693_952",
                         M9 => "2_361_183_241_434_822_606_848",
                         M12 => "2_417_851_639_229_258_349_412_352",
                     }
-                    B4 => match self.model_type {
+                    B4 => match model_type {
                         M3 => "288_230_376_151_711_744",
                         M6 => "295_147_905_179_352_825_856",
                         M9 => "302_231_454_903_657_293_676_544",
                         M12 => "309_485_009_821_345_068_724_781_056",
                     }
-                    B6 => match self.model_type {
+                    B6 => match model_type {
                         M3 => "36_893_488_147_419_103_232",
                         M6 => "37_778_931_862_957_161_709_568",
                         M9 => "38_685_626_227_668_133_590_597_632",
                         M12 => "39_614_081_257_132_168_796_771_975_168",
                     }
-                    Bmax => match self.model_type {
+                    Bmax => match model_type {
                         M3 => "324_518_553_658_426_726_783_156_020_576_256",
                         M6 => "332_306_998_946_228_968_225_951_765_070_086_144",
                         M9 => "340_282_366_920_938_463_463_374_607_431_768_211_456",
@@ -632,6 +1139,114 @@ impl MaskConfig {
         // safe unwrap: string and radix are valid
         BigUint::from_str_radix(order_str, 10).unwrap()
     }
+
+    /// Gets the finite group order value for masking/unmasking of quantized numerical values and
+    /// custom masking configurations.
+    ///
+    /// The orders are constructed like the catalogued ones, i.e. as the smallest integer, prime or
+    /// power of two greater than `2 * b * 10^p * m`, where `b` is the bound, `p` the number of
+    /// decimal places and `m` the maximum number of models.
+    ///
+    /// The orders are cached, since computing the prime orders is expensive.
+    fn computed_order(&self) -> BigUint {
+        let cached = COMPUTED_ORDERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(self)
+            .cloned();
+        if let Some(order) = cached {
+            return order;
+        }
+
+        // the order is computed without holding the lock
+        let order = self.compute_order();
+        let mut orders = COMPUTED_ORDERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if orders.len() >= ORDER_CACHE_CAPACITY {
+            orders.clear();
+        }
+        orders.insert(*self, order.clone());
+        order
+    }
+
+    /// Computes the finite group order value of an uncatalogued masking configuration.
+    fn compute_order(&self) -> BigUint {
+        // safe unwraps: the shifts are positive integers
+        let bound = self.add_shift().to_integer().to_biguint().unwrap();
+        let precision = self.exp_shift().to_biguint().unwrap();
+        let integer_order =
+            BigUint::from(2_u8) * bound * precision * self.model_type.max_nb_models_big() + 1_u8;
+        match self.group_type {
+            GroupType::Integer => integer_order,
+            GroupType::Prime => next_prime(integer_order),
+            GroupType::Power2 => BigUint::from(1_u8) << integer_order.bits() as usize,
+        }
+    }
+}
+
+/// The catalogued bound types, for which the group orders are precomputed.
+#[derive(Clone, Copy)]
+enum CatalogueBound {
+    B0,
+    B2,
+    B4,
+    B6,
+    Bmax,
+}
+
+/// The catalogued model types, for which the group orders are precomputed.
+#[derive(Clone, Copy)]
+enum CatalogueModel {
+    M3,
+    M6,
+    M9,
+    M12,
+}
+
+/// Gets the smallest prime greater than or equal to the odd integer `n > 2`.
+fn next_prime(mut n: BigUint) -> BigUint {
+    while !is_prime(&n) {
+        n += 2_u8;
+    }
+    n
+}
+
+/// Checks whether the odd integer `n > 2` is a prime with the Miller-Rabin primality test.
+///
+/// The test is deterministic for the group orders of custom masking configurations up to 24
+/// decimal digits and a composite is extremely unlikely to pass it otherwise. Masking doesn't
+/// depend on the primality of the group order anyways.
+fn is_prime(n: &BigUint) -> bool {
+    if PRIME_BASES.iter().any(|base| n == &BigUint::from(*base)) {
+        return true;
+    }
+    if PRIME_BASES
+        .iter()
+        .any(|base| (n % *base).to_u8() == Some(0))
+    {
+        return false;
+    }
+
+    let one = BigUint::one();
+    let n_minus_one = n - 1_u8;
+    // safe unwrap: n - 1 is even and non-zero
+    let zeros = n_minus_one.trailing_zeros().unwrap();
+    let odd = &n_minus_one >> zeros as usize;
+    'bases: for base in PRIME_BASES.iter() {
+        let mut x = BigUint::from(*base).modpow(&odd, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..zeros {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -646,10 +1261,273 @@ pub struct MaskConfigPair {
 
 impl From<MaskConfig> for MaskConfigPair {
     /// Creates two copies of the given masking configuration as a pair.
+    ///
+    /// The unit value is always dense and unquantized, hence the sparsity and quantization of the
+    /// given masking configuration only apply to the vector configuration.
     fn from(config: MaskConfig) -> Self {
         Self {
             vect: config,
-            unit: config,
+            unit: MaskConfig {
+                sparsity_type: SparsityType::S0,
+                quantization_type: QuantizationType::Qmax,
+                ..config
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mask::FromPrimitives;
+
+    // the smallest primes greater than `2 * 10^e` for the exponents `e` of the quantized masking
+    // configurations, starting at `e = 5`
+    const QUANTIZED_PRIME_ORDERS: [u128; 18] = [
+        200_003,
+        2_000_003,
+        20_000_003,
+        200_000_033,
+        2_000_000_011,
+        20_000_000_089,
+        200_000_000_041,
+        2_000_000_000_003,
+        20_000_000_000_021,
+        200_000_000_000_027,
+        2_000_000_000_000_021,
+        20_000_000_000_000_003,
+        200_000_000_000_000_003,
+        2_000_000_000_000_000_057,
+        20_000_000_000_000_000_011,
+        200_000_000_000_000_000_089,
+        2_000_000_000_000_000_000_069,
+        20_000_000_000_000_000_000_003,
+    ];
+
+    fn config(group_type: GroupType, bound_type: BoundType) -> MaskConfig {
+        MaskConfig {
+            group_type,
+            data_type: DataType::F32,
+            bound_type,
+            model_type: ModelType::M3,
+            sparsity_type: SparsityType::S0,
+            quantization_type: QuantizationType::Q2,
+        }
+    }
+
+    #[test]
+    fn test_nb_selected() {
+        assert_eq!(SparsityType::S0.nb_selected(1_234), 1_234);
+        assert_eq!(SparsityType::S1.nb_selected(1_234), 124);
+        assert_eq!(SparsityType::S2.nb_selected(1_234), 13);
+        assert_eq!(SparsityType::S3.nb_selected(1_234), 2);
+        assert_eq!(SparsityType::S3.nb_selected(1_000), 1);
+        assert_eq!(SparsityType::S3.nb_selected(0), 0);
+    }
+
+    #[test]
+    fn test_quantized_order() {
+        let integer = config(GroupType::Integer, BoundType::B0);
+        assert!(integer.is_quantized());
+        assert_eq!(integer.exp_shift(), BigInt::from(100));
+        assert_eq!(integer.order(), BigUint::from(200_001_u32));
+        assert_eq!(integer.bytes_per_number(), 3);
+
+        let prime = config(GroupType::Prime, BoundType::B0);
+        assert_eq!(prime.order(), BigUint::from(200_003_u32));
+
+        let power2 = config(GroupType::Power2, BoundType::B0);
+        assert_eq!(power2.order(), BigUint::from(262_144_u32));
+
+        let largest = MaskConfig {
+            bound_type: BoundType::B6,
+            model_type: ModelType::M12,
+            quantization_type: QuantizationType::Q4,
+            ..prime
+        };
+        assert_eq!(
+            largest.order(),
+            BigUint::from(20_000_000_000_000_000_000_003_u128),
+        );
+    }
+
+    #[test]
+    fn test_bmax_is_not_quantized() {
+        let quantized = config(GroupType::Prime, BoundType::Bmax);
+        let unquantized = MaskConfig {
+            quantization_type: QuantizationType::Qmax,
+            ..quantized
+        };
+        assert!(!quantized.is_quantized());
+        assert_eq!(quantized.exp_shift(), unquantized.exp_shift());
+        assert_eq!(quantized.order(), unquantized.order());
+    }
+
+    #[test]
+    fn test_unit_config_is_dense_and_unquantized() {
+        let config = MaskConfig {
+            sparsity_type: SparsityType::S2,
+            ..config(GroupType::Prime, BoundType::B0)
+        };
+        let pair = MaskConfigPair::from(config);
+        assert_eq!(pair.vect, config);
+        assert_eq!(pair.unit.sparsity_type, SparsityType::S0);
+        assert_eq!(pair.unit.quantization_type, QuantizationType::Qmax);
+    }
+
+    #[test]
+    fn test_next_prime() {
+        for (exp, prime) in (5_u8..).zip(QUANTIZED_PRIME_ORDERS.iter()) {
+            let integer = BigUint::from(2_u8) * BigUint::from(10_u8).pow(exp) + 1_u8;
+            assert_eq!(next_prime(integer), BigUint::from(*prime));
         }
+        assert!(!is_prime(&BigUint::from(3_215_031_751_u64)));
+        assert!(is_prime(&BigUint::from(71_u8)));
+    }
+
+    #[test]
+    fn test_custom_order_equals_catalogued_order() {
+        use BoundType::{B0, B2, B4, B6};
+        use DataType::{F32, F64, I32, I64};
+        use GroupType::{Integer, Power2, Prime};
+        use ModelType::{M12, M3, M6, M9};
+
+        for &group_type in [Integer, Prime, Power2].iter() {
+            for &data_type in [F32, F64, I32, I64].iter() {
+                for &bound_type in [B0, B2, B4, B6].iter() {
+                    for &model_type in [M3, M6, M9, M12].iter() {
+                        let catalogued = MaskConfig {
+                            group_type,
+                            data_type,
+                            bound_type,
+                            model_type,
+                            sparsity_type: SparsityType::S0,
+                            quantization_type: QuantizationType::Qmax,
+                        };
+                        let custom = MaskConfig {
+                            bound_type: BoundType::Custom(bound_type.bound().unwrap()),
+                            model_type: ModelType::Custom(model_type.max_nb_models() as u64),
+                            ..catalogued
+                        };
+                        assert_eq!(custom.order(), catalogued.order());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_order() {
+        let integer = MaskConfig {
+            bound_type: BoundType::Custom(5),
+            model_type: ModelType::Custom(50),
+            quantization_type: QuantizationType::Qmax,
+            ..config(GroupType::Integer, BoundType::B0)
+        };
+        assert!(integer.validate().is_ok());
+        assert_eq!(integer.add_shift(), Ratio::from_integer(BigInt::from(5)));
+        assert_eq!(integer.order(), BigUint::from(5_000_000_000_001_u64));
+
+        let quantized = MaskConfig {
+            quantization_type: QuantizationType::Custom(3),
+            ..integer
+        };
+        assert_eq!(quantized.exp_shift(), BigInt::from(1_000));
+        assert_eq!(quantized.order(), BigUint::from(500_001_u32));
+        assert_eq!(quantized.bytes_per_number(), 3);
+
+        let prime = MaskConfig {
+            group_type: GroupType::Prime,
+            ..quantized
+        };
+        assert_eq!(prime.order(), BigUint::from(500_009_u32));
+        // the second order is cached
+        assert_eq!(prime.order(), prime.compute_order());
+
+        let power2 = MaskConfig {
+            group_type: GroupType::Power2,
+            ..quantized
+        };
+        assert_eq!(power2.order(), BigUint::from(524_288_u32));
+    }
+
+    #[test]
+    fn test_validate() {
+        let valid = config(GroupType::Prime, BoundType::Custom(1));
+        assert!(valid.validate().is_ok());
+        assert!(matches!(
+            config(GroupType::Prime, BoundType::Custom(0)).validate(),
+            Err(InvalidMaskConfigError::BoundType),
+        ));
+        assert!(matches!(
+            MaskConfig {
+                model_type: ModelType::Custom(0),
+                ..valid
+            }
+            .validate(),
+            Err(InvalidMaskConfigError::ModelType),
+        ));
+        assert!(matches!(
+            MaskConfig {
+                bound_type: BoundType::Bmax,
+                model_type: ModelType::Custom(10),
+                ..valid
+            }
+            .validate(),
+            Err(InvalidMaskConfigError::ModelType),
+        ));
+        assert!(matches!(
+            MaskConfig {
+                quantization_type: QuantizationType::Custom(MAX_DECIMAL_PLACES + 1),
+                ..valid
+            }
+            .validate(),
+            Err(InvalidMaskConfigError::QuantizationType),
+        ));
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(
+            BoundType::from_tag(BoundType::B4.tag(), 0).unwrap(),
+            BoundType::B4,
+        );
+        assert_eq!(
+            BoundType::from_tag(CUSTOM_TAG, 250).unwrap(),
+            BoundType::Custom(250),
+        );
+        assert!(BoundType::from_tag(CUSTOM_TAG, 0).is_err());
+        assert_eq!(
+            ModelType::from_tag(CUSTOM_TAG, 5_000).unwrap(),
+            ModelType::Custom(5_000),
+        );
+        assert_eq!(
+            QuantizationType::from_tag(CUSTOM_TAG, 3).unwrap(),
+            QuantizationType::Custom(3),
+        );
+        assert!(QuantizationType::from_tag(CUSTOM_TAG, 256).is_err());
+    }
+
+    #[test]
+    fn test_recommend() {
+        let model = Model::from_primitives(vec![0.5_f32, -3.2, 1.].into_iter()).unwrap();
+        let config = MaskConfig::recommend(GroupType::Prime, DataType::F32, &model, 1_000).unwrap();
+        assert_eq!(config.bound_type, BoundType::Custom(4));
+        assert_eq!(config.model_type, ModelType::M3);
+        assert!(config.validate().is_ok());
+
+        let model = Model::from_primitives(vec![0.5_f32, -100.].into_iter()).unwrap();
+        let config = MaskConfig::recommend(GroupType::Prime, DataType::F32, &model, 20).unwrap();
+        assert_eq!(config.bound_type, BoundType::B2);
+        assert_eq!(config.model_type, ModelType::Custom(20));
+
+        let model = Model::from_primitives(vec![1e30_f64].into_iter()).unwrap();
+        let config = MaskConfig::recommend(GroupType::Prime, DataType::F64, &model, 5_000).unwrap();
+        assert_eq!(config.bound_type, BoundType::Bmax);
+        assert_eq!(config.model_type, ModelType::M6);
+        assert!(matches!(
+            MaskConfig::recommend(GroupType::Prime, DataType::F64, &model, 10_u64.pow(13)),
+            Err(InvalidMaskConfigError::ModelType),
+        ));
     }
 }
@@ -1,7 +1,13 @@
 //! Serialization of masking configurations.
 //!
+//! A masking configuration is serialized as the byte representations of its types, followed by
+//! the [LEB128] encoded values of the custom bound, model and quantization types in this order.
+//! Hence, the masking configurations of the catalogue are always serialized into 6 bytes and the
+//! custom values only take as many bytes as they need.
+//!
 //! See the [mask module] documentation since this is a private module anyways.
 //!
+//! [LEB128]: https://en.wikipedia.org/wiki/LEB128
 //! [mask module]: ../index.html
 
 use std::convert::TryInto;
@@ -9,7 +15,7 @@ use std::convert::TryInto;
 use anyhow::{anyhow, Context};
 
 use crate::{
-    mask::config::MaskConfig,
+    mask::config::{BoundType, MaskConfig, ModelType, QuantizationType, CUSTOM_TAG},
     message::{
         traits::{FromBytes, ToBytes},
         DecodeError,
@@ -20,7 +26,14 @@ const GROUP_TYPE_FIELD: usize = 0;
 const DATA_TYPE_FIELD: usize = 1;
 const BOUND_TYPE_FIELD: usize = 2;
 const MODEL_TYPE_FIELD: usize = 3;
-pub(crate) const MASK_CONFIG_BUFFER_LEN: usize = 4;
+const SPARSITY_TYPE_FIELD: usize = 4;
+const QUANTIZATION_TYPE_FIELD: usize = 5;
+pub(crate) const MASK_CONFIG_BUFFER_LEN: usize = 6;
+
+// the fields which may contain custom types, in the order of their serialized values
+const CUSTOM_FIELDS: [usize; 3] = [BOUND_TYPE_FIELD, MODEL_TYPE_FIELD, QUANTIZATION_TYPE_FIELD];
+// the maximum number of bytes of a LEB128 encoded u64
+const MAX_VALUE_LEN: usize = 10;
 
 /// A buffer for serialized masking configurations.
 pub struct MaskConfigBuffer<T> {
@@ -48,7 +61,7 @@ impl<T: AsRef<[u8]>> MaskConfigBuffer<T> {
     /// Checks if this buffer conforms to the required buffer length for masking configurations.
     ///
     /// # Errors
-    /// Fails if the buffer is too small.
+    /// Fails if the buffer is too small or if the custom values are malformed.
     pub fn check_buffer_length(&self) -> Result<(), DecodeError> {
         let len = self.inner.as_ref().len();
         if len < MASK_CONFIG_BUFFER_LEN {
@@ -58,7 +71,46 @@ impl<T: AsRef<[u8]>> MaskConfigBuffer<T> {
                 MASK_CONFIG_BUFFER_LEN
             ));
         }
-        Ok(())
+        self.try_len().map(|_| ())
+    }
+
+    /// Gets the expected number of bytes of this buffer wrt to the custom types. This is similar
+    /// to [`len()`] but cannot panic for buffers of at least 6 bytes.
+    ///
+    /// # Errors
+    /// Fails if the custom values are truncated or malformed.
+    ///
+    /// [`len()`]: MaskConfigBuffer::len
+    pub fn try_len(&self) -> Result<usize, DecodeError> {
+        self.custom_values().map(|(_, len)| len)
+    }
+
+    /// Gets the expected number of bytes of this buffer wrt to the custom types.
+    ///
+    /// # Panics
+    /// May panic if this buffer is unchecked.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.try_len().unwrap()
+    }
+
+    /// Gets the serialized values of the custom bound, model and quantization types, which are
+    /// zero for catalogued types, and the expected number of bytes of this buffer.
+    ///
+    /// # Errors
+    /// Fails if the custom values are truncated or malformed.
+    fn custom_values(&self) -> Result<([u64; 3], usize), DecodeError> {
+        let bytes = self.inner.as_ref();
+        let mut values = [0; 3];
+        let mut len = MASK_CONFIG_BUFFER_LEN;
+        for (value, field) in values.iter_mut().zip(CUSTOM_FIELDS.iter()) {
+            if bytes[*field] == CUSTOM_TAG {
+                let (custom, custom_len) = read_value(bytes[len..].iter().copied())?;
+                *value = custom;
+                len += custom_len;
+            }
+        }
+        Ok((values, len))
     }
 
     /// Gets the serialized group type of the masking configuration.
@@ -92,6 +144,22 @@ impl<T: AsRef<[u8]>> MaskConfigBuffer<T> {
     pub fn model_type(&self) -> u8 {
         self.inner.as_ref()[MODEL_TYPE_FIELD]
     }
+
+    /// Gets the serialized sparsity type of the masking configuration.
+    ///
+    /// # Panics
+    /// May panic if this buffer is unchecked.
+    pub fn sparsity_type(&self) -> u8 {
+        self.inner.as_ref()[SPARSITY_TYPE_FIELD]
+    }
+
+    /// Gets the serialized quantization type of the masking configuration.
+    ///
+    /// # Panics
+    /// May panic if this buffer is unchecked.
+    pub fn quantization_type(&self) -> u8 {
+        self.inner.as_ref()[QUANTIZATION_TYPE_FIELD]
+    }
 }
 
 impl<T: AsMut<[u8]>> MaskConfigBuffer<T> {
@@ -126,26 +194,110 @@ impl<T: AsMut<[u8]>> MaskConfigBuffer<T> {
     pub fn set_model_type(&mut self, value: u8) {
         self.inner.as_mut()[MODEL_TYPE_FIELD] = value;
     }
+
+    /// Sets the serialized sparsity type of the masking configuration.
+    ///
+    /// # Panics
+    /// May panic if this buffer is unchecked.
+    pub fn set_sparsity_type(&mut self, value: u8) {
+        self.inner.as_mut()[SPARSITY_TYPE_FIELD] = value;
+    }
+
+    /// Sets the serialized quantization type of the masking configuration.
+    ///
+    /// # Panics
+    /// May panic if this buffer is unchecked.
+    pub fn set_quantization_type(&mut self, value: u8) {
+        self.inner.as_mut()[QUANTIZATION_TYPE_FIELD] = value;
+    }
+
+    /// Sets the serialized values of the custom types of the masking configuration.
+    ///
+    /// The values must be given in the order of the bound, model and quantization types and only
+    /// for the custom ones.
+    ///
+    /// # Panics
+    /// May panic if this buffer is unchecked.
+    pub fn set_custom_values(&mut self, values: impl IntoIterator<Item = u64>) {
+        let mut len = MASK_CONFIG_BUFFER_LEN;
+        for value in values {
+            len += write_value(value, &mut self.inner.as_mut()[len..]);
+        }
+    }
+}
+
+/// Gets the values of the custom types of the masking `config`.
+fn custom_values(config: &MaskConfig) -> impl Iterator<Item = u64> {
+    config
+        .bound_type
+        .custom_value()
+        .into_iter()
+        .chain(config.model_type.custom_value())
+        .chain(config.quantization_type.custom_value())
+}
+
+/// Gets the number of bytes of the LEB128 encoding of the `value`.
+fn value_len(value: u64) -> usize {
+    let bits = 64 - value.leading_zeros() as usize;
+    ((bits + 6) / 7).max(1)
+}
+
+/// Writes the LEB128 encoding of the `value` into the `buffer` and returns its length.
+fn write_value(mut value: u64, buffer: &mut [u8]) -> usize {
+    let mut len = 0;
+    while value >= 0x80 {
+        buffer[len] = value as u8 | 0x80;
+        value >>= 7;
+        len += 1;
+    }
+    buffer[len] = value as u8;
+    len + 1
+}
+
+/// Reads a LEB128 encoded value from the `bytes` and returns it with its length.
+///
+/// # Errors
+/// Fails if the encoding is truncated, not minimal or overflows.
+fn read_value(bytes: impl Iterator<Item = u8>) -> Result<(u64, usize), DecodeError> {
+    let mut value = 0_u64;
+    for (i, byte) in bytes.take(MAX_VALUE_LEN).enumerate() {
+        let bits = u64::from(byte & 0x7f);
+        if i == MAX_VALUE_LEN - 1 && bits > 1 {
+            return Err(anyhow!("custom value overflows"));
+        }
+        value |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            if byte == 0 && i > 0 {
+                return Err(anyhow!("custom value is not minimally encoded"));
+            }
+            return Ok((value, i + 1));
+        }
+    }
+    Err(anyhow!("custom value is truncated"))
 }
 
 impl ToBytes for MaskConfig {
     fn buffer_length(&self) -> usize {
-        MASK_CONFIG_BUFFER_LEN
+        MASK_CONFIG_BUFFER_LEN + custom_values(self).map(value_len).sum::<usize>()
     }
 
     fn to_bytes<T: AsMut<[u8]>>(&self, buffer: &mut T) {
         let mut writer = MaskConfigBuffer::new_unchecked(buffer.as_mut());
         writer.set_group_type(self.group_type as u8);
         writer.set_data_type(self.data_type as u8);
-        writer.set_bound_type(self.bound_type as u8);
-        writer.set_model_type(self.model_type as u8);
+        writer.set_bound_type(self.bound_type.tag());
+        writer.set_model_type(self.model_type.tag());
+        writer.set_sparsity_type(self.sparsity_type as u8);
+        writer.set_quantization_type(self.quantization_type.tag());
+        writer.set_custom_values(custom_values(self));
     }
 }
 
 impl FromBytes for MaskConfig {
     fn from_byte_slice<T: AsRef<[u8]>>(buffer: &T) -> Result<Self, DecodeError> {
         let reader = MaskConfigBuffer::new(buffer.as_ref())?;
-        Ok(Self {
+        let ([bound, nb_models, decimal_places], _) = reader.custom_values()?;
+        let config = Self {
             group_type: reader
                 .group_type()
                 .try_into()
@@ -154,21 +306,42 @@ impl FromBytes for MaskConfig {
                 .data_type()
                 .try_into()
                 .context("invalid masking config")?,
-            bound_type: reader
-                .bound_type()
-                .try_into()
+            bound_type: BoundType::from_tag(reader.bound_type(), bound)
+                .context("invalid masking config")?,
+            model_type: ModelType::from_tag(reader.model_type(), nb_models)
                 .context("invalid masking config")?,
-            model_type: reader
-                .model_type()
+            sparsity_type: reader
+                .sparsity_type()
                 .try_into()
                 .context("invalid masking config")?,
-        })
+            quantization_type: QuantizationType::from_tag(
+                reader.quantization_type(),
+                decimal_places,
+            )
+            .context("invalid masking config")?,
+        };
+        config.validate().context("invalid masking config")?;
+        Ok(config)
     }
 
     fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
         iter: &mut I,
     ) -> Result<Self, DecodeError> {
-        let buf: Vec<u8> = iter.take(MASK_CONFIG_BUFFER_LEN).collect();
+        let mut buf: Vec<u8> = iter.by_ref().take(MASK_CONFIG_BUFFER_LEN).collect();
+        if buf.len() == MASK_CONFIG_BUFFER_LEN {
+            let nb_values = CUSTOM_FIELDS
+                .iter()
+                .filter(|field| buf[**field] == CUSTOM_TAG)
+                .count();
+            for _ in 0..nb_values {
+                for byte in iter.by_ref().take(MAX_VALUE_LEN) {
+                    buf.push(byte);
+                    if byte & 0x80 == 0 {
+                        break;
+                    }
+                }
+            }
+        }
         Self::from_byte_slice(&buf)
     }
 }
@@ -176,7 +349,15 @@ impl FromBytes for MaskConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mask::config::{BoundType, DataType, GroupType, MaskConfig, ModelType};
+    use crate::mask::config::{
+        BoundType,
+        DataType,
+        GroupType,
+        MaskConfig,
+        ModelType,
+        QuantizationType,
+        SparsityType,
+    };
 
     #[test]
     fn serialize() {
@@ -185,16 +366,18 @@ mod tests {
             data_type: DataType::F64,
             bound_type: BoundType::Bmax,
             model_type: ModelType::M9,
+            sparsity_type: SparsityType::S2,
+            quantization_type: QuantizationType::Q4,
         };
 
-        let mut buf = vec![0xff; 4];
+        let mut buf = vec![0xff; 6];
         config.to_bytes(&mut buf);
-        assert_eq!(buf, vec![1, 1, 255, 9]);
+        assert_eq!(buf, vec![1, 1, 255, 9, 2, 4]);
     }
 
     #[test]
     fn deserialize() {
-        let bytes = vec![1, 1, 255, 9];
+        let bytes = vec![1, 1, 255, 9, 2, 4];
         let config = MaskConfig::from_byte_slice(&bytes).unwrap();
         assert_eq!(
             config,
@@ -203,13 +386,15 @@ mod tests {
                 data_type: DataType::F64,
                 bound_type: BoundType::Bmax,
                 model_type: ModelType::M9,
+                sparsity_type: SparsityType::S2,
+                quantization_type: QuantizationType::Q4,
             }
         );
     }
 
     #[test]
     fn stream_deserialize() {
-        let mut bytes = vec![1, 1, 255, 9].into_iter();
+        let mut bytes = vec![1, 1, 255, 9, 2, 4].into_iter();
         let config = MaskConfig::from_byte_stream(&mut bytes).unwrap();
         assert_eq!(
             config,
@@ -218,7 +403,78 @@ mod tests {
                 data_type: DataType::F64,
                 bound_type: BoundType::Bmax,
                 model_type: ModelType::M9,
+                sparsity_type: SparsityType::S2,
+                quantization_type: QuantizationType::Q4,
             }
         );
     }
+
+    fn custom_config() -> MaskConfig {
+        MaskConfig {
+            group_type: GroupType::Prime,
+            data_type: DataType::F32,
+            bound_type: BoundType::Custom(300),
+            model_type: ModelType::M3,
+            sparsity_type: SparsityType::S0,
+            quantization_type: QuantizationType::Custom(3),
+        }
+    }
+
+    #[test]
+    fn serialize_custom() {
+        let config = custom_config();
+        assert_eq!(config.buffer_length(), 9);
+
+        let mut buf = vec![0xff; 9];
+        config.to_bytes(&mut buf);
+        assert_eq!(buf, vec![1, 0, 254, 3, 0, 254, 0xac, 0x02, 3]);
+    }
+
+    #[test]
+    fn deserialize_custom() {
+        let bytes = vec![1, 0, 254, 3, 0, 254, 0xac, 0x02, 3];
+        let config = MaskConfig::from_byte_slice(&bytes).unwrap();
+        assert_eq!(config, custom_config());
+    }
+
+    #[test]
+    fn stream_deserialize_custom() {
+        let mut bytes = vec![1, 0, 254, 3, 0, 254, 0xac, 0x02, 3, 42].into_iter();
+        let config = MaskConfig::from_byte_stream(&mut bytes).unwrap();
+        assert_eq!(config, custom_config());
+        assert_eq!(bytes.next(), Some(42));
+    }
+
+    #[test]
+    fn deserialize_invalid_custom() {
+        // truncated value
+        let bytes = vec![1, 0, 254, 3, 0, 254, 0xac];
+        assert!(MaskConfig::from_byte_slice(&bytes).is_err());
+        // zero bound
+        let bytes = vec![1, 0, 254, 3, 0, 255, 0];
+        assert!(MaskConfig::from_byte_slice(&bytes).is_err());
+        // non-minimal value
+        let bytes = vec![1, 0, 254, 3, 0, 255, 0x81, 0];
+        assert!(MaskConfig::from_byte_slice(&bytes).is_err());
+        // overflowing value
+        let bytes = vec![
+            1, 0, 254, 3, 0, 255, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 2,
+        ];
+        assert!(MaskConfig::from_byte_slice(&bytes).is_err());
+        // too many decimal places
+        let bytes = vec![1, 0, 0, 3, 0, 254, 21];
+        assert!(MaskConfig::from_byte_slice(&bytes).is_err());
+    }
+
+    #[test]
+    fn value_roundtrip() {
+        for value in [0, 1, 127, 128, 300, u64::MAX].iter() {
+            let mut buf = vec![0; value_len(*value)];
+            assert_eq!(write_value(*value, &mut buf), buf.len());
+            assert_eq!(
+                read_value(buf.iter().copied()).unwrap(),
+                (*value, buf.len())
+            );
+        }
+    }
 }
@@ -0,0 +1,407 @@
+//! Fixed-width arithmetic for masking configurations of small power-of-two group orders.
+//!
+//! The elements of mask vectors are generally represented as [`BigUint`]s. If the group order is
+//! a power of two which fits into 64 or 128 bits, the elements are represented as [`u64`]s or
+//! [`u128`]s instead, see [`MaskVectData`], which avoids the allocations of the big integers and
+//! allows for vectorized aggregation. The results are bit-identical to the big integer arithmetic.
+//!
+//! See the [mask module] documentation since this is a private module anyways.
+//!
+//! [mask module]: ../index.html
+
+use std::{fmt::Debug, ops::BitAnd};
+
+use num::bigint::BigUint;
+use rand::RngCore;
+use rand_chacha::ChaCha20Rng;
+
+use crate::mask::{
+    config::{GroupType, MaskConfig},
+    object::MaskVectData,
+};
+
+/// An unsigned integer of fixed width for the elements of mask objects.
+pub(crate) trait FixedInt: Copy + Debug + Eq + Ord + BitAnd<Output = Self> {
+    /// The number of bytes of the integer.
+    const BYTES: usize;
+
+    /// Creates an integer from at most [`Self::BYTES`] little endian `bytes`.
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    /// Writes the integer into little endian `bytes`, truncated or padded with zeros to their length.
+    fn to_le_slice(self, bytes: &mut [u8]);
+
+    /// Creates an integer from the lowest bits of a big integer.
+    fn from_biguint(int: &BigUint) -> Self;
+
+    /// Converts the integer into a big integer.
+    fn into_biguint(self) -> BigUint;
+
+    /// Adds two integers with wrap-around.
+    fn wrapping_add(self, other: Self) -> Self;
+
+    /// Subtracts two integers with wrap-around.
+    fn wrapping_sub(self, other: Self) -> Self;
+}
+
+impl FixedInt for u64 {
+    const BYTES: usize = 8;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        let mut buffer = [0_u8; 8];
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(buffer)
+    }
+
+    fn to_le_slice(self, bytes: &mut [u8]) {
+        let len = bytes.len().min(8);
+        bytes[..len].copy_from_slice(&self.to_le_bytes()[..len]);
+        for byte in bytes[len..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    fn from_biguint(int: &BigUint) -> Self {
+        int.iter_u64_digits().next().unwrap_or(0)
+    }
+
+    fn into_biguint(self) -> BigUint {
+        BigUint::from(self)
+    }
+
+    fn wrapping_add(self, other: Self) -> Self {
+        u64::wrapping_add(self, other)
+    }
+
+    fn wrapping_sub(self, other: Self) -> Self {
+        u64::wrapping_sub(self, other)
+    }
+}
+
+impl FixedInt for u128 {
+    const BYTES: usize = 16;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        let mut buffer = [0_u8; 16];
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        u128::from_le_bytes(buffer)
+    }
+
+    fn to_le_slice(self, bytes: &mut [u8]) {
+        let len = bytes.len().min(16);
+        bytes[..len].copy_from_slice(&self.to_le_bytes()[..len]);
+        for byte in bytes[len..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    fn from_biguint(int: &BigUint) -> Self {
+        let mut digits = int.iter_u64_digits();
+        let low = digits.next().unwrap_or(0) as u128;
+        let high = digits.next().unwrap_or(0) as u128;
+        high << 64 | low
+    }
+
+    fn into_biguint(self) -> BigUint {
+        BigUint::from(self)
+    }
+
+    fn wrapping_add(self, other: Self) -> Self {
+        u128::wrapping_add(self, other)
+    }
+
+    fn wrapping_sub(self, other: Self) -> Self {
+        u128::wrapping_sub(self, other)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// The fixed width of the elements of mask objects.
+pub(crate) enum FixedWidth {
+    /// Elements represented as [`u64`]s.
+    U64,
+    /// Elements represented as [`u128`]s.
+    U128,
+}
+
+impl FixedWidth {
+    /// Gets the fixed width of the elements of mask objects wrt the masking configuration.
+    ///
+    /// Returns `None` if the group order is not a power of two which fits into 128 bits.
+    pub(crate) fn of(config: &MaskConfig) -> Option<Self> {
+        if config.group_type != GroupType::Power2 {
+            return None;
+        }
+        // the order is 2^k, hence its bit length is k + 1
+        match config.order().bits() - 1 {
+            0..=64 => Some(Self::U64),
+            65..=128 => Some(Self::U128),
+            _ => None,
+        }
+    }
+}
+
+/// The parameters of the finite group of a power-of-two order for fixed-width arithmetic.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FixedGroup<T: FixedInt> {
+    /// The largest element of the group, i.e. the order minus one.
+    max: T,
+    /// The number of bytes of the order as a big integer, i.e. the number of random bytes to draw
+    /// per random element.
+    order_bytes: usize,
+}
+
+impl<T: FixedInt> FixedGroup<T> {
+    /// Creates the group parameters wrt the masking configuration.
+    ///
+    /// The masking configuration must be of the fixed width of `T`.
+    pub(crate) fn new(config: &MaskConfig) -> Self {
+        let order = config.order();
+        Self {
+            max: T::from_biguint(&(&order - 1_u8)),
+            order_bytes: ((order.bits() + 7) / 8) as usize,
+        }
+    }
+
+    /// Reduces an integer to an element of the group.
+    fn reduce(&self, int: T) -> T {
+        int & self.max
+    }
+
+    /// Generates a random element of the group.
+    ///
+    /// Draws exactly the same bytes from the `prng` as [`generate_integer()`], hence the elements
+    /// are identical.
+    ///
+    /// [`generate_integer()`]: crate::crypto::prng::generate_integer
+    pub(crate) fn generate(&self, prng: &mut ChaCha20Rng) -> T {
+        // the order has at most 17 bytes for elements of at most 128 bits
+        let mut bytes = [0_u8; 17];
+        loop {
+            prng.fill_bytes(&mut bytes[..self.order_bytes]);
+            if let Some(int) = self.decode(&bytes[..self.order_bytes]) {
+                return int;
+            }
+        }
+    }
+
+    /// Creates an element of the group from little endian `bytes`.
+    ///
+    /// Returns `None` if the integer isn't an element of the group.
+    pub(crate) fn decode(&self, bytes: &[u8]) -> Option<T> {
+        let low_bytes = bytes.len().min(T::BYTES);
+        if bytes[low_bytes..].iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        Some(T::from_le_slice(&bytes[..low_bytes])).filter(|int| self.contains(*int))
+    }
+
+    /// Checks if an integer is an element of the group.
+    pub(crate) fn contains(&self, int: T) -> bool {
+        int <= self.max
+    }
+
+    /// Masks the `ints` with random elements of the group drawn from the `prng`.
+    fn mask(&self, ints: &[BigUint], prng: &mut ChaCha20Rng) -> Vec<T> {
+        ints.iter()
+            .map(|int| self.reduce(T::from_biguint(int).wrapping_add(self.generate(prng))))
+            .collect()
+    }
+
+    /// Generates `len` random elements of the group drawn from the `prng`.
+    fn generate_vect(&self, len: usize, prng: &mut ChaCha20Rng) -> Vec<T> {
+        (0..len).map(|_| self.generate(prng)).collect()
+    }
+
+    /// Aggregates the `aggregated` elements with the elements of another mask vector.
+    pub(crate) fn aggregate(&self, aggregated: &mut [T], ints: &[T]) {
+        for (aggregated, int) in aggregated.iter_mut().zip(ints) {
+            *aggregated = self.reduce(aggregated.wrapping_add(*int));
+        }
+    }
+
+    /// Subtracts the elements of the `mask` from the `masked` elements.
+    pub(crate) fn unmask(self, masked: Vec<T>, mask: Vec<T>) -> impl Iterator<Item = BigUint> {
+        masked
+            .into_iter()
+            .zip(mask)
+            .map(move |(masked, mask)| self.reduce(masked.wrapping_sub(mask)).into_biguint())
+    }
+}
+
+/// Masks the `ints` with random elements of the finite group drawn from the `prng` using
+/// fixed-width arithmetic.
+///
+/// Returns `None` if the masking configuration is not eligible for fixed-width arithmetic, in which
+/// case nothing is drawn from the `prng`.
+pub(crate) fn mask(
+    config: &MaskConfig,
+    ints: &[BigUint],
+    prng: &mut ChaCha20Rng,
+) -> Option<MaskVectData> {
+    match FixedWidth::of(config)? {
+        FixedWidth::U64 => Some(MaskVectData::U64(
+            FixedGroup::<u64>::new(config).mask(ints, prng),
+        )),
+        FixedWidth::U128 => Some(MaskVectData::U128(
+            FixedGroup::<u128>::new(config).mask(ints, prng),
+        )),
+    }
+}
+
+/// Generates `len` random elements of the finite group drawn from the `prng` using fixed-width
+/// arithmetic.
+///
+/// Returns `None` if the masking configuration is not eligible for fixed-width arithmetic, in which
+/// case nothing is drawn from the `prng`.
+pub(crate) fn generate_vect(
+    config: &MaskConfig,
+    len: usize,
+    prng: &mut ChaCha20Rng,
+) -> Option<MaskVectData> {
+    match FixedWidth::of(config)? {
+        FixedWidth::U64 => Some(MaskVectData::U64(
+            FixedGroup::<u64>::new(config).generate_vect(len, prng),
+        )),
+        FixedWidth::U128 => Some(MaskVectData::U128(
+            FixedGroup::<u128>::new(config).generate_vect(len, prng),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{
+        crypto::prng::generate_integer,
+        mask::config::{BoundType, DataType, GroupType, ModelType, QuantizationType, SparsityType},
+    };
+
+    fn config(data_type: DataType, bound_type: BoundType, model_type: ModelType) -> MaskConfig {
+        MaskConfig {
+            group_type: GroupType::Power2,
+            data_type,
+            bound_type,
+            model_type,
+            sparsity_type: SparsityType::S0,
+            quantization_type: QuantizationType::Qmax,
+        }
+    }
+
+    /// Power-of-two configurations of various orders from 2^18 to 2^128.
+    fn configs() -> Vec<MaskConfig> {
+        vec![
+            MaskConfig {
+                quantization_type: QuantizationType::Q2,
+                ..config(DataType::F32, BoundType::B0, ModelType::M3)
+            },
+            config(DataType::F32, BoundType::B0, ModelType::M3),
+            config(DataType::I32, BoundType::B2, ModelType::M3),
+            config(DataType::F32, BoundType::B6, ModelType::M12),
+            config(DataType::F64, BoundType::B0, ModelType::M3),
+            config(DataType::F64, BoundType::B6, ModelType::M12),
+        ]
+    }
+
+    fn random_ints(config: &MaskConfig, len: usize, seed: u8) -> Vec<BigUint> {
+        let mut prng = ChaCha20Rng::from_seed([seed; 32]);
+        let order = config.order();
+        iter::repeat_with(|| generate_integer(&mut prng, &order))
+            .take(len)
+            .collect()
+    }
+
+    /// Aggregates the `first` and `second` integers and unmasks the aggregate by the `second`
+    /// integers again.
+    fn aggregate_and_unmask<T: FixedInt>(
+        config: &MaskConfig,
+        first: &[BigUint],
+        second: &[BigUint],
+    ) -> (Vec<BigUint>, Vec<BigUint>) {
+        let group = FixedGroup::<T>::new(config);
+        let mut aggregated = first.iter().map(T::from_biguint).collect::<Vec<_>>();
+        let second = second.iter().map(T::from_biguint).collect::<Vec<_>>();
+        group.aggregate(&mut aggregated, &second);
+        let unmasked = group.unmask(aggregated.clone(), second).collect();
+        let aggregated = aggregated.into_iter().map(T::into_biguint).collect();
+        (aggregated, unmasked)
+    }
+
+    #[test]
+    fn test_fixed_width() {
+        let widths = configs().iter().map(FixedWidth::of).collect::<Vec<_>>();
+        assert_eq!(
+            widths,
+            vec![
+                Some(FixedWidth::U64),
+                Some(FixedWidth::U64),
+                Some(FixedWidth::U64),
+                Some(FixedWidth::U128),
+                Some(FixedWidth::U128),
+                Some(FixedWidth::U128),
+            ],
+        );
+
+        let prime = MaskConfig {
+            group_type: GroupType::Prime,
+            ..config(DataType::F32, BoundType::B0, ModelType::M3)
+        };
+        assert_eq!(FixedWidth::of(&prime), None);
+        let unbounded = config(DataType::F32, BoundType::Bmax, ModelType::M3);
+        assert_eq!(FixedWidth::of(&unbounded), None);
+    }
+
+    #[test]
+    fn test_generate_vect_is_bit_identical() {
+        for config in configs() {
+            let expected = random_ints(&config, 100, 0);
+            let mut prng = ChaCha20Rng::from_seed([0_u8; 32]);
+            let generated = generate_vect(&config, 100, &mut prng).unwrap();
+            assert!(!matches!(generated, MaskVectData::BigUint(_)));
+            assert_eq!(generated.into_biguints(), expected);
+        }
+    }
+
+    #[test]
+    fn test_mask_is_bit_identical() {
+        for config in configs() {
+            let order = config.order();
+            let ints = random_ints(&config, 100, 1);
+            let masks = random_ints(&config, 100, 0);
+            let expected = ints
+                .iter()
+                .zip(masks)
+                .map(|(int, mask)| (int + mask) % &order)
+                .collect::<Vec<_>>();
+            let mut prng = ChaCha20Rng::from_seed([0_u8; 32]);
+            let masked = mask(&config, &ints, &mut prng).unwrap();
+            assert!(!matches!(masked, MaskVectData::BigUint(_)));
+            assert_eq!(masked.into_biguints(), expected);
+        }
+    }
+
+    #[test]
+    fn test_aggregate_and_unmask_are_bit_identical() {
+        for config in configs() {
+            let order = config.order();
+            let first = random_ints(&config, 100, 0);
+            let second = random_ints(&config, 100, 1);
+            let aggregated = first
+                .iter()
+                .zip(second.iter())
+                .map(|(first, second)| (first + second) % &order)
+                .collect::<Vec<_>>();
+
+            let (fixed, unmasked) = match FixedWidth::of(&config).unwrap() {
+                FixedWidth::U64 => aggregate_and_unmask::<u64>(&config, &first, &second),
+                FixedWidth::U128 => aggregate_and_unmask::<u128>(&config, &first, &second),
+            };
+            assert_eq!(fixed, aggregated);
+            assert_eq!(unmasked, first);
+        }
+    }
+}
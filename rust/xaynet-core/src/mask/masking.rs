@@ -4,12 +4,13 @@
 //!
 //! [mask module]: ../index.html
 
-use std::iter::{self, Iterator};
+use std::mem;
 
 use num::{
     bigint::{BigInt, BigUint, ToBigInt},
     clamp,
     rational::Ratio,
+    traits::Zero,
 };
 use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
@@ -19,8 +20,9 @@ use crate::{
     crypto::{prng::generate_integer, ByteObject},
     mask::{
         config::MaskConfigPair,
+        fixed::{self, FixedGroup},
         model::{float_to_ratio_bounded, Model},
-        object::{MaskObject, MaskUnit, MaskVect},
+        object::{MaskObject, MaskUnit, MaskVect, MaskVectData},
         seed::MaskSeed,
     },
 };
@@ -69,6 +71,10 @@ pub enum AggregationError {
 
 #[derive(Debug, Clone)]
 /// An aggregator for masks and masked models.
+///
+/// If the group order of the vector configuration is a power of two which fits into 128 bits, then
+/// the aggregated vector is represented as [`u64`]s or [`u128`]s instead of [`BigUint`]s, see
+/// [`MaskVectData`].
 pub struct Aggregation {
     nb_models: usize,
     object: MaskObject,
@@ -115,6 +121,77 @@ impl Aggregation {
         }
     }
 
+    /// Gets the number of aggregated masks or masked models.
+    pub fn nb_models(&self) -> usize {
+        self.nb_models
+    }
+
+    /// Splits the aggregator into at most `nb_shards` aggregators for consecutive ranges of the
+    /// aggregated vector, which can aggregate the corresponding ranges of further mask objects
+    /// independently of each other. Each shard aggregates the scalar as well.
+    ///
+    /// The lengths of the ranges differ by at most one. Mask objects must be split with
+    /// [`MaskObject::split()`] wrt the [`len()`]s of the shards before their aggregation and the
+    /// shards can be merged again with [`merge()`].
+    ///
+    /// [`len()`]: #method.len
+    /// [`merge()`]: #method.merge
+    pub fn into_shards(self, nb_shards: usize) -> Vec<Self> {
+        let nb_shards = nb_shards.min(self.object_size).max(1);
+        let (base, rest) = (self.object_size / nb_shards, self.object_size % nb_shards);
+        let lengths = (0..nb_shards)
+            .map(|i| if i < rest { base + 1 } else { base })
+            .collect::<Vec<_>>();
+
+        if self.nb_models == 0 {
+            let config = self.config();
+            return lengths
+                .into_iter()
+                .map(|len| Self::new(config, len))
+                .collect();
+        }
+
+        let nb_models = self.nb_models;
+        let object: MaskObject = self.into();
+        object
+            .split(&lengths)
+            .into_iter()
+            .zip(lengths)
+            .map(|(object, object_size)| Self {
+                nb_models,
+                object,
+                object_size,
+            })
+            .collect()
+    }
+
+    /// Merges the `shards` of an aggregator in the order in which they were created by
+    /// [`into_shards()`]. The shards must have aggregated the same mask objects.
+    ///
+    /// Returns `None` if there are no shards.
+    ///
+    /// [`into_shards()`]: #method.into_shards
+    pub fn merge(shards: Vec<Self>) -> Option<Self> {
+        let mut shards = shards.into_iter();
+        let first = shards.next()?;
+        let nb_models = first.nb_models;
+        let mut object_size = first.object_size;
+        let mut object: MaskObject = first.into();
+        for shard in shards {
+            object_size += shard.object_size;
+            let shard: MaskObject = shard.into();
+            object
+                .vect
+                .data
+                .extend(&object.vect.config, shard.vect.data);
+        }
+        Some(Self {
+            nb_models,
+            object,
+            object_size,
+        })
+    }
+
     /// Validates if unmasking of the aggregated masked model with the given `mask` may be
     /// safely performed.
     ///
@@ -204,20 +281,33 @@ impl Aggregation {
         let scaled_add_shift_n = config_n.add_shift() * BigInt::from(self.nb_models);
         let exp_shift_n = config_n.exp_shift();
         let order_n = config_n.order();
-        masked_n
-            .into_iter()
-            .zip(mask_n)
-            .map(|(masked, mask)| {
-                // PANIC_SAFE: The substraction panics if it
-                // underflows, which can only happen if:
-                //
-                //     mask > order_n
-                //
-                // If the mask is valid, we are guaranteed that this
-                // cannot happen. Thus this method may panic only if
-                // given an invalid mask.
-                let n = (masked + &order_n - mask) % &order_n;
-
+        let unmasked_n: Box<dyn Iterator<Item = BigUint> + '_> = match (masked_n, mask_n) {
+            (MaskVectData::U64(masked_n), MaskVectData::U64(mask_n)) => {
+                Box::new(FixedGroup::new(&config_n).unmask(masked_n, mask_n))
+            }
+            (MaskVectData::U128(masked_n), MaskVectData::U128(mask_n)) => {
+                Box::new(FixedGroup::new(&config_n).unmask(masked_n, mask_n))
+            }
+            (masked_n, mask_n) => Box::new(
+                masked_n
+                    .into_biguints()
+                    .into_iter()
+                    .zip(mask_n.into_biguints())
+                    .map(|(masked, mask)| {
+                        // PANIC_SAFE: The substraction panics if it
+                        // underflows, which can only happen if:
+                        //
+                        //     mask > order_n
+                        //
+                        // If the mask is valid, we are guaranteed that this
+                        // cannot happen. Thus this method may panic only if
+                        // given an invalid mask.
+                        (masked + &order_n - mask) % &order_n
+                    }),
+            ),
+        };
+        unmasked_n
+            .map(|n| {
                 // UNWRAP_SAFE: to_bigint never fails for BigUint
                 let ratio = Ratio::<BigInt>::from(n.to_bigint().unwrap());
                 let unmasked = ratio / &exp_shift_n - &scaled_add_shift_n;
@@ -294,15 +384,31 @@ impl Aggregation {
             return;
         }
 
-        let order_n = self.object.vect.config.order();
-        for (i, j) in self
-            .object
-            .vect
-            .data
-            .iter_mut()
-            .zip(object.vect.data.into_iter())
-        {
-            *i = (&*i + j) % &order_n
+        let config_n = &self.object.vect.config;
+        match (&mut self.object.vect.data, object.vect.data) {
+            (MaskVectData::U64(a), MaskVectData::U64(b)) => {
+                FixedGroup::new(config_n).aggregate(a, &b)
+            }
+            (MaskVectData::U128(a), MaskVectData::U128(b)) => {
+                FixedGroup::new(config_n).aggregate(a, &b)
+            }
+            (MaskVectData::BigUint(a), MaskVectData::BigUint(b)) => {
+                let order_n = config_n.order();
+                for (i, j) in a.iter_mut().zip(b) {
+                    *i = (&*i + j) % &order_n
+                }
+            }
+            // the representations only differ for invalid objects
+            (a, b) => {
+                let order_n = config_n.order();
+                let aggregated = mem::replace(a, MaskVectData::BigUint(Vec::new()))
+                    .into_biguints()
+                    .into_iter()
+                    .zip(b.into_biguints())
+                    .map(|(i, j)| (i + j) % &order_n)
+                    .collect();
+                *a = MaskVectData::new(config_n, aggregated);
+            }
         }
 
         let order_1 = self.object.unit.config.order();
@@ -343,7 +449,8 @@ impl Masker {
     /// - Clamp the scalar and the weights according to the masking configuration.
     /// - Scale the weights by the scalar.
     /// - Shift the weights into the non-negative reals.
-    /// - Shift the weights into the non-negative integers.
+    /// - Shift the weights into the non-negative integers. Quantized weights are stochastically
+    ///   rounded, all other weights are truncated.
     /// - Shift the weights into the finite group.
     /// - Mask the weights with random elements from the finite group.
     ///
@@ -354,7 +461,8 @@ impl Masker {
     ///
     /// [`unmask()`]: struct.Aggregation.html#method.unmask
     pub fn mask(self, scalar: f64, model: &Model) -> (MaskSeed, MaskObject) {
-        let (random_int, mut random_ints) = self.random_ints();
+        let (random_int, mut prng) = self.random_int();
+        let mut rounding_prng = self.rounding_prng();
         let Self { config, seed } = self;
         let MaskConfigPair {
             vect: config_n,
@@ -370,25 +478,41 @@ impl Masker {
         let exp_shift_n = config_n.exp_shift();
         let add_shift_n = config_n.add_shift();
         let order_n = config_n.order();
+        let is_quantized = config_n.is_quantized();
         let higher_bound = &add_shift_n;
         let lower_bound = -&add_shift_n;
 
-        // mask the (scaled) weights
-        let masked_weights = model
+        // shift the (scaled) weights
+        let shifted_weights = model
             .iter()
-            .zip(&mut random_ints)
-            .map(|(weight, rand_int)| {
+            .map(|weight| {
                 let scaled = scalar_clamped * weight;
                 let scaled_clamped = clamp(&scaled, &lower_bound, higher_bound);
+                let shifted = (scaled_clamped + &add_shift_n) * &exp_shift_n;
+                let shifted = if is_quantized {
+                    round_stochastically(&shifted, &mut rounding_prng)
+                } else {
+                    shifted.to_integer()
+                };
                 // PANIC_SAFE: shifted weight is guaranteed to be non-negative
-                let shifted = ((scaled_clamped + &add_shift_n) * &exp_shift_n)
-                    .to_integer()
-                    .to_biguint()
-                    .unwrap();
-                (shifted + rand_int) % &order_n
+                shifted.to_biguint().unwrap()
             })
-            .collect();
-        let masked_model = MaskVect::new_unchecked(config_n, masked_weights);
+            .collect::<Vec<_>>();
+
+        // mask the shifted weights, preferably with fixed-width arithmetic
+        let masked_weights =
+            fixed::mask(&config_n, &shifted_weights, &mut prng).unwrap_or_else(|| {
+                MaskVectData::BigUint(
+                    shifted_weights
+                        .into_iter()
+                        .map(|shifted| (shifted + generate_integer(&mut prng, &order_n)) % &order_n)
+                        .collect(),
+                )
+            });
+        let masked_model = MaskVect {
+            data: masked_weights,
+            config: config_n,
+        };
 
         // mask the scalar
         // PANIC_SAFE: shifted scalar is guaranteed to be non-negative
@@ -402,17 +526,45 @@ impl Masker {
         (seed, MaskObject::new_unchecked(masked_model, masked_scalar))
     }
 
-    /// Randomly generates integers wrt the masking configurations.
+    /// Randomly generates an integer wrt the scalar configuration.
+    ///
+    /// The PRNG is returned as well, since the integers wrt the vector configuration are generated
+    /// afterwards from the same PRNG.
+    fn random_int(&self) -> (BigUint, ChaCha20Rng) {
+        let mut prng = ChaCha20Rng::from_seed(self.seed.as_array());
+        let int = generate_integer(&mut prng, &self.config.unit.order());
+        (int, prng)
+    }
+
+    /// Creates a PRNG for the stochastic rounding of quantized weights.
     ///
-    /// The first is generated wrt the scalar configuration, while the rest are
-    /// wrt the vector configuration and returned as an iterator.
-    fn random_ints(&self) -> (BigUint, impl Iterator<Item = BigUint>) {
-        let order_n = self.config.vect.order();
-        let order_1 = self.config.unit.order();
+    /// The PRNG is derived from the seed, but employs another stream than the masks, such that the
+    /// masks don't depend on the quantization.
+    fn rounding_prng(&self) -> ChaCha20Rng {
         let mut prng = ChaCha20Rng::from_seed(self.seed.as_array());
-        let int = generate_integer(&mut prng, &order_1);
-        let ints = iter::from_fn(move || Some(generate_integer(&mut prng, &order_n)));
-        (int, ints)
+        prng.set_stream(1);
+        prng
+    }
+}
+
+/// Rounds the non-negative `ratio` stochastically to one of its neighbouring integers.
+///
+/// The `ratio` is rounded up with a probability equal to its fractional part and rounded down
+/// otherwise, hence the rounding is unbiased.
+fn round_stochastically(ratio: &Ratio<BigInt>, prng: &mut ChaCha20Rng) -> BigInt {
+    let floor = ratio.to_integer();
+    let fract = ratio.fract();
+    if fract.is_zero() {
+        return floor;
+    }
+
+    // UNWRAP_SAFE: the fractional part of a non-negative ratio is non-negative
+    let numer = fract.numer().to_biguint().unwrap();
+    let denom = fract.denom().to_biguint().unwrap();
+    if generate_integer(prng, &denom) < numer {
+        floor + 1
+    } else {
+        floor
     }
 }
 
@@ -430,11 +582,13 @@ mod tests {
     use super::*;
     use crate::mask::{
         config::{
-            BoundType::{Bmax, B0, B2, B4, B6},
+            BoundType::{self, Bmax, B0, B2, B4, B6},
             DataType::{F32, F64, I32, I64},
             GroupType::{Integer, Power2, Prime},
             MaskConfig,
-            ModelType::M3,
+            ModelType::{self, M3},
+            QuantizationType::{self, Qmax, Q2},
+            SparsityType::S0,
         },
         model::FromPrimitives,
     };
@@ -470,6 +624,8 @@ mod tests {
                             _ => Bmax,
                         },
                         model_type: M3,
+                        sparsity_type: S0,
+                        quantization_type: Qmax,
                     };
                     let vect_len = $len as usize;
 
@@ -619,6 +775,8 @@ mod tests {
                             _ => Bmax,
                         },
                         model_type: M3,
+                        sparsity_type: S0,
+                        quantization_type: Qmax,
                     };
                     let vect_len = $len as usize;
 
@@ -723,6 +881,8 @@ mod tests {
                         data_type: $data,
                         bound_type: $bound,
                         model_type: M3,
+                        sparsity_type: S0,
+                        quantization_type: Qmax,
                     };
                     let vect_len = $len as usize;
 
@@ -749,10 +909,11 @@ mod tests {
                         aggregated_masked_model.aggregate(masked_model);
 
                         assert_eq!(aggregated_masked_model.nb_models, nb);
-                        assert_eq!(aggregated_masked_model.object.vect.data.len(), vect_len);
-                        assert_eq!(aggregated_masked_model.object.vect.config, config);
-                        assert_eq!(aggregated_masked_model.object.unit.config, config);
-                        assert!(aggregated_masked_model.object.is_valid());
+                        let aggregated_object: MaskObject = aggregated_masked_model.clone().into();
+                        assert_eq!(aggregated_object.vect.data.len(), vect_len);
+                        assert_eq!(aggregated_object.vect.config, config);
+                        assert_eq!(aggregated_object.unit.config, config);
+                        assert!(aggregated_object.is_valid());
                     }
                 }
             }
@@ -863,6 +1024,8 @@ mod tests {
                             _ => Bmax,
                         },
                         model_type: M3,
+                        sparsity_type: S0,
+                        quantization_type: Qmax,
                     };
                     let vect_len = $len as usize;
 
@@ -1046,6 +1209,8 @@ mod tests {
                             _ => Bmax,
                         },
                         model_type: M3,
+                        sparsity_type: S0,
+                        quantization_type: Qmax,
                     };
                     let vect_len = $len as usize;
 
@@ -1143,4 +1308,157 @@ mod tests {
     test_masking_and_aggregation_scalar!(pow_f64_b4, Power2, f64, 10_000, 10, 2);
     test_masking_and_aggregation_scalar!(pow_f64_b6, Power2, f64, 1_000_000, 10, 2);
     test_masking_and_aggregation_scalar!(pow_f64_bmax, Power2, f64, 10, 2);
+
+    #[test]
+    fn test_sharded_aggregation() {
+        for group_type in [Prime, Power2].iter() {
+            let config = MaskConfig {
+                group_type: *group_type,
+                data_type: F32,
+                bound_type: B0,
+                model_type: M3,
+                sparsity_type: S0,
+                quantization_type: Qmax,
+            };
+            let mut prng = ChaCha20Rng::from_seed(MaskSeed::generate().as_array());
+            let order = config.order();
+            let objects = iter::repeat_with(|| {
+                let integer = generate_integer(&mut prng, &order);
+                let integers = iter::repeat_with(|| generate_integer(&mut prng, &order))
+                    .take(10)
+                    .collect::<Vec<_>>();
+                MaskObject::new(config.into(), integers, integer).unwrap()
+            })
+            .take(3)
+            .collect::<Vec<_>>();
+
+            let mut aggregation = Aggregation::new(config.into(), 10);
+            for object in objects.iter().cloned() {
+                aggregation.aggregate(object);
+            }
+
+            let mut shards = Aggregation::new(config.into(), 10).into_shards(4);
+            assert_eq!(
+                shards.iter().map(Aggregation::len).collect::<Vec<_>>(),
+                vec![3, 3, 2, 2],
+            );
+            let lengths = shards.iter().map(Aggregation::len).collect::<Vec<_>>();
+            for object in objects.iter().cloned() {
+                for (shard, object) in shards.iter_mut().zip(object.split(&lengths)) {
+                    assert!(shard.validate_aggregation(&object).is_ok());
+                    shard.aggregate(object);
+                }
+            }
+            let merged = Aggregation::merge(shards).unwrap();
+            assert_eq!(merged.nb_models(), 3);
+            assert_eq!(merged.len(), 10);
+            assert_eq!(
+                <Aggregation as Into<MaskObject>>::into(merged),
+                aggregation.clone().into(),
+            );
+
+            let resharded = Aggregation::merge(aggregation.clone().into_shards(3)).unwrap();
+            assert_eq!(resharded.nb_models(), 3);
+            assert_eq!(
+                <Aggregation as Into<MaskObject>>::into(resharded),
+                aggregation.into(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_masking_quantized() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: B0,
+            model_type: M3,
+            sparsity_type: S0,
+            quantization_type: Q2,
+        };
+        let weights = vec![0.123_f32, -0.5, 0.999, 1., -1.];
+        let model = Model::from_primitives(weights.into_iter()).unwrap();
+
+        let seed = MaskSeed::generate();
+        let (mask_seed, masked_model) =
+            Masker::with_seed(config.into(), seed.clone()).mask(1_f64, &model);
+        assert!(masked_model.is_valid());
+        assert_eq!(masked_model.vect.config.bytes_per_number(), 3);
+        assert_eq!(masked_model.unit.config.quantization_type, Qmax);
+
+        // the stochastic rounding is derived from the seed as well
+        let (_, masked_model_again) = Masker::with_seed(config.into(), seed).mask(1_f64, &model);
+        assert_eq!(masked_model, masked_model_again);
+
+        let mask = mask_seed.derive_mask(model.len(), config.into());
+        let unmasked_model = Aggregation::from(masked_model).unmask(mask);
+        let tolerance = Ratio::new(BigInt::from(1), BigInt::from(100));
+        assert!(model
+            .iter()
+            .zip(unmasked_model.iter())
+            .all(|(weight, unmasked_weight)| (weight - unmasked_weight).abs() <= tolerance));
+    }
+
+    #[test]
+    fn test_masking_and_aggregation_custom() {
+        let config = MaskConfig {
+            group_type: Prime,
+            data_type: F32,
+            bound_type: BoundType::Custom(3),
+            model_type: ModelType::Custom(2),
+            sparsity_type: S0,
+            quantization_type: QuantizationType::Custom(3),
+        };
+        assert!(config.validate().is_ok());
+        let models = vec![
+            Model::from_primitives(vec![2.5_f32, -3., 0.123].into_iter()).unwrap(),
+            Model::from_primitives(vec![-1.5_f32, 3., 0.5].into_iter()).unwrap(),
+        ];
+
+        let mut aggregated_masked_model = Aggregation::new(config.into(), 3);
+        let mut aggregated_mask = Aggregation::new(config.into(), 3);
+        for model in models.iter() {
+            let (mask_seed, masked_model) = Masker::new(config.into()).mask(0.5, model);
+            assert!(aggregated_masked_model
+                .validate_aggregation(&masked_model)
+                .is_ok());
+            aggregated_masked_model.aggregate(masked_model);
+            aggregated_mask.aggregate(mask_seed.derive_mask(3, config.into()));
+        }
+        let (_, masked_model) = Masker::new(config.into()).mask(0.5, &models[0]);
+        assert!(matches!(
+            aggregated_masked_model.validate_aggregation(&masked_model),
+            Err(AggregationError::TooManyModels),
+        ));
+
+        let unmasked_model = aggregated_masked_model.unmask(aggregated_mask.into());
+        let averaged_model = vec![
+            Ratio::new(BigInt::from(1), BigInt::from(2)),
+            Ratio::from_integer(BigInt::from(0)),
+            Ratio::new(BigInt::from(623), BigInt::from(2_000)),
+        ];
+        let tolerance = Ratio::new(BigInt::from(1), BigInt::from(1_000));
+        assert!(averaged_model
+            .iter()
+            .zip(unmasked_model.iter())
+            .all(|(weight, unmasked_weight)| (weight - unmasked_weight).abs() <= tolerance));
+    }
+
+    #[test]
+    fn test_round_stochastically() {
+        let mut prng = ChaCha20Rng::from_seed([0_u8; 32]);
+        let integer = Ratio::from_integer(BigInt::from(3));
+        assert_eq!(round_stochastically(&integer, &mut prng), BigInt::from(3));
+
+        // 3.25 is rounded up to 4 in about a quarter of the cases
+        let ratio = Ratio::new(BigInt::from(13), BigInt::from(4));
+        let rounded = iter::repeat_with(|| round_stochastically(&ratio, &mut prng))
+            .take(10_000)
+            .collect::<Vec<_>>();
+        assert!(rounded
+            .iter()
+            .all(|int| *int == BigInt::from(3) || *int == BigInt::from(4)));
+        let sum = rounded.into_iter().sum::<BigInt>();
+        assert!(BigInt::from(32_300) < sum && sum < BigInt::from(32_700));
+    }
 }
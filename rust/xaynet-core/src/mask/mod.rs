@@ -22,14 +22,16 @@
 //! # Masking configurations
 //! The masking, aggregation and unmasking of models requires certain information about the models
 //! to guarantee that no information is lost during the process, which is configured via the
-//! [`MaskConfig`]. Each masking configuration consists of the group type, data type, bound type and
-//! model type. Usually, a masking configuration is decided on and configured depending on the
-//! specific machine learning use case as part of the setup for the XayNet federated learning
-//! platform.
+//! [`MaskConfig`]. Each masking configuration consists of the group type, data type, bound type,
+//! model type, sparsity type and quantization type. Usually, a masking configuration is decided on
+//! and configured depending on the specific machine learning use case as part of the setup for the
+//! XayNet federated learning platform.
 //!
-//! Currently, those choices are catalogued for certain fixed variants for each type, but we aim
-//! to generalize this in the future to more flexible masking configurations to allow for a more
-//! fine-grained tradeoff between representability and performance.
+//! Those choices are catalogued for certain fixed variants for each type. Additionally, the bound,
+//! model and quantization types have custom variants for arbitrary absolute bounds, maximum numbers
+//! of models and decimal places, which allow for a more fine-grained tradeoff between
+//! representability and performance. [`MaskConfig::recommend()`] gets the smallest masking
+//! configuration for a given model and number of participants.
 //!
 //! ## Group type
 //! The [`GroupType`] describes the order of the finite group in which the masked model weights are
@@ -41,6 +43,10 @@
 //! - Prime: usually small gap with higher performance.
 //! - Power2: usually higher gap with potentially highest performance.
 //!
+//! If the order of a Power2 group fits into 64 or 128 bits, then masking and aggregation are
+//! performed with fixed-width [`u64`] or [`u128`] arithmetic instead of arbitrary precision
+//! arithmetic. This is selected automatically and the results are bit-identical.
+//!
 //! ## Data type
 //! The [`DataType`] describes the original primitive data type of the model weights. This in
 //! combination with the bound type influences the preserved decimal places of the model weights
@@ -63,6 +69,7 @@
 //! - B6: all model weights are absolutely bounded by 1,000,000.
 //! - Bmax: all model weights are absolutely bounded by their primitive data type's absolute
 //!   maximum value.
+//! - Custom: all model weights are absolutely bounded by a custom positive integer.
 //!
 //! ## Model type
 //! The [`ModelType`] describes the maximum number of masked models that can be aggregated without
@@ -72,6 +79,33 @@
 //! - M6: at most 1,000,000 masked models may be aggregated.
 //! - M9: at most 1,000,000,000 masked models may be aggregated.
 //! - M12: at most 1,000,000,000,000 masked models may be aggregated.
+//! - Custom: at most a custom positive number of masked models may be aggregated. This is not
+//!   supported for the Bmax bound type.
+//!
+//! ## Sparsity type
+//! The [`SparsityType`] describes the fraction of the model weights which are masked and sent to
+//! the coordinator. The masked weights are a random subset of the model weights which is derived
+//! from the round seed via a [`MaskSelection`], hence all participants of a round mask the same
+//! weights and the masked weights can be aggregated as usual. The sparsity type variants are:
+//! - S0: all model weights are masked.
+//! - S1: one in 10 model weights is masked.
+//! - S2: one in 100 model weights is masked.
+//! - S3: one in 1,000 model weights is masked.
+//!
+//! Selecting the weights per participant instead, e.g. the top-k weights of each local model, is
+//! not supported, because masked weights can only be aggregated if they belong to the same indices.
+//!
+//! ## Quantization type
+//! The [`QuantizationType`] describes the preserved decimal places of bounded model weights. The
+//! less decimal places, the less bytes are required to represent the masked model weights. The
+//! model weights are stochastically rounded to the preserved decimal places, such that the rounding
+//! is unbiased on average. The quantization type variants are:
+//! - Q2: 2 decimal places.
+//! - Q4: 4 decimal places.
+//! - Qmax: the decimal places of the data type.
+//! - Custom: a custom number of decimal places up to 20.
+//!
+//! Quantization is ignored for the Bmax bound type.
 //!
 //! # Masking, aggregation and unmasking
 //! Local models should be masked (i.e. encrypted) before they are communicated somewhere else to
@@ -89,7 +123,7 @@
 //! can be generated via the additionally returned [`MaskSeed`].
 //!
 //! ```
-//! # use xaynet_core::mask::{BoundType, DataType, FromPrimitives, GroupType, MaskConfig, Masker, Model, ModelType};
+//! # use xaynet_core::mask::{BoundType, DataType, FromPrimitives, GroupType, MaskConfig, Masker, Model, ModelType, QuantizationType, SparsityType};
 //! // create local models and a fitting masking configuration
 //! let number_weights = 10;
 //! let scalar = 0.5;
@@ -100,6 +134,8 @@
 //!     data_type: DataType::F32,
 //!     bound_type: BoundType::B0,
 //!     model_type: ModelType::M3,
+//!     sparsity_type: SparsityType::S0,
+//!     quantization_type: QuantizationType::Qmax,
 //! };
 //!
 //! // mask the local models
@@ -118,12 +154,12 @@
 //! safely performed wrt the chosen masking configuration without possible loss of information.
 //!
 //! ```
-//! # use xaynet_core::mask::{Aggregation, BoundType, DataType, FromPrimitives, GroupType, MaskConfig, Masker, MaskObject, Model, ModelType};
+//! # use xaynet_core::mask::{Aggregation, BoundType, DataType, FromPrimitives, GroupType, MaskConfig, Masker, MaskObject, Model, ModelType, QuantizationType, SparsityType};
 //! # let number_weights = 10;
 //! # let scalar = 0.5;
 //! # let local_model_1 = Model::from_primitives_bounded(vec![0_f32; number_weights].into_iter());
 //! # let local_model_2 = Model::from_primitives_bounded(vec![1_f32; number_weights].into_iter());
-//! # let config = MaskConfig { group_type: GroupType::Prime, data_type: DataType::F32, bound_type: BoundType::B0, model_type: ModelType::M3};
+//! # let config = MaskConfig { group_type: GroupType::Prime, data_type: DataType::F32, bound_type: BoundType::B0, model_type: ModelType::M3, sparsity_type: SparsityType::S0, quantization_type: QuantizationType::Qmax };
 //! # let (local_mask_seed_1, masked_local_model_1) = Masker::new(config.into()).mask(scalar, &local_model_1);
 //! # let (local_mask_seed_2, masked_local_model_2) = Masker::new(config.into()).mask(scalar, &local_model_2);
 //! # let local_model_mask_1 = local_mask_seed_1.derive_mask(number_weights, config.into());
@@ -154,12 +190,12 @@
 //! configuration without possible loss of information.
 //!
 //! ```no_run
-//! # use xaynet_core::mask::{Aggregation, BoundType, DataType, FromPrimitives, GroupType, MaskConfig, Masker, MaskObject, Model, ModelType};
+//! # use xaynet_core::mask::{Aggregation, BoundType, DataType, FromPrimitives, GroupType, MaskConfig, Masker, MaskObject, Model, ModelType, QuantizationType, SparsityType};
 //! # let number_weights = 10;
 //! # let scalar = 0.5;
 //! # let local_model_1 = Model::from_primitives_bounded(vec![0_f32; number_weights].into_iter());
 //! # let local_model_2 = Model::from_primitives_bounded(vec![1_f32; number_weights].into_iter());
-//! # let config = MaskConfig { group_type: GroupType::Prime, data_type: DataType::F32, bound_type: BoundType::B0, model_type: ModelType::M3};
+//! # let config = MaskConfig { group_type: GroupType::Prime, data_type: DataType::F32, bound_type: BoundType::B0, model_type: ModelType::M3, sparsity_type: SparsityType::S0, quantization_type: QuantizationType::Qmax };
 //! # let (local_mask_seed_1, masked_local_model_1) = Masker::new(config.into()).mask(scalar, &local_model_1);
 //! # let (local_mask_seed_2, masked_local_model_2) = Masker::new(config.into()).mask(scalar, &local_model_2);
 //! # let local_model_mask_1 = local_mask_seed_1.derive_mask(number_weights, config.into());
@@ -180,12 +216,42 @@
 //!     );
 //! };
 //! ```
+//!
+//! # Secret sharing of mask seeds
+//! A [`MaskSeed`] can be split into shares via Shamir's secret sharing over `GF(2^8)`, such that
+//! any number of shares above a threshold suffices to reconstruct the seed, while fewer shares
+//! reveal nothing about it.
+//!
+//! This allows to unmask an aggregated masked model even if some of the sum participants drop out:
+//! An update participant masks its model with one mask seed per sum participant via
+//! [`share_mask_seed()`] and splits each of these seeds among the other sum participants. Every
+//! sum participant aggregates only the masks derived from its own seeds. The aggregated masks of
+//! the sum participants who drop out are reconstructed from the shares held by the others, such
+//! that the seeds of the remaining sum participants are never revealed.
+//!
+//! ```
+//! # use xaynet_core::{crypto::ByteObject, mask::MaskSeed};
+//! let seed = MaskSeed::generate();
+//! let shares = seed.split(2, 3).unwrap();
+//! let reconstructed = MaskSeed::reconstruct(&[(1, shares[0].clone()), (3, shares[2].clone())]);
+//! assert_eq!(reconstructed.unwrap(), seed);
+//! ```
+//!
+//! # Noise
+//! Differential privacy for the models is provided by adding noise to their weights, either
+//! locally by the participants or centrally by the coordinator. The noise is sampled from a
+//! centered normal distribution via [`sample_gaussian()`] or from a centered Laplace distribution
+//! via [`sample_laplace()`].
 
 pub(crate) mod config;
+pub(crate) mod fixed;
 pub(crate) mod masking;
 pub(crate) mod model;
+pub(crate) mod noise;
 pub(crate) mod object;
 pub(crate) mod seed;
+pub(crate) mod sharing;
+pub(crate) mod sparsity;
 
 pub use self::{
     config::{
@@ -197,15 +263,22 @@ pub use self::{
         MaskConfig,
         MaskConfigPair,
         ModelType,
+        QuantizationType,
+        SparsityType,
+        MAX_DECIMAL_PLACES,
     },
     masking::{Aggregation, AggregationError, Masker, UnmaskingError},
     model::{FromPrimitives, IntoPrimitives, Model, ModelCastError, PrimitiveCastError},
+    noise::{sample_gaussian, sample_laplace},
     object::{
         serialization::vect::MaskVectBuffer,
         InvalidMaskObjectError,
         MaskObject,
         MaskUnit,
         MaskVect,
+        MaskVectData,
     },
     seed::{EncryptedMaskSeed, MaskSeed},
+    sharing::{share_indices, share_mask_seed, sharing_threshold, SharingError, MAX_SHARES},
+    sparsity::MaskSelection,
 };
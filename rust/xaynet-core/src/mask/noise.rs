@@ -0,0 +1,63 @@
+//! Sampling of noise for differential privacy.
+//!
+//! See the [mask module] documentation since this is a private module anyways.
+//!
+//! [mask module]: ../index.html
+
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+/// Samples from a centered normal distribution via the Box-Muller transform.
+pub fn sample_gaussian<R: Rng>(rng: &mut R, std_dev: f64) -> f64 {
+    // u_1 is sampled from (0, 1] to avoid ln(0)
+    let u_1 = 1. - rng.gen::<f64>();
+    let u_2 = rng.gen::<f64>();
+    std_dev * (-2. * u_1.ln()).sqrt() * (2. * PI * u_2).cos()
+}
+
+/// Samples from a centered Laplace distribution via the inverse cumulative distribution function.
+pub fn sample_laplace<R: Rng>(rng: &mut R, diversity: f64) -> f64 {
+    loop {
+        let u = rng.gen::<f64>() - 0.5;
+        let v = 1. - 2. * u.abs();
+        // v is 0 if u is exactly -0.5, which would result in ln(0)
+        if v > 0. {
+            return -diversity * u.signum() * v.ln();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+
+    use super::*;
+
+    // Returns the mean and the variance of the samples.
+    fn moments(sample: impl Fn(&mut ChaCha20Rng) -> f64) -> (f64, f64) {
+        let len = 100_000;
+        let mut rng = ChaCha20Rng::from_seed([0; 32]);
+        let samples: Vec<f64> = (0..len).map(|_| sample(&mut rng)).collect();
+
+        let mean = samples.iter().sum::<f64>() / len as f64;
+        let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / len as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_sample_gaussian() {
+        let std_dev = 2.;
+        let (mean, variance) = moments(|rng| sample_gaussian(rng, std_dev));
+        assert!(mean.abs() < 0.05 * std_dev);
+        assert!((variance / (std_dev * std_dev) - 1.).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_sample_laplace() {
+        let diversity = 2.;
+        let (mean, variance) = moments(|rng| sample_laplace(rng, diversity));
+        assert!(mean.abs() < 0.05 * diversity);
+        assert!((variance / (2. * diversity * diversity) - 1.).abs() < 0.05);
+    }
+}
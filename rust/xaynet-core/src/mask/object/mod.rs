@@ -6,30 +6,149 @@
 
 pub mod serialization;
 
-use std::iter::Iterator;
+use std::{iter::Iterator, mem};
 
 use num::bigint::BigUint;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::mask::config::{MaskConfig, MaskConfigPair};
+use crate::mask::{
+    config::{MaskConfig, MaskConfigPair},
+    fixed::{FixedGroup, FixedInt, FixedWidth},
+};
 
 #[derive(Error, Debug)]
 #[error("the mask object is invalid: data is incompatible with the masking configuration")]
 /// Errors related to invalid mask objects.
 pub struct InvalidMaskObjectError;
 
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// The elements of a mask vector.
+///
+/// The elements are represented as [`u64`]s or [`u128`]s if the group order of the masking
+/// configuration is a power of two which fits into 64 or 128 bits and as [`BigUint`]s otherwise.
+pub enum MaskVectData {
+    /// Elements of arbitrary size.
+    BigUint(Vec<BigUint>),
+    /// Elements of power-of-two groups of orders up to `2^64`.
+    U64(Vec<u64>),
+    /// Elements of power-of-two groups of orders up to `2^128`.
+    U128(Vec<u128>),
+}
+
+impl MaskVectData {
+    /// Creates the elements in the representation of the masking configuration.
+    ///
+    /// The elements are kept as [`BigUint`]s if any of them isn't an element of the group, in
+    /// which case they don't conform to the masking configuration.
+    pub fn new(config: &MaskConfig, data: Vec<BigUint>) -> Self {
+        match FixedWidth::of(config) {
+            Some(FixedWidth::U64) => Self::from_biguints(config, data, Self::U64),
+            Some(FixedWidth::U128) => Self::from_biguints(config, data, Self::U128),
+            None => Self::BigUint(data),
+        }
+    }
+
+    /// Converts the elements into fixed-width integers if they are elements of the group.
+    fn from_biguints<T: FixedInt>(
+        config: &MaskConfig,
+        data: Vec<BigUint>,
+        variant: fn(Vec<T>) -> Self,
+    ) -> Self {
+        let order = config.order();
+        if data.iter().all(|int| int < &order) {
+            variant(data.iter().map(T::from_biguint).collect())
+        } else {
+            Self::BigUint(data)
+        }
+    }
+
+    /// Creates an empty vector of elements in the representation of the masking configuration.
+    fn with_capacity(config: &MaskConfig, capacity: usize) -> Self {
+        match FixedWidth::of(config) {
+            Some(FixedWidth::U64) => Self::U64(Vec::with_capacity(capacity)),
+            Some(FixedWidth::U128) => Self::U128(Vec::with_capacity(capacity)),
+            None => Self::BigUint(Vec::with_capacity(capacity)),
+        }
+    }
+
+    /// Gets the number of elements.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::BigUint(data) => data.len(),
+            Self::U64(data) => data.len(),
+            Self::U128(data) => data.len(),
+        }
+    }
+
+    /// Checks if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Converts the elements into big integers.
+    pub fn into_biguints(self) -> Vec<BigUint> {
+        match self {
+            Self::BigUint(data) => data,
+            Self::U64(data) => data.into_iter().map(BigUint::from).collect(),
+            Self::U128(data) => data.into_iter().map(BigUint::from).collect(),
+        }
+    }
+
+    /// Appends the `other` elements.
+    ///
+    /// The elements are converted into the representation of the masking configuration if the
+    /// representations differ.
+    pub(crate) fn extend(&mut self, config: &MaskConfig, other: Self) {
+        match (&mut *self, other) {
+            (Self::BigUint(data), Self::BigUint(other)) => data.extend(other),
+            (Self::U64(data), Self::U64(other)) => data.extend(other),
+            (Self::U128(data), Self::U128(other)) => data.extend(other),
+            (this, other) => {
+                let mut data = mem::replace(this, Self::BigUint(Vec::new())).into_biguints();
+                data.extend(other.into_biguints());
+                *this = Self::new(config, data);
+            }
+        }
+    }
+
+    /// Splits the elements into consecutive ranges of the given `lengths`.
+    ///
+    /// Elements beyond the sum of the `lengths` are dropped.
+    fn split(self, lengths: &[usize]) -> Vec<Self> {
+        fn split<T>(data: Vec<T>, lengths: &[usize]) -> Vec<Vec<T>> {
+            let mut data = data.into_iter();
+            lengths
+                .iter()
+                .map(|len| data.by_ref().take(*len).collect())
+                .collect()
+        }
+
+        match self {
+            Self::BigUint(data) => split(data, lengths)
+                .into_iter()
+                .map(Self::BigUint)
+                .collect(),
+            Self::U64(data) => split(data, lengths).into_iter().map(Self::U64).collect(),
+            Self::U128(data) => split(data, lengths).into_iter().map(Self::U128).collect(),
+        }
+    }
+}
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Serialize, Deserialize)]
 /// A *mask vector* which represents a masked model or its corresponding mask.
 pub struct MaskVect {
-    pub data: Vec<BigUint>,
+    pub data: MaskVectData,
     pub config: MaskConfig,
 }
 
 impl MaskVect {
     /// Creates a new mask vector from the given data and masking configuration.
     pub fn new_unchecked(config: MaskConfig, data: Vec<BigUint>) -> Self {
-        Self { data, config }
+        Self {
+            data: MaskVectData::new(&config, data),
+            config,
+        }
     }
 
     /// Creates a new mask vector from the given data and masking configuration.
@@ -48,15 +167,30 @@ impl MaskVect {
     /// Creates a new empty mask vector of given size and masking configuration.
     pub fn empty(config: MaskConfig, size: usize) -> Self {
         Self {
-            data: Vec::with_capacity(size),
+            data: MaskVectData::with_capacity(&config, size),
             config,
         }
     }
 
     /// Checks if the elements of this mask vector conform to the masking configuration.
+    ///
+    /// The elements must be in the representation of the masking configuration as well.
     pub fn is_valid(&self) -> bool {
-        let order = self.config.order();
-        self.data.iter().all(|i| i < &order)
+        match (&self.data, FixedWidth::of(&self.config)) {
+            (MaskVectData::BigUint(data), None) => {
+                let order = self.config.order();
+                data.iter().all(|i| i < &order)
+            }
+            (MaskVectData::U64(data), Some(FixedWidth::U64)) => {
+                let group = FixedGroup::new(&self.config);
+                data.iter().all(|i| group.contains(*i))
+            }
+            (MaskVectData::U128(data), Some(FixedWidth::U128)) => {
+                let group = FixedGroup::new(&self.config);
+                data.iter().all(|i| group.contains(*i))
+            }
+            _ => false,
+        }
     }
 }
 
@@ -148,4 +282,17 @@ impl MaskObject {
     pub fn is_valid(&self) -> bool {
         self.vect.is_valid() && self.unit.is_valid()
     }
+    /// Splits this mask object into mask objects of consecutive ranges of the vector part with the
+    /// given `lengths`. Each of them contains a copy of the unit part.
+    ///
+    /// Elements of the vector part beyond the sum of the `lengths` are dropped.
+    pub fn split(self, lengths: &[usize]) -> Vec<Self> {
+        let Self { vect, unit } = self;
+        let config = vect.config;
+        vect.data
+            .split(lengths)
+            .into_iter()
+            .map(|data| Self::new_unchecked(MaskVect { data, config }, unit.clone()))
+            .collect()
+    }
 }
@@ -149,7 +149,15 @@ impl FromBytes for MaskObject {
 pub(crate) mod tests {
     use super::*;
     use crate::mask::{
-        config::{BoundType, DataType, GroupType, MaskConfig, ModelType},
+        config::{
+            BoundType,
+            DataType,
+            GroupType,
+            MaskConfig,
+            ModelType,
+            QuantizationType,
+            SparsityType,
+        },
         object::serialization::{unit::tests::mask_unit, vect::tests::mask_vect},
         MaskObject,
     };
@@ -162,8 +170,10 @@ pub(crate) mod tests {
             data_type: DataType::I32,
             bound_type: BoundType::B0,
             model_type: ModelType::M3,
+            sparsity_type: SparsityType::S0,
+            quantization_type: QuantizationType::Qmax,
         };
-        let bytes = vec![0x00, 0x02, 0x00, 0x03];
+        let bytes = vec![0x00, 0x02, 0x00, 0x03, 0x00, 0xff];
         (config, bytes)
     }
 
@@ -179,7 +189,7 @@ pub(crate) mod tests {
     #[test]
     fn serialize_mask_object() {
         let (mask_object, expected) = mask_object();
-        let mut buf = vec![0xff; 42];
+        let mut buf = vec![0xff; 46];
         mask_object.to_bytes(&mut buf);
         assert_eq!(buf, expected);
     }
@@ -198,4 +208,28 @@ pub(crate) mod tests {
             expected
         );
     }
+
+    #[test]
+    fn roundtrip_custom_mask_object() {
+        // config.order() = 6_000_000_000_000_001 with this config, so the data
+        // should be stored on 7 bytes after the 8 bytes of the config.
+        let config = MaskConfig {
+            bound_type: BoundType::Custom(300),
+            ..mask_config().0
+        };
+        let mask_object = MaskObject::new_unchecked(
+            MaskVect::new_unchecked(config, vec![1_u8.into(), 2_u8.into(), 3_u8.into()]),
+            MaskUnit::new_unchecked(config, 1_u8.into()),
+        );
+        assert_eq!(mask_object.buffer_length(), 48);
+
+        let mut buf = vec![0xff; 48];
+        mask_object.to_bytes(&mut buf);
+        assert_eq!(&buf[..12], &[0, 2, 254, 3, 0, 255, 0xac, 0x02, 0, 0, 0, 3]);
+        assert_eq!(MaskObject::from_byte_slice(&buf).unwrap(), mask_object);
+        assert_eq!(
+            MaskObject::from_byte_stream(&mut buf.into_iter()).unwrap(),
+            mask_object
+        );
+    }
 }
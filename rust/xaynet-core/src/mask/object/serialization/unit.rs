@@ -4,25 +4,23 @@
 //!
 //! [mask module]: ../index.html
 
-use std::ops::Range;
-
 use anyhow::{anyhow, Context};
 use num::bigint::BigUint;
 
 use crate::{
     mask::{
-        config::{serialization::MASK_CONFIG_BUFFER_LEN, MaskConfig},
+        config::{
+            serialization::{MaskConfigBuffer, MASK_CONFIG_BUFFER_LEN},
+            MaskConfig,
+        },
         object::MaskUnit,
     },
     message::{
         traits::{FromBytes, ToBytes},
-        utils::range,
         DecodeError,
     },
 };
 
-const MASK_CONFIG_FIELD: Range<usize> = range(0, MASK_CONFIG_BUFFER_LEN);
-
 /// A buffer for serialized mask units.
 pub struct MaskUnitBuffer<T> {
     inner: T,
@@ -52,11 +50,11 @@ impl<T: AsRef<[u8]>> MaskUnitBuffer<T> {
     /// Fails if the buffer is too small.
     pub fn check_buffer_length(&self) -> Result<(), DecodeError> {
         let len = self.inner.as_ref().len();
-        if len < MASK_CONFIG_FIELD.end {
+        if len < MASK_CONFIG_BUFFER_LEN {
             return Err(anyhow!(
                 "invalid buffer length: {} < {}",
                 len,
-                MASK_CONFIG_FIELD.end
+                MASK_CONFIG_BUFFER_LEN
             ));
         }
 
@@ -77,10 +75,10 @@ impl<T: AsRef<[u8]>> MaskUnitBuffer<T> {
     ///
     /// [`len()`]: MaskUnitBuffer::len
     pub fn try_len(&self) -> Result<usize, DecodeError> {
-        let config =
-            MaskConfig::from_byte_slice(&self.config()).context("invalid mask unit buffer")?;
+        let config = MaskConfig::from_byte_slice(&self.inner.as_ref())
+            .context("invalid mask unit buffer")?;
         let data_length = config.bytes_per_number();
-        Ok(MASK_CONFIG_FIELD.end + data_length)
+        Ok(config.buffer_length() + data_length)
     }
 
     /// Gets the expected number of bytes of this buffer wrt to the masking configuration.
@@ -90,7 +88,15 @@ impl<T: AsRef<[u8]>> MaskUnitBuffer<T> {
     pub fn len(&self) -> usize {
         let config = MaskConfig::from_byte_slice(&self.config()).unwrap();
         let data_length = config.bytes_per_number();
-        MASK_CONFIG_FIELD.end + data_length
+        self.config_len() + data_length
+    }
+
+    /// Gets the length of the serialized masking configuration.
+    ///
+    /// # Panics
+    /// May panic if this buffer is unchecked.
+    fn config_len(&self) -> usize {
+        MaskConfigBuffer::new_unchecked(self.inner.as_ref()).len()
     }
 
     /// Gets the serialized masking configuration.
@@ -98,7 +104,7 @@ impl<T: AsRef<[u8]>> MaskUnitBuffer<T> {
     /// # Panics
     /// May panic if this buffer is unchecked.
     pub fn config(&self) -> &[u8] {
-        &self.inner.as_ref()[MASK_CONFIG_FIELD]
+        &self.inner.as_ref()[..self.config_len()]
     }
 
     /// Gets the serialized mask unit element.
@@ -106,17 +112,20 @@ impl<T: AsRef<[u8]>> MaskUnitBuffer<T> {
     /// # Panics
     /// May panic if this buffer is unchecked.
     pub fn data(&self) -> &[u8] {
-        &self.inner.as_ref()[MASK_CONFIG_FIELD.end..self.len()]
+        &self.inner.as_ref()[self.config_len()..self.len()]
     }
 }
 
 impl<T: AsRef<[u8]> + AsMut<[u8]>> MaskUnitBuffer<T> {
     /// Gets the serialized masking configuration.
     ///
+    /// The masking configuration has a variable length, hence the returned buffer extends to the
+    /// end and the configuration must be set before the other fields.
+    ///
     /// # Panics
     /// May panic if this buffer is unchecked.
     pub fn config_mut(&mut self) -> &mut [u8] {
-        &mut self.inner.as_mut()[MASK_CONFIG_FIELD]
+        self.inner.as_mut()
     }
 
     /// Gets the serialized mask unit element.
@@ -125,13 +134,14 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> MaskUnitBuffer<T> {
     /// May panic if this buffer is unchecked.
     pub fn data_mut(&mut self) -> &mut [u8] {
         let end = self.len();
-        &mut self.inner.as_mut()[MASK_CONFIG_FIELD.end..end]
+        let start = self.config_len();
+        &mut self.inner.as_mut()[start..end]
     }
 }
 
 impl ToBytes for MaskUnit {
     fn buffer_length(&self) -> usize {
-        MASK_CONFIG_FIELD.end + self.config.bytes_per_number()
+        self.config.buffer_length() + self.config.bytes_per_number()
     }
 
     fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
@@ -11,18 +11,22 @@ use num::bigint::BigUint;
 
 use crate::{
     mask::{
-        config::{serialization::MASK_CONFIG_BUFFER_LEN, MaskConfig},
-        object::MaskVect,
+        config::{
+            serialization::{MaskConfigBuffer, MASK_CONFIG_BUFFER_LEN},
+            MaskConfig,
+        },
+        fixed::{FixedGroup, FixedInt, FixedWidth},
+        object::{MaskVect, MaskVectData},
     },
     message::{
         traits::{FromBytes, ToBytes},
-        utils::{range, ChunkableIterator},
+        utils::range,
         DecodeError,
     },
 };
 
-const MASK_CONFIG_FIELD: Range<usize> = range(0, MASK_CONFIG_BUFFER_LEN);
-const NUMBERS_FIELD: Range<usize> = range(MASK_CONFIG_FIELD.end, 4);
+// the length of the numbers field, which follows the masking configuration field
+const NUMBERS_LEN: usize = 4;
 
 // target dependent maximum number of mask object elements
 #[cfg(target_pointer_width = "16")]
@@ -58,14 +62,22 @@ impl<T: AsRef<[u8]>> MaskVectBuffer<T> {
     /// Fails if the buffer is too small.
     pub fn check_buffer_length(&self) -> Result<(), DecodeError> {
         let len = self.inner.as_ref().len();
-        if len < NUMBERS_FIELD.end {
+        if len < MASK_CONFIG_BUFFER_LEN {
             return Err(anyhow!(
                 "invalid buffer length: {} < {}",
                 len,
-                NUMBERS_FIELD.end
+                MASK_CONFIG_BUFFER_LEN
             ));
         }
 
+        let numbers_end = MaskConfigBuffer::new_unchecked(self.inner.as_ref())
+            .try_len()
+            .context("invalid mask vector buffer")?
+            + NUMBERS_LEN;
+        if len < numbers_end {
+            return Err(anyhow!("invalid buffer length: {} < {}", len, numbers_end));
+        }
+
         let total_expected_length = self.try_len()?;
         if len < total_expected_length {
             return Err(anyhow!(
@@ -92,7 +104,7 @@ impl<T: AsRef<[u8]>> MaskVectBuffer<T> {
                 "invalid MaskObject buffer: invalid masking config or numbers field"
             ));
         }
-        Ok(NUMBERS_FIELD.end + data_length)
+        Ok(self.numbers_field().end + data_length)
     }
 
     /// Gets the expected number of bytes of this buffer wrt to the masking configuration.
@@ -103,7 +115,17 @@ impl<T: AsRef<[u8]>> MaskVectBuffer<T> {
         let config = MaskConfig::from_byte_slice(&self.config()).unwrap();
         let bytes_per_number = config.bytes_per_number();
         let data_length = self.numbers() * bytes_per_number;
-        NUMBERS_FIELD.end + data_length
+        self.numbers_field().end + data_length
+    }
+
+    /// Gets the range of the numbers field, which depends on the length of the serialized masking
+    /// configuration.
+    ///
+    /// # Panics
+    /// May panic if this buffer is unchecked.
+    fn numbers_field(&self) -> Range<usize> {
+        let config_len = MaskConfigBuffer::new_unchecked(self.inner.as_ref()).len();
+        range(config_len, NUMBERS_LEN)
     }
 
     /// Gets the number of serialized mask object elements.
@@ -114,7 +136,11 @@ impl<T: AsRef<[u8]>> MaskVectBuffer<T> {
     /// Panics if the number can't be represented as usize on targets smaller than 32 bits.
     pub fn numbers(&self) -> usize {
         // UNWRAP SAFE: the slice is exactly 4 bytes long
-        let nb = u32::from_be_bytes(self.inner.as_ref()[NUMBERS_FIELD].try_into().unwrap());
+        let nb = u32::from_be_bytes(
+            self.inner.as_ref()[self.numbers_field()]
+                .try_into()
+                .unwrap(),
+        );
 
         // smaller targets than 32 bits are currently not of interest
         #[cfg(target_pointer_width = "16")]
@@ -130,7 +156,7 @@ impl<T: AsRef<[u8]>> MaskVectBuffer<T> {
     /// # Panics
     /// May panic if this buffer is unchecked.
     pub fn config(&self) -> &[u8] {
-        &self.inner.as_ref()[MASK_CONFIG_FIELD]
+        &self.inner.as_ref()[..self.numbers_field().start]
     }
 
     /// Gets the serialized mask vector elements.
@@ -138,7 +164,7 @@ impl<T: AsRef<[u8]>> MaskVectBuffer<T> {
     /// # Panics
     /// May panic if this buffer is unchecked.
     pub fn data(&self) -> &[u8] {
-        &self.inner.as_ref()[NUMBERS_FIELD.end..self.len()]
+        &self.inner.as_ref()[self.numbers_field().end..self.len()]
     }
 }
 
@@ -148,15 +174,19 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> MaskVectBuffer<T> {
     /// # Panics
     /// May panic if this buffer is unchecked.
     pub fn set_numbers(&mut self, value: u32) {
-        self.inner.as_mut()[NUMBERS_FIELD].copy_from_slice(&value.to_be_bytes());
+        let numbers_field = self.numbers_field();
+        self.inner.as_mut()[numbers_field].copy_from_slice(&value.to_be_bytes());
     }
 
     /// Gets the serialized masking configuration.
     ///
+    /// The masking configuration has a variable length, hence the returned buffer extends to the
+    /// end and the configuration must be set before the other fields.
+    ///
     /// # Panics
     /// May panic if this buffer is unchecked.
     pub fn config_mut(&mut self) -> &mut [u8] {
-        &mut self.inner.as_mut()[MASK_CONFIG_FIELD]
+        self.inner.as_mut()
     }
 
     /// Gets the serialized mask vector elements.
@@ -165,13 +195,14 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> MaskVectBuffer<T> {
     /// May panic if this buffer is unchecked.
     pub fn data_mut(&mut self) -> &mut [u8] {
         let end = self.len();
-        &mut self.inner.as_mut()[NUMBERS_FIELD.end..end]
+        let start = self.numbers_field().end;
+        &mut self.inner.as_mut()[start..end]
     }
 }
 
 impl ToBytes for MaskVect {
     fn buffer_length(&self) -> usize {
-        NUMBERS_FIELD.end + self.config.bytes_per_number() * self.data.len()
+        self.config.buffer_length() + NUMBERS_LEN + self.config.bytes_per_number() * self.data.len()
     }
 
     fn to_bytes<T: AsMut<[u8]>>(&self, buffer: &mut T) {
@@ -182,33 +213,76 @@ impl ToBytes for MaskVect {
         let mut data = writer.data_mut();
         let bytes_per_number = self.config.bytes_per_number();
 
-        for int in self.data.iter() {
-            // FIXME: this allocates a vec which is sub-optimal. See
-            // https://github.com/rust-num/num-bigint/issues/152
-            let bytes = int.to_bytes_le();
-            // This may panic if the data is invalid and contains
-            // integers that are bigger than what is expected by the
-            // configuration.
-            data[..bytes.len()].copy_from_slice(&bytes[..]);
-            // padding
-            for b in data.iter_mut().take(bytes_per_number).skip(bytes.len()) {
-                *b = 0;
+        match self.data {
+            MaskVectData::BigUint(ref ints) => {
+                for int in ints.iter() {
+                    // FIXME: this allocates a vec which is sub-optimal. See
+                    // https://github.com/rust-num/num-bigint/issues/152
+                    let bytes = int.to_bytes_le();
+                    // This may panic if the data is invalid and contains
+                    // integers that are bigger than what is expected by the
+                    // configuration.
+                    data[..bytes.len()].copy_from_slice(&bytes[..]);
+                    // padding
+                    for b in data.iter_mut().take(bytes_per_number).skip(bytes.len()) {
+                        *b = 0;
+                    }
+                    data = &mut data[bytes_per_number..];
+                }
             }
-            data = &mut data[bytes_per_number..];
+            MaskVectData::U64(ref ints) => write_fixed(ints, data, bytes_per_number),
+            MaskVectData::U128(ref ints) => write_fixed(ints, data, bytes_per_number),
         }
     }
 }
 
+/// Writes the fixed-width elements into `bytes_per_number` little endian bytes each.
+fn write_fixed<T: FixedInt>(ints: &[T], data: &mut [u8], bytes_per_number: usize) {
+    for (int, bytes) in ints.iter().zip(data.chunks_mut(bytes_per_number)) {
+        int.to_le_slice(bytes);
+    }
+}
+
+/// Reads the serialized elements in the representation of the masking configuration.
+///
+/// The elements are read as big integers if any of them isn't an element of the group, in which
+/// case the mask vector doesn't conform to the masking configuration.
+fn read_data(config: &MaskConfig, data: &[u8]) -> MaskVectData {
+    let bytes_per_number = config.bytes_per_number();
+    let fixed = match FixedWidth::of(config) {
+        Some(FixedWidth::U64) => read_fixed(config, data, bytes_per_number).map(MaskVectData::U64),
+        Some(FixedWidth::U128) => {
+            read_fixed(config, data, bytes_per_number).map(MaskVectData::U128)
+        }
+        None => None,
+    };
+    fixed.unwrap_or_else(|| {
+        MaskVectData::BigUint(
+            data.chunks(bytes_per_number)
+                .map(BigUint::from_bytes_le)
+                .collect(),
+        )
+    })
+}
+
+/// Reads the serialized elements as fixed-width integers if they are elements of the group.
+fn read_fixed<T: FixedInt>(
+    config: &MaskConfig,
+    data: &[u8],
+    bytes_per_number: usize,
+) -> Option<Vec<T>> {
+    let group = FixedGroup::new(config);
+    data.chunks(bytes_per_number)
+        .map(|bytes| group.decode(bytes))
+        .collect()
+}
+
 impl FromBytes for MaskVect {
     fn from_byte_slice<T: AsRef<[u8]>>(buffer: &T) -> Result<Self, DecodeError> {
         let reader = MaskVectBuffer::new(buffer.as_ref())?;
 
         let config = MaskConfig::from_byte_slice(&reader.config())?;
-        let mut data = Vec::with_capacity(reader.numbers());
-        let bytes_per_number = config.bytes_per_number();
-        for chunk in reader.data().chunks(bytes_per_number) {
-            data.push(BigUint::from_bytes_le(chunk));
-        }
+        let data = read_data(&config, reader.data());
 
         Ok(MaskVect { data, config })
     }
@@ -217,7 +291,7 @@ impl FromBytes for MaskVect {
         iter: &mut I,
     ) -> Result<Self, DecodeError> {
         let config = MaskConfig::from_byte_stream(iter)?;
-        if iter.len() < 4 {
+        if iter.len() < NUMBERS_LEN {
             return Err(anyhow!("byte stream exhausted"));
         }
         let numbers = u32::from_byte_stream(iter)
@@ -233,14 +307,8 @@ impl FromBytes for MaskVect {
             ));
         }
 
-        let mut data = Vec::with_capacity(numbers as usize);
-        let mut buf = vec![0; bytes_per_number];
-        for chunk in iter.take(data_len).chunks(bytes_per_number).into_iter() {
-            for (i, b) in chunk.enumerate() {
-                buf[i] = b;
-            }
-            data.push(BigUint::from_bytes_le(buf.as_slice()));
-        }
+        let bytes = iter.take(data_len).collect::<Vec<_>>();
+        let data = read_data(&config, &bytes);
 
         Ok(MaskVect { data, config })
     }
@@ -250,7 +318,10 @@ impl FromBytes for MaskVect {
 pub(crate) mod tests {
     use super::*;
 
-    use crate::mask::object::serialization::tests::mask_config;
+    use crate::mask::{
+        config::{BoundType, DataType, GroupType, ModelType, QuantizationType, SparsityType},
+        object::serialization::tests::mask_config,
+    };
 
     pub fn mask_vect() -> (MaskVect, Vec<u8>) {
         let (config, mut bytes) = mask_config();
@@ -296,4 +367,60 @@ pub(crate) mod tests {
             expected
         );
     }
+
+    fn power2_config() -> MaskConfig {
+        MaskConfig {
+            group_type: GroupType::Power2,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+            sparsity_type: SparsityType::S0,
+            quantization_type: QuantizationType::Qmax,
+        }
+    }
+
+    #[test]
+    fn serialize_and_deserialize_fixed_mask_vect() {
+        let config = power2_config();
+        let data = vec![
+            BigUint::from(1_u8),
+            BigUint::from(2_u8),
+            config.order() - 1_u8,
+        ];
+        let mask_vect = MaskVect::new(config, data.clone()).unwrap();
+        assert!(matches!(mask_vect.data, MaskVectData::U64(_)));
+
+        // the fixed-width elements are serialized exactly as the big integers
+        let expected = MaskVect {
+            data: MaskVectData::BigUint(data),
+            config,
+        };
+        let mut bytes = vec![0xff; expected.buffer_length()];
+        expected.to_bytes(&mut bytes);
+        let mut buf = vec![0xff; mask_vect.buffer_length()];
+        mask_vect.to_bytes(&mut buf);
+        assert_eq!(buf, bytes);
+
+        assert_eq!(MaskVect::from_byte_slice(&&bytes[..]).unwrap(), mask_vect);
+        assert_eq!(
+            MaskVect::from_byte_stream(&mut bytes.into_iter()).unwrap(),
+            mask_vect
+        );
+    }
+
+    #[test]
+    fn deserialize_invalid_fixed_mask_vect() {
+        let config = power2_config();
+        let invalid = MaskVect {
+            data: MaskVectData::BigUint(vec![BigUint::from(1_u8), config.order()]),
+            config,
+        };
+        let mut bytes = vec![0xff; invalid.buffer_length()];
+        invalid.to_bytes(&mut bytes);
+
+        // elements beyond the group are kept as big integers and fail the validation
+        let mask_vect = MaskVect::from_byte_slice(&&bytes[..]).unwrap();
+        assert_eq!(mask_vect, invalid);
+        assert!(!mask_vect.is_valid());
+    }
 }
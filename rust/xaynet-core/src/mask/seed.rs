@@ -16,7 +16,8 @@ use thiserror::Error;
 use crate::{
     crypto::{encrypt::SEALBYTES, prng::generate_integer, ByteObject},
     mask::{
-        object::{MaskObject, MaskUnit, MaskVect},
+        fixed,
+        object::{MaskObject, MaskUnit, MaskVect, MaskVectData},
         MaskConfigPair,
     },
     SumParticipantEphemeralPublicKey,
@@ -68,11 +69,18 @@ impl MaskSeed {
         let rand_int = generate_integer(&mut prng, &config_1.order());
         let scalar_mask = MaskUnit::new_unchecked(config_1, rand_int);
 
-        let order_n = config_n.order();
-        let rand_ints = iter::repeat_with(|| generate_integer(&mut prng, &order_n))
-            .take(len)
-            .collect();
-        let model_mask = MaskVect::new_unchecked(config_n, rand_ints);
+        let rand_ints = fixed::generate_vect(&config_n, len, &mut prng).unwrap_or_else(|| {
+            let order_n = config_n.order();
+            MaskVectData::BigUint(
+                iter::repeat_with(|| generate_integer(&mut prng, &order_n))
+                    .take(len)
+                    .collect(),
+            )
+        });
+        let model_mask = MaskVect {
+            data: rand_ints,
+            config: config_n,
+        };
 
         MaskObject::new_unchecked(model_mask, scalar_mask)
     }
@@ -80,6 +88,11 @@ impl MaskSeed {
 
 #[derive(AsRef, AsMut, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// An encrypted mask seed.
+///
+/// If the mask seeds are secret-shared, this holds several individually encrypted seeds, see
+/// [`share_mask_seed()`].
+///
+/// [`share_mask_seed()`]: crate::mask::share_mask_seed
 pub struct EncryptedMaskSeed(Vec<u8>);
 
 impl From<Vec<u8>> for EncryptedMaskSeed {
@@ -91,8 +104,10 @@ impl From<Vec<u8>> for EncryptedMaskSeed {
 impl ByteObject for EncryptedMaskSeed {
     const LENGTH: usize = SEALBYTES + MaskSeed::LENGTH;
 
+    /// Creates an encrypted mask seed from the given bytes, which may contain several encrypted
+    /// seeds of [`Self::LENGTH`] each.
     fn from_slice(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() == Self::LENGTH {
+        if !bytes.is_empty() && bytes.len() % Self::LENGTH == 0 {
             Some(Self(bytes.to_vec()))
         } else {
             None
@@ -117,6 +132,16 @@ pub enum InvalidMaskSeed {
 }
 
 impl EncryptedMaskSeed {
+    /// Concatenates the given encrypted seeds.
+    pub fn concat(seeds: impl IntoIterator<Item = EncryptedMaskSeed>) -> Self {
+        Self(seeds.into_iter().flat_map(|seed| seed.0).collect())
+    }
+
+    /// Gets the number of encrypted seeds.
+    pub fn count(&self) -> usize {
+        self.0.len() / Self::LENGTH
+    }
+
     /// Decrypts this seed as a [`MaskSeed`].
     ///
     /// # Errors
@@ -133,6 +158,21 @@ impl EncryptedMaskSeed {
         )
         .ok_or(InvalidMaskSeed::InvalidLength)
     }
+
+    /// Decrypts all the seeds of this encrypted seed.
+    ///
+    /// # Errors
+    /// Fails if the decryption of any seed fails.
+    pub fn decrypt_all(
+        &self,
+        pk: &SumParticipantEphemeralPublicKey,
+        sk: &SumParticipantEphemeralSecretKey,
+    ) -> Result<Vec<MaskSeed>, InvalidMaskSeed> {
+        self.0
+            .chunks(Self::LENGTH)
+            .map(|seed| EncryptedMaskSeed(seed.to_vec()).decrypt(pk, sk))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -140,7 +180,15 @@ mod tests {
     use super::*;
     use crate::{
         crypto::encrypt::EncryptKeyPair,
-        mask::config::{BoundType, DataType, GroupType, MaskConfig, ModelType},
+        mask::config::{
+            BoundType,
+            DataType,
+            GroupType,
+            MaskConfig,
+            ModelType,
+            QuantizationType,
+            SparsityType,
+        },
     };
 
     #[test]
@@ -164,6 +212,8 @@ mod tests {
             data_type: DataType::F32,
             bound_type: BoundType::B0,
             model_type: ModelType::M3,
+            sparsity_type: SparsityType::S0,
+            quantization_type: QuantizationType::Qmax,
         };
         let seed = MaskSeed::generate();
         let mask = seed.derive_mask(10, config.into());
@@ -171,6 +221,7 @@ mod tests {
         assert!(mask
             .vect
             .data
+            .into_biguints()
             .iter()
             .all(|integer| integer < &config.order()));
     }
@@ -186,4 +237,19 @@ mod tests {
         let decr_seed = encr_seed.decrypt(&public, &secret).unwrap();
         assert_eq!(seed, decr_seed);
     }
+
+    #[test]
+    fn test_encryption_of_several_seeds() {
+        let seeds = vec![MaskSeed::generate(), MaskSeed::generate()];
+        let EncryptKeyPair { public, secret } = EncryptKeyPair::generate();
+        let encr_seeds = EncryptedMaskSeed::concat(seeds.iter().map(|seed| seed.encrypt(&public)));
+        assert_eq!(encr_seeds.count(), 2);
+        assert_eq!(
+            EncryptedMaskSeed::from_slice(encr_seeds.as_slice()).unwrap(),
+            encr_seeds
+        );
+        assert!(EncryptedMaskSeed::from_slice(&encr_seeds.as_slice()[1..]).is_none());
+        assert_eq!(encr_seeds.decrypt_all(&public, &secret).unwrap(), seeds);
+        assert!(encr_seeds.decrypt(&public, &secret).is_err());
+    }
 }
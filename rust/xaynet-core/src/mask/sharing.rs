@@ -0,0 +1,425 @@
+//! Threshold secret sharing of mask seeds.
+//!
+//! See the [mask module] documentation since this is a private module anyways.
+//!
+//! [mask module]: ../index.html
+
+use std::{
+    collections::{HashMap, HashSet},
+    iter,
+};
+
+use sodiumoxide::randombytes::randombytes;
+use thiserror::Error;
+
+use crate::{crypto::ByteObject, mask::seed::MaskSeed, SumDict, SumParticipantPublicKey};
+
+/// The maximum number of shares of a mask seed.
+///
+/// Shares are evaluated at the non-zero elements of `GF(2^8)`.
+pub const MAX_SHARES: usize = u8::MAX as usize;
+
+#[derive(Debug, Error, Eq, PartialEq)]
+/// Errors related to the secret sharing of mask seeds.
+pub enum SharingError {
+    #[error("the threshold must be in [1, {0}], but is {1}")]
+    InvalidThreshold(usize, usize),
+    #[error("at most {} shares are supported, but {0} are requested", MAX_SHARES)]
+    TooManyShares(usize),
+    #[error("at least one share is required")]
+    NoShares,
+    #[error("the share index must not be zero")]
+    InvalidShareIndex,
+    #[error("the share index {0} is duplicated")]
+    DuplicatedShareIndex(u8),
+    #[error("not enough shares: {0} < {1}")]
+    NotEnoughShares(usize, usize),
+    #[error("the shares are inconsistent")]
+    InconsistentShares,
+}
+
+/// Computes the number of shares which are required to reconstruct a mask seed.
+///
+/// The threshold is the given `fraction` of the `participants` rounded up and clamped to
+/// `[1, participants]`.
+pub fn sharing_threshold(fraction: f64, participants: usize) -> usize {
+    let threshold = (fraction * participants as f64).ceil();
+    if threshold < 1. {
+        1
+    } else {
+        (threshold as usize).min(participants.max(1))
+    }
+}
+
+/// Assigns a share index to each sum participant.
+///
+/// The share index of a sum participant is its 1-based position in the sum dictionary sorted by
+/// public keys. Hence, every participant who knows the sum dictionary derives the same indices.
+///
+/// # Errors
+/// Fails if the sum dictionary contains more than [`MAX_SHARES`] participants.
+pub fn share_indices(
+    sum_dict: &SumDict,
+) -> Result<HashMap<SumParticipantPublicKey, u8>, SharingError> {
+    if sum_dict.len() > MAX_SHARES {
+        return Err(SharingError::TooManyShares(sum_dict.len()));
+    }
+    let mut pks = sum_dict.keys().copied().collect::<Vec<_>>();
+    pks.sort_unstable();
+    Ok(pks
+        .into_iter()
+        .enumerate()
+        .map(|(index, pk)| (pk, index as u8 + 1))
+        .collect())
+}
+
+impl MaskSeed {
+    /// Splits this seed into `shares` shares such that any `threshold` of them suffice to
+    /// reconstruct the seed, while fewer reveal nothing about it.
+    ///
+    /// The share at position `i` of the returned vector has the share index `i + 1`.
+    ///
+    /// # Errors
+    /// Fails if more than [`MAX_SHARES`] shares are requested or if the threshold is not in
+    /// `[1, shares]`.
+    pub fn split(&self, threshold: usize, shares: usize) -> Result<Vec<MaskSeed>, SharingError> {
+        if shares > MAX_SHARES {
+            return Err(SharingError::TooManyShares(shares));
+        }
+        if threshold == 0 || threshold > shares {
+            return Err(SharingError::InvalidThreshold(shares, threshold));
+        }
+
+        // the secret is the constant term of a random polynomial of degree `threshold - 1` per
+        // byte of the seed
+        let secret = self.as_slice();
+        let coefficients = randombytes((threshold - 1) * Self::LENGTH);
+        let split = (1..=shares as u8)
+            .map(|x| {
+                let share = secret
+                    .iter()
+                    .enumerate()
+                    .map(|(j, s)| {
+                        // Horner's scheme from the highest to the constant coefficient
+                        let acc = coefficients
+                            .chunks_exact(Self::LENGTH)
+                            .rev()
+                            .fold(0, |acc, coefficient| gf_mul(acc, x) ^ coefficient[j]);
+                        gf_mul(acc, x) ^ s
+                    })
+                    .collect::<Vec<u8>>();
+                // safe unwrap: length of slice is guaranteed by constants
+                MaskSeed::from_slice_unchecked(&share)
+            })
+            .collect();
+        Ok(split)
+    }
+
+    /// Reconstructs a seed from the given pairs of share indices and shares.
+    ///
+    /// The result is only the original seed if at least as many shares as the sharing threshold
+    /// are provided.
+    ///
+    /// # Errors
+    /// Fails if no shares are provided or if the share indices are zero or duplicated.
+    pub fn reconstruct(shares: &[(u8, MaskSeed)]) -> Result<Self, SharingError> {
+        check_indices(shares)?;
+        Ok(MaskSeed::from_slice_unchecked(&interpolate(shares, 0)))
+    }
+
+    /// Reconstructs a seed from the given pairs of share indices and shares and checks that all
+    /// the shares are consistent.
+    ///
+    /// The seed is reconstructed from the first `threshold` shares. Every further share must
+    /// belong to the same seed, i.e. any other subset of the shares of the size of the threshold
+    /// reconstructs the same seed.
+    ///
+    /// # Errors
+    /// Fails if fewer shares than the threshold are provided, if the share indices are zero or
+    /// duplicated or if the shares are inconsistent.
+    pub fn reconstruct_checked(
+        shares: &[(u8, MaskSeed)],
+        threshold: usize,
+    ) -> Result<Self, SharingError> {
+        if threshold == 0 || shares.len() < threshold {
+            return Err(SharingError::NotEnoughShares(shares.len(), threshold));
+        }
+        check_indices(shares)?;
+
+        let (basis, checks) = shares.split_at(threshold);
+        for (index, share) in checks {
+            if interpolate(basis, *index) != share.as_array() {
+                return Err(SharingError::InconsistentShares);
+            }
+        }
+        Ok(MaskSeed::from_slice_unchecked(&interpolate(basis, 0)))
+    }
+}
+
+/// Splits the masking of an update participant among `participants` sum participants.
+///
+/// Every sum participant gets a mask seed of its own, where the first one is the given
+/// `mask_seed`. The mask of the update participant is the aggregation of the masks derived from
+/// all these seeds. Additionally, the mask seed of every sum participant is split into shares
+/// among the other sum participants, such that the `threshold` of them suffice to reconstruct it
+/// if the sum participant drops out.
+///
+/// The seeds at position `i` of the returned vector are for the sum participant with the share
+/// index `i + 1`. Its seed at position `j` is its own mask seed if `j == i` and its share of the
+/// mask seed of the sum participant with the share index `j + 1` otherwise.
+///
+/// # Errors
+/// Fails if more than [`MAX_SHARES`] participants are requested or if the threshold is not in
+/// `[1, participants]`.
+pub fn share_mask_seed(
+    mask_seed: MaskSeed,
+    threshold: usize,
+    participants: usize,
+) -> Result<Vec<Vec<MaskSeed>>, SharingError> {
+    let seeds = iter::once(mask_seed)
+        .chain(iter::repeat_with(MaskSeed::generate))
+        .take(participants)
+        .collect::<Vec<_>>();
+    let shares = seeds
+        .iter()
+        .map(|seed| seed.split(threshold, participants))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((0..participants)
+        .map(|i| {
+            (0..participants)
+                .map(|j| {
+                    if i == j {
+                        seeds[j].clone()
+                    } else {
+                        shares[j][i].clone()
+                    }
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Checks that the share indices are non-zero and unique.
+fn check_indices(shares: &[(u8, MaskSeed)]) -> Result<(), SharingError> {
+    if shares.is_empty() {
+        return Err(SharingError::NoShares);
+    }
+    let mut indices = HashSet::with_capacity(shares.len());
+    for (index, _) in shares {
+        if *index == 0 {
+            return Err(SharingError::InvalidShareIndex);
+        }
+        if !indices.insert(*index) {
+            return Err(SharingError::DuplicatedShareIndex(*index));
+        }
+    }
+    Ok(())
+}
+
+/// Evaluates the polynomials through the given shares at the point `x` via Lagrange
+/// interpolation, where subtraction in `GF(2^8)` is xor.
+fn interpolate(shares: &[(u8, MaskSeed)], x: u8) -> [u8; MaskSeed::LENGTH] {
+    let mut value = [0_u8; MaskSeed::LENGTH];
+    for (i, (x_i, share)) in shares.iter().enumerate() {
+        let basis = shares
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(1, |acc, (_, (x_j, _))| {
+                gf_mul(acc, gf_mul(x ^ x_j, gf_inv(x_j ^ x_i)))
+            });
+        for (v, y) in value.iter_mut().zip(share.as_slice()) {
+            *v ^= gf_mul(basis, *y);
+        }
+    }
+    value
+}
+
+/// Multiplies two elements of `GF(2^8)` modulo the AES polynomial `x^8 + x^4 + x^3 + x + 1`.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Inverts a non-zero element of `GF(2^8)` via `a^254`.
+fn gf_inv(a: u8) -> u8 {
+    let mut inverse = 1;
+    let mut power = a;
+    let mut exponent = 254_u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            inverse = gf_mul(inverse, power);
+        }
+        power = gf_mul(power, power);
+        exponent >>= 1;
+    }
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{EncryptKeyPair, SigningKeyPair};
+
+    #[test]
+    fn test_gf_arithmetic() {
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        for a in 1..=u8::MAX {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn test_sharing_threshold() {
+        assert_eq!(sharing_threshold(0.5, 10), 5);
+        assert_eq!(sharing_threshold(0.51, 10), 6);
+        assert_eq!(sharing_threshold(1., 10), 10);
+        assert_eq!(sharing_threshold(0.01, 10), 1);
+        assert_eq!(sharing_threshold(0.5, 0), 1);
+    }
+
+    #[test]
+    fn test_share_indices() {
+        sodiumoxide::init().unwrap();
+        let sum_dict = (0..10)
+            .map(|_| {
+                (
+                    SigningKeyPair::generate().public,
+                    EncryptKeyPair::generate().public,
+                )
+            })
+            .collect::<SumDict>();
+        let indices = share_indices(&sum_dict).unwrap();
+        assert_eq!(indices.len(), 10);
+        let mut values = indices.values().copied().collect::<Vec<u8>>();
+        values.sort_unstable();
+        assert_eq!(values, (1..=10).collect::<Vec<u8>>());
+        let min_pk = sum_dict.keys().min().unwrap();
+        assert_eq!(indices[min_pk], 1);
+    }
+
+    #[test]
+    fn test_split_and_reconstruct() {
+        sodiumoxide::init().unwrap();
+        let seed = MaskSeed::generate();
+        let shares = seed.split(3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        let indexed = shares
+            .into_iter()
+            .enumerate()
+            .map(|(i, share)| (i as u8 + 1, share))
+            .collect::<Vec<_>>();
+        // any 3 shares reconstruct the seed
+        assert_eq!(MaskSeed::reconstruct(&indexed[..3]).unwrap(), seed);
+        assert_eq!(MaskSeed::reconstruct(&indexed[2..]).unwrap(), seed);
+        let subset = vec![indexed[0].clone(), indexed[2].clone(), indexed[4].clone()];
+        assert_eq!(MaskSeed::reconstruct(&subset).unwrap(), seed);
+        assert_eq!(MaskSeed::reconstruct(&indexed).unwrap(), seed);
+        // fewer shares don't
+        assert_ne!(MaskSeed::reconstruct(&indexed[..2]).unwrap(), seed);
+    }
+
+    #[test]
+    fn test_reconstruct_checked() {
+        sodiumoxide::init().unwrap();
+        let seed = MaskSeed::generate();
+        let mut indexed = seed
+            .split(3, 5)
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(i, share)| (i as u8 + 1, share))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            MaskSeed::reconstruct_checked(&indexed[..3], 3).unwrap(),
+            seed
+        );
+        assert_eq!(MaskSeed::reconstruct_checked(&indexed, 3).unwrap(), seed);
+        assert_eq!(
+            MaskSeed::reconstruct_checked(&indexed[..2], 3).unwrap_err(),
+            SharingError::NotEnoughShares(2, 3)
+        );
+
+        // a corrupted share is detected by any other subset of shares
+        indexed[4].1 = MaskSeed::generate();
+        assert_eq!(
+            MaskSeed::reconstruct_checked(&indexed, 3).unwrap_err(),
+            SharingError::InconsistentShares
+        );
+        indexed.swap(0, 4);
+        assert_eq!(
+            MaskSeed::reconstruct_checked(&indexed, 3).unwrap_err(),
+            SharingError::InconsistentShares
+        );
+    }
+
+    #[test]
+    fn test_share_mask_seed() {
+        sodiumoxide::init().unwrap();
+        let seed = MaskSeed::generate();
+        let seeds = share_mask_seed(seed.clone(), 2, 4).unwrap();
+        assert_eq!(seeds.len(), 4);
+        assert!(seeds.iter().all(|seeds| seeds.len() == 4));
+        assert_eq!(seeds[0][0], seed);
+
+        // the mask seed of every sum participant is reconstructed from the shares of the others
+        for (i, own) in seeds.iter().enumerate() {
+            let shares = (0..4)
+                .filter(|j| *j != i)
+                .map(|j| (j as u8 + 1, seeds[j][i].clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(MaskSeed::reconstruct_checked(&shares, 2).unwrap(), own[i]);
+        }
+        assert_ne!(seeds[1][1], seeds[2][2]);
+    }
+
+    #[test]
+    fn test_split_errors() {
+        sodiumoxide::init().unwrap();
+        let seed = MaskSeed::generate();
+        assert_eq!(
+            seed.split(0, 5).unwrap_err(),
+            SharingError::InvalidThreshold(5, 0)
+        );
+        assert_eq!(
+            seed.split(6, 5).unwrap_err(),
+            SharingError::InvalidThreshold(5, 6)
+        );
+        assert_eq!(
+            seed.split(1, 256).unwrap_err(),
+            SharingError::TooManyShares(256)
+        );
+    }
+
+    #[test]
+    fn test_reconstruct_errors() {
+        sodiumoxide::init().unwrap();
+        let seed = MaskSeed::generate();
+        assert_eq!(
+            MaskSeed::reconstruct(&[]).unwrap_err(),
+            SharingError::NoShares
+        );
+        assert_eq!(
+            MaskSeed::reconstruct(&[(0, seed.clone())]).unwrap_err(),
+            SharingError::InvalidShareIndex
+        );
+        assert_eq!(
+            MaskSeed::reconstruct(&[(1, seed.clone()), (1, seed)]).unwrap_err(),
+            SharingError::DuplicatedShareIndex(1)
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Selection of the masked weights of sparse models.
+//!
+//! See the [mask module] documentation since this is a private module anyways.
+//!
+//! [mask module]: ../index.html
+
+use rand::{seq::index, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use crate::{
+    common::RoundSeed,
+    crypto::{ByteObject, Sha256},
+    mask::{config::SparsityType, model::Model},
+};
+
+/// The domain separator for deriving the selection of the masked weights from a round seed.
+const SELECTION_DOMAIN: &[u8] = b"xaynet mask selection";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The indices of the weights of a sparse model which are masked in a round.
+///
+/// The indices are a random subset of the model weights, which is derived from the round seed.
+/// Hence, all participants of a round and the coordinator select the same weights and the masked
+/// weights can be aggregated as usual. Selecting the weights per participant instead, e.g. the
+/// top-k weights of each local model, would be incompatible with the aggregation of masked models.
+pub struct MaskSelection(Vec<usize>);
+
+#[allow(clippy::len_without_is_empty)]
+impl MaskSelection {
+    /// Selects the masked weights of a model with `model_length` weights for the round with the
+    /// given round `seed` wrt the `sparsity`.
+    ///
+    /// Returns `None` if all weights are masked.
+    pub fn new(seed: &RoundSeed, model_length: usize, sparsity: SparsityType) -> Option<Self> {
+        if sparsity == SparsityType::S0 {
+            return None;
+        }
+
+        let digest = Sha256::hash(&[seed.as_slice(), SELECTION_DOMAIN].concat());
+        let mut prng_seed = [0_u8; 32];
+        prng_seed.copy_from_slice(digest.as_slice());
+        let mut prng = ChaCha20Rng::from_seed(prng_seed);
+        let amount = sparsity.nb_selected(model_length);
+        let mut indices = index::sample(&mut prng, model_length, amount).into_vec();
+        indices.sort_unstable();
+        Some(Self(indices))
+    }
+
+    /// Gets the number of selected weights.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Gets the indices of the selected weights in ascending order.
+    pub fn indices(&self) -> &[usize] {
+        self.0.as_slice()
+    }
+
+    /// Gets the selected weights of the `model`.
+    ///
+    /// Selected indices beyond the length of the `model` are skipped.
+    pub fn select(&self, model: &Model) -> Model {
+        let mut indices = self.0.iter().peekable();
+        model
+            .iter()
+            .enumerate()
+            .filter_map(|(i, weight)| {
+                if indices.peek() == Some(&&i) {
+                    indices.next();
+                    Some(weight.clone())
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Scatters the selected `weights` into the `model`, i.e. replaces the weights of the `model`
+    /// at the selected indices in ascending order.
+    ///
+    /// Selected indices beyond the length of the `model` and surplus `weights` are skipped.
+    pub fn scatter(&self, weights: Model, mut model: Model) -> Model {
+        let mut indices = self.0.iter().peekable();
+        let mut weights = weights.into_iter();
+        for (i, weight) in model.iter_mut().enumerate() {
+            if indices.peek() == Some(&&i) {
+                indices.next();
+                match weights.next() {
+                    Some(selected) => *weight = selected,
+                    None => break,
+                }
+            }
+        }
+        model
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::{bigint::BigInt, rational::Ratio};
+
+    use super::*;
+
+    fn model(weights: &[i32]) -> Model {
+        weights
+            .iter()
+            .map(|weight| Ratio::from_integer(BigInt::from(*weight)))
+            .collect()
+    }
+
+    #[test]
+    fn test_dense_selection() {
+        let seed = RoundSeed::generate();
+        assert!(MaskSelection::new(&seed, 100, SparsityType::S0).is_none());
+    }
+
+    #[test]
+    fn test_selection_is_derived_from_round_seed() {
+        let seed = RoundSeed::generate();
+        let selection = MaskSelection::new(&seed, 1_000, SparsityType::S1).unwrap();
+        assert_eq!(selection.len(), 100);
+        assert!(selection.indices().windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(selection.indices().iter().all(|i| *i < 1_000));
+        assert_eq!(
+            MaskSelection::new(&seed, 1_000, SparsityType::S1).unwrap(),
+            selection,
+        );
+
+        let other_seed = RoundSeed::generate();
+        assert_ne!(
+            MaskSelection::new(&other_seed, 1_000, SparsityType::S1).unwrap(),
+            selection,
+        );
+    }
+
+    #[test]
+    fn test_select_and_scatter() {
+        let selection = MaskSelection(vec![1, 3, 4]);
+        let weights = selection.select(&model(&[0, 1, 2, 3, 4, 5]));
+        assert_eq!(weights, model(&[1, 3, 4]));
+
+        let scattered = selection.scatter(model(&[7, 8, 9]), model(&[0; 6]));
+        assert_eq!(scattered, model(&[0, 7, 0, 8, 9, 0]));
+    }
+
+    #[test]
+    fn test_select_and_scatter_short_model() {
+        let selection = MaskSelection(vec![1, 3, 4]);
+        assert_eq!(selection.select(&model(&[0, 1, 2, 3])), model(&[1, 3]));
+        assert_eq!(
+            selection.scatter(model(&[7, 8, 9]), model(&[0; 4])),
+            model(&[0, 7, 0, 8]),
+        );
+        assert_eq!(
+            selection.scatter(model(&[7]), model(&[0; 6])),
+            model(&[0, 7, 0, 0, 0, 0]),
+        );
+    }
+}
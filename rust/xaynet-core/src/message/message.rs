@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     crypto::{ByteObject, PublicEncryptKey, PublicSigningKey, SecretSigningKey, Signature},
-    message::{Chunk, DecodeError, FromBytes, Payload, Sum, Sum2, ToBytes, Update},
+    message::{Chunk, DecodeError, FromBytes, Payload, Sum, Sum2, Sum2Shares, ToBytes, Update},
 };
 
 pub(crate) mod ranges {
@@ -136,8 +136,8 @@ pub const HEADER_LENGTH: usize = ranges::RESERVED.end;
 /// - `length` is the length in bytes of the _full_ message, _i.e._
 ///   including the header. This is a 32 bits field so in theory,
 ///   messages can be as big as 2^32 = 4,294,967,296 bytes.
-/// - `tag` indicates the type of message (sum, update, sum2 or
-///   multipart message)
+/// - `tag` indicates the type of message (sum, update, sum2, sum2
+///   shares or multipart message)
 /// - the `flags` field currently supports a single flag, that
 ///   indicates whether this is a multipart message
 ///
@@ -441,6 +441,8 @@ pub enum Tag {
     Update,
     /// A tag for [`Sum2`] messages
     Sum2,
+    /// A tag for [`Sum2Shares`] messages
+    Sum2Shares,
 }
 
 impl TryFrom<u8> for Tag {
@@ -451,6 +453,7 @@ impl TryFrom<u8> for Tag {
             1 => Tag::Sum,
             2 => Tag::Update,
             3 => Tag::Sum2,
+            4 => Tag::Sum2Shares,
             _ => return Err(anyhow!("invalid tag {}", value)),
         })
     }
@@ -462,6 +465,7 @@ impl Into<u8> for Tag {
             Tag::Sum => 1,
             Tag::Update => 2,
             Tag::Sum2 => 3,
+            Tag::Sum2Shares => 4,
         }
     }
 }
@@ -482,7 +486,8 @@ pub struct Message {
     /// The type of message. This information is partially redundant
     /// with the `payload` field. So when serializing the message,
     /// this field is ignored if the payload is a [`Payload::Sum`],
-    /// [`Payload::Update`], or [`Payload::Sum2`]. However, it is
+    /// [`Payload::Update`], [`Payload::Sum2`] or
+    /// [`Payload::Sum2Shares`]. However, it is
     /// taken as is for [`Payload::Chunk`].
     pub tag: Tag,
     /// Message payload
@@ -524,6 +529,23 @@ impl Message {
         }
     }
 
+    /// Create a new sum2 message with seed shares with the given
+    /// participant and coordinator public keys.
+    pub fn new_sum2_shares(
+        participant_pk: PublicSigningKey,
+        coordinator_pk: PublicEncryptKey,
+        message: Sum2Shares,
+    ) -> Self {
+        Self {
+            signature: None,
+            participant_pk,
+            coordinator_pk,
+            is_multipart: false,
+            tag: Tag::Sum2Shares,
+            payload: message.into(),
+        }
+    }
+
     /// Create a new update message with the given participant and
     /// coordinator public keys.
     pub fn new_update(
@@ -581,6 +603,7 @@ impl Message {
                 Tag::Sum => Sum::from_byte_slice(&reader.payload()).map(Into::into),
                 Tag::Update => Update::from_byte_slice(&reader.payload()).map(Into::into),
                 Tag::Sum2 => Sum2::from_byte_slice(&reader.payload()).map(Into::into),
+                Tag::Sum2Shares => Sum2Shares::from_byte_slice(&reader.payload()).map(Into::into),
             }
         }
         .context("failed to parse message payload")?;
@@ -627,6 +650,7 @@ impl Message {
             Payload::Sum(_) => Tag::Sum,
             Payload::Update(_) => Tag::Update,
             Payload::Sum2(_) => Tag::Sum2,
+            Payload::Sum2Shares(_) => Tag::Sum2Shares,
             Payload::Chunk(_) => self.tag,
         };
         writer.set_tag(tag.into());
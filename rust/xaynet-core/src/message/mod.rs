@@ -34,9 +34,9 @@ pub(crate) mod utils;
 pub use self::{
     message::{Flags, Message, MessageBuffer, Tag, HEADER_LENGTH as MESSAGE_HEADER_LENGTH},
     payload::{
-        chunk::{Chunk, ChunkBuffer},
+        chunk::{Chunk, ChunkBuffer, HEADER_LENGTH as CHUNK_HEADER_LENGTH},
         sum::{Sum, SumBuffer},
-        sum2::{Sum2, Sum2Buffer},
+        sum2::{Sum2, Sum2Buffer, Sum2Shares, Sum2SharesBuffer},
         update::{Update, UpdateBuffer},
         Payload,
     },
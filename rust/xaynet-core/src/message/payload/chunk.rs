@@ -22,7 +22,7 @@ pub(crate) mod ranges {
 }
 
 /// Length in bytes of a chunk message header
-const HEADER_LENGTH: usize = ranges::RESERVED.end;
+pub const HEADER_LENGTH: usize = ranges::RESERVED.end;
 
 /// A message chunk.
 #[derive(Eq, PartialEq, Debug, Clone)]
@@ -12,7 +12,12 @@ pub(crate) mod update;
 use derive_more::From;
 
 use crate::message::{
-    payload::{chunk::Chunk, sum::Sum, sum2::Sum2, update::Update},
+    payload::{
+        chunk::Chunk,
+        sum::Sum,
+        sum2::{Sum2, Sum2Shares},
+        update::Update,
+    },
     traits::ToBytes,
 };
 
@@ -27,6 +32,8 @@ pub enum Payload {
     Update(Update),
     /// The payload of a [`Sum2`] message.
     Sum2(Sum2),
+    /// The payload of a [`Sum2Shares`] message.
+    Sum2Shares(Sum2Shares),
     /// The payload of a [`Chunk`] message.
     Chunk(Chunk),
 }
@@ -44,6 +51,10 @@ impl Payload {
         matches!(self, Self::Sum2(_))
     }
 
+    pub fn is_sum2_shares(&self) -> bool {
+        matches!(self, Self::Sum2Shares(_))
+    }
+
     pub fn is_chunk(&self) -> bool {
         matches!(self, Self::Chunk(_))
     }
@@ -54,6 +65,7 @@ impl ToBytes for Payload {
         match self {
             Payload::Sum(m) => m.buffer_length(),
             Payload::Sum2(m) => m.buffer_length(),
+            Payload::Sum2Shares(m) => m.buffer_length(),
             Payload::Update(m) => m.buffer_length(),
             Payload::Chunk(m) => m.buffer_length(),
         }
@@ -63,6 +75,7 @@ impl ToBytes for Payload {
         match self {
             Payload::Sum(m) => m.to_bytes(buffer),
             Payload::Sum2(m) => m.to_bytes(buffer),
+            Payload::Sum2Shares(m) => m.to_bytes(buffer),
             Payload::Update(m) => m.to_bytes(buffer),
             Payload::Chunk(m) => m.to_bytes(buffer),
         }
@@ -12,11 +12,12 @@ use crate::{
     crypto::ByteObject,
     mask::object::{serialization::MaskObjectBuffer, MaskObject},
     message::{
-        traits::{FromBytes, ToBytes},
+        traits::{FromBytes, LengthValueBuffer, ToBytes},
         utils::range,
         DecodeError,
     },
     ParticipantTaskSignature,
+    SeedShareDict,
 };
 
 const SUM_SIGNATURE_RANGE: Range<usize> = range(0, ParticipantTaskSignature::LENGTH);
@@ -160,9 +161,153 @@ impl FromBytes for Sum2 {
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A wrapper around a buffer that contains a [`Sum2Shares`] message.
+///
+/// It provides getters and setters to access the different fields of the message safely.
+pub struct Sum2SharesBuffer<T> {
+    inner: T,
+}
+
+impl<T: AsRef<[u8]>> Sum2SharesBuffer<T> {
+    /// Performs bound checks for the various message fields on `bytes` and returns a new
+    /// [`Sum2SharesBuffer`].
+    ///
+    /// # Errors
+    /// Fails if the `bytes` are smaller than a minimal-sized sum2 shares message buffer.
+    pub fn new(bytes: T) -> Result<Self, DecodeError> {
+        let buffer = Self { inner: bytes };
+        buffer
+            .check_buffer_length()
+            .context("not a valid Sum2SharesBuffer")?;
+        Ok(buffer)
+    }
+
+    /// Returns a `Sum2SharesBuffer` with the given `bytes` without performing bound checks.
+    ///
+    /// This means that accessing the message fields may panic.
+    pub fn new_unchecked(bytes: T) -> Self {
+        Self { inner: bytes }
+    }
+
+    /// Performs bound checks for the various message fields on this buffer.
+    pub fn check_buffer_length(&self) -> Result<(), DecodeError> {
+        let len = self.inner.as_ref().len();
+        if len < SUM_SIGNATURE_RANGE.end {
+            return Err(anyhow!(
+                "invalid buffer length: {} < {}",
+                len,
+                SUM_SIGNATURE_RANGE.end
+            ));
+        }
+
+        // check the length of the seed share dictionary field
+        let _ = LengthValueBuffer::new(&self.inner.as_ref()[self.seed_shares_offset()..])
+            .context("invalid seed share dictionary length")?;
+
+        Ok(())
+    }
+
+    /// Gets the offset of the seed share dictionary field.
+    fn seed_shares_offset(&self) -> usize {
+        SUM_SIGNATURE_RANGE.end
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Sum2SharesBuffer<T> {
+    /// Gets a mutable reference to the sum signature field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn sum_signature_mut(&mut self) -> &mut [u8] {
+        &mut self.inner.as_mut()[SUM_SIGNATURE_RANGE]
+    }
+
+    /// Gets a mutable reference to the seed share dictionary field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn seed_shares_mut(&mut self) -> &mut [u8] {
+        let offset = self.seed_shares_offset();
+        &mut self.inner.as_mut()[offset..]
+    }
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> Sum2SharesBuffer<&'a T> {
+    /// Gets a reference to the sum signature field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn sum_signature(&self) -> &'a [u8] {
+        &self.inner.as_ref()[SUM_SIGNATURE_RANGE]
+    }
+
+    /// Gets a reference to the seed share dictionary field.
+    ///
+    /// # Panics
+    /// Accessing the field may panic if the buffer has not been checked before.
+    pub fn seed_shares(&self) -> &'a [u8] {
+        let offset = self.seed_shares_offset();
+        &self.inner.as_ref()[offset..]
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Debug)]
+/// A high level representation of a sum2 message with secret-shared mask seeds.
+///
+/// These messages are sent by sum participants during the sum2 phase in addition to a [`Sum2`]
+/// message if the mask seeds are secret-shared and some sum participants dropped out. The
+/// participant sends its decrypted shares of the mask seeds of the sum participants who dropped
+/// out, from which the coordinator reconstructs their aggregated masks as long as enough sum
+/// participants respond.
+pub struct Sum2Shares {
+    /// The signature of the round seed and the word "sum".
+    ///
+    /// This is used to determine whether a participant is selected for the sum task.
+    pub sum_signature: ParticipantTaskSignature,
+
+    /// The decrypted shares of the mask seeds of the sum participants who dropped out.
+    pub seed_shares: SeedShareDict,
+}
+
+impl ToBytes for Sum2Shares {
+    fn buffer_length(&self) -> usize {
+        SUM_SIGNATURE_RANGE.end + self.seed_shares.buffer_length()
+    }
+
+    fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
+        let mut writer = Sum2SharesBuffer::new_unchecked(buffer.as_mut());
+        self.sum_signature.to_bytes(&mut writer.sum_signature_mut());
+        self.seed_shares.to_bytes(&mut writer.seed_shares_mut());
+    }
+}
+
+impl FromBytes for Sum2Shares {
+    fn from_byte_slice<T: AsRef<[u8]>>(buffer: &T) -> Result<Self, DecodeError> {
+        let reader = Sum2SharesBuffer::new(buffer.as_ref())?;
+        Ok(Self {
+            sum_signature: ParticipantTaskSignature::from_byte_slice(&reader.sum_signature())
+                .context("invalid sum signature")?,
+            seed_shares: SeedShareDict::from_byte_slice(&reader.seed_shares())
+                .context("invalid seed share dictionary")?,
+        })
+    }
+
+    fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
+        iter: &mut I,
+    ) -> Result<Self, DecodeError> {
+        Ok(Self {
+            sum_signature: ParticipantTaskSignature::from_byte_stream(iter)
+                .context("invalid sum signature")?,
+            seed_shares: SeedShareDict::from_byte_stream(iter)
+                .context("invalid seed share dictionary")?,
+        })
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
-    use crate::testutils::messages::sum2 as helpers;
+    use crate::testutils::messages::{sum2 as helpers, sum2_shares as shares_helpers};
 
     use super::*;
 
@@ -180,8 +325,8 @@ pub mod tests {
 
     #[test]
     fn buffer_write() {
-        // length = 64 (signature) + 42 (mask) = 106
-        let mut bytes = vec![0xff; 106];
+        // length = 64 (signature) + 46 (mask) = 110
+        let mut bytes = vec![0xff; 110];
         {
             let mut buffer = Sum2Buffer::new_unchecked(&mut bytes);
             buffer
@@ -216,4 +361,52 @@ pub mod tests {
         let parsed = Sum2::from_byte_stream(&mut bytes.into_iter()).unwrap();
         assert_eq!(parsed, sum2);
     }
+
+    #[test]
+    fn shares_buffer_read() {
+        let bytes = shares_helpers::payload().1;
+        let buffer = Sum2SharesBuffer::new(&bytes).unwrap();
+        assert_eq!(buffer.sum_signature(), &helpers::sum_task_signature().1[..]);
+        assert_eq!(buffer.seed_shares(), &shares_helpers::seed_shares().1[..]);
+    }
+
+    #[test]
+    fn shares_decode_invalid_seed_shares() {
+        let mut invalid = shares_helpers::seed_shares().1;
+        // This truncates the last entry of the seed share dictionary
+        invalid[3] = 0x43;
+        let mut bytes = helpers::sum_task_signature().1;
+        bytes.extend(invalid);
+
+        let e = Sum2Shares::from_byte_slice(&bytes).unwrap_err();
+        let cause = e.source().unwrap().to_string();
+        assert_eq!(
+            cause,
+            "invalid seed share dictionary: trailing bytes".to_string()
+        );
+    }
+
+    #[test]
+    fn shares_encode() {
+        let (sum2_shares, bytes) = shares_helpers::payload();
+        assert_eq!(sum2_shares.buffer_length(), bytes.len());
+
+        let mut buf = vec![0xff; sum2_shares.buffer_length()];
+        sum2_shares.to_bytes(&mut buf);
+        assert_eq!(buf, bytes);
+    }
+
+    #[test]
+    fn shares_decode() {
+        let (sum2_shares, bytes) = shares_helpers::payload();
+        let parsed = Sum2Shares::from_byte_slice(&bytes).unwrap();
+        assert_eq!(parsed, sum2_shares);
+    }
+
+    #[test]
+    fn shares_stream_parse() {
+        let (sum2_shares, bytes) = shares_helpers::payload();
+        let parsed = Sum2Shares::from_byte_stream(&mut bytes.into_iter()).unwrap();
+        assert_eq!(parsed, sum2_shares);
+    }
 }
@@ -239,7 +239,7 @@ impl FromBytes for Update {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::testutils::messages::update as helpers;
+    use crate::{mask::EncryptedMaskSeed, testutils::messages::update as helpers};
 
     #[test]
     fn buffer_read() {
@@ -277,6 +277,28 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn decode_shared_seed_dict() {
+        let (mut update, _) = helpers::payload();
+        for seeds in update.local_seed_dict.values_mut() {
+            let shares = vec![0x99; 2 * EncryptedMaskSeed::LENGTH];
+            *seeds = EncryptedMaskSeed::concat(vec![seeds.clone(), shares.into()]);
+        }
+        let mut bytes = vec![0; update.buffer_length()];
+        update.to_bytes(&mut bytes);
+        // every encrypted seed is encoded as an entry of its own
+        assert_eq!(
+            bytes.len(),
+            64 * 2 + 46 + 4 + 6 * (32 + EncryptedMaskSeed::LENGTH)
+        );
+
+        assert_eq!(Update::from_byte_slice(&bytes).unwrap(), update);
+        assert_eq!(
+            Update::from_byte_stream(&mut bytes.into_iter()).unwrap(),
+            update
+        );
+    }
+
     #[test]
     fn decode() {
         let (update, bytes) = helpers::payload();
@@ -302,9 +324,9 @@ pub mod tests {
         // sorted.
         //
         // First compute the offset at which the local seed dict value
-        // starts: two signature (64 bytes), the masked model (32
-        // bytes), the length field (4 bytes), the masked scalar (10 bytes)
-        let offset = 64 * 2 + 32 + 4 + 10;
+        // starts: two signature (64 bytes), the masked model (34
+        // bytes), the length field (4 bytes), the masked scalar (12 bytes)
+        let offset = 64 * 2 + 34 + 4 + 12;
         // Sort the end of the buffer
         (&mut buf[offset..]).sort_unstable();
         assert_eq!(buf, bytes);
@@ -5,6 +5,7 @@
 //! [message module]: ../index.html
 
 use std::{
+    collections::{hash_map::Entry, HashMap},
     convert::TryInto,
     io::{Cursor, Write},
     iter::{ExactSizeIterator, Iterator},
@@ -15,10 +16,12 @@ use anyhow::{anyhow, Context};
 
 use crate::{
     crypto::ByteObject,
-    mask::seed::EncryptedMaskSeed,
+    mask::seed::{EncryptedMaskSeed, MaskSeed},
     message::{utils::ChunkableIterator, DecodeError},
     LocalSeedDict,
+    SeedShareDict,
     SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
 };
 
 /// An interface for serializable message types.
@@ -276,9 +279,15 @@ impl<'a, T: AsRef<[u8]> + ?Sized> LengthValueBuffer<&'a T> {
 
 const ENTRY_LENGTH: usize = SumParticipantPublicKey::LENGTH + EncryptedMaskSeed::LENGTH;
 
+// If the mask seeds are secret-shared, a sum participant gets several encrypted seeds, which are
+// encoded as consecutive entries with the same key.
 impl ToBytes for LocalSeedDict {
     fn buffer_length(&self) -> usize {
-        LENGTH_FIELD.end + self.len() * ENTRY_LENGTH
+        LENGTH_FIELD.end
+            + self
+                .values()
+                .map(|value| value.count() * ENTRY_LENGTH)
+                .sum::<usize>()
     }
 
     fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
@@ -286,8 +295,20 @@ impl ToBytes for LocalSeedDict {
         let length = self.buffer_length() as u32;
         let _ = writer.write(&length.to_be_bytes()).unwrap();
         for (key, value) in self {
-            let _ = writer.write(key.as_slice()).unwrap();
-            let _ = writer.write(value.as_ref()).unwrap();
+            for seed in value.as_slice().chunks_exact(EncryptedMaskSeed::LENGTH) {
+                let _ = writer.write(key.as_slice()).unwrap();
+                let _ = writer.write(seed).unwrap();
+            }
+        }
+    }
+}
+
+/// Adds an encrypted seed to the local seed dictionary.
+fn insert_seed(dict: &mut LocalSeedDict, key: SumParticipantPublicKey, value: EncryptedMaskSeed) {
+    match dict.entry(key) {
+        Entry::Occupied(mut entry) => entry.get_mut().as_mut().extend(value.as_slice()),
+        Entry::Vacant(entry) => {
+            entry.insert(value);
         }
     }
 }
@@ -304,9 +325,7 @@ impl FromBytes for LocalSeedDict {
             // by constants.
             let key = SumParticipantPublicKey::from_slice(&chunk[..key_length]).unwrap();
             let value = EncryptedMaskSeed::from_slice(&chunk[key_length..]).unwrap();
-            if dict.insert(key, value).is_some() {
-                return Err(anyhow!("invalid local seed dictionary: duplicated key"));
-            }
+            insert_seed(&mut dict, key, value);
         }
         if !entries.remainder().is_empty() {
             return Err(anyhow!("invalid local seed dictionary: trailing bytes"));
@@ -344,7 +363,104 @@ impl FromBytes for LocalSeedDict {
                     "unknown error while parsing seed dict entry: entry buffer not fully consumed"
                 ));
             }
-            if dict.insert(key, value).is_some() {
+            insert_seed(&mut dict, key, value);
+        }
+        Ok(dict)
+    }
+}
+
+const SHARE_ENTRY_LENGTH: usize =
+    SumParticipantPublicKey::LENGTH + UpdateParticipantPublicKey::LENGTH + MaskSeed::LENGTH;
+
+impl ToBytes for SeedShareDict {
+    fn buffer_length(&self) -> usize {
+        LENGTH_FIELD.end + self.values().map(HashMap::len).sum::<usize>() * SHARE_ENTRY_LENGTH
+    }
+
+    fn to_bytes<T: AsMut<[u8]> + AsRef<[u8]>>(&self, buffer: &mut T) {
+        let mut writer = Cursor::new(buffer.as_mut());
+        let length = self.buffer_length() as u32;
+        let _ = writer.write(&length.to_be_bytes()).unwrap();
+        for (sum_pk, shares) in self {
+            for (update_pk, share) in shares {
+                let _ = writer.write(sum_pk.as_slice()).unwrap();
+                let _ = writer.write(update_pk.as_slice()).unwrap();
+                let _ = writer.write(share.as_slice()).unwrap();
+            }
+        }
+    }
+}
+
+impl FromBytes for SeedShareDict {
+    fn from_byte_slice<T: AsRef<[u8]>>(buffer: &T) -> Result<Self, DecodeError> {
+        let reader = LengthValueBuffer::new(buffer.as_ref())?;
+        let mut dict = SeedShareDict::new();
+
+        let sum_key_length = SumParticipantPublicKey::LENGTH;
+        let update_key_length = UpdateParticipantPublicKey::LENGTH;
+        let mut entries = reader.value().chunks_exact(SHARE_ENTRY_LENGTH);
+        for chunk in &mut entries {
+            // safe unwraps: lengths of slices are guaranteed
+            // by constants.
+            let sum_pk = SumParticipantPublicKey::from_slice(&chunk[..sum_key_length]).unwrap();
+            let update_pk = UpdateParticipantPublicKey::from_slice(
+                &chunk[sum_key_length..sum_key_length + update_key_length],
+            )
+            .unwrap();
+            let share = MaskSeed::from_slice(&chunk[sum_key_length + update_key_length..]).unwrap();
+            if dict
+                .entry(sum_pk)
+                .or_default()
+                .insert(update_pk, share)
+                .is_some()
+            {
+                return Err(anyhow!("invalid seed share dictionary: duplicated key"));
+            }
+        }
+        if !entries.remainder().is_empty() {
+            return Err(anyhow!("invalid seed share dictionary: trailing bytes"));
+        }
+        Ok(dict)
+    }
+
+    fn from_byte_stream<I: Iterator<Item = u8> + ExactSizeIterator>(
+        iter: &mut I,
+    ) -> Result<Self, DecodeError> {
+        let len = u32::from_byte_stream(iter).context("cannot parse length field")? as usize;
+        if len < 4 {
+            return Err(anyhow!("invalid length field"));
+        }
+        if iter.len() < len - 4 {
+            return Err(anyhow!(
+                "expected {} bytes, but only {} left",
+                len - 4,
+                iter.len()
+            ));
+        }
+
+        let mut dict = SeedShareDict::new();
+        let entries = iter.take(len - 4).chunks(SHARE_ENTRY_LENGTH);
+        for mut chunk in entries.into_iter() {
+            let sum_pk = SumParticipantPublicKey::from_byte_stream(&mut chunk)
+                .context("invalid entry: cannot parse sum participant public key")?;
+            let update_pk = UpdateParticipantPublicKey::from_byte_stream(&mut chunk)
+                .context("invalid entry: cannot parse update participant public key")?;
+            let share = MaskSeed::from_byte_stream(&mut chunk)
+                .context("invalid entry: cannot parse mask seed share")?;
+            // This should really not happen, but it's worth checking
+            // because our chunkable iterator panics if the chunks are
+            // not fully consumed.
+            if chunk.len() > 0 {
+                return Err(anyhow!(
+                    "unknown error while parsing seed share dict entry: entry buffer not fully consumed"
+                ));
+            }
+            if dict
+                .entry(sum_pk)
+                .or_default()
+                .insert(update_pk, share)
+                .is_some()
+            {
                 return Err(anyhow!("duplicated key"));
             }
         }
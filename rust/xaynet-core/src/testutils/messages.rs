@@ -7,9 +7,10 @@ use num::BigUint;
 
 use crate::{
     crypto::{ByteObject, PublicEncryptKey, PublicSigningKey, Signature},
-    mask::EncryptedMaskSeed,
-    message::{Message, Payload, Sum, Sum2, Tag, Update},
+    mask::{EncryptedMaskSeed, MaskSeed},
+    message::{Message, Payload, Sum, Sum2, Sum2Shares, Tag, Update},
     LocalSeedDict,
+    SeedShareDict,
 };
 
 // A message adds 136 bytes of overhead:
@@ -52,6 +53,7 @@ where
         Payload::Sum(_) => Tag::Sum,
         Payload::Update(_) => Tag::Update,
         Payload::Sum2(_) => Tag::Sum2,
+        Payload::Sum2Shares(_) => Tag::Sum2Shares,
         _ => panic!("chunks not supported"),
     };
     let message = Message {
@@ -186,6 +188,47 @@ pub mod sum2 {
     }
 }
 
+pub mod sum2_shares {
+    //! This module provides helpers for generating sum2 payloads with seed shares
+    pub use sum::sum_task_signature;
+
+    use super::*;
+
+    /// Return a seed share dictionary with a single entry and its serialized version
+    pub fn seed_shares() -> (SeedShareDict, Vec<u8>) {
+        let mut dict = SeedShareDict::new();
+        let mut bytes = vec![];
+
+        // Length 32 + 32 + 32 + 4 = 100
+        bytes.extend(vec![0x00, 0x00, 0x00, 0x64]);
+
+        bytes.extend(vec![0x55; PublicSigningKey::LENGTH]);
+        bytes.extend(vec![0x77; PublicSigningKey::LENGTH]);
+        bytes.extend(vec![0x99; MaskSeed::LENGTH]);
+        dict.entry(PublicSigningKey::from_slice(vec![0x55; 32].as_slice()).unwrap())
+            .or_default()
+            .insert(
+                PublicSigningKey::from_slice(vec![0x77; 32].as_slice()).unwrap(),
+                MaskSeed::from_slice(vec![0x99; MaskSeed::LENGTH].as_slice()).unwrap(),
+            );
+
+        (dict, bytes)
+    }
+
+    /// Return a sum2 message with seed shares and its serialized version
+    pub fn payload() -> (Sum2Shares, Vec<u8>) {
+        let (sum_signature, sum_signature_bytes) = sum_task_signature();
+        let (seed_shares, seed_shares_bytes) = seed_shares();
+        let bytes = [sum_signature_bytes.as_slice(), seed_shares_bytes.as_slice()].concat();
+
+        let sum2_shares = Sum2Shares {
+            sum_signature,
+            seed_shares,
+        };
+        (sum2_shares, bytes)
+    }
+}
+
 pub mod mask {
     //! This module provides helpers for generating mask objects
     use crate::mask::{
@@ -197,6 +240,8 @@ pub mod mask {
         MaskUnit,
         MaskVect,
         ModelType,
+        QuantizationType,
+        SparsityType,
     };
 
     use super::*;
@@ -210,8 +255,10 @@ pub mod mask {
             data_type: DataType::I32,
             bound_type: BoundType::B0,
             model_type: ModelType::M3,
+            sparsity_type: SparsityType::S0,
+            quantization_type: QuantizationType::Qmax,
         };
-        let bytes = vec![0x00, 0x02, 0x00, 0x03];
+        let bytes = vec![0x00, 0x02, 0x00, 0x03, 0x00, 0xff];
         (config, bytes)
     }
 
@@ -14,6 +14,8 @@ use crate::{
         MaskUnit,
         MaskVect,
         ModelType,
+        QuantizationType,
+        SparsityType,
     },
     message::{Message, ToBytes, Update},
     testutils::messages,
@@ -46,23 +48,23 @@ pub fn local_seed_dict(len: usize) -> LocalSeedDict {
 pub fn mask_object(len: usize) -> MaskObject {
     // The model contains 2 sub mask objects:
     //    - the masked model, which has:
-    //         - 4 bytes for the config
+    //         - 6 bytes for the config
     //         - 4 bytes for the number of weights
     //         - 6 bytes (with our config) for each weight
     //    - the masked scalar:
-    //         - 4 bytes for the config
+    //         - 6 bytes for the config
     //         - 6 bytes (with our config) for the scalar
     //
     // The only parameter we control to make the length vary is
     // the number of weights. The lengths is then:
     //
-    // len = (4 + 4 + n_weights * 6) + (4 + 6) = 18 + 6 * n_weights
+    // len = (6 + 4 + n_weights * 6) + (6 + 6) = 22 + 6 * n_weights
     //
-    // So we must have: (len - 18) % 6 = 0
-    if (len - 18) % 6 != 0 {
+    // So we must have: (len - 22) % 6 = 0
+    if (len - 22) % 6 != 0 {
         panic!("invalid masked model length")
     }
-    let n_weights = (len - 18) / 6;
+    let n_weights = (len - 22) / 6;
     // Let's not be too crazy, it makes no sense to test with too
     // many weights
     assert!(n_weights < u32::MAX as usize);
@@ -89,6 +91,8 @@ pub fn mask_config() -> MaskConfig {
         data_type: DataType::I32,
         bound_type: BoundType::B0,
         model_type: ModelType::M3,
+        sparsity_type: SparsityType::S0,
+        quantization_type: QuantizationType::Qmax,
     }
 }
 
@@ -108,3 +108,9 @@ pub const ERR_GLOBALMODEL_DATATYPE: c_int = 12;
 pub const ERR_GLOBALMODEL_LEN: c_int = 13;
 /// Failed to get the global model: invalid model
 pub const ERR_GLOBALMODEL_CONVERT: c_int = 14;
+/// Invalid local differential privacy settings
+pub const ERR_SETTINGS_LOCAL_PRIVACY: c_int = 15;
+/// Invalid federated learning task name
+pub const ERR_INVALID_TASK: c_int = 16;
+/// Invalid participant credential
+pub const ERR_INVALID_CREDENTIAL: c_int = 17;
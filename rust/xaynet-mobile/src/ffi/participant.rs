@@ -283,6 +283,55 @@ pub unsafe extern "C" fn xaynet_ffi_participant_restore(
     }
 }
 
+/// Restore the participant from a buffer that contained its serialized state like
+/// [`xaynet_ffi_participant_restore()`], with the credential which the coordinator issued for
+/// the participant. The credential isn't part of the serialized state.
+///
+/// # Return value
+///
+/// - a NULL pointer on failure
+/// - a pointer to the restored participant on success
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointers are NULL
+/// *or* all of the following is true:
+/// - The pointers must be properly [aligned].
+/// - They must be "dereferencable" in the sense defined in the [`::std::ptr`] module
+///   documentation.
+///
+/// [`::std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_participant_restore_with_credential(
+    url: FfiStr,
+    credential: FfiStr,
+    buffer: *const ByteBuffer,
+) -> *mut Participant {
+    let url = match url.as_opt_str() {
+        Some(url) => url,
+        None => return ptr::null_mut(),
+    };
+
+    let credential = match credential.as_opt_str() {
+        Some(credential) => credential,
+        None => return ptr::null_mut(),
+    };
+
+    let buffer: &ByteBuffer = match unsafe { buffer.as_ref() } {
+        Some(ptr) => ptr,
+        None => return ptr::null_mut(),
+    };
+
+    if let Ok(participant) =
+        Participant::restore_with_credential(buffer.as_slice(), url, Some(credential))
+    {
+        Box::into_raw(Box::new(participant))
+    } else {
+        ptr::null_mut()
+    }
+}
+
 /// Set the participant's model. Usually this should be called when the value returned
 /// by [`xaynet_ffi_participant_tick()`] contains the [`PARTICIPANT_SHOULD_SET_MODEL`]
 /// flag, but it can be called anytime. The model just won't be sent to the coordinator
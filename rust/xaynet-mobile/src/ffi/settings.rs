@@ -7,13 +7,17 @@ use zeroize::Zeroize;
 use super::{
     ERR_CRYPTO_PUBLIC_KEY,
     ERR_CRYPTO_SECRET_KEY,
+    ERR_INVALID_CREDENTIAL,
+    ERR_INVALID_TASK,
     ERR_INVALID_URL,
     ERR_NULLPTR,
     ERR_SETTINGS_KEYS,
+    ERR_SETTINGS_LOCAL_PRIVACY,
     ERR_SETTINGS_URL,
     OK,
 };
 use crate::{Settings, SettingsError};
+use xaynet_sdk::settings::LocalPrivacy;
 
 mod pv {
     use super::Settings;
@@ -93,6 +97,46 @@ pub unsafe extern "C" fn xaynet_ffi_settings_set_scalar(
     }
 }
 
+/// Set local differential privacy settings. The local model is clipped to an L2 norm of
+/// at most `clipping_norm` and Gaussian noise with a standard deviation of
+/// `clipping_norm * noise_multiplier` is added to each weight before it is masked.
+///
+/// # Return value
+///
+/// - [`OK`] if successful
+/// - [`ERR_NULLPTR`] if `settings` is `NULL`
+/// - [`ERR_SETTINGS_LOCAL_PRIVACY`] if `clipping_norm` is not positive or if
+///   `noise_multiplier` is negative
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointer is NULL *or*
+/// all of the following is true:
+/// - The pointer must be properly [aligned].
+/// - It must be "dereferencable" in the sense defined in the [`::std::ptr`] module
+///   documentation.
+///
+/// [`::std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_settings_set_local_privacy(
+    settings: *mut Settings,
+    clipping_norm: c_double,
+    noise_multiplier: c_double,
+) -> c_int {
+    let local_privacy = match LocalPrivacy::new(clipping_norm, noise_multiplier) {
+        Ok(local_privacy) => local_privacy,
+        Err(_) => return ERR_SETTINGS_LOCAL_PRIVACY,
+    };
+    match unsafe { settings.as_mut() } {
+        Some(settings) => {
+            settings.set_local_privacy(local_privacy);
+            OK
+        }
+        None => ERR_NULLPTR,
+    }
+}
+
 /// Set coordinator URL.
 ///
 /// # Return value
@@ -129,6 +173,80 @@ pub unsafe extern "C" fn xaynet_ffi_settings_set_url(
     }
 }
 
+/// Set the name of the federated learning task in which the participant takes part. If
+/// not set, the participant takes part in the default task of the coordinator.
+///
+/// # Return value
+///
+/// - [`OK`] if successful
+/// - [`ERR_INVALID_TASK`] if `task` is not a valid string
+/// - [`ERR_NULLPTR`] if `settings` is `NULL`
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointers are NULL
+/// *or* all of the following is true:
+/// - The pointers must be properly [aligned].
+/// - They must be "dereferencable" in the sense defined in the [`::std::ptr`] module
+///   documentation.
+///
+/// [`::std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_settings_set_task(
+    settings: *mut Settings,
+    task: FfiStr,
+) -> c_int {
+    let task = match task.as_opt_str() {
+        Some(task) => task,
+        None => return ERR_INVALID_TASK,
+    };
+    match unsafe { settings.as_mut() } {
+        Some(settings) => {
+            settings.set_task(task.to_string());
+            OK
+        }
+        None => ERR_NULLPTR,
+    }
+}
+
+/// Set the credential which the coordinator issued for the participant signing keys. It is
+/// only required if the coordinator restricts which participants it admits.
+///
+/// # Return value
+///
+/// - [`OK`] if successful
+/// - [`ERR_INVALID_CREDENTIAL`] if `credential` is not a valid string
+/// - [`ERR_NULLPTR`] if `settings` is `NULL`
+///
+/// # Safety
+///
+/// When calling this method, you have to ensure that *either* the pointers are NULL
+/// *or* all of the following is true:
+/// - The pointers must be properly [aligned].
+/// - They must be "dereferencable" in the sense defined in the [`::std::ptr`] module
+///   documentation.
+///
+/// [`::std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+/// [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+#[no_mangle]
+pub unsafe extern "C" fn xaynet_ffi_settings_set_credential(
+    settings: *mut Settings,
+    credential: FfiStr,
+) -> c_int {
+    let credential = match credential.as_opt_str() {
+        Some(credential) => credential,
+        None => return ERR_INVALID_CREDENTIAL,
+    };
+    match unsafe { settings.as_mut() } {
+        Some(settings) => {
+            settings.set_credential(credential.to_string());
+            OK
+        }
+        None => ERR_NULLPTR,
+    }
+}
+
 // TODO: add a way to save the key pair
 /// A signing key pair
 pub struct KeyPair {
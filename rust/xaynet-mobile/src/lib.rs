@@ -40,6 +40,7 @@ pub use self::{
     participant::{Event, Events, InitError, Notifier, Participant, Task},
     settings::{Settings, SettingsError},
 };
+pub use xaynet_sdk::settings::{InvalidLocalPrivacy, LocalPrivacy};
 pub mod ffi;
 
 mod reqwest_client;
@@ -176,8 +176,15 @@ pub struct GetGlobalModelError(xaynet_sdk::client::ClientError);
 impl Participant {
     /// Create a new participant with the given settings
     pub fn new(settings: Settings) -> Result<Self, InitError> {
+        let credential = settings.credential().map(str::to_string);
         let (url, pet_settings) = settings.try_into()?;
-        let client = new_client(url.as_str(), None, None)?;
+        let mut client = new_client(url.as_str(), None, None)?;
+        if let Some(ref task) = pet_settings.task {
+            client = client.with_task(task);
+        }
+        if let Some(ref credential) = credential {
+            client = client.with_credential(credential);
+        }
         let (events, notifier) = Events::new();
         let store = Store::new();
         let state_machine =
@@ -187,12 +194,28 @@ impl Participant {
 
     /// Restore a participant from it's serialized state. The coordinator client that
     /// the participant uses internally is not part of the participant state, so the
-    /// `url` is used to instantiate a new one.
+    /// `url` is used to instantiate a new one. A participant which takes part in a task
+    /// other than the default task must be restored with the URL of the task, i.e.
+    /// `{url}/tasks/{name}`.
     pub fn restore(state: &[u8], url: &str) -> Result<Self, InitError> {
+        Self::restore_with_credential(state, url, None)
+    }
+
+    /// Restore a participant from it's serialized state like [`Participant::restore()`]. The
+    /// credential isn't part of the participant state either, so a participant which presents
+    /// a credential must be restored with it.
+    pub fn restore_with_credential(
+        state: &[u8],
+        url: &str,
+        credential: Option<&str>,
+    ) -> Result<Self, InitError> {
         let state: SerializableState = bincode::deserialize(state)?;
         let (events, notifier) = Events::new();
         let store = Store::new();
-        let client = new_client(url, None, None)?;
+        let mut client = new_client(url, None, None)?;
+        if let Some(credential) = credential {
+            client = client.with_credential(credential);
+        }
         let state_machine = StateMachine::restore(state, client.clone(), store.clone(), notifier);
         Self::init(state_machine, client, events, store)
     }
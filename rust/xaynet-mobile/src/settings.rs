@@ -5,7 +5,7 @@
 use std::convert::TryInto;
 use thiserror::Error;
 use xaynet_core::crypto::SigningKeyPair;
-use xaynet_sdk::settings::{MaxMessageSize, PetSettings};
+use xaynet_sdk::settings::{LocalPrivacy, MaxMessageSize, PetSettings};
 
 /// A participant settings
 #[derive(Clone, Debug)]
@@ -16,6 +16,12 @@ pub struct Settings {
     url: Option<String>,
     /// The scalar used for masking
     scalar: f64,
+    /// The local differential privacy settings
+    local_privacy: Option<LocalPrivacy>,
+    /// The federated learning task
+    task: Option<String>,
+    /// The credential which admits the participant
+    credential: Option<String>,
 }
 
 impl Default for Settings {
@@ -31,6 +37,9 @@ impl Settings {
             keys: None,
             url: None,
             scalar: 1.0,
+            local_privacy: None,
+            task: None,
+            credential: None,
         }
     }
 
@@ -44,11 +53,34 @@ impl Settings {
         self.scalar = scalar;
     }
 
+    /// Set the local differential privacy settings. If set, the local model is
+    /// clipped and noised before it is masked.
+    pub fn set_local_privacy(&mut self, local_privacy: LocalPrivacy) {
+        self.local_privacy = Some(local_privacy);
+    }
+
     /// Set the Xaynet coordinator address
     pub fn set_url(&mut self, url: String) {
         self.url = Some(url);
     }
 
+    /// Set the name of the federated learning task in which the participant takes part. If
+    /// not set, the participant takes part in the default task of the coordinator.
+    pub fn set_task(&mut self, task: String) {
+        self.task = Some(task);
+    }
+
+    /// Set the credential which the coordinator issued for the participant signing keys. It is
+    /// only required if the coordinator restricts which participants it admits.
+    pub fn set_credential(&mut self, credential: String) {
+        self.credential = Some(credential);
+    }
+
+    /// Get the credential which admits the participant, if any
+    pub(crate) fn credential(&self) -> Option<&str> {
+        self.credential.as_deref()
+    }
+
     /// Check whether the settings are complete and valid
     pub fn check(&self) -> Result<(), SettingsError> {
         if self.url.is_none() {
@@ -74,7 +106,14 @@ impl TryInto<(String, PetSettings)> for Settings {
     type Error = SettingsError;
 
     fn try_into(self) -> Result<(String, PetSettings), Self::Error> {
-        let Settings { keys, url, scalar } = self;
+        let Settings {
+            keys,
+            url,
+            scalar,
+            local_privacy,
+            task,
+            ..
+        } = self;
 
         let url = url.ok_or(SettingsError::MissingUrl)?;
 
@@ -83,7 +122,9 @@ impl TryInto<(String, PetSettings)> for Settings {
         let pet_settings = PetSettings {
             scalar,
             max_message_size: MaxMessageSize::default(),
+            local_privacy,
             keys,
+            task,
         };
 
         Ok((url, pet_settings))
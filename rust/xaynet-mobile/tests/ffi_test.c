@@ -46,6 +46,53 @@ static char *test_settings_set_url() {
   return 0;
 }
 
+static char *test_settings_set_task() {
+  Settings *settings = xaynet_ffi_settings_new();
+
+  int err = xaynet_ffi_settings_set_task(settings, NULL);
+  mu_assert("settings invalid task should fail", err == ERR_INVALID_TASK);
+
+  err = xaynet_ffi_settings_set_task(settings, "keyboard");
+  mu_assert("failed to set task", !err);
+
+  xaynet_ffi_settings_destroy(settings);
+
+  return 0;
+}
+
+static char *test_settings_set_credential() {
+  Settings *settings = xaynet_ffi_settings_new();
+
+  int err = xaynet_ffi_settings_set_credential(settings, NULL);
+  mu_assert("settings invalid credential should fail",
+            err == ERR_INVALID_CREDENTIAL);
+
+  err = xaynet_ffi_settings_set_credential(settings, "credential");
+  mu_assert("failed to set credential", !err);
+
+  xaynet_ffi_settings_destroy(settings);
+
+  return 0;
+}
+
+static char *test_settings_set_local_privacy() {
+  Settings *settings = xaynet_ffi_settings_new();
+
+  int err = xaynet_ffi_settings_set_local_privacy(settings, 0.0, 1.0);
+  mu_assert("settings invalid clipping norm should fail",
+            err == ERR_SETTINGS_LOCAL_PRIVACY);
+
+  err = xaynet_ffi_settings_set_local_privacy(settings, 1.0, -1.0);
+  mu_assert("settings invalid noise multiplier should fail",
+            err == ERR_SETTINGS_LOCAL_PRIVACY);
+
+  err = xaynet_ffi_settings_set_local_privacy(settings, 1.0, 0.1);
+  mu_assert("failed to set local privacy", !err);
+
+  xaynet_ffi_settings_destroy(settings);
+  return 0;
+}
+
 void with_keys(Settings *settings) {
   const KeyPair *keys = xaynet_ffi_generate_key_pair();
   int err = xaynet_ffi_settings_set_keys(settings, keys);
@@ -175,6 +222,9 @@ static char *all_tests() {
   mu_run_test(test_settings_new);
   mu_run_test(test_settings_set_keys);
   mu_run_test(test_settings_set_url);
+  mu_run_test(test_settings_set_task);
+  mu_run_test(test_settings_set_credential);
+  mu_run_test(test_settings_set_local_privacy);
   mu_run_test(test_settings);
   mu_run_test(test_global_model);
   mu_run_test(test_participant_save_and_restore);
@@ -82,6 +82,21 @@
  */
 #define ERR_GLOBALMODEL_CONVERT 14
 
+/**
+ * Invalid local differential privacy settings
+ */
+#define ERR_SETTINGS_LOCAL_PRIVACY 15
+
+/**
+ * Invalid federated learning task name
+ */
+#define ERR_INVALID_TASK 16
+
+/**
+ * Invalid participant credential
+ */
+#define ERR_INVALID_CREDENTIAL 17
+
 /**
  * The participant is not taking part in the sum or update task
  */
@@ -529,6 +544,31 @@ const struct ByteBuffer *xaynet_ffi_participant_save(struct Participant *partici
  */
 struct Participant *xaynet_ffi_participant_restore(FfiStr url, const struct ByteBuffer *buffer);
 
+/**
+ * Restore the participant from a buffer that contained its serialized state like
+ * [`xaynet_ffi_participant_restore()`], with the credential which the coordinator issued for
+ * the participant. The credential isn't part of the serialized state.
+ *
+ * # Return value
+ *
+ * - a NULL pointer on failure
+ * - a pointer to the restored participant on success
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointers are NULL
+ * *or* all of the following is true:
+ * - The pointers must be properly [aligned].
+ * - They must be "dereferencable" in the sense defined in the [`::std::ptr`] module
+ *   documentation.
+ *
+ * [`::std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+struct Participant *xaynet_ffi_participant_restore_with_credential(FfiStr url,
+                                                                   FfiStr credential,
+                                                                   const struct ByteBuffer *buffer);
+
 /**
  * Set the participant's model. Usually this should be called when the value returned
  * by [`xaynet_ffi_participant_tick()`] contains the [`PARTICIPANT_SHOULD_SET_MODEL`]
@@ -681,6 +721,33 @@ struct Settings *xaynet_ffi_settings_new(void);
  */
 int xaynet_ffi_settings_set_scalar(struct Settings *settings, double scalar);
 
+/**
+ * Set local differential privacy settings. The local model is clipped to an L2 norm of
+ * at most `clipping_norm` and Gaussian noise with a standard deviation of
+ * `clipping_norm * noise_multiplier` is added to each weight before it is masked.
+ *
+ * # Return value
+ *
+ * - [`OK`] if successful
+ * - [`ERR_NULLPTR`] if `settings` is `NULL`
+ * - [`ERR_SETTINGS_LOCAL_PRIVACY`] if `clipping_norm` is not positive or if
+ *   `noise_multiplier` is negative
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointer is NULL *or*
+ * all of the following is true:
+ * - The pointer must be properly [aligned].
+ * - It must be "dereferencable" in the sense defined in the [`::std::ptr`] module
+ *   documentation.
+ *
+ * [`::std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_settings_set_local_privacy(struct Settings *settings,
+                                          double clipping_norm,
+                                          double noise_multiplier);
+
 /**
  * Set coordinator URL.
  *
@@ -703,6 +770,52 @@ int xaynet_ffi_settings_set_scalar(struct Settings *settings, double scalar);
  */
 int xaynet_ffi_settings_set_url(struct Settings *settings, FfiStr url);
 
+/**
+ * Set the name of the federated learning task in which the participant takes part. If
+ * not set, the participant takes part in the default task of the coordinator.
+ *
+ * # Return value
+ *
+ * - [`OK`] if successful
+ * - [`ERR_INVALID_TASK`] if `task` is not a valid string
+ * - [`ERR_NULLPTR`] if `settings` is `NULL`
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointers are NULL
+ * *or* all of the following is true:
+ * - The pointers must be properly [aligned].
+ * - They must be "dereferencable" in the sense defined in the [`::std::ptr`] module
+ *   documentation.
+ *
+ * [`::std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_settings_set_task(struct Settings *settings, FfiStr task);
+
+/**
+ * Set the credential which the coordinator issued for the participant signing keys. It is
+ * only required if the coordinator restricts which participants it admits.
+ *
+ * # Return value
+ *
+ * - [`OK`] if successful
+ * - [`ERR_INVALID_CREDENTIAL`] if `credential` is not a valid string
+ * - [`ERR_NULLPTR`] if `settings` is `NULL`
+ *
+ * # Safety
+ *
+ * When calling this method, you have to ensure that *either* the pointers are NULL
+ * *or* all of the following is true:
+ * - The pointers must be properly [aligned].
+ * - They must be "dereferencable" in the sense defined in the [`::std::ptr`] module
+ *   documentation.
+ *
+ * [`::std::ptr`]: https://doc.rust-lang.org/std/ptr/index.html#safety
+ * [aligned]: https://doc.rust-lang.org/std/ptr/index.html#alignment
+ */
+int xaynet_ffi_settings_set_credential(struct Settings *settings, FfiStr credential);
+
 /**
  * Generate a new signing key pair that can be used in the [`Settings`]. **Before
  * calling this function you must initialize the crypto library with
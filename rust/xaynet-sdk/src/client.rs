@@ -3,13 +3,15 @@ use thiserror::Error;
 use url::Url;
 
 use xaynet_core::{
-    common::RoundParameters,
+    common::{MessageError, RoundParameters},
     crypto::{ByteObject, PublicSigningKey},
     mask::Model,
     SumDict,
     UpdateSeedDict,
 };
 
+#[cfg(feature = "reqwest-client")]
+use crate::events::EventStream;
 use crate::XaynetClient;
 
 /// Error returned upon failing to build a new [`Client`]
@@ -30,6 +32,13 @@ pub enum ClientError {
     #[error("Unexpected response")]
     UnexpectedResponse(u16),
 
+    #[error("The coordinator failed to handle the message: {0}")]
+    Message(#[from] MessageError),
+
+    #[cfg(feature = "grpc-client")]
+    #[error("gRPC request failed: {0}")]
+    Grpc(String),
+
     #[error("Unexpected certificate extension")]
     UnexpectedCertificate,
 
@@ -70,7 +79,56 @@ pub trait XaynetHttpClient {
     async fn get(&mut self, url: &str) -> Result<Option<Self::GetResponse>, ClientError>;
 
     /// Perform an HTTP `POST` on the given URL, with the given body.
+    ///
+    /// If the coordinator fails to handle a PET message, it replies with an error status and a
+    /// [`MessageError`] body, which the implementor must return as [`ClientError::Message`].
     async fn post(&mut self, url: &str, body: Vec<u8>) -> Result<(), ClientError>;
+
+    /// Perform an HTTP `POST` on the given URL, with the given body and credential.
+    ///
+    /// If a credential is given, the implementor must send it in the `X-Xaynet-Credential`
+    /// header. Otherwise, the request is the same as for [`post()`].
+    ///
+    /// The default implementation ignores the credential.
+    ///
+    /// [`post()`]: XaynetHttpClient::post
+    async fn post_with_credential(
+        &mut self,
+        url: &str,
+        body: Vec<u8>,
+        _credential: Option<&str>,
+    ) -> Result<(), ClientError> {
+        self.post(url, body).await
+    }
+
+    /// Perform a conditional HTTP `GET` on the given URL.
+    ///
+    /// If an entity tag is given, the implementor should send it in the `If-None-Match` header
+    /// and return [`Conditional::NotModified`] if the response is `NOT_MODIFIED`. The entity tag
+    /// of a response should be returned along with its body.
+    ///
+    /// The default implementation performs an unconditional `GET` and ignores entity tags.
+    async fn get_if_none_match(
+        &mut self,
+        url: &str,
+        _etag: Option<&str>,
+    ) -> Result<Conditional<Self::GetResponse>, ClientError> {
+        Ok(match self.get(url).await? {
+            Some(body) => Conditional::Modified { body, etag: None },
+            None => Conditional::NoContent,
+        })
+    }
+}
+
+/// The response to a conditional `GET` request.
+#[derive(Debug)]
+pub enum Conditional<T> {
+    /// The data didn't change since the entity tag of the request.
+    NotModified,
+    /// The data is not available.
+    NoContent,
+    /// The data along with its entity tag, if any.
+    Modified { body: T, etag: Option<String> },
 }
 
 #[derive(Debug, Clone)]
@@ -80,6 +138,10 @@ pub struct Client<C> {
     client: C,
     /// Coordinator URL
     base_url: Url,
+    /// The last global model along with its entity tag
+    cached_model: Option<(String, Model)>,
+    /// The credential which is presented with the PET messages
+    credential: Option<String>,
 }
 
 /// Error returned when trying to client a [`Client`] with an invalid
@@ -111,16 +173,44 @@ where
         Ok(Self {
             client: http_client,
             base_url,
+            cached_model: None,
+            credential: None,
         })
     }
 
+    /// Scope the client to the federated learning task with the given name.
+    ///
+    /// The coordinator serves the routes of each task other than its default task under
+    /// `/tasks/{name}`.
+    pub fn with_task(mut self, task: &str) -> Self {
+        // safe unwrap: `new()` checks that the base URL can be a base
+        self.base_url
+            .path_segments_mut()
+            .unwrap()
+            .extend(&["tasks", task]);
+        self
+    }
+
+    /// Present the given credential with every PET message.
+    ///
+    /// The coordinator requires a credential if it only admits certain participants.
+    pub fn with_credential(mut self, credential: &str) -> Self {
+        self.credential = Some(credential.to_string());
+        self
+    }
+
     /// Append the given segment to the client base URL
     fn url(&self, segment: &str) -> Url {
         let mut url = self.base_url.clone();
         url.path_segments_mut().unwrap().push(segment);
         url
     }
+}
 
+impl<C> Client<C>
+where
+    C: XaynetHttpClient + Send,
+{
     async fn get<T>(&mut self, url: &Url) -> Result<Option<T>, ClientError>
     where
         T: for<'a> serde::Deserialize<'a>,
@@ -132,7 +222,9 @@ where
     }
 
     async fn post(&mut self, url: &Url, data: Vec<u8>) -> Result<(), ClientError> {
-        self.client.post(url.as_str(), data).await
+        self.client
+            .post_with_credential(url.as_str(), data, self.credential.as_deref())
+            .await
     }
 }
 
@@ -166,9 +258,32 @@ where
         self.get(&url).await
     }
 
+    async fn get_dropouts(&mut self) -> Result<Option<SumDict>, Self::Error> {
+        let url = self.url("dropouts");
+        self.get(&url).await
+    }
+
+    /// Fetches the global model.
+    ///
+    /// The last model is cached and only downloaded again if it changed.
     async fn get_model(&mut self) -> Result<Option<Model>, Self::Error> {
         let url = self.url("model");
-        Ok(self.get(&url).await?)
+        let etag = self.cached_model.as_ref().map(|(etag, _)| etag.as_str());
+        match self.client.get_if_none_match(url.as_str(), etag).await? {
+            Conditional::NotModified => match self.cached_model {
+                Some((_, ref model)) => Ok(Some(model.clone())),
+                None => Err(ClientError::UnexpectedResponse(304)),
+            },
+            Conditional::NoContent => {
+                self.cached_model = None;
+                Ok(None)
+            }
+            Conditional::Modified { body, etag } => {
+                let model = bincode::deserialize::<Model>(body.as_ref())?;
+                self.cached_model = etag.map(|etag| (etag, model.clone()));
+                Ok(Some(model))
+            }
+        }
     }
 
     async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Self::Error> {
@@ -177,6 +292,28 @@ where
     }
 }
 
+#[cfg(feature = "reqwest-client")]
+impl Client<reqwest::Client> {
+    /// Subscribe to the round events pushed by the coordinator.
+    ///
+    /// A participant can wait for the next event instead of polling the coordinator when the
+    /// state machine is pending.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the request fails.
+    pub async fn events(&self) -> Result<EventStream, ClientError> {
+        let response = reqwest::Client::get(&self.client, self.url("events"))
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .map_err(ClientError::http_error)?
+            .error_for_status()
+            .map_err(ClientError::http_error)?;
+        Ok(EventStream::new(response))
+    }
+}
+
 #[cfg(feature = "reqwest-client")]
 #[async_trait]
 impl XaynetHttpClient for reqwest::Client {
@@ -199,14 +336,176 @@ impl XaynetHttpClient for reqwest::Client {
         }
     }
 
-    async fn post(&mut self, url: &str, body: Vec<u8>) -> Result<(), ClientError> {
-        let _resp = reqwest::Client::post(self, url)
-            .body(body)
+    async fn get_if_none_match(
+        &mut self,
+        url: &str,
+        etag: Option<&str>,
+    ) -> Result<Conditional<Self::GetResponse>, ClientError> {
+        let mut request = reqwest::Client::get(self, url);
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let resp = request
             .send()
             .await
             .map_err(ClientError::http_error)?
             .error_for_status()
             .map_err(ClientError::http_error)?;
-        Ok(())
+        match resp.status() {
+            reqwest::StatusCode::OK => {
+                let etag = resp
+                    .headers()
+                    .get(reqwest::header::ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                    .map(str::to_string);
+                let body = resp.bytes().await.map_err(ClientError::http_error)?;
+                Ok(Conditional::Modified { body, etag })
+            }
+            reqwest::StatusCode::NO_CONTENT => Ok(Conditional::NoContent),
+            reqwest::StatusCode::NOT_MODIFIED => Ok(Conditional::NotModified),
+            status => Err(ClientError::UnexpectedResponse(status.as_u16())),
+        }
+    }
+
+    async fn post(&mut self, url: &str, body: Vec<u8>) -> Result<(), ClientError> {
+        self.post_with_credential(url, body, None).await
+    }
+
+    async fn post_with_credential(
+        &mut self,
+        url: &str,
+        body: Vec<u8>,
+        credential: Option<&str>,
+    ) -> Result<(), ClientError> {
+        let mut request = reqwest::Client::post(self, url);
+        if let Some(credential) = credential {
+            request = request.header("x-xaynet-credential", credential);
+        }
+        let resp = request
+            .body(body)
+            .send()
+            .await
+            .map_err(ClientError::http_error)?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = resp.bytes().await.map_err(ClientError::http_error)?;
+        match bincode::deserialize::<MessageError>(&body) {
+            Ok(error) => Err(ClientError::Message(error)),
+            Err(_) => Err(ClientError::UnexpectedResponse(status.as_u16())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xaynet_core::mask::FromPrimitives;
+
+    /// An HTTP client which serves a single model with an entity tag.
+    struct ModelHttpClient {
+        model: Vec<u8>,
+        etag: String,
+        requests: Vec<Option<String>>,
+        credentials: Vec<Option<String>>,
+    }
+
+    #[async_trait]
+    impl XaynetHttpClient for ModelHttpClient {
+        type Error = std::convert::Infallible;
+        type GetResponse = Vec<u8>;
+
+        async fn get(&mut self, _url: &str) -> Result<Option<Self::GetResponse>, ClientError> {
+            Ok(Some(self.model.clone()))
+        }
+
+        async fn post(&mut self, _url: &str, _body: Vec<u8>) -> Result<(), ClientError> {
+            Ok(())
+        }
+
+        async fn post_with_credential(
+            &mut self,
+            _url: &str,
+            _body: Vec<u8>,
+            credential: Option<&str>,
+        ) -> Result<(), ClientError> {
+            self.credentials.push(credential.map(str::to_string));
+            Ok(())
+        }
+
+        async fn get_if_none_match(
+            &mut self,
+            _url: &str,
+            etag: Option<&str>,
+        ) -> Result<Conditional<Self::GetResponse>, ClientError> {
+            self.requests.push(etag.map(str::to_string));
+            if etag == Some(self.etag.as_str()) {
+                return Ok(Conditional::NotModified);
+            }
+            Ok(Conditional::Modified {
+                body: self.model.clone(),
+                etag: Some(self.etag.clone()),
+            })
+        }
+    }
+
+    #[test]
+    fn test_url_with_task() {
+        let http_client = ModelHttpClient {
+            model: Vec::new(),
+            etag: String::new(),
+            requests: Vec::new(),
+            credentials: Vec::new(),
+        };
+        let client = Client::new(http_client, "http://localhost:8081").unwrap();
+        assert_eq!(
+            client.url("params").as_str(),
+            "http://localhost:8081/params"
+        );
+
+        let client = client.with_task("keyboard");
+        assert_eq!(
+            client.url("params").as_str(),
+            "http://localhost:8081/tasks/keyboard/params"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_model_cached() {
+        let model = Model::from_primitives(vec![1_i32, 2, 3].into_iter()).unwrap();
+        let http_client = ModelHttpClient {
+            model: bincode::serialize(&model).unwrap(),
+            etag: "\"model-1_0123-bincode\"".to_string(),
+            requests: Vec::new(),
+            credentials: Vec::new(),
+        };
+        let mut client = Client::new(http_client, "http://localhost:8081").unwrap();
+
+        assert_eq!(client.get_model().await.unwrap(), Some(model.clone()));
+        assert_eq!(client.get_model().await.unwrap(), Some(model));
+        assert_eq!(
+            client.client.requests,
+            vec![None, Some("\"model-1_0123-bincode\"".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_message_with_credential() {
+        let http_client = ModelHttpClient {
+            model: Vec::new(),
+            etag: String::new(),
+            requests: Vec::new(),
+            credentials: Vec::new(),
+        };
+        let mut client = Client::new(http_client, "http://localhost:8081").unwrap();
+        client.send_message(Vec::new()).await.unwrap();
+
+        let mut client = client.with_credential("credential");
+        client.send_message(Vec::new()).await.unwrap();
+        assert_eq!(
+            client.client.credentials,
+            vec![None, Some("credential".to_string())]
+        );
     }
 }
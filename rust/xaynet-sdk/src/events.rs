@@ -0,0 +1,127 @@
+//! Round events pushed by the coordinator.
+//!
+//! Instead of polling the coordinator whenever the [`StateMachine`] is pending, a participant can
+//! subscribe to the round events which the coordinator pushes as server-sent events and make the
+//! next transition once an event arrives. See [`Client::events`].
+//!
+//! Requires the `reqwest-client` feature to be enabled.
+//!
+//! [`StateMachine`]: crate::StateMachine
+//! [`Client::events`]: crate::client::Client::events
+
+use xaynet_core::common::RoundEvent;
+
+use crate::client::ClientError;
+
+/// A stream of the round events pushed by the coordinator.
+///
+/// The stream starts with the events of the current state of the coordinator.
+#[derive(Debug)]
+pub struct EventStream {
+    response: reqwest::Response,
+    parser: EventParser,
+}
+
+impl EventStream {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            parser: EventParser::default(),
+        }
+    }
+
+    /// Wait for the next round event.
+    ///
+    /// Returns `None` if the coordinator closed the stream.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the connection fails or an event can't be parsed.
+    pub async fn next(&mut self) -> Result<Option<RoundEvent>, ClientError> {
+        loop {
+            if let Some(event) = self.parser.next_event()? {
+                return Ok(Some(event));
+            }
+            match self
+                .response
+                .chunk()
+                .await
+                .map_err(|e| ClientError::Http(e.to_string()))?
+            {
+                Some(chunk) => self.parser.push(&chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// An incremental parser of server-sent events.
+#[derive(Debug, Default)]
+struct EventParser {
+    buffer: Vec<u8>,
+}
+
+impl EventParser {
+    /// Append received bytes to the buffer.
+    fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Parse the next complete event from the buffer.
+    ///
+    /// Events without data, like the comments sent to keep the connection alive, are skipped.
+    fn next_event(&mut self) -> Result<Option<RoundEvent>, ClientError> {
+        while let Some(end) = self.buffer.windows(2).position(|window| window == b"\n\n") {
+            let block = self.buffer.drain(..end + 2).collect::<Vec<u8>>();
+            let block = String::from_utf8_lossy(&block);
+            let data = block
+                .lines()
+                .filter_map(|line| line.trim_end_matches('\r').strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect::<Vec<_>>();
+            if data.is_empty() {
+                continue;
+            }
+            return serde_json::from_str(&data.join("\n"))
+                .map(Some)
+                .map_err(|e| ClientError::Deserialize(e.to_string()));
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let mut parser = EventParser::default();
+        parser.push(b":\n\nevent:new_round\ndata:{\"NewRound\":{\"round_id\":1}}\n\nevent:pha");
+        assert_eq!(
+            parser.next_event().unwrap(),
+            Some(RoundEvent::NewRound { round_id: 1 })
+        );
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        parser.push(b"se\ndata:{\"Phase\":{\"round_id\":1,\"phase\":\"Sum\"}}\n\n");
+        assert_eq!(
+            parser.next_event().unwrap(),
+            Some(RoundEvent::Phase {
+                round_id: 1,
+                phase: "Sum".to_string()
+            })
+        );
+        assert_eq!(parser.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_event() {
+        let mut parser = EventParser::default();
+        parser.push(b"event:model\ndata:{}\n\n");
+        assert!(matches!(
+            parser.next_event(),
+            Err(ClientError::Deserialize(_))
+        ));
+    }
+}
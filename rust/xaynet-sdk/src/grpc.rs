@@ -0,0 +1,433 @@
+//! A [`XaynetClient`] which communicates with the coordinator's gRPC API.
+//!
+//! Requires the `grpc-client` feature to be enabled.
+
+use std::convert::{TryFrom, TryInto};
+
+use async_trait::async_trait;
+use prost::Message;
+use tonic::{metadata::MetadataValue, transport::Channel, Request, Status};
+
+use self::proto::coordinator_client::CoordinatorClient;
+use crate::{client::ClientError, XaynetClient};
+use xaynet_core::{
+    common::{MessageError, MessageErrorKind, RoundParameters, RoundSeed},
+    crypto::{ByteObject, PublicEncryptKey, PublicSigningKey},
+    mask::{
+        BoundType,
+        DataType,
+        EncryptedMaskSeed,
+        FromPrimitives,
+        GroupType,
+        InvalidMaskConfigError,
+        MaskConfig,
+        MaskConfigPair,
+        Model,
+        ModelType,
+        QuantizationType,
+        SparsityType,
+    },
+    SumDict,
+    UpdateSeedDict,
+};
+
+#[allow(clippy::all, missing_docs)]
+/// The protobuf messages and client generated from the protobuf definition.
+pub mod proto {
+    tonic::include_proto!("xaynet.v1");
+}
+
+#[derive(Debug, Clone)]
+/// A client that communicates with the coordinator's API via gRPC.
+pub struct GrpcClient {
+    client: CoordinatorClient<Channel>,
+    /// The credential which is presented with the PET messages
+    credential: Option<String>,
+    /// The task which is selected by every call, if any
+    task: Option<String>,
+}
+
+impl GrpcClient {
+    /// Create a new client from an established channel.
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            client: CoordinatorClient::new(channel),
+            credential: None,
+            task: None,
+        }
+    }
+
+    /// Connect to the coordinator's gRPC API at the given URL, e.g. `http://127.0.0.1:8082`.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the URL is invalid or the connection fails.
+    pub async fn connect(url: String) -> Result<Self, ClientError> {
+        let client = CoordinatorClient::connect(url)
+            .await
+            .map_err(|e| ClientError::Grpc(e.to_string()))?;
+        Ok(Self {
+            client,
+            credential: None,
+            task: None,
+        })
+    }
+
+    /// Present the given credential in the `x-xaynet-credential` metadata of every PET message.
+    ///
+    /// The coordinator requires a credential if it only admits certain participants.
+    pub fn with_credential(mut self, credential: &str) -> Self {
+        self.credential = Some(credential.to_string());
+        self
+    }
+
+    /// Select the federated learning task with the given name in the `x-xaynet-task` metadata of
+    /// every call.
+    ///
+    /// The coordinator handles calls which don't select a task with its default task.
+    pub fn with_task(mut self, task: &str) -> Self {
+        self.task = Some(task.to_string());
+        self
+    }
+
+    /// Create a request which selects the task of the client, if any.
+    fn request<T>(&self, message: T) -> Result<Request<T>, ClientError> {
+        let mut request = Request::new(message);
+        if let Some(ref task) = self.task {
+            let task = MetadataValue::from_str(task)
+                .map_err(|_| ClientError::Other("invalid task".to_string()))?;
+            request.metadata_mut().insert("x-xaynet-task", task);
+        }
+        Ok(request)
+    }
+}
+
+#[async_trait]
+impl XaynetClient for GrpcClient {
+    type Error = ClientError;
+
+    async fn get_round_params(&mut self) -> Result<RoundParameters, Self::Error> {
+        let request = self.request(proto::GetRoundParametersRequest {})?;
+        let params = self
+            .client
+            .get_round_parameters(request)
+            .await?
+            .into_inner();
+        params.try_into()
+    }
+
+    async fn get_sums(&mut self) -> Result<Option<SumDict>, Self::Error> {
+        let request = self.request(proto::GetSumsRequest {})?;
+        let response = self.client.get_sums(request).await?.into_inner();
+        response.sum_dict.map(SumDict::try_from).transpose()
+    }
+
+    async fn get_seeds(
+        &mut self,
+        pk: PublicSigningKey,
+    ) -> Result<Option<UpdateSeedDict>, Self::Error> {
+        let request = self.request(proto::GetSeedsRequest {
+            pk: pk.as_slice().to_vec(),
+        })?;
+        let response = self.client.get_seeds(request).await?.into_inner();
+        response.seed_dict.map(UpdateSeedDict::try_from).transpose()
+    }
+
+    async fn get_dropouts(&mut self) -> Result<Option<SumDict>, Self::Error> {
+        let request = self.request(proto::GetDropoutsRequest {})?;
+        let response = self.client.get_dropouts(request).await?.into_inner();
+        response.dropout_dict.map(SumDict::try_from).transpose()
+    }
+
+    async fn get_model(&mut self) -> Result<Option<Model>, Self::Error> {
+        let request = self.request(proto::GetModelRequest {})?;
+        let response = self.client.get_model(request).await?.into_inner();
+        response.model.map(Model::try_from).transpose()
+    }
+
+    async fn send_message(&mut self, msg: Vec<u8>) -> Result<(), Self::Error> {
+        let mut request = self.request(proto::SendMessageRequest { message: msg })?;
+        if let Some(ref credential) = self.credential {
+            let credential = MetadataValue::from_str(credential)
+                .map_err(|_| ClientError::Other("invalid credential".to_string()))?;
+            request
+                .metadata_mut()
+                .insert("x-xaynet-credential", credential);
+        }
+        self.client.send_message(request).await?;
+        Ok(())
+    }
+}
+
+impl From<Status> for ClientError {
+    /// Converts a status into a [`ClientError::Message`] if the coordinator failed to handle a
+    /// PET message and into a [`ClientError::Grpc`] otherwise.
+    fn from(status: Status) -> Self {
+        // only failed PET messages come with details
+        if status.details().is_empty() {
+            return Self::Grpc(status.to_string());
+        }
+        match proto::MessageError::decode(status.details()) {
+            Ok(error) => Self::Message(error.into()),
+            Err(_) => Self::Grpc(status.to_string()),
+        }
+    }
+}
+
+impl From<proto::MessageError> for MessageError {
+    fn from(error: proto::MessageError) -> Self {
+        use self::proto::message_error::Kind;
+
+        let kind = match error.kind() {
+            Kind::Decrypt => MessageErrorKind::Decrypt,
+            Kind::Parsing => MessageErrorKind::Parsing,
+            Kind::InvalidSignature => MessageErrorKind::InvalidSignature,
+            Kind::InvalidCoordinatorPublicKey => MessageErrorKind::InvalidCoordinatorPublicKey,
+            Kind::UnexpectedMessage => MessageErrorKind::UnexpectedMessage,
+            Kind::NotEligible => MessageErrorKind::NotEligible,
+            Kind::Discarded => MessageErrorKind::Discarded,
+            Kind::Rejected => MessageErrorKind::Rejected,
+            Kind::Internal => MessageErrorKind::Internal,
+            Kind::TooLarge => MessageErrorKind::TooLarge,
+            Kind::RateLimited => MessageErrorKind::RateLimited,
+            Kind::NotAdmitted => MessageErrorKind::NotAdmitted,
+        };
+        Self {
+            kind,
+            description: error.description,
+        }
+    }
+}
+
+/// Parses a byte object from its protobuf representation.
+fn byte_object<B: ByteObject>(bytes: &[u8], name: &str) -> Result<B, ClientError> {
+    B::from_slice(bytes).ok_or_else(|| ClientError::Deserialize(format!("invalid {}", name)))
+}
+
+/// Parses an enum of a masking configuration from its protobuf representation.
+fn mask_config_enum<E: TryFrom<u8>>(value: u32, name: &str) -> Result<E, ClientError> {
+    u8::try_from(value)
+        .ok()
+        .and_then(|value| E::try_from(value).ok())
+        .ok_or_else(|| ClientError::Deserialize(format!("invalid {}: {}", name, value)))
+}
+
+/// Parses an enum with a custom variant of a masking configuration from its protobuf
+/// representation, where the `custom` value is only used for the custom variant.
+fn mask_config_custom_enum<E>(
+    value: u32,
+    custom: u64,
+    from_tag: fn(u8, u64) -> Result<E, InvalidMaskConfigError>,
+    name: &str,
+) -> Result<E, ClientError> {
+    u8::try_from(value)
+        .ok()
+        .and_then(|value| from_tag(value, custom).ok())
+        .ok_or_else(|| ClientError::Deserialize(format!("invalid {}: {}", name, value)))
+}
+
+/// Parses a masking configuration from its protobuf representation.
+fn mask_config(config: Option<proto::MaskConfig>) -> Result<MaskConfig, ClientError> {
+    let config =
+        config.ok_or_else(|| ClientError::Deserialize("missing mask config".to_string()))?;
+    let mask_config = MaskConfig {
+        group_type: mask_config_enum::<GroupType>(config.group_type, "group type")?,
+        data_type: mask_config_enum::<DataType>(config.data_type, "data type")?,
+        bound_type: mask_config_custom_enum(
+            config.bound_type,
+            config.bound,
+            BoundType::from_tag,
+            "bound type",
+        )?,
+        model_type: mask_config_custom_enum(
+            config.model_type,
+            config.max_nb_models,
+            ModelType::from_tag,
+            "model type",
+        )?,
+        sparsity_type: mask_config_enum::<SparsityType>(config.sparsity_type, "sparsity type")?,
+        quantization_type: mask_config_custom_enum(
+            config.quantization_type,
+            config.decimal_places.into(),
+            QuantizationType::from_tag,
+            "quantization type",
+        )?,
+    };
+    mask_config
+        .validate()
+        .map_err(|error| ClientError::Deserialize(format!("invalid mask config: {}", error)))?;
+    Ok(mask_config)
+}
+
+impl TryFrom<proto::RoundParameters> for RoundParameters {
+    type Error = ClientError;
+
+    fn try_from(params: proto::RoundParameters) -> Result<Self, Self::Error> {
+        let mask_config = params
+            .mask_config
+            .ok_or_else(|| ClientError::Deserialize("missing mask config".to_string()))?;
+        Ok(Self {
+            pk: byte_object::<PublicEncryptKey>(&params.pk, "coordinator public key")?,
+            sum: params.sum,
+            update: params.update,
+            seed: byte_object::<RoundSeed>(&params.seed, "round seed")?,
+            mask_config: MaskConfigPair {
+                vect: self::mask_config(mask_config.vect)?,
+                unit: self::mask_config(mask_config.unit)?,
+            },
+            model_length: params.model_length as usize,
+            seed_sharing_threshold: params.seed_sharing_threshold,
+            model_delta: params.model_delta,
+        })
+    }
+}
+
+impl TryFrom<proto::SumDict> for SumDict {
+    type Error = ClientError;
+
+    fn try_from(dict: proto::SumDict) -> Result<Self, Self::Error> {
+        dict.entries
+            .iter()
+            .map(|entry| {
+                Ok((
+                    byte_object::<PublicSigningKey>(&entry.pk, "sum participant public key")?,
+                    byte_object::<PublicEncryptKey>(&entry.ephm_pk, "ephemeral public key")?,
+                ))
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<proto::UpdateSeedDict> for UpdateSeedDict {
+    type Error = ClientError;
+
+    fn try_from(dict: proto::UpdateSeedDict) -> Result<Self, Self::Error> {
+        dict.entries
+            .iter()
+            .map(|entry| {
+                Ok((
+                    byte_object::<PublicSigningKey>(&entry.pk, "update participant public key")?,
+                    byte_object::<EncryptedMaskSeed>(&entry.seed, "encrypted mask seed")?,
+                ))
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<proto::Model> for Model {
+    type Error = ClientError;
+
+    fn try_from(model: proto::Model) -> Result<Self, Self::Error> {
+        use self::proto::model::Weights;
+
+        match model.weights {
+            Some(Weights::F32(weights)) => {
+                Model::from_primitives(weights.weights.into_iter()).map_err(|e| e.to_string())
+            }
+            Some(Weights::F64(weights)) => {
+                Model::from_primitives(weights.weights.into_iter()).map_err(|e| e.to_string())
+            }
+            Some(Weights::I32(weights)) => {
+                Model::from_primitives(weights.weights.into_iter()).map_err(|e| e.to_string())
+            }
+            Some(Weights::I64(weights)) => {
+                Model::from_primitives(weights.weights.into_iter()).map_err(|e| e.to_string())
+            }
+            None => Err("missing model weights".to_string()),
+        }
+        .map_err(ClientError::Deserialize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+
+    use super::*;
+
+    #[test]
+    fn test_message_error_from_status() {
+        let error = proto::MessageError {
+            kind: proto::message_error::Kind::Discarded as i32,
+            description: "the message was discarded".to_string(),
+        };
+        let mut details = Vec::new();
+        error.encode(&mut details).unwrap();
+        let status = Status::with_details(
+            Code::FailedPrecondition,
+            "the message was discarded",
+            details.into(),
+        );
+
+        match ClientError::from(status) {
+            ClientError::Message(error) => {
+                assert_eq!(error.kind, MessageErrorKind::Discarded);
+                assert_eq!(error.description, "the message was discarded");
+            }
+            error => panic!("unexpected error: {:?}", error),
+        }
+        assert!(matches!(
+            ClientError::from(Status::unavailable("connection refused")),
+            ClientError::Grpc(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_with_task() {
+        let channel = Channel::from_static("http://127.0.0.1:8082")
+            .connect_lazy()
+            .unwrap();
+        let client = GrpcClient::new(channel);
+        let request = client.request(proto::GetModelRequest {}).unwrap();
+        assert!(request.metadata().get("x-xaynet-task").is_none());
+
+        let client = client.with_task("keyboard");
+        let request = client.request(proto::GetModelRequest {}).unwrap();
+        assert_eq!(request.metadata().get("x-xaynet-task").unwrap(), "keyboard");
+    }
+
+    #[test]
+    fn test_model() {
+        let model = proto::Model {
+            weights: Some(proto::model::Weights::I32(proto::model::I32 {
+                weights: vec![1, -2, 3],
+            })),
+        };
+        assert_eq!(
+            Model::try_from(model).unwrap(),
+            Model::from_primitives(vec![1_i32, -2, 3].into_iter()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_custom_mask_config() {
+        let config = proto::MaskConfig {
+            group_type: 1,
+            data_type: 0,
+            bound_type: 254,
+            model_type: 3,
+            sparsity_type: 0,
+            quantization_type: 254,
+            bound: 300,
+            max_nb_models: 1_000,
+            decimal_places: 3,
+        };
+        assert_eq!(
+            mask_config(Some(config.clone())).unwrap(),
+            MaskConfig {
+                group_type: GroupType::Prime,
+                data_type: DataType::F32,
+                bound_type: BoundType::Custom(300),
+                model_type: ModelType::M3,
+                sparsity_type: SparsityType::S0,
+                quantization_type: QuantizationType::Custom(3),
+            },
+        );
+
+        let config = proto::MaskConfig { bound: 0, ..config };
+        assert!(matches!(
+            mask_config(Some(config)),
+            Err(ClientError::Deserialize(_)),
+        ));
+    }
+}
@@ -68,6 +68,27 @@
 //! }
 //! ```
 //!
+//! Instead of waiting for a fixed tick, an agent which uses the [`Client`] can subscribe to the
+//! round events pushed by the coordinator and only try again once something happened:
+//!
+//! ```rust,ignore
+//! use xaynet_sdk::{client::Client, StateMachine, TransitionOutcome};
+//!
+//! async fn run_agent(mut state_machine: StateMachine, client: Client<reqwest::Client>) {
+//!     let mut events = client.events().await.unwrap();
+//!     loop {
+//!         state_machine = match state_machine.transition().await {
+//!             // Wait for the coordinator to report some progress
+//!             TransitionOutcome::Pending(state_machine) => {
+//!                 events.next().await.unwrap();
+//!                 state_machine
+//!             }
+//!             TransitionOutcome::Complete(state_machine) => state_machine,
+//!         };
+//!     }
+//! }
+//! ```
+//!
 //! This agent needs to be fed a [`StateMachine`] in order to run. A
 //! state machine requires found components:
 //!
@@ -80,12 +101,15 @@
 //! - a client to talk with the Xaynet coordinator. This can be any
 //!   type that implements the [`XaynetClient`] trait. For this we're
 //!   going to use the [`Client`] that is available when compiling
-//!   with `--features reqwest-client`.
+//!   with `--features reqwest-client`. Alternatively, the
+//!   [`GrpcClient`] talks to the gRPC API of the coordinator when
+//!   compiling with `--features grpc-client`.
 //! - a notifier that the state machine can use to send
 //!   notifications. This can be any type that implements the
 //!   [`Notify`] trait. We'll use channels for this.
 //!
 //! [`Client`]: [crate::clients::Client]
+//! [`GrpcClient`]: [crate::grpc::GrpcClient]
 //!
 //! Finally we can start our agent and log the events it emits. Here
 //! is the full code:
@@ -100,7 +124,17 @@
 //! use tokio::time::delay_for;
 //! use xaynet_core::{
 //!     crypto::SigningKeyPair,
-//!     mask::{BoundType, DataType, FromPrimitives, GroupType, MaskConfig, Model, ModelType},
+//!     mask::{
+//!         BoundType,
+//!         DataType,
+//!         FromPrimitives,
+//!         GroupType,
+//!         MaskConfig,
+//!         Model,
+//!         ModelType,
+//!         QuantizationType,
+//!         SparsityType,
+//!     },
 //! };
 //! use xaynet_sdk::{
 //!     client::Client,
@@ -179,6 +213,8 @@
 //!         data_type: DataType::F32,
 //!         bound_type: BoundType::B0,
 //!         model_type: ModelType::M3,
+//!         sparsity_type: SparsityType::S0,
+//!         quantization_type: QuantizationType::Qmax,
 //!     };
 //!     let keys = SigningKeyPair::generate();
 //!     let settings = PetSettings::new(keys, mask_config);
@@ -202,6 +238,12 @@
 
 pub mod client;
 
+#[cfg(feature = "reqwest-client")]
+pub mod events;
+
+#[cfg(feature = "grpc-client")]
+pub mod grpc;
+
 mod message_encoder;
 pub(crate) use self::message_encoder::MessageEncoder;
 
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Invalid [`LocalPrivacy`] value
+#[derive(Debug, Error)]
+pub enum InvalidLocalPrivacy {
+    #[error("clipping norm must be positive and finite")]
+    ClippingNorm,
+    #[error("noise multiplier must be non-negative and finite")]
+    NoiseMultiplier,
+}
+
+/// Represent the local differential privacy settings of a
+/// participant. If set, the local model is clipped to an L2 norm of
+/// at most `clipping_norm` and Gaussian noise with a standard
+/// deviation of `clipping_norm * noise_multiplier` is added to each
+/// weight before the model is masked. Thus, the contribution of a
+/// participant to the global model is always bounded, regardless of
+/// the model it trained.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LocalPrivacy {
+    clipping_norm: f64,
+    noise_multiplier: f64,
+}
+
+impl LocalPrivacy {
+    /// Create new local differential privacy settings.
+    ///
+    /// # Errors
+    ///
+    /// This method returns an [`InvalidLocalPrivacy`] error if
+    /// `clipping_norm` is not positive or if `noise_multiplier` is
+    /// negative.
+    pub fn new(clipping_norm: f64, noise_multiplier: f64) -> Result<Self, InvalidLocalPrivacy> {
+        if !(clipping_norm.is_finite() && clipping_norm > 0.) {
+            return Err(InvalidLocalPrivacy::ClippingNorm);
+        }
+        if !(noise_multiplier.is_finite() && noise_multiplier >= 0.) {
+            return Err(InvalidLocalPrivacy::NoiseMultiplier);
+        }
+        Ok(Self {
+            clipping_norm,
+            noise_multiplier,
+        })
+    }
+
+    /// Get the maximum L2 norm of the local model.
+    pub fn clipping_norm(&self) -> f64 {
+        self.clipping_norm
+    }
+
+    /// Get the noise multiplier.
+    pub fn noise_multiplier(&self) -> f64 {
+        self.noise_multiplier
+    }
+
+    /// Get the standard deviation of the Gaussian noise which is
+    /// added to each weight.
+    pub fn noise_std_dev(&self) -> f64 {
+        self.clipping_norm * self.noise_multiplier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_privacy_new() {
+        let local_privacy = LocalPrivacy::new(2.0, 0.5).unwrap();
+        assert_eq!(local_privacy.clipping_norm(), 2.0);
+        assert_eq!(local_privacy.noise_multiplier(), 0.5);
+        assert_eq!(local_privacy.noise_std_dev(), 1.0);
+
+        assert!(LocalPrivacy::new(1.0, 0.0).is_ok());
+    }
+
+    #[test]
+    fn local_privacy_new_err() {
+        assert!(matches!(
+            LocalPrivacy::new(0.0, 1.0),
+            Err(InvalidLocalPrivacy::ClippingNorm)
+        ));
+        assert!(matches!(
+            LocalPrivacy::new(f64::INFINITY, 1.0),
+            Err(InvalidLocalPrivacy::ClippingNorm)
+        ));
+        assert!(matches!(
+            LocalPrivacy::new(1.0, -1.0),
+            Err(InvalidLocalPrivacy::NoiseMultiplier)
+        ));
+        assert!(matches!(
+            LocalPrivacy::new(1.0, f64::NAN),
+            Err(InvalidLocalPrivacy::NoiseMultiplier)
+        ));
+    }
+}
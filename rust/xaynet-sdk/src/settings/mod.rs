@@ -1,7 +1,9 @@
+mod local_privacy;
 mod max_message_size;
 
 use serde::{Deserialize, Serialize};
 
+pub use local_privacy::{InvalidLocalPrivacy, LocalPrivacy};
 pub use max_message_size::{InvalidMaxMessageSize, MaxMessageSize, MIN_MESSAGE_SIZE};
 use xaynet_core::crypto::SigningKeyPair;
 
@@ -10,6 +12,13 @@ pub struct PetSettings {
     pub keys: SigningKeyPair,
     pub scalar: f64,
     pub max_message_size: MaxMessageSize,
+    pub local_privacy: Option<LocalPrivacy>,
+    /// The name of the federated learning task in which the participant takes part, or `None`
+    /// for the default task of the coordinator. The client must be scoped to the same task, see
+    /// [`Client::with_task()`].
+    ///
+    /// [`Client::with_task()`]: crate::client::Client::with_task
+    pub task: Option<String>,
 }
 
 impl PetSettings {
@@ -18,6 +27,8 @@ impl PetSettings {
             keys,
             scalar: 1.0,
             max_message_size: MaxMessageSize::default(),
+            local_privacy: None,
+            task: None,
         }
     }
 }
@@ -0,0 +1,81 @@
+//! A delay which doesn't depend on an async runtime.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::Duration,
+};
+
+/// The state of a [`Delay`] which is shared with its timer thread.
+#[derive(Default)]
+struct Timer {
+    /// Whether the duration elapsed.
+    elapsed: bool,
+    /// The waker of the task which awaits the delay.
+    waker: Option<Waker>,
+}
+
+/// A future which completes after a duration.
+///
+/// The SDK can't rely on the timer of a specific async runtime, hence the duration is timed by a
+/// thread which is spawned when the delay is polled for the first time.
+pub(crate) struct Delay {
+    duration: Duration,
+    timer: Option<Arc<Mutex<Timer>>>,
+}
+
+impl Delay {
+    /// Creates a delay of the given duration.
+    pub(crate) fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            timer: None,
+        }
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let duration = self.duration;
+        let timer = self.timer.get_or_insert_with(|| {
+            let timer = Arc::new(Mutex::new(Timer::default()));
+            let thread_timer = timer.clone();
+            thread::spawn(move || {
+                thread::sleep(duration);
+                let mut timer = thread_timer.lock().unwrap();
+                timer.elapsed = true;
+                if let Some(waker) = timer.waker.take() {
+                    waker.wake();
+                }
+            });
+            timer
+        });
+
+        let mut timer = timer.lock().unwrap();
+        if timer.elapsed {
+            Poll::Ready(())
+        } else {
+            timer.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_delay() {
+        let start = Instant::now();
+        Delay::new(Duration::from_millis(50)).await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -59,6 +59,8 @@ pub(crate) trait IO: Send + 'static {
         &mut self,
         pk: SumParticipantPublicKey,
     ) -> Result<Option<UpdateSeedDict>, Box<dyn Error>>;
+    /// Fetch the sum participants who dropped out from the coordinator
+    async fn get_dropouts(&mut self) -> Result<Option<SumDict>, Box<dyn Error>>;
     /// Fetch the latest global model from the coordinator
     async fn get_model(&mut self) -> Result<Option<Model>, Box<dyn Error>>;
     /// Send the given signed and encrypted PET message to the coordinator
@@ -140,6 +142,13 @@ where
             .map_err(|e| Box::new(e) as Box<dyn Error>)
     }
 
+    async fn get_dropouts(&mut self) -> Result<Option<SumDict>, Box<dyn Error>> {
+        self.xaynet_client
+            .get_dropouts()
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+
     async fn get_model(&mut self) -> Result<Option<Model>, Box<dyn Error>> {
         self.xaynet_client
             .get_model()
@@ -198,6 +207,10 @@ impl IO for Box<dyn IO<Model = Box<dyn AsRef<Model> + Send>>> {
         self.as_mut().get_seeds(pk).await
     }
 
+    async fn get_dropouts(&mut self) -> Result<Option<SumDict>, Box<dyn Error>> {
+        self.as_mut().get_dropouts().await
+    }
+
     async fn get_model(&mut self) -> Result<Option<Model>, Box<dyn Error>> {
         self.as_mut().get_model().await
     }
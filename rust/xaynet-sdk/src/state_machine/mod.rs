@@ -2,6 +2,7 @@
 // macro to be used in the other modules (until declarative macros are stable)
 #[macro_use]
 mod phase;
+mod delay;
 mod io;
 mod phases;
 #[allow(clippy::module_inception)]
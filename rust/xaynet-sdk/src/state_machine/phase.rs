@@ -1,17 +1,20 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use derive_more::From;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{debug, error, info, warn};
 
-use super::{Awaiting, NewRound, Sum, Sum2, Update, IO};
+use super::{delay::Delay, Awaiting, NewRound, Sum, Sum2, Update, IO};
 use crate::{
-    settings::{MaxMessageSize, PetSettings},
+    client::ClientError,
+    settings::{LocalPrivacy, MaxMessageSize, PetSettings},
     state_machine::{StateMachine, TransitionOutcome},
     MessageEncoder,
 };
 use xaynet_core::{
-    common::{RoundParameters, RoundSeed},
+    common::{MessageError, RoundParameters, RoundSeed},
     crypto::{ByteObject, PublicEncryptKey, SigningKeyPair},
     mask::{self, DataType, MaskConfig, Model},
     message::Payload,
@@ -68,6 +71,9 @@ pub struct SharedState {
     /// Maximum message size the participant can send. Messages larger
     /// than `message_size` are split in several parts.
     pub message_size: MaxMessageSize,
+    /// Local differential privacy settings. If set, the local model
+    /// is clipped and noised before it is masked.
+    pub local_privacy: Option<LocalPrivacy>,
     /// Current round parameters
     pub round_params: RoundParameters,
 }
@@ -87,9 +93,13 @@ fn dummy_round_parameters() -> RoundParameters {
             data_type: mask::DataType::F32,
             bound_type: mask::BoundType::B0,
             model_type: mask::ModelType::M3,
+            sparsity_type: mask::SparsityType::S0,
+            quantization_type: mask::QuantizationType::Qmax,
         }
         .into(),
         model_length: 0,
+        seed_sharing_threshold: None,
+        model_delta: false,
     }
 }
 
@@ -99,6 +109,7 @@ impl SharedState {
             keys: settings.keys,
             scalar: settings.scalar,
             message_size: settings.max_message_size,
+            local_privacy: settings.local_privacy,
             round_params: dummy_round_parameters(),
         }
     }
@@ -226,15 +237,38 @@ impl<P> Phase<P> {
     /// Send the message created by the given message encoder.
     ///
     /// If the message is split in multiple parts, they are sent sequentially. If a
-    /// single part fails, the remaining parts are not sent. There is no retry
-    /// mechanism.
+    /// single part fails, the remaining parts are not sent. A part is only sent
+    /// again if the coordinator failed to handle it due to an internal error or an
+    /// exceeded rate limit, up to [`MAX_SEND_ATTEMPTS`] times. The first retry is
+    /// delayed by [`SEND_RETRY_DELAY`], which doubles with every further retry. Any
+    /// other rejection by the coordinator is final.
     pub async fn send_message(&mut self, encoder: MessageEncoder) -> Result<(), SendMessageError> {
         for part in encoder {
             let data = self.state.shared.round_params.pk.encrypt(part.as_slice());
-            self.io.send_message(data).await.map_err(|e| {
-                error!("failed to send message: {:?}", e);
-                SendMessageError
-            })?
+            let mut attempts = 0;
+            let mut delay = SEND_RETRY_DELAY;
+            loop {
+                attempts += 1;
+                // the error must be dropped before the delay, because it isn't `Send`
+                match self.io.send_message(data.clone()).await {
+                    Ok(()) => break,
+                    Err(e) => {
+                        error!("failed to send message: {:?}", e);
+                        match e.downcast::<ClientError>().map(|e| *e) {
+                            Ok(ClientError::Message(error))
+                                if !error.kind.is_retryable() || attempts >= MAX_SEND_ATTEMPTS =>
+                            {
+                                return Err(SendMessageError::Rejected(error));
+                            }
+                            Ok(ClientError::Message(_)) => {}
+                            _ => return Err(SendMessageError::Failed),
+                        }
+                    }
+                }
+                warn!("sending the message again in {:?}", delay);
+                Delay::new(delay).await;
+                delay *= 2;
+            }
         }
         Ok(())
     }
@@ -299,9 +333,20 @@ pub struct LocalModelConfig {
     pub len: usize,
 }
 
+/// The maximum number of attempts to send a message part which the coordinator failed to
+/// handle due to an internal error or an exceeded rate limit.
+pub const MAX_SEND_ATTEMPTS: usize = 3;
+
+/// The delay before a message part is sent again for the first time.
+pub const SEND_RETRY_DELAY: Duration = Duration::from_secs(1);
+
 #[derive(Error, Debug)]
-#[error("failed to send a PET message")]
-pub struct SendMessageError;
+pub enum SendMessageError {
+    #[error("the coordinator rejected the PET message: {0}")]
+    Rejected(MessageError),
+    #[error("failed to send a PET message")]
+    Failed,
+}
 
 /// Round freshness indicator
 pub enum RoundFreshness {
@@ -4,4 +4,5 @@ mod sum;
 mod sum2;
 mod update;
 
+pub(crate) use self::update::{privatize_model, share_seed, subtract_global_model};
 pub use self::{awaiting::Awaiting, new_round::NewRound, sum::Sum, sum2::Sum2, update::Update};
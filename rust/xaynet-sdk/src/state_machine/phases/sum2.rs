@@ -1,10 +1,16 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 use xaynet_core::{
     crypto::{EncryptKeyPair, Signature},
-    mask::{Aggregation, MaskObject, MaskSeed},
-    message::Sum2 as Sum2Message,
+    mask::{share_indices, Aggregation, MaskObject, MaskSeed},
+    message::{Sum2 as Sum2Message, Sum2Shares},
+    SeedShareDict,
+    SumDict,
+    SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
     UpdateSeedDict,
 };
 
@@ -13,6 +19,9 @@ use crate::{
     MessageEncoder,
 };
 
+/// The decrypted mask seeds and seed shares of every update participant.
+pub type SeedShares = HashMap<UpdateParticipantPublicKey, Vec<MaskSeed>>;
+
 /// Sum2 phase data
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Sum2 {
@@ -25,11 +34,22 @@ pub struct Sum2 {
     /// Dictionary containing the encrypted mask seed of every update
     /// participants.
     pub seed_dict: Option<UpdateSeedDict>,
+    /// The sum dictionary, if the mask seeds are secret-shared. It
+    /// assigns the share indices to the sum participants.
+    pub sum_dict: Option<SumDict>,
     /// The decrypted mask seeds
     pub seeds: Option<Vec<MaskSeed>>,
+    /// The decrypted mask seeds and seed shares of every update
+    /// participant, if the mask seeds are secret-shared. They are
+    /// ordered by the share indices of the sum participants.
+    pub seed_shares: Option<SeedShares>,
     /// The global mask, obtained by aggregating the masks derived
     /// from the mask seeds.
     pub mask: Option<MaskObject>,
+    /// Whether the sum2 message with the mask has been sent. If the
+    /// mask seeds are secret-shared, the participant then waits for
+    /// the sum participants who dropped out.
+    pub sent_mask: bool,
     /// Final sum2 message to send to the coordinator
     pub message: Option<MessageEncoder>,
 }
@@ -40,8 +60,11 @@ impl Sum2 {
             ephm_keys,
             sum_signature,
             seed_dict: None,
+            sum_dict: None,
             seeds: None,
+            seed_shares: None,
             mask: None,
+            sent_mask: false,
             message: None,
         }
     }
@@ -59,7 +82,7 @@ impl Sum2 {
     }
 
     fn has_composed_message(&self) -> bool {
-        self.message.is_some()
+        self.message.is_some() || self.sent_mask
     }
 }
 
@@ -92,7 +115,37 @@ impl Phase<Sum2> {
         }
     }
 
+    /// Retrieve the sum dictionary if the mask seeds are secret-shared.
+    pub(crate) async fn fetch_sum_dict(mut self) -> Progress<Sum2> {
+        if !self.is_shared()
+            || self.state.private.sum_dict.is_some()
+            || self.state.private.has_decrypted_seeds()
+        {
+            return Progress::Continue(self);
+        }
+        debug!("polling for sum dict");
+        match self.io.get_sums().await {
+            Err(e) => {
+                warn!("failed to fetch sum dict: {}", e);
+                Progress::Stuck(self)
+            }
+            Ok(None) => {
+                debug!("sum dict not available yet");
+                Progress::Stuck(self)
+            }
+            Ok(Some(dict)) => {
+                self.state.private.sum_dict = Some(dict);
+                Progress::Updated(self.into())
+            }
+        }
+    }
+
     /// Decrypt the mask seeds that the update participants generated.
+    ///
+    /// If the mask seeds are secret-shared, every update participant
+    /// generated a mask seed and seed shares for each sum
+    /// participant. The mask seeds at our share index are ours, the
+    /// others are kept in case other sum participants drop out.
     pub(crate) fn decrypt_seeds(mut self) -> Progress<Sum2> {
         if self.state.private.has_decrypted_seeds() {
             return Progress::Continue(self);
@@ -101,16 +154,37 @@ impl Phase<Sum2> {
         let keys = &self.state.private.ephm_keys;
         // UNWRAP_SAFE: the seed dict is set in
         // `self.fetch_seed_dict()` which is called before this method
-        let seeds: Result<Vec<MaskSeed>, ()> = self
+        let seeds: Result<HashMap<_, _>, ()> = self
             .state
             .private
             .seed_dict
             .take()
             .unwrap()
             .into_iter()
-            .map(|(_, seed)| seed.decrypt(&keys.public, &keys.secret).map_err(|_| ()))
+            .map(|(pk, seed)| {
+                seed.decrypt_all(&keys.public, &keys.secret)
+                    .map(|seeds| (pk, seeds))
+                    .map_err(|_| ())
+            })
             .collect();
 
+        let pk = &self.state.shared.keys.public;
+        let seeds = match (seeds, self.state.private.sum_dict.as_ref()) {
+            (Ok(seeds), Some(sum_dict)) => {
+                own_seeds(pk, sum_dict, seeds).map(|(own_seeds, seed_shares)| {
+                    self.state.private.seed_shares = Some(seed_shares);
+                    own_seeds
+                })
+            }
+            (Ok(seeds), None) => seeds
+                .into_iter()
+                .map(|(_, mut seeds)| match seeds.len() {
+                    1 => seeds.pop().ok_or(()),
+                    _ => Err(()),
+                })
+                .collect(),
+            (Err(_), _) => Err(()),
+        };
         match seeds {
             Ok(seeds) => {
                 self.state.private.seeds = Some(seeds);
@@ -134,7 +208,7 @@ impl Phase<Sum2> {
 
         info!("aggregating masks");
         let config = self.state.shared.round_params.mask_config;
-        let mask_len = self.state.shared.round_params.model_length;
+        let mask_len = self.state.shared.round_params.mask_length();
         let mut mask_agg = Aggregation::new(config, mask_len as usize);
         // UNWRAP_SAFE: the seeds are set in `self.decrypt_seeds()`
         // which is called before this method
@@ -167,6 +241,113 @@ impl Phase<Sum2> {
         self.state.private.message = Some(self.message_encoder(sum2.into()));
         Progress::Updated(self.into())
     }
+
+    /// Retrieve the sum participants who dropped out, if the mask
+    /// seeds are secret-shared. If none dropped out or if we are
+    /// considered to have dropped out, the phase is over.
+    pub(crate) async fn fetch_dropout_dict(mut self) -> Progress<Sum2> {
+        if self.state.private.has_composed_shares_message() {
+            return Progress::Continue(self);
+        }
+        debug!("polling for dropout dict");
+        let dropout_dict = match self.io.get_dropouts().await {
+            Err(e) => {
+                warn!("failed to fetch dropout dict: {}", e);
+                return Progress::Stuck(self);
+            }
+            Ok(None) => {
+                debug!("dropout dict not available yet");
+                return Progress::Stuck(self);
+            }
+            Ok(Some(dict)) => dict,
+        };
+
+        if dropout_dict.is_empty() {
+            info!("no sum participants dropped out, going back to awaiting phase");
+            return Progress::Updated(self.into_awaiting().into());
+        }
+        if dropout_dict.contains_key(&self.state.shared.keys.public) {
+            warn!("the coordinator considers us dropped out, going back to awaiting phase");
+            return Progress::Updated(self.into_awaiting().into());
+        }
+        match self.compose_sum2_shares_message(&dropout_dict) {
+            Some(message) => {
+                self.state.private.message = Some(message);
+                Progress::Updated(self.into())
+            }
+            None => {
+                error!("sum2 phase failed: cannot provide the seed shares of the dropouts");
+                error!("going to awaiting phase");
+                Progress::Updated(self.into_awaiting().into())
+            }
+        }
+    }
+
+    /// Build the message with our seed shares of the sum participants
+    /// who dropped out.
+    fn compose_sum2_shares_message(&mut self, dropout_dict: &SumDict) -> Option<MessageEncoder> {
+        let sum_dict = self.state.private.sum_dict.as_ref()?;
+        let seed_shares = self.state.private.seed_shares.take()?;
+        let indices = share_indices(sum_dict).ok()?;
+        let seed_shares = dropout_dict
+            .keys()
+            .map(|pk| {
+                // the share indices are 1-based
+                let index = *indices.get(pk)? as usize - 1;
+                let shares = seed_shares
+                    .iter()
+                    .map(|(update_pk, seeds)| (*update_pk, seeds[index].clone()))
+                    .collect();
+                Some((*pk, shares))
+            })
+            .collect::<Option<SeedShareDict>>()?;
+        info!(
+            "sending the seed shares of {} sum participants who dropped out",
+            seed_shares.len()
+        );
+
+        let sum2_shares = Sum2Shares {
+            sum_signature: self.state.private.sum_signature,
+            seed_shares,
+        };
+        Some(self.message_encoder(sum2_shares.into()))
+    }
+
+    fn is_shared(&self) -> bool {
+        self.state
+            .shared
+            .round_params
+            .seed_sharing_threshold
+            .is_some()
+    }
+}
+
+/// Picks the mask seeds of the sum participant `pk` from the decrypted
+/// mask seeds and seed shares of every update participant.
+fn own_seeds(
+    pk: &SumParticipantPublicKey,
+    sum_dict: &SumDict,
+    seeds: SeedShares,
+) -> Result<(Vec<MaskSeed>, SeedShares), ()> {
+    let index = share_indices(sum_dict)
+        .ok()
+        .and_then(|indices| indices.get(pk).copied())
+        .ok_or(())?;
+    if seeds.values().any(|seeds| seeds.len() != sum_dict.len()) {
+        return Err(());
+    }
+    // the share indices are 1-based
+    let own_seeds = seeds
+        .values()
+        .map(|seeds| seeds[index as usize - 1].clone())
+        .collect();
+    Ok((own_seeds, seeds))
+}
+
+impl Sum2 {
+    fn has_composed_shares_message(&self) -> bool {
+        self.sent_mask && self.message.is_some()
+    }
 }
 
 #[async_trait]
@@ -174,19 +355,44 @@ impl Step for Phase<Sum2> {
     async fn step(mut self) -> TransitionOutcome {
         info!("sum2 task");
         self = try_progress!(self.fetch_seed_dict().await);
+        self = try_progress!(self.fetch_sum_dict().await);
         self = try_progress!(self.decrypt_seeds());
         self = try_progress!(self.aggregate_masks());
         self = try_progress!(self.compose_sum2_message());
 
-        // FIXME: currently if sending fails, we lose the message,
-        // thus wasting all the work we've done in this phase
+        if !self.state.private.sent_mask {
+            // FIXME: currently if sending fails, we lose the message,
+            // thus wasting all the work we've done in this phase
+            let message = self.state.private.message.take().unwrap();
+            match self.send_message(message).await {
+                Ok(_) => {
+                    info!("sent sum2 message");
+                    // if the mask seeds are secret-shared, the seed
+                    // shares of the sum participants who drop out are
+                    // still needed
+                    if self.is_shared() {
+                        self.state.private.sent_mask = true;
+                        return TransitionOutcome::Complete(self.into());
+                    }
+                }
+                Err(e) => {
+                    warn!("failed to send sum2 message: {}", e);
+                    warn!("sum2 phase failed");
+                }
+            }
+            info!("going back to awaiting phase");
+            return TransitionOutcome::Complete(self.into_awaiting().into());
+        }
+
+        self = try_progress!(self.fetch_dropout_dict().await);
+
         let message = self.state.private.message.take().unwrap();
         match self.send_message(message).await {
             Ok(_) => {
-                info!("sent sum2 message");
+                info!("sent sum2 shares message");
             }
             Err(e) => {
-                warn!("failed to send sum2 message: {}", e);
+                warn!("failed to send sum2 shares message: {}", e);
                 warn!("sum2 phase failed");
             }
         }
@@ -2,12 +2,30 @@ use std::ops::Deref;
 
 use async_trait::async_trait;
 use derive_more::From;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use tracing::{debug, info, warn};
 
 use xaynet_core::{
     crypto::Signature,
-    mask::{MaskObject, MaskSeed, Masker, Model},
+    mask::{
+        sample_gaussian,
+        share_indices,
+        share_mask_seed,
+        sharing_threshold,
+        Aggregation,
+        EncryptedMaskSeed,
+        FromPrimitives,
+        IntoPrimitives,
+        MaskConfigPair,
+        MaskObject,
+        MaskSeed,
+        Masker,
+        Model,
+        ModelCastError,
+        SharingError,
+    },
     message::Update as UpdateMessage,
     LocalSeedDict,
     ParticipantTaskSignature,
@@ -15,10 +33,116 @@ use xaynet_core::{
 };
 
 use crate::{
+    settings::LocalPrivacy,
     state_machine::{IntoPhase, Phase, PhaseIo, Progress, State, Step, TransitionOutcome, IO},
     MessageEncoder,
 };
 
+/// Error that occurs while applying local differential privacy to a
+/// model.
+#[derive(Debug, Error)]
+pub enum LocalPrivacyError {
+    #[error("failed to convert the model: {0}")]
+    ModelCast(#[from] ModelCastError),
+    #[error("the model contains non-finite weights")]
+    NonFiniteWeights,
+}
+
+/// Clip the model to the L2 norm and add Gaussian noise to each of
+/// its weights, as configured in the local privacy settings.
+pub(crate) fn privatize_model<R: Rng>(
+    settings: &LocalPrivacy,
+    model: &Model,
+    rng: &mut R,
+) -> Result<Model, LocalPrivacyError> {
+    let weights = model.to_primitives().collect::<Result<Vec<f64>, _>>()?;
+
+    let norm = weights
+        .iter()
+        .map(|weight| weight * weight)
+        .sum::<f64>()
+        .sqrt();
+    if !norm.is_finite() {
+        return Err(LocalPrivacyError::NonFiniteWeights);
+    }
+    let clipping_factor = if norm > settings.clipping_norm() {
+        settings.clipping_norm() / norm
+    } else {
+        1.0
+    };
+
+    let std_dev = settings.noise_std_dev();
+    let weights = weights.into_iter().map(|weight| {
+        let noise = if std_dev > 0.0 {
+            sample_gaussian(rng, std_dev)
+        } else {
+            0.0
+        };
+        weight * clipping_factor + noise
+    });
+    Model::from_primitives(weights).map_err(|_| LocalPrivacyError::NonFiniteWeights)
+}
+
+/// Subtract the global model of the previous round from the local
+/// model. A missing global model or one of another length counts as a
+/// model of zeros, just like for the coordinator.
+pub(crate) fn subtract_global_model(model: &Model, global_model: Option<&Model>) -> Model {
+    match global_model {
+        Some(global_model) if global_model.len() == model.len() => model
+            .iter()
+            .zip(global_model.iter())
+            .map(|(weight, global_weight)| weight - global_weight)
+            .collect(),
+        Some(global_model) => {
+            warn!(
+                "ignoring the global model of length {} instead of {}",
+                global_model.len(),
+                model.len()
+            );
+            model.clone()
+        }
+        None => model.clone(),
+    }
+}
+
+/// Split the masking among the sum participants and encrypt the mask
+/// seed and the seed shares of each sum participant with its ephemeral
+/// public key. The masks derived from the additional mask seeds of the
+/// sum participants are added to the masked model. The seeds are
+/// assigned to the sum participants via their share indices.
+pub(crate) fn share_seed(
+    mask_seed: MaskSeed,
+    masked_model: MaskObject,
+    sum_dict: &SumDict,
+    fraction: f64,
+) -> Result<(MaskObject, LocalSeedDict), SharingError> {
+    let indices = share_indices(sum_dict)?;
+    let threshold = sharing_threshold(fraction, sum_dict.len());
+    let seeds = share_mask_seed(mask_seed, threshold, sum_dict.len())?;
+
+    // the first mask seed is the one the model is already masked with
+    let config = MaskConfigPair {
+        vect: masked_model.vect.config,
+        unit: masked_model.unit.config,
+    };
+    let len = masked_model.vect.data.len();
+    let mut masked_model = Aggregation::from(masked_model);
+    for (i, seeds) in seeds.iter().enumerate().skip(1) {
+        masked_model.aggregate(seeds[i].derive_mask(len, config));
+    }
+
+    let seed_dict = sum_dict
+        .iter()
+        .map(|(pk, ephm_pk)| {
+            // the share indices are 1-based
+            let seeds = &seeds[indices[pk] as usize - 1];
+            let encrypted = EncryptedMaskSeed::concat(seeds.iter().map(|seed| seed.encrypt(ephm_pk)));
+            (*pk, encrypted)
+        })
+        .collect();
+    Ok((masked_model.into(), seed_dict))
+}
+
 #[derive(From)]
 pub enum LocalModel {
     Dyn(Box<dyn AsRef<Model> + Send>),
@@ -74,6 +198,9 @@ pub struct Update {
     pub sum_dict: Option<SumDict>,
     pub seed_dict: Option<LocalSeedDict>,
     pub model: Option<LocalModel>,
+    /// The difference between the local model and the global model,
+    /// if the round parameters ask for it.
+    pub model_delta: Option<Model>,
     pub mask: Option<(MaskSeed, MaskObject)>,
     pub message: Option<MessageEncoder>,
 }
@@ -86,6 +213,7 @@ impl Update {
             sum_dict: None,
             seed_dict: None,
             model: None,
+            model_delta: None,
             mask: None,
             message: None,
         }
@@ -96,7 +224,11 @@ impl Update {
     }
 
     fn has_loaded_model(&self) -> bool {
-        self.model.is_some() || self.has_masked_model()
+        self.model.is_some() || self.has_computed_model_delta()
+    }
+
+    fn has_computed_model_delta(&self) -> bool {
+        self.model_delta.is_some() || self.has_masked_model()
     }
 
     fn has_masked_model(&self) -> bool {
@@ -127,6 +259,7 @@ impl Step for Phase<Update> {
     async fn step(mut self) -> TransitionOutcome {
         self = try_progress!(self.fetch_sum_dict().await);
         self = try_progress!(self.load_model().await);
+        self = try_progress!(self.compute_model_delta().await);
         self = try_progress!(self.mask_model());
         self = try_progress!(self.build_seed_dict());
         self = try_progress!(self.compose_update_message());
@@ -198,6 +331,32 @@ impl Phase<Update> {
         }
     }
 
+    /// Fetch the global model and subtract it from the local model, if
+    /// the round parameters ask for the model delta.
+    pub(crate) async fn compute_model_delta(mut self) -> Progress<Update> {
+        if !self.state.shared.round_params.model_delta
+            || self.state.private.has_computed_model_delta()
+        {
+            return Progress::Continue(self);
+        }
+
+        debug!("fetching global model");
+        let global_model = match self.io.get_model().await {
+            Ok(global_model) => global_model,
+            Err(e) => {
+                warn!("failed to fetch global model: {:?}", e);
+                return Progress::Stuck(self);
+            }
+        };
+        info!("computing model delta");
+        // UNWRAP_SAFE: the model is set in `self.load_model()` which
+        // is called before this method
+        let model = self.state.private.model.take().unwrap();
+        let model_delta = subtract_global_model(model.as_ref(), global_model.as_ref());
+        self.state.private.model_delta = Some(model_delta);
+        Progress::Updated(self.into())
+    }
+
     /// Generate a mask seed and mask a local model.
     pub(crate) fn mask_model(mut self) -> Progress<Update> {
         if self.state.private.has_masked_model() {
@@ -207,9 +366,36 @@ impl Phase<Update> {
         info!("computing masked model");
         let config = self.state.shared.round_params.mask_config;
         let masker = Masker::new(config);
-        // UNWRAP_SAFE: the model is set, per the `has_masked_model()`
-        // check above
-        let model = self.state.private.model.take().unwrap();
+        // UNWRAP_SAFE: either the model or the model delta is set,
+        // per the `has_masked_model()` check above
+        let model = match self.state.private.model_delta.take() {
+            Some(model_delta) => LocalModel::Owned(model_delta),
+            None => self.state.private.model.take().unwrap(),
+        };
+        let model = match self.state.shared.local_privacy {
+            Some(ref local_privacy) => {
+                debug!("applying local differential privacy");
+                match privatize_model(local_privacy, model.as_ref(), &mut rand::thread_rng()) {
+                    Ok(model) => LocalModel::Owned(model),
+                    Err(e) => {
+                        // never send a model which isn't protected as configured
+                        warn!("failed to apply local differential privacy: {}", e);
+                        warn!("update phase failed");
+                        info!("going back to awaiting phase");
+                        return Progress::Updated(self.into_awaiting().into());
+                    }
+                }
+            }
+            None => model,
+        };
+        // only the weights selected for the round are masked for sparse masking configurations
+        let model = match self.state.shared.round_params.mask_selection() {
+            Some(selection) => {
+                debug!("selecting {} model weights to be masked", selection.len());
+                LocalModel::Owned(selection.select(model.as_ref()))
+            }
+            None => model,
+        };
         let scalar = self.state.shared.scalar;
         self.state.private.mask = Some(masker.mask(scalar, model.as_ref()));
         Progress::Updated(self.into())
@@ -223,17 +409,36 @@ impl Phase<Update> {
         }
         // UNWRAP_SAFE: the mask is set `self.mask_model()` which is
         // called before this method.
-        let mask_seed = &self.state.private.mask.as_ref().unwrap().0;
-        info!("building local seed dictionary");
-        let seeds = self
-            .state
-            .private
-            .sum_dict
-            .take()
-            .unwrap()
-            .into_iter()
-            .map(|(pk, ephm_pk)| (pk, mask_seed.encrypt(&ephm_pk)))
-            .collect();
+        let (mask_seed, masked_model) = self.state.private.mask.take().unwrap();
+        // UNWRAP_SAFE: the sum dict is set in
+        // `self.fetch_sum_dict()` which is called before this method
+        let sum_dict = self.state.private.sum_dict.take().unwrap();
+        let seeds = match self.state.shared.round_params.seed_sharing_threshold {
+            Some(fraction) => {
+                info!("building local seed dictionary with seed shares");
+                match share_seed(mask_seed.clone(), masked_model, &sum_dict, fraction) {
+                    Ok((masked_model, seeds)) => {
+                        self.state.private.mask = Some((mask_seed, masked_model));
+                        seeds
+                    }
+                    Err(e) => {
+                        warn!("failed to share the mask seed: {}", e);
+                        warn!("update phase failed");
+                        info!("going back to awaiting phase");
+                        return Progress::Updated(self.into_awaiting().into());
+                    }
+                }
+            }
+            None => {
+                info!("building local seed dictionary");
+                let seeds = sum_dict
+                    .into_iter()
+                    .map(|(pk, ephm_pk)| (pk, mask_seed.encrypt(&ephm_pk)))
+                    .collect();
+                self.state.private.mask = Some((mask_seed, masked_model));
+                seeds
+            }
+        };
         self.state.private.seed_dict = Some(seeds);
         Progress::Updated(self.into())
     }
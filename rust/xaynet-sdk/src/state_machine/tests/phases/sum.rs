@@ -1,8 +1,15 @@
+use std::time::Instant;
+
 use thiserror::Error;
-use xaynet_core::crypto::{ByteObject, EncryptKeyPair, EncryptKeySeed};
+use xaynet_core::{
+    common::{MessageError, MessageErrorKind},
+    crypto::{ByteObject, EncryptKeyPair, EncryptKeySeed},
+};
 
 use crate::{
+    client::ClientError,
     state_machine::{
+        phase::SEND_RETRY_DELAY,
         tests::utils::{shared_state, SelectFor},
         IntoPhase,
         MockIO,
@@ -78,3 +85,64 @@ async fn test_send_sum_message_fails() {
 
     let _phase = unwrap_step!(phase, complete, awaiting);
 }
+
+fn message_error(kind: MessageErrorKind) -> Box<dyn std::error::Error> {
+    Box::new(ClientError::Message(MessageError {
+        kind,
+        description: String::new(),
+    }))
+}
+
+#[tokio::test]
+async fn test_send_sum_message_is_retried_after_internal_error() {
+    let mut phase = check_step_1().await;
+
+    let mut io = MockIO::new();
+    let mut attempts = 0;
+    io.expect_send_message().times(2).returning(move |_| {
+        attempts += 1;
+        if attempts == 1 {
+            Err(message_error(MessageErrorKind::Internal))
+        } else {
+            Ok(())
+        }
+    });
+    let _ = std::mem::replace(&mut phase.io, Box::new(io));
+
+    let _phase = unwrap_step!(phase, complete, sum2);
+}
+
+#[tokio::test]
+async fn test_send_sum_message_is_retried_with_delay_after_rate_limit() {
+    let mut phase = check_step_1().await;
+
+    let mut io = MockIO::new();
+    let mut attempts = 0;
+    io.expect_send_message().times(3).returning(move |_| {
+        attempts += 1;
+        if attempts < 3 {
+            Err(message_error(MessageErrorKind::RateLimited))
+        } else {
+            Ok(())
+        }
+    });
+    let _ = std::mem::replace(&mut phase.io, Box::new(io));
+
+    let start = Instant::now();
+    let _phase = unwrap_step!(phase, complete, sum2);
+    assert!(start.elapsed() >= SEND_RETRY_DELAY * 3);
+}
+
+#[tokio::test]
+async fn test_send_sum_message_is_not_retried_after_rejection() {
+    let mut phase = check_step_1().await;
+
+    let mut io = MockIO::new();
+    io.expect_send_message()
+        .times(1)
+        .returning(|_| Err(message_error(MessageErrorKind::NotEligible)));
+    io.expect_notify_idle().times(1).return_const(());
+    let _ = std::mem::replace(&mut phase.io, Box::new(io));
+
+    let _phase = unwrap_step!(phase, complete, awaiting);
+}
@@ -1,13 +1,24 @@
 use mockall::Sequence;
 use xaynet_core::{
-    crypto::{ByteObject, EncryptKeyPair, EncryptKeySeed, PublicEncryptKey},
-    mask::{FromPrimitives, MaskConfigPair, MaskObject, MaskSeed, Masker, Model},
+    crypto::{ByteObject, EncryptKeyPair, EncryptKeySeed, PublicEncryptKey, PublicSigningKey},
+    mask::{
+        share_indices,
+        share_mask_seed,
+        EncryptedMaskSeed,
+        FromPrimitives,
+        MaskConfigPair,
+        MaskObject,
+        MaskSeed,
+        Masker,
+        Model,
+    },
+    SumDict,
     UpdateSeedDict,
 };
 
 use crate::{
     state_machine::{
-        tests::utils::{shared_state, SelectFor, SigningKeyGenerator},
+        tests::utils::{shared_state, EncryptKeyGenerator, SelectFor, SigningKeyGenerator},
         Awaiting,
         IntoPhase,
         MockIO,
@@ -22,7 +33,17 @@ use crate::{
 
 /// Instantiate a sum phase.
 fn make_phase() -> Phase<Sum2> {
-    let shared = shared_state(SelectFor::Sum);
+    make_phase_with_shared_state(shared_state(SelectFor::Sum))
+}
+
+/// Instantiate a sum phase where the mask seeds are secret-shared.
+fn make_phase_with_seed_sharing() -> Phase<Sum2> {
+    let mut shared = shared_state(SelectFor::Sum);
+    shared.round_params.seed_sharing_threshold = Some(0.5);
+    make_phase_with_shared_state(shared)
+}
+
+fn make_phase_with_shared_state(shared: Box<SharedState>) -> Phase<Sum2> {
     let sum2 = make_sum2(&shared);
 
     // Check IntoPhase<Sum2> implementation
@@ -42,12 +63,48 @@ fn make_sum2(shared: &SharedState) -> Box<Sum2> {
         ephm_keys,
         sum_signature: signature,
         seed_dict: None,
+        sum_dict: None,
         seeds: None,
+        seed_shares: None,
         mask: None,
+        sent_mask: false,
         message: None,
     })
 }
 
+/// Make a sum dictionary of three sum participants, including us.
+fn make_sum_dict(shared: &SharedState, ephm_pk: PublicEncryptKey) -> SumDict {
+    let mut signing_keys = SigningKeyGenerator::new();
+    let mut encrypt_keys = EncryptKeyGenerator::new();
+    let mut dict = SumDict::new();
+    dict.insert(shared.keys.public, ephm_pk);
+    while dict.len() < 3 {
+        dict.insert(signing_keys.next().public, encrypt_keys.next().public);
+    }
+    dict
+}
+
+/// Make a seed dictionary where the mask seeds are secret-shared
+/// among the sum participants of the sum dictionary.
+fn make_shared_seed_dict(
+    mask_config: MaskConfigPair,
+    sum_dict: &SumDict,
+    pk: &PublicSigningKey,
+    ephm_pk: PublicEncryptKey,
+) -> UpdateSeedDict {
+    let index = share_indices(sum_dict).unwrap()[pk] as usize - 1;
+    let mut key_gen = SigningKeyGenerator::new();
+    let mut dict = UpdateSeedDict::new();
+    for _ in 0..4 {
+        let (seed, _mask) = make_masked_model(mask_config);
+        let seeds = share_mask_seed(seed, 2, sum_dict.len()).unwrap();
+        let encrypted =
+            EncryptedMaskSeed::concat(seeds[index].iter().map(|seed| seed.encrypt(&ephm_pk)));
+        dict.insert(key_gen.next().public, encrypted);
+    }
+    dict
+}
+
 fn make_seed_dict(mask_config: MaskConfigPair, ephm_pk: PublicEncryptKey) -> UpdateSeedDict {
     let (seed, _mask) = make_masked_model(mask_config);
     let mut key_gen = SigningKeyGenerator::new();
@@ -125,6 +182,89 @@ async fn step4_compose_sum2_message(phase: Phase<Sum2>) -> Phase<Sum2> {
     phase
 }
 
+async fn step1_fetch_shared_seed_dict(mut phase: Phase<Sum2>) -> Phase<Sum2> {
+    let mask_config = phase.state.shared.round_params.mask_config;
+    let ephm_pk = phase.state.private.ephm_keys.public;
+    let pk = phase.state.shared.keys.public;
+    let sum_dict = make_sum_dict(&phase.state.shared, ephm_pk);
+    let seed_dict = make_shared_seed_dict(mask_config, &sum_dict, &pk, ephm_pk);
+    phase.with_io_mock(move |mock| {
+        let mut seq = Sequence::new();
+        mock.expect_get_seeds()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_| Ok(Some(seed_dict.clone())));
+        // The sum dict assigns the share indices
+        mock.expect_get_sums()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move || Ok(Some(sum_dict.clone())));
+    });
+
+    let phase = unwrap_step!(phase, complete, sum2);
+    let mut phase = unwrap_step!(phase, complete, sum2);
+    phase.check_io_mock();
+    assert!(phase.state.private.sum_dict.is_some());
+    phase
+}
+
+async fn step2_decrypt_shared_seeds(phase: Phase<Sum2>) -> Phase<Sum2> {
+    let phase = unwrap_step!(phase, complete, sum2);
+    // We keep our own seeds and the seed shares of the others
+    assert_eq!(phase.state.private.seeds.as_ref().unwrap().len(), 4);
+    let seed_shares = phase.state.private.seed_shares.as_ref().unwrap();
+    assert_eq!(seed_shares.len(), 4);
+    assert!(seed_shares.values().all(|seeds| seeds.len() == 3));
+    assert!(phase.state.private.seed_dict.is_none());
+    phase
+}
+
+async fn step5_send_message_and_wait(mut phase: Phase<Sum2>) -> Phase<Sum2> {
+    phase.with_io_mock(|mock| {
+        mock.expect_send_message().times(1).returning(|_| Ok(()));
+    });
+    // The sum2 message is sent, but the seed shares may still be
+    // needed
+    let mut phase = unwrap_step!(phase, complete, sum2);
+    phase.check_io_mock();
+    assert!(phase.state.private.sent_mask);
+    assert!(phase.state.private.message.is_none());
+    phase
+}
+
+async fn step6_fetch_dropout_dict(mut phase: Phase<Sum2>) -> Phase<Sum2> {
+    let sum_dict = phase.state.private.sum_dict.clone().unwrap();
+    let pk = phase.state.shared.keys.public;
+    phase.with_io_mock(move |mock| {
+        let mut seq = Sequence::new();
+        // The first time the state machine fetches the dropout dict,
+        // pretend it's not published yet
+        mock.expect_get_dropouts()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(None));
+        // The second time, one of the other sum participants dropped
+        // out
+        let dropout_dict: SumDict = sum_dict
+            .into_iter()
+            .filter(|(sum_pk, _)| *sum_pk != pk)
+            .take(1)
+            .collect();
+        mock.expect_get_dropouts()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move || Ok(Some(dropout_dict.clone())));
+    });
+
+    let phase = unwrap_step!(phase, pending, sum2);
+    let mut phase = unwrap_step!(phase, complete, sum2);
+    phase.check_io_mock();
+    assert!(phase.state.private.message.is_some());
+    // Make sure this steps consumes the seed shares.
+    assert!(phase.state.private.seed_shares.is_none());
+    phase
+}
+
 async fn step5_send_message(mut phase: Phase<Sum2>) -> Phase<Awaiting> {
     phase.with_io_mock(|mock| {
         let mut seq = Sequence::new();
@@ -151,3 +291,38 @@ async fn test_phase() {
     let phase = step4_compose_sum2_message(phase).await;
     let _phase = step5_send_message(phase).await;
 }
+
+#[tokio::test]
+async fn test_phase_with_seed_sharing() {
+    let phase = make_phase_with_seed_sharing();
+    let phase = step1_fetch_shared_seed_dict(phase).await;
+    let phase = step2_decrypt_shared_seeds(phase).await;
+    let phase = step3_aggregate_masks(phase).await;
+    let phase = step4_compose_sum2_message(phase).await;
+    let phase = step5_send_message_and_wait(phase).await;
+    let phase = step6_fetch_dropout_dict(phase).await;
+    let _phase = step5_send_message(phase).await;
+}
+
+#[tokio::test]
+async fn test_phase_with_seed_sharing_without_dropouts() {
+    let phase = make_phase_with_seed_sharing();
+    let phase = step1_fetch_shared_seed_dict(phase).await;
+    let phase = step2_decrypt_shared_seeds(phase).await;
+    let phase = step3_aggregate_masks(phase).await;
+    let phase = step4_compose_sum2_message(phase).await;
+    let mut phase = step5_send_message_and_wait(phase).await;
+    phase.with_io_mock(|mock| {
+        let mut seq = Sequence::new();
+        mock.expect_get_dropouts()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(Some(SumDict::new())));
+        mock.expect_notify_idle()
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(());
+    });
+    let mut phase = unwrap_step!(phase, complete, awaiting);
+    phase.check_io_mock();
+}
@@ -1,13 +1,25 @@
 use mockall::Sequence;
+use rand::{rngs::StdRng, SeedableRng};
 use xaynet_core::{
     crypto::ByteObject,
-    mask::{FromPrimitives, Model},
+    mask::{
+        share_indices,
+        Aggregation,
+        FromPrimitives,
+        IntoPrimitives,
+        MaskSeed,
+        Masker,
+        Model,
+        SparsityType,
+    },
     SumDict,
 };
 
 use crate::{
     save_and_restore,
+    settings::LocalPrivacy,
     state_machine::{
+        phases::{privatize_model, share_seed, subtract_global_model},
         tests::utils::{shared_state, EncryptKeyGenerator, SelectFor, SigningKeyGenerator},
         Awaiting,
         IntoPhase,
@@ -47,6 +59,7 @@ fn make_update(shared: &SharedState) -> Box<Update> {
         sum_dict: None,
         seed_dict: None,
         model: None,
+        model_delta: None,
         mask: None,
         message: None,
     })
@@ -127,6 +140,38 @@ async fn step2_load_model(mut phase: Phase<Update>) -> Phase<Update> {
     phase
 }
 
+async fn step2_compute_model_delta(mut phase: Phase<Update>) -> Phase<Update> {
+    phase.with_io_mock(|mock| {
+        let mut seq = Sequence::new();
+        // The first time the state machine fetches the global model,
+        // pretend the request fails
+        mock.expect_get_model()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Err("connection refused".into()));
+        // The second time, return a global model.
+        mock.expect_get_model()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|| Ok(Some(make_model())));
+    });
+
+    // First time: no progress should be made, since we didn't
+    // fetch the global model
+    let phase = unwrap_step!(phase, pending, update);
+
+    // Second time: now the state machine should have made progress
+    let phase = unwrap_step!(phase, complete, update);
+
+    // Calling `compute_model_delta` again should return Progress::Continue
+    let mut phase = unwrap_progress_continue!(phase, compute_model_delta, async);
+    let model_delta = phase.state.private.model_delta.clone().unwrap();
+    let weights: Vec<f32> = model_delta.into_primitives_unchecked().collect();
+    assert_eq!(weights, vec![0.; 4]);
+    phase.check_io_mock();
+    phase
+}
+
 async fn step3_mask_model(phase: Phase<Update>) -> Phase<Update> {
     let phase = unwrap_step!(phase, complete, update);
     let mut phase = unwrap_progress_continue!(phase, mask_model);
@@ -208,3 +253,187 @@ async fn test_save_and_restore() {
     });
     save_and_restore!(phase, Update);
 }
+
+#[tokio::test]
+async fn test_update_phase_with_local_privacy() {
+    let mut phase = make_phase();
+    phase.state.shared.local_privacy = Some(LocalPrivacy::new(1.0, 0.1).unwrap());
+    let phase = step1_fetch_sum_dict(phase).await;
+    let phase = step2_load_model(phase).await;
+    let phase = step3_mask_model(phase).await;
+
+    // the masked model is the local model clipped to a norm of 1 plus the noise
+    let weights = unmask_model(&phase);
+    let model: Vec<f64> = make_model().into_primitives_unchecked().collect();
+    let norm = model
+        .iter()
+        .map(|weight| weight * weight)
+        .sum::<f64>()
+        .sqrt();
+    let noise = weights
+        .iter()
+        .zip(model.iter())
+        .map(|(weight, model_weight)| weight - model_weight / norm)
+        .collect::<Vec<_>>();
+    let noise_norm = noise.iter().map(|n| n * n).sum::<f64>().sqrt();
+    // the noise has a standard deviation of 0.1 per weight
+    assert!(noise_norm > 0.0);
+    assert!(noise_norm < 1.0);
+
+    let phase = step4_build_seed_dict(phase).await;
+    let phase = step5_compose_update_message(phase).await;
+    step6_send_message(phase).await;
+}
+
+#[tokio::test]
+async fn test_update_phase_with_model_delta() {
+    let mut phase = make_phase();
+    phase.state.shared.round_params.model_delta = true;
+    let phase = step1_fetch_sum_dict(phase).await;
+    let phase = step2_load_model(phase).await;
+    let phase = step2_compute_model_delta(phase).await;
+    let phase = step3_mask_model(phase).await;
+    let phase = step4_build_seed_dict(phase).await;
+    let phase = step5_compose_update_message(phase).await;
+    step6_send_message(phase).await;
+}
+
+#[tokio::test]
+async fn test_update_phase_with_sparse_masking() {
+    let mut phase = make_phase();
+    let round_params = &mut phase.state.shared.round_params;
+    round_params.mask_config.vect.sparsity_type = SparsityType::S1;
+    round_params.model_length = 4;
+    let phase = step1_fetch_sum_dict(phase).await;
+    let phase = step2_load_model(phase).await;
+    let phase = step3_mask_model(phase).await;
+    // only one in ten weights is masked, but at least one
+    let (_, masked_model) = phase.state.private.mask.as_ref().unwrap();
+    assert_eq!(masked_model.vect.data.len(), 1);
+    let phase = step4_build_seed_dict(phase).await;
+    let phase = step5_compose_update_message(phase).await;
+    step6_send_message(phase).await;
+}
+
+/// Unmask the masked model of the phase with its own mask.
+fn unmask_model(phase: &Phase<Update>) -> Vec<f64> {
+    let (mask_seed, masked_model) = phase.state.private.mask.as_ref().unwrap();
+    let config = phase.state.shared.round_params.mask_config;
+    let len = masked_model.vect.data.len();
+    let mut aggregation = Aggregation::new(config, len);
+    aggregation.aggregate(masked_model.clone());
+    let mask = mask_seed.derive_mask(len, config);
+    aggregation
+        .unmask(mask)
+        .into_primitives_unchecked()
+        .collect()
+}
+
+#[test]
+fn test_subtract_global_model() {
+    let model = Model::from_primitives(vec![5_i32, 3, 9].into_iter()).unwrap();
+    let global_model = Model::from_primitives(vec![4_i32, 5, 6].into_iter()).unwrap();
+    let model_delta = subtract_global_model(&model, Some(&global_model));
+    let weights: Vec<i32> = model_delta.into_primitives_unchecked().collect();
+    assert_eq!(weights, vec![1, -2, 3]);
+
+    // without a matching global model the model is the delta
+    assert_eq!(subtract_global_model(&model, None), model);
+    let global_model = Model::from_primitives(vec![4_i32, 5].into_iter()).unwrap();
+    assert_eq!(subtract_global_model(&model, Some(&global_model)), model);
+}
+
+#[test]
+fn test_privatize_model_clipping() {
+    let local_privacy = LocalPrivacy::new(1.0, 0.0).unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    // the norm of the model is 5, hence it is scaled down to a norm of 1
+    let model = Model::from_primitives(vec![3_f64, 4.0].into_iter()).unwrap();
+    let privatized = privatize_model(&local_privacy, &model, &mut rng).unwrap();
+    let weights: Vec<f64> = privatized.into_primitives_unchecked().collect();
+    assert!((weights[0] - 0.6).abs() < 1e-9);
+    assert!((weights[1] - 0.8).abs() < 1e-9);
+    let norm = weights
+        .iter()
+        .map(|weight| weight * weight)
+        .sum::<f64>()
+        .sqrt();
+    assert!((norm - 1.0).abs() < 1e-9);
+
+    // models within the norm remain unchanged
+    let model = Model::from_primitives(vec![0.5_f64, -0.5].into_iter()).unwrap();
+    let privatized = privatize_model(&local_privacy, &model, &mut rng).unwrap();
+    assert_eq!(privatized, model);
+}
+
+#[test]
+fn test_privatize_model_noise() {
+    let local_privacy = LocalPrivacy::new(2.0, 0.5).unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let len = 10_000;
+    let model = Model::from_primitives(vec![0_f64; len].into_iter()).unwrap();
+    let privatized = privatize_model(&local_privacy, &model, &mut rng).unwrap();
+    let noise: Vec<f64> = privatized.into_primitives_unchecked().collect();
+
+    let mean = noise.iter().sum::<f64>() / len as f64;
+    let variance = noise.iter().map(|n| (n - mean).powi(2)).sum::<f64>() / len as f64;
+    assert!(mean.abs() < 0.05);
+    assert!((variance - 1.0).abs() < 0.1);
+}
+
+#[test]
+fn test_share_seed() {
+    let mut signing_keys = SigningKeyGenerator::new();
+    let mut encrypt_keys = EncryptKeyGenerator::new();
+    let mut sum_dict = SumDict::new();
+    let mut ephm_keys = Vec::new();
+    for _ in 0..3 {
+        let keys = encrypt_keys.next();
+        sum_dict.insert(signing_keys.next().public, keys.public);
+        ephm_keys.push(keys);
+    }
+
+    let config = shared_state(SelectFor::Update).round_params.mask_config;
+    let model = Model::from_primitives(vec![1_f32, -0.5, 0.25].into_iter()).unwrap();
+    let (mask_seed, masked_model) = Masker::new(config).mask(1.0, &model);
+    let (masked_model, seed_dict) =
+        share_seed(mask_seed.clone(), masked_model, &sum_dict, 0.5).unwrap();
+    assert_eq!(seed_dict.len(), 3);
+
+    // every sum participant gets a mask seed and the seed shares of the others
+    let indices = share_indices(&sum_dict).unwrap();
+    let mut rows = vec![Vec::new(); 3];
+    for (pk, ephm_pk) in sum_dict.iter() {
+        let keys = ephm_keys
+            .iter()
+            .find(|keys| keys.public == *ephm_pk)
+            .unwrap();
+        let seeds = seed_dict[pk]
+            .decrypt_all(&keys.public, &keys.secret)
+            .unwrap();
+        assert_eq!(seeds.len(), 3);
+        rows[indices[pk] as usize - 1] = seeds;
+    }
+    assert_eq!(rows[0][0], mask_seed);
+
+    // the model is unmasked with the masks of all the sum participants
+    let len = masked_model.vect.data.len();
+    let mut mask = Aggregation::new(config, len);
+    for (i, row) in rows.iter().enumerate() {
+        mask.aggregate(row[i].derive_mask(len, config));
+    }
+    let mut aggregation = Aggregation::new(config, len);
+    aggregation.aggregate(masked_model);
+    assert_eq!(aggregation.unmask(mask.into()), model);
+
+    // any two sum participants can reconstruct the mask seed of the third one
+    for (i, own) in rows.iter().enumerate() {
+        let shares: Vec<(u8, MaskSeed)> = (0..3)
+            .filter(|j| *j != i)
+            .map(|j| (j as u8 + 1, rows[j][i].clone()))
+            .collect();
+        assert_eq!(MaskSeed::reconstruct_checked(&shares, 2).unwrap(), own[i]);
+    }
+}
@@ -116,6 +116,8 @@ pub fn mask_config() -> MaskConfig {
         data_type: mask::DataType::F32,
         bound_type: mask::BoundType::B0,
         model_type: mask::ModelType::M3,
+        sparsity_type: mask::SparsityType::S0,
+        quantization_type: mask::QuantizationType::Qmax,
     }
 }
 
@@ -127,6 +129,8 @@ pub fn round_params(task: SelectFor) -> RoundParameters {
         seed: RoundSeed::zeroed(),
         mask_config: mask_config().into(),
         model_length: 0,
+        seed_sharing_threshold: None,
+        model_delta: false,
     }
 }
 
@@ -135,6 +139,7 @@ pub fn shared_state(task: SelectFor) -> Box<SharedState> {
         keys: SigningKeyPair::derive_from_seed(&SigningKeySeed::zeroed()),
         scalar: 1.0,
         message_size: MaxMessageSize::unlimited(),
+        local_privacy: None,
         round_params: round_params(task),
     })
 }
@@ -33,6 +33,10 @@ pub trait Notify {
 /// A trait used by the [`StateMachine`] to load the model trained by
 /// the participant, when it has been selected for the update task.
 ///
+/// The store always provides the full local model. If the round
+/// parameters ask for the model delta, the [`StateMachine`] subtracts
+/// the global model itself.
+///
 /// [`StateMachine`]: [crate::StateMachine]
 #[async_trait]
 pub trait ModelStore {
@@ -65,6 +69,11 @@ pub trait XaynetClient {
         pk: SumParticipantPublicKey,
     ) -> Result<Option<UpdateSeedDict>, Self::Error>;
 
+    /// Retrieve the sum participants who dropped out during the
+    /// current round, if available. Only used if the mask seeds are
+    /// secret-shared.
+    async fn get_dropouts(&mut self) -> Result<Option<SumDict>, Self::Error>;
+
     /// Retrieve the current global model, if available.
     async fn get_model(&mut self) -> Result<Option<Model>, Self::Error>;
 
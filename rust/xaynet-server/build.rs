@@ -0,0 +1,9 @@
+fn main() {
+    // The protobuf definitions must be identical to the ones of `xaynet-sdk`, which are copied
+    // because a package can't refer to files outside of its crate. The CI compares them.
+    #[cfg(feature = "grpc")]
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/xaynet/coordinator.proto"], &["proto"])
+        .expect("failed to compile the protobuf definitions");
+}
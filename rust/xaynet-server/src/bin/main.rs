@@ -1,22 +1,64 @@
-use std::{path::PathBuf, process};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+};
+
+use futures::{stream::FuturesUnordered, Future, StreamExt};
+use rayon::{ThreadPool, ThreadPoolBuilder};
 
 use structopt::StructOpt;
 use tokio::signal;
-use tracing::warn;
+use tracing::{error, info, warn};
 use tracing_subscriber::*;
 
+#[cfg(feature = "grpc")]
+use xaynet_server::grpc;
 #[cfg(feature = "metrics")]
-use xaynet_server::{metrics, settings::InfluxSettings};
+use xaynet_server::{
+    metrics,
+    settings::{MetricsBackend, MetricsSettings},
+};
+
+#[cfg(unix)]
+use xaynet_server::settings::SettingsReloader;
 
+#[cfg(feature = "model-persistence")]
+use xaynet_server::storage::model_storage::s3;
 use xaynet_server::{
     rest::{serve, RestError},
-    services,
-    settings::{LoggingSettings, RedisSettings, Settings},
-    state_machine::StateMachineInitializer,
-    storage::{coordinator_storage::redis, CoordinatorStorage, ModelStorage, Store},
+    services::{
+        self,
+        admission::Admission,
+        fetchers::Fetcher,
+        messages::SharedLimits,
+        TaskServices,
+    },
+    settings::{
+        ApiSettings,
+        CoordinatorStorageBackend,
+        CoordinatorStorageSettings,
+        LimitSettings,
+        LoggingSettings,
+        ModelStorageBackend,
+        RedisSettings,
+        RestoreSettings,
+        Settings,
+        TaskSettings,
+        ThreadPoolSettings,
+        DEFAULT_TASK,
+    },
+    state_machine::{StateMachine, StateMachineInitializer},
+    storage::{
+        coordinator_storage::{self, in_memory::InMemory, redis},
+        model_storage::{self, noop::NoOp},
+        CoordinatorStorage,
+        ModelStorage,
+        Store,
+    },
 };
-#[cfg(feature = "model-persistence")]
-use xaynet_server::{settings::S3Settings, storage::model_storage::s3};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "Coordinator")]
@@ -26,11 +68,28 @@ struct Opt {
     config_path: PathBuf,
 }
 
+/// The settings of the state machines and the REST API.
+struct CoordinatorSettings {
+    #[cfg(unix)]
+    path: PathBuf,
+    /// The settings of the default task, which is configured by the top-level settings.
+    default_task: TaskSettings,
+    restore: RestoreSettings,
+    api: ApiSettings,
+    limits: LimitSettings,
+    /// The rate limits and the multipart memory budget of the PET messages, which are shared by
+    /// all tasks.
+    shared_limits: SharedLimits,
+    admission: Option<Arc<Admission>>,
+    tasks: Vec<TaskSettings>,
+    thread_pool: ThreadPoolSettings,
+}
+
 #[tokio::main]
 async fn main() {
     let opt = Opt::from_args();
 
-    let settings = Settings::new(opt.config_path).unwrap_or_else(|err| {
+    let settings = Settings::new(&opt.config_path).unwrap_or_else(|err| {
         eprintln!("{}", err);
         process::exit(1);
     });
@@ -40,7 +99,15 @@ async fn main() {
         api: api_settings,
         log: log_settings,
         model: model_settings,
+        aggregation: aggregation_settings,
+        differential_privacy: differential_privacy_settings,
+        coordinator_storage: coordinator_storage_settings,
         redis: redis_settings,
+        model_storage: model_storage_settings,
+        tasks: task_settings,
+        limits: limit_settings,
+        admission: admission_settings,
+        thread_pool: thread_pool_settings,
         ..
     } = settings;
 
@@ -52,44 +119,340 @@ async fn main() {
     sodiumoxide::init().unwrap();
 
     #[cfg(feature = "metrics")]
-    init_metrics(settings.metrics.influxdb);
+    init_metrics(settings.metrics);
 
-    let store = init_store(
-        redis_settings,
+    let coordinator_settings = CoordinatorSettings {
+        #[cfg(unix)]
+        path: opt.config_path,
+        default_task: TaskSettings {
+            name: DEFAULT_TASK.to_string(),
+            pet: pet_settings,
+            mask: mask_settings,
+            model: model_settings,
+            aggregation: aggregation_settings,
+            differential_privacy: differential_privacy_settings,
+        },
+        restore: settings.restore,
+        api: api_settings,
+        shared_limits: SharedLimits::new(&limit_settings),
+        limits: limit_settings,
+        admission: admission_settings
+            .as_ref()
+            .map(|settings| Arc::new(Admission::new(settings))),
+        tasks: task_settings,
+        thread_pool: thread_pool_settings,
+    };
+
+    // the presence of the settings of the selected backends is checked during the settings
+    // validation
+    match model_storage_settings.backend {
+        ModelStorageBackend::NoOp => {
+            let task_model_stores = coordinator_settings.tasks.iter().map(|_| NoOp).collect();
+            init_coordinator_store_and_run(
+                NoOp,
+                task_model_stores,
+                coordinator_storage_settings,
+                redis_settings,
+                coordinator_settings,
+            )
+            .await
+        }
+        ModelStorageBackend::File => {
+            let path = model_storage_settings
+                .path
+                .expect("missing model storage path");
+            let model_store =
+                model_storage::file::FileStorage::new(&path, model_storage_settings.retention)
+                    .await
+                    .expect("failed to create the model storage directory");
+            let mut task_model_stores = Vec::with_capacity(coordinator_settings.tasks.len());
+            for task in coordinator_settings.tasks.iter() {
+                let task_model_store = model_storage::file::FileStorage::new(
+                    path.join(&task.name),
+                    model_storage_settings.retention,
+                )
+                .await
+                .expect("failed to create the model storage directory of a task");
+                task_model_stores.push(task_model_store);
+            }
+            init_coordinator_store_and_run(
+                model_store,
+                task_model_stores,
+                coordinator_storage_settings,
+                redis_settings,
+                coordinator_settings,
+            )
+            .await
+        }
         #[cfg(feature = "model-persistence")]
-        settings.s3,
+        ModelStorageBackend::S3 => {
+            let s3_settings = settings.s3.expect("missing s3 settings");
+            let model_store = s3::Client::new(s3_settings).expect("failed to create S3 client");
+            model_store
+                .create_global_models_bucket()
+                .await
+                .expect("failed to create bucket for global models");
+            let task_model_stores = coordinator_settings
+                .tasks
+                .iter()
+                .map(|task| model_store.clone().with_namespace(&task.name))
+                .collect();
+            init_coordinator_store_and_run(
+                model_store,
+                task_model_stores,
+                coordinator_storage_settings,
+                redis_settings,
+                coordinator_settings,
+            )
+            .await
+        }
+    }
+}
+
+/// Initializes the coordinator stores of the default task and of the additional tasks and runs the
+/// coordinator.
+///
+/// The model stores of the additional tasks are given in the order of the task settings.
+async fn init_coordinator_store_and_run<M>(
+    model_store: M,
+    task_model_stores: Vec<M>,
+    coordinator_storage_settings: CoordinatorStorageSettings,
+    redis_settings: Option<RedisSettings>,
+    settings: CoordinatorSettings,
+) where
+    M: ModelStorage,
+{
+    match coordinator_storage_settings.backend {
+        CoordinatorStorageBackend::Redis => {
+            let redis_settings = redis_settings.expect("missing redis settings");
+            let coordinator_store = redis::Client::new(redis_settings.url)
+                .await
+                .expect("failed to establish a connection to Redis");
+            let task_stores = settings
+                .tasks
+                .iter()
+                .zip(task_model_stores)
+                .map(|(task, model_store)| {
+                    let coordinator_store = coordinator_store.clone().with_namespace(&task.name);
+                    Store::new(coordinator_store, model_store)
+                })
+                .collect();
+            run(
+                Store::new(coordinator_store, model_store),
+                task_stores,
+                settings,
+            )
+            .await
+        }
+        CoordinatorStorageBackend::File => {
+            let path = coordinator_storage_settings
+                .path
+                .expect("missing coordinator storage path");
+            let coordinator_store = coordinator_storage::file::FileStorage::open(&path)
+                .await
+                .expect("failed to open the coordinator storage file");
+            let mut task_stores = Vec::with_capacity(settings.tasks.len());
+            for (task, model_store) in settings.tasks.iter().zip(task_model_stores) {
+                let coordinator_store =
+                    coordinator_storage::file::FileStorage::open(task_path(&path, &task.name))
+                        .await
+                        .expect("failed to open the coordinator storage file of a task");
+                task_stores.push(Store::new(coordinator_store, model_store));
+            }
+            run(
+                Store::new(coordinator_store, model_store),
+                task_stores,
+                settings,
+            )
+            .await
+        }
+        CoordinatorStorageBackend::InMemory => {
+            let task_stores = task_model_stores
+                .into_iter()
+                .map(|model_store| Store::new(InMemory::new(), model_store))
+                .collect();
+            run(
+                Store::new(InMemory::new(), model_store),
+                task_stores,
+                settings,
+            )
+            .await
+        }
+    }
+}
+
+/// Returns the path of the coordinator storage file of a task, which is located next to the
+/// coordinator storage file of the default task.
+fn task_path(path: &Path, task: &str) -> PathBuf {
+    let mut file_name = OsString::from(format!("{}-", task));
+    file_name.push(path.file_name().unwrap_or_default());
+    path.with_file_name(file_name)
+}
+
+async fn run<C, M>(store: Store<C, M>, task_stores: Vec<Store<C, M>>, settings: CoordinatorSettings)
+where
+    C: CoordinatorStorage,
+    M: ModelStorage,
+{
+    // rayon falls back to the number of logical CPUs for zero threads
+    let thread_pool = Arc::new(
+        ThreadPoolBuilder::new()
+            .num_threads(settings.thread_pool.num_threads.unwrap_or(0))
+            .build()
+            .expect("failed to build the thread-pool"),
+    );
+
+    let (state_machine, default_task) = init_task(
+        &settings.default_task,
+        store,
+        &settings,
+        thread_pool.clone(),
     )
     .await;
+    #[cfg(unix)]
+    let mut settings_reloader = SettingsReloader::new(
+        settings.path.clone(),
+        settings.default_task.mask,
+        settings.default_task.model.clone(),
+        default_task.admin_handle.clone(),
+    );
+    let state_machines = FuturesUnordered::new();
+    state_machines.push(spawn_task(DEFAULT_TASK.to_string(), state_machine));
 
+    let mut tasks = HashMap::with_capacity(settings.tasks.len());
+    for (task, store) in settings.tasks.iter().zip(task_stores) {
+        let (state_machine, services) =
+            init_task(task, store, &settings, thread_pool.clone()).await;
+        #[cfg(unix)]
+        {
+            settings_reloader = settings_reloader.with_task(
+                task.name.clone(),
+                task.mask,
+                task.model.clone(),
+                services.admin_handle.clone(),
+            );
+        }
+        state_machines.push(spawn_task(task.name.clone(), state_machine));
+        tasks.insert(task.name.clone(), services);
+    }
+    #[cfg(unix)]
+    tokio::spawn(settings_reloader.run());
+
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_bind_address) = settings.api.grpc_bind_address {
+        let grpc_server = grpc::GrpcServer::bind(grpc_bind_address, &settings.api)
+            .await
+            .expect("failed to start the gRPC server");
+        let (default_task, tasks) = (default_task.clone(), tasks.clone());
+        tokio::spawn(async move {
+            if let Err(err) = grpc_server.serve(default_task, tasks).await {
+                warn!("gRPC server terminated: {}", err);
+            }
+        });
+    }
+
+    let rest_server = serve(
+        settings.api,
+        settings.limits,
+        default_task,
+        tasks,
+        settings.admission,
+    );
+    let ctrl_c = signal::ctrl_c();
+    tokio::pin!(rest_server, ctrl_c, state_machines);
+
+    // the state machines of the tasks run independently, the coordinator keeps serving the
+    // remaining tasks if a task terminates
+    loop {
+        tokio::select! {
+            terminated = state_machines.next() => match terminated {
+                Some(task) => warn!("the state machine of the task {} terminated", task),
+                None => {
+                    warn!("shutting down: the state machines of all tasks terminated");
+                    break;
+                }
+            },
+            result = &mut rest_server => {
+                match result {
+                    Ok(()) => warn!("shutting down: REST server terminated"),
+                    Err(RestError::InvalidTlsConfig) => {
+                        warn!("shutting down: invalid TLS settings for REST server");
+                    },
+                }
+                break;
+            }
+            _ = &mut ctrl_c => {
+                info!("shutting down: received the interrupt signal");
+                break;
+            }
+        }
+    }
+}
+
+/// Initializes the state machine of a task and its services.
+async fn init_task<C, M>(
+    task: &TaskSettings,
+    store: Store<C, M>,
+    settings: &CoordinatorSettings,
+    thread_pool: Arc<ThreadPool>,
+) -> (
+    StateMachine<C, M>,
+    TaskServices<impl Fetcher + Sync + Send + Clone + 'static>,
+)
+where
+    C: CoordinatorStorage,
+    M: ModelStorage,
+{
     let (state_machine, requests_tx, event_subscriber) = StateMachineInitializer::new(
-        pet_settings,
-        mask_settings,
-        model_settings,
-        #[cfg(feature = "model-persistence")]
+        task.pet,
+        task.mask,
+        task.model.clone(),
+        task.aggregation,
+        task.differential_privacy,
         settings.restore,
         store,
+        thread_pool.clone(),
     )
+    .with_task(task.name.clone())
     .init()
     .await
-    .expect("failed to initialize state machine");
-
-    let fetcher = services::fetchers::fetcher(&event_subscriber);
-    let message_handler =
-        services::messages::PetMessageHandler::new(&event_subscriber, requests_tx);
+    .unwrap_or_else(|err| {
+        panic!(
+            "failed to initialize the state machine of the task {}: {}",
+            task.name, err
+        )
+    });
+    let services = TaskServices {
+        fetcher: services::fetchers::fetcher(&event_subscriber),
+        pet_message_handler: services::messages::PetMessageHandler::new(
+            &task.name,
+            &event_subscriber,
+            requests_tx,
+            &settings.limits,
+            settings.shared_limits.clone(),
+            settings.admission.clone(),
+            thread_pool,
+        ),
+        admin_handle: state_machine.admin_handle(),
+        event_subscriber,
+    };
+    (state_machine, services)
+}
 
-    tokio::select! {
-        _ = state_machine.run() => {
-            warn!("shutting down: Service terminated");
-        }
-        result = serve(api_settings, fetcher, message_handler) => {
-            match result {
-                Ok(()) => warn!("shutting down: REST server terminated"),
-                Err(RestError::InvalidTlsConfig) => {
-                    warn!("shutting down: invalid TLS settings for REST server");
-                },
-            }
+/// Spawns the state machine of a task.
+///
+/// The returned future resolves to the name of the task once the state machine terminated.
+fn spawn_task<C, M>(task: String, state_machine: StateMachine<C, M>) -> impl Future<Output = String>
+where
+    C: CoordinatorStorage,
+    M: ModelStorage,
+{
+    let state_machine = tokio::spawn(state_machine.run());
+    async move {
+        if let Err(err) = state_machine.await {
+            error!("the state machine of the task {} failed: {}", task, err);
         }
-        _ =  signal::ctrl_c() => {}
+        task
     }
 }
 
@@ -101,35 +464,16 @@ fn init_tracing(settings: LoggingSettings) {
 }
 
 #[cfg(feature = "metrics")]
-fn init_metrics(settings: InfluxSettings) {
-    let recorder = metrics::Recorder::new(settings);
+fn init_metrics(settings: MetricsSettings) {
+    // the presence of the settings of the selected backend is checked during the settings
+    // validation
+    let recorder = match settings.backend {
+        MetricsBackend::InfluxDb => {
+            metrics::Recorder::influxdb(settings.influxdb.expect("missing influxdb settings"))
+        }
+        MetricsBackend::Prometheus => metrics::Recorder::prometheus(),
+    };
     if metrics::GlobalRecorder::install(recorder).is_err() {
         warn!("failed to install metrics recorder");
     };
 }
-
-async fn init_store(
-    redis_settings: RedisSettings,
-    #[cfg(feature = "model-persistence")] s3_settings: S3Settings,
-) -> Store<impl CoordinatorStorage, impl ModelStorage> {
-    let coordinator_store = redis::Client::new(redis_settings.url)
-        .await
-        .expect("failed to establish a connection to Redis");
-
-    let model_store = {
-        #[cfg(not(feature = "model-persistence"))]
-        {
-            xaynet_server::storage::model_storage::noop::NoOp
-        }
-
-        #[cfg(feature = "model-persistence")]
-        {
-            let s3 = s3::Client::new(s3_settings).expect("failed to create S3 client");
-            s3.create_global_models_bucket()
-                .await
-                .expect("failed to create bucket for global models");
-            s3
-        }
-    };
-    Store::new(coordinator_store, model_store)
-}
@@ -0,0 +1,536 @@
+//! A gRPC API for the PET protocol interactions.
+//!
+//! The gRPC API offers the same operations as the [REST API] for participants which already
+//! speak gRPC. Its protobuf definition is `proto/xaynet/coordinator.proto`. Participants present
+//! their credential, if any, in the `x-xaynet-credential` metadata of their `SendMessage` calls.
+//!
+//! Calls are handled by the default task unless they select an additional task by its name in
+//! their `x-xaynet-task` metadata. Calls which select an unknown task fail with `NOT_FOUND`.
+//!
+//! If the `tls` feature is enabled, the gRPC API is served with the same TLS settings as the REST
+//! API.
+//!
+//! Requires the `grpc` feature to be enabled.
+//!
+//! [REST API]: crate::rest
+
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+
+use prost::Message;
+use thiserror::Error;
+use tokio::net::TcpListener;
+#[cfg(feature = "tls")]
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+use tonic::{transport::Server, Code, Request, Response, Status};
+use tracing::warn;
+
+use self::proto::coordinator_server::{Coordinator, CoordinatorServer};
+use crate::{
+    services::{
+        fetchers::{FetchError, Fetcher},
+        TaskServices,
+    },
+    settings::ApiSettings,
+};
+use xaynet_core::{
+    common::{MessageError, MessageErrorKind, RoundParameters},
+    crypto::ByteObject,
+    mask::{DataType, IntoPrimitives, MaskConfig, MaskConfigPair, Model, ModelCastError},
+    ParticipantPublicKey,
+    SumDict,
+    UpdateSeedDict,
+};
+
+#[allow(clippy::all, missing_docs)]
+/// The protobuf messages and services generated from the protobuf definition.
+pub mod proto {
+    tonic::include_proto!("xaynet.v1");
+}
+
+/// A gRPC server which is bound to its address, but doesn't serve requests yet.
+pub struct GrpcServer {
+    listener: TcpListener,
+    server: Server,
+}
+
+impl GrpcServer {
+    /// Binds a gRPC server to the given address.
+    ///
+    /// If the `tls` feature is enabled, the server is configured with the TLS server certificate
+    /// and key of the `api_settings` and with their trust anchor for TLS client authentication.
+    ///
+    /// # Errors
+    /// Fails if the server can't be bound to the address or the TLS settings are invalid.
+    #[cfg_attr(not(feature = "tls"), allow(unused_variables))]
+    pub async fn bind(
+        bind_address: SocketAddr,
+        api_settings: &ApiSettings,
+    ) -> Result<Self, GrpcError> {
+        let server = Server::builder();
+        #[cfg(feature = "tls")]
+        let server = server.tls_config(tls_config(api_settings)?)?;
+        let listener = TcpListener::bind(bind_address)
+            .await
+            .map_err(GrpcError::Bind)?;
+        Ok(Self { listener, server })
+    }
+
+    /// Serves requests for data and PET messages.
+    ///
+    /// * `default_task`: services of the default task, which handle calls without a task.
+    /// * `tasks`: services of the additional tasks by name, which handle the calls selecting them.
+    ///
+    /// # Errors
+    /// Fails if the server terminates with an error.
+    pub async fn serve<F>(
+        self,
+        default_task: TaskServices<F>,
+        tasks: HashMap<String, TaskServices<F>>,
+    ) -> Result<(), GrpcError>
+    where
+        F: Fetcher + Sync + Send + 'static + Clone,
+    {
+        let Self {
+            mut listener,
+            mut server,
+        } = self;
+        let service = CoordinatorService {
+            default_task,
+            tasks: Arc::new(tasks),
+        };
+        server
+            .add_service(CoordinatorServer::new(service))
+            .serve_with_incoming(listener.incoming())
+            .await
+            .map_err(GrpcError::from)
+    }
+}
+
+#[cfg(feature = "tls")]
+/// Creates the TLS configuration of the gRPC server from the TLS settings of the REST API.
+///
+/// # Errors
+/// Fails if the TLS server certificate or key is missing or if any of the files can't be read.
+fn tls_config(api_settings: &ApiSettings) -> Result<ServerTlsConfig, GrpcError> {
+    let (certificate, key) = match (&api_settings.tls_certificate, &api_settings.tls_key) {
+        (Some(certificate), Some(key)) => (
+            std::fs::read(certificate).map_err(|_| GrpcError::InvalidTlsConfig)?,
+            std::fs::read(key).map_err(|_| GrpcError::InvalidTlsConfig)?,
+        ),
+        _ => return Err(GrpcError::InvalidTlsConfig),
+    };
+    let mut tls_config = ServerTlsConfig::new().identity(Identity::from_pem(certificate, key));
+    if let Some(trust_anchor) = &api_settings.tls_client_auth {
+        let trust_anchor = std::fs::read(trust_anchor).map_err(|_| GrpcError::InvalidTlsConfig)?;
+        tls_config = tls_config.client_ca_root(Certificate::from_pem(trust_anchor));
+    }
+    Ok(tls_config)
+}
+
+#[derive(Debug, Error)]
+/// Errors of the gRPC server.
+pub enum GrpcError {
+    #[error("failed to bind the gRPC server: {0}")]
+    Bind(#[source] std::io::Error),
+    #[cfg(feature = "tls")]
+    #[error("invalid TLS configuration was provided")]
+    InvalidTlsConfig,
+    #[error("the gRPC server failed: {0}")]
+    Transport(#[from] tonic::transport::Error),
+}
+
+/// The gRPC coordinator service.
+struct CoordinatorService<F> {
+    default_task: TaskServices<F>,
+    tasks: Arc<HashMap<String, TaskServices<F>>>,
+}
+
+impl<F> CoordinatorService<F> {
+    /// Selects the services of the task of a call via its `x-xaynet-task` metadata.
+    ///
+    /// # Errors
+    /// Fails if the selected task is unknown.
+    fn task<T>(&self, request: &Request<T>) -> Result<&TaskServices<F>, Status> {
+        match request.metadata().get("x-xaynet-task") {
+            None => Ok(&self.default_task),
+            Some(name) => name
+                .to_str()
+                .ok()
+                .and_then(|name| self.tasks.get(name))
+                .ok_or_else(|| Status::not_found("unknown task")),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<F> Coordinator for CoordinatorService<F>
+where
+    F: Fetcher + Sync + Send + 'static + Clone,
+{
+    async fn send_message(
+        &self,
+        request: Request<proto::SendMessageRequest>,
+    ) -> Result<Response<proto::SendMessageResponse>, Status> {
+        let mut handler = self.task(&request)?.pet_message_handler.clone();
+        let ip = request.remote_addr().map(|addr| addr.ip());
+        let credential = request
+            .metadata()
+            .get("x-xaynet-credential")
+            .and_then(|credential| credential.to_str().ok())
+            .map(String::from);
+        let message = request.into_inner().message;
+        match handler.handle_message(message, credential, ip).await {
+            Ok(_) => Ok(Response::new(proto::SendMessageResponse {})),
+            Err(e) => {
+                warn!("failed to handle message: {:?}", e);
+                Err(message_error_status(MessageError::from(e)))
+            }
+        }
+    }
+
+    async fn get_round_parameters(
+        &self,
+        request: Request<proto::GetRoundParametersRequest>,
+    ) -> Result<Response<proto::RoundParameters>, Status> {
+        let params = self
+            .task(&request)?
+            .fetcher
+            .clone()
+            .round_params()
+            .await
+            .map_err(|e| fetch_error_status("round parameters", e))?;
+        Ok(Response::new(params.into()))
+    }
+
+    async fn get_sums(
+        &self,
+        request: Request<proto::GetSumsRequest>,
+    ) -> Result<Response<proto::GetSumsResponse>, Status> {
+        let sum_dict = self
+            .task(&request)?
+            .fetcher
+            .clone()
+            .sum_dict()
+            .await
+            .map_err(|e| fetch_error_status("sum dict", e))?;
+        Ok(Response::new(proto::GetSumsResponse {
+            sum_dict: sum_dict.map(|dict| dict.data.as_ref().into()),
+        }))
+    }
+
+    async fn get_seeds(
+        &self,
+        request: Request<proto::GetSeedsRequest>,
+    ) -> Result<Response<proto::GetSeedsResponse>, Status> {
+        let mut fetcher = self.task(&request)?.fetcher.clone();
+        let pk = ParticipantPublicKey::from_slice(&request.into_inner().pk)
+            .ok_or_else(|| Status::invalid_argument("invalid public key"))?;
+        let seed_dict = fetcher
+            .seed_dict()
+            .await
+            .map_err(|e| fetch_error_status("seed dict", e))?;
+        Ok(Response::new(proto::GetSeedsResponse {
+            seed_dict: seed_dict
+                .as_ref()
+                .and_then(|dict| dict.data.get(&pk))
+                .map(proto::UpdateSeedDict::from),
+        }))
+    }
+
+    async fn get_dropouts(
+        &self,
+        request: Request<proto::GetDropoutsRequest>,
+    ) -> Result<Response<proto::GetDropoutsResponse>, Status> {
+        let dropout_dict = self
+            .task(&request)?
+            .fetcher
+            .clone()
+            .dropout_dict()
+            .await
+            .map_err(|e| fetch_error_status("dropout dict", e))?;
+        Ok(Response::new(proto::GetDropoutsResponse {
+            dropout_dict: dropout_dict.map(|dict| dict.data.as_ref().into()),
+        }))
+    }
+
+    async fn get_model(
+        &self,
+        request: Request<proto::GetModelRequest>,
+    ) -> Result<Response<proto::GetModelResponse>, Status> {
+        let mut fetcher = self.task(&request)?.fetcher.clone();
+        let model = match fetcher.model().await {
+            Ok(Some(model)) => model.data,
+            Ok(None) => return Ok(Response::new(proto::GetModelResponse { model: None })),
+            Err(e) => return Err(fetch_error_status("model", e)),
+        };
+        // the model is cast to the data type of the current round
+        let model = fetcher.round_params().await.and_then(|params| {
+            proto::Model::new(&model, params.mask_config.vect.data_type).map_err(FetchError::from)
+        });
+        match model {
+            Ok(model) => Ok(Response::new(proto::GetModelResponse {
+                model: Some(model),
+            })),
+            Err(e) => Err(fetch_error_status("model", e)),
+        }
+    }
+}
+
+/// Converts the error of a PET message into a status with the encoded error as details.
+fn message_error_status(error: MessageError) -> Status {
+    let code = match error.kind {
+        MessageErrorKind::Decrypt
+        | MessageErrorKind::Parsing
+        | MessageErrorKind::InvalidSignature
+        | MessageErrorKind::Rejected
+        | MessageErrorKind::TooLarge => Code::InvalidArgument,
+        MessageErrorKind::NotEligible | MessageErrorKind::NotAdmitted => Code::PermissionDenied,
+        MessageErrorKind::InvalidCoordinatorPublicKey
+        | MessageErrorKind::UnexpectedMessage
+        | MessageErrorKind::Discarded => Code::FailedPrecondition,
+        MessageErrorKind::Internal => Code::Internal,
+        MessageErrorKind::RateLimited => Code::ResourceExhausted,
+    };
+    let description = error.description.clone();
+    let mut details = Vec::new();
+    // safe unwrap: the vector grows as needed
+    proto::MessageError::from(error)
+        .encode(&mut details)
+        .unwrap();
+    Status::with_details(code, description, details.into())
+}
+
+/// Converts a failed data request into an internal error status.
+fn fetch_error_status(data: &str, error: FetchError) -> Status {
+    warn!("failed to handle {} request: {:?}", data, error);
+    Status::internal(format!("failed to fetch the {}", data))
+}
+
+impl From<MessageError> for proto::MessageError {
+    fn from(error: MessageError) -> Self {
+        use self::proto::message_error::Kind;
+
+        let kind = match error.kind {
+            MessageErrorKind::Decrypt => Kind::Decrypt,
+            MessageErrorKind::Parsing => Kind::Parsing,
+            MessageErrorKind::InvalidSignature => Kind::InvalidSignature,
+            MessageErrorKind::InvalidCoordinatorPublicKey => Kind::InvalidCoordinatorPublicKey,
+            MessageErrorKind::UnexpectedMessage => Kind::UnexpectedMessage,
+            MessageErrorKind::NotEligible => Kind::NotEligible,
+            MessageErrorKind::Discarded => Kind::Discarded,
+            MessageErrorKind::Rejected => Kind::Rejected,
+            MessageErrorKind::Internal => Kind::Internal,
+            MessageErrorKind::TooLarge => Kind::TooLarge,
+            MessageErrorKind::RateLimited => Kind::RateLimited,
+            MessageErrorKind::NotAdmitted => Kind::NotAdmitted,
+        };
+        Self {
+            kind: kind as i32,
+            description: error.description,
+        }
+    }
+}
+
+impl From<MaskConfig> for proto::MaskConfig {
+    fn from(config: MaskConfig) -> Self {
+        Self {
+            group_type: config.group_type as u32,
+            data_type: config.data_type as u32,
+            bound_type: config.bound_type.tag() as u32,
+            model_type: config.model_type.tag() as u32,
+            sparsity_type: config.sparsity_type as u32,
+            quantization_type: config.quantization_type.tag() as u32,
+            bound: config.bound_type.bound().unwrap_or_default(),
+            max_nb_models: config.model_type.max_nb_models() as u64,
+            decimal_places: config
+                .quantization_type
+                .decimal_places()
+                .unwrap_or_default() as u32,
+        }
+    }
+}
+
+impl From<MaskConfigPair> for proto::MaskConfigPair {
+    fn from(config: MaskConfigPair) -> Self {
+        Self {
+            vect: Some(config.vect.into()),
+            unit: Some(config.unit.into()),
+        }
+    }
+}
+
+impl From<RoundParameters> for proto::RoundParameters {
+    fn from(params: RoundParameters) -> Self {
+        Self {
+            pk: params.pk.as_slice().to_vec(),
+            sum: params.sum,
+            update: params.update,
+            seed: params.seed.as_slice().to_vec(),
+            mask_config: Some(params.mask_config.into()),
+            model_length: params.model_length as u64,
+            seed_sharing_threshold: params.seed_sharing_threshold,
+            model_delta: params.model_delta,
+        }
+    }
+}
+
+impl From<&SumDict> for proto::SumDict {
+    fn from(dict: &SumDict) -> Self {
+        let entries = dict
+            .iter()
+            .map(|(pk, ephm_pk)| proto::sum_dict::Entry {
+                pk: pk.as_slice().to_vec(),
+                ephm_pk: ephm_pk.as_slice().to_vec(),
+            })
+            .collect();
+        Self { entries }
+    }
+}
+
+impl From<&UpdateSeedDict> for proto::UpdateSeedDict {
+    fn from(dict: &UpdateSeedDict) -> Self {
+        let entries = dict
+            .iter()
+            .map(|(pk, seed)| proto::update_seed_dict::Entry {
+                pk: pk.as_slice().to_vec(),
+                seed: seed.as_slice().to_vec(),
+            })
+            .collect();
+        Self { entries }
+    }
+}
+
+impl proto::Model {
+    /// Converts a [`Model`] into its protobuf representation with weights of the data type.
+    ///
+    /// # Errors
+    /// Fails if a weight can't be cast to the data type.
+    fn new(model: &Model, data_type: DataType) -> Result<Self, ModelCastError> {
+        use self::proto::model::{Weights, F32, F64, I32, I64};
+
+        let weights = match data_type {
+            DataType::F32 => Weights::F32(F32 {
+                weights: model.to_primitives().collect::<Result<_, _>>()?,
+            }),
+            DataType::F64 => Weights::F64(F64 {
+                weights: model.to_primitives().collect::<Result<_, _>>()?,
+            }),
+            DataType::I32 => Weights::I32(I32 {
+                weights: model.to_primitives().collect::<Result<_, _>>()?,
+            }),
+            DataType::I64 => Weights::I64(I64 {
+                weights: model.to_primitives().collect::<Result<_, _>>()?,
+            }),
+        };
+        Ok(Self {
+            weights: Some(weights),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        services::tests::utils::task_services,
+        settings::{LimitSettings, DEFAULT_TASK},
+    };
+    use xaynet_core::mask::FromPrimitives;
+
+    #[test]
+    fn test_message_error_status() {
+        let status = message_error_status(MessageError {
+            kind: MessageErrorKind::NotEligible,
+            description: "participant is not eligible for sum task".to_string(),
+        });
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(status.message(), "participant is not eligible for sum task");
+
+        let error = proto::MessageError::decode(status.details()).unwrap();
+        assert_eq!(error.kind(), proto::message_error::Kind::NotEligible);
+        assert_eq!(
+            error.description,
+            "participant is not eligible for sum task"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_select_task() {
+        let limits = LimitSettings::default();
+        let mut tasks = HashMap::new();
+        tasks.insert("keyboard".to_string(), task_services("keyboard", &limits));
+        let service = CoordinatorService {
+            default_task: task_services(DEFAULT_TASK, &limits),
+            tasks: Arc::new(tasks),
+        };
+
+        let mut request = Request::new(proto::GetModelRequest {});
+        let task = service.task(&request).unwrap();
+        assert_eq!(task.pet_message_handler.task(), DEFAULT_TASK);
+
+        request
+            .metadata_mut()
+            .insert("x-xaynet-task", "keyboard".parse().unwrap());
+        let task = service.task(&request).unwrap();
+        assert_eq!(task.pet_message_handler.task(), "keyboard");
+
+        request
+            .metadata_mut()
+            .insert("x-xaynet-task", "unknown".parse().unwrap());
+        assert_eq!(
+            service.task(&request).map(|_| ()).unwrap_err().code(),
+            Code::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bind() {
+        let bind_address = ([127, 0, 0, 1], 0).into();
+        let api_settings = ApiSettings {
+            bind_address,
+            admin_token: None,
+            grpc_bind_address: Some(bind_address),
+            #[cfg(feature = "tls")]
+            tls_certificate: None,
+            #[cfg(feature = "tls")]
+            tls_key: None,
+            #[cfg(feature = "tls")]
+            tls_client_auth: None,
+        };
+
+        #[cfg(not(feature = "tls"))]
+        {
+            let server = GrpcServer::bind(bind_address, &api_settings).await.unwrap();
+            let bound_address = server.listener.local_addr().unwrap();
+            assert!(matches!(
+                GrpcServer::bind(bound_address, &api_settings).await,
+                Err(GrpcError::Bind(_))
+            ));
+        }
+        #[cfg(feature = "tls")]
+        assert!(matches!(
+            GrpcServer::bind(bind_address, &api_settings).await,
+            Err(GrpcError::InvalidTlsConfig)
+        ));
+    }
+
+    #[test]
+    fn test_protobuf_definitions_are_identical() {
+        assert_eq!(
+            include_str!("../proto/xaynet/coordinator.proto"),
+            include_str!("../../xaynet-sdk/proto/xaynet/coordinator.proto"),
+        );
+    }
+
+    #[test]
+    fn test_model() {
+        let model = Model::from_primitives(vec![1_i32, -2, 3].into_iter()).unwrap();
+        let model = proto::Model::new(&model, DataType::I32).unwrap();
+        assert_eq!(
+            model.weights,
+            Some(proto::model::Weights::I32(proto::model::I32 {
+                weights: vec![1, -2, 3],
+            }))
+        );
+    }
+}
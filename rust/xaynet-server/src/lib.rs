@@ -14,6 +14,8 @@
 
 pub mod examples;
 
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod metrics;
 pub mod rest;
 pub mod services;
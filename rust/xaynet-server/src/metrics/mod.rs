@@ -1,5 +1,8 @@
 pub mod recorders;
-pub use self::recorders::influxdb::{Measurement, Recorder, Tags};
+pub use self::recorders::{
+    influxdb::{Measurement, Tags},
+    Recorder,
+};
 use once_cell::sync::OnceCell;
 
 static RECORDER: OnceCell<Recorder> = OnceCell::new();
@@ -13,6 +13,16 @@ pub enum Measurement {
     MessageSum2,
     MessageDiscarded,
     MessageRejected,
+    MessageRateLimited,
+    MessageTooLarge,
+    MessageNotAdmitted,
+    MultipartMessageCompleted,
+    MultipartMessageExpired,
+    MultipartMessageEvicted,
+    PrivacyBudgetEpsilon,
+    PrivacyBudgetDelta,
+    SettingsReloaded,
+    SettingsRejected,
 }
 
 impl From<&Measurement> for &'static str {
@@ -28,6 +38,16 @@ impl From<&Measurement> for &'static str {
             Measurement::MessageSum2 => "message_sum2",
             Measurement::MessageDiscarded => "message_discarded",
             Measurement::MessageRejected => "message_rejected",
+            Measurement::MessageRateLimited => "message_rate_limited",
+            Measurement::MessageTooLarge => "message_too_large",
+            Measurement::MessageNotAdmitted => "message_not_admitted",
+            Measurement::MultipartMessageCompleted => "multipart_message_completed",
+            Measurement::MultipartMessageExpired => "multipart_message_expired",
+            Measurement::MultipartMessageEvicted => "multipart_message_evicted",
+            Measurement::PrivacyBudgetEpsilon => "privacy_budget_epsilon",
+            Measurement::PrivacyBudgetDelta => "privacy_budget_delta",
+            Measurement::SettingsReloaded => "settings_reloaded",
+            Measurement::SettingsRejected => "settings_rejected",
         }
     }
 }
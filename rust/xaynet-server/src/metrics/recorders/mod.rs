@@ -1 +1,47 @@
 pub mod influxdb;
+pub mod prometheus;
+
+use self::influxdb::{Measurement, Tags};
+use crate::settings::InfluxSettings;
+
+/// A metrics / events recorder.
+pub enum Recorder {
+    /// Pushes the metrics / events to an InfluxDB instance.
+    InfluxDb(influxdb::Recorder),
+    /// Exposes the metrics / events to be scraped by Prometheus.
+    Prometheus(prometheus::Recorder),
+}
+
+impl Recorder {
+    /// Creates a new InfluxDB recorder.
+    pub fn influxdb(settings: InfluxSettings) -> Self {
+        Recorder::InfluxDb(influxdb::Recorder::new(settings))
+    }
+
+    /// Creates a new Prometheus recorder.
+    pub fn prometheus() -> Self {
+        Recorder::Prometheus(prometheus::Recorder::new())
+    }
+
+    /// Records a new metric.
+    pub fn metric<V>(&self, measurement: Measurement, value: V, tags: Option<Tags>)
+    where
+        V: Into<::influxdb::Type> + Send + 'static,
+    {
+        match self {
+            Recorder::InfluxDb(recorder) => recorder.metric(measurement, value, tags),
+            Recorder::Prometheus(recorder) => recorder.metric(measurement, value, tags),
+        }
+    }
+
+    /// Records a new event.
+    pub fn event<T>(&self, title: T, description: Option<&str>, tags: Option<&[&str]>)
+    where
+        T: Into<String> + Send + 'static,
+    {
+        match self {
+            Recorder::InfluxDb(recorder) => recorder.event(title, description, tags),
+            Recorder::Prometheus(recorder) => recorder.event(title, description, tags),
+        }
+    }
+}
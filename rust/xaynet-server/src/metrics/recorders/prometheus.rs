@@ -0,0 +1,251 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+use influxdb::Type;
+
+use super::influxdb::{Measurement, Tags};
+
+/// The prefix of the names of all exposed metrics.
+const PREFIX: &str = "xaynet_";
+
+/// The tag of the round id, which isn't exposed as label. Every round would start new time series
+/// otherwise. The current round is exposed by the `round_total_number` gauge instead.
+const ROUND_ID_TAG: &str = "round_id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The Prometheus metric types.
+enum Kind {
+    /// A value which only increases, e.g. the number of processed messages.
+    Counter,
+    /// A value which can arbitrarily change, e.g. the current phase.
+    Gauge,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Counter => "counter",
+            Kind::Gauge => "gauge",
+        }
+    }
+}
+
+impl From<&Measurement> for Kind {
+    fn from(measurement: &Measurement) -> Self {
+        match measurement {
+            Measurement::MessageSum
+            | Measurement::MessageUpdate
+            | Measurement::MessageSum2
+            | Measurement::MessageDiscarded
+            | Measurement::MessageRejected
+            | Measurement::MessageRateLimited
+            | Measurement::MessageTooLarge
+            | Measurement::MessageNotAdmitted
+            | Measurement::MultipartMessageCompleted
+            | Measurement::MultipartMessageExpired
+            | Measurement::MultipartMessageEvicted
+            | Measurement::SettingsReloaded
+            | Measurement::SettingsRejected => Kind::Counter,
+            Measurement::RoundParamSum
+            | Measurement::RoundParamUpdate
+            | Measurement::Phase
+            | Measurement::MasksTotalNumber
+            | Measurement::RoundTotalNumber
+            | Measurement::PrivacyBudgetEpsilon
+            | Measurement::PrivacyBudgetDelta => Kind::Gauge,
+        }
+    }
+}
+
+/// A time series identified by its metric name and its labels.
+type Series = (String, Vec<(String, String)>);
+
+/// A Prometheus metrics / events recorder.
+///
+/// The recorded metrics are kept in memory and exposed in the Prometheus text format via the
+/// `/metrics` endpoint of the REST API. Events are counted per title.
+#[derive(Debug, Default)]
+pub struct Recorder {
+    samples: Mutex<BTreeMap<Series, (Kind, f64)>>,
+}
+
+impl Recorder {
+    /// Creates a new Prometheus recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a new metric.
+    ///
+    /// Counters are incremented by the value, whereas gauges are set to the value. Non-numerical
+    /// values and the round id tag are ignored.
+    pub fn metric<V>(&self, measurement: Measurement, value: V, tags: Option<Tags>)
+    where
+        V: Into<Type>,
+    {
+        let value = match into_f64(value.into()) {
+            Some(value) => value,
+            None => return,
+        };
+        let kind = Kind::from(&measurement);
+        let measurement: &str = (&measurement).into();
+        let name = match kind {
+            Kind::Counter => format!("{}{}_total", PREFIX, measurement),
+            Kind::Gauge => format!("{}{}", PREFIX, measurement),
+        };
+        let mut labels = tags
+            .map(|tags| {
+                tags.into_inner()
+                    .into_iter()
+                    .filter(|(key, _)| key != ROUND_ID_TAG)
+                    .map(|(key, value)| (key, into_string(value)))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        labels.sort();
+
+        self.record((name, labels), kind, value);
+    }
+
+    /// Records a new event.
+    ///
+    /// Only the title of the event is exposed as label of the event counter.
+    pub fn event<T>(&self, title: T, _description: Option<&str>, _tags: Option<&[&str]>)
+    where
+        T: Into<String>,
+    {
+        let name = format!("{}events_total", PREFIX);
+        let labels = vec![("title".to_string(), title.into())];
+        self.record((name, labels), Kind::Counter, 1.);
+    }
+
+    fn record(&self, series: Series, kind: Kind, value: f64) {
+        let mut samples = self.samples.lock().unwrap();
+        let sample = samples.entry(series).or_insert((kind, 0.));
+        match kind {
+            Kind::Counter => sample.1 += value,
+            Kind::Gauge => sample.1 = value,
+        }
+    }
+
+    /// Renders the recorded metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let samples = self.samples.lock().unwrap();
+        let mut rendered = String::new();
+        let mut last_name = None;
+        for ((name, labels), (kind, value)) in samples.iter() {
+            // samples are sorted by name, hence each type hint is rendered once
+            if last_name != Some(name) {
+                let _ = writeln!(rendered, "# TYPE {} {}", name, kind.as_str());
+                last_name = Some(name);
+            }
+            rendered.push_str(name);
+            if !labels.is_empty() {
+                let labels = labels
+                    .iter()
+                    .map(|(key, value)| format!("{}=\"{}\"", key, escape(value)))
+                    .collect::<Vec<_>>();
+                let _ = write!(rendered, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(rendered, " {}", value);
+        }
+        rendered
+    }
+}
+
+fn into_f64(value: Type) -> Option<f64> {
+    match value {
+        Type::Boolean(value) => Some(if value { 1. } else { 0. }),
+        Type::Float(value) => Some(value),
+        Type::SignedInteger(value) => Some(value as f64),
+        Type::UnsignedInteger(value) => Some(value as f64),
+        Type::Text(_) => None,
+    }
+}
+
+fn into_string(value: Type) -> String {
+    match value {
+        Type::Boolean(value) => value.to_string(),
+        Type::Float(value) => value.to_string(),
+        Type::SignedInteger(value) => value.to_string(),
+        Type::UnsignedInteger(value) => value.to_string(),
+        Type::Text(value) => value,
+    }
+}
+
+/// Escapes a label value as required by the Prometheus text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(round_id: u64, phase: u8) -> Tags {
+        let mut tags = Tags::new();
+        tags.add(ROUND_ID_TAG, round_id);
+        tags.add("phase", phase);
+        tags
+    }
+
+    #[test]
+    fn test_gauge() {
+        let recorder = Recorder::new();
+        recorder.metric(Measurement::Phase, 1, None);
+        recorder.metric(Measurement::Phase, 2, None);
+        assert_eq!(
+            recorder.render(),
+            "# TYPE xaynet_phase gauge\nxaynet_phase 2\n"
+        );
+    }
+
+    #[test]
+    fn test_counter_with_tags() {
+        let recorder = Recorder::new();
+        recorder.metric(Measurement::MessageSum, 1, Some(tags(1, 1)));
+        recorder.metric(Measurement::MessageSum, 1, Some(tags(1, 1)));
+        recorder.metric(Measurement::MessageSum, 1, Some(tags(2, 1)));
+        recorder.metric(Measurement::MessageSum, 1, Some(tags(2, 2)));
+        assert_eq!(
+            recorder.render(),
+            "# TYPE xaynet_message_sum_total counter\n\
+             xaynet_message_sum_total{phase=\"1\"} 3\n\
+             xaynet_message_sum_total{phase=\"2\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn test_float_gauge() {
+        let recorder = Recorder::new();
+        recorder.metric(Measurement::RoundParamSum, 0.25, Some(tags(1, 0)));
+        assert_eq!(
+            recorder.render(),
+            "# TYPE xaynet_round_param_sum gauge\n\
+             xaynet_round_param_sum{phase=\"0\"} 0.25\n"
+        );
+    }
+
+    #[test]
+    fn test_text_value_is_ignored() {
+        let recorder = Recorder::new();
+        recorder.metric(Measurement::Phase, "sum", None);
+        assert_eq!(recorder.render(), "");
+    }
+
+    #[test]
+    fn test_event() {
+        let recorder = Recorder::new();
+        recorder.event("Phase error", Some("something went wrong"), None);
+        recorder.event("Phase error", None, None);
+        recorder.event("Settings \"rejected\"", None, None);
+        assert_eq!(
+            recorder.render(),
+            "# TYPE xaynet_events_total counter\n\
+             xaynet_events_total{title=\"Phase error\"} 2\n\
+             xaynet_events_total{title=\"Settings \\\"rejected\\\"\"} 1\n"
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! Streamed, compressed and ranged downloads of the global model.
+//!
+//! See the [rest module] documentation since this is a private module anyways.
+//!
+//! [rest module]: ../index.html
+
+use std::{io, ops::Range, sync::Arc};
+
+use async_compression::tokio_02::bufread::{GzipEncoder, ZstdEncoder};
+use bytes::Bytes;
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    Stream,
+    StreamExt,
+};
+use warp::{
+    http::{
+        header::{
+            HeaderValue,
+            ACCEPT_RANGES,
+            CONTENT_ENCODING,
+            CONTENT_LENGTH,
+            CONTENT_RANGE,
+            CONTENT_TYPE,
+        },
+        Response,
+        StatusCode,
+    },
+    hyper::Body,
+};
+
+use tokio::io::{reader_stream, stream_reader};
+use xaynet_core::mask::Model;
+
+/// The size of the chunks in which the model is streamed.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The size of the length prefix of the bincode serialization of a model.
+const LENGTH_PREFIX_SIZE: u64 = 8;
+
+/// The content coding of a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Identity,
+    Gzip,
+    Zstd,
+}
+
+impl Encoding {
+    /// Negotiates the content coding from the codings of an `Accept-Encoding` header.
+    ///
+    /// The supported coding with the highest quality value wins, the first one in case of a tie.
+    /// Codings with a quality value of 0 or an invalid one aren't acceptable. Defaults to no
+    /// compression.
+    pub fn negotiate(accept_encoding: Option<&str>) -> Self {
+        accept_encoding
+            .into_iter()
+            .flat_map(|accept_encoding| accept_encoding.split(','))
+            .filter_map(|coding| {
+                let mut params = coding.split(';');
+                let encoding = match params.next()?.trim() {
+                    "zstd" => Encoding::Zstd,
+                    "gzip" => Encoding::Gzip,
+                    "identity" => Encoding::Identity,
+                    _ => return None,
+                };
+                let quality = match params.map(str::trim).find(|param| param.starts_with("q=")) {
+                    Some(param) => param[2..].parse::<f32>().ok()?,
+                    None => 1.,
+                };
+                Some((encoding, quality))
+            })
+            .filter(|(_, quality)| *quality > 0.)
+            .fold(None, |best, (encoding, quality)| match best {
+                Some((_, best_quality)) if best_quality >= quality => best,
+                _ => Some((encoding, quality)),
+            })
+            .map_or(Encoding::Identity, |(encoding, _)| encoding)
+    }
+
+    /// Gets the name of the content coding, if the content is compressed.
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            Encoding::Identity => None,
+            Encoding::Gzip => Some("gzip"),
+            Encoding::Zstd => Some("zstd"),
+        }
+    }
+
+    /// Encodes a stream of chunks.
+    fn encode<S>(self, chunks: S) -> BoxStream<'static, io::Result<Bytes>>
+    where
+        S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+    {
+        match self {
+            Encoding::Identity => chunks.boxed(),
+            Encoding::Gzip => reader_stream(GzipEncoder::new(stream_reader(chunks))).boxed(),
+            Encoding::Zstd => reader_stream(ZstdEncoder::new(stream_reader(chunks))).boxed(),
+        }
+    }
+
+    /// Replies with the encoded body of a response.
+    pub fn reply(self, response: Response<Vec<u8>>) -> Response<Body> {
+        let (mut parts, body) = response.into_parts();
+        let body = match self.name() {
+            Some(name) => {
+                parts
+                    .headers
+                    .insert(CONTENT_ENCODING, HeaderValue::from_static(name));
+                parts.headers.remove(CONTENT_LENGTH);
+                let chunks = stream::once(future::ready(Ok(Bytes::from(body))));
+                Body::wrap_stream(self.encode(chunks))
+            }
+            None => Body::from(body),
+        };
+        Response::from_parts(parts, body)
+    }
+}
+
+/// The byte range requested by a `Range` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ByteRange {
+    /// The whole content is requested.
+    Full,
+    /// A part of the content is requested.
+    Partial(Range<u64>),
+    /// The requested range lies outside of the content.
+    Unsatisfiable,
+}
+
+impl ByteRange {
+    /// Parses the byte range of a `Range` header for content of the given length.
+    ///
+    /// Only single byte ranges are supported, the whole content is served for any other range.
+    pub fn parse(range: Option<&str>, len: u64) -> Self {
+        let spec = match range.and_then(|range| range.trim().strip_prefix("bytes=")) {
+            Some(spec) if !spec.contains(',') => spec.trim(),
+            _ => return ByteRange::Full,
+        };
+        let (start, end) = match spec.find('-') {
+            Some(dash) => (&spec[..dash], &spec[dash + 1..]),
+            None => return ByteRange::Full,
+        };
+        let range = match (start.parse::<u64>(), end.parse::<u64>()) {
+            // `bytes=first-last`
+            (Ok(first), Ok(last)) if first <= last => first..len.min(last + 1),
+            // `bytes=first-`
+            (Ok(first), Err(_)) if end.is_empty() => first..len,
+            // `bytes=-suffix`
+            (Err(_), Ok(suffix)) if start.is_empty() => len.saturating_sub(suffix)..len,
+            _ => return ByteRange::Full,
+        };
+        if range.start < range.end {
+            ByteRange::Partial(range)
+        } else {
+            ByteRange::Unsatisfiable
+        }
+    }
+}
+
+/// The bincode serialization of a model, which is produced in chunks.
+pub struct SerializedModel {
+    model: Arc<Model>,
+    len: u64,
+}
+
+impl SerializedModel {
+    /// Creates the serialization of a model.
+    pub fn new(model: Arc<Model>) -> Self {
+        let weights = model
+            .iter()
+            // safe unwrap: the size of a weight is always computable
+            .map(|weight| bincode::serialized_size(weight).unwrap())
+            .sum::<u64>();
+        Self {
+            model,
+            len: LENGTH_PREFIX_SIZE + weights,
+        }
+    }
+
+    /// Gets the length of the serialization in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Streams the given range of the serialization in chunks.
+    ///
+    /// The weights are serialized one after another as the chunks are polled and weights before
+    /// the range are skipped without being serialized.
+    pub fn stream(&self, range: Range<u64>) -> impl Stream<Item = io::Result<Bytes>> + Send {
+        stream::iter(Chunks {
+            model: self.model.clone(),
+            range,
+            position: 0,
+            next: None,
+        })
+        .map(Ok)
+    }
+}
+
+/// An iterator over the chunks of a range of the serialization of a model.
+struct Chunks {
+    model: Arc<Model>,
+    range: Range<u64>,
+    /// The position of the next item in the serialization.
+    position: u64,
+    /// The index of the next weight, or `None` if the length prefix is next.
+    next: Option<usize>,
+}
+
+impl Chunks {
+    /// Gets the serialization of the next item if it overlaps with the range, along with its
+    /// position. Returns `None` if the serialization is exhausted.
+    fn next_item(&mut self) -> Option<(u64, Option<Vec<u8>>)> {
+        let (size, item) = match self.next {
+            None => {
+                self.next = Some(0);
+                let prefix = (self.model.len() as u64).to_le_bytes().to_vec();
+                (LENGTH_PREFIX_SIZE, Some(prefix))
+            }
+            Some(index) if index < self.model.len() => {
+                self.next = Some(index + 1);
+                let weight = &self.model[index];
+                // safe unwrap: the size of a weight is always computable
+                let size = bincode::serialized_size(weight).unwrap();
+                let item = if self.position + size > self.range.start {
+                    // safe unwrap: weights are always serializable
+                    Some(bincode::serialize(weight).unwrap())
+                } else {
+                    None
+                };
+                (size, item)
+            }
+            Some(_) => return None,
+        };
+        let position = self.position;
+        self.position += size;
+        Some((position, item))
+    }
+}
+
+impl Iterator for Chunks {
+    type Item = Bytes;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+        while chunk.len() < CHUNK_SIZE && self.position < self.range.end {
+            let (position, item) = match self.next_item() {
+                Some((position, Some(item))) => (position, item),
+                Some((_, None)) => continue,
+                None => break,
+            };
+            let start = self.range.start.max(position);
+            let end = self.range.end.min(position + item.len() as u64);
+            if start < end {
+                let (start, end) = ((start - position) as usize, (end - position) as usize);
+                chunk.extend_from_slice(&item[start..end]);
+            }
+        }
+        Some(Bytes::from(chunk)).filter(|chunk| !chunk.is_empty())
+    }
+}
+
+/// Replies with the bincode serialization of a model.
+///
+/// The whole model is encoded with the content coding, while a byte range of the model is never
+/// encoded, so that a partially downloaded model can be resumed.
+pub fn reply_model(model: Arc<Model>, encoding: Encoding, range: Option<&str>) -> Response<Body> {
+    let model = SerializedModel::new(model);
+    let len = model.len();
+    let response = Response::builder()
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .header(ACCEPT_RANGES, "bytes");
+    match ByteRange::parse(range, len) {
+        ByteRange::Full => match encoding.name() {
+            Some(name) => response
+                .header(CONTENT_ENCODING, name)
+                .status(StatusCode::OK)
+                .body(Body::wrap_stream(encoding.encode(model.stream(0..len))))
+                .unwrap(),
+            None => response
+                .header(CONTENT_LENGTH, len)
+                .status(StatusCode::OK)
+                .body(Body::wrap_stream(model.stream(0..len)))
+                .unwrap(),
+        },
+        ByteRange::Partial(range) => response
+            .header(
+                CONTENT_RANGE,
+                format!("bytes {}-{}/{}", range.start, range.end - 1, len),
+            )
+            .header(CONTENT_LENGTH, range.end - range.start)
+            .status(StatusCode::PARTIAL_CONTENT)
+            .body(Body::wrap_stream(model.stream(range)))
+            .unwrap(),
+        ByteRange::Unsatisfiable => response
+            .header(CONTENT_RANGE, format!("bytes */{}", len))
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .body(Body::empty())
+            .unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+    use xaynet_core::mask::FromPrimitives;
+
+    fn model() -> Arc<Model> {
+        let weights = (0..10_000).map(|weight| weight as f32 / 3.);
+        Arc::new(Model::from_primitives(weights).unwrap())
+    }
+
+    async fn collect(stream: impl Stream<Item = io::Result<Bytes>>) -> Vec<u8> {
+        stream
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>()
+            .await
+            .concat()
+    }
+
+    #[tokio::test]
+    async fn test_serialized_model() {
+        let model = model();
+        let bytes = bincode::serialize(model.as_ref()).unwrap();
+        let serialized = SerializedModel::new(model);
+        assert_eq!(serialized.len(), bytes.len() as u64);
+
+        let len = serialized.len();
+        assert_eq!(collect(serialized.stream(0..len)).await, bytes);
+        for range in [0..1, 3..20, 100..len, 12_345..54_321, len - 1..len]
+            .iter()
+            .cloned()
+        {
+            let expected = &bytes[range.start as usize..range.end as usize];
+            assert_eq!(collect(serialized.stream(range)).await, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(ByteRange::parse(None, 100), ByteRange::Full);
+        assert_eq!(
+            ByteRange::parse(Some("bytes=10-19"), 100),
+            ByteRange::Partial(10..20)
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=90-200"), 100),
+            ByteRange::Partial(90..100)
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=50-"), 100),
+            ByteRange::Partial(50..100)
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=-10"), 100),
+            ByteRange::Partial(90..100)
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=100-"), 100),
+            ByteRange::Unsatisfiable
+        );
+        assert_eq!(
+            ByteRange::parse(Some("bytes=0-1,5-9"), 100),
+            ByteRange::Full
+        );
+        assert_eq!(ByteRange::parse(Some("items=0-1"), 100), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_negotiate_encoding() {
+        assert_eq!(Encoding::negotiate(None), Encoding::Identity);
+        assert_eq!(Encoding::negotiate(Some("gzip, deflate")), Encoding::Gzip);
+        assert_eq!(
+            Encoding::negotiate(Some("br, zstd;q=0.9, gzip")),
+            Encoding::Gzip
+        );
+        assert_eq!(
+            Encoding::negotiate(Some("gzip;q=0.5, zstd;q=0.8, br")),
+            Encoding::Zstd
+        );
+        assert_eq!(Encoding::negotiate(Some("zstd, gzip")), Encoding::Zstd);
+        assert_eq!(
+            Encoding::negotiate(Some("gzip;q=0, br")),
+            Encoding::Identity
+        );
+        assert_eq!(
+            Encoding::negotiate(Some("zstd;q=invalid, gzip;q=0.1")),
+            Encoding::Gzip
+        );
+        assert_eq!(Encoding::negotiate(Some("br")), Encoding::Identity);
+    }
+
+    #[tokio::test]
+    async fn test_encode() {
+        let model = model();
+        let bytes = bincode::serialize(model.as_ref()).unwrap();
+        let serialized = SerializedModel::new(model);
+        let len = serialized.len();
+        let compressed = collect(Encoding::Gzip.encode(serialized.stream(0..len))).await;
+        assert!(compressed.len() < bytes.len());
+
+        let mut decompressed = Vec::new();
+        async_compression::tokio_02::bufread::GzipDecoder::new(&compressed[..])
+            .read_to_end(&mut decompressed)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, bytes);
+    }
+}
@@ -0,0 +1,156 @@
+//! Server-sent events of the REST API.
+//!
+//! See the [rest module] documentation since this is a private module anyways.
+//!
+//! [rest module]: ../index.html
+
+use std::convert::Infallible;
+
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    Stream,
+    StreamExt,
+};
+use warp::sse::ServerSentEvent;
+
+use crate::state_machine::events::{DictionaryUpdate, EventSubscriber, ModelUpdate};
+use xaynet_core::common::RoundEvent;
+
+/// Creates a stream of the [`RoundEvent`]s which are pushed to participants.
+///
+/// The stream starts with the events of the current state of the coordinator, i.e. the current
+/// round and phase as well as the dictionaries and the global model if they are available.
+pub fn round_events(event_subscriber: &EventSubscriber) -> impl Stream<Item = RoundEvent> {
+    let params = event_subscriber
+        .params_listener()
+        .map(|event| RoundEvent::NewRound {
+            round_id: event.round_id,
+        })
+        .boxed();
+    let phase = event_subscriber
+        .phase_listener()
+        .map(|event| RoundEvent::Phase {
+            round_id: event.round_id,
+            phase: event.event.to_string(),
+        })
+        .boxed();
+    let sum_dict = event_subscriber
+        .sum_dict_listener()
+        .filter_map(|event| {
+            future::ready(match event.event {
+                DictionaryUpdate::New(_) => Some(RoundEvent::SumDict {
+                    round_id: event.round_id,
+                }),
+                DictionaryUpdate::Invalidate => None,
+            })
+        })
+        .boxed();
+    let seed_dict = event_subscriber
+        .seed_dict_listener()
+        .filter_map(|event| {
+            future::ready(match event.event {
+                DictionaryUpdate::New(_) => Some(RoundEvent::SeedDict {
+                    round_id: event.round_id,
+                }),
+                DictionaryUpdate::Invalidate => None,
+            })
+        })
+        .boxed();
+    let model = event_subscriber
+        .model_listener()
+        .filter_map(|event| {
+            future::ready(match event.event {
+                ModelUpdate::New { .. } => Some(RoundEvent::Model {
+                    round_id: event.round_id,
+                }),
+                ModelUpdate::Invalidate => None,
+            })
+        })
+        .boxed();
+
+    let streams: Vec<BoxStream<'static, RoundEvent>> =
+        vec![params, phase, sum_dict, seed_dict, model];
+    stream::select_all(streams)
+}
+
+/// Converts a [`RoundEvent`] into a server-sent event.
+///
+/// The name of the server-sent event is the name of the round event and its data is the JSON
+/// representation of the round event.
+pub fn server_sent_event(
+    event: RoundEvent,
+) -> Result<impl ServerSentEvent + Send + 'static, Infallible> {
+    // safe unwrap: round events are always serializable
+    let data = serde_json::to_string(&event).unwrap();
+    Ok((warp::sse::event(event.name()), warp::sse::data(data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_machine::{events::EventPublisher, phases::PhaseName};
+    use xaynet_core::{
+        common::{RoundParameters, RoundSeed},
+        crypto::{ByteObject, EncryptKeyPair},
+        mask::{
+            BoundType,
+            DataType,
+            GroupType,
+            MaskConfig,
+            ModelType,
+            QuantizationType,
+            SparsityType,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_round_events() {
+        sodiumoxide::init().unwrap();
+        let keys = EncryptKeyPair::generate();
+        let mask_config = MaskConfig {
+            group_type: GroupType::Integer,
+            data_type: DataType::F32,
+            bound_type: BoundType::B0,
+            model_type: ModelType::M3,
+            sparsity_type: SparsityType::S0,
+            quantization_type: QuantizationType::Qmax,
+        };
+        let params = RoundParameters {
+            pk: keys.public,
+            sum: 0.0,
+            update: 0.0,
+            seed: RoundSeed::generate(),
+            mask_config: mask_config.into(),
+            model_length: 0,
+            seed_sharing_threshold: None,
+            model_delta: false,
+        };
+        let (mut publisher, subscriber) =
+            EventPublisher::init(1, keys, params, PhaseName::Idle, ModelUpdate::Invalidate);
+        let mut events = round_events(&subscriber);
+
+        let mut current = vec![events.next().await.unwrap(), events.next().await.unwrap()];
+        current.sort_by_key(|event| event.name());
+        assert_eq!(
+            current,
+            vec![
+                RoundEvent::NewRound { round_id: 1 },
+                RoundEvent::Phase {
+                    round_id: 1,
+                    phase: "Idle".to_string()
+                },
+            ]
+        );
+
+        publisher.set_round_id(2);
+        publisher.broadcast_phase(PhaseName::Sum);
+        assert_eq!(
+            events.next().await.unwrap(),
+            RoundEvent::Phase {
+                round_id: 2,
+                phase: "Sum".to_string()
+            }
+        );
+    }
+}
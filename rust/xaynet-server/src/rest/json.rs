@@ -0,0 +1,153 @@
+//! JSON representations of the data served by the REST API.
+//!
+//! See the [rest module] documentation since this is a private module anyways.
+//!
+//! [rest module]: ../index.html
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use xaynet_core::{
+    common::RoundParameters,
+    crypto::ByteObject,
+    mask::{DataType, IntoPrimitives, MaskConfigPair, Model, ModelCastError},
+    SumDict,
+    UpdateSeedDict,
+};
+
+/// Encodes a byte object as base64 string.
+fn encode<B: ByteObject>(bytes: &B) -> String {
+    base64::encode(bytes.as_slice())
+}
+
+/// The JSON representation of the [`RoundParameters`].
+///
+/// Keys and seeds are encoded as base64 strings.
+#[derive(Debug, Serialize)]
+pub struct JsonRoundParameters {
+    /// The public key of the coordinator used for encryption.
+    pub pk: String,
+    /// Fraction of participants to be selected for the sum task.
+    pub sum: f64,
+    /// Fraction of participants to be selected for the update task.
+    pub update: f64,
+    /// The random round seed.
+    pub seed: String,
+    /// The masking configuration.
+    pub mask_config: MaskConfigPair,
+    /// The length of the model.
+    pub model_length: usize,
+    /// Fraction of sum participants whose shares are required to reconstruct a mask seed.
+    pub seed_sharing_threshold: Option<f64>,
+    /// Whether the participants mask the difference to the previous global model.
+    pub model_delta: bool,
+}
+
+impl From<&RoundParameters> for JsonRoundParameters {
+    fn from(params: &RoundParameters) -> Self {
+        Self {
+            pk: encode(&params.pk),
+            sum: params.sum,
+            update: params.update,
+            seed: encode(&params.seed),
+            mask_config: params.mask_config,
+            model_length: params.model_length,
+            seed_sharing_threshold: params.seed_sharing_threshold,
+            model_delta: params.model_delta,
+        }
+    }
+}
+
+/// The JSON representation of a [`SumDict`].
+///
+/// The public signing keys of the sum participants are mapped to their ephemeral public
+/// encryption keys, all encoded as base64 strings.
+pub type JsonSumDict = BTreeMap<String, String>;
+
+/// Converts a [`SumDict`] into its JSON representation.
+pub fn sum_dict(dict: &SumDict) -> JsonSumDict {
+    dict.iter()
+        .map(|(pk, ephm_pk)| (encode(pk), encode(ephm_pk)))
+        .collect()
+}
+
+/// The JSON representation of an [`UpdateSeedDict`].
+///
+/// The public signing keys of the update participants are mapped to their encrypted mask
+/// seeds, all encoded as base64 strings.
+pub type JsonUpdateSeedDict = BTreeMap<String, String>;
+
+/// Converts an [`UpdateSeedDict`] into its JSON representation.
+pub fn update_seed_dict(dict: &UpdateSeedDict) -> JsonUpdateSeedDict {
+    dict.iter()
+        .map(|(pk, seed)| (encode(pk), encode(seed)))
+        .collect()
+}
+
+/// The JSON representation of a [`Model`].
+///
+/// The weights are cast to the data type of the masking configuration of the round.
+#[derive(Debug, Serialize)]
+#[serde(tag = "data_type", content = "weights")]
+pub enum JsonModel {
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+}
+
+impl JsonModel {
+    /// Converts a [`Model`] into its JSON representation.
+    ///
+    /// # Errors
+    /// Fails if a weight can't be cast to the data type.
+    pub fn new(model: &Model, data_type: DataType) -> Result<Self, ModelCastError> {
+        Ok(match data_type {
+            DataType::F32 => Self::F32(model.to_primitives().collect::<Result<_, _>>()?),
+            DataType::F64 => Self::F64(model.to_primitives().collect::<Result<_, _>>()?),
+            DataType::I32 => Self::I32(model.to_primitives().collect::<Result<_, _>>()?),
+            DataType::I64 => Self::I64(model.to_primitives().collect::<Result<_, _>>()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xaynet_core::{
+        crypto::{EncryptKeyPair, SigningKeyPair},
+        mask::FromPrimitives,
+    };
+
+    #[test]
+    fn test_sum_dict() {
+        sodiumoxide::init().unwrap();
+        let pk = SigningKeyPair::generate().public;
+        let ephm_pk = EncryptKeyPair::generate().public;
+        let dict = vec![(pk, ephm_pk)].into_iter().collect::<SumDict>();
+
+        let json = sum_dict(&dict);
+        assert_eq!(json.len(), 1);
+        assert_eq!(
+            json[&base64::encode(pk.as_slice())],
+            base64::encode(ephm_pk.as_slice())
+        );
+    }
+
+    #[test]
+    fn test_model() {
+        let model = Model::from_primitives(vec![1_i32, -2, 3].into_iter()).unwrap();
+        let json = serde_json::to_value(JsonModel::new(&model, DataType::I32).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "data_type": "I32", "weights": [1, -2, 3] })
+        );
+
+        let json = serde_json::to_value(JsonModel::new(&model, DataType::F64).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "data_type": "F64", "weights": [1., -2., 3.] })
+        );
+    }
+}
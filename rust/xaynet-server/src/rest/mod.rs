@@ -0,0 +1,1411 @@
+//! A HTTP API for the PET protocol interactions.
+//!
+//! The round parameters, dictionaries and global model are served as bincode by default. Clients
+//! which prefer `application/json` in their `Accept` header get documented JSON representations
+//! instead, see the OpenAPI document served at `/openapi.json`.
+//!
+//! The dictionaries and global model are served with an `ETag`. Clients which send it back in
+//! the `If-None-Match` header of the next request get an empty `304 Not Modified` response if the
+//! data didn't change in the meantime. The entity tags change whenever the coordinator restarts,
+//! because the round ids which identify the data may repeat afterwards. All data responses depend
+//! on the `Accept` header, which is indicated by their `Vary` header.
+//!
+//! The bincode representation of the global model is streamed and can be compressed with gzip or
+//! zstd as negotiated by the `Accept-Encoding` header. Clients on flaky networks can resume a
+//! partially downloaded model via a `Range` header, in which case the model isn't compressed.
+//!
+//! Participants which don't want to poll the data can subscribe to the round events served as
+//! server-sent events at `/events`. Every event is named after the kind of the [`RoundEvent`]
+//! and its data is the JSON representation of the event.
+//!
+//! Besides the default task, whose routes are served at the root, the coordinator can run
+//! additional federated learning tasks. Their routes are scoped under `/tasks/{name}`, e.g.
+//! `/tasks/{name}/params` or `/tasks/{name}/admin/status`.
+//!
+//! PET messages which exceed the configured [`LimitSettings`] are refused with a `413 Payload Too
+//! Large` or `429 Too Many Requests` response. The body of a request is read only up to the
+//! maximum size of an encrypted message, the remaining limits are enforced while the message is
+//! handled. A `429 Too Many Requests` response comes with a `Retry-After` header.
+//!
+//! If a PET message can't be handled, the kind of the error is named in the `X-Xaynet-Error-Kind`
+//! header of the response. Its body is the bincode representation of the [`MessageError`] by
+//! default and the JSON representation if `application/json` is requested via the `Accept`
+//! header.
+//!
+//! If the [`AdmissionSettings`] are configured, participants which aren't allowed explicitly must
+//! present a credential in the `X-Xaynet-Credential` header of their PET messages. Credentials are
+//! issued via the admin API.
+//!
+//! [`AdmissionSettings`]: crate::settings::AdmissionSettings
+//! [`MessageError`]: xaynet_core::common::MessageError
+//! [`RoundEvent`]: xaynet_core::common::RoundEvent
+
+mod download;
+mod events;
+mod json;
+mod openapi;
+
+#[cfg(feature = "tls")]
+use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use bytes::Buf;
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sodiumoxide::utils::memcmp;
+use thiserror::Error;
+use tracing::{error, warn};
+use validator::Validate;
+use warp::{
+    filters::BoxedFilter,
+    http::{
+        header::{
+            HeaderMap,
+            HeaderName,
+            HeaderValue,
+            ACCEPT_ENCODING,
+            ETAG,
+            IF_NONE_MATCH,
+            IF_RANGE,
+            RANGE,
+            RETRY_AFTER,
+            VARY,
+        },
+        Response,
+        StatusCode,
+    },
+    hyper::Body,
+    reply::Reply,
+    Filter,
+};
+#[cfg(feature = "tls")]
+use warp::{Server, TlsServer};
+
+use self::{
+    download::Encoding,
+    json::{JsonModel, JsonRoundParameters},
+};
+use crate::{
+    metric,
+    metrics::{GlobalRecorder, Measurement, Recorder},
+    services::{
+        admission::Admission,
+        fetchers::{FetchError, Fetcher},
+        messages::{PetMessageHandler, ServiceError},
+        TaskServices,
+    },
+    settings::{ApiSettings, LimitSettings, PetSettings},
+    state_machine::{
+        admin::{AdminHandle, MessageCounters},
+        events::EventListener,
+        phases::PhaseName,
+    },
+};
+use xaynet_core::{
+    common::{MessageError, MessageErrorKind},
+    crypto::ByteObject,
+    ParticipantPublicKey,
+};
+
+/// The epoch of the entity tags, which is random for every start of the coordinator.
+static ETAG_EPOCH: Lazy<String> = Lazy::new(|| format!("{:016x}", rand::random::<u64>()));
+
+/// The header which names the kind of the error of a PET message which failed to be handled.
+const ERROR_KIND: &str = "x-xaynet-error-kind";
+
+/// The representation of the data requested by a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Bincode,
+    Json,
+}
+
+impl Format {
+    /// Negotiates the format from the media types of an `Accept` header.
+    ///
+    /// The first supported media type wins, quality values are not taken into account. Defaults
+    /// to bincode.
+    fn negotiate(accept: Option<&str>) -> Self {
+        accept
+            .into_iter()
+            .flat_map(|accept| accept.split(','))
+            .filter_map(|media_range| media_range.split(';').next())
+            .find_map(|media_type| match media_type.trim() {
+                "application/octet-stream" => Some(Format::Bincode),
+                "application/json" => Some(Format::Json),
+                _ => None,
+            })
+            .unwrap_or(Format::Bincode)
+    }
+
+    /// Creates the entity tag of data with the given tag in this format.
+    ///
+    /// The representations of the data differ, hence each format gets its own entity tag.
+    fn etag(self, tag: &str) -> String {
+        let suffix = match self {
+            Format::Bincode => "bincode",
+            Format::Json => "json",
+        };
+        format!("\"{}-{}-{}\"", *ETAG_EPOCH, tag, suffix)
+    }
+
+    /// Replies with the data in this format.
+    ///
+    /// The JSON representation is only created if it is requested.
+    fn reply<B, J>(self, bincode: &B, json: impl FnOnce() -> J) -> Response<Vec<u8>>
+    where
+        B: Serialize + ?Sized,
+        J: Serialize,
+    {
+        match self {
+            Format::Bincode => reply_bincode(bincode),
+            Format::Json => reply_json(&json()),
+        }
+    }
+}
+
+/// Replies with the bincode representation of the data.
+fn reply_bincode<T: Serialize + ?Sized>(data: &T) -> Response<Vec<u8>> {
+    Response::builder()
+        .header("Content-Type", "application/octet-stream")
+        .status(StatusCode::OK)
+        .body(bincode::serialize(data).unwrap())
+        .unwrap()
+}
+
+/// Indicates that the response depends on the `Accept` header.
+fn vary_accept<B>(mut response: Response<B>) -> Response<B> {
+    response
+        .headers_mut()
+        .insert(VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+/// Checks whether the entity tag matches any of the entity tags of an `If-None-Match` header.
+///
+/// Weak entity tags are compared as if they were strong ones.
+fn matches_etag(if_none_match: Option<&str>, etag: &str) -> bool {
+    if_none_match
+        .into_iter()
+        .flat_map(|if_none_match| if_none_match.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Replies with the tagged data in the given format, or with `304 Not Modified` if the client
+/// already has the data.
+fn reply_tagged<B: Default>(
+    format: Format,
+    tag: &str,
+    if_none_match: Option<&str>,
+    reply: impl FnOnce() -> Response<B>,
+) -> Response<B> {
+    let etag = format.etag(tag);
+    let mut response = if matches_etag(if_none_match, &etag) {
+        Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .body(B::default())
+            .unwrap()
+    } else {
+        reply()
+    };
+    if response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED {
+        // safe unwrap: the tags of the fetchers are visible ASCII
+        response
+            .headers_mut()
+            .insert(ETAG, HeaderValue::from_str(&etag).unwrap());
+    }
+    response
+}
+
+/// Replies with the JSON representation of the data.
+fn reply_json<T: Serialize>(data: &T) -> Response<Vec<u8>> {
+    Response::builder()
+        .header("Content-Type", "application/json")
+        .status(StatusCode::OK)
+        .body(serde_json::to_vec(data).unwrap())
+        .unwrap()
+}
+
+#[derive(Deserialize, Serialize)]
+struct SeedDictQuery {
+    pk: String,
+}
+
+/// The status of the coordinator as reported by the admin API.
+#[derive(Serialize)]
+struct AdminStatus {
+    round_id: u64,
+    phase: PhaseName,
+    paused: bool,
+    pending_pet_settings: bool,
+    messages: HashMap<PhaseName, MessageCounters>,
+}
+
+/// A request of the admin API to issue a credential.
+#[derive(Deserialize)]
+struct CredentialRequest {
+    /// The base64 encoded public signing key of the participant.
+    participant_pk: String,
+    /// The organization of the participant.
+    organization: String,
+    /// The expiry time in seconds since the Unix epoch, if any.
+    expires_at: Option<u64>,
+}
+
+/// A credential issued by the admin API.
+#[derive(Serialize)]
+struct CredentialResponse {
+    credential: String,
+}
+
+/// Starts a HTTP server at the given address, listening to GET requests for
+/// data and POST requests containing PET messages.
+///
+/// * `api_settings`: address of the server and optional certificate and key for TLS server
+///   authentication as well as trusted anchors for TLS client authentication.
+/// * `limit_settings`: maximum size of the body of the PET messages.
+/// * `default_task`: services of the default task, which are served at the root.
+/// * `tasks`: services of the additional tasks by name, which are served under `/tasks/{name}`.
+/// * `admission`: admission control of the participants, which issues the credentials of the
+///   admin API.
+///
+/// The admin API under `/admin` and `/tasks/{name}/admin` is only served if an admin token is
+/// configured, which must be presented as bearer token in the `Authorization` header of every
+/// admin request.
+/// # Errors
+/// Fails if the TLS settings are invalid.
+pub async fn serve<F>(
+    api_settings: ApiSettings,
+    limit_settings: LimitSettings,
+    default_task: TaskServices<F>,
+    tasks: HashMap<String, TaskServices<F>>,
+    admission: Option<Arc<Admission>>,
+) -> Result<(), RestError>
+where
+    F: Fetcher + Sync + Send + 'static + Clone,
+{
+    let routes = routes(
+        api_settings.admin_token.clone(),
+        limit_settings,
+        default_task,
+        tasks,
+        admission,
+    )
+    .recover(handle_reject)
+    .with(warp::log("http"));
+
+    #[cfg(not(feature = "tls"))]
+    return run_http(routes, api_settings)
+        .await
+        .map_err(RestError::from);
+    #[cfg(feature = "tls")]
+    return run_https(routes, api_settings).await;
+}
+
+/// Creates the routes of the REST API.
+///
+/// The admin routes are only matched if an `admin_token` is given.
+fn routes<F>(
+    admin_token: Option<String>,
+    limit_settings: LimitSettings,
+    default_task: TaskServices<F>,
+    tasks: HashMap<String, TaskServices<F>>,
+    admission: Option<Arc<Admission>>,
+) -> impl Filter<Extract = (impl Reply,), Error = warp::Rejection> + Clone
+where
+    F: Fetcher + Sync + Send + 'static + Clone,
+{
+    let max_size = limit_settings.max_message_size.max_encrypted_size();
+    let retry_after = retry_after(&limit_settings);
+    let admin_auth = with_admin_auth(admin_token).boxed();
+
+    let default_task_routes = task_routes(
+        warp::any().map(move || default_task.clone()).boxed(),
+        admin_auth.clone(),
+        max_size,
+        retry_after,
+    );
+
+    let tasks = Arc::new(tasks);
+    let named_task_routes = task_routes(
+        warp::path("tasks")
+            .and(warp::path::param())
+            .and_then(move |name: String| {
+                let task = tasks.get(&name).cloned();
+                async move { task.ok_or_else(warp::reject::not_found) }
+            })
+            .boxed(),
+        admin_auth.clone(),
+        max_size,
+        retry_after,
+    );
+
+    let openapi = warp::path!("openapi.json")
+        .and(warp::get())
+        .map(|| warp::reply::json(&openapi::document()));
+
+    let metrics = warp::path!("metrics")
+        .and(warp::get())
+        .and_then(handle_metrics);
+
+    let admin_credentials = warp::path!("admin" / "credentials")
+        .and(warp::post())
+        .and(admin_auth)
+        .and(warp::body::json())
+        .map(move |request: CredentialRequest| issue_credential(admission.as_deref(), request));
+
+    default_task_routes
+        .or(named_task_routes)
+        .or(metrics)
+        .or(openapi)
+        .or(admin_credentials)
+}
+
+/// Creates the routes of the PET protocol interactions and of the administration of a federated
+/// learning task.
+///
+/// The `task` filter matches the path prefix of the task and extracts its services. The
+/// `admin_auth` filter authenticates the admin requests. PET messages which exceed the `max_size`
+/// of an encrypted message are refused without reading them completely. Rate limited PET messages
+/// may be sent again after `retry_after` seconds.
+fn task_routes<F>(
+    task: BoxedFilter<(TaskServices<F>,)>,
+    admin_auth: BoxedFilter<()>,
+    max_size: Option<usize>,
+    retry_after: u64,
+) -> impl Filter<Extract = (impl Reply,), Error = warp::Rejection> + Clone
+where
+    F: Fetcher + Sync + Send + 'static + Clone,
+{
+    let message = task
+        .clone()
+        .and(warp::path!("message"))
+        .and(warp::post())
+        .and(warp::addr::remote())
+        .and(warp::header::optional::<String>("x-xaynet-credential"))
+        .and(warp::header::optional::<u64>("content-length"))
+        .and(warp::body::stream())
+        .and(with_format())
+        .and_then(
+            move |task: TaskServices<F>,
+                  addr: Option<SocketAddr>,
+                  credential,
+                  content_length,
+                  body,
+                  format| {
+                let handler = task.pet_message_handler;
+                let reply_error = MessageErrorReply {
+                    format,
+                    retry_after,
+                };
+                async move {
+                    match read_message_body(handler.task(), body, content_length, max_size).await {
+                        Ok(body) => {
+                            let ip = addr.map(|addr| addr.ip());
+                            Ok(handle_message(body, credential, ip, handler, reply_error).await)
+                        }
+                        Err(rejection) => match rejection.find::<MessageRefused>() {
+                            Some(MessageRefused(error)) => Ok(reply_error.reply(error)),
+                            None => Err(rejection),
+                        },
+                    }
+                }
+            },
+        );
+
+    let sum_dict = task
+        .clone()
+        .and(warp::path!("sums"))
+        .and(warp::get())
+        .and(with_format())
+        .and(with_if_none_match())
+        .and_then(|task: TaskServices<F>, format, if_none_match| {
+            handle_sums(format, if_none_match, task.fetcher)
+        });
+
+    let seed_dict = task
+        .clone()
+        .and(warp::path!("seeds"))
+        .and(warp::get())
+        .and(warp::query::<SeedDictQuery>().and_then(part_pk))
+        .and(with_format())
+        .and(with_if_none_match())
+        .and_then(|task: TaskServices<F>, pk, format, if_none_match| {
+            handle_seeds(pk, format, if_none_match, task.fetcher)
+        });
+
+    let dropout_dict = task
+        .clone()
+        .and(warp::path!("dropouts"))
+        .and(warp::get())
+        .and(with_format())
+        .and(with_if_none_match())
+        .and_then(|task: TaskServices<F>, format, if_none_match| {
+            handle_dropouts(format, if_none_match, task.fetcher)
+        });
+
+    let round_params = task
+        .clone()
+        .and(warp::path!("params"))
+        .and(warp::get())
+        .and(with_format())
+        .and_then(|task: TaskServices<F>, format| handle_params(format, task.fetcher));
+
+    let model = task
+        .clone()
+        .and(warp::path!("model"))
+        .and(warp::get())
+        .and(with_format())
+        .and(warp::header::headers_cloned())
+        .and_then(|task: TaskServices<F>, format, headers| {
+            handle_model(format, headers, task.fetcher)
+        });
+
+    let round_events = task
+        .clone()
+        .and(warp::path!("events"))
+        .and(warp::get())
+        .map(|task: TaskServices<F>| {
+            let events =
+                events::round_events(&task.event_subscriber).map(events::server_sent_event);
+            warp::sse::reply(warp::sse::keep_alive().stream(events))
+        });
+
+    let admin_status = task
+        .clone()
+        .and(warp::path!("admin" / "status"))
+        .and(warp::get())
+        .and(admin_auth.clone())
+        .and_then(|task: TaskServices<F>| {
+            handle_admin_status(task.admin_handle, task.event_subscriber.phase_listener())
+        });
+
+    let admin_pause = task
+        .clone()
+        .and(warp::path!("admin" / "pause"))
+        .and(warp::post())
+        .and(admin_auth.clone())
+        .map(|task: TaskServices<F>| {
+            task.admin_handle.pause();
+            StatusCode::ACCEPTED
+        });
+
+    let admin_resume = task
+        .clone()
+        .and(warp::path!("admin" / "resume"))
+        .and(warp::post())
+        .and(admin_auth.clone())
+        .map(|task: TaskServices<F>| {
+            task.admin_handle.resume();
+            StatusCode::ACCEPTED
+        });
+
+    let admin_abort = task
+        .clone()
+        .and(warp::path!("admin" / "abort"))
+        .and(warp::post())
+        .and(admin_auth.clone())
+        .map(|task: TaskServices<F>| {
+            task.admin_handle.abort();
+            StatusCode::ACCEPTED
+        });
+
+    let admin_shutdown = task
+        .clone()
+        .and(warp::path!("admin" / "shutdown"))
+        .and(warp::post())
+        .and(admin_auth.clone())
+        .map(|task: TaskServices<F>| {
+            task.admin_handle.shutdown();
+            StatusCode::ACCEPTED
+        });
+
+    let admin_pet_settings = task
+        .and(warp::path!("admin" / "settings" / "pet"))
+        .and(warp::put())
+        .and(admin_auth)
+        .and(warp::body::json())
+        .and_then(|task: TaskServices<F>, pet_settings| {
+            handle_admin_pet_settings(pet_settings, task.admin_handle)
+        });
+
+    message
+        .or(round_params)
+        .or(sum_dict)
+        .or(seed_dict)
+        .or(dropout_dict)
+        .or(model)
+        .or(round_events)
+        .or(admin_status)
+        .or(admin_pause)
+        .or(admin_resume)
+        .or(admin_abort)
+        .or(admin_shutdown)
+        .or(admin_pet_settings)
+}
+
+/// Handles and responds to a PET message.
+///
+/// If the message can't be handled, the response is a [`MessageError`] replied by `reply_error`.
+async fn handle_message(
+    body: Vec<u8>,
+    credential: Option<String>,
+    ip: Option<IpAddr>,
+    mut handler: PetMessageHandler,
+    reply_error: MessageErrorReply,
+) -> Response<Vec<u8>> {
+    match handler.handle_message(body, credential, ip).await {
+        Ok(_) => Response::builder()
+            .status(StatusCode::OK)
+            .body(Vec::new())
+            .unwrap(),
+        Err(e) => {
+            warn!("failed to handle message: {:?}", e);
+            reply_error.reply(&MessageError::from(e))
+        }
+    }
+}
+
+/// The reply to a PET message which failed to be handled.
+#[derive(Debug, Clone, Copy)]
+struct MessageErrorReply {
+    /// The negotiated representation of the error.
+    format: Format,
+    /// The seconds after which a rate limited message may be sent again.
+    retry_after: u64,
+}
+
+impl MessageErrorReply {
+    /// Responds with the error in the negotiated format.
+    ///
+    /// The status code depends on the kind of the error, which is named in the
+    /// `X-Xaynet-Error-Kind` header as well. Rate limited messages get a `Retry-After` header.
+    fn reply(self, error: &MessageError) -> Response<Vec<u8>> {
+        let mut response = self.format.reply(error, || error);
+        *response.status_mut() = message_error_status(error.kind);
+        let headers = response.headers_mut();
+        // safe unwrap: the names of the kinds are visible ASCII
+        headers.insert(
+            ERROR_KIND,
+            HeaderValue::from_str(&format!("{:?}", error.kind)).unwrap(),
+        );
+        if error.kind == MessageErrorKind::RateLimited {
+            headers.insert(RETRY_AFTER, HeaderValue::from(self.retry_after));
+        }
+        vary_accept(response)
+    }
+}
+
+/// Gets the seconds after which a rate limited PET message may be sent again.
+///
+/// This is the time until the slowest of the configured rate limits allows another message, but
+/// at least a second.
+fn retry_after(limit_settings: &LimitSettings) -> u64 {
+    limit_settings
+        .ip
+        .iter()
+        .chain(limit_settings.participant.iter())
+        .map(|limit| (1. / limit.rate).ceil() as u64)
+        .max()
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Gets the status code of the response to a PET message which failed to be handled.
+fn message_error_status(kind: MessageErrorKind) -> StatusCode {
+    match kind {
+        MessageErrorKind::Decrypt
+        | MessageErrorKind::Parsing
+        | MessageErrorKind::InvalidSignature => StatusCode::BAD_REQUEST,
+        MessageErrorKind::NotEligible | MessageErrorKind::NotAdmitted => StatusCode::FORBIDDEN,
+        MessageErrorKind::InvalidCoordinatorPublicKey
+        | MessageErrorKind::UnexpectedMessage
+        | MessageErrorKind::Discarded => StatusCode::CONFLICT,
+        MessageErrorKind::Rejected => StatusCode::UNPROCESSABLE_ENTITY,
+        MessageErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        MessageErrorKind::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+        MessageErrorKind::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+    }
+}
+
+/// Handles and responds to a request for the sum dictionary.
+async fn handle_sums<F: Fetcher>(
+    format: Format,
+    if_none_match: Option<String>,
+    mut fetcher: F,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(vary_accept(match fetcher.sum_dict().await {
+        Err(e) => {
+            warn!("failed to handle sum dict request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Vec::new())
+                .unwrap()
+        }
+        Ok(None) => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Vec::new())
+            .unwrap(),
+        Ok(Some(dict)) => reply_tagged(format, &dict.tag, if_none_match.as_deref(), || {
+            format.reply(dict.data.as_ref(), || json::sum_dict(&dict.data))
+        }),
+    }))
+}
+
+/// Handles and responds to a request for the seed dictionary.
+async fn handle_seeds<F: Fetcher>(
+    pk: ParticipantPublicKey,
+    format: Format,
+    if_none_match: Option<String>,
+    mut fetcher: F,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(vary_accept(match fetcher.seed_dict().await {
+        Err(e) => {
+            warn!("failed to handle seed dict request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Vec::new())
+                .unwrap()
+        }
+        Ok(Some(dict)) if dict.data.get(&pk).is_some() => {
+            let seeds = dict.data.as_ref().get(&pk).unwrap();
+            reply_tagged(format, &dict.tag, if_none_match.as_deref(), || {
+                format.reply(seeds, || json::update_seed_dict(seeds))
+            })
+        }
+        _ => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Vec::new())
+            .unwrap(),
+    }))
+}
+
+/// Handles and responds to a request for the dropout dictionary.
+async fn handle_dropouts<F: Fetcher>(
+    format: Format,
+    if_none_match: Option<String>,
+    mut fetcher: F,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(vary_accept(match fetcher.dropout_dict().await {
+        Err(e) => {
+            warn!("failed to handle dropout dict request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Vec::new())
+                .unwrap()
+        }
+        Ok(None) => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Vec::new())
+            .unwrap(),
+        Ok(Some(dict)) => reply_tagged(format, &dict.tag, if_none_match.as_deref(), || {
+            format.reply(dict.data.as_ref(), || json::sum_dict(&dict.data))
+        }),
+    }))
+}
+
+/// Handles and responds to a request for the global model.
+///
+/// The bincode representation of the model is streamed in chunks and byte ranges of it can be
+/// requested. The JSON representation of the model is cast to the data type of the current round.
+/// Both are compressed according to the `Accept-Encoding` header, unless a byte range is requested.
+async fn handle_model<F: Fetcher>(
+    format: Format,
+    headers: HeaderMap,
+    mut fetcher: F,
+) -> Result<impl warp::Reply, Infallible> {
+    let model = match fetcher.model().await {
+        Ok(Some(model)) => model,
+        Ok(None) => {
+            return Ok(Response::builder()
+                .status(StatusCode::NO_CONTENT)
+                .body(Body::empty())
+                .unwrap())
+        }
+        Err(e) => {
+            warn!("failed to handle model request: {:?}", e);
+            return Ok(Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap());
+        }
+    };
+
+    // byte ranges always refer to the uncompressed model, so that a download can be resumed
+    let range = header_str(&headers, RANGE);
+    let encoding = match range {
+        Some(_) => Encoding::Identity,
+        None => Encoding::negotiate(header_str(&headers, ACCEPT_ENCODING)),
+    };
+    let tag = match encoding.name() {
+        Some(name) => format!("{}-{}", model.tag, name),
+        None => model.tag.clone(),
+    };
+    // the whole model is served if the partially downloaded model is outdated
+    let if_range = header_str(&headers, IF_RANGE);
+    let etag = format.etag(&tag);
+    let range = range.filter(|_| if_range.map_or(true, |if_range| if_range == etag));
+    let if_none_match = header_str(&headers, IF_NONE_MATCH);
+
+    // the model is only cast if the client doesn't have the JSON representation already
+    let mut response = if format == Format::Bincode || matches_etag(if_none_match, &etag) {
+        reply_tagged(format, &tag, if_none_match, || {
+            download::reply_model(model.data, encoding, range)
+        })
+    } else {
+        let json_model = fetcher.round_params().await.and_then(|params| {
+            JsonModel::new(&model.data, params.mask_config.vect.data_type).map_err(FetchError::from)
+        });
+        match json_model {
+            Ok(json_model) => reply_tagged(format, &tag, None, || {
+                encoding.reply(reply_json(&json_model))
+            }),
+            Err(e) => {
+                warn!("failed to handle model request: {:?}", e);
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::empty())
+                    .unwrap()
+            }
+        }
+    };
+    response
+        .headers_mut()
+        .insert(VARY, HeaderValue::from_static("accept, accept-encoding"));
+    Ok(response)
+}
+
+/// Handles and responds to a request for the round parameters.
+async fn handle_params<F: Fetcher>(
+    format: Format,
+    mut fetcher: F,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(vary_accept(match fetcher.round_params().await {
+        Ok(params) => format.reply(&params, || JsonRoundParameters::from(&params)),
+        Err(e) => {
+            warn!("failed to handle round parameters request: {:?}", e);
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Vec::new())
+                .unwrap()
+        }
+    }))
+}
+
+/// Handles and responds to a request for the metrics in the Prometheus text format.
+async fn handle_metrics() -> Result<impl warp::Reply, Infallible> {
+    Ok(match GlobalRecorder::global() {
+        Some(Recorder::Prometheus(recorder)) => Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .status(StatusCode::OK)
+            .body(recorder.render().into_bytes())
+            .unwrap(),
+        // the metrics are either disabled or pushed to another backend
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Vec::new())
+            .unwrap(),
+    })
+}
+
+/// Handles and responds to a request for the coordinator status.
+async fn handle_admin_status(
+    admin: AdminHandle,
+    phases: EventListener<PhaseName>,
+) -> Result<impl warp::Reply, Infallible> {
+    let phase = phases.get_latest();
+    Ok(warp::reply::json(&AdminStatus {
+        round_id: phase.round_id,
+        phase: phase.event,
+        paused: admin.is_paused(),
+        pending_pet_settings: admin.pending_pet_settings().is_some(),
+        messages: admin.counters(),
+    }))
+}
+
+/// Handles and responds to a request to update the PET settings for the next round.
+async fn handle_admin_pet_settings(
+    pet_settings: PetSettings,
+    admin: AdminHandle,
+) -> Result<impl warp::Reply, Infallible> {
+    Ok(match pet_settings.validate() {
+        Ok(()) => {
+            admin.update_pet_settings(pet_settings);
+            warp::reply::with_status(String::new(), StatusCode::ACCEPTED)
+        }
+        Err(e) => {
+            warn!("invalid PET settings: {}", e);
+            warp::reply::with_status(e.to_string(), StatusCode::BAD_REQUEST)
+        }
+    })
+}
+
+/// Issues a credential and responds with it.
+///
+/// All requests are rejected as not found if credentials are disabled.
+fn issue_credential(
+    admission: Option<&Admission>,
+    request: CredentialRequest,
+) -> Response<Vec<u8>> {
+    let participant_pk = match base64::decode(request.participant_pk.as_bytes())
+        .ok()
+        .and_then(|bytes| ParticipantPublicKey::from_slice(&bytes))
+    {
+        Some(pk) => pk,
+        None => {
+            return Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Vec::new())
+                .unwrap()
+        }
+    };
+    match admission.and_then(|admission| {
+        admission.issue(participant_pk, request.organization, request.expires_at)
+    }) {
+        Some(credential) => reply_json(&CredentialResponse {
+            credential: credential.encode(),
+        }),
+        None => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Vec::new())
+            .unwrap(),
+    }
+}
+
+/// Extracts the negotiated [`Format`] of the requested data.
+fn with_format() -> impl Filter<Extract = (Format,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("accept")
+        .map(|accept: Option<String>| Format::negotiate(accept.as_deref()))
+}
+
+/// Extracts the entity tags of an `If-None-Match` header.
+fn with_if_none_match() -> impl Filter<Extract = (Option<String>,), Error = warp::Rejection> + Clone
+{
+    warp::header::optional::<String>("if-none-match")
+}
+
+/// Gets the value of a header if it is visible ASCII.
+fn header_str(headers: &HeaderMap, name: HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Reads the body of a PET message of a task.
+///
+/// The message is refused as soon as the body exceeds the maximum size of an encrypted message,
+/// without reading the rest of it. A body whose announced `Content-Length` exceeds the maximum
+/// size isn't read at all.
+async fn read_message_body<S, B>(
+    task: &str,
+    body: S,
+    content_length: Option<u64>,
+    max_size: Option<usize>,
+) -> Result<Vec<u8>, warp::Rejection>
+where
+    S: Stream<Item = Result<B, warp::Error>>,
+    B: Buf,
+{
+    let too_large = |max_size| {
+        metric!(Measurement::MessageTooLarge, 1, ("task", task));
+        warp::reject::custom(MessageRefused(MessageError::from(ServiceError::TooLarge(
+            max_size,
+        ))))
+    };
+
+    let mut message = match (max_size, content_length) {
+        (Some(max_size), Some(content_length)) if content_length > max_size as u64 => {
+            return Err(too_large(max_size));
+        }
+        (Some(_), Some(content_length)) => Vec::with_capacity(content_length as usize),
+        _ => Vec::new(),
+    };
+    futures::pin_mut!(body);
+    while let Some(buf) = body.next().await {
+        let mut buf = buf.map_err(|_| warp::reject::custom(InvalidBody))?;
+        if let Some(max_size) = max_size {
+            if message.len() + buf.remaining() > max_size {
+                return Err(too_large(max_size));
+            }
+        }
+        message.extend_from_slice(&buf.to_bytes());
+    }
+    Ok(message)
+}
+
+/// Creates a `warp` filter which authenticates admin requests via the given bearer token.
+///
+/// All admin requests are rejected as not found if no token is configured.
+fn with_admin_auth(
+    token: Option<String>,
+) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let authorized = authorize(token.as_deref(), header.as_deref());
+            async move { authorized }
+        })
+        .untuple_one()
+}
+
+/// Checks the bearer token of an `Authorization` header against the configured token.
+fn authorize(token: Option<&str>, header: Option<&str>) -> Result<(), warp::Rejection> {
+    let token = token.ok_or_else(warp::reject::not_found)?;
+    match header.and_then(|header| header.strip_prefix("Bearer ")) {
+        // constant time comparison
+        Some(bearer) if memcmp(bearer.as_bytes(), token.as_bytes()) => Ok(()),
+        _ => Err(warp::reject::custom(Unauthorized)),
+    }
+}
+
+/// Extracts a participant public key from the url query string
+async fn part_pk(query: SeedDictQuery) -> Result<ParticipantPublicKey, warp::Rejection> {
+    match base64::decode(query.pk.as_bytes()) {
+        Ok(bytes) => {
+            if let Some(pk) = ParticipantPublicKey::from_slice(&bytes[..]) {
+                Ok(pk)
+            } else {
+                Err(warp::reject::custom(InvalidPublicKey))
+            }
+        }
+        Err(_) => Err(warp::reject::custom(InvalidPublicKey)),
+    }
+}
+
+#[derive(Debug)]
+struct InvalidPublicKey;
+
+impl warp::reject::Reject for InvalidPublicKey {}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+#[derive(Debug)]
+struct InvalidBody;
+
+impl warp::reject::Reject for InvalidBody {}
+
+/// A PET message which was refused before it was handled.
+#[derive(Debug)]
+struct MessageRefused(MessageError);
+
+impl warp::reject::Reject for MessageRefused {}
+
+/// Handles `warp` rejections of bad requests.
+async fn handle_reject(err: warp::Rejection) -> Result<Response<Vec<u8>>, Infallible> {
+    let code = if err.is_not_found() {
+        StatusCode::NOT_FOUND
+    } else if let Some(InvalidPublicKey) = err.find() {
+        StatusCode::BAD_REQUEST
+    } else if let Some(InvalidBody) = err.find() {
+        StatusCode::BAD_REQUEST
+    } else if let Some(Unauthorized) = err.find() {
+        StatusCode::UNAUTHORIZED
+    } else if err.find::<warp::body::BodyDeserializeError>().is_some() {
+        StatusCode::BAD_REQUEST
+    } else {
+        error!("unhandled rejection: {:?}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    // reply with empty body; the status code is the interesting part
+    Ok(Response::builder().status(code).body(Vec::new()).unwrap())
+}
+
+#[derive(Debug, Error)]
+/// Errors of the rest server.
+pub enum RestError {
+    #[error("invalid TLS configuration was provided")]
+    InvalidTlsConfig,
+}
+
+impl From<Infallible> for RestError {
+    fn from(infallible: Infallible) -> RestError {
+        match infallible {}
+    }
+}
+
+#[cfg(feature = "tls")]
+/// Configures a server for TLS server and client authentication.
+///
+/// # Errors
+/// Fails if the TLS settings are invalid.
+fn configure_tls<F>(
+    server: Server<F>,
+    tls_certificate: Option<PathBuf>,
+    tls_key: Option<PathBuf>,
+    tls_client_auth: Option<PathBuf>,
+) -> Result<TlsServer<F>, RestError>
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply,
+{
+    if tls_certificate.is_none() && tls_key.is_none() && tls_client_auth.is_none() {
+        return Err(RestError::InvalidTlsConfig);
+    }
+
+    let mut server = server.tls();
+    match (tls_certificate, tls_key) {
+        (Some(cert), Some(key)) => server = server.cert_path(cert).key_path(key),
+        (None, None) => {}
+        _ => return Err(RestError::InvalidTlsConfig),
+    }
+    if let Some(trust_anchor) = tls_client_auth {
+        server = server.client_auth_required_path(trust_anchor);
+    }
+    Ok(server)
+}
+
+#[cfg(not(feature = "tls"))]
+/// Runs a server with the provided filter routes.
+async fn run_http<F>(filter: F, api_settings: ApiSettings) -> Result<(), Infallible>
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply,
+{
+    warp::serve(filter).run(api_settings.bind_address).await;
+    Ok(())
+}
+
+#[cfg(feature = "tls")]
+/// Runs a TLS server with the provided filter routes.
+///
+/// # Errors
+/// Fails if the TLS settings are invalid.
+async fn run_https<F>(filter: F, api_settings: ApiSettings) -> Result<(), RestError>
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    F::Extract: Reply,
+{
+    configure_tls(
+        warp::serve(filter),
+        api_settings.tls_certificate,
+        api_settings.tls_key,
+        api_settings.tls_client_auth,
+    )?
+    .run(api_settings.bind_address)
+    .await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use warp::{http::Method, reject::MethodNotAllowed};
+
+    use super::*;
+    use crate::{
+        services::{admission::Credential, tests::utils::task_services},
+        settings::{AdmissionSettings, RateLimitSettings, DEFAULT_TASK},
+    };
+    use xaynet_core::crypto::{SigningKeyPair, SigningKeySeed};
+
+    #[test]
+    fn test_matches_etag() {
+        let etag = Format::Json.etag("model-1_0123");
+        assert_eq!(etag, format!("\"{}-model-1_0123-json\"", *ETAG_EPOCH));
+        assert!(!matches_etag(None, &etag));
+        assert!(matches_etag(Some("*"), &etag));
+        assert!(matches_etag(Some(&etag), &etag));
+        assert!(matches_etag(Some(&format!("\"a\", W/{}", etag)), &etag));
+        assert!(!matches_etag(
+            Some(&Format::Bincode.etag("model-1_0123")),
+            &etag
+        ));
+        // the tags of a previous start of the coordinator don't match
+        assert!(!matches_etag(Some("\"model-1_0123-json\""), &etag));
+    }
+
+    #[test]
+    fn test_reply_tagged() {
+        let reply = || reply_bincode(&42_u32);
+        let etag = Format::Bincode.etag("sum-dict-1");
+
+        let response = reply_tagged(Format::Bincode, "sum-dict-1", None, reply);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ETAG], etag.as_str());
+        assert!(!response.body().is_empty());
+
+        let if_none_match = Some(etag.as_str());
+        let response = reply_tagged(Format::Bincode, "sum-dict-1", if_none_match, reply);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[ETAG], etag.as_str());
+        assert!(response.body().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_data_responses_vary_on_accept() {
+        sodiumoxide::init().unwrap();
+        let limit_settings = LimitSettings::default();
+        let routes = routes(
+            None,
+            limit_settings,
+            task_services(DEFAULT_TASK, &limit_settings),
+            HashMap::new(),
+            None,
+        );
+
+        let pk = base64::encode(SigningKeyPair::generate().public.as_slice())
+            .replace('+', "%2B")
+            .replace('/', "%2F");
+        for path in &[
+            "/params".to_string(),
+            "/sums".to_string(),
+            format!("/seeds?pk={}", pk),
+            "/dropouts".to_string(),
+        ] {
+            let response = warp::test::request()
+                .path(path)
+                .header("accept", "application/json")
+                .reply(&routes)
+                .await;
+            assert!(response.status().is_success(), "failed request {}", path);
+            assert_eq!(response.headers()[VARY], "accept", "missing Vary {}", path);
+        }
+    }
+
+    #[test]
+    fn test_negotiate_format() {
+        assert_eq!(Format::negotiate(None), Format::Bincode);
+        assert_eq!(Format::negotiate(Some("*/*")), Format::Bincode);
+        assert_eq!(Format::negotiate(Some("application/json")), Format::Json);
+        assert_eq!(
+            Format::negotiate(Some("text/html, application/json;q=0.9, */*;q=0.8")),
+            Format::Json
+        );
+        assert_eq!(
+            Format::negotiate(Some("application/octet-stream, application/json")),
+            Format::Bincode
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_message_body() {
+        let body = || {
+            futures::stream::iter(vec![
+                Ok::<_, warp::Error>(Bytes::from_static(&[1; 6])),
+                Ok(Bytes::from_static(&[2; 5])),
+            ])
+        };
+        let refused_kind = |result: Result<Vec<u8>, warp::Rejection>| {
+            result
+                .unwrap_err()
+                .find::<MessageRefused>()
+                .map(|MessageRefused(error)| error.kind)
+        };
+
+        let message = read_message_body(DEFAULT_TASK, body(), None, None).await;
+        assert_eq!(message.unwrap().len(), 11);
+        let message = read_message_body(DEFAULT_TASK, body(), Some(11), Some(11)).await;
+        assert_eq!(message.unwrap().len(), 11);
+
+        // the announced size is refused before the body is read
+        let message = read_message_body(DEFAULT_TASK, body(), Some(11), Some(10)).await;
+        assert_eq!(refused_kind(message), Some(MessageErrorKind::TooLarge));
+        // the body is refused while it is read, whether or not its size is announced
+        let message = read_message_body(DEFAULT_TASK, body(), None, Some(10)).await;
+        assert_eq!(refused_kind(message), Some(MessageErrorKind::TooLarge));
+        let message = read_message_body(DEFAULT_TASK, body(), Some(1), Some(10)).await;
+        assert_eq!(refused_kind(message), Some(MessageErrorKind::TooLarge));
+
+        assert_eq!(
+            message_error_status(MessageErrorKind::TooLarge),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[test]
+    fn test_reply_message_error() {
+        let reply_error = |format| MessageErrorReply {
+            format,
+            retry_after: 10,
+        };
+
+        let error = MessageError::from(ServiceError::RateLimited);
+        let response = reply_error(Format::Bincode).reply(&error);
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[ERROR_KIND], "RateLimited");
+        assert_eq!(response.headers()[RETRY_AFTER], "10");
+        assert_eq!(
+            bincode::deserialize::<MessageError>(response.body()).unwrap(),
+            error
+        );
+
+        let response = reply_error(Format::Json).reply(&error);
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        assert_eq!(response.headers()[VARY], "accept");
+        assert_eq!(
+            serde_json::from_slice::<MessageError>(response.body()).unwrap(),
+            error
+        );
+
+        let error = MessageError::from(ServiceError::TooLarge(10));
+        let response = reply_error(Format::Json).reply(&error);
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response.headers()[ERROR_KIND], "TooLarge");
+        assert!(response.headers().get(RETRY_AFTER).is_none());
+    }
+
+    #[test]
+    fn test_retry_after() {
+        assert_eq!(retry_after(&LimitSettings::default()), 1);
+        let limit_settings = LimitSettings {
+            ip: Some(RateLimitSettings {
+                rate: 10.,
+                burst: 100,
+            }),
+            participant: Some(RateLimitSettings {
+                rate: 0.3,
+                burst: 1,
+            }),
+            ..LimitSettings::default()
+        };
+        assert_eq!(retry_after(&limit_settings), 4);
+    }
+
+    #[test]
+    fn test_issue_credential() {
+        let pk = SigningKeyPair::generate().public;
+        let request = || CredentialRequest {
+            participant_pk: base64::encode(pk.as_slice()),
+            organization: "xayn".to_string(),
+            expires_at: None,
+        };
+        let admission = Admission::new(&AdmissionSettings {
+            allowed_participants: Vec::new(),
+            issuer_seed: Some(SigningKeySeed::generate()),
+        });
+
+        let response = issue_credential(Some(&admission), request());
+        assert_eq!(response.status(), StatusCode::OK);
+        let response: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        let credential = response["credential"].as_str().unwrap();
+        assert_eq!(Credential::decode(credential).unwrap().organization, "xayn");
+        assert!(admission.admit(&pk, Some(credential)));
+
+        let invalid_request = CredentialRequest {
+            participant_pk: "invalid".to_string(),
+            ..request()
+        };
+        assert_eq!(
+            issue_credential(Some(&admission), invalid_request).status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let admission = Admission::new(&AdmissionSettings {
+            allowed_participants: vec![pk],
+            issuer_seed: None,
+        });
+        assert_eq!(
+            issue_credential(Some(&admission), request()).status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            issue_credential(None, request()).status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_by_task() {
+        sodiumoxide::init().unwrap();
+        let limit_settings = LimitSettings::default();
+        let default_task = task_services(DEFAULT_TASK, &limit_settings);
+        let task = task_services("keyboard", &limit_settings);
+        let (default_admin, admin) = (default_task.admin_handle.clone(), task.admin_handle.clone());
+        let mut tasks = HashMap::new();
+        tasks.insert("keyboard".to_string(), task);
+        let routes = routes(
+            Some("token".to_string()),
+            limit_settings,
+            default_task,
+            tasks,
+            None,
+        )
+        .recover(handle_reject);
+
+        let pause = |path: &'static str| {
+            warp::test::request()
+                .method("POST")
+                .path(path)
+                .header("authorization", "Bearer token")
+                .reply(&routes)
+        };
+        assert_eq!(
+            pause("/tasks/keyboard/admin/pause").await.status(),
+            StatusCode::ACCEPTED
+        );
+        assert!(admin.is_paused());
+        assert!(!default_admin.is_paused());
+
+        assert_eq!(pause("/admin/pause").await.status(), StatusCode::ACCEPTED);
+        assert!(default_admin.is_paused());
+
+        assert_eq!(
+            pause("/tasks/unknown/admin/pause").await.status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_openapi_document_matches_routes() {
+        sodiumoxide::init().unwrap();
+        let limit_settings = LimitSettings::default();
+        let mut tasks = HashMap::new();
+        tasks.insert(
+            "keyboard".to_string(),
+            task_services("keyboard", &limit_settings),
+        );
+        let routes = routes(
+            Some("token".to_string()),
+            limit_settings,
+            task_services(DEFAULT_TASK, &limit_settings),
+            tasks,
+            None,
+        );
+
+        let methods = [Method::GET, Method::POST, Method::PUT, Method::DELETE];
+        let document = openapi::document();
+        for (path, operations) in document["paths"].as_object().unwrap() {
+            // the routes of the PET protocol interactions and of the administration of a task are
+            // served for additional tasks as well
+            let mut paths = vec![path.clone()];
+            if !["/admin/credentials", "/metrics", "/openapi.json"]
+                .iter()
+                .any(|prefix| path.starts_with(prefix))
+            {
+                paths.push(format!("/tasks/keyboard{}", path));
+            }
+
+            for (path, method) in paths
+                .iter()
+                .flat_map(|path| methods.iter().map(move |method| (path, method)))
+            {
+                let result = warp::test::request()
+                    .method(method.as_str())
+                    .path(path)
+                    .header("authorization", "Bearer token")
+                    .filter(&routes)
+                    .await;
+                let documented = operations.get(method.as_str().to_lowercase()).is_some();
+                match result {
+                    Ok(_) => assert!(documented, "undocumented route {} {}", method, path),
+                    Err(rejection) if rejection.is_not_found() => {
+                        panic!("missing route {}", path)
+                    }
+                    Err(rejection) if rejection.find::<MethodNotAllowed>().is_some() => {
+                        assert!(!documented, "missing route {} {}", method, path)
+                    }
+                    // the route is matched, but the request is incomplete
+                    Err(_) => assert!(documented, "undocumented route {} {}", method, path),
+                }
+            }
+        }
+    }
+}
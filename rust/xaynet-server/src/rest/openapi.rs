@@ -0,0 +1,473 @@
+//! The OpenAPI document of the REST API.
+//!
+//! See the [rest module] documentation since this is a private module anyways.
+//!
+//! [rest module]: ../index.html
+
+use serde_json::{json, Value};
+
+/// Creates a response object of a data request which can be either bincode or JSON.
+fn data_response(description: &str, schema: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/octet-stream": {
+                "schema": { "type": "string", "format": "binary" }
+            },
+            "application/json": {
+                "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+            }
+        }
+    })
+}
+
+/// Creates a response object of a data request which comes with an entity tag.
+fn tagged_data_response(description: &str, schema: &str) -> Value {
+    let mut response = data_response(description, schema);
+    response["headers"] = json!({
+        "ETag": {
+            "description": "The entity tag of the data",
+            "schema": { "type": "string" }
+        }
+    });
+    response
+}
+
+/// Creates a response object of a PET message which failed to be handled.
+fn message_error_response(description: &str) -> Value {
+    let mut response = data_response(description, "MessageError");
+    response["headers"] = json!({
+        "X-Xaynet-Error-Kind": {
+            "description": "The kind of the error",
+            "schema": { "$ref": "#/components/schemas/MessageErrorKind" }
+        }
+    });
+    response
+}
+
+/// Creates the OpenAPI document of the REST API.
+pub fn document() -> Value {
+    let base64 = json!({ "type": "string", "format": "byte" });
+    let data_type = json!({ "type": "string", "enum": ["F32", "F64", "I32", "I64"] });
+    let no_content = json!({ "description": "The data is not available in the current phase" });
+    let internal_error = json!({ "description": "The data could not be fetched" });
+    let accepted = json!({ "description": "The request was accepted" });
+    let unauthorized = json!({ "description": "The admin token is invalid" });
+    let admin = json!([{ "bearerAuth": [] }]);
+    let if_none_match = json!({
+        "name": "If-None-Match",
+        "in": "header",
+        "required": false,
+        "description": "The entity tag of the data which the client already has",
+        "schema": { "type": "string" }
+    });
+    let not_modified = json!({ "description": "The data didn't change since the entity tag" });
+    let mut rate_limited = message_error_response("The rate limit of the sender is exceeded");
+    rate_limited["headers"]["Retry-After"] = json!({
+        "description": "The seconds after which the message may be sent again",
+        "schema": { "type": "integer", "minimum": 1 }
+    });
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Xaynet coordinator API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "The data requests and the errors of the PET messages respond with \
+                bincode by default and with JSON if `application/json` is requested via the \
+                `Accept` header. The routes of the PET protocol interactions and of the \
+                administration of a task are served for the default task at the root and for \
+                additional tasks under `/tasks/{name}`, e.g. `/tasks/{name}/params` or \
+                `/tasks/{name}/admin/status`. Shutting a task down doesn't affect the other tasks."
+        },
+        "paths": {
+            "/message": {
+                "post": {
+                    "summary": "Sends an encrypted PET message",
+                    "parameters": [{
+                        "name": "X-Xaynet-Credential",
+                        "in": "header",
+                        "required": false,
+                        "description": "The credential which the coordinator issued for the \
+                            participant, if the participant is not allowed explicitly",
+                        "schema": { "type": "string" }
+                    }],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/octet-stream": {
+                                "schema": { "type": "string", "format": "binary" }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": { "description": "The message was handled" },
+                        "400": message_error_response("The message is malformed"),
+                        "403": message_error_response(
+                            "The participant is not eligible for the task or not admitted"
+                        ),
+                        "409": message_error_response(
+                            "The message is unexpected in the current phase"
+                        ),
+                        "413": message_error_response("The message exceeds the maximum size"),
+                        "422": message_error_response("The message was rejected"),
+                        "429": rate_limited,
+                        "500": message_error_response("The message could not be handled")
+                    }
+                }
+            },
+            "/params": {
+                "get": {
+                    "summary": "Gets the round parameters",
+                    "responses": {
+                        "200": data_response("The round parameters", "RoundParameters"),
+                        "500": internal_error
+                    }
+                }
+            },
+            "/sums": {
+                "get": {
+                    "summary": "Gets the sum dictionary",
+                    "parameters": [if_none_match],
+                    "responses": {
+                        "200": tagged_data_response("The sum dictionary", "SumDict"),
+                        "204": no_content,
+                        "304": not_modified,
+                        "500": internal_error
+                    }
+                }
+            },
+            "/seeds": {
+                "get": {
+                    "summary": "Gets the encrypted mask seeds of a sum participant",
+                    "parameters": [{
+                        "name": "pk",
+                        "in": "query",
+                        "required": true,
+                        "description": "The public signing key of the sum participant",
+                        "schema": base64
+                    }, if_none_match],
+                    "responses": {
+                        "200": tagged_data_response("The update seed dictionary", "UpdateSeedDict"),
+                        "204": no_content,
+                        "304": not_modified,
+                        "400": { "description": "The public key is invalid" },
+                        "500": internal_error
+                    }
+                }
+            },
+            "/dropouts": {
+                "get": {
+                    "summary": "Gets the sum participants who dropped out if the mask seeds are secret-shared",
+                    "parameters": [if_none_match],
+                    "responses": {
+                        "200": tagged_data_response("The dropout dictionary", "SumDict"),
+                        "204": no_content,
+                        "304": not_modified,
+                        "500": internal_error
+                    }
+                }
+            },
+            "/model": {
+                "get": {
+                    "summary": "Gets the global model of the previous round",
+                    "description": "The model is compressed as negotiated by the \
+                        `Accept-Encoding` header, unless a byte range of the bincode \
+                        representation is requested to resume a download.",
+                    "parameters": [if_none_match, {
+                        "name": "Range",
+                        "in": "header",
+                        "required": false,
+                        "description": "A single byte range of the bincode representation",
+                        "schema": { "type": "string" }
+                    }, {
+                        "name": "If-Range",
+                        "in": "header",
+                        "required": false,
+                        "description": "The entity tag of the partially downloaded model",
+                        "schema": { "type": "string" }
+                    }],
+                    "responses": {
+                        "200": tagged_data_response("The global model", "Model"),
+                        "204": { "description": "No global model is available yet" },
+                        "206": {
+                            "description": "The requested byte range of the global model",
+                            "content": {
+                                "application/octet-stream": {
+                                    "schema": { "type": "string", "format": "binary" }
+                                }
+                            }
+                        },
+                        "304": not_modified,
+                        "416": { "description": "The byte range lies outside of the model" },
+                        "500": internal_error
+                    }
+                }
+            },
+            "/events": {
+                "get": {
+                    "summary": "Subscribes to the round events as server-sent events",
+                    "description": "The events are named `new_round`, `phase`, `sum_dict`, \
+                        `seed_dict` and `model` and carry their JSON representation as data. The \
+                        events of the current state of the coordinator are sent first.",
+                    "responses": {
+                        "200": {
+                            "description": "The stream of round events",
+                            "content": { "text/event-stream": { "schema": { "type": "string" } } }
+                        }
+                    }
+                }
+            },
+            "/metrics": {
+                "get": {
+                    "summary": "Gets the metrics in the Prometheus text format",
+                    "responses": {
+                        "200": {
+                            "description": "The metrics",
+                            "content": { "text/plain": { "schema": { "type": "string" } } }
+                        },
+                        "404": { "description": "The metrics are not recorded by Prometheus" }
+                    }
+                }
+            },
+            "/openapi.json": {
+                "get": {
+                    "summary": "Gets this OpenAPI document",
+                    "responses": {
+                        "200": {
+                            "description": "The OpenAPI document",
+                            "content": { "application/json": { "schema": { "type": "object" } } }
+                        }
+                    }
+                }
+            },
+            "/admin/status": {
+                "get": {
+                    "summary": "Gets the status of the task",
+                    "security": admin,
+                    "responses": {
+                        "200": {
+                            "description": "The status of the task",
+                            "content": { "application/json": { "schema": { "type": "object" } } }
+                        },
+                        "401": unauthorized
+                    }
+                }
+            },
+            "/admin/pause": {
+                "post": {
+                    "summary": "Pauses the task after the current round",
+                    "security": admin,
+                    "responses": { "202": accepted, "401": unauthorized }
+                }
+            },
+            "/admin/resume": {
+                "post": {
+                    "summary": "Resumes a paused task",
+                    "security": admin,
+                    "responses": { "202": accepted, "401": unauthorized }
+                }
+            },
+            "/admin/abort": {
+                "post": {
+                    "summary": "Aborts the current round",
+                    "security": admin,
+                    "responses": { "202": accepted, "401": unauthorized }
+                }
+            },
+            "/admin/shutdown": {
+                "post": {
+                    "summary": "Shuts the task down",
+                    "security": admin,
+                    "responses": { "202": accepted, "401": unauthorized }
+                }
+            },
+            "/admin/settings/pet": {
+                "put": {
+                    "summary": "Schedules the PET settings for the next round",
+                    "security": admin,
+                    "requestBody": {
+                        "required": true,
+                        "content": { "application/json": { "schema": { "type": "object" } } }
+                    },
+                    "responses": {
+                        "202": accepted,
+                        "400": { "description": "The PET settings are invalid" },
+                        "401": unauthorized
+                    }
+                }
+            },
+            "/admin/credentials": {
+                "post": {
+                    "summary": "Issues a credential for a participant",
+                    "security": admin,
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "required": ["participant_pk", "organization"],
+                                    "properties": {
+                                        "participant_pk": base64,
+                                        "organization": { "type": "string" },
+                                        "expires_at": {
+                                            "description": "The expiry time in seconds since the \
+                                                Unix epoch",
+                                            "type": "integer",
+                                            "minimum": 0,
+                                            "nullable": true
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "The encoded credential",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": { "credential": { "type": "string" } }
+                                    }
+                                }
+                            }
+                        },
+                        "400": { "description": "The public key is invalid" },
+                        "401": unauthorized,
+                        "404": { "description": "The coordinator doesn't issue credentials" }
+                    }
+                }
+            }
+        },
+        "components": {
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer" }
+            },
+            "schemas": {
+                "MessageErrorKind": {
+                    "type": "string",
+                    "enum": [
+                        "Decrypt",
+                        "Parsing",
+                        "InvalidSignature",
+                        "InvalidCoordinatorPublicKey",
+                        "UnexpectedMessage",
+                        "NotEligible",
+                        "Discarded",
+                        "Rejected",
+                        "Internal",
+                        "TooLarge",
+                        "RateLimited",
+                        "NotAdmitted"
+                    ]
+                },
+                "MessageError": {
+                    "type": "object",
+                    "properties": {
+                        "kind": { "$ref": "#/components/schemas/MessageErrorKind" },
+                        "description": { "type": "string" }
+                    }
+                },
+                "MaskConfig": {
+                    "type": "object",
+                    "properties": {
+                        "group_type": { "type": "string", "enum": ["Integer", "Prime", "Power2"] },
+                        "data_type": data_type,
+                        "bound_type": {
+                            "oneOf": [
+                                { "type": "string", "enum": ["B0", "B2", "B4", "B6", "Bmax"] },
+                                { "type": "integer", "minimum": 1 }
+                            ]
+                        },
+                        "model_type": {
+                            "oneOf": [
+                                { "type": "string", "enum": ["M3", "M6", "M9", "M12"] },
+                                { "type": "integer", "minimum": 1 }
+                            ]
+                        },
+                        "sparsity_type": { "type": "string", "enum": ["S0", "S1", "S2", "S3"] },
+                        "quantization_type": {
+                            "oneOf": [
+                                { "type": "string", "enum": ["Q2", "Q4", "Qmax"] },
+                                { "type": "integer", "minimum": 0, "maximum": 20 }
+                            ]
+                        }
+                    }
+                },
+                "RoundParameters": {
+                    "type": "object",
+                    "properties": {
+                        "pk": base64,
+                        "sum": { "type": "number" },
+                        "update": { "type": "number" },
+                        "seed": base64,
+                        "mask_config": {
+                            "type": "object",
+                            "properties": {
+                                "vect": { "$ref": "#/components/schemas/MaskConfig" },
+                                "unit": { "$ref": "#/components/schemas/MaskConfig" }
+                            }
+                        },
+                        "model_length": { "type": "integer", "minimum": 0 },
+                        "seed_sharing_threshold": { "type": "number", "nullable": true },
+                        "model_delta": { "type": "boolean" }
+                    }
+                },
+                "SumDict": {
+                    "description": "The base64 encoded ephemeral public keys of the sum \
+                        participants by their base64 encoded public signing keys",
+                    "type": "object",
+                    "additionalProperties": base64
+                },
+                "UpdateSeedDict": {
+                    "description": "The base64 encoded encrypted mask seeds of the update \
+                        participants by their base64 encoded public signing keys",
+                    "type": "object",
+                    "additionalProperties": base64
+                },
+                "Model": {
+                    "description": "The weights of the model cast to the data type of the \
+                        masking configuration",
+                    "type": "object",
+                    "properties": {
+                        "data_type": data_type,
+                        "weights": { "type": "array", "items": { "type": "number" } }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document() {
+        let document = document();
+        assert_eq!(document["openapi"], "3.0.3");
+        for path in &[
+            "/message",
+            "/params",
+            "/sums",
+            "/seeds",
+            "/dropouts",
+            "/model",
+        ] {
+            assert!(document["paths"][path].is_object(), "missing path {}", path);
+        }
+        assert_eq!(
+            document["paths"]["/params"]["get"]["responses"]["200"]["content"]["application/json"]
+                ["schema"]["$ref"],
+            "#/components/schemas/RoundParameters"
+        );
+        assert!(
+            document["paths"]["/message"]["post"]["responses"]["429"]["headers"]["Retry-After"]
+                .is_object()
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Admission control of the participants.
+//!
+//! See [`AdmissionSettings`] for the configuration of the admission control.
+
+use std::{
+    collections::HashSet,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::debug;
+
+use crate::settings::AdmissionSettings;
+use xaynet_core::{
+    crypto::{PublicSigningKey, SecretSigningKey, Signature, SigningKeyPair},
+    ParticipantPublicKey,
+};
+
+/// The context which separates the signatures of credentials from other signatures.
+const CREDENTIAL_CONTEXT: &str = "xaynet-credential-v1";
+
+/// Error returned upon failing to decode a [`Credential`].
+#[derive(Debug, Error)]
+pub enum CredentialError {
+    #[error("the credential is not valid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+
+    #[error("the credential is malformed: {0}")]
+    Malformed(#[from] bincode::Error),
+}
+
+/// A credential which binds the public signing key of a participant to an organization.
+///
+/// Credentials are issued and signed by the coordinator. They are encoded as URL safe base64
+/// without padding, so that participants can present them in a HTTP header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credential {
+    /// The public signing key of the participant.
+    pub participant_pk: ParticipantPublicKey,
+    /// The organization of the participant.
+    pub organization: String,
+    /// The expiry time in seconds since the Unix epoch, if any.
+    pub expires_at: Option<u64>,
+    /// The signature of the issuer.
+    pub signature: Signature,
+}
+
+impl Credential {
+    /// Issues a new credential signed with the secret signing key of the issuer.
+    pub fn issue(
+        issuer_sk: &SecretSigningKey,
+        participant_pk: ParticipantPublicKey,
+        organization: String,
+        expires_at: Option<u64>,
+    ) -> Self {
+        let signature = issuer_sk.sign_detached(&Self::signed_data(
+            &participant_pk,
+            &organization,
+            expires_at,
+        ));
+        Self {
+            participant_pk,
+            organization,
+            expires_at,
+            signature,
+        }
+    }
+
+    /// Gets the data which is signed by the issuer.
+    fn signed_data(
+        participant_pk: &ParticipantPublicKey,
+        organization: &str,
+        expires_at: Option<u64>,
+    ) -> Vec<u8> {
+        // safe unwrap: serializing a tuple of plain data can't fail
+        bincode::serialize(&(CREDENTIAL_CONTEXT, participant_pk, organization, expires_at)).unwrap()
+    }
+
+    /// Checks that the credential is signed by the given issuer and not expired at the given
+    /// time in seconds since the Unix epoch.
+    pub fn verify(&self, issuer_pk: &PublicSigningKey, now: u64) -> bool {
+        self.expires_at.map_or(true, |expires_at| now < expires_at)
+            && issuer_pk.verify_detached(
+                &self.signature,
+                &Self::signed_data(&self.participant_pk, &self.organization, self.expires_at),
+            )
+    }
+
+    /// Encodes the credential.
+    pub fn encode(&self) -> String {
+        // safe unwrap: serializing plain data can't fail
+        base64::encode_config(bincode::serialize(self).unwrap(), base64::URL_SAFE_NO_PAD)
+    }
+
+    /// Decodes an encoded credential.
+    ///
+    /// # Errors
+    /// Fails if the credential is malformed. The signature is not verified.
+    pub fn decode(credential: &str) -> Result<Self, CredentialError> {
+        let bytes = base64::decode_config(credential.trim(), base64::URL_SAFE_NO_PAD)?;
+        Ok(bincode::deserialize(&bytes)?)
+    }
+}
+
+/// The admission control of the participants.
+///
+/// A participant is admitted if it is allowed explicitly or presents a valid credential for its
+/// public signing key.
+pub struct Admission {
+    allowed_participants: HashSet<ParticipantPublicKey>,
+    issuer: Option<SigningKeyPair>,
+}
+
+impl fmt::Debug for Admission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the issuer keys contain a secret
+        f.debug_struct("Admission")
+            .field("allowed_participants", &self.allowed_participants)
+            .field(
+                "issuer_pk",
+                &self.issuer.as_ref().map(|issuer| issuer.public),
+            )
+            .finish()
+    }
+}
+
+impl Admission {
+    /// Creates the admission control from its settings.
+    pub fn new(settings: &AdmissionSettings) -> Self {
+        Self {
+            allowed_participants: settings.allowed_participants.iter().cloned().collect(),
+            issuer: settings
+                .issuer_seed
+                .as_ref()
+                .map(SigningKeyPair::derive_from_seed),
+        }
+    }
+
+    /// Checks whether the participant with the given public key and encoded credential, if any,
+    /// is admitted.
+    pub fn admit(&self, participant_pk: &ParticipantPublicKey, credential: Option<&str>) -> bool {
+        self.admit_at(participant_pk, credential, unix_time())
+    }
+
+    fn admit_at(
+        &self,
+        participant_pk: &ParticipantPublicKey,
+        credential: Option<&str>,
+        now: u64,
+    ) -> bool {
+        if self.allowed_participants.contains(participant_pk) {
+            return true;
+        }
+
+        let (issuer, credential) = match (&self.issuer, credential) {
+            (Some(issuer), Some(credential)) => (issuer, credential),
+            _ => return false,
+        };
+        match Credential::decode(credential) {
+            Ok(credential) if credential.participant_pk == *participant_pk => {
+                let is_valid = credential.verify(&issuer.public, now);
+                if is_valid {
+                    debug!("admitted participant of {}", credential.organization);
+                }
+                is_valid
+            }
+            Ok(_) => false,
+            Err(e) => {
+                debug!("invalid credential: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Issues a credential for the participant with the given public key.
+    ///
+    /// Returns `None` if credentials are disabled.
+    pub fn issue(
+        &self,
+        participant_pk: ParticipantPublicKey,
+        organization: String,
+        expires_at: Option<u64>,
+    ) -> Option<Credential> {
+        self.issuer.as_ref().map(|issuer| {
+            Credential::issue(&issuer.secret, participant_pk, organization, expires_at)
+        })
+    }
+}
+
+/// Gets the current time in seconds since the Unix epoch.
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xaynet_core::crypto::{ByteObject, SigningKeySeed};
+
+    fn admission(allowed_participants: Vec<ParticipantPublicKey>) -> Admission {
+        Admission::new(&AdmissionSettings {
+            allowed_participants,
+            issuer_seed: Some(SigningKeySeed::generate()),
+        })
+    }
+
+    #[test]
+    fn test_encode_decode_credential() {
+        let credential = admission(Vec::new())
+            .issue(
+                SigningKeyPair::generate().public,
+                "xayn".to_string(),
+                Some(42),
+            )
+            .unwrap();
+        let encoded = credential.encode();
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(Credential::decode(&encoded).unwrap(), credential);
+
+        assert!(matches!(
+            Credential::decode("not base64!"),
+            Err(CredentialError::Base64(_))
+        ));
+        assert!(matches!(
+            Credential::decode("AAAA"),
+            Err(CredentialError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_admit_allowed_participant() {
+        let allowed_pk = SigningKeyPair::generate().public;
+        let admission = admission(vec![allowed_pk]);
+
+        assert!(admission.admit(&allowed_pk, None));
+        assert!(!admission.admit(&SigningKeyPair::generate().public, None));
+    }
+
+    #[test]
+    fn test_admit_with_credential() {
+        let admission = admission(Vec::new());
+        let pk = SigningKeyPair::generate().public;
+        let credential = admission
+            .issue(pk, "xayn".to_string(), Some(100))
+            .unwrap()
+            .encode();
+
+        assert!(admission.admit_at(&pk, Some(&credential), 99));
+        assert!(!admission.admit_at(&pk, None, 99));
+        // expired
+        assert!(!admission.admit_at(&pk, Some(&credential), 100));
+        // bound to another participant
+        let other_pk = SigningKeyPair::generate().public;
+        assert!(!admission.admit_at(&other_pk, Some(&credential), 99));
+        // malformed
+        assert!(!admission.admit_at(&pk, Some("invalid"), 99));
+    }
+
+    #[test]
+    fn test_reject_forged_credential() {
+        let admission = admission(Vec::new());
+        let pk = SigningKeyPair::generate().public;
+
+        // issued by another issuer
+        let forged = Credential::issue(
+            &SigningKeyPair::generate().secret,
+            pk,
+            "xayn".to_string(),
+            None,
+        );
+        assert!(!admission.admit(&pk, Some(&forged.encode())));
+
+        // tampered with
+        let mut tampered = admission.issue(pk, "xayn".to_string(), None).unwrap();
+        assert!(admission.admit(&pk, Some(&tampered.encode())));
+        tampered.organization = "evil".to_string();
+        assert!(!admission.admit(&pk, Some(&tampered.encode())));
+
+        // signature of another credential
+        let other = admission
+            .issue(SigningKeyPair::generate().public, "xayn".to_string(), None)
+            .unwrap();
+        let forged = Credential {
+            signature: other.signature,
+            ..admission.issue(pk, "xayn".to_string(), None).unwrap()
+        };
+        assert!(!admission.admit(&pk, Some(&forged.encode())));
+    }
+
+    #[test]
+    fn test_credentials_disabled() {
+        let pk = SigningKeyPair::generate().public;
+        let credential = admission(Vec::new())
+            .issue(pk, "xayn".to_string(), None)
+            .unwrap()
+            .encode();
+        let admission = Admission::new(&AdmissionSettings {
+            allowed_participants: Vec::new(),
+            issuer_seed: None,
+        });
+
+        assert!(admission.issue(pk, "xayn".to_string(), None).is_none());
+        assert!(!admission.admit(&pk, Some(&credential)));
+    }
+}
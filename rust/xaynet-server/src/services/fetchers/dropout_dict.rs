@@ -0,0 +1,55 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::future::{self, Ready};
+use tower::Service;
+use tracing::error_span;
+use tracing_futures::{Instrument, Instrumented};
+
+use super::Tagged;
+use crate::state_machine::events::{DictionaryUpdate, EventListener, EventSubscriber};
+use xaynet_core::SumDict;
+
+/// A service that returns the dictionary of the sum participants who dropped out during the
+/// current round.
+pub struct DropoutDictService(EventListener<DictionaryUpdate<SumDict>>);
+
+/// [`DropoutDictService`]'s request type
+#[derive(Default, Clone, Eq, PartialEq, Debug)]
+pub struct DropoutDictRequest;
+
+/// [`DropoutDictService`]'s response type.
+///
+/// The response is `None` when no dropout dictionary is currently
+/// available. The dictionary is tagged with the round id.
+pub type DropoutDictResponse = Option<Tagged<Arc<SumDict>>>;
+
+impl DropoutDictService {
+    pub fn new(events: &EventSubscriber) -> Self {
+        Self(events.dropout_dict_listener())
+    }
+}
+
+impl Service<DropoutDictRequest> for DropoutDictService {
+    type Response = DropoutDictResponse;
+    type Error = std::convert::Infallible;
+    type Future = Instrumented<Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _req: DropoutDictRequest) -> Self::Future {
+        let event = self.0.get_latest();
+        future::ready(match event.event {
+            DictionaryUpdate::Invalidate => Ok(None),
+            DictionaryUpdate::New(dict) => Ok(Some(Tagged {
+                tag: format!("dropout-dict-{}", event.round_id),
+                data: dict,
+            })),
+        })
+        .instrument(error_span!("dropout_dict_fetch_request"))
+    }
+}
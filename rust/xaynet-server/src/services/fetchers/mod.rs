@@ -3,6 +3,7 @@
 //! There are multiple such services and the [`Fetcher`] trait
 //! provides a single unifying interface for all of these.
 
+mod dropout_dict;
 mod model;
 mod round_parameters;
 mod seed_dict;
@@ -15,6 +16,7 @@ use futures::future::poll_fn;
 use tower::{layer::Layer, Service, ServiceBuilder};
 
 pub use self::{
+    dropout_dict::{DropoutDictRequest, DropoutDictResponse, DropoutDictService},
     model::{ModelRequest, ModelResponse, ModelService},
     round_parameters::{RoundParamsRequest, RoundParamsResponse, RoundParamsService},
     seed_dict::{SeedDictRequest, SeedDictResponse, SeedDictService},
@@ -39,6 +41,23 @@ pub trait Fetcher {
     /// dictionary to encrypt their masking seed for each sum
     /// participant.
     async fn sum_dict(&mut self) -> Result<SumDictResponse, FetchError>;
+
+    /// Fetch the dropout dictionary. The sum participants need this
+    /// dictionary to provide their seed shares for the sum
+    /// participants who dropped out if the mask seeds are
+    /// secret-shared.
+    async fn dropout_dict(&mut self) -> Result<DropoutDictResponse, FetchError>;
+}
+
+/// Data served by a fetcher along with a tag which identifies the version of the data.
+///
+/// The tag changes whenever the data changes, which allows clients to cache the data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tagged<T> {
+    /// The tag of the data.
+    pub tag: String,
+    /// The data.
+    pub data: T,
 }
 
 /// An error returned by the [`Fetcher`]'s method.
@@ -51,8 +70,8 @@ fn into_fetch_error<E: Into<Box<dyn ::std::error::Error + 'static + Sync + Send>
 }
 
 #[async_trait]
-impl<RoundParams, SumDict, SeedDict, Model> Fetcher
-    for Fetchers<RoundParams, SumDict, SeedDict, Model>
+impl<RoundParams, SumDict, SeedDict, DropoutDict, Model> Fetcher
+    for Fetchers<RoundParams, SumDict, SeedDict, DropoutDict, Model>
 where
     Self: Send + Sync + 'static,
 
@@ -75,6 +94,11 @@ where
     <SumDict as Service<SumDictRequest>>::Future: Send + Sync + 'static,
     <SumDict as Service<SumDictRequest>>::Error:
         Into<Box<dyn ::std::error::Error + 'static + Sync + Send>>,
+
+    DropoutDict: Service<DropoutDictRequest, Response = DropoutDictResponse> + Send + 'static,
+    <DropoutDict as Service<DropoutDictRequest>>::Future: Send + Sync + 'static,
+    <DropoutDict as Service<DropoutDictRequest>>::Error:
+        Into<Box<dyn ::std::error::Error + 'static + Sync + Send>>,
 {
     async fn round_params(&mut self) -> Result<RoundParamsResponse, FetchError> {
         poll_fn(|cx| {
@@ -122,6 +146,20 @@ where
                 .map_err(into_fetch_error)?,
         )
     }
+
+    async fn dropout_dict(&mut self) -> Result<DropoutDictResponse, FetchError> {
+        poll_fn(|cx| {
+            <DropoutDict as Service<DropoutDictRequest>>::poll_ready(&mut self.dropout_dict, cx)
+        })
+        .await
+        .map_err(into_fetch_error)?;
+        Ok(<DropoutDict as Service<DropoutDictRequest>>::call(
+            &mut self.dropout_dict,
+            DropoutDictRequest,
+        )
+        .await
+        .map_err(into_fetch_error)?)
+    }
 }
 
 pub(in crate::services) struct FetcherService<S>(S);
@@ -154,24 +192,29 @@ impl<S> Layer<S> for FetcherLayer {
 }
 
 #[derive(Debug, Clone)]
-pub struct Fetchers<RoundParams, SumDict, SeedDict, Model> {
+pub struct Fetchers<RoundParams, SumDict, SeedDict, DropoutDict, Model> {
     round_params: RoundParams,
     sum_dict: SumDict,
     seed_dict: SeedDict,
+    dropout_dict: DropoutDict,
     model: Model,
 }
 
-impl<RoundParams, SumDict, SeedDict, Model> Fetchers<RoundParams, SumDict, SeedDict, Model> {
+impl<RoundParams, SumDict, SeedDict, DropoutDict, Model>
+    Fetchers<RoundParams, SumDict, SeedDict, DropoutDict, Model>
+{
     pub fn new(
         round_params: RoundParams,
         sum_dict: SumDict,
         seed_dict: SeedDict,
+        dropout_dict: DropoutDict,
         model: Model,
     ) -> Self {
         Self {
             round_params,
             sum_dict,
             seed_dict,
+            dropout_dict,
             model,
         }
     }
@@ -203,5 +246,11 @@ pub fn fetcher(event_subscriber: &EventSubscriber) -> impl Fetcher + Sync + Send
         .layer(FetcherLayer)
         .service(SeedDictService::new(event_subscriber));
 
-    Fetchers::new(round_params, sum_dict, seed_dict, model)
+    let dropout_dict = ServiceBuilder::new()
+        .buffer(100)
+        .concurrency_limit(100)
+        .layer(FetcherLayer)
+        .service(DropoutDictService::new(event_subscriber));
+
+    Fetchers::new(round_params, sum_dict, seed_dict, dropout_dict, model)
 }
@@ -8,6 +8,7 @@ use tower::Service;
 use tracing::error_span;
 use tracing_futures::{Instrument, Instrumented};
 
+use super::Tagged;
 use crate::state_machine::events::{EventListener, EventSubscriber, ModelUpdate};
 use xaynet_core::mask::Model;
 
@@ -17,8 +18,9 @@ pub struct ModelRequest;
 
 /// [`ModelService`]'s response type.
 ///
-/// The response is `None` when no model is currently available.
-pub type ModelResponse = Option<Arc<Model>>;
+/// The response is `None` when no model is currently available. The model is tagged with its
+/// global model id.
+pub type ModelResponse = Option<Tagged<Arc<Model>>>;
 
 /// A service that serves the latest available global model
 pub struct ModelService(EventListener<ModelUpdate>);
@@ -41,7 +43,10 @@ impl Service<ModelRequest> for ModelService {
     fn call(&mut self, _req: ModelRequest) -> Self::Future {
         future::ready(match self.0.get_latest().event {
             ModelUpdate::Invalidate => Ok(None),
-            ModelUpdate::New(model) => Ok(Some(model)),
+            ModelUpdate::New { id, model } => Ok(Some(Tagged {
+                tag: format!("model-{}", id),
+                data: model,
+            })),
         })
         .instrument(error_span!("model_fetch_request"))
     }
@@ -8,6 +8,7 @@ use tower::Service;
 use tracing::error_span;
 use tracing_futures::{Instrument, Instrumented};
 
+use super::Tagged;
 use crate::state_machine::events::{DictionaryUpdate, EventListener, EventSubscriber};
 use xaynet_core::SeedDict;
 
@@ -27,8 +28,8 @@ pub struct SeedDictRequest;
 /// [`SeedDictService`]'s response type.
 ///
 /// The response is `None` when no seed dictionary is currently
-/// available
-pub type SeedDictResponse = Option<Arc<SeedDict>>;
+/// available. The dictionary is tagged with the round id.
+pub type SeedDictResponse = Option<Tagged<Arc<SeedDict>>>;
 
 impl Service<SeedDictRequest> for SeedDictService {
     type Response = SeedDictResponse;
@@ -40,9 +41,13 @@ impl Service<SeedDictRequest> for SeedDictService {
     }
 
     fn call(&mut self, _req: SeedDictRequest) -> Self::Future {
-        future::ready(match self.0.get_latest().event {
+        let event = self.0.get_latest();
+        future::ready(match event.event {
             DictionaryUpdate::Invalidate => Ok(None),
-            DictionaryUpdate::New(dict) => Ok(Some(dict)),
+            DictionaryUpdate::New(dict) => Ok(Some(Tagged {
+                tag: format!("seed-dict-{}", event.round_id),
+                data: dict,
+            })),
         })
         .instrument(error_span!("seed_dict_fetch_request"))
     }
@@ -8,6 +8,7 @@ use tower::Service;
 use tracing::error_span;
 use tracing_futures::{Instrument, Instrumented};
 
+use super::Tagged;
 use crate::state_machine::events::{DictionaryUpdate, EventListener, EventSubscriber};
 use xaynet_core::SumDict;
 
@@ -21,8 +22,8 @@ pub struct SumDictRequest;
 /// [`SumDictService`]'s response type.
 ///
 /// The response is `None` when no sum dictionary is currently
-/// available
-pub type SumDictResponse = Option<Arc<SumDict>>;
+/// available. The dictionary is tagged with the round id.
+pub type SumDictResponse = Option<Tagged<Arc<SumDict>>>;
 
 impl SumDictService {
     pub fn new(events: &EventSubscriber) -> Self {
@@ -40,9 +41,13 @@ impl Service<SumDictRequest> for SumDictService {
     }
 
     fn call(&mut self, _req: SumDictRequest) -> Self::Future {
-        future::ready(match self.0.get_latest().event {
+        let event = self.0.get_latest();
+        future::ready(match event.event {
             DictionaryUpdate::Invalidate => Ok(None),
-            DictionaryUpdate::New(dict) => Ok(Some(dict)),
+            DictionaryUpdate::New(dict) => Ok(Some(Tagged {
+                tag: format!("sum-dict-{}", event.round_id),
+                data: dict,
+            })),
         })
         .instrument(error_span!("sum_dict_fetch_request"))
     }
@@ -1,7 +1,10 @@
 use thiserror::Error;
 
 use crate::state_machine::RequestError;
-use xaynet_core::message::DecodeError;
+use xaynet_core::{
+    common::{MessageError, MessageErrorKind},
+    message::DecodeError,
+};
 
 /// Error type for the message parsing service
 #[derive(Debug, Error)]
@@ -32,10 +35,53 @@ pub enum ServiceError {
     #[error("participant is not eligible for update task")]
     NotUpdateEligible,
 
+    #[error("The message exceeds the maximum size of {0} bytes")]
+    TooLarge(usize),
+
+    #[error("The participant exceeded the rate limit")]
+    RateLimited,
+
+    #[error("The participant exceeded the maximum number of {0} multipart messages")]
+    TooManyMultipartMessages(usize),
+
+    #[error("The participant is not admitted")]
+    NotAdmitted,
+
     #[error("Internal error: {0}")]
     InternalError(String),
 }
 
+impl ServiceError {
+    /// Gets the machine-readable kind of this error.
+    pub fn kind(&self) -> MessageErrorKind {
+        match self {
+            Self::Decrypt => MessageErrorKind::Decrypt,
+            Self::Parsing(_) => MessageErrorKind::Parsing,
+            Self::InvalidMessageSignature => MessageErrorKind::InvalidSignature,
+            Self::InvalidCoordinatorPublicKey => MessageErrorKind::InvalidCoordinatorPublicKey,
+            Self::UnexpectedMessage => MessageErrorKind::UnexpectedMessage,
+            Self::NotSumEligible | Self::NotUpdateEligible => MessageErrorKind::NotEligible,
+            Self::TooLarge(_) => MessageErrorKind::TooLarge,
+            Self::RateLimited | Self::TooManyMultipartMessages(_) => MessageErrorKind::RateLimited,
+            Self::NotAdmitted => MessageErrorKind::NotAdmitted,
+            Self::StateMachine(RequestError::MessageDiscarded) => MessageErrorKind::Discarded,
+            Self::StateMachine(RequestError::InternalError(_))
+            | Self::StateMachine(RequestError::CoordinatorStorage(_))
+            | Self::InternalError(_) => MessageErrorKind::Internal,
+            Self::StateMachine(_) => MessageErrorKind::Rejected,
+        }
+    }
+}
+
+impl From<ServiceError> for MessageError {
+    fn from(error: ServiceError) -> Self {
+        Self {
+            kind: error.kind(),
+            description: error.to_string(),
+        }
+    }
+}
+
 impl From<Box<dyn ::std::error::Error>> for ServiceError {
     fn from(e: Box<dyn ::std::error::Error>) -> Self {
         match e.downcast::<ServiceError>() {
@@ -50,3 +96,47 @@ impl From<Box<dyn ::std::error::Error + Sync + Send>> for ServiceError {
         ServiceError::from(e as Box<dyn ::std::error::Error>)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(ServiceError::Decrypt.kind(), MessageErrorKind::Decrypt);
+        assert_eq!(
+            ServiceError::NotUpdateEligible.kind(),
+            MessageErrorKind::NotEligible
+        );
+        assert_eq!(
+            ServiceError::StateMachine(RequestError::MessageDiscarded).kind(),
+            MessageErrorKind::Discarded
+        );
+        assert_eq!(
+            ServiceError::StateMachine(RequestError::MessageRejected).kind(),
+            MessageErrorKind::Rejected
+        );
+        assert_eq!(
+            ServiceError::StateMachine(RequestError::InternalError("oops")).kind(),
+            MessageErrorKind::Internal
+        );
+        assert_eq!(
+            ServiceError::TooManyMultipartMessages(1).kind(),
+            MessageErrorKind::RateLimited
+        );
+        assert_eq!(
+            ServiceError::NotAdmitted.kind(),
+            MessageErrorKind::NotAdmitted
+        );
+    }
+
+    #[test]
+    fn test_into_message_error() {
+        let error = MessageError::from(ServiceError::UnexpectedMessage);
+        assert_eq!(error.kind, MessageErrorKind::UnexpectedMessage);
+        assert_eq!(
+            error.description,
+            "The message was not expected in the current phase"
+        );
+    }
+}
@@ -7,35 +7,43 @@ use tower::{layer::Layer, limit::concurrency::ConcurrencyLimit, Service, Service
 use tracing::{debug, info, trace, warn};
 
 use crate::{
-    services::messages::{BoxedServiceFuture, ServiceError},
+    metric,
+    metrics::Measurement,
+    services::{
+        admission::Admission,
+        messages::{BoxedServiceFuture, ServiceError},
+    },
     state_machine::{
         events::{EventListener, EventSubscriber},
         phases::PhaseName,
     },
 };
 use xaynet_core::{
-    crypto::{EncryptKeyPair, PublicEncryptKey},
+    crypto::{EncryptKeyPair, PublicEncryptKey, PublicSigningKey},
     message::{FromBytes, Message, MessageBuffer, Tag},
 };
 
+/// A request to parse a message
+pub struct ParseRequest<T> {
+    /// The buffer that contains the message to parse
+    pub message: T,
+    /// The encoded credential presented by the sender of the message
+    pub credential: Option<String>,
+}
+
 /// A type that hold a un-parsed message
 struct RawMessage<T> {
     /// The buffer that contains the message to parse
     buffer: Arc<MessageBuffer<T>>,
+    /// The encoded credential presented by the sender of the message
+    credential: Option<Arc<str>>,
 }
 
 impl<T> Clone for RawMessage<T> {
     fn clone(&self) -> Self {
         Self {
             buffer: self.buffer.clone(),
-        }
-    }
-}
-
-impl<T> From<MessageBuffer<T>> for RawMessage<T> {
-    fn from(buffer: MessageBuffer<T>) -> Self {
-        RawMessage {
-            buffer: Arc::new(buffer),
+            credential: self.credential.clone(),
         }
     }
 }
@@ -45,7 +53,7 @@ impl<T> From<MessageBuffer<T>> for RawMessage<T> {
 #[derive(Debug, Clone)]
 struct BufferWrapper<S>(S);
 
-impl<S, T> Service<T> for BufferWrapper<S>
+impl<S, T> Service<ParseRequest<T>> for BufferWrapper<S>
 where
     T: AsRef<[u8]> + Send + 'static,
     S: Service<RawMessage<T>, Response = Message, Error = ServiceError>,
@@ -59,11 +67,14 @@ where
         self.0.poll_ready(cx)
     }
 
-    fn call(&mut self, req: T) -> Self::Future {
+    fn call(&mut self, req: ParseRequest<T>) -> Self::Future {
         debug!("creating a RawMessage request");
-        match MessageBuffer::new(req) {
+        match MessageBuffer::new(req.message) {
             Ok(buffer) => {
-                let fut = self.0.call(RawMessage::from(buffer));
+                let fut = self.0.call(RawMessage {
+                    buffer: Arc::new(buffer),
+                    credential: req.credential.map(Arc::from),
+                });
                 Box::pin(async move {
                     trace!("calling inner service");
                     fut.await
@@ -114,7 +125,8 @@ where
             Ok(tag) => match (phase, tag) {
                 (PhaseName::Sum, Tag::Sum)
                 | (PhaseName::Update, Tag::Update)
-                | (PhaseName::Sum2, Tag::Sum2) => {
+                | (PhaseName::Sum2, Tag::Sum2)
+                | (PhaseName::Sum2, Tag::Sum2Shares) => {
                     let fut = self.next_svc.call(req);
                     Box::pin(async move { fut.await })
                 }
@@ -225,6 +237,70 @@ impl<S> Layer<S> for SignatureVerifierLayer {
     }
 }
 
+/// A service that discards messages of participants which are not
+/// admitted
+///
+/// The participant public key is only trustworthy after the message
+/// signature has been verified, hence this service must be called
+/// after the [`SignatureVerifier`].
+#[derive(Debug, Clone)]
+struct AdmissionFilter<S> {
+    /// The admission control, if any
+    admission: Option<Arc<Admission>>,
+    /// The name of the task, which tags the metrics
+    task: Arc<str>,
+    /// Next service to be called
+    next_svc: S,
+}
+
+impl<T, S> Service<RawMessage<T>> for AdmissionFilter<S>
+where
+    T: AsRef<[u8]> + Send + 'static,
+    S: Service<RawMessage<T>, Response = Message, Error = ServiceError>,
+    S::Future: Sync + Send + 'static,
+{
+    type Response = Message;
+    type Error = ServiceError;
+    type Future = BoxedServiceFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.next_svc.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: RawMessage<T>) -> Self::Future {
+        if let Some(ref admission) = self.admission {
+            let is_admitted =
+                PublicSigningKey::from_byte_slice(&req.buffer.as_ref().as_ref().participant_pk())
+                    .map(|pk| admission.admit(&pk, req.credential.as_deref()))
+                    .unwrap_or(false);
+            if !is_admitted {
+                warn!("participant is not admitted");
+                metric!(Measurement::MessageNotAdmitted, 1, ("task", &*self.task));
+                return Box::pin(future::ready(Err(ServiceError::NotAdmitted)));
+            }
+        }
+        let fut = self.next_svc.call(req);
+        Box::pin(async move { fut.await })
+    }
+}
+
+struct AdmissionFilterLayer {
+    admission: Option<Arc<Admission>>,
+    task: Arc<str>,
+}
+
+impl<S> Layer<S> for AdmissionFilterLayer {
+    type Service = AdmissionFilter<S>;
+
+    fn layer(&self, service: S) -> AdmissionFilter<S> {
+        AdmissionFilter {
+            admission: self.admission.clone(),
+            task: self.task.clone(),
+            next_svc: service,
+        }
+    }
+}
+
 /// A service that verifies the coordinator public key embedded in PET
 /// messsages
 #[derive(Debug, Clone)]
@@ -309,13 +385,15 @@ where
 }
 
 type InnerService = BufferWrapper<
-    PhaseFilter<ConcurrencyLimit<SignatureVerifier<CoordinatorPublicKeyValidator<Parser>>>>,
+    PhaseFilter<
+        ConcurrencyLimit<SignatureVerifier<AdmissionFilter<CoordinatorPublicKeyValidator<Parser>>>>,
+    >,
 >;
 
 #[derive(Debug, Clone)]
 pub struct MessageParser(InnerService);
 
-impl<T> Service<T> for MessageParser
+impl<T> Service<ParseRequest<T>> for MessageParser
 where
     T: AsRef<[u8]> + Sync + Send + 'static,
 {
@@ -324,23 +402,29 @@ where
     type Future = BoxedServiceFuture<Self::Response, Self::Error>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        <InnerService as Service<T>>::poll_ready(&mut self.0, cx)
+        <InnerService as Service<ParseRequest<T>>>::poll_ready(&mut self.0, cx)
     }
 
-    fn call(&mut self, req: T) -> Self::Future {
+    fn call(&mut self, req: ParseRequest<T>) -> Self::Future {
         let fut = self.0.call(req);
         Box::pin(async move { fut.await })
     }
 }
 
 impl MessageParser {
-    pub fn new(events: &EventSubscriber, thread_pool: Arc<ThreadPool>) -> Self {
+    pub fn new(
+        events: &EventSubscriber,
+        thread_pool: Arc<ThreadPool>,
+        admission: Option<Arc<Admission>>,
+        task: Arc<str>,
+    ) -> Self {
         let inner = ServiceBuilder::new()
             .layer(BufferWrapperLayer)
             .layer(PhaseFilterLayer {
                 phase: events.phase_listener(),
             })
             .layer(SignatureVerifierLayer { thread_pool })
+            .layer(AdmissionFilterLayer { admission, task })
             .layer(CoordinatorPublicKeyValidatorLayer {
                 keys: events.keys_listener(),
             })
@@ -358,20 +442,41 @@ mod tests {
     use super::*;
     use crate::{
         services::tests::utils,
+        settings::{AdmissionSettings, DEFAULT_TASK},
         state_machine::events::{EventPublisher, EventSubscriber},
     };
+    use xaynet_core::crypto::{ByteObject, SigningKeyPair, SigningKeySeed};
+
+    fn spawn_parser(
+        subscriber: &EventSubscriber,
+        admission: Option<Admission>,
+    ) -> Spawn<MessageParser> {
+        let thread_pool = Arc::new(ThreadPoolBuilder::new().build().unwrap());
+        Spawn::new(MessageParser::new(
+            subscriber,
+            thread_pool,
+            admission.map(Arc::new),
+            DEFAULT_TASK.into(),
+        ))
+    }
 
     fn spawn_svc() -> (EventPublisher, EventSubscriber, Spawn<MessageParser>) {
         let (publisher, subscriber) = utils::new_event_channels();
-        let thread_pool = Arc::new(ThreadPoolBuilder::new().build().unwrap());
-        let task = Spawn::new(MessageParser::new(&subscriber, thread_pool));
+        let task = spawn_parser(&subscriber, None);
         (publisher, subscriber, task)
     }
 
+    fn request(message: Vec<u8>, credential: Option<String>) -> ParseRequest<Vec<u8>> {
+        ParseRequest {
+            message,
+            credential,
+        }
+    }
+
     #[tokio::test]
     async fn test_valid_request() {
         let (mut publisher, subscriber, mut task) = spawn_svc();
-        assert_ready!(task.poll_ready::<Vec<u8>>()).unwrap();
+        assert_ready!(task.poll_ready::<ParseRequest<Vec<u8>>>()).unwrap();
 
         let round_params = subscriber.params_listener().get_latest().event;
         let (message, signing_keys) = utils::new_sum_message(&round_params);
@@ -383,7 +488,7 @@ mod tests {
         publisher.broadcast_phase(PhaseName::Sum);
 
         // Call the service
-        let mut resp = task.call(serialized_message).await.unwrap();
+        let mut resp = task.call(request(serialized_message, None)).await.unwrap();
         // The signature should be set. However in `message` it's not been
         // computed, so we just check that it's there, then set it to
         // `None` in `resp`
@@ -396,15 +501,60 @@ mod tests {
     #[tokio::test]
     async fn test_unexpected_message() {
         let (_publisher, subscriber, mut task) = spawn_svc();
-        assert_ready!(task.poll_ready::<Vec<u8>>()).unwrap();
+        assert_ready!(task.poll_ready::<ParseRequest<Vec<u8>>>()).unwrap();
 
         let round_params = subscriber.params_listener().get_latest().event;
         let (message, signing_keys) = utils::new_sum_message(&round_params);
         let serialized_message = utils::serialize_message(&message, &signing_keys);
-        let err = task.call(serialized_message).await.unwrap_err();
+        let err = task
+            .call(request(serialized_message, None))
+            .await
+            .unwrap_err();
         match err {
             ServiceError::UnexpectedMessage => {}
             _ => panic!("expected ServiceError::UnexpectedMessage got {:?}", err),
         }
     }
+
+    #[tokio::test]
+    async fn test_admission() {
+        let (mut publisher, subscriber) = utils::new_event_channels();
+        publisher.broadcast_phase(PhaseName::Sum);
+        let round_params = subscriber.params_listener().get_latest().event;
+        let (message, signing_keys) = utils::new_sum_message(&round_params);
+        let serialized_message = utils::serialize_message(&message, &signing_keys);
+
+        let admission = |allowed_participants| {
+            Admission::new(&AdmissionSettings {
+                allowed_participants,
+                issuer_seed: Some(SigningKeySeed::from_slice_unchecked(&[1; 32])),
+            })
+        };
+        let credential = admission(Vec::new())
+            .issue(message.participant_pk, "xayn".to_string(), None)
+            .unwrap()
+            .encode();
+        let other_credential = admission(Vec::new())
+            .issue(SigningKeyPair::generate().public, "xayn".to_string(), None)
+            .unwrap()
+            .encode();
+
+        for (allowed_participants, credential, is_admitted) in vec![
+            (vec![message.participant_pk], None, true),
+            (Vec::new(), Some(credential), true),
+            (Vec::new(), None, false),
+            (Vec::new(), Some(other_credential), false),
+        ] {
+            let mut task = spawn_parser(&subscriber, Some(admission(allowed_participants)));
+            assert_ready!(task.poll_ready::<ParseRequest<Vec<u8>>>()).unwrap();
+            let result = task
+                .call(request(serialized_message.clone(), credential))
+                .await;
+            if is_admitted {
+                assert!(result.is_ok());
+            } else {
+                assert!(matches!(result, Err(ServiceError::NotAdmitted)));
+            }
+        }
+    }
 }
@@ -7,36 +7,79 @@ mod decryptor;
 mod error;
 mod message_parser;
 mod multipart;
+mod rate_limits;
 mod state_machine;
 mod task_validator;
 
-use std::sync::Arc;
+use std::{
+    net::IpAddr,
+    sync::{atomic::AtomicUsize, Arc},
+};
 
 use futures::future::poll_fn;
-use rayon::ThreadPoolBuilder;
+use rayon::ThreadPool;
 use tower::Service;
-use xaynet_core::message::Message;
+use xaynet_core::message::{Message, ToBytes};
 
-pub use self::error::ServiceError;
 use self::{
     decryptor::Decryptor,
-    message_parser::MessageParser,
-    multipart::MultipartHandler,
+    message_parser::{MessageParser, ParseRequest},
+    multipart::{MultipartHandler, MultipartRequest},
     state_machine::StateMachine,
     task_validator::TaskValidator,
 };
-use crate::state_machine::{events::EventSubscriber, requests::RequestSender};
+pub use self::{error::ServiceError, rate_limits::RateLimits};
+use crate::{
+    metric,
+    metrics::Measurement,
+    services::admission::Admission,
+    settings::{LimitSettings, MaxMessageSizeSettings},
+    state_machine::{events::EventSubscriber, requests::RequestSender},
+};
+
+/// The state of the message limits which is shared by the message handlers of all tasks and APIs.
+#[derive(Debug, Clone, Default)]
+pub struct SharedLimits {
+    /// The rate limits of the messages.
+    pub(in crate::services::messages) rate_limits: RateLimits,
+    /// The accumulated size of the chunks of the incomplete multipart messages of all tasks, which
+    /// is limited by the multipart memory budget.
+    pub(in crate::services::messages) multipart_buffered_size: Arc<AtomicUsize>,
+}
+
+impl SharedLimits {
+    /// Creates the shared state of the limits configured by the given `limits`.
+    pub fn new(limits: &LimitSettings) -> Self {
+        Self {
+            rate_limits: RateLimits::new(limits),
+            multipart_buffered_size: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
 
 impl PetMessageHandler {
-    pub fn new(event_subscriber: &EventSubscriber, requests_tx: RequestSender) -> Self {
-        // TODO: make this configurable. Users should be able to
-        // choose how many threads they want etc.
-        //
-        // TODO: don't unwrap
-        let thread_pool = Arc::new(ThreadPoolBuilder::new().build().unwrap());
+    /// Creates a new handler.
+    ///
+    /// The messages are charged against the `shared_limits`, which may be shared with the handlers
+    /// of other tasks. If an admission control is given, only the messages of admitted participants
+    /// are handled. The `thread_pool` is used for the decryption and parsing of the messages and may be shared
+    /// with the state machine. The metrics of the handler are tagged with the name of the `task`.
+    pub fn new(
+        task: &str,
+        event_subscriber: &EventSubscriber,
+        requests_tx: RequestSender,
+        limits: &LimitSettings,
+        shared_limits: SharedLimits,
+        admission: Option<Arc<Admission>>,
+        thread_pool: Arc<ThreadPool>,
+    ) -> Self {
+        let task: Arc<str> = task.into();
         let decryptor = Decryptor::new(event_subscriber, thread_pool.clone());
-        let multipart_handler = MultipartHandler::new();
-        let message_parser = MessageParser::new(event_subscriber, thread_pool);
+        let rate_limits = shared_limits.rate_limits.clone();
+        let multipart_handler =
+            MultipartHandler::new(event_subscriber, limits, shared_limits, task.clone());
+        let message_parser =
+            MessageParser::new(event_subscriber, thread_pool, admission, task.clone());
         let task_validator = TaskValidator::new(event_subscriber);
         let state_machine = StateMachine::new(requests_tx);
 
@@ -46,17 +89,70 @@ impl PetMessageHandler {
             message_parser,
             task_validator,
             state_machine,
+            rate_limits,
+            max_message_size: limits.max_message_size,
+            task,
+        }
+    }
+
+    /// Gets the name of the task whose messages are handled.
+    pub fn task(&self) -> &str {
+        &self.task
+    }
+
+    /// Checks that an encrypted message doesn't exceed the maximum size of an encrypted message of
+    /// any kind.
+    fn check_encrypted_size(&self, enc_data: &[u8]) -> Result<(), ServiceError> {
+        if let Some(max_size) = self.max_message_size.max_encrypted_size() {
+            if enc_data.len() > max_size {
+                metric!(Measurement::MessageTooLarge, 1, ("task", &*self.task));
+                return Err(ServiceError::TooLarge(max_size));
+            }
         }
+        Ok(())
+    }
+
+    /// Charges a single part message against the rate limit of its participant and checks that
+    /// it doesn't exceed the maximum size of its kind. Multipart messages are checked by the
+    /// [`MultipartHandler`].
+    fn check_limits(&self, message: &Message) -> Result<(), ServiceError> {
+        if message.is_multipart {
+            return Ok(());
+        }
+
+        self.rate_limits
+            .check_participant(&self.task, message.participant_pk)?;
+        if let Some(max_size) = self.max_message_size.get(message.tag) {
+            if message.payload.buffer_length() > max_size {
+                metric!(Measurement::MessageTooLarge, 1, ("task", &*self.task));
+                return Err(ServiceError::TooLarge(max_size));
+            }
+        }
+        Ok(())
     }
     async fn decrypt(&mut self, enc_data: Vec<u8>) -> Result<Vec<u8>, ServiceError> {
         poll_fn(|cx| <Decryptor as Service<Vec<u8>>>::poll_ready(&mut self.decryptor, cx)).await?;
         self.decryptor.call(enc_data).await
     }
 
-    async fn parse(&mut self, data: Vec<u8>) -> Result<Message, ServiceError> {
-        poll_fn(|cx| <MessageParser as Service<Vec<u8>>>::poll_ready(&mut self.message_parser, cx))
-            .await?;
-        self.message_parser.call(data).await
+    async fn parse(
+        &mut self,
+        data: Vec<u8>,
+        credential: Option<String>,
+    ) -> Result<Message, ServiceError> {
+        poll_fn(|cx| {
+            <MessageParser as Service<ParseRequest<Vec<u8>>>>::poll_ready(
+                &mut self.message_parser,
+                cx,
+            )
+        })
+        .await?;
+        self.message_parser
+            .call(ParseRequest {
+                message: data,
+                credential,
+            })
+            .await
     }
 
     async fn handle_multipart(
@@ -64,7 +160,9 @@ impl PetMessageHandler {
         message: Message,
     ) -> Result<Option<Message>, ServiceError> {
         poll_fn(|cx| self.multipart_handler.poll_ready(cx)).await?;
-        self.multipart_handler.call(message).await
+        self.multipart_handler
+            .call(MultipartRequest::Message(message))
+            .await
     }
 
     async fn validate_task(&mut self, message: Message) -> Result<Message, ServiceError> {
@@ -77,9 +175,23 @@ impl PetMessageHandler {
         self.state_machine.call(message).await
     }
 
-    pub async fn handle_message(&mut self, enc_data: Vec<u8>) -> Result<(), ServiceError> {
+    /// Handles an encrypted PET message along with the encoded credential presented by its
+    /// sender and the IP address it was sent from, if any.
+    ///
+    /// The message is refused if it exceeds the limits of the [`LimitSettings`], regardless of
+    /// the API which received it. The rate limit of the IP address is charged before the message
+    /// is decrypted, the one of the participant only after its signature has been verified.
+    pub async fn handle_message(
+        &mut self,
+        enc_data: Vec<u8>,
+        credential: Option<String>,
+        ip: Option<IpAddr>,
+    ) -> Result<(), ServiceError> {
+        self.check_encrypted_size(&enc_data)?;
+        self.rate_limits.check_ip(&self.task, ip)?;
         let raw_message = self.decrypt(enc_data).await?;
-        let message = self.parse(raw_message).await?;
+        let message = self.parse(raw_message, credential).await?;
+        self.check_limits(&message)?;
         match self.handle_multipart(message).await? {
             Some(message) => {
                 let message = self.validate_task(message).await?;
@@ -111,8 +223,51 @@ pub struct PetMessageHandler {
     message_parser: MessageParser,
     task_validator: TaskValidator,
     state_machine: StateMachine,
+    /// The rate limits of the messages, which are shared by all clones.
+    rate_limits: RateLimits,
+    max_message_size: MaxMessageSizeSettings,
+    /// The name of the task, which tags the metrics.
+    task: Arc<str>,
 }
 
 pub type BoxedServiceFuture<Response, Error> = std::pin::Pin<
     Box<dyn futures::Future<Output = Result<Response, Error>> + 'static + Send + Sync>,
 >;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        services::tests::utils,
+        settings::{RateLimitSettings, DEFAULT_TASK},
+    };
+
+    #[tokio::test]
+    async fn test_rate_limit_undecryptable_messages() {
+        let limits = LimitSettings {
+            ip: Some(RateLimitSettings { rate: 1., burst: 2 }),
+            ..LimitSettings::default()
+        };
+        let mut handler = utils::task_services(DEFAULT_TASK, &limits).pet_message_handler;
+        let ip = Some(IpAddr::from([127, 0, 0, 1]));
+
+        for _ in 0..2 {
+            assert!(matches!(
+                handler.handle_message(vec![0; 64], None, ip).await,
+                Err(ServiceError::Decrypt)
+            ));
+        }
+        // further messages are refused before they are decrypted
+        assert!(matches!(
+            handler.handle_message(vec![0; 64], None, ip).await,
+            Err(ServiceError::RateLimited)
+        ));
+
+        // the messages of other IP addresses are still decrypted
+        let other_ip = Some(IpAddr::from([127, 0, 0, 2]));
+        assert!(matches!(
+            handler.handle_message(vec![0; 64], None, other_ip).await,
+            Err(ServiceError::Decrypt)
+        ));
+    }
+}
@@ -1,43 +1,91 @@
 mod buffer;
 mod service;
 
-use std::task::{Context, Poll};
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
 
-use futures::future::TryFutureExt;
+use futures::future::{poll_fn, TryFutureExt};
+use tokio::{stream::StreamExt, time::interval};
 use tower::{buffer::Buffer, Service, ServiceBuilder};
 
-use crate::services::messages::ServiceError;
+pub use self::service::MultipartRequest;
+use crate::{
+    services::messages::{ServiceError, SharedLimits},
+    settings::LimitSettings,
+    state_machine::{
+        events::{EventListener, EventSubscriber},
+        phases::PhaseName,
+    },
+};
 use xaynet_core::message::Message;
 
-type Inner = Buffer<service::MultipartHandler, Message>;
+type Inner = Buffer<service::MultipartHandler, MultipartRequest>;
 
 #[derive(Clone)]
 pub struct MultipartHandler(Inner);
 
-impl Service<Message> for MultipartHandler {
+impl Service<MultipartRequest> for MultipartHandler {
     type Response = Option<Message>;
     type Error = ServiceError;
     #[allow(clippy::type_complexity)]
     type Future = futures::future::MapErr<
-        <Inner as Service<Message>>::Future,
-        fn(<Inner as Service<Message>>::Error) -> ServiceError,
+        <Inner as Service<MultipartRequest>>::Future,
+        fn(<Inner as Service<MultipartRequest>>::Error) -> ServiceError,
     >;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        <Inner as Service<Message>>::poll_ready(&mut self.0, cx).map_err(ServiceError::from)
+        <Inner as Service<MultipartRequest>>::poll_ready(&mut self.0, cx)
+            .map_err(ServiceError::from)
     }
 
-    fn call(&mut self, req: Message) -> Self::Future {
-        <<Inner as Service<Message>>::Future>::map_err(self.0.call(req), ServiceError::from)
+    fn call(&mut self, req: MultipartRequest) -> Self::Future {
+        <<Inner as Service<MultipartRequest>>::Future>::map_err(
+            self.0.call(req),
+            ServiceError::from,
+        )
     }
 }
 
 impl MultipartHandler {
-    pub fn new() -> Self {
-        Self(
-            ServiceBuilder::new()
-                .buffer(100)
-                .service(service::MultipartHandler::new()),
-        )
+    /// Creates a new multipart message handler.
+    ///
+    /// The outdated incomplete multipart messages are evicted at the end of every phase and
+    /// periodically, even if no further chunks are received, until the phase events end.
+    pub fn new(
+        event_subscriber: &EventSubscriber,
+        limits: &LimitSettings,
+        shared_limits: SharedLimits,
+        task: Arc<str>,
+    ) -> Self {
+        let handler = Self(ServiceBuilder::new().buffer(100).service(
+            service::MultipartHandler::new(event_subscriber, limits, shared_limits, task),
+        ));
+        tokio::spawn(
+            handler
+                .clone()
+                .evict_outdated(event_subscriber.phase_listener()),
+        );
+        handler
+    }
+
+    /// Requests the eviction of the outdated incomplete multipart messages whenever the phase
+    /// changes or the expiry interval elapses.
+    async fn evict_outdated(mut self, mut phases: EventListener<PhaseName>) {
+        let mut expiry = interval(service::EXPIRY_INTERVAL);
+        loop {
+            tokio::select! {
+                phase = phases.next() => if phase.is_none() {
+                    break;
+                },
+                _ = expiry.tick() => {}
+            }
+            if poll_fn(|cx| self.poll_ready(cx)).await.is_err()
+                || self.call(MultipartRequest::Evict).await.is_err()
+            {
+                break;
+            }
+        }
     }
 }
@@ -1,6 +1,11 @@
 use std::{
     collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::Poll,
+    time::{Duration, Instant},
 };
 
 use futures::{
@@ -10,12 +15,41 @@ use futures::{
 use tower::Service;
 use tracing::{debug, trace, warn};
 
-use crate::services::messages::{multipart::buffer::MultipartMessageBuffer, ServiceError};
+use crate::{
+    metric,
+    metrics::Measurement,
+    services::messages::{
+        multipart::buffer::MultipartMessageBuffer,
+        RateLimits,
+        ServiceError,
+        SharedLimits,
+    },
+    settings::{LimitSettings, MaxMessageSizeSettings},
+    state_machine::{
+        events::{Event, EventListener, EventSubscriber},
+        phases::PhaseName,
+    },
+};
 use xaynet_core::{
     crypto::{PublicEncryptKey, PublicSigningKey},
-    message::{Chunk, DecodeError, FromBytes, Message, Payload, Sum, Sum2, Tag, Update},
+    message::{
+        Chunk,
+        DecodeError,
+        FromBytes,
+        Message,
+        Payload,
+        Sum,
+        Sum2,
+        Sum2Shares,
+        Tag,
+        Update,
+    },
 };
 
+/// The minimum interval between two checks for expired multipart
+/// messages.
+pub(super) const EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
 /// A `MessageBuilder` stores chunks of a multipart message. Once it
 /// has all the chunks, it can be consumed and turned into a
 /// full-blown [`Message`] (see [`into_message()`]).
@@ -35,6 +69,10 @@ pub struct MessageBuilder {
     last_chunk_id: Option<u16>,
     /// Chunks, ordered by ID
     data: BTreeMap<u16, Vec<u8>>,
+    /// The accumulated size of the chunks
+    size: usize,
+    /// The time when the last chunk was received
+    updated: Instant,
 }
 
 impl MessageBuilder {
@@ -46,6 +84,8 @@ impl MessageBuilder {
             coordinator_pk,
             data: BTreeMap::new(),
             last_chunk_id: None,
+            size: 0,
+            updated: Instant::now(),
         }
     }
 
@@ -66,7 +106,10 @@ impl MessageBuilder {
         if last {
             self.last_chunk_id = Some(id);
         }
-        self.data.insert(id, data);
+        self.size += data.len();
+        if let Some(replaced) = self.data.insert(id, data) {
+            self.size -= replaced.len();
+        }
     }
 
     /// Aggregate all the chunks. This method should only be called
@@ -78,6 +121,7 @@ impl MessageBuilder {
             Tag::Sum => Sum::from_byte_stream(&mut bytes).map(Into::into)?,
             Tag::Update => Update::from_byte_stream(&mut bytes).map(Into::into)?,
             Tag::Sum2 => Sum2::from_byte_stream(&mut bytes).map(Into::into)?,
+            Tag::Sum2Shares => Sum2Shares::from_byte_stream(&mut bytes).map(Into::into)?,
         };
         let message = Message {
             signature: None,
@@ -100,21 +144,234 @@ pub struct MessageId {
     participant_pk: PublicSigningKey,
 }
 
+/// A request to the [`MultipartHandler`].
+#[derive(Debug)]
+// the messages are moved into the buffer of the service either way
+#[allow(clippy::large_enum_variant)]
+pub enum MultipartRequest {
+    /// Handles a message of a participant.
+    Message(Message),
+    /// Evicts the outdated incomplete multipart messages.
+    Evict,
+}
+
 /// A service that handles multipart messages.
+///
+/// A multipart message is charged against the rate limit per
+/// participant once, when its first chunk is received.
+///
+/// Incomplete multipart messages are evicted when the phase ends, when
+/// no chunk was received for the configured timeout or when the
+/// chunks of all incomplete multipart messages exceed the configured
+/// memory budget. The evictions happen when the next chunk is handled
+/// or an eviction is requested.
 pub struct MultipartHandler {
     message_builders: HashMap<MessageId, MessageBuilder>,
+    /// The number of incomplete multipart messages per participant
+    message_counts: HashMap<PublicSigningKey, usize>,
+    /// The accumulated size of the chunks of the incomplete multipart
+    /// messages of this handler
+    buffered_size: usize,
+    /// The accumulated size of the chunks of the incomplete multipart
+    /// messages of the handlers of all tasks
+    total_buffered_size: Arc<AtomicUsize>,
+    /// The rate limits which new multipart messages are charged against
+    /// per participant
+    rate_limits: RateLimits,
+    /// The maximum number of incomplete multipart messages per participant
+    max_messages: Option<usize>,
+    /// The maximum sizes of the multipart messages by their tag
+    max_message_size: MaxMessageSizeSettings,
+    /// The maximum accumulated size of the chunks of the incomplete
+    /// multipart messages of the handlers of all tasks
+    memory_budget: Option<usize>,
+    /// The time after the last received chunk when an incomplete
+    /// multipart message expires
+    timeout: Option<Duration>,
+    /// The time when the expired messages were evicted the last time
+    last_expiry: Instant,
+    phase_listener: EventListener<PhaseName>,
+    /// The phase in which the incomplete multipart messages were
+    /// received
+    phase: Event<PhaseName>,
+    /// The name of the task, which tags the metrics
+    task: Arc<str>,
 }
 
 impl MultipartHandler {
-    #[allow(dead_code)]
-    pub fn new() -> Self {
+    /// Creates a new multipart message handler, which enforces the
+    /// multipart message limits of the given `limits`.
+    pub fn new(
+        event_subscriber: &EventSubscriber,
+        limits: &LimitSettings,
+        shared_limits: SharedLimits,
+        task: Arc<str>,
+    ) -> Self {
+        let phase_listener = event_subscriber.phase_listener();
         Self {
             message_builders: HashMap::new(),
+            message_counts: HashMap::new(),
+            buffered_size: 0,
+            total_buffered_size: shared_limits.multipart_buffered_size,
+            rate_limits: shared_limits.rate_limits,
+            max_messages: limits.max_multipart_messages,
+            max_message_size: limits.max_message_size,
+            memory_budget: limits.multipart_memory_budget,
+            timeout: limits.multipart_timeout.map(Duration::from_secs),
+            last_expiry: Instant::now(),
+            phase: phase_listener.get_latest(),
+            phase_listener,
+            task,
+        }
+    }
+
+    /// Checks whether the participant may start another multipart message and counts it.
+    fn start_message(&mut self, participant_pk: PublicSigningKey) -> Result<(), ServiceError> {
+        self.rate_limits
+            .check_participant(&self.task, participant_pk)?;
+        let count = self.message_counts.entry(participant_pk).or_insert(0);
+        match self.max_messages {
+            Some(max_messages) if *count >= max_messages => {
+                metric!(
+                    Measurement::MessageRateLimited,
+                    1,
+                    ("limit", "multipart"),
+                    ("task", &*self.task)
+                );
+                Err(ServiceError::TooManyMultipartMessages(max_messages))
+            }
+            _ => {
+                *count += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes a multipart message and uncounts it.
+    fn remove_message(&mut self, id: &MessageId) -> Option<MessageBuilder> {
+        let builder = self.message_builders.remove(id)?;
+        self.resize_buffered(builder.size, 0);
+        if let Some(count) = self.message_counts.get_mut(&id.participant_pk) {
+            *count -= 1;
+            if *count == 0 {
+                self.message_counts.remove(&id.participant_pk);
+            }
+        }
+        Some(builder)
+    }
+
+    /// Evicts all incomplete multipart messages if the phase ended
+    /// and the expired ones otherwise.
+    fn evict_outdated(&mut self, now: Instant) {
+        let phase = self.phase_listener.get_latest();
+        if phase != self.phase {
+            self.phase = phase;
+            if !self.message_builders.is_empty() {
+                debug!(
+                    "evicting {} incomplete multipart messages at the end of the phase",
+                    self.message_builders.len()
+                );
+                metric!(
+                    Measurement::MultipartMessageEvicted,
+                    self.message_builders.len() as u64,
+                    ("task", &*self.task)
+                );
+                self.message_builders.clear();
+                self.message_counts.clear();
+                self.resize_buffered(self.buffered_size, 0);
+            }
+            return;
+        }
+
+        if let Some(timeout) = self.timeout {
+            // checking every message for expiry on every chunk would
+            // be wasteful, a precision of a second is good enough
+            if now.saturating_duration_since(self.last_expiry) >= EXPIRY_INTERVAL {
+                self.last_expiry = now;
+                let expired = self
+                    .message_builders
+                    .iter()
+                    .filter(|(_, builder)| {
+                        now.saturating_duration_since(builder.updated) >= timeout
+                    })
+                    .map(|(id, _)| id.clone())
+                    .collect::<Vec<_>>();
+                if !expired.is_empty() {
+                    debug!("evicting {} expired multipart messages", expired.len());
+                    metric!(
+                        Measurement::MultipartMessageExpired,
+                        expired.len() as u64,
+                        ("task", &*self.task)
+                    );
+                    for id in expired {
+                        self.remove_message(&id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records that some of the chunks of this handler take `size`
+    /// instead of `previous_size` bytes now.
+    fn resize_buffered(&mut self, previous_size: usize, size: usize) {
+        self.buffered_size = self.buffered_size - previous_size + size;
+        // the total includes the chunks of this handler, hence it
+        // doesn't underflow
+        if size >= previous_size {
+            self.total_buffered_size
+                .fetch_add(size - previous_size, Ordering::SeqCst);
+        } else {
+            self.total_buffered_size
+                .fetch_sub(previous_size - size, Ordering::SeqCst);
+        }
+    }
+
+    /// Checks whether the chunks of the handlers of all tasks exceed
+    /// the memory budget.
+    fn exceeds_memory_budget(&self, memory_budget: usize) -> bool {
+        self.total_buffered_size.load(Ordering::SeqCst) > memory_budget
+    }
+
+    /// Evicts the least recently extended multipart messages except
+    /// for the given one until the chunks fit into the memory budget.
+    ///
+    /// Only the messages of this handler are evicted, the ones of the
+    /// handlers of other tasks are evicted when they handle their next
+    /// chunk or expire.
+    fn evict_least_recent(&mut self, keep: &MessageId, memory_budget: usize) {
+        while self.exceeds_memory_budget(memory_budget) {
+            let least_recent = self
+                .message_builders
+                .iter()
+                .filter(|(id, _)| *id != keep)
+                .min_by_key(|(_, builder)| builder.updated)
+                .map(|(id, _)| id.clone());
+            match least_recent {
+                Some(id) => {
+                    debug!(
+                        "evicting multipart message (id = {}) to fit into the memory budget",
+                        id.message_id
+                    );
+                    metric!(
+                        Measurement::MultipartMessageEvicted,
+                        1,
+                        ("task", &*self.task)
+                    );
+                    self.remove_message(&id);
+                }
+                None => break,
+            }
         }
     }
 }
 
-impl Service<Message> for MultipartHandler {
+impl Drop for MultipartHandler {
+    fn drop(&mut self) {
+        self.resize_buffered(self.buffered_size, 0);
+    }
+}
+
+impl Service<MultipartRequest> for MultipartHandler {
     type Response = Option<Message>;
     type Error = ServiceError;
     type Future = Ready<Result<Self::Response, Self::Error>>;
@@ -123,7 +380,15 @@ impl Service<Message> for MultipartHandler {
         Poll::Ready(Ok(()))
     }
 
-    fn call(&mut self, message: Message) -> Self::Future {
+    fn call(&mut self, req: MultipartRequest) -> Self::Future {
+        let message = match req {
+            MultipartRequest::Message(message) => message,
+            MultipartRequest::Evict => {
+                self.evict_outdated(Instant::now());
+                return ready_ok(None);
+            }
+        };
+
         // If the message doesn't have the multipart flag, this
         // service has nothing to do with it.
         if !message.is_multipart {
@@ -132,6 +397,8 @@ impl Service<Message> for MultipartHandler {
         }
 
         debug!("handling multipart message");
+        let now = Instant::now();
+        self.evict_outdated(now);
         if let Message {
             tag,
             participant_pk,
@@ -145,21 +412,55 @@ impl Service<Message> for MultipartHandler {
                 participant_pk,
             };
             // If we don't have a partial message for this ID, create
-            // an empty one.
-            let mp_message = self.message_builders.entry(id.clone()).or_insert_with(|| {
+            // an empty one, unless the participant exceeds its rate
+            // limits or already has too many partial messages.
+            if !self.message_builders.contains_key(&id) {
+                if let Err(e) = self.start_message(participant_pk) {
+                    warn!(
+                        "refused new multipart message (id = {}): {}",
+                        id.message_id, e
+                    );
+                    return ready_err(e);
+                }
                 debug!("new multipart message (id = {})", id.message_id);
-                MessageBuilder::new(tag, participant_pk, coordinator_pk)
-            });
+                self.message_builders.insert(
+                    id.clone(),
+                    MessageBuilder::new(tag, participant_pk, coordinator_pk),
+                );
+            }
+            // This entry exists, because it was created above if
+            // necessary, so it's ok to unwrap.
+            let mp_message = self.message_builders.get_mut(&id).unwrap();
             // Add the chunk to the partial message
+            let previous_size = mp_message.size;
             mp_message.add_chunk(chunk);
+            mp_message.updated = now;
+            let size = mp_message.size;
+            let has_all_chunks = mp_message.has_all_chunks();
+            self.resize_buffered(previous_size, size);
+
+            // Drop the partial message if it grows too large
+            if let Some(max_size) = self.max_message_size.get(tag) {
+                if size > max_size {
+                    warn!("multipart message (id = {}) is too large", id.message_id);
+                    metric!(Measurement::MessageTooLarge, 1, ("task", &*self.task));
+                    self.remove_message(&id);
+                    return ready_err(ServiceError::TooLarge(max_size));
+                }
+            }
 
             // Check if the message is complete, and if so parse it
             // and return it
-            if mp_message.has_all_chunks() {
+            if has_all_chunks {
                 debug!("received the final message chunk, now parsing the full message");
-                // This entry exists, because `mp_message` above
-                // refers to it, so it's ok to unwrap.
-                match self.message_builders.remove(&id).unwrap().into_message() {
+                metric!(
+                    Measurement::MultipartMessageCompleted,
+                    1,
+                    ("task", &*self.task)
+                );
+                // This entry exists, because it was created above if
+                // necessary, so it's ok to unwrap.
+                match self.remove_message(&id).unwrap().into_message() {
                     Ok(message) => {
                         debug!("multipart message succesfully parsed");
                         ready_ok(Some(message))
@@ -170,6 +471,25 @@ impl Service<Message> for MultipartHandler {
                     }
                 }
             } else {
+                // Make room for the partial message, unless it
+                // exceeds the memory budget along with the messages
+                // of other tasks
+                if let Some(memory_budget) = self.memory_budget {
+                    self.evict_least_recent(&id, memory_budget);
+                    if self.exceeds_memory_budget(memory_budget) {
+                        warn!(
+                            "multipart message (id = {}) exceeds the memory budget",
+                            id.message_id
+                        );
+                        metric!(
+                            Measurement::MultipartMessageEvicted,
+                            1,
+                            ("task", &*self.task)
+                        );
+                        self.remove_message(&id);
+                        return ready_err(ServiceError::TooLarge(memory_budget));
+                    }
+                }
                 ready_ok(None)
             }
         } else {
@@ -196,9 +516,41 @@ mod tests {
     use xaynet_core::crypto::{ByteObject, PublicEncryptKey, Signature};
 
     use super::*;
+    use crate::{
+        services::tests::utils::new_event_channels,
+        settings::{RateLimitSettings, DEFAULT_TASK},
+        state_machine::events::EventPublisher,
+    };
 
     fn spawn_svc() -> Spawn<MultipartHandler> {
-        Spawn::new(MultipartHandler::new())
+        spawn_svc_with_limits(LimitSettings::default()).1
+    }
+
+    fn spawn_svc_with_limits(limits: LimitSettings) -> (EventPublisher, Spawn<MultipartHandler>) {
+        spawn_svc_with_shared_limits(&limits, SharedLimits::new(&limits))
+    }
+
+    fn spawn_svc_with_shared_limits(
+        limits: &LimitSettings,
+        shared_limits: SharedLimits,
+    ) -> (EventPublisher, Spawn<MultipartHandler>) {
+        let (publisher, subscriber) = new_event_channels();
+        let task = Spawn::new(MultipartHandler::new(
+            &subscriber,
+            limits,
+            shared_limits,
+            DEFAULT_TASK.into(),
+        ));
+        (publisher, task)
+    }
+
+    fn make_message(pk: PublicSigningKey, chunk: &Chunk) -> MultipartRequest {
+        MultipartRequest::Message(Message::new_multipart(
+            pk,
+            PublicEncryptKey::zeroed(),
+            chunk.clone(),
+            Tag::Sum,
+        ))
     }
 
     fn sum() -> (Vec<u8>, Sum) {
@@ -392,8 +744,14 @@ mod tests {
         };
         // function that take a data chunk and create Chunk message
         // with `pk1` as participant public key in the header
-        let make_message1 =
-            |chunk: &Chunk| Message::new_multipart(pk1, coordinator_pk, chunk.clone(), Tag::Sum);
+        let make_message1 = |chunk: &Chunk| {
+            MultipartRequest::Message(Message::new_multipart(
+                pk1,
+                coordinator_pk,
+                chunk.clone(),
+                Tag::Sum,
+            ))
+        };
 
         // Do the same thing to fake a second participant: generate a
         // public key, a message ID, and a function to create messages
@@ -403,8 +761,14 @@ mod tests {
             message_id: 1234,
             participant_pk: pk2,
         };
-        let make_message2 =
-            |chunk: &Chunk| Message::new_multipart(pk2, coordinator_pk, chunk.clone(), Tag::Sum);
+        let make_message2 = |chunk: &Chunk| {
+            MultipartRequest::Message(Message::new_multipart(
+                pk2,
+                coordinator_pk,
+                chunk.clone(),
+                Tag::Sum,
+            ))
+        };
 
         // Start of the actual test. Notice that we send the chunks
         // out of order.
@@ -443,4 +807,234 @@ mod tests {
         assert_eq!(res1, Message::new_sum(pk1, coordinator_pk, sum.clone()));
         assert_eq!(res2, Message::new_sum(pk2, coordinator_pk, sum.clone()));
     }
+
+    #[tokio::test]
+    async fn test_max_messages() {
+        let (_publisher, mut task) = spawn_svc_with_limits(LimitSettings {
+            max_multipart_messages: Some(1),
+            ..LimitSettings::default()
+        });
+        assert_ready!(task.poll_ready()).unwrap();
+
+        let pk = PublicSigningKey::from_slice(&[0x11; PublicSigningKey::LENGTH]).unwrap();
+        let (data, _) = sum();
+        let (c1, c2, c3, c4, c5) = chunks(data);
+        let other_chunk = Chunk {
+            message_id: 4321,
+            ..c1.clone()
+        };
+
+        assert!(task.call(make_message(pk, &c1)).await.unwrap().is_none());
+        // the participant already has an incomplete multipart message
+        assert!(matches!(
+            task.call(make_message(pk, &other_chunk)).await,
+            Err(ServiceError::TooManyMultipartMessages(1))
+        ));
+
+        for chunk in &[c2, c3, c4] {
+            assert!(task.call(make_message(pk, chunk)).await.unwrap().is_none());
+        }
+        assert!(task.call(make_message(pk, &c5)).await.unwrap().is_some());
+        assert!(task.get_ref().message_counts.is_empty());
+
+        // the completed message doesn't count anymore
+        assert!(task
+            .call(make_message(pk, &other_chunk))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_per_message() {
+        let (_publisher, mut task) = spawn_svc_with_limits(LimitSettings {
+            participant: Some(RateLimitSettings { rate: 1., burst: 1 }),
+            ..LimitSettings::default()
+        });
+        assert_ready!(task.poll_ready()).unwrap();
+
+        let pk = PublicSigningKey::zeroed();
+        let (data, _) = sum();
+        let (c1, c2, c3, c4, c5) = chunks(data);
+        let other_chunk = Chunk {
+            message_id: 4321,
+            ..c1.clone()
+        };
+
+        // only the first chunk of a message is charged
+        for chunk in &[c1, c2, c3, c4] {
+            assert!(task.call(make_message(pk, chunk)).await.unwrap().is_none());
+        }
+        assert!(task.call(make_message(pk, &c5)).await.unwrap().is_some());
+        assert!(matches!(
+            task.call(make_message(pk, &other_chunk)).await,
+            Err(ServiceError::RateLimited)
+        ));
+        assert!(task.get_ref().message_builders.is_empty());
+        assert!(task.get_ref().message_counts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_max_message_size() {
+        let (_publisher, mut task) = spawn_svc_with_limits(LimitSettings {
+            max_message_size: MaxMessageSizeSettings {
+                sum: Some(50),
+                ..MaxMessageSizeSettings::default()
+            },
+            ..LimitSettings::default()
+        });
+        assert_ready!(task.poll_ready()).unwrap();
+
+        let pk = PublicSigningKey::zeroed();
+        let (data, _) = sum();
+        let (c1, _, _, _, c5) = chunks(data);
+
+        assert!(task.call(make_message(pk, &c1)).await.unwrap().is_none());
+        assert!(matches!(
+            task.call(make_message(pk, &c5)).await,
+            Err(ServiceError::TooLarge(50))
+        ));
+        assert!(task.get_ref().message_builders.is_empty());
+        assert!(task.get_ref().message_counts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_evict_at_phase_end() {
+        let (mut publisher, mut task) = spawn_svc_with_limits(LimitSettings::default());
+        assert_ready!(task.poll_ready()).unwrap();
+
+        let pk = PublicSigningKey::zeroed();
+        let (data, _) = sum();
+        let (c1, c2, _, _, _) = chunks(data);
+
+        assert!(task.call(make_message(pk, &c1)).await.unwrap().is_none());
+        assert_eq!(task.get_ref().buffered_size, 1);
+
+        publisher.broadcast_phase(PhaseName::Sum);
+        assert!(task.call(make_message(pk, &c2)).await.unwrap().is_none());
+        // only the chunk of the new phase is left
+        let builder = task.get_ref().message_builders.values().next().unwrap();
+        assert_eq!(builder.data.len(), 1);
+        assert_eq!(task.get_ref().buffered_size, 2);
+    }
+
+    #[tokio::test]
+    async fn test_evict_expired() {
+        let (_publisher, mut task) = spawn_svc_with_limits(LimitSettings {
+            multipart_timeout: Some(10),
+            ..LimitSettings::default()
+        });
+        assert_ready!(task.poll_ready()).unwrap();
+
+        let pk = PublicSigningKey::zeroed();
+        let (data, _) = sum();
+        let (c1, _, _, _, _) = chunks(data);
+        assert!(task.call(make_message(pk, &c1)).await.unwrap().is_none());
+
+        let now = Instant::now();
+        task.get_mut().evict_outdated(now + Duration::from_secs(5));
+        assert_eq!(task.get_ref().message_builders.len(), 1);
+
+        task.get_mut().evict_outdated(now + Duration::from_secs(11));
+        assert!(task.get_ref().message_builders.is_empty());
+        assert!(task.get_ref().message_counts.is_empty());
+        assert_eq!(task.get_ref().buffered_size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_evict_on_request() {
+        let (mut publisher, mut task) = spawn_svc_with_limits(LimitSettings::default());
+        assert_ready!(task.poll_ready()).unwrap();
+
+        let pk = PublicSigningKey::zeroed();
+        let (data, _) = sum();
+        let (c1, _, _, _, _) = chunks(data);
+        assert!(task.call(make_message(pk, &c1)).await.unwrap().is_none());
+
+        assert!(task.call(MultipartRequest::Evict).await.unwrap().is_none());
+        assert_eq!(task.get_ref().message_builders.len(), 1);
+
+        // no further chunk is needed to evict the messages of the ended phase
+        publisher.broadcast_phase(PhaseName::Sum);
+        assert!(task.call(MultipartRequest::Evict).await.unwrap().is_none());
+        assert!(task.get_ref().message_builders.is_empty());
+        assert!(task.get_ref().message_counts.is_empty());
+        assert_eq!(task.get_ref().buffered_size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_evict_over_memory_budget() {
+        let (_publisher, mut task) = spawn_svc_with_limits(LimitSettings {
+            multipart_memory_budget: Some(9),
+            ..LimitSettings::default()
+        });
+        assert_ready!(task.poll_ready()).unwrap();
+
+        let pk1 = PublicSigningKey::from_slice(&[0x11; PublicSigningKey::LENGTH]).unwrap();
+        let pk2 = PublicSigningKey::from_slice(&[0x22; PublicSigningKey::LENGTH]).unwrap();
+        let (data, _) = sum();
+        let (_, _, c3, c4, c5) = chunks(data);
+
+        // 3 + 4 bytes fit into the budget
+        assert!(task.call(make_message(pk1, &c3)).await.unwrap().is_none());
+        assert!(task.call(make_message(pk2, &c4)).await.unwrap().is_none());
+        assert_eq!(task.get_ref().buffered_size, 7);
+
+        // 3 + 4 + 3 bytes don't, the least recently extended message is evicted
+        assert!(task.call(make_message(pk2, &c3)).await.unwrap().is_none());
+        assert_eq!(task.get_ref().message_builders.len(), 1);
+        assert_eq!(task.get_ref().buffered_size, 7);
+        assert!(task.get_ref().message_counts.get(&pk1).is_none());
+
+        // a single message which exceeds the budget is refused
+        assert!(matches!(
+            task.call(make_message(pk2, &c5)).await,
+            Err(ServiceError::TooLarge(9))
+        ));
+        assert!(task.get_ref().message_builders.is_empty());
+        assert_eq!(task.get_ref().buffered_size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_memory_budget_shared_by_tasks() {
+        let limits = LimitSettings {
+            multipart_memory_budget: Some(9),
+            ..LimitSettings::default()
+        };
+        let shared_limits = SharedLimits::new(&limits);
+        let (_publisher1, mut task1) = spawn_svc_with_shared_limits(&limits, shared_limits.clone());
+        let (_publisher2, mut task2) = spawn_svc_with_shared_limits(&limits, shared_limits.clone());
+        assert_ready!(task1.poll_ready()).unwrap();
+        assert_ready!(task2.poll_ready()).unwrap();
+
+        let pk1 = PublicSigningKey::from_slice(&[0x11; PublicSigningKey::LENGTH]).unwrap();
+        let pk2 = PublicSigningKey::from_slice(&[0x22; PublicSigningKey::LENGTH]).unwrap();
+        let (data, _) = sum();
+        let (_, _, c3, c4, _) = chunks(data);
+
+        // 3 + 4 bytes of the first task fit into the budget
+        assert!(task1.call(make_message(pk1, &c3)).await.unwrap().is_none());
+        assert!(task1.call(make_message(pk1, &c4)).await.unwrap().is_none());
+
+        // 3 more bytes of the second task don't, the messages of the
+        // first task aren't evicted by the second task
+        assert!(matches!(
+            task2.call(make_message(pk2, &c3)).await,
+            Err(ServiceError::TooLarge(9))
+        ));
+        assert!(task2.get_ref().message_builders.is_empty());
+        assert_eq!(task1.get_ref().buffered_size, 7);
+        assert_eq!(
+            shared_limits.multipart_buffered_size.load(Ordering::SeqCst),
+            7
+        );
+
+        // the chunks of a dropped handler are released
+        drop(task1);
+        assert_eq!(
+            shared_limits.multipart_buffered_size.load(Ordering::SeqCst),
+            0
+        );
+        assert!(task2.call(make_message(pk2, &c3)).await.unwrap().is_none());
+    }
 }
@@ -0,0 +1,135 @@
+use std::{net::IpAddr, sync::Arc};
+
+use xaynet_core::ParticipantPublicKey;
+
+use crate::{
+    metric,
+    metrics::Measurement,
+    services::{messages::ServiceError, rate_limiter::RateLimiter},
+    settings::LimitSettings,
+};
+
+/// The rate limits of the PET messages per IP address and per participant.
+///
+/// The rate limiters are shared by all clones, hence the message handlers of all tasks and APIs
+/// share the same rate limits. Every request is charged against its IP address before it is
+/// decrypted, such that undecryptable requests are charged as well, i.e. every chunk of a
+/// multipart message is charged. Against its participant, a message is charged once after its
+/// signature has been verified, i.e. a multipart message is charged when its first chunk is
+/// received.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+    ip: Option<Arc<RateLimiter<IpAddr>>>,
+    participant: Option<Arc<RateLimiter<ParticipantPublicKey>>>,
+}
+
+impl RateLimits {
+    /// Creates the rate limits configured by the given `limits`.
+    pub fn new(limits: &LimitSettings) -> Self {
+        Self {
+            ip: limits
+                .ip
+                .map(|settings| Arc::new(RateLimiter::new(settings))),
+            participant: limits
+                .participant
+                .map(|settings| Arc::new(RateLimiter::new(settings))),
+        }
+    }
+
+    /// Charges a request of a `task` which was sent from the IP address.
+    ///
+    /// Requests whose IP address is unknown aren't rate limited by their IP address.
+    pub(in crate::services::messages) fn check_ip(
+        &self,
+        task: &str,
+        ip: Option<IpAddr>,
+    ) -> Result<(), ServiceError> {
+        if let (Some(rate_limiter), Some(ip)) = (&self.ip, ip) {
+            if !rate_limiter.check(ip) {
+                metric!(
+                    Measurement::MessageRateLimited,
+                    1,
+                    ("limit", "ip"),
+                    ("task", task)
+                );
+                return Err(ServiceError::RateLimited);
+            }
+        }
+        Ok(())
+    }
+
+    /// Charges a message of a `task` which the participant sent.
+    pub(in crate::services::messages) fn check_participant(
+        &self,
+        task: &str,
+        participant_pk: ParticipantPublicKey,
+    ) -> Result<(), ServiceError> {
+        if let Some(ref rate_limiter) = self.participant {
+            if !rate_limiter.check(participant_pk) {
+                metric!(
+                    Measurement::MessageRateLimited,
+                    1,
+                    ("limit", "participant"),
+                    ("task", task)
+                );
+                return Err(ServiceError::RateLimited);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xaynet_core::crypto::SigningKeyPair;
+
+    use super::*;
+    use crate::settings::{RateLimitSettings, DEFAULT_TASK};
+
+    #[test]
+    fn test_check_ip() {
+        let rate_limit = RateLimitSettings { rate: 1., burst: 1 };
+        let (ip1, ip2) = (IpAddr::from([127, 0, 0, 1]), IpAddr::from([127, 0, 0, 2]));
+
+        let rate_limits = RateLimits::new(&LimitSettings {
+            ip: Some(rate_limit),
+            ..LimitSettings::default()
+        });
+        assert!(rate_limits.check_ip(DEFAULT_TASK, Some(ip1)).is_ok());
+        assert!(matches!(
+            rate_limits.clone().check_ip(DEFAULT_TASK, Some(ip1)),
+            Err(ServiceError::RateLimited)
+        ));
+        assert!(rate_limits.check_ip(DEFAULT_TASK, Some(ip2)).is_ok());
+        // unknown addresses aren't rate limited
+        assert!(rate_limits.check_ip(DEFAULT_TASK, None).is_ok());
+
+        // the IP addresses aren't rate limited without a limit per IP address
+        let rate_limits = RateLimits::new(&LimitSettings {
+            participant: Some(rate_limit),
+            ..LimitSettings::default()
+        });
+        assert!(rate_limits.check_ip(DEFAULT_TASK, Some(ip1)).is_ok());
+        assert!(rate_limits.check_ip(DEFAULT_TASK, Some(ip1)).is_ok());
+    }
+
+    #[test]
+    fn test_check_participant() {
+        let rate_limit = RateLimitSettings { rate: 1., burst: 1 };
+        let (pk1, pk2) = (
+            SigningKeyPair::generate().public,
+            SigningKeyPair::generate().public,
+        );
+
+        let rate_limits = RateLimits::new(&LimitSettings {
+            participant: Some(rate_limit),
+            ..LimitSettings::default()
+        });
+        assert!(rate_limits.check_participant(DEFAULT_TASK, pk1).is_ok());
+        assert!(rate_limits.check_participant(DEFAULT_TASK, pk2).is_ok());
+        assert!(matches!(
+            rate_limits.clone().check_participant(DEFAULT_TASK, pk1),
+            Err(ServiceError::RateLimited)
+        ));
+    }
+}
@@ -42,6 +42,7 @@ impl Service<Message> for TaskValidator {
             Payload::Sum(ref sum) => (sum.sum_signature, None),
             Payload::Update(ref update) => (update.sum_signature, Some(update.update_signature)),
             Payload::Sum2(ref sum2) => (sum2.sum_signature, None),
+            Payload::Sum2Shares(ref sum2_shares) => (sum2_shares.sum_signature, None),
             _ => return future::ready(Err(ServiceError::UnexpectedMessage)),
         };
         let params = self.params_listener.get_latest().event;
@@ -68,7 +69,7 @@ impl Service<Message> for TaskValidator {
                 .unwrap_or(false);
 
         match message.payload {
-            Payload::Sum(_) | Payload::Sum2(_) => {
+            Payload::Sum(_) | Payload::Sum2(_) | Payload::Sum2Shares(_) => {
                 if is_summer {
                     future::ready(Ok(message))
                 } else {
@@ -7,9 +7,34 @@
 //!   module
 //! - the services for processing PET message are provided by the
 //!   [`messages`] module.
+//!
+//! The [`rate_limiter`] module provides the rate limiter of the
+//! [`messages::RateLimits`], which are part of the [`messages::SharedLimits`]
+//! shared by all message services, the
+//! [`admission`] module the admission control of the participants.
+//!
+//! The services of a federated learning task are bundled by
+//! [`TaskServices`], which are served by the APIs.
 
+pub mod admission;
 pub mod fetchers;
 pub mod messages;
+pub mod rate_limiter;
+
+use crate::state_machine::{admin::AdminHandle, events::EventSubscriber};
+
+/// The services of a federated learning task which are served by the REST and gRPC APIs.
+#[derive(Clone)]
+pub struct TaskServices<F> {
+    /// The fetcher for responding to data requests.
+    pub fetcher: F,
+    /// The handler for responding to PET messages.
+    pub pet_message_handler: messages::PetMessageHandler,
+    /// The subscriber for the round events pushed to participants.
+    pub event_subscriber: EventSubscriber,
+    /// The handle for the admin API to inspect and control the state machine.
+    pub admin_handle: AdminHandle,
+}
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
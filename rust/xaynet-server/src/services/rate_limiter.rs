@@ -0,0 +1,144 @@
+//! A token bucket rate limiter for the PET messages.
+
+use std::{collections::HashMap, hash::Hash, sync::Mutex, time::Instant};
+
+use crate::settings::RateLimitSettings;
+
+/// The minimum number of buckets before the full buckets are swept.
+const MIN_SWEEP_THRESHOLD: usize = 1024;
+
+/// The token bucket of a single key.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// A rate limiter which keeps a token bucket per key.
+///
+/// Every request takes a token from the bucket of its key. A bucket holds up to `burst` tokens and
+/// is refilled with `rate` tokens per second. Buckets which are full again are equivalent to new
+/// buckets and are swept from time to time to bound the memory usage.
+#[derive(Debug)]
+pub struct RateLimiter<K> {
+    rate: f64,
+    burst: f64,
+    state: Mutex<State<K>>,
+}
+
+#[derive(Debug)]
+struct State<K> {
+    buckets: HashMap<K, Bucket>,
+    sweep_threshold: usize,
+}
+
+impl<K> RateLimiter<K>
+where
+    K: Hash + Eq,
+{
+    /// Creates a new rate limiter.
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            rate: settings.rate,
+            burst: settings.burst as f64,
+            state: Mutex::new(State {
+                buckets: HashMap::new(),
+                sweep_threshold: MIN_SWEEP_THRESHOLD,
+            }),
+        }
+    }
+
+    /// Takes a token for a request of the given key.
+    ///
+    /// Returns `false` if the request exceeds the rate limit.
+    pub fn check(&self, key: K) -> bool {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: K, now: Instant) -> bool {
+        // the lock is only poisoned if a panic occurred while holding it, which can't happen
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let (rate, burst) = (self.rate, self.burst);
+
+        let bucket = state.buckets.entry(key).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated = now;
+        let is_allowed = 1. <= bucket.tokens;
+        if is_allowed {
+            bucket.tokens -= 1.;
+        }
+
+        if state.buckets.len() > state.sweep_threshold {
+            state.buckets.retain(|_, bucket| {
+                let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+                bucket.tokens + elapsed * rate < burst
+            });
+            state.sweep_threshold = MIN_SWEEP_THRESHOLD.max(2 * state.buckets.len());
+        }
+
+        is_allowed
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.state.lock().unwrap().buckets.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn rate_limiter(rate: f64, burst: u32) -> RateLimiter<u32> {
+        RateLimiter::new(RateLimitSettings { rate, burst })
+    }
+
+    #[test]
+    fn test_burst_and_refill() {
+        let limiter = rate_limiter(2., 3);
+        let now = Instant::now();
+
+        assert!(limiter.check_at(1, now));
+        assert!(limiter.check_at(1, now));
+        assert!(limiter.check_at(1, now));
+        assert!(!limiter.check_at(1, now));
+
+        // other keys have their own bucket
+        assert!(limiter.check_at(2, now));
+
+        // half a second refills a single token
+        let now = now + Duration::from_millis(500);
+        assert!(limiter.check_at(1, now));
+        assert!(!limiter.check_at(1, now));
+
+        // the bucket never holds more than the burst
+        let now = now + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(limiter.check_at(1, now));
+        }
+        assert!(!limiter.check_at(1, now));
+    }
+
+    #[test]
+    fn test_sweep_full_buckets() {
+        let limiter = rate_limiter(1., 1);
+        let now = Instant::now();
+        for key in 0..MIN_SWEEP_THRESHOLD as u32 {
+            assert!(limiter.check_at(key, now));
+        }
+        assert_eq!(limiter.len(), MIN_SWEEP_THRESHOLD);
+
+        // all previous buckets are full again and get swept
+        let now = now + Duration::from_secs(1);
+        assert!(limiter.check_at(MIN_SWEEP_THRESHOLD as u32, now));
+        assert_eq!(limiter.len(), 1);
+        assert!(!limiter.check_at(MIN_SWEEP_THRESHOLD as u32, now));
+    }
+}
@@ -6,6 +6,8 @@ use tower_test::mock::Spawn;
 use crate::{
     services::{
         fetchers::{
+            DropoutDictRequest,
+            DropoutDictService,
             ModelRequest,
             ModelService,
             RoundParamsRequest,
@@ -14,6 +16,7 @@ use crate::{
             SeedDictService,
             SumDictRequest,
             SumDictService,
+            Tagged,
         },
         tests::utils::{mask_config, new_event_channels},
     },
@@ -39,10 +42,19 @@ async fn test_model_svc() {
     assert_eq!(resp, Ok(None));
 
     let model = Arc::new(Model::from(vec![]));
-    publisher.broadcast_model(ModelUpdate::New(model.clone()));
+    publisher.broadcast_model(ModelUpdate::New {
+        id: "1_0123".to_string(),
+        model: model.clone(),
+    });
     assert_ready!(task.poll_ready()).unwrap();
     let resp = task.call(ModelRequest).await;
-    assert_eq!(resp, Ok(Some(model)));
+    assert_eq!(
+        resp,
+        Ok(Some(Tagged {
+            tag: "model-1_0123".to_string(),
+            data: model
+        }))
+    );
 
     publisher.broadcast_model(ModelUpdate::Invalidate);
     assert_ready!(task.poll_ready()).unwrap();
@@ -68,6 +80,8 @@ async fn test_round_params_svc() {
         seed: RoundSeed::fill_with(0x11),
         mask_config: mask_config().into(),
         model_length: 42,
+        seed_sharing_threshold: None,
+        model_delta: false,
     };
     publisher.broadcast_params(params.clone());
     assert_ready!(task.poll_ready()).unwrap();
@@ -109,7 +123,13 @@ async fn test_seed_dict_svc() {
     publisher.broadcast_seed_dict(DictionaryUpdate::New(seed_dict.clone()));
     assert_ready!(task.poll_ready()).unwrap();
     let resp = task.call(SeedDictRequest).await;
-    assert_eq!(resp, Ok(Some(seed_dict)));
+    assert_eq!(
+        resp,
+        Ok(Some(Tagged {
+            tag: "seed-dict-0".to_string(),
+            data: seed_dict
+        }))
+    );
 
     publisher.broadcast_seed_dict(DictionaryUpdate::Invalidate);
     assert_ready!(task.poll_ready()).unwrap();
@@ -144,10 +164,44 @@ async fn test_sum_dict_svc() {
     publisher.broadcast_sum_dict(DictionaryUpdate::New(sum_dict.clone()));
     assert_ready!(task.poll_ready()).unwrap();
     let resp = task.call(SumDictRequest).await;
-    assert_eq!(resp, Ok(Some(sum_dict)));
+    assert_eq!(
+        resp,
+        Ok(Some(Tagged {
+            tag: "sum-dict-0".to_string(),
+            data: sum_dict
+        }))
+    );
 
     publisher.broadcast_sum_dict(DictionaryUpdate::Invalidate);
     assert_ready!(task.poll_ready()).unwrap();
     let resp = task.call(SumDictRequest).await;
     assert_eq!(resp, Ok(None));
 }
+
+#[tokio::test]
+async fn test_dropout_dict_svc() {
+    let (mut publisher, subscriber) = new_event_channels();
+
+    let mut task = Spawn::new(DropoutDictService::new(&subscriber));
+    assert_ready!(task.poll_ready()).unwrap();
+
+    let resp = task.call(DropoutDictRequest).await;
+    assert_eq!(resp, Ok(None));
+
+    let dropout_dict = Arc::new(dummy_sum_dict());
+    publisher.broadcast_dropout_dict(DictionaryUpdate::New(dropout_dict.clone()));
+    assert_ready!(task.poll_ready()).unwrap();
+    let resp = task.call(DropoutDictRequest).await;
+    assert_eq!(
+        resp,
+        Ok(Some(Tagged {
+            tag: "dropout-dict-0".to_string(),
+            data: dropout_dict
+        }))
+    );
+
+    publisher.broadcast_dropout_dict(DictionaryUpdate::Invalidate);
+    assert_ready!(task.poll_ready()).unwrap();
+    let resp = task.call(DropoutDictRequest).await;
+    assert_eq!(resp, Ok(None));
+}
@@ -1,6 +1,17 @@
-use crate::state_machine::{
-    events::{EventPublisher, EventSubscriber, ModelUpdate},
-    phases::PhaseName,
+use crate::{
+    services::{
+        fetchers::{fetcher, Fetcher},
+        messages::{PetMessageHandler, SharedLimits},
+        TaskServices,
+    },
+    settings::LimitSettings,
+    state_machine::{
+        admin::AdminHandle,
+        events::{EventPublisher, EventSubscriber, ModelUpdate},
+        phases::PhaseName,
+        requests::RequestReceiver,
+        tests::utils::thread_pool,
+    },
 };
 use xaynet_core::{
     common::{RoundParameters, RoundSeed},
@@ -15,6 +26,8 @@ pub fn mask_config() -> MaskConfig {
         data_type: mask::DataType::F32,
         bound_type: mask::BoundType::B0,
         model_type: mask::ModelType::M3,
+        sparsity_type: mask::SparsityType::S0,
+        quantization_type: mask::QuantizationType::Qmax,
     }
 }
 
@@ -30,6 +43,8 @@ pub fn new_event_channels() -> (EventPublisher, EventSubscriber) {
         seed: RoundSeed::generate(),
         mask_config: mask_config().into(),
         model_length: 0,
+        seed_sharing_threshold: None,
+        model_delta: false,
     };
     let phase = PhaseName::Idle;
     let round_id = 0;
@@ -37,6 +52,29 @@ pub fn new_event_channels() -> (EventPublisher, EventSubscriber) {
     EventPublisher::init(round_id, keys, params, phase, model)
 }
 
+/// Create the services of a task whose state machine doesn't run.
+pub fn task_services(
+    task: &str,
+    limits: &LimitSettings,
+) -> TaskServices<impl Fetcher + Sync + Send + Clone + 'static> {
+    let (_, event_subscriber) = new_event_channels();
+    let (_, requests_tx) = RequestReceiver::new();
+    TaskServices {
+        fetcher: fetcher(&event_subscriber),
+        pet_message_handler: PetMessageHandler::new(
+            task,
+            &event_subscriber,
+            requests_tx,
+            limits,
+            SharedLimits::new(limits),
+            None,
+            thread_pool(),
+        ),
+        event_subscriber,
+        admin_handle: AdminHandle::new(),
+    }
+}
+
 /// Simulate a participant generating keys and crafting a valid sum
 /// message for the given round parameters. The keys generated by the
 /// participants are returned along with the message.
@@ -2,27 +2,50 @@
 //!
 //! Values defined in the configuration file can be overridden by environment variables. Examples of
 //! configuration files can be found in the `configs/` directory located in the repository root.
+//!
+//! The PET settings can be reloaded from the configuration file without a restart by sending a
+//! `SIGHUP` to the coordinator. The reloaded PET settings are validated and take effect when the
+//! next round starts. See [`SettingsReloader`] for more details.
 
-#[cfg(feature = "tls")]
-use std::path::PathBuf;
-use std::{fmt, path::Path};
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+};
 
 use config::{Config, ConfigError, Environment};
 use redis::{ConnectionInfo, IntoConnectionInfo};
 use serde::{
     de::{self, Deserializer, Visitor},
     Deserialize,
+    Serialize,
 };
 use thiserror::Error;
 use tracing_subscriber::filter::EnvFilter;
 use validator::{Validate, ValidationError, ValidationErrors};
 
-use xaynet_core::mask::{BoundType, DataType, GroupType, MaskConfig, ModelType};
+use xaynet_core::{
+    crypto::{ByteObject, PublicSigningKey, SigningKeySeed, SEALBYTES},
+    mask::{
+        BoundType,
+        DataType,
+        GroupType,
+        InvalidMaskConfigError,
+        MaskConfig,
+        ModelType,
+        QuantizationType,
+        SparsityType,
+        MAX_SHARES,
+    },
+    message::{Tag, CHUNK_HEADER_LENGTH, MESSAGE_HEADER_LENGTH},
+};
 
+mod reload;
 #[cfg(feature = "model-persistence")]
 pub mod s3;
+pub use self::reload::{ReloadError, SettingsReloader};
 #[cfg(feature = "model-persistence")]
-pub use self::{s3::RestoreSettings, s3::S3BucketsSettings, s3::S3Settings};
+pub use self::{s3::S3BucketsSettings, s3::S3Settings};
 
 #[derive(Error, Debug)]
 /// An error related to loading and validation of settings.
@@ -34,6 +57,7 @@ pub enum SettingsError {
 }
 
 #[derive(Debug, Validate, Deserialize)]
+#[validate(schema(function = "validate_settings"))]
 /// The combined settings.
 ///
 /// Each section in the configuration file corresponds to the identically named settings field.
@@ -41,18 +65,41 @@ pub struct Settings {
     pub api: ApiSettings,
     #[validate]
     pub pet: PetSettings,
+    #[validate]
     pub mask: MaskSettings,
     pub log: LoggingSettings,
     pub model: ModelSettings,
+    #[serde(default)]
+    #[validate]
+    pub aggregation: AggregationSettings,
+    #[serde(default)]
+    #[validate]
+    pub differential_privacy: DifferentialPrivacySettings,
     #[validate]
     pub metrics: MetricsSettings,
-    pub redis: RedisSettings,
-    #[cfg(feature = "model-persistence")]
+    #[serde(default)]
+    pub coordinator_storage: CoordinatorStorageSettings,
+    pub redis: Option<RedisSettings>,
+    #[serde(default)]
     #[validate]
-    pub s3: S3Settings,
+    pub model_storage: ModelStorageSettings,
     #[cfg(feature = "model-persistence")]
     #[validate]
+    pub s3: Option<S3Settings>,
+    #[serde(default)]
+    #[validate]
     pub restore: RestoreSettings,
+    #[serde(default)]
+    #[validate]
+    pub tasks: Vec<TaskSettings>,
+    #[serde(default)]
+    #[validate]
+    pub limits: LimitSettings,
+    #[validate]
+    pub admission: Option<AdmissionSettings>,
+    #[serde(default)]
+    #[validate]
+    pub thread_pool: ThreadPoolSettings,
 }
 
 impl Settings {
@@ -72,6 +119,50 @@ impl Settings {
         config.merge(Environment::with_prefix("xaynet").separator("__"))?;
         config.try_into()
     }
+
+    /// Checks that the settings of the selected storage backends are present.
+    fn validate_settings(&self) -> Result<(), ValidationError> {
+        match self.coordinator_storage.backend {
+            CoordinatorStorageBackend::Redis if self.redis.is_none() => {
+                return Err(ValidationError::new("missing redis settings"));
+            }
+            CoordinatorStorageBackend::File if self.coordinator_storage.path.is_none() => {
+                return Err(ValidationError::new("missing coordinator storage path"));
+            }
+            _ => {}
+        }
+
+        if self.metrics.backend == MetricsBackend::InfluxDb && self.metrics.influxdb.is_none() {
+            return Err(ValidationError::new("missing influxdb settings"));
+        }
+
+        match self.model_storage.backend {
+            #[cfg(feature = "model-persistence")]
+            ModelStorageBackend::S3 if self.s3.is_none() => {
+                return Err(ValidationError::new("missing s3 settings"));
+            }
+            ModelStorageBackend::File if self.model_storage.path.is_none() => {
+                return Err(ValidationError::new("missing model storage path"));
+            }
+            _ => {}
+        }
+
+        let mut names = HashSet::with_capacity(self.tasks.len());
+        if self
+            .tasks
+            .iter()
+            .all(|task| names.insert(task.name.as_str()))
+        {
+            Ok(())
+        } else {
+            Err(ValidationError::new("duplicate task names"))
+        }
+    }
+}
+
+/// A wrapper for validate derive.
+fn validate_settings(s: &Settings) -> Result<(), ValidationError> {
+    s.validate_settings()
 }
 
 #[derive(Debug, Validate, Deserialize, Clone, Copy)]
@@ -275,6 +366,32 @@ pub struct PetSettings {
     /// XAYNET_PET__UPDATE=0.1
     /// ```
     pub update: f64,
+
+    /// The fraction of sum participants whose shares are required to reconstruct the mask seed of
+    /// an update participant. If set, the update participants secret-share their mask seeds among
+    /// the sum participants and the sum participants send their shares in the `sum2` phase instead
+    /// of an aggregated mask. The aggregated mask can then be reconstructed by the coordinator as
+    /// long as at least this fraction of the sum participants respond in the `sum2` phase. Leave
+    /// this out to disable secret sharing of the mask seeds.
+    ///
+    /// The value must be between `0` and `1` (i.e. `0 < seed_sharing_threshold <= 1`) and secret
+    /// sharing supports at most `255` sum participants (i.e. `max_sum_count <= 255`). Note that the
+    /// coordinator learns the individual mask seeds of the update participants if it colludes with
+    /// this fraction of the sum participants.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [pet]
+    /// seed_sharing_threshold = 0.5
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_PET__SEED_SHARING_THRESHOLD=0.5
+    /// ```
+    pub seed_sharing_threshold: Option<f64>,
 }
 
 impl PetSettings {
@@ -282,7 +399,8 @@ impl PetSettings {
     fn validate_pet(&self) -> Result<(), ValidationError> {
         self.validate_phase_counts()?;
         self.validate_phase_times()?;
-        self.validate_fractions()
+        self.validate_fractions()?;
+        self.validate_seed_sharing()
     }
 
     /// Checks validity of phase count ranges.
@@ -319,6 +437,19 @@ impl PetSettings {
             Err(ValidationError::new("starvation"))
         }
     }
+
+    /// Checks validity of the seed sharing threshold.
+    fn validate_seed_sharing(&self) -> Result<(), ValidationError> {
+        match self.seed_sharing_threshold {
+            Some(threshold)
+                if !(0. < threshold && threshold <= 1.)
+                    || self.max_sum_count > MAX_SHARES as u64 =>
+            {
+                Err(ValidationError::new("invalid seed sharing threshold"))
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 /// A wrapper for validate derive.
@@ -356,6 +487,49 @@ pub struct ApiSettings {
     /// ```
     pub bind_address: std::net::SocketAddr,
 
+    /// The bearer token to authenticate requests to the admin API under `/admin`. Leave this out
+    /// to disable the admin API.
+    ///
+    /// The admin API allows to inspect the current round, to pause or abort rounds, to shut the
+    /// coordinator down and to update the PET settings for the next round. Hence, the token
+    /// should be a long random string and the API should only be exposed via TLS.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// admin_token = "a-long-random-string"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_API__ADMIN_TOKEN=a-long-random-string
+    /// ```
+    pub admin_token: Option<String>,
+
+    #[cfg(feature = "grpc")]
+    /// The address to which the gRPC API should be bound. Leave this out to disable the gRPC API.
+    /// The coordinator fails to start if the gRPC API can't be bound to the address. If the `tls`
+    /// feature is enabled, the gRPC API requires `tls_certificate` and `tls_key` as well and
+    /// authenticates clients via `tls_client_auth` if it is present.
+    ///
+    /// Requires the `grpc` feature to be enabled.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [api]
+    /// grpc_bind_address = "0.0.0.0:8082"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_API__GRPC_BIND_ADDRESS=0.0.0.0:8082
+    /// ```
+    pub grpc_bind_address: Option<std::net::SocketAddr>,
+
     #[cfg(feature = "tls")]
     /// The path to the server certificate to enable TLS server authentication. Leave this out to
     /// disable server authentication. If this is present, then `tls_key` must also be present.
@@ -435,7 +609,341 @@ fn validate_api(s: &ApiSettings) -> Result<(), ValidationError> {
     s.validate_api()
 }
 
-#[derive(Debug, Validate, Deserialize, Clone, Copy)]
+#[derive(Debug, Validate, Deserialize, Clone, Copy, PartialEq, Default)]
+#[validate(schema(function = "validate_limits"))]
+#[serde(default)]
+/// Limits of the PET messages which the coordinator accepts.
+///
+/// The limits protect the coordinator against participants which flood it with messages. All
+/// limits are optional and disabled by default. Messages which exceed a limit are refused with a
+/// [`MessageErrorKind::TooLarge`] or [`MessageErrorKind::RateLimited`] error.
+///
+/// [`MessageErrorKind::TooLarge`]: xaynet_core::common::MessageErrorKind::TooLarge
+/// [`MessageErrorKind::RateLimited`]: xaynet_core::common::MessageErrorKind::RateLimited
+pub struct LimitSettings {
+    /// The rate limit of the messages per IP address, whether they are sent via the REST or the
+    /// gRPC API. The limit is charged before a message is decrypted, hence every chunk of a
+    /// multipart message counts as a message.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [limits.ip]
+    /// rate = 10.0
+    /// burst = 100
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_LIMITS__IP__RATE=10.0
+    /// XAYNET_LIMITS__IP__BURST=100
+    /// ```
+    pub ip: Option<RateLimitSettings>,
+
+    /// The rate limit of the messages per participant public key. A multipart message counts as
+    /// a single message.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [limits.participant]
+    /// rate = 1.0
+    /// burst = 20
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_LIMITS__PARTICIPANT__RATE=1.0
+    /// XAYNET_LIMITS__PARTICIPANT__BURST=20
+    /// ```
+    pub participant: Option<RateLimitSettings>,
+
+    /// The maximum payload sizes of the messages by their kind.
+    pub max_message_size: MaxMessageSizeSettings,
+
+    /// The maximum number of incomplete multipart messages per participant. The value must be
+    /// greater than `0`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [limits]
+    /// max_multipart_messages = 2
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_LIMITS__MAX_MULTIPART_MESSAGES=2
+    /// ```
+    pub max_multipart_messages: Option<usize>,
+
+    /// The time in seconds after the last received chunk when an incomplete multipart message is
+    /// evicted. The value must be greater than `0`. Incomplete multipart messages are always
+    /// evicted when the phase ends.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [limits]
+    /// multipart_timeout = 60
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_LIMITS__MULTIPART_TIMEOUT=60
+    /// ```
+    pub multipart_timeout: Option<u64>,
+
+    /// The maximum number of bytes of all chunks of incomplete multipart messages of all tasks
+    /// together. If the budget is exceeded, the least recently extended multipart messages of the
+    /// task of the received chunk are evicted. The value must be greater than `0`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [limits]
+    /// multipart_memory_budget = 1_000_000_000
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_LIMITS__MULTIPART_MEMORY_BUDGET=1000000000
+    /// ```
+    pub multipart_memory_budget: Option<usize>,
+}
+
+impl LimitSettings {
+    /// Checks that the rates, bursts, sizes and the multipart message limits are positive.
+    fn validate_limits(&self) -> Result<(), ValidationError> {
+        let MaxMessageSizeSettings {
+            sum,
+            update,
+            sum2,
+            sum2_shares,
+        } = self.max_message_size;
+        let is_valid = self
+            .ip
+            .iter()
+            .chain(self.participant.iter())
+            .all(|limit| 0. < limit.rate && 0 < limit.burst)
+            && [
+                sum,
+                update,
+                sum2,
+                sum2_shares,
+                self.max_multipart_messages,
+                self.multipart_memory_budget,
+            ]
+            .iter()
+            .all(|limit| limit.map(|limit| 0 < limit).unwrap_or(true))
+            && self
+                .multipart_timeout
+                .map(|timeout| 0 < timeout)
+                .unwrap_or(true);
+        if is_valid {
+            Ok(())
+        } else {
+            Err(ValidationError::new("invalid limit(s)"))
+        }
+    }
+}
+
+/// A wrapper for validate derive.
+fn validate_limits(s: &LimitSettings) -> Result<(), ValidationError> {
+    s.validate_limits()
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+/// Settings of a rate limit.
+///
+/// The rate limit is a token bucket: every message takes a token from the bucket, which holds up
+/// to `burst` tokens and is refilled with `rate` tokens per second. Messages are refused while
+/// the bucket is empty.
+pub struct RateLimitSettings {
+    /// The sustained number of messages per second. The value must be greater than `0`.
+    pub rate: f64,
+    /// The maximum number of messages in a burst. The value must be greater than `0`.
+    pub burst: u32,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+/// The maximum payload sizes of the messages by their kind in bytes.
+///
+/// The size of a multipart message is the accumulated size of its chunks. The values must be
+/// greater than `0`. If not set, the size of the respective kind isn't limited.
+///
+/// # Examples
+///
+/// **TOML**
+/// ```text
+/// [limits.max_message_size]
+/// sum = 256
+/// update = 10_000_000
+/// sum2 = 5_000_000
+/// sum2_shares = 10_000
+/// ```
+///
+/// **Environment variable**
+/// ```text
+/// XAYNET_LIMITS__MAX_MESSAGE_SIZE__UPDATE=10000000
+/// ```
+pub struct MaxMessageSizeSettings {
+    /// The maximum payload size of sum messages.
+    pub sum: Option<usize>,
+    /// The maximum payload size of update messages.
+    pub update: Option<usize>,
+    /// The maximum payload size of sum2 messages.
+    pub sum2: Option<usize>,
+    /// The maximum payload size of sum2 shares messages.
+    pub sum2_shares: Option<usize>,
+}
+
+impl MaxMessageSizeSettings {
+    /// Gets the maximum payload size of the messages with the given tag.
+    pub fn get(&self, tag: Tag) -> Option<usize> {
+        match tag {
+            Tag::Sum => self.sum,
+            Tag::Update => self.update,
+            Tag::Sum2 => self.sum2,
+            Tag::Sum2Shares => self.sum2_shares,
+        }
+    }
+
+    /// Gets the maximum size of an encrypted message of any kind, including a single chunk of a
+    /// multipart message.
+    ///
+    /// Returns `None` if the size of any kind isn't limited.
+    pub fn max_encrypted_size(&self) -> Option<usize> {
+        let max_payload_size = [self.sum, self.update, self.sum2, self.sum2_shares]
+            .iter()
+            .try_fold(0, |max, size| size.map(|size| max.max(size)))?;
+        Some(SEALBYTES + MESSAGE_HEADER_LENGTH + CHUNK_HEADER_LENGTH + max_payload_size)
+    }
+}
+
+#[derive(Validate, Deserialize, Clone, Default)]
+#[validate(schema(function = "validate_admission"))]
+#[serde(default)]
+/// Admission control of the participants.
+///
+/// If the admission settings are present, the coordinator only accepts PET messages of the
+/// participants which are either allowed explicitly or present a valid credential issued by the
+/// coordinator. At least one of the two must be configured. Messages of other participants are
+/// refused with a [`MessageErrorKind::NotAdmitted`] error.
+///
+/// A credential binds the public signing key of a participant to an organization and is issued
+/// via the admin API. Participants present it in the `X-Xaynet-Credential` header of their
+/// requests or the `x-xaynet-credential` metadata of their gRPC calls.
+///
+/// [`MessageErrorKind::NotAdmitted`]: xaynet_core::common::MessageErrorKind::NotAdmitted
+pub struct AdmissionSettings {
+    /// The base64 encoded public signing keys of the participants which are admitted without a
+    /// credential.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [admission]
+    /// allowed_participants = [
+    ///     "/7Z6nT8CtkZ2eSOgEKSi8FLPqg6Ty0d+bzuLN8XHa5g=",
+    ///     "4mRb7GlHFrTfxEVuY1OzMD8KY+LBxsv9kiHHRANHjvI=",
+    /// ]
+    /// ```
+    #[serde(deserialize_with = "deserialize_public_keys")]
+    pub allowed_participants: Vec<PublicSigningKey>,
+
+    /// The base64 encoded seed of the signing key pair with which the coordinator issues and
+    /// verifies credentials. Leave this out to disable credentials.
+    ///
+    /// The seed must be kept secret, since anyone who knows it can issue credentials. A new seed
+    /// can be generated with `head -c 32 /dev/urandom | base64`. Changing the seed revokes all
+    /// credentials issued so far.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [admission]
+    /// issuer_seed = "<base64 encoded 32 byte seed>"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_ADMISSION__ISSUER_SEED="$(head -c 32 /dev/urandom | base64)"
+    /// ```
+    #[serde(deserialize_with = "deserialize_issuer_seed")]
+    pub issuer_seed: Option<SigningKeySeed>,
+}
+
+impl fmt::Debug for AdmissionSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the issuer seed is a secret
+        f.debug_struct("AdmissionSettings")
+            .field("allowed_participants", &self.allowed_participants)
+            .field(
+                "issuer_seed",
+                &self.issuer_seed.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
+}
+
+impl AdmissionSettings {
+    /// Checks that any participant can be admitted.
+    fn validate_admission(&self) -> Result<(), ValidationError> {
+        if self.allowed_participants.is_empty() && self.issuer_seed.is_none() {
+            Err(ValidationError::new("no participant can be admitted"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A wrapper for validate derive.
+fn validate_admission(s: &AdmissionSettings) -> Result<(), ValidationError> {
+    s.validate_admission()
+}
+
+/// Decodes a base64 encoded byte object of a setting.
+fn decode_byte_object<B, E>(value: &str, expected: &str) -> Result<B, E>
+where
+    B: ByteObject,
+    E: de::Error,
+{
+    base64::decode(value)
+        .ok()
+        .and_then(|bytes| B::from_slice(&bytes))
+        .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(value), &expected))
+}
+
+fn deserialize_public_keys<'de, D>(deserializer: D) -> Result<Vec<PublicSigningKey>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pk| decode_byte_object(pk, "a base64 encoded public signing key"))
+        .collect()
+}
+
+fn deserialize_issuer_seed<'de, D>(deserializer: D) -> Result<Option<SigningKeySeed>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|seed| decode_byte_object(&seed, "a base64 encoded signing key seed"))
+        .transpose()
+}
+
+#[derive(Debug, Validate, Deserialize, Clone, Copy, PartialEq)]
+#[validate(schema(function = "validate_mask"))]
 /// Masking settings.
 pub struct MaskSettings {
     /// The order of the finite group.
@@ -470,7 +978,8 @@ pub struct MaskSettings {
     /// ```
     pub data_type: DataType,
 
-    /// The bounds of the numbers to be masked.
+    /// The bounds of the numbers to be masked. Either one of the catalogued bound types or a
+    /// custom positive integer which absolutely bounds the numbers.
     ///
     /// # Examples
     ///
@@ -478,15 +987,20 @@ pub struct MaskSettings {
     /// ```text
     /// [mask]
     /// bound_type = "B0"
+    /// # or
+    /// bound_type = 50
     /// ```
     ///
     /// **Environment variable**
     /// ```text
     /// XAYNET_MASK__BOUND_TYPE=B0
+    /// # or
+    /// XAYNET_MASK__BOUND_TYPE=50
     /// ```
     pub bound_type: BoundType,
 
-    /// The maximum number of models to be aggregated.
+    /// The maximum number of models to be aggregated. Either one of the catalogued model types or
+    /// a custom positive number, which is not supported for the `Bmax` bound type.
     ///
     /// # Examples
     ///
@@ -494,13 +1008,79 @@ pub struct MaskSettings {
     /// ```text
     /// [mask]
     /// model_type = "M3"
+    /// # or
+    /// model_type = 5000
     /// ```
     ///
     /// **Environment variable**
     /// ```text
     /// XAYNET_MASK__MODEL_TYPE=M3
+    /// # or
+    /// XAYNET_MASK__MODEL_TYPE=5000
     /// ```
     pub model_type: ModelType,
+
+    /// The fraction of the weights of a model to be masked. The masked weights are a random subset
+    /// of the model weights derived from the round seed, the other weights are not sent to the
+    /// coordinator. Defaults to `S0`, i.e. all weights are masked.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [mask]
+    /// sparsity_type = "S2"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_MASK__SPARSITY_TYPE=S2
+    /// ```
+    #[serde(default)]
+    pub sparsity_type: SparsityType,
+
+    /// The precision of the numbers to be masked. Bounded numbers are stochastically rounded to
+    /// the chosen number of decimal places, which is either one of the catalogued quantization
+    /// types or a custom number of at most 20 decimal places. Defaults to `Qmax`, i.e. the full
+    /// precision of the data type.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [mask]
+    /// quantization_type = "Q4"
+    /// # or
+    /// quantization_type = 3
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_MASK__QUANTIZATION_TYPE=Q4
+    /// # or
+    /// XAYNET_MASK__QUANTIZATION_TYPE=3
+    /// ```
+    #[serde(default)]
+    pub quantization_type: QuantizationType,
+}
+
+impl MaskSettings {
+    /// Checks the custom types of the mask settings.
+    fn validate_mask(&self) -> Result<(), ValidationError> {
+        MaskConfig::from(*self).validate().map_err(|error| {
+            ValidationError::new(match error {
+                InvalidMaskConfigError::BoundType => "invalid custom bound type",
+                InvalidMaskConfigError::ModelType => "invalid custom model type",
+                InvalidMaskConfigError::QuantizationType => "invalid custom quantization type",
+                _ => "invalid mask settings",
+            })
+        })
+    }
+}
+
+/// A wrapper for validate derive.
+fn validate_mask(s: &MaskSettings) -> Result<(), ValidationError> {
+    s.validate_mask()
 }
 
 impl From<MaskSettings> for MaskConfig {
@@ -510,6 +1090,8 @@ impl From<MaskSettings> for MaskConfig {
             data_type,
             bound_type,
             model_type,
+            sparsity_type,
+            quantization_type,
         }: MaskSettings,
     ) -> MaskConfig {
         MaskConfig {
@@ -517,11 +1099,13 @@ impl From<MaskSettings> for MaskConfig {
             data_type,
             bound_type,
             model_type,
+            sparsity_type,
+            quantization_type,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 /// Model settings.
 pub struct ModelSettings {
     /// The expected length of the model. The model length corresponds to the number of elements.
@@ -540,20 +1124,415 @@ pub struct ModelSettings {
     /// XAYNET_MODEL__LENGTH=100
     /// ```
     pub length: usize,
-}
-
-#[derive(Debug, Deserialize, Validate)]
-/// Metrics settings.
-pub struct MetricsSettings {
-    #[validate]
-    /// Settings for the InfluxDB backend.
-    pub influxdb: InfluxSettings,
-}
 
-#[derive(Debug, Deserialize, Validate)]
-/// InfluxDB settings.
-pub struct InfluxSettings {
-    #[validate(url)]
+    /// Whether the participants send the difference between their local model and the global
+    /// model of the previous round instead of their local model. The aggregated difference is added
+    /// back onto the previous global model. Since the differences are usually much smaller than
+    /// the models themselves, this allows for a tighter bound type of the masking configuration.
+    /// Defaults to `false`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [model]
+    /// delta = true
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_MODEL__DELTA=true
+    /// ```
+    #[serde(default)]
+    pub delta: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// The aggregation strategies which compute the global model from the unmasked aggregated model.
+pub enum AggregationStrategyType {
+    /// Federated averaging. The global model moves from the previous global model towards the
+    /// unmasked aggregated model by the server learning rate. A server learning rate of `1`
+    /// corresponds to the plain unmasked aggregated model.
+    FedAvg,
+    /// Adaptive federated optimization with the Adam optimizer on the server.
+    FedAdam,
+    /// Adaptive federated optimization with the Yogi optimizer on the server.
+    FedYogi,
+}
+
+#[derive(Debug, Validate, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[validate(schema(function = "validate_aggregation"))]
+#[serde(default)]
+/// Aggregation settings.
+///
+/// All values are optional and fall back to their defaults.
+pub struct AggregationSettings {
+    /// The strategy which computes the global model from the unmasked aggregated model. Defaults to
+    /// `FedAvg`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [aggregation]
+    /// strategy = "FedAdam"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_AGGREGATION__STRATEGY=FedAdam
+    /// ```
+    pub strategy: AggregationStrategyType,
+
+    /// The server learning rate. The value must be greater than `0`. Defaults to `1`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [aggregation]
+    /// learning_rate = 0.1
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_AGGREGATION__LEARNING_RATE=0.1
+    /// ```
+    pub learning_rate: f64,
+
+    #[validate(range(min = 0, max = 1))]
+    /// The decay rate of the first moment of the `FedAdam` and `FedYogi` strategies. The value
+    /// must be between `0` and `1`. Defaults to `0.9`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [aggregation]
+    /// beta_1 = 0.9
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_AGGREGATION__BETA_1=0.9
+    /// ```
+    pub beta_1: f64,
+
+    #[validate(range(min = 0, max = 1))]
+    /// The decay rate of the second moment of the `FedAdam` and `FedYogi` strategies. The value
+    /// must be between `0` and `1`. Defaults to `0.99`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [aggregation]
+    /// beta_2 = 0.99
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_AGGREGATION__BETA_2=0.99
+    /// ```
+    pub beta_2: f64,
+
+    /// The degree of adaptivity of the `FedAdam` and `FedYogi` strategies. The value must be
+    /// greater than `0`. Defaults to `0.001`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [aggregation]
+    /// tau = 0.001
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_AGGREGATION__TAU=0.001
+    /// ```
+    pub tau: f64,
+
+    /// The maximum L2 norm of the difference between the unmasked aggregated model and the
+    /// previous global model. Larger differences are scaled down to this norm before the strategy
+    /// is applied. The value must be greater than `0`. If not set, the difference isn't clipped.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [aggregation]
+    /// clipping_norm = 10.0
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_AGGREGATION__CLIPPING_NORM=10.0
+    /// ```
+    pub clipping_norm: Option<f64>,
+}
+
+impl Default for AggregationSettings {
+    fn default() -> Self {
+        Self {
+            strategy: AggregationStrategyType::FedAvg,
+            learning_rate: 1.,
+            beta_1: 0.9,
+            beta_2: 0.99,
+            tau: 0.001,
+            clipping_norm: None,
+        }
+    }
+}
+
+impl AggregationSettings {
+    /// Checks that the learning rate, tau and the clipping norm are positive.
+    fn validate_aggregation(&self) -> Result<(), ValidationError> {
+        if 0. < self.learning_rate
+            && 0. < self.tau
+            && self.clipping_norm.map(|norm| 0. < norm).unwrap_or(true)
+        {
+            Ok(())
+        } else {
+            Err(ValidationError::new("invalid aggregation parameter(s)"))
+        }
+    }
+}
+
+/// A wrapper for validate derive.
+fn validate_aggregation(s: &AggregationSettings) -> Result<(), ValidationError> {
+    s.validate_aggregation()
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// The noise mechanisms of the differential privacy.
+pub enum NoiseMechanism {
+    /// The Gaussian mechanism, which provides `(epsilon, delta)`-differential privacy with respect
+    /// to the L2 sensitivity.
+    Gaussian,
+    /// The Laplace mechanism, which provides `epsilon`-differential privacy with respect to the L1
+    /// sensitivity.
+    Laplace,
+}
+
+#[derive(Debug, Validate, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[validate(schema(function = "validate_differential_privacy"))]
+#[serde(default)]
+/// Differential privacy settings.
+///
+/// If enabled, calibrated noise is added to every global model and the privacy budget that is
+/// spent by the coordinator is tracked across rounds. No new rounds are started once the budget
+/// is exhausted. All values are optional and fall back to their defaults.
+pub struct DifferentialPrivacySettings {
+    /// Whether noise is added to the global models. Defaults to `false`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [differential_privacy]
+    /// enable = true
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_DIFFERENTIAL_PRIVACY__ENABLE=true
+    /// ```
+    pub enable: bool,
+
+    /// The noise mechanism. Defaults to `Gaussian`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [differential_privacy]
+    /// mechanism = "Laplace"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_DIFFERENTIAL_PRIVACY__MECHANISM=Laplace
+    /// ```
+    pub mechanism: NoiseMechanism,
+
+    /// The sensitivity of the unmasked aggregated model with respect to a single participant, i.e.
+    /// the L2 sensitivity for the `Gaussian` and the L1 sensitivity for the `Laplace` mechanism.
+    /// The value must be greater than `0`. Defaults to `1`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [differential_privacy]
+    /// sensitivity = 0.01
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_DIFFERENTIAL_PRIVACY__SENSITIVITY=0.01
+    /// ```
+    pub sensitivity: f64,
+
+    /// The privacy parameter `epsilon` which is spent per round. The value must be greater than
+    /// `0` and, for the `Gaussian` mechanism, less than `1`. Defaults to `0.5`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [differential_privacy]
+    /// epsilon = 0.5
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_DIFFERENTIAL_PRIVACY__EPSILON=0.5
+    /// ```
+    pub epsilon: f64,
+
+    /// The privacy parameter `delta` which is spent per round by the `Gaussian` mechanism. The
+    /// value must be between `0` and `1`. Defaults to `0.00001`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [differential_privacy]
+    /// delta = 0.00001
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_DIFFERENTIAL_PRIVACY__DELTA=0.00001
+    /// ```
+    pub delta: f64,
+
+    /// The total privacy budget for `epsilon` over all rounds. The value must be greater or equal
+    /// to `epsilon`. Defaults to `10`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [differential_privacy]
+    /// epsilon_budget = 10.0
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_DIFFERENTIAL_PRIVACY__EPSILON_BUDGET=10.0
+    /// ```
+    pub epsilon_budget: f64,
+
+    /// The total privacy budget for `delta` over all rounds. Only relevant for the `Gaussian`
+    /// mechanism. The value must be greater or equal to `delta`. Defaults to `0.001`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [differential_privacy]
+    /// delta_budget = 0.001
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_DIFFERENTIAL_PRIVACY__DELTA_BUDGET=0.001
+    /// ```
+    pub delta_budget: f64,
+}
+
+impl Default for DifferentialPrivacySettings {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            mechanism: NoiseMechanism::Gaussian,
+            sensitivity: 1.,
+            epsilon: 0.5,
+            delta: 0.00001,
+            epsilon_budget: 10.,
+            delta_budget: 0.001,
+        }
+    }
+}
+
+impl DifferentialPrivacySettings {
+    /// Checks the validity of the privacy parameters and budgets.
+    fn validate_differential_privacy(&self) -> Result<(), ValidationError> {
+        let valid_epsilon = 0. < self.epsilon && self.epsilon <= self.epsilon_budget;
+        let valid_delta = match self.mechanism {
+            // the classical Gaussian mechanism is only defined for epsilon < 1
+            NoiseMechanism::Gaussian => {
+                self.epsilon < 1.
+                    && 0. < self.delta
+                    && self.delta < 1.
+                    && self.delta <= self.delta_budget
+            }
+            NoiseMechanism::Laplace => true,
+        };
+        if 0. < self.sensitivity && valid_epsilon && valid_delta {
+            Ok(())
+        } else {
+            Err(ValidationError::new(
+                "invalid differential privacy parameter(s)",
+            ))
+        }
+    }
+}
+
+/// A wrapper for validate derive.
+fn validate_differential_privacy(s: &DifferentialPrivacySettings) -> Result<(), ValidationError> {
+    s.validate_differential_privacy()
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// The backends to which the metrics are exported.
+pub enum MetricsBackend {
+    /// Pushes the metrics to InfluxDB. Requires the [`InfluxSettings`].
+    InfluxDb,
+    /// Exposes the metrics to be scraped by Prometheus from the `/metrics` endpoint of the REST
+    /// API.
+    Prometheus,
+}
+
+impl Default for MetricsBackend {
+    fn default() -> Self {
+        MetricsBackend::InfluxDb
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+/// Metrics settings.
+pub struct MetricsSettings {
+    #[serde(default)]
+    /// The backend to which the metrics are exported. Defaults to `InfluxDb`.
+    ///
+    /// Requires the `metrics` feature to be enabled.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [metrics]
+    /// backend = "Prometheus"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_METRICS__BACKEND=Prometheus
+    /// ```
+    pub backend: MetricsBackend,
+
+    #[validate]
+    /// Settings for the InfluxDB backend. Only required if the `InfluxDb` backend is selected.
+    pub influxdb: Option<InfluxSettings>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+/// InfluxDB settings.
+pub struct InfluxSettings {
+    #[validate(url)]
     /// The URL where InfluxDB is running.
     ///
     /// # Examples
@@ -587,8 +1566,283 @@ pub struct InfluxSettings {
     pub db: String,
 }
 
+/// The name of the default task, which is configured by the top-level settings.
+///
+/// The name is reserved and can't be used by any of the additional tasks.
+pub const DEFAULT_TASK: &str = "default";
+
+#[derive(Debug, Validate, Deserialize, Clone)]
+/// Settings of an additional federated learning task.
+///
+/// The top-level PET, mask, model, aggregation and differential privacy settings configure the
+/// default task, which is named [`DEFAULT_TASK`]. Each additional task runs its own state machine
+/// with its own keypair next to the default task. Its REST routes, including its admin routes, are
+/// scoped under `/tasks/{name}/`, gRPC calls select it via the `x-xaynet-task` metadata and its
+/// metrics are tagged with its name. Its data is stored in a separate namespace of the selected
+/// storage backends:
+/// - Redis: the keys are prefixed with `{name}:`.
+/// - S3: the global models are stored under the object key prefix `{name}/`.
+/// - File model storage: the global models are stored in the subdirectory `{name}`.
+/// - File coordinator storage: the data is stored in the file `{name}-{file name}` next to the
+///   configured file.
+///
+/// # Examples
+///
+/// **TOML**
+/// ```text
+/// [[tasks]]
+/// name = "keyboard"
+///
+/// [tasks.pet]
+/// min_sum_count = 1
+/// # ...
+///
+/// [tasks.mask]
+/// group_type = "Prime"
+/// # ...
+///
+/// [tasks.model]
+/// length = 100
+/// ```
+pub struct TaskSettings {
+    #[validate(custom = "validate_task_name")]
+    /// The name of the task. The name must consist of 1 to 32 lowercase ASCII letters, digits and
+    /// hyphens, must be unique among all tasks and must not be the name of the default task.
+    pub name: String,
+    #[validate]
+    /// The PET protocol settings of the task.
+    pub pet: PetSettings,
+    /// The masking settings of the task.
+    pub mask: MaskSettings,
+    /// The model settings of the task.
+    pub model: ModelSettings,
+    #[serde(default)]
+    #[validate]
+    /// The aggregation settings of the task.
+    pub aggregation: AggregationSettings,
+    #[serde(default)]
+    #[validate]
+    /// The differential privacy settings of the task.
+    pub differential_privacy: DifferentialPrivacySettings,
+}
+
+/// Checks that a task name is a valid path segment and storage namespace which doesn't clash with
+/// the default task.
+fn validate_task_name(name: &str) -> Result<(), ValidationError> {
+    let is_valid = name != DEFAULT_TASK
+        && (1..=32).contains(&name.len())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+    if is_valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid task name"))
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// The backends in which the coordinator data can be stored.
+pub enum CoordinatorStorageBackend {
+    /// Stores the coordinator data in Redis. Requires the [`RedisSettings`].
+    Redis,
+    /// Stores the coordinator data in the memory of the coordinator process. The data is lost
+    /// when the coordinator shuts down.
+    InMemory,
+    /// Stores the coordinator data in a file on the local disk. Requires the
+    /// [`CoordinatorStorageSettings::path`].
+    File,
+}
+
+#[derive(Debug, Deserialize)]
+/// Coordinator storage settings.
+pub struct CoordinatorStorageSettings {
+    /// The backend in which the coordinator data is stored. Defaults to `Redis`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [coordinator_storage]
+    /// backend = "InMemory"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_COORDINATOR_STORAGE__BACKEND=InMemory
+    /// ```
+    pub backend: CoordinatorStorageBackend,
+
+    /// The path of the file in which the coordinator data is stored. Only required if the `File`
+    /// backend is selected. The directory of the file must exist. The changes since the data was
+    /// last compacted into the file are logged next to it, in a file with an additional `.log`
+    /// extension.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [coordinator_storage]
+    /// backend = "File"
+    /// path = "/var/lib/xaynet/coordinator.db"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_COORDINATOR_STORAGE__PATH=/var/lib/xaynet/coordinator.db
+    /// ```
+    pub path: Option<PathBuf>,
+}
+
+// Default value for the coordinator storage
+impl Default for CoordinatorStorageSettings {
+    fn default() -> Self {
+        Self {
+            backend: CoordinatorStorageBackend::Redis,
+            path: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// The backends in which the global models can be stored.
+pub enum ModelStorageBackend {
+    /// Doesn't store the global models.
+    NoOp,
+    /// Stores the global models in files in a directory on the local disk. Requires the
+    /// [`ModelStorageSettings::path`].
+    File,
+    #[cfg(feature = "model-persistence")]
+    /// Stores the global models in a S3 bucket. Requires the [`S3Settings`].
+    S3,
+}
+
+#[derive(Debug, Validate, Deserialize)]
+/// Model storage settings.
+pub struct ModelStorageSettings {
+    /// The backend in which the global models are stored. Defaults to `S3` if the coordinator is
+    /// built with the `model-persistence` feature, otherwise to `NoOp`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [model_storage]
+    /// backend = "File"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_MODEL_STORAGE__BACKEND=File
+    /// ```
+    pub backend: ModelStorageBackend,
+
+    /// The path of the directory in which the global models are stored. Only required if the
+    /// `File` backend is selected. The directory is created if it doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [model_storage]
+    /// backend = "File"
+    /// path = "/var/lib/xaynet/global_models"
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_MODEL_STORAGE__PATH=/var/lib/xaynet/global_models
+    /// ```
+    pub path: Option<PathBuf>,
+
+    #[validate(range(min = 1))]
+    /// The number of the most recent global models which are kept by the `File` backend. Older
+    /// global models are deleted. If not set, all global models are kept. The value must be
+    /// greater or equal to `1`.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [model_storage]
+    /// retention = 10
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_MODEL_STORAGE__RETENTION=10
+    /// ```
+    pub retention: Option<usize>,
+}
+
+// Default value for the model storage
+impl Default for ModelStorageSettings {
+    fn default() -> Self {
+        Self {
+            #[cfg(not(feature = "model-persistence"))]
+            backend: ModelStorageBackend::NoOp,
+            #[cfg(feature = "model-persistence")]
+            backend: ModelStorageBackend::S3,
+            path: None,
+            retention: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Validate, Clone, Copy)]
+/// Restore settings.
+pub struct RestoreSettings {
+    /// If set to `false`, the restoring of coordinator state is prevented.
+    /// Instead, the state is reset and the coordinator is started with the
+    /// settings of the configuration file. Defaults to `false`.
+    ///
+    /// The global model is only restored along with the coordinator state if the selected model
+    /// storage keeps the global models, i.e. for the `File` and `S3` backends.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [restore]
+    /// enable = true
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_RESTORE__ENABLE=false
+    /// ```
+    pub enable: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Validate, Clone, Copy, PartialEq)]
+/// Settings of the thread-pool.
+///
+/// The thread-pool is shared by all tasks. It decrypts and parses the PET messages and aggregates
+/// the masked models.
+pub struct ThreadPoolSettings {
+    #[validate(range(min = 1))]
+    /// The number of threads of the thread-pool. The value must be greater than `0`. Defaults to
+    /// the number of logical CPUs.
+    ///
+    /// # Examples
+    ///
+    /// **TOML**
+    /// ```text
+    /// [thread_pool]
+    /// num_threads = 4
+    /// ```
+    ///
+    /// **Environment variable**
+    /// ```text
+    /// XAYNET_THREAD_POOL__NUM_THREADS=4
+    /// ```
+    pub num_threads: Option<usize>,
+}
+
 #[derive(Debug, Deserialize)]
 /// Redis settings.
+///
+/// Only required if the Redis coordinator storage backend is selected.
 pub struct RedisSettings {
     /// The URL where Redis is running.
     ///
@@ -690,7 +1944,10 @@ where
 
 #[cfg(test)]
 mod tests {
+    use serial_test::serial;
+
     use super::*;
+    use xaynet_core::crypto::SigningKeyPair;
 
     impl Default for PetSettings {
         fn default() -> Self {
@@ -705,6 +1962,7 @@ mod tests {
                 max_update_time: 604800,
                 sum: 0.01,
                 update: 0.1,
+                seed_sharing_threshold: None,
             }
         }
     }
@@ -716,16 +1974,351 @@ mod tests {
                 data_type: DataType::F32,
                 bound_type: BoundType::B0,
                 model_type: ModelType::M3,
+                sparsity_type: SparsityType::S0,
+                quantization_type: QuantizationType::Qmax,
             }
         }
     }
 
     #[test]
+    #[serial]
     fn test_settings_new() {
         assert!(Settings::new("../../configs/config.toml").is_ok());
         assert!(Settings::new("").is_err());
     }
 
+    #[test]
+    #[serial]
+    fn test_validate_settings() {
+        let mut settings = Settings::new("../../configs/config.toml").unwrap();
+        assert_eq!(
+            settings.coordinator_storage.backend,
+            CoordinatorStorageBackend::Redis
+        );
+
+        settings.redis = None;
+        assert!(settings.validate().is_err());
+
+        settings.coordinator_storage.backend = CoordinatorStorageBackend::InMemory;
+        assert!(settings.validate().is_ok());
+
+        settings.coordinator_storage.backend = CoordinatorStorageBackend::File;
+        assert!(settings.validate().is_err());
+
+        settings.coordinator_storage.path = Some(PathBuf::from("coordinator.db"));
+        assert!(settings.validate().is_ok());
+
+        settings.model_storage.backend = ModelStorageBackend::File;
+        assert!(settings.validate().is_err());
+
+        settings.model_storage.path = Some(PathBuf::from("global_models"));
+        assert!(settings.validate().is_ok());
+
+        settings.model_storage.retention = Some(0);
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_validate_metrics_settings() {
+        let mut settings = Settings::new("../../configs/config.toml").unwrap();
+        assert_eq!(settings.metrics.backend, MetricsBackend::InfluxDb);
+
+        settings.metrics.influxdb = None;
+        assert!(settings.validate().is_err());
+
+        settings.metrics.backend = MetricsBackend::Prometheus;
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_validate_tasks() {
+        let mut settings = Settings::new("../../configs/config.toml").unwrap();
+        let task = TaskSettings {
+            name: "keyboard".into(),
+            pet: PetSettings::default(),
+            mask: MaskSettings::default(),
+            model: ModelSettings {
+                length: 10,
+                delta: false,
+            },
+            aggregation: AggregationSettings::default(),
+            differential_privacy: DifferentialPrivacySettings::default(),
+        };
+        settings.tasks = vec![task.clone()];
+        assert!(settings.validate().is_ok());
+
+        settings.tasks.push(TaskSettings {
+            name: "next-word-2".into(),
+            ..task.clone()
+        });
+        assert!(settings.validate().is_ok());
+
+        settings.tasks.push(task.clone());
+        assert!(settings.validate().is_err());
+
+        for name in &[
+            "",
+            "Keyboard",
+            "key_board",
+            "tasks/keyboard",
+            "a".repeat(33).as_str(),
+            DEFAULT_TASK,
+        ] {
+            settings.tasks = vec![TaskSettings {
+                name: name.to_string(),
+                ..task.clone()
+            }];
+            assert!(settings.validate().is_err());
+        }
+
+        settings.tasks = vec![TaskSettings {
+            pet: PetSettings {
+                sum: 0.,
+                ..PetSettings::default()
+            },
+            ..task
+        }];
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_limits() {
+        assert!(LimitSettings::default().validate().is_ok());
+
+        let rate_limit = RateLimitSettings {
+            rate: 1.,
+            burst: 10,
+        };
+        assert!(LimitSettings {
+            ip: Some(rate_limit),
+            participant: Some(rate_limit),
+            max_multipart_messages: Some(1),
+            ..LimitSettings::default()
+        }
+        .validate()
+        .is_ok());
+        assert!(LimitSettings {
+            ip: Some(RateLimitSettings {
+                rate: 0.,
+                ..rate_limit
+            }),
+            ..LimitSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(LimitSettings {
+            participant: Some(RateLimitSettings {
+                burst: 0,
+                ..rate_limit
+            }),
+            ..LimitSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(LimitSettings {
+            max_multipart_messages: Some(0),
+            ..LimitSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(LimitSettings {
+            multipart_timeout: Some(0),
+            ..LimitSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(LimitSettings {
+            multipart_memory_budget: Some(0),
+            ..LimitSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(LimitSettings {
+            max_message_size: MaxMessageSizeSettings {
+                update: Some(0),
+                ..MaxMessageSizeSettings::default()
+            },
+            ..LimitSettings::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_validate_thread_pool() {
+        assert!(ThreadPoolSettings::default().validate().is_ok());
+        assert!(ThreadPoolSettings {
+            num_threads: Some(4)
+        }
+        .validate()
+        .is_ok());
+        assert!(ThreadPoolSettings {
+            num_threads: Some(0)
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_max_encrypted_size() {
+        let mut max_message_size = MaxMessageSizeSettings {
+            sum: Some(256),
+            update: Some(1000),
+            sum2: Some(500),
+            sum2_shares: None,
+        };
+        assert_eq!(max_message_size.get(Tag::Update), Some(1000));
+        assert_eq!(max_message_size.get(Tag::Sum2Shares), None);
+        assert_eq!(max_message_size.max_encrypted_size(), None);
+
+        max_message_size.sum2_shares = Some(10);
+        assert_eq!(
+            max_message_size.max_encrypted_size(),
+            Some(SEALBYTES + MESSAGE_HEADER_LENGTH + CHUNK_HEADER_LENGTH + 1000)
+        );
+    }
+
+    #[test]
+    fn test_validate_admission() {
+        assert!(AdmissionSettings::default().validate().is_err());
+
+        let pk = SigningKeyPair::generate().public;
+        assert!(AdmissionSettings {
+            allowed_participants: vec![pk],
+            ..AdmissionSettings::default()
+        }
+        .validate()
+        .is_ok());
+        assert!(AdmissionSettings {
+            issuer_seed: Some(SigningKeySeed::generate()),
+            ..AdmissionSettings::default()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn test_deserialize_admission() {
+        let pk = SigningKeyPair::generate().public;
+        let seed = SigningKeySeed::generate();
+        let encoded_seed = base64::encode(seed.as_slice());
+        let settings: AdmissionSettings = serde_json::from_value(serde_json::json!({
+            "allowed_participants": [base64::encode(pk.as_slice())],
+            "issuer_seed": encoded_seed,
+        }))
+        .unwrap();
+        assert_eq!(settings.allowed_participants, vec![pk]);
+        assert!(settings.issuer_seed == Some(seed));
+        assert!(!format!("{:?}", settings).contains(&encoded_seed));
+
+        let settings: AdmissionSettings = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(settings.allowed_participants.is_empty());
+        assert!(settings.issuer_seed.is_none());
+
+        let invalid_pk = serde_json::json!({ "allowed_participants": ["invalid"] });
+        assert!(serde_json::from_value::<AdmissionSettings>(invalid_pk).is_err());
+        let invalid_seed = serde_json::json!({ "issuer_seed": base64::encode([0; 16]) });
+        assert!(serde_json::from_value::<AdmissionSettings>(invalid_seed).is_err());
+    }
+
+    #[test]
+    fn test_validate_aggregation() {
+        assert!(AggregationSettings::default().validate().is_ok());
+
+        assert!(AggregationSettings {
+            learning_rate: 0.,
+            ..AggregationSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(AggregationSettings {
+            beta_1: 1.1,
+            ..AggregationSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(AggregationSettings {
+            beta_2: -0.1,
+            ..AggregationSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(AggregationSettings {
+            tau: 0.,
+            ..AggregationSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(AggregationSettings {
+            clipping_norm: Some(0.),
+            ..AggregationSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(AggregationSettings {
+            clipping_norm: Some(1.),
+            ..AggregationSettings::default()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn test_validate_differential_privacy() {
+        assert!(DifferentialPrivacySettings::default().validate().is_ok());
+
+        assert!(DifferentialPrivacySettings {
+            sensitivity: 0.,
+            ..DifferentialPrivacySettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(DifferentialPrivacySettings {
+            epsilon: 0.,
+            ..DifferentialPrivacySettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(DifferentialPrivacySettings {
+            epsilon_budget: 0.1,
+            ..DifferentialPrivacySettings::default()
+        }
+        .validate()
+        .is_err());
+
+        // gaussian mechanism
+        assert!(DifferentialPrivacySettings {
+            epsilon: 1.,
+            ..DifferentialPrivacySettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(DifferentialPrivacySettings {
+            delta: 0.,
+            ..DifferentialPrivacySettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(DifferentialPrivacySettings {
+            delta_budget: 0.,
+            ..DifferentialPrivacySettings::default()
+        }
+        .validate()
+        .is_err());
+
+        // laplace mechanism
+        assert!(DifferentialPrivacySettings {
+            mechanism: NoiseMechanism::Laplace,
+            epsilon: 1.,
+            delta: 0.,
+            ..DifferentialPrivacySettings::default()
+        }
+        .validate()
+        .is_ok());
+    }
+
     #[test]
     fn test_validate_pet() {
         assert!(PetSettings::default().validate_pet().is_ok());
@@ -771,6 +2364,96 @@ mod tests {
         }
         .validate()
         .is_err());
+
+        // seed sharing
+        assert!(PetSettings {
+            seed_sharing_threshold: Some(0.5),
+            ..PetSettings::default()
+        }
+        .validate()
+        .is_ok());
+        assert!(PetSettings {
+            seed_sharing_threshold: Some(0.),
+            ..PetSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(PetSettings {
+            seed_sharing_threshold: Some(1. + f64::EPSILON),
+            ..PetSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(PetSettings {
+            max_sum_count: 256,
+            seed_sharing_threshold: Some(0.5),
+            ..PetSettings::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_deserialize_mask() {
+        let settings: MaskSettings = serde_json::from_value(serde_json::json!({
+            "group_type": "Prime",
+            "data_type": "F32",
+            "bound_type": 50,
+            "model_type": "5000",
+            "sparsity_type": "S0",
+            "quantization_type": 3,
+        }))
+        .unwrap();
+        assert_eq!(settings.bound_type, BoundType::Custom(50));
+        assert_eq!(settings.model_type, ModelType::Custom(5000));
+        assert_eq!(settings.quantization_type, QuantizationType::Custom(3));
+
+        let invalid_bound = serde_json::json!({
+            "group_type": "Prime",
+            "data_type": "F32",
+            "bound_type": "B1",
+            "model_type": "M3",
+        });
+        assert!(serde_json::from_value::<MaskSettings>(invalid_bound).is_err());
+    }
+
+    #[test]
+    fn test_validate_mask() {
+        assert!(MaskSettings::default().validate().is_ok());
+        assert!(MaskSettings {
+            bound_type: BoundType::Custom(50),
+            model_type: ModelType::Custom(5000),
+            quantization_type: QuantizationType::Custom(3),
+            ..MaskSettings::default()
+        }
+        .validate()
+        .is_ok());
+
+        assert!(MaskSettings {
+            bound_type: BoundType::Custom(0),
+            ..MaskSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(MaskSettings {
+            model_type: ModelType::Custom(0),
+            ..MaskSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(MaskSettings {
+            bound_type: BoundType::Bmax,
+            model_type: ModelType::Custom(5000),
+            ..MaskSettings::default()
+        }
+        .validate()
+        .is_err());
+        assert!(MaskSettings {
+            quantization_type: QuantizationType::Custom(21),
+            ..MaskSettings::default()
+        }
+        .validate()
+        .is_err());
     }
 
     #[cfg(feature = "tls")]
@@ -781,6 +2464,9 @@ mod tests {
 
         assert!(ApiSettings {
             bind_address,
+            admin_token: None,
+            #[cfg(feature = "grpc")]
+            grpc_bind_address: None,
             tls_certificate: some_path.clone(),
             tls_key: some_path.clone(),
             tls_client_auth: some_path.clone(),
@@ -789,6 +2475,9 @@ mod tests {
         .is_ok());
         assert!(ApiSettings {
             bind_address,
+            admin_token: None,
+            #[cfg(feature = "grpc")]
+            grpc_bind_address: None,
             tls_certificate: some_path.clone(),
             tls_key: some_path.clone(),
             tls_client_auth: None,
@@ -797,6 +2486,9 @@ mod tests {
         .is_ok());
         assert!(ApiSettings {
             bind_address,
+            admin_token: None,
+            #[cfg(feature = "grpc")]
+            grpc_bind_address: None,
             tls_certificate: None,
             tls_key: None,
             tls_client_auth: some_path.clone(),
@@ -806,6 +2498,9 @@ mod tests {
 
         assert!(ApiSettings {
             bind_address,
+            admin_token: None,
+            #[cfg(feature = "grpc")]
+            grpc_bind_address: None,
             tls_certificate: some_path.clone(),
             tls_key: None,
             tls_client_auth: some_path.clone(),
@@ -814,6 +2509,9 @@ mod tests {
         .is_err());
         assert!(ApiSettings {
             bind_address,
+            admin_token: None,
+            #[cfg(feature = "grpc")]
+            grpc_bind_address: None,
             tls_certificate: None,
             tls_key: some_path.clone(),
             tls_client_auth: some_path.clone(),
@@ -822,6 +2520,9 @@ mod tests {
         .is_err());
         assert!(ApiSettings {
             bind_address,
+            admin_token: None,
+            #[cfg(feature = "grpc")]
+            grpc_bind_address: None,
             tls_certificate: some_path.clone(),
             tls_key: None,
             tls_client_auth: None,
@@ -830,6 +2531,9 @@ mod tests {
         .is_err());
         assert!(ApiSettings {
             bind_address,
+            admin_token: None,
+            #[cfg(feature = "grpc")]
+            grpc_bind_address: None,
             tls_certificate: None,
             tls_key: some_path,
             tls_client_auth: None,
@@ -838,6 +2542,9 @@ mod tests {
         .is_err());
         assert!(ApiSettings {
             bind_address,
+            admin_token: None,
+            #[cfg(feature = "grpc")]
+            grpc_bind_address: None,
             tls_certificate: None,
             tls_key: None,
             tls_client_auth: None,
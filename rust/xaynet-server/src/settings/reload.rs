@@ -0,0 +1,357 @@
+//! Hot reloading of the PET settings between rounds.
+//!
+//! See the [settings module] documentation since this is a private module anyways.
+//!
+//! [settings module]: ../index.html
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+#[cfg(unix)]
+use tracing::{error, info};
+
+#[cfg(unix)]
+use crate::{event, metric, metrics::Measurement};
+use crate::{
+    settings::{MaskSettings, ModelSettings, PetSettings, Settings, SettingsError, DEFAULT_TASK},
+    state_machine::admin::AdminHandle,
+};
+
+#[derive(Debug, Error)]
+/// An error related to the reloading of settings.
+pub enum ReloadError {
+    #[error(transparent)]
+    Settings(#[from] SettingsError),
+    #[error("the task {0} can't be removed without a restart")]
+    TaskRemoved(String),
+    #[error("the mask settings of the task {0} can't be changed without a restart")]
+    MaskSettingsChanged(String),
+    #[error("the model settings of the task {0} can't be changed without a restart")]
+    ModelSettingsChanged(String),
+}
+
+/// The settings of a running task which are checked and updated by the [`SettingsReloader`].
+struct ReloadedTask {
+    name: String,
+    mask_settings: MaskSettings,
+    model_settings: ModelSettings,
+    admin_handle: AdminHandle,
+}
+
+/// A reloader of the PET settings from the configuration file.
+///
+/// Reloaded PET settings are applied by the [`StateMachine`] of each task in its next idle phase,
+/// i.e. before its next round starts. Changes of the mask or model settings are rejected, because
+/// the masks and models of the participants must be compatible with the coordinator across rounds.
+/// The PET settings are only applied if they are valid for all tasks. Changes of any other
+/// settings, including added tasks, are ignored and require a restart of the coordinator.
+///
+/// [`StateMachine`]: crate::state_machine::StateMachine
+pub struct SettingsReloader {
+    path: PathBuf,
+    tasks: Vec<ReloadedTask>,
+}
+
+impl SettingsReloader {
+    /// Creates a new reloader for the given configuration file.
+    ///
+    /// The mask and model settings are the ones the default task was started with.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        mask_settings: MaskSettings,
+        model_settings: ModelSettings,
+        admin_handle: AdminHandle,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            tasks: vec![ReloadedTask {
+                name: DEFAULT_TASK.to_string(),
+                mask_settings,
+                model_settings,
+                admin_handle,
+            }],
+        }
+    }
+
+    /// Reloads the PET settings of the additional task with the given name as well.
+    ///
+    /// The mask and model settings are the ones the task was started with.
+    pub fn with_task(
+        mut self,
+        name: impl Into<String>,
+        mask_settings: MaskSettings,
+        model_settings: ModelSettings,
+        admin_handle: AdminHandle,
+    ) -> Self {
+        self.tasks.push(ReloadedTask {
+            name: name.into(),
+            mask_settings,
+            model_settings,
+            admin_handle,
+        });
+        self
+    }
+
+    /// Loads and validates the settings from the configuration file and schedules the PET
+    /// settings of every task for its next round.
+    ///
+    /// Returns the scheduled PET settings by task name.
+    ///
+    /// # Errors
+    /// Fails if the settings can't be loaded, are invalid, remove a task or change the mask or
+    /// model settings of a task. No PET settings are scheduled in that case.
+    pub fn reload(&self) -> Result<Vec<(&str, PetSettings)>, ReloadError> {
+        let settings = Settings::new(&self.path)?;
+        let pet_settings = self
+            .tasks
+            .iter()
+            .map(|task| {
+                let (pet, mask, model) = if task.name == DEFAULT_TASK {
+                    (settings.pet, &settings.mask, &settings.model)
+                } else {
+                    settings
+                        .tasks
+                        .iter()
+                        .find(|settings| settings.name == task.name)
+                        .map(|settings| (settings.pet, &settings.mask, &settings.model))
+                        .ok_or_else(|| ReloadError::TaskRemoved(task.name.clone()))?
+                };
+                if *mask != task.mask_settings {
+                    return Err(ReloadError::MaskSettingsChanged(task.name.clone()));
+                }
+                if *model != task.model_settings {
+                    return Err(ReloadError::ModelSettingsChanged(task.name.clone()));
+                }
+                Ok(pet)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(self
+            .tasks
+            .iter()
+            .zip(pet_settings)
+            .map(|(task, pet_settings)| {
+                task.admin_handle.update_pet_settings(pet_settings);
+                (task.name.as_str(), pet_settings)
+            })
+            .collect())
+    }
+
+    #[cfg(unix)]
+    /// Reloads the settings whenever the coordinator receives a `SIGHUP`.
+    pub async fn run(self) {
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => hangups,
+            Err(err) => {
+                error!("failed to listen for SIGHUP: {}", err);
+                return;
+            }
+        };
+
+        while hangups.recv().await.is_some() {
+            info!("reloading the settings from {}", self.path.display());
+            match self.reload() {
+                Ok(pet_settings) => {
+                    for (task, pet_settings) in pet_settings {
+                        info!(
+                            "reloaded PET settings of the task {} for the next round: {:?}",
+                            task, pet_settings
+                        );
+                        metric!(Measurement::SettingsReloaded, 1, ("task", task));
+                    }
+                }
+                Err(err) => {
+                    error!("rejected the reloaded settings: {}", err);
+                    for task in self.tasks.iter() {
+                        metric!(
+                            Measurement::SettingsRejected,
+                            1,
+                            ("task", task.name.as_str())
+                        );
+                    }
+                    event!("Settings rejected", &err.to_string());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serial_test::serial;
+    use toml::Value;
+
+    use super::*;
+
+    const CONFIG: &str = "../../configs/config.toml";
+
+    fn reloader(path: impl Into<PathBuf>) -> SettingsReloader {
+        let settings = Settings::new(CONFIG).unwrap();
+        SettingsReloader::new(path, settings.mask, settings.model, AdminHandle::new())
+    }
+
+    /// Reads the example configuration.
+    fn read_config() -> Value {
+        fs::read_to_string(CONFIG).unwrap().parse().unwrap()
+    }
+
+    /// Writes the configuration into the directory.
+    fn write(dir: &tempfile::TempDir, config: &Value) -> PathBuf {
+        let path = dir.path().join("config.toml");
+        fs::write(&path, toml::to_string(config).unwrap()).unwrap();
+        path
+    }
+
+    /// Writes a copy of the example configuration with a single setting changed.
+    fn write_config(dir: &tempfile::TempDir, section: &str, key: &str, value: Value) -> PathBuf {
+        let mut config = read_config();
+        let setting = config[section].get_mut(key).unwrap();
+        assert_ne!(*setting, value);
+        *setting = value;
+        write(dir, &config)
+    }
+
+    /// Writes a copy of the example configuration with an additional task whose settings equal
+    /// the ones of the default task except for the sum ratio.
+    fn write_config_with_task(dir: &tempfile::TempDir, name: &str, sum: f64) -> PathBuf {
+        let mut config = read_config();
+        let mut task = toml::map::Map::new();
+        task.insert("name".to_string(), Value::String(name.to_string()));
+        for section in &["pet", "mask", "model"] {
+            task.insert(section.to_string(), config[section].clone());
+        }
+        task["pet"]
+            .as_table_mut()
+            .unwrap()
+            .insert("sum".to_string(), Value::Float(sum));
+        config
+            .as_table_mut()
+            .unwrap()
+            .insert("tasks".to_string(), Value::Array(vec![Value::Table(task)]));
+        write(dir, &config)
+    }
+
+    #[test]
+    #[serial]
+    fn test_reload_pet_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "pet", "sum", Value::Float(0.2));
+        let reloader = reloader(path);
+
+        let pet_settings = reloader.reload().unwrap();
+        assert_eq!(pet_settings.len(), 1);
+        assert_eq!(pet_settings[0].0, DEFAULT_TASK);
+        assert_eq!(pet_settings[0].1.sum, 0.2);
+        assert_eq!(
+            reloader.tasks[0]
+                .admin_handle
+                .pending_pet_settings()
+                .unwrap()
+                .sum,
+            0.2
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_reload_pet_settings_of_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config_with_task(&dir, "keyboard", 0.3);
+        let settings = Settings::new(CONFIG).unwrap();
+        let reloader = reloader(path).with_task(
+            "keyboard",
+            settings.mask,
+            settings.model.clone(),
+            AdminHandle::new(),
+        );
+
+        let pet_settings = reloader.reload().unwrap();
+        assert_eq!(
+            pet_settings
+                .iter()
+                .map(|(task, pet_settings)| (*task, pet_settings.sum))
+                .collect::<Vec<_>>(),
+            vec![(DEFAULT_TASK, settings.pet.sum), ("keyboard", 0.3)]
+        );
+        assert_eq!(
+            reloader.tasks[1]
+                .admin_handle
+                .pending_pet_settings()
+                .unwrap()
+                .sum,
+            0.3
+        );
+
+        // the settings of a running task must not be removed
+        let path = write(&dir, &read_config());
+        let reloader = self::reloader(path).with_task(
+            "keyboard",
+            settings.mask,
+            settings.model,
+            AdminHandle::new(),
+        );
+        assert!(matches!(
+            reloader.reload().unwrap_err(),
+            ReloadError::TaskRemoved(task) if task == "keyboard"
+        ));
+        assert!(reloader.tasks[0]
+            .admin_handle
+            .pending_pet_settings()
+            .is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_reload_invalid_pet_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "pet", "min_sum_count", Value::Integer(1000));
+        let reloader = reloader(path);
+
+        assert!(matches!(
+            reloader.reload().unwrap_err(),
+            ReloadError::Settings(SettingsError::Validation(_))
+        ));
+        assert!(reloader.tasks[0]
+            .admin_handle
+            .pending_pet_settings()
+            .is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_reload_changed_mask_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "mask", "model_type", Value::String("M6".into()));
+        let reloader = reloader(path);
+
+        assert!(matches!(
+            reloader.reload().unwrap_err(),
+            ReloadError::MaskSettingsChanged(task) if task == DEFAULT_TASK
+        ));
+        assert!(reloader.tasks[0]
+            .admin_handle
+            .pending_pet_settings()
+            .is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_reload_changed_model_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(&dir, "model", "length", Value::Integer(5));
+        let reloader = reloader(path);
+
+        assert!(matches!(
+            reloader.reload().unwrap_err(),
+            ReloadError::ModelSettingsChanged(task) if task == DEFAULT_TASK
+        ));
+        assert!(reloader.tasks[0]
+            .admin_handle
+            .pending_pet_settings()
+            .is_none());
+    }
+}
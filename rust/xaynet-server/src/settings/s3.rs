@@ -183,28 +183,6 @@ where
     deserializer.deserialize_any(S3RegionVisitor)
 }
 
-#[derive(Debug, Deserialize, Validate)]
-/// Restore settings.
-pub struct RestoreSettings {
-    /// If set to `false`, the restoring of coordinator state is prevented.
-    /// Instead, the state is reset and the coordinator is started with the
-    /// settings of the configuration file.
-    ///
-    /// # Examples
-    ///
-    /// **TOML**
-    /// ```text
-    /// [restore]
-    /// enable = true
-    /// ```
-    ///
-    /// **Environment variable**
-    /// ```text
-    /// XAYNET_RESTORE__ENABLE=false
-    /// ```
-    pub enable: bool,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -396,7 +374,7 @@ mod tests {
 
         let settings = Settings::load_from_str(&config).unwrap();
         assert_eq!(
-            settings.s3.buckets.global_models,
+            settings.s3.unwrap().buckets.global_models,
             S3BucketsSettings::default().global_models
         )
     }
@@ -418,7 +396,10 @@ mod tests {
             .build();
 
         let settings = Settings::load_from_str(&config).unwrap();
-        assert_eq!(settings.s3.buckets.global_models, "global-models-toml")
+        assert_eq!(
+            settings.s3.unwrap().buckets.global_models,
+            "global-models-toml"
+        )
     }
 
     #[test]
@@ -439,7 +420,10 @@ mod tests {
 
         std::env::set_var("XAYNET_S3__BUCKETS__GLOBAL_MODELS", "global-models-env");
         let settings = Settings::load_from_str(&config).unwrap();
-        assert_eq!(settings.s3.buckets.global_models, "global-models-env");
+        assert_eq!(
+            settings.s3.unwrap().buckets.global_models,
+            "global-models-env"
+        );
         std::env::remove_var("XAYNET_S3__BUCKETS__GLOBAL_MODELS");
     }
 
@@ -460,7 +444,10 @@ mod tests {
 
         std::env::set_var("XAYNET_S3__BUCKETS__GLOBAL_MODELS", "global-models-env");
         let settings = Settings::load_from_str(&config).unwrap();
-        assert_eq!(settings.s3.buckets.global_models, "global-models-env");
+        assert_eq!(
+            settings.s3.unwrap().buckets.global_models,
+            "global-models-env"
+        );
         std::env::remove_var("XAYNET_S3__BUCKETS__GLOBAL_MODELS");
     }
 
@@ -487,7 +474,7 @@ mod tests {
             .build();
 
         let settings = Settings::load_from_str(&config).unwrap();
-        assert!(matches!(settings.s3.region, Region::EuWest1));
+        assert!(matches!(settings.s3.unwrap().region, Region::EuWest1));
     }
 
     #[test]
@@ -507,7 +494,7 @@ mod tests {
 
         let settings = Settings::load_from_str(&config).unwrap();
         assert!(matches!(
-            settings.s3.region,
+            settings.s3.unwrap().region,
             Region::Custom {
                 name,
                 endpoint
@@ -532,7 +519,7 @@ mod tests {
 
         std::env::set_var("XAYNET_S3__REGION", "eu-west-1");
         let settings = Settings::load_from_str(&config).unwrap();
-        assert!(matches!(settings.s3.region, Region::EuWest1));
+        assert!(matches!(settings.s3.unwrap().region, Region::EuWest1));
         std::env::remove_var("XAYNET_S3__REGION");
     }
 
@@ -578,7 +565,7 @@ mod tests {
         std::env::set_var("XAYNET_S3__REGION", "minio-env http://localhost:8000");
         let settings = Settings::load_from_str(&config).unwrap();
         assert!(matches!(
-            settings.s3.region,
+            settings.s3.unwrap().region,
             Region::Custom {
                 name,
                 endpoint
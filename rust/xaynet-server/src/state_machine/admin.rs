@@ -0,0 +1,268 @@
+//! This module provides the [`AdminHandle`] to inspect and control the [`StateMachine`].
+//!
+//! [`StateMachine`]: crate::state_machine::StateMachine
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        Mutex,
+    },
+};
+
+use serde::Serialize;
+use tokio::sync::Notify;
+use tracing::info;
+
+use crate::{settings::PetSettings, state_machine::phases::PhaseName};
+
+/// The counters of the requests processed in a phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MessageCounters {
+    /// The number of requests successfully processed.
+    pub accepted: u64,
+    /// The number of requests failed to be processed.
+    pub rejected: u64,
+    /// The number of requests discarded without being processed.
+    pub discarded: u64,
+}
+
+/// The outcome of a processed request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(in crate::state_machine) enum RequestOutcome {
+    Accepted,
+    Rejected,
+    Discarded,
+}
+
+/// An administrative interruption of the current phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(in crate::state_machine) enum Interrupt {
+    /// Aborts the current round into the error phase.
+    Abort,
+    /// Shuts the [`StateMachine`] down.
+    ///
+    /// [`StateMachine`]: crate::state_machine::StateMachine
+    Shutdown,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    paused: AtomicBool,
+    resumed: Notify,
+    interrupt: Mutex<Option<Interrupt>>,
+    interrupted: Notify,
+    pet_settings: Mutex<Option<PetSettings>>,
+    counters: Mutex<HashMap<PhaseName, MessageCounters>>,
+}
+
+/// A handle to inspect and control the [`StateMachine`].
+///
+/// The handle is shared between the [`StateMachine`] and its administrators, e.g. the admin REST
+/// API. Cloning the handle is cheap.
+///
+/// [`StateMachine`]: crate::state_machine::StateMachine
+#[derive(Debug, Clone, Default)]
+pub struct AdminHandle(Arc<Inner>);
+
+impl AdminHandle {
+    /// Creates a new admin handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pauses the [`StateMachine`] before the next round starts.
+    ///
+    /// The current round is run to completion.
+    ///
+    /// [`StateMachine`]: crate::state_machine::StateMachine
+    pub fn pause(&self) {
+        info!("pausing after the current round");
+        self.0.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Resumes a paused [`StateMachine`].
+    ///
+    /// [`StateMachine`]: crate::state_machine::StateMachine
+    pub fn resume(&self) {
+        info!("resuming");
+        self.0.paused.store(false, Ordering::SeqCst);
+        self.0.resumed.notify();
+    }
+
+    /// Checks whether the [`StateMachine`] is paused or will pause after the current round.
+    ///
+    /// [`StateMachine`]: crate::state_machine::StateMachine
+    pub fn is_paused(&self) -> bool {
+        self.0.paused.load(Ordering::SeqCst)
+    }
+
+    /// Aborts the current round, which moves the [`StateMachine`] into the error phase.
+    ///
+    /// If no round is running, then the next round is aborted. A running phase is only aborted
+    /// while it waits for requests, otherwise the next phase is aborted before it starts.
+    ///
+    /// [`StateMachine`]: crate::state_machine::StateMachine
+    pub fn abort(&self) {
+        info!("aborting the current round");
+        self.interrupt(Interrupt::Abort);
+    }
+
+    /// Shuts the [`StateMachine`] down without completing the current round.
+    ///
+    /// Like an abort, the shutdown only interrupts a phase while it waits for requests or before
+    /// it starts.
+    ///
+    /// [`StateMachine`]: crate::state_machine::StateMachine
+    pub fn shutdown(&self) {
+        info!("shutting down");
+        self.interrupt(Interrupt::Shutdown);
+    }
+
+    /// Schedules the PET settings to be applied in the next idle phase.
+    ///
+    /// The settings are expected to be validated already. Previously scheduled settings which
+    /// haven't been applied yet are replaced.
+    pub fn update_pet_settings(&self, pet_settings: PetSettings) {
+        info!("scheduling new PET settings for the next round");
+        *self.0.pet_settings.lock().unwrap() = Some(pet_settings);
+    }
+
+    /// Gets the scheduled PET settings which haven't been applied yet.
+    pub fn pending_pet_settings(&self) -> Option<PetSettings> {
+        *self.0.pet_settings.lock().unwrap()
+    }
+
+    /// Gets the request counters of the phases of the current round.
+    pub fn counters(&self) -> HashMap<PhaseName, MessageCounters> {
+        self.0.counters.lock().unwrap().clone()
+    }
+
+    fn interrupt(&self, interrupt: Interrupt) {
+        let mut pending = self.0.interrupt.lock().unwrap();
+        // a shutdown must not be downgraded to an abort
+        if *pending != Some(Interrupt::Shutdown) {
+            *pending = Some(interrupt);
+        }
+        self.0.interrupted.notify();
+    }
+
+    /// Takes the requested interruption, if any.
+    pub(in crate::state_machine) fn take_interrupt(&self) -> Option<Interrupt> {
+        self.0.interrupt.lock().unwrap().take()
+    }
+
+    /// Waits until an interruption is requested.
+    pub(in crate::state_machine) async fn interrupted(&self) -> Interrupt {
+        loop {
+            if let Some(interrupt) = self.take_interrupt() {
+                return interrupt;
+            }
+            self.0.interrupted.notified().await;
+        }
+    }
+
+    /// Waits until the [`StateMachine`] is resumed, if it is paused.
+    ///
+    /// [`StateMachine`]: crate::state_machine::StateMachine
+    pub(in crate::state_machine) async fn resumed(&self) {
+        while self.is_paused() {
+            self.0.resumed.notified().await;
+        }
+    }
+
+    /// Takes the scheduled PET settings.
+    pub(in crate::state_machine) fn take_pet_settings(&self) -> Option<PetSettings> {
+        self.0.pet_settings.lock().unwrap().take()
+    }
+
+    /// Counts a processed request of the given phase.
+    pub(in crate::state_machine) fn count(&self, phase: PhaseName, outcome: RequestOutcome) {
+        let mut counters = self.0.counters.lock().unwrap();
+        let counters = counters.entry(phase).or_default();
+        match outcome {
+            RequestOutcome::Accepted => counters.accepted += 1,
+            RequestOutcome::Rejected => counters.rejected += 1,
+            RequestOutcome::Discarded => counters.discarded += 1,
+        }
+    }
+
+    /// Resets the request counters for a new round.
+    pub(in crate::state_machine) fn reset_counters(&self) {
+        self.0.counters.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::{timeout, Duration};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_interrupt() {
+        let admin = AdminHandle::new();
+        let waiting = admin.clone();
+        let interrupted = tokio::spawn(async move { waiting.interrupted().await });
+        admin.abort();
+        assert_eq!(interrupted.await.unwrap(), Interrupt::Abort);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_is_not_downgraded() {
+        let admin = AdminHandle::new();
+        admin.shutdown();
+        admin.abort();
+        assert_eq!(admin.interrupted().await, Interrupt::Shutdown);
+        assert!(timeout(Duration::from_millis(10), admin.interrupted())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let admin = AdminHandle::new();
+        admin.resumed().await;
+
+        admin.pause();
+        assert!(admin.is_paused());
+        assert!(timeout(Duration::from_millis(10), admin.resumed())
+            .await
+            .is_err());
+
+        admin.resume();
+        assert!(!admin.is_paused());
+        admin.resumed().await;
+    }
+
+    #[test]
+    fn test_counters() {
+        let admin = AdminHandle::new();
+        admin.count(PhaseName::Sum, RequestOutcome::Accepted);
+        admin.count(PhaseName::Sum, RequestOutcome::Accepted);
+        admin.count(PhaseName::Sum, RequestOutcome::Rejected);
+        admin.count(PhaseName::Update, RequestOutcome::Discarded);
+
+        let counters = admin.counters();
+        assert_eq!(
+            counters[&PhaseName::Sum],
+            MessageCounters {
+                accepted: 2,
+                rejected: 1,
+                discarded: 0,
+            }
+        );
+        assert_eq!(
+            counters[&PhaseName::Update],
+            MessageCounters {
+                accepted: 0,
+                rejected: 0,
+                discarded: 1,
+            }
+        );
+
+        admin.reset_counters();
+        assert!(admin.counters().is_empty());
+    }
+}
@@ -0,0 +1,447 @@
+//! Aggregation strategies which compute the global model after unmasking.
+//!
+//! The [`Unmask`] phase computes the unmasked aggregated model, i.e. the scalar-weighted average
+//! of the local models. An [`AggregationStrategy`] derives the new global model from it, from the
+//! previous global model and from the [`AggregationState`] of the previous rounds, for example by
+//! applying a server learning rate or a server optimizer.
+//!
+//! The previous global model is the latest global model of the [`ModelStorage`], hence it is only
+//! available after a restart of the coordinator if the model storage keeps the global models. The
+//! [`AggregationState`] is stored separately from the [`CoordinatorState`] and only after the
+//! global model it belongs to has been saved.
+//!
+//! [`Unmask`]: crate::state_machine::phases::Unmask
+//! [`ModelStorage`]: crate::storage::ModelStorage
+//! [`CoordinatorState`]: crate::state_machine::coordinator::CoordinatorState
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::settings::{AggregationSettings, AggregationStrategyType};
+use xaynet_core::mask::{FromPrimitives, IntoPrimitives, Model, ModelCastError};
+
+/// Error that occurs while applying an aggregation strategy.
+#[derive(Debug, Error)]
+pub enum AggregationError {
+    #[error("converting the model failed: {0}")]
+    ModelCast(#[from] ModelCastError),
+    #[error("the aggregated model contains non-finite weights")]
+    NonFiniteWeights,
+}
+
+/// The state of an aggregation strategy which is carried over from round to round.
+///
+/// The state doesn't grow with the number of rounds: it holds at most the moments of the current
+/// strategy, each of the length of the global model. Everything which isn't needed by the current
+/// strategy is dropped.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AggregationState {
+    /// The id of the global model which the state belongs to.
+    pub global_model_id: Option<String>,
+    /// The first moment of the server optimizer.
+    pub first_moment: Option<Vec<f64>>,
+    /// The second moment of the server optimizer.
+    pub second_moment: Option<Vec<f64>>,
+}
+
+/// A strategy that computes the global model from the unmasked aggregated model.
+pub trait AggregationStrategy {
+    /// Computes the new global model from the unmasked aggregated model and the previous
+    /// `global_model` and returns it along with the new state.
+    ///
+    /// The given `state` is left untouched, so that it can be replaced by the new state once the
+    /// new global model has been saved.
+    ///
+    /// # Errors
+    /// Fails if the models can't be converted or if the new global model isn't finite.
+    fn aggregate(
+        &self,
+        state: &AggregationState,
+        global_model: Option<&Model>,
+        model: Model,
+    ) -> Result<(Model, AggregationState), AggregationError>;
+}
+
+/// Creates the aggregation strategy which is configured in the settings.
+pub fn from_settings(settings: &AggregationSettings) -> Box<dyn AggregationStrategy + Send> {
+    match settings.strategy {
+        AggregationStrategyType::FedAvg => Box::new(FedAvg {
+            learning_rate: settings.learning_rate,
+            clipping_norm: settings.clipping_norm,
+        }),
+        AggregationStrategyType::FedAdam => Box::new(FedOpt {
+            optimizer: Optimizer::Adam,
+            learning_rate: settings.learning_rate,
+            beta_1: settings.beta_1,
+            beta_2: settings.beta_2,
+            tau: settings.tau,
+            clipping_norm: settings.clipping_norm,
+        }),
+        AggregationStrategyType::FedYogi => Box::new(FedOpt {
+            optimizer: Optimizer::Yogi,
+            learning_rate: settings.learning_rate,
+            beta_1: settings.beta_1,
+            beta_2: settings.beta_2,
+            tau: settings.tau,
+            clipping_norm: settings.clipping_norm,
+        }),
+    }
+}
+
+/// Federated averaging with a server learning rate.
+///
+/// The new global model is `global_model + learning_rate * delta`, where `delta` is the
+/// (optionally clipped) difference between the unmasked aggregated model and the previous global
+/// model.
+#[derive(Debug, Clone, Copy)]
+pub struct FedAvg {
+    pub learning_rate: f64,
+    pub clipping_norm: Option<f64>,
+}
+
+impl AggregationStrategy for FedAvg {
+    #[allow(clippy::float_cmp)]
+    fn aggregate(
+        &self,
+        _state: &AggregationState,
+        global_model: Option<&Model>,
+        model: Model,
+    ) -> Result<(Model, AggregationState), AggregationError> {
+        // the plain unmasked aggregated model doesn't depend on the previous global model, hence
+        // it is passed through unchanged to avoid any loss of precision
+        if self.learning_rate == 1. && self.clipping_norm.is_none() {
+            return Ok((model, AggregationState::default()));
+        }
+
+        let (global_model, delta) = match prepare(global_model, &model, self.clipping_norm)? {
+            Some(prepared) => prepared,
+            None => return Ok((model, AggregationState::default())),
+        };
+        let new_global_model = global_model
+            .iter()
+            .zip(delta.iter())
+            .map(|(weight, delta)| weight + self.learning_rate * delta)
+            .collect::<Vec<_>>();
+
+        finish(new_global_model, None)
+    }
+}
+
+/// The server optimizers of the adaptive federated optimization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Optimizer {
+    Adam,
+    Yogi,
+}
+
+/// Adaptive federated optimization.
+///
+/// The difference between the unmasked aggregated model and the previous global model is used as
+/// a pseudo-gradient for a server optimizer, see [Reddi et al.](https://arxiv.org/abs/2003.00295).
+#[derive(Debug, Clone, Copy)]
+pub struct FedOpt {
+    pub optimizer: Optimizer,
+    pub learning_rate: f64,
+    pub beta_1: f64,
+    pub beta_2: f64,
+    pub tau: f64,
+    pub clipping_norm: Option<f64>,
+}
+
+impl AggregationStrategy for FedOpt {
+    fn aggregate(
+        &self,
+        state: &AggregationState,
+        global_model: Option<&Model>,
+        model: Model,
+    ) -> Result<(Model, AggregationState), AggregationError> {
+        let (global_model, delta) = match prepare(global_model, &model, self.clipping_norm)? {
+            Some(prepared) => prepared,
+            None => return Ok((model, AggregationState::default())),
+        };
+
+        let len = global_model.len();
+        let mut first_moment = state
+            .first_moment
+            .clone()
+            .filter(|moment| moment.len() == len)
+            .unwrap_or_else(|| vec![0.; len]);
+        let mut second_moment = state
+            .second_moment
+            .clone()
+            .filter(|moment| moment.len() == len)
+            .unwrap_or_else(|| vec![self.tau * self.tau; len]);
+
+        for ((m, v), d) in first_moment
+            .iter_mut()
+            .zip(second_moment.iter_mut())
+            .zip(delta.iter())
+        {
+            *m = self.beta_1 * *m + (1. - self.beta_1) * d;
+            let d_squared = d * d;
+            *v = match self.optimizer {
+                Optimizer::Adam => self.beta_2 * *v + (1. - self.beta_2) * d_squared,
+                Optimizer::Yogi => *v - (1. - self.beta_2) * d_squared * (*v - d_squared).signum(),
+            };
+        }
+
+        let new_global_model = global_model
+            .iter()
+            .zip(first_moment.iter().zip(second_moment.iter()))
+            .map(|(weight, (m, v))| weight + self.learning_rate * m / (v.sqrt() + self.tau))
+            .collect::<Vec<_>>();
+
+        finish(new_global_model, Some((first_moment, second_moment)))
+    }
+}
+
+/// Returns the previous global model and the (clipped) difference to the aggregated model.
+///
+/// If there is no previous global model of the same length, the aggregated model becomes the new
+/// global model without any state and `None` is returned.
+#[allow(clippy::type_complexity)]
+fn prepare(
+    global_model: Option<&Model>,
+    model: &Model,
+    clipping_norm: Option<f64>,
+) -> Result<Option<(Vec<f64>, Vec<f64>)>, AggregationError> {
+    let aggregated_model = model.to_primitives().collect::<Result<Vec<f64>, _>>()?;
+    if aggregated_model.iter().any(|weight| !weight.is_finite()) {
+        return Err(AggregationError::NonFiniteWeights);
+    }
+
+    let global_model = match global_model {
+        Some(global_model) if global_model.len() == aggregated_model.len() => global_model
+            .to_primitives()
+            .collect::<Result<Vec<f64>, _>>()?,
+        _ => return Ok(None),
+    };
+
+    let mut delta = aggregated_model
+        .iter()
+        .zip(global_model.iter())
+        .map(|(aggregated, global)| aggregated - global)
+        .collect::<Vec<_>>();
+    if let Some(clipping_norm) = clipping_norm {
+        clip(&mut delta, clipping_norm);
+    }
+    Ok(Some((global_model, delta)))
+}
+
+/// Scales the delta down to the clipping norm if its L2 norm exceeds the clipping norm.
+fn clip(delta: &mut [f64], clipping_norm: f64) {
+    let norm = delta.iter().map(|d| d * d).sum::<f64>().sqrt();
+    if norm > clipping_norm {
+        let scale = clipping_norm / norm;
+        delta.iter_mut().for_each(|d| *d *= scale);
+    }
+}
+
+/// Converts the new global model and returns it along with the new state of the moments.
+///
+/// The moments of a previous strategy are dropped if the current strategy has none.
+fn finish(
+    new_global_model: Vec<f64>,
+    moments: Option<(Vec<f64>, Vec<f64>)>,
+) -> Result<(Model, AggregationState), AggregationError> {
+    let model = Model::from_primitives(new_global_model.into_iter())
+        .map_err(|_| AggregationError::NonFiniteWeights)?;
+
+    let (first_moment, second_moment) = match moments {
+        Some((first_moment, second_moment)) => (Some(first_moment), Some(second_moment)),
+        None => (None, None),
+    };
+    let state = AggregationState {
+        global_model_id: None,
+        first_moment,
+        second_moment,
+    };
+    Ok((model, state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(weights: &[f64]) -> Model {
+        Model::from_primitives(weights.iter().copied()).unwrap()
+    }
+
+    fn weights(model: Model) -> Vec<f64> {
+        model.into_primitives_unchecked().collect()
+    }
+
+    fn assert_approx_eq(left: &[f64], right: &[f64]) {
+        assert_eq!(left.len(), right.len());
+        for (l, r) in left.iter().zip(right.iter()) {
+            assert!((l - r).abs() < 1e-9, "{:?} != {:?}", left, right);
+        }
+    }
+
+    #[test]
+    fn test_fedavg_default_is_identity() {
+        let strategy = from_settings(&AggregationSettings::default());
+        let state = AggregationState::default();
+
+        let aggregated = model(&[0.5, -1.5, 2.]);
+        let (global_model, new_state) = strategy
+            .aggregate(&state, Some(&model(&[1., 1., 1.])), aggregated.clone())
+            .unwrap();
+        assert_eq!(global_model, aggregated);
+        assert_eq!(new_state, AggregationState::default());
+    }
+
+    #[test]
+    fn test_fedavg_learning_rate() {
+        let strategy = FedAvg {
+            learning_rate: 0.5,
+            clipping_norm: None,
+        };
+        let state = AggregationState::default();
+
+        // without a previous global model the aggregated model becomes the global model
+        let (global_model, state) = strategy.aggregate(&state, None, model(&[1., 2.])).unwrap();
+        assert_approx_eq(&weights(global_model.clone()), &[1., 2.]);
+
+        let (global_model, _) = strategy
+            .aggregate(&state, Some(&global_model), model(&[3., 0.]))
+            .unwrap();
+        assert_approx_eq(&weights(global_model), &[2., 1.]);
+    }
+
+    #[test]
+    fn test_fedavg_clipping() {
+        let strategy = FedAvg {
+            learning_rate: 1.,
+            clipping_norm: Some(1.),
+        };
+        let state = AggregationState::default();
+
+        // the delta has a norm of 5 and is scaled down to a norm of 1
+        let (global_model, state) = strategy
+            .aggregate(&state, Some(&model(&[0., 0.])), model(&[3., 4.]))
+            .unwrap();
+        assert_approx_eq(&weights(global_model.clone()), &[0.6, 0.8]);
+
+        // deltas within the clipping norm are not changed
+        let (global_model, _) = strategy
+            .aggregate(&state, Some(&global_model), model(&[0.6, 1.]))
+            .unwrap();
+        assert_approx_eq(&weights(global_model), &[0.6, 1.]);
+    }
+
+    #[test]
+    fn test_fedadam() {
+        let strategy = FedOpt {
+            optimizer: Optimizer::Adam,
+            learning_rate: 0.1,
+            beta_1: 0.9,
+            beta_2: 0.99,
+            tau: 0.001,
+            clipping_norm: None,
+        };
+        let state = AggregationState::default();
+
+        let (global_model, new_state) = strategy
+            .aggregate(&state, Some(&model(&[1., 1.])), model(&[2., 0.]))
+            .unwrap();
+
+        let m = 0.1;
+        let v = 0.99 * 0.001 * 0.001 + 0.01;
+        let step = 0.1 * m / (f64::sqrt(v) + 0.001);
+        assert_approx_eq(&weights(global_model), &[1. + step, 1. - step]);
+        assert_approx_eq(new_state.first_moment.as_ref().unwrap(), &[m, -m]);
+        assert_approx_eq(new_state.second_moment.as_ref().unwrap(), &[v, v]);
+        // the given state is left untouched
+        assert_eq!(state, AggregationState::default());
+    }
+
+    #[test]
+    fn test_fedyogi() {
+        let strategy = FedOpt {
+            optimizer: Optimizer::Yogi,
+            learning_rate: 0.1,
+            beta_1: 0.9,
+            beta_2: 0.99,
+            tau: 0.001,
+            clipping_norm: None,
+        };
+        let state = AggregationState::default();
+
+        let (global_model, new_state) = strategy
+            .aggregate(&state, Some(&model(&[1.])), model(&[2.]))
+            .unwrap();
+
+        let m = 0.1;
+        let v = 0.001 * 0.001 + 0.01;
+        let step = 0.1 * m / (f64::sqrt(v) + 0.001);
+        assert_approx_eq(&weights(global_model), &[1. + step]);
+        assert_approx_eq(new_state.second_moment.as_ref().unwrap(), &[v]);
+    }
+
+    #[test]
+    fn test_model_length_mismatch_resets_state() {
+        let strategy = FedOpt {
+            optimizer: Optimizer::Adam,
+            learning_rate: 0.1,
+            beta_1: 0.9,
+            beta_2: 0.99,
+            tau: 0.001,
+            clipping_norm: None,
+        };
+        let state = AggregationState {
+            global_model_id: Some("id".to_string()),
+            first_moment: Some(vec![1., 1.]),
+            second_moment: Some(vec![1., 1.]),
+        };
+
+        let aggregated = model(&[2., 0., 1.]);
+        let (global_model, new_state) = strategy
+            .aggregate(&state, Some(&model(&[1., 1.])), aggregated.clone())
+            .unwrap();
+        assert_eq!(global_model, aggregated);
+        assert_eq!(new_state, AggregationState::default());
+    }
+
+    #[test]
+    fn test_state_is_bounded() {
+        let fedadam = FedOpt {
+            optimizer: Optimizer::Adam,
+            learning_rate: 0.1,
+            beta_1: 0.9,
+            beta_2: 0.99,
+            tau: 0.001,
+            clipping_norm: None,
+        };
+        let mut state = AggregationState::default();
+        let mut global_model = None;
+        for round in 0..100 {
+            let weight = round as f64;
+            let (new_global_model, new_state) = fedadam
+                .aggregate(&state, global_model.as_ref(), model(&[weight, -weight]))
+                .unwrap();
+            global_model = Some(new_global_model);
+            state = new_state;
+        }
+        assert_eq!(state.first_moment.as_ref().unwrap().len(), 2);
+        assert_eq!(state.second_moment.as_ref().unwrap().len(), 2);
+
+        // the moments are dropped once they aren't needed anymore
+        let fedavg = FedAvg {
+            learning_rate: 0.5,
+            clipping_norm: None,
+        };
+        let (_, state) = fedavg
+            .aggregate(&state, global_model.as_ref(), model(&[1., 1.]))
+            .unwrap();
+        assert!(state.first_moment.is_none());
+        assert!(state.second_moment.is_none());
+    }
+
+    #[test]
+    fn test_deserialize_missing_state() {
+        let state: AggregationState = serde_json::from_str("{}").unwrap();
+        assert_eq!(state, AggregationState::default());
+    }
+}
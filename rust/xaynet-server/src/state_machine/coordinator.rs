@@ -2,7 +2,16 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::settings::{MaskSettings, ModelSettings, PetSettings};
+use crate::{
+    settings::{
+        AggregationSettings,
+        DifferentialPrivacySettings,
+        MaskSettings,
+        ModelSettings,
+        PetSettings,
+    },
+    state_machine::{aggregation::AggregationState, privacy::PrivacyAccountant},
+};
 use xaynet_core::{
     common::{RoundParameters, RoundSeed},
     crypto::{ByteObject, EncryptKeyPair},
@@ -10,7 +19,7 @@ use xaynet_core::{
 };
 
 /// The coordinator state.
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CoordinatorState {
     /// The credentials of the coordinator.
     pub keys: EncryptKeyPair,
@@ -34,6 +43,17 @@ pub struct CoordinatorState {
     pub max_sum_time: u64,
     /// The maximum time (in seconds) permitted for processing update messages.
     pub max_update_time: u64,
+    /// The settings of the aggregation strategy.
+    #[serde(default)]
+    pub aggregation_settings: AggregationSettings,
+    /// The state of the aggregation strategy, which is stored separately from the coordinator
+    /// state since it may be as large as several global models.
+    #[serde(skip)]
+    pub aggregation: AggregationState,
+    /// The settings of the central differential privacy.
+    pub privacy_settings: DifferentialPrivacySettings,
+    /// The privacy budget which has been spent so far.
+    pub privacy_accountant: PrivacyAccountant,
 }
 
 impl CoordinatorState {
@@ -41,6 +61,8 @@ impl CoordinatorState {
         pet_settings: PetSettings,
         mask_settings: MaskSettings,
         model_settings: ModelSettings,
+        aggregation_settings: AggregationSettings,
+        privacy_settings: DifferentialPrivacySettings,
     ) -> Self {
         let keys = EncryptKeyPair::generate();
         let mask_config: MaskConfig = mask_settings.into();
@@ -51,6 +73,8 @@ impl CoordinatorState {
             seed: RoundSeed::zeroed(),
             mask_config: mask_config.clone().into(),
             model_length: model_settings.length,
+            seed_sharing_threshold: pet_settings.seed_sharing_threshold,
+            model_delta: model_settings.delta,
         };
         let round_id = 0;
         Self {
@@ -65,6 +89,27 @@ impl CoordinatorState {
             min_update_time: pet_settings.min_update_time,
             max_sum_time: pet_settings.max_sum_time,
             max_update_time: pet_settings.max_update_time,
+            aggregation_settings,
+            aggregation: AggregationState::default(),
+            privacy_settings,
+            privacy_accountant: PrivacyAccountant::default(),
         }
     }
+
+    /// Applies new PET settings to the coordinator state.
+    ///
+    /// This must only happen between rounds, i.e. in the idle phase.
+    pub fn apply_pet_settings(&mut self, pet_settings: PetSettings) {
+        self.round_params.sum = pet_settings.sum;
+        self.round_params.update = pet_settings.update;
+        self.round_params.seed_sharing_threshold = pet_settings.seed_sharing_threshold;
+        self.min_sum_count = pet_settings.min_sum_count;
+        self.min_update_count = pet_settings.min_update_count;
+        self.max_sum_count = pet_settings.max_sum_count;
+        self.max_update_count = pet_settings.max_update_count;
+        self.min_sum_time = pet_settings.min_sum_time;
+        self.min_update_time = pet_settings.min_update_time;
+        self.max_sum_time = pet_settings.max_sum_time;
+        self.max_update_time = pet_settings.max_update_time;
+    }
 }
@@ -36,7 +36,11 @@ pub struct Event<E> {
 #[derive(Debug, Clone, PartialEq)]
 pub enum ModelUpdate {
     Invalidate,
-    New(Arc<Model>),
+    /// A new global model with its global model id.
+    New {
+        id: String,
+        model: Arc<Model>,
+    },
 }
 
 /// Dictionary update event.
@@ -55,13 +59,16 @@ pub struct EventPublisher {
     params_tx: EventBroadcaster<RoundParameters>,
     phase_tx: EventBroadcaster<PhaseName>,
     model_tx: EventBroadcaster<ModelUpdate>,
+    /// Keeps track of the latest global model.
+    model_rx: EventListener<ModelUpdate>,
     sum_dict_tx: EventBroadcaster<DictionaryUpdate<SumDict>>,
     seed_dict_tx: EventBroadcaster<DictionaryUpdate<SeedDict>>,
+    dropout_dict_tx: EventBroadcaster<DictionaryUpdate<SumDict>>,
 }
 
 /// The `EventSubscriber` hands out `EventListener`s for any
 /// coordinator event.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EventSubscriber {
     keys_rx: EventListener<EncryptKeyPair>,
     params_rx: EventListener<RoundParameters>,
@@ -69,6 +76,7 @@ pub struct EventSubscriber {
     model_rx: EventListener<ModelUpdate>,
     sum_dict_rx: EventListener<DictionaryUpdate<SumDict>>,
     seed_dict_rx: EventListener<DictionaryUpdate<SeedDict>>,
+    dropout_dict_rx: EventListener<DictionaryUpdate<SumDict>>,
 }
 
 impl EventPublisher {
@@ -107,6 +115,12 @@ impl EventPublisher {
                 event: DictionaryUpdate::Invalidate,
             });
 
+        let (dropout_dict_tx, dropout_dict_rx) =
+            watch::channel::<Event<DictionaryUpdate<SumDict>>>(Event {
+                round_id,
+                event: DictionaryUpdate::Invalidate,
+            });
+
         let (params_tx, params_rx) = watch::channel::<Event<RoundParameters>>(Event {
             round_id,
             event: params,
@@ -118,8 +132,10 @@ impl EventPublisher {
             params_tx: params_tx.into(),
             phase_tx: phase_tx.into(),
             model_tx: model_tx.into(),
+            model_rx: model_rx.clone().into(),
             sum_dict_tx: sum_dict_tx.into(),
             seed_dict_tx: seed_dict_tx.into(),
+            dropout_dict_tx: dropout_dict_tx.into(),
         };
 
         let subscriber = EventSubscriber {
@@ -129,6 +145,7 @@ impl EventPublisher {
             model_rx: model_rx.into(),
             sum_dict_rx: sum_dict_rx.into(),
             seed_dict_rx: seed_dict_rx.into(),
+            dropout_dict_rx: dropout_dict_rx.into(),
         };
 
         (publisher, subscriber)
@@ -166,6 +183,14 @@ impl EventPublisher {
         let _ = self.model_tx.broadcast(self.event(update));
     }
 
+    /// Get the latest global model, if any.
+    pub fn latest_model(&self) -> Option<Arc<Model>> {
+        match self.model_rx.get_latest().event {
+            ModelUpdate::Invalidate => None,
+            ModelUpdate::New { model, .. } => Some(model),
+        }
+    }
+
     /// Emit a sum dictionary update
     pub fn broadcast_sum_dict(&mut self, update: DictionaryUpdate<SumDict>) {
         let _ = self.sum_dict_tx.broadcast(self.event(update));
@@ -175,6 +200,12 @@ impl EventPublisher {
     pub fn broadcast_seed_dict(&mut self, update: DictionaryUpdate<SeedDict>) {
         let _ = self.seed_dict_tx.broadcast(self.event(update));
     }
+
+    /// Emit a dropout dictionary update, i.e. the sum participants whose masks must be
+    /// reconstructed from the seed shares
+    pub fn broadcast_dropout_dict(&mut self, update: DictionaryUpdate<SumDict>) {
+        let _ = self.dropout_dict_tx.broadcast(self.event(update));
+    }
 }
 
 impl EventSubscriber {
@@ -208,6 +239,11 @@ impl EventSubscriber {
     pub fn seed_dict_listener(&self) -> EventListener<DictionaryUpdate<SeedDict>> {
         self.seed_dict_rx.clone()
     }
+
+    /// Get a listener for dropout dictionary updates
+    pub fn dropout_dict_listener(&self) -> EventListener<DictionaryUpdate<SumDict>> {
+        self.dropout_dict_rx.clone()
+    }
 }
 
 /// A listener for coordinator events. It can be used to either
@@ -1,11 +1,19 @@
+use std::sync::Arc;
+
+use rayon::ThreadPool;
 use thiserror::Error;
-#[cfg(feature = "model-persistence")]
 use tracing::{debug, info};
 
-#[cfg(feature = "model-persistence")]
-use crate::settings::RestoreSettings;
 use crate::{
-    settings::{MaskSettings, ModelSettings, PetSettings},
+    settings::{
+        AggregationSettings,
+        DifferentialPrivacySettings,
+        MaskSettings,
+        ModelSettings,
+        PetSettings,
+        RestoreSettings,
+        DEFAULT_TASK,
+    },
     state_machine::{
         coordinator::CoordinatorState,
         events::{EventPublisher, EventSubscriber, ModelUpdate},
@@ -15,8 +23,6 @@ use crate::{
     },
     storage::{CoordinatorStorage, ModelStorage, StorageError, Store},
 };
-
-#[cfg(feature = "model-persistence")]
 use xaynet_core::mask::Model;
 
 type StateMachineInitializationResult<T> = Result<T, StateMachineInitializationError>;
@@ -34,6 +40,8 @@ pub enum StateMachineInitializationError {
     FetchLatestGlobalModelId(StorageError),
     #[error("fetching global model failed: {0}")]
     FetchGlobalModel(StorageError),
+    #[error("fetching aggregation state failed: {0}")]
+    FetchAggregationState(StorageError),
     #[error("{0}")]
     GlobalModelUnavailable(String),
     #[error("{0}")]
@@ -49,10 +57,13 @@ where
     pet_settings: PetSettings,
     mask_settings: MaskSettings,
     model_settings: ModelSettings,
-    #[cfg(feature = "model-persistence")]
+    aggregation_settings: AggregationSettings,
+    privacy_settings: DifferentialPrivacySettings,
     restore_settings: RestoreSettings,
+    task: String,
 
     store: Store<C, M>,
+    thread_pool: Arc<ThreadPool>,
 }
 
 impl<C, M> StateMachineInitializer<C, M>
@@ -61,34 +72,37 @@ where
     M: ModelStorage,
 {
     /// Creates a new [`StateMachineInitializer`].
+    ///
+    /// The `thread_pool` is used for the aggregation of the masked models.
     pub fn new(
         pet_settings: PetSettings,
         mask_settings: MaskSettings,
         model_settings: ModelSettings,
-        #[cfg(feature = "model-persistence")] restore_settings: RestoreSettings,
+        aggregation_settings: AggregationSettings,
+        privacy_settings: DifferentialPrivacySettings,
+        restore_settings: RestoreSettings,
         store: Store<C, M>,
+        thread_pool: Arc<ThreadPool>,
     ) -> Self {
         Self {
             pet_settings,
             mask_settings,
             model_settings,
-            #[cfg(feature = "model-persistence")]
+            aggregation_settings,
+            privacy_settings,
             restore_settings,
+            task: DEFAULT_TASK.to_string(),
             store,
+            thread_pool,
         }
     }
 
-    #[cfg(not(feature = "model-persistence"))]
-    /// Initializes a new [`StateMachine`] with the given settings.
-    pub async fn init(
-        mut self,
-    ) -> StateMachineInitializationResult<(StateMachine<C, M>, RequestSender, EventSubscriber)>
-    {
-        // crucial: init must be called before anything else in this module
-        sodiumoxide::init().or(Err(StateMachineInitializationError::CryptoInit))?;
-
-        let (coordinator_state, global_model) = { self.from_settings().await? };
-        Ok(self.init_state_machine(coordinator_state, global_model))
+    /// Sets the name of the task of the [`StateMachine`], which tags its metrics.
+    ///
+    /// Defaults to the name of the default task.
+    pub fn with_task(mut self, task: impl Into<String>) -> Self {
+        self.task = task.into();
+        self
     }
 
     // Creates a new [`CoordinatorState`] from the given settings and deletes
@@ -106,6 +120,8 @@ where
                 self.pet_settings,
                 self.mask_settings,
                 self.model_settings.clone(),
+                self.aggregation_settings,
+                self.privacy_settings,
             ),
             ModelUpdate::Invalidate,
         ))
@@ -127,19 +143,19 @@ where
 
         let (request_rx, request_tx) = RequestReceiver::new();
 
-        let shared = Shared::new(coordinator_state, event_publisher, request_rx, self.store);
+        let mut shared = Shared::new(
+            coordinator_state,
+            event_publisher,
+            request_rx,
+            self.store,
+            self.thread_pool,
+        );
+        shared.task = self.task;
 
         let state_machine = StateMachine::from(PhaseState::<Idle, _, _>::new(shared));
         (state_machine, request_tx, event_subscriber)
     }
-}
 
-#[cfg(feature = "model-persistence")]
-impl<C, M> StateMachineInitializer<C, M>
-where
-    C: CoordinatorStorage,
-    M: ModelStorage,
-{
     /// Initializes a new [`StateMachine`] by trying to restore the previous coordinator state
     /// along with the latest global model. After a successful initialization, the state machine
     /// always starts from a new round. This means that the round id is increased by one.
@@ -153,10 +169,12 @@ where
     ///   state will be reset and a new [`StateMachine`] is created with the given settings.
     /// - If no coordinator state exists, the current coordinator state will be reset and a new
     ///   [`StateMachine`] is created with the given settings.
-    /// - If a coordinator state exists but no global model has been created so far, the
-    ///   [`StateMachine`] will be restored with the coordinator state but without a global model.
+    /// - If a coordinator state exists but no global model has been created so far or the model
+    ///   storage doesn't keep the global models, the [`StateMachine`] will be restored with the
+    ///   coordinator state but without a global model.
     /// - If a coordinator state and a global model exists, the [`StateMachine`] will be restored
-    ///   with the coordinator state and the global model.
+    ///   with the coordinator state and the global model, along with the state of the aggregation
+    ///   strategy if it belongs to the global model.
     /// - If a global model has been created but does not exists, the initialization will fail with
     ///   [`StateMachineInitializationError::GlobalModelUnavailable`].
     /// - If a global model exists but its properties do not match the coordinator model settings,
@@ -202,7 +220,7 @@ where
     // see [`StateMachineInitializer::init`]
     async fn try_restore_state(
         &mut self,
-        coordinator_state: CoordinatorState,
+        mut coordinator_state: CoordinatorState,
     ) -> StateMachineInitializationResult<(CoordinatorState, ModelUpdate)> {
         let global_model_id = match self
             .store
@@ -226,13 +244,26 @@ where
             .load_global_model(&coordinator_state, &global_model_id)
             .await?;
 
+        // the aggregation state is only valid for the global model which it belongs to, otherwise
+        // the aggregation strategy starts afresh from the global model
+        coordinator_state.aggregation = self
+            .store
+            .aggregation_state()
+            .await
+            .map_err(StateMachineInitializationError::FetchAggregationState)?
+            .filter(|state| state.global_model_id.as_ref() == Some(&global_model_id))
+            .unwrap_or_default();
+
         debug!(
             "restore coordinator with global model id: {}",
             global_model_id
         );
         Ok((
             coordinator_state,
-            ModelUpdate::New(std::sync::Arc::new(global_model)),
+            ModelUpdate::New {
+                id: global_model_id,
+                model: std::sync::Arc::new(global_model),
+            },
         ))
     }
 
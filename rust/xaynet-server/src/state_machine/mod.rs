@@ -20,7 +20,8 @@
 //! Publishes [`PhaseName::Idle`], increments the `round id` by `1`, invalidates the
 //! [`SumDict`], [`SeedDict`], `scalar` and `mask length`, updates the [`EncryptKeyPair`],
 //! `thresholds` as well as the `seed` and publishes the [`EncryptKeyPair`] and the
-//! [`RoundParameters`].
+//! [`RoundParameters`]. If the [`StateMachine`] is paused, it waits to be resumed beforehand and
+//! applies any scheduled PET settings.
 //!
 //! **Sum**
 //!
@@ -36,18 +37,23 @@
 //!
 //! Publishes [`PhaseName::Sum2`], builds the mask dictionary, ensures that enough sum2
 //! messages have been submitted and determines the applicable mask for unmasking the global
-//! masked model.
+//! masked model. If the mask seeds are secret-shared, it aggregates the masks of the sum
+//! participants instead, publishes the sum participants who dropped out and collects the seed
+//! shares of their mask seeds from the others.
 //!
 //! **Unmask**
 //!
-//! Publishes [`PhaseName::Unmask`], unmasks the global masked model and publishes the global
-//! model.
+//! Publishes [`PhaseName::Unmask`], completes the mask with the masks reconstructed from the seed
+//! shares if the mask seeds are secret-shared, unmasks the global masked model, optionally adds
+//! differential privacy noise, applies the aggregation strategy and publishes the global model.
 //!
 //! **Error**
 //!
 //! Publishes [`PhaseName::Error`] and handles [`PhaseStateError`]s that can occur during the
 //! execution of the [`StateMachine`]. In most cases, the error is handled by restarting the round.
-//! However, if a [`PhaseStateError::RequestChannel`] occurs, the [`StateMachine`] will shut down.
+//! However, if a [`PhaseStateError::RequestChannel`] occurs or if the privacy budget is
+//! exhausted, the [`StateMachine`] will shut down. Aborting a round via the
+//! [`AdminHandle`] leads to this phase as well.
 //!
 //! **Shutdown**
 //!
@@ -75,6 +81,15 @@
 //!
 //! See [here][events] for more details.
 //!
+//! # Administration
+//!
+//! The [`StateMachine`] can be inspected and controlled via an [`AdminHandle`], which is obtained
+//! through [`StateMachine::admin_handle()`]. It exposes the request counters of the current round
+//! and allows to pause the [`StateMachine`] after the current round, to abort the current round,
+//! to shut the [`StateMachine`] down and to schedule new PET settings for the next round.
+//!
+//! See [here][admin] for more details.
+//!
 //! [settings]: ../settings/index.html
 //! [`PhaseName::Idle`]: crate::state_machine::phases::PhaseName::Idle
 //! [`PhaseName::Sum`]: crate::state_machine::phases::PhaseName::Sum
@@ -92,19 +107,28 @@
 //! [`RequestSender`]: crate::state_machine::requests::RequestSender
 //! [`RequestReceiver`]: crate::state_machine::requests::RequestReceiver
 //! [events]: ./events/index.html
+//! [admin]: ./admin/index.html
+//! [`AdminHandle`]: crate::state_machine::admin::AdminHandle
 //! [`EventSubscriber`]: crate::state_machine::events::EventSubscriber
 
+pub mod admin;
+pub mod aggregation;
 pub mod coordinator;
 pub mod events;
 pub mod initializer;
 pub mod phases;
+pub mod privacy;
 pub mod requests;
+pub mod sharding;
 pub use self::initializer::StateMachineInitializer;
 
 use derive_more::From;
 use thiserror::Error;
 
-use self::phases::{Idle, Phase, PhaseState, PhaseStateError, Shutdown, Sum, Sum2, Unmask, Update};
+use self::{
+    admin::AdminHandle,
+    phases::{Idle, Phase, PhaseState, PhaseStateError, Shutdown, Sum, Sum2, Unmask, Update},
+};
 use crate::storage::{
     CoordinatorStorage,
     LocalSeedDictAddError,
@@ -194,6 +218,19 @@ where
         }
     }
 
+    /// Gets a handle to inspect and control the [`StateMachine`].
+    pub fn admin_handle(&self) -> AdminHandle {
+        match self {
+            StateMachine::Idle(state) => state.shared.admin.clone(),
+            StateMachine::Sum(state) => state.shared.admin.clone(),
+            StateMachine::Update(state) => state.shared.admin.clone(),
+            StateMachine::Sum2(state) => state.shared.admin.clone(),
+            StateMachine::Unmask(state) => state.shared.admin.clone(),
+            StateMachine::Error(state) => state.shared.admin.clone(),
+            StateMachine::Shutdown(state) => state.shared.admin.clone(),
+        }
+    }
+
     /// Runs the state machine until it shuts down.
     /// The [`StateMachine`] shuts down once all [`RequestSender`] have been dropped.
     ///
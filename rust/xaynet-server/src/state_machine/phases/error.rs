@@ -11,6 +11,7 @@ use crate::{
         phases::{
             idle::IdleStateError,
             sum::SumStateError,
+            sum2::Sum2StateError,
             unmask::UnmaskStateError,
             update::UpdateStateError,
             Idle,
@@ -32,6 +33,10 @@ pub enum PhaseStateError {
     RequestChannel(&'static str),
     #[error("phase timeout")]
     PhaseTimeout(#[from] tokio::time::Elapsed),
+    #[error("the round was aborted by the admin")]
+    Aborted,
+    #[error("the coordinator was shut down by the admin")]
+    Shutdown,
 
     #[error("idle phase failed: {0}")]
     Idle(#[from] IdleStateError),
@@ -42,6 +47,9 @@ pub enum PhaseStateError {
     #[error("update phase failed: {0}")]
     Update(#[from] UpdateStateError),
 
+    #[error("sum2 phase failed: {0}")]
+    Sum2(#[from] Sum2StateError),
+
     #[error("unmask phase failed: {0}")]
     Unmask(#[from] UnmaskStateError),
 }
@@ -80,7 +88,11 @@ where
     async fn run(&mut self) -> Result<(), PhaseStateError> {
         error!("phase state error: {}", self.private);
 
-        event!("Phase error", &self.private.to_string());
+        event!(
+            "Phase error",
+            &self.private.to_string(),
+            [self.shared.task.as_str()]
+        );
 
         self.wait_for_store_readiness().await;
 
@@ -89,7 +101,8 @@ where
 
     fn next(self) -> Option<StateMachine<C, M>> {
         Some(match self.private {
-            PhaseStateError::RequestChannel(_) => {
+            PhaseStateError::RequestChannel(_)
+            | PhaseStateError::Idle(IdleStateError::PrivacyBudgetExhausted) => {
                 PhaseState::<Shutdown, _, _>::new(self.shared).into()
             }
             _ => PhaseState::<Idle, _, _>::new(self.shared).into(),
@@ -8,7 +8,7 @@ use crate::{
     metrics::Measurement,
     state_machine::{
         events::DictionaryUpdate,
-        phases::{Phase, PhaseName, PhaseState, Shared, Sum},
+        phases::{interrupt_error, Phase, PhaseName, PhaseState, Shared, Sum},
         PhaseStateError,
         StateMachine,
     },
@@ -26,6 +26,8 @@ pub enum IdleStateError {
     SetCoordinatorState(StorageError),
     #[error("deleting the dictionaries failed: {0}")]
     DeleteDictionaries(StorageError),
+    #[error("the privacy budget is exhausted")]
+    PrivacyBudgetExhausted,
 }
 
 /// Idle state
@@ -41,6 +43,21 @@ where
     const NAME: PhaseName = PhaseName::Idle;
 
     async fn run(&mut self) -> Result<(), PhaseStateError> {
+        if self.shared.admin.is_paused() {
+            info!("paused: waiting to be resumed");
+            let admin = self.shared.admin.clone();
+            tokio::select! {
+                _ = admin.resumed() => {}
+                interrupt = admin.interrupted() => return Err(interrupt_error(interrupt)),
+            }
+        }
+        self.shared.admin.reset_counters();
+
+        if let Some(pet_settings) = self.shared.admin.take_pet_settings() {
+            info!("applying new PET settings");
+            self.shared.state.apply_pet_settings(pet_settings);
+        }
+
         info!("updating the keys");
         self.gen_round_keypair();
 
@@ -56,6 +73,17 @@ where
             .await
             .map_err(IdleStateError::SetCoordinatorState)?;
 
+        // the state is persisted beforehand, such that the spent privacy budget survives a restart
+        let privacy_settings = &self.shared.state.privacy_settings;
+        if !self
+            .shared
+            .state
+            .privacy_accountant
+            .can_spend(privacy_settings)
+        {
+            return Err(IdleStateError::PrivacyBudgetExhausted.into());
+        }
+
         let events = &mut self.shared.events;
 
         info!("broadcasting new keys");
@@ -67,6 +95,9 @@ where
         info!("broadcasting invalidation of seed dictionary from previous round");
         events.broadcast_seed_dict(DictionaryUpdate::Invalidate);
 
+        info!("broadcasting invalidation of dropout dictionary from previous round");
+        events.broadcast_dropout_dict(DictionaryUpdate::Invalidate);
+
         self.shared
             .store
             .delete_dicts()
@@ -76,18 +107,24 @@ where
         info!("broadcasting new round parameters");
         events.broadcast_params(self.shared.state.round_params.clone());
 
-        metric!(Measurement::RoundTotalNumber, self.shared.state.round_id);
+        metric!(
+            Measurement::RoundTotalNumber,
+            self.shared.state.round_id,
+            ("task", self.shared.task.as_str())
+        );
         metric!(
             Measurement::RoundParamSum,
             self.shared.state.round_params.sum,
             ("round_id", self.shared.state.round_id),
-            ("phase", Self::NAME as u8)
+            ("phase", Self::NAME as u8),
+            ("task", self.shared.task.as_str())
         );
         metric!(
             Measurement::RoundParamUpdate,
             self.shared.state.round_params.update,
             ("round_id", self.shared.state.round_id),
-            ("phase", Self::NAME as u8)
+            ("phase", Self::NAME as u8),
+            ("task", self.shared.task.as_str())
         );
 
         Ok(())
@@ -233,5 +270,70 @@ mod tests {
             events.seed_dict_listener().get_latest(),
             expected_event(DictionaryUpdate::Invalidate)
         );
+
+        assert_eq!(
+            events.dropout_dict_listener().get_latest(),
+            expected_event(DictionaryUpdate::Invalidate)
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn integration_idle_applies_pending_pet_settings() {
+        let store = init_store().await;
+        let (state_machine, _request_tx, events) = StateMachineBuilder::new(store).build();
+        assert!(state_machine.is_idle());
+
+        let mut pet_settings = utils::pet_settings();
+        pet_settings.sum = 0.2;
+        pet_settings.min_sum_count = 5;
+        pet_settings.max_update_time = 10;
+        let admin = state_machine.admin_handle();
+        admin.update_pet_settings(pet_settings);
+
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_sum());
+        assert!(admin.pending_pet_settings().is_none());
+
+        let PhaseState { shared, .. } = state_machine.into_sum_phase_state();
+        assert_eq!(shared.state.round_params.sum, 0.2);
+        assert_eq!(shared.state.min_sum_count, 5);
+        assert_eq!(shared.state.max_update_time, 10);
+        assert_eq!(events.params_listener().get_latest().event.sum, 0.2);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn integration_idle_waits_while_paused() {
+        let store = init_store().await;
+        let (state_machine, _request_tx, _events) = StateMachineBuilder::new(store).build();
+        let admin = state_machine.admin_handle();
+        admin.pause();
+
+        let next = tokio::spawn(state_machine.next());
+        tokio::time::delay_for(tokio::time::Duration::from_millis(100)).await;
+        assert!(admin.is_paused());
+
+        admin.resume();
+        let state_machine = next.await.unwrap().unwrap();
+        assert!(state_machine.is_sum());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn integration_idle_to_shutdown_if_privacy_budget_is_exhausted() {
+        let store = init_store().await;
+        let mut coordinator_state = utils::coordinator_state();
+        coordinator_state.privacy_settings.enable = true;
+        coordinator_state.privacy_accountant.spent_epsilon =
+            coordinator_state.privacy_settings.epsilon_budget;
+        let (shared, _request_tx, _events) = utils::init_shared(coordinator_state, store);
+
+        let state_machine: StateMachine<_, _> = PhaseState::<Idle, _, _>::new(shared).into();
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_error());
+
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_shutdown());
     }
 }
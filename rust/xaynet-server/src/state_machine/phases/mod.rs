@@ -8,10 +8,13 @@ mod sum2;
 mod unmask;
 mod update;
 
-use std::fmt;
+use std::{fmt, sync::Arc};
 
 use async_trait::async_trait;
+use derive_more::Display;
 use futures::StreamExt;
+use rayon::ThreadPool;
+use serde::Serialize;
 use tracing::{debug, error, error_span, info, warn, Span};
 use tracing_futures::Instrument;
 
@@ -20,14 +23,16 @@ pub use self::{
     idle::{Idle, IdleStateError},
     shutdown::Shutdown,
     sum::{Sum, SumStateError},
-    sum2::Sum2,
+    sum2::{SeedShares, Sum2, Sum2StateError},
     unmask::{Unmask, UnmaskStateError},
     update::{Update, UpdateStateError},
 };
 use crate::{
     metric,
     metrics::Measurement,
+    settings::DEFAULT_TASK,
     state_machine::{
+        admin::{AdminHandle, Interrupt, RequestOutcome},
         coordinator::CoordinatorState,
         events::EventPublisher,
         requests::{RequestReceiver, ResponseSender, StateMachineRequest},
@@ -38,7 +43,7 @@ use crate::{
 };
 
 /// The name of the current phase.
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Display, Clone, Copy, Eq, PartialEq, Hash, Serialize)]
 pub enum PhaseName {
     Idle,
     Sum,
@@ -110,6 +115,12 @@ where
     pub(in crate::state_machine) events: EventPublisher,
     /// The store for storing coordinator and model data.
     pub(in crate::state_machine) store: Store<C, M>,
+    /// The handle for the administration of the state machine.
+    pub(in crate::state_machine) admin: AdminHandle,
+    /// The thread-pool for the aggregation of masked models.
+    pub(in crate::state_machine) thread_pool: Arc<ThreadPool>,
+    /// The name of the task, which tags the metrics of the state machine.
+    pub(in crate::state_machine) task: String,
 }
 
 impl<C, M> fmt::Debug for Shared<C, M>
@@ -122,6 +133,9 @@ where
             .field("state", &self.state)
             .field("request_rx", &self.request_rx)
             .field("events", &self.events)
+            .field("admin", &self.admin)
+            .field("thread_pool", &self.thread_pool)
+            .field("task", &self.task)
             .finish()
     }
 }
@@ -131,18 +145,22 @@ where
     C: CoordinatorStorage,
     M: ModelStorage,
 {
-    /// Creates a new shared state.
+    /// Creates a new shared state of the default task.
     pub fn new(
         coordinator_state: CoordinatorState,
         publisher: EventPublisher,
         request_rx: RequestReceiver,
         store: Store<C, M>,
+        thread_pool: Arc<ThreadPool>,
     ) -> Self {
         Self {
             state: coordinator_state,
             request_rx,
             events: publisher,
             store,
+            admin: AdminHandle::new(),
+            thread_pool,
+            task: DEFAULT_TASK.to_string(),
         }
     }
 
@@ -191,7 +209,7 @@ where
                     debug!("duration elapsed");
                     break Ok(());
                 }
-                next = self.next_request() => {
+                next = self.next_request_or_interrupt() => {
                     let (req, span, resp_tx) = next?;
                     self.process_single(req, span, resp_tx).await;
                 }
@@ -202,7 +220,7 @@ where
     /// Processes requests until there are enough.
     async fn process_until_enough(&mut self) -> Result<(), PhaseStateError> {
         while !self.has_enough_messages() {
-            let (req, span, resp_tx) = self.next_request().await?;
+            let (req, span, resp_tx) = self.next_request_or_interrupt().await?;
             self.process_single(req, span, resp_tx).await;
         }
         Ok(())
@@ -220,11 +238,15 @@ where
         let res = if self.has_overmuch_messages() {
             // discard if the maximum message count is reached
             self.increment_discarded();
+            self.shared
+                .admin
+                .count(Self::NAME, RequestOutcome::Discarded);
             metric!(
                 Measurement::MessageDiscarded,
                 1,
                 ("round_id", self.shared.state.round_id),
-                ("phase", Self::NAME as u8)
+                ("phase", Self::NAME as u8),
+                ("task", self.shared.task.as_str())
             );
             Err(RequestError::MessageDiscarded)
         } else {
@@ -232,6 +254,9 @@ where
                 // accept if processed successfully
                 ok @ Ok(_) => {
                     self.increment_accepted();
+                    self.shared
+                        .admin
+                        .count(Self::NAME, RequestOutcome::Accepted);
                     // TODO: currently the metric! macro contains redundant information in case of
                     // accepted messages: the `Measurement::MessageSum/Update/Sum2` as well as the
                     // ("phase", name_u8). once we change those three enum variants to just one
@@ -246,18 +271,23 @@ where
                         },
                         1,
                         ("round_id", self.shared.state.round_id),
-                        ("phase", Self::NAME as u8)
+                        ("phase", Self::NAME as u8),
+                        ("task", self.shared.task.as_str())
                     );
                     ok
                 }
                 // otherwise reject
                 error @ Err(_) => {
                     self.increment_rejected();
+                    self.shared
+                        .admin
+                        .count(Self::NAME, RequestOutcome::Rejected);
                     metric!(
                         Measurement::MessageRejected,
                         1,
                         ("round_id", self.shared.state.round_id),
-                        ("phase", Self::NAME as u8)
+                        ("phase", Self::NAME as u8),
+                        ("task", self.shared.task.as_str())
                     );
                     error
                 }
@@ -286,10 +316,35 @@ where
             info!("broadcasting phase event");
             self.shared.events.broadcast_phase(phase);
 
-            metric!(Measurement::Phase, phase as u8);
+            metric!(
+                Measurement::Phase,
+                phase as u8,
+                ("task", self.shared.task.as_str())
+            );
 
-            if let Err(err) = self.run().await {
-                return Some(self.into_error_state(err));
+            // the error and shutdown phases can't be interrupted by the admin, the other phases
+            // are only interrupted before they start and while they wait for requests, such that
+            // an interruption never cancels a pending storage operation
+            let interrupt = if matches!(phase, PhaseName::Error | PhaseName::Shutdown) {
+                None
+            } else {
+                self.shared.admin.take_interrupt()
+            };
+            let result = match interrupt {
+                Some(interrupt) => Err(interrupt_error(interrupt)),
+                None => self.run().await,
+            };
+            match result {
+                Ok(()) => {}
+                Err(PhaseStateError::Aborted) => {
+                    warn!("aborting the round on request");
+                    return Some(self.into_error_state(PhaseStateError::Aborted));
+                }
+                Err(PhaseStateError::Shutdown) => {
+                    warn!("shutting down on request");
+                    return Some(self.into_shutdown_state());
+                }
+                Err(err) => return Some(self.into_error_state(err)),
             }
 
             info!("phase ran successfully");
@@ -322,12 +377,16 @@ where
                     let _span_guard = span.enter();
                     info!("discarding outdated request");
                     let _ = resp_tx.send(Err(RequestError::MessageRejected));
+                    self.shared
+                        .admin
+                        .count(Self::NAME, RequestOutcome::Discarded);
 
                     metric!(
                         Measurement::MessageDiscarded,
                         1,
                         ("round_id", self.shared.state.round_id),
-                        ("phase", Self::NAME as u8)
+                        ("phase", Self::NAME as u8),
+                        ("task", self.shared.task.as_str())
                     );
                 }
                 None => return Ok(()),
@@ -356,6 +415,25 @@ where
         })
     }
 
+    /// Receives the next [`StateMachineRequest`] unless the admin interrupts the phase first.
+    ///
+    /// The interruption is only awaited in between requests, since a request which is being
+    /// processed may be in the middle of a storage operation.
+    ///
+    /// # Errors
+    /// Returns [`PhaseStateError::Aborted`] or [`PhaseStateError::Shutdown`] when the admin
+    /// interrupts the phase and [`PhaseStateError::RequestChannel`] when all sender halves have
+    /// been dropped.
+    async fn next_request_or_interrupt(
+        &mut self,
+    ) -> Result<(StateMachineRequest, Span, ResponseSender), PhaseStateError> {
+        let admin = self.shared.admin.clone();
+        tokio::select! {
+            next = self.next_request() => next,
+            interrupt = admin.interrupted() => Err(interrupt_error(interrupt)),
+        }
+    }
+
     fn try_next_request(
         &mut self,
     ) -> Result<Option<(StateMachineRequest, Span, ResponseSender)>, PhaseStateError> {
@@ -377,14 +455,39 @@ where
     fn into_error_state(self, err: PhaseStateError) -> StateMachine<C, M> {
         PhaseState::<PhaseStateError, _, _>::new(self.shared, err).into()
     }
+
+    fn into_shutdown_state(self) -> StateMachine<C, M> {
+        PhaseState::<Shutdown, _, _>::new(self.shared).into()
+    }
+}
+
+/// Converts an administrative interruption into the error which ends the interrupted phase.
+fn interrupt_error(interrupt: Interrupt) -> PhaseStateError {
+    match interrupt {
+        Interrupt::Abort => PhaseStateError::Aborted,
+        Interrupt::Shutdown => PhaseStateError::Shutdown,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use serial_test::serial;
 
+    use tokio::time::{delay_for, Duration};
+
     use super::*;
-    use crate::{state_machine::tests::utils, storage::tests::init_store};
+    use crate::{
+        state_machine::tests::utils,
+        storage::{
+            coordinator_storage::in_memory::InMemory,
+            model_storage::noop::NoOp,
+            tests::init_store,
+        },
+    };
+
+    fn in_memory_store() -> Store<InMemory, NoOp> {
+        Store::new(InMemory::new(), NoOp)
+    }
 
     #[tokio::test]
     #[serial]
@@ -410,4 +513,64 @@ mod tests {
         let id = phases.get_latest().round_id;
         assert_eq!(id, 1);
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn integration_admin_abort_and_shutdown() {
+        let store = init_store().await;
+        let coordinator_state = utils::coordinator_state();
+        let (shared, _request_tx, _events) = utils::init_shared(coordinator_state, store);
+        let admin = shared.admin.clone();
+
+        // an aborted round moves into the error phase
+        let state_machine: StateMachine<_, _> = PhaseState::<Sum, _, _>::new(shared).into();
+        admin.abort();
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_error());
+        let PhaseState { private, shared } = state_machine.into_error_phase_state();
+        assert!(matches!(private, PhaseStateError::Aborted));
+
+        // a shutdown moves directly into the shutdown phase
+        let state_machine: StateMachine<_, _> = PhaseState::<Sum, _, _>::new(shared).into();
+        admin.shutdown();
+        let state_machine = state_machine.next().await.unwrap();
+        assert!(state_machine.is_shutdown());
+    }
+
+    #[tokio::test]
+    async fn test_admin_abort_while_waiting_for_requests() {
+        let coordinator_state = utils::coordinator_state();
+        let (shared, _request_tx, _events) =
+            utils::init_shared(coordinator_state, in_memory_store());
+        let admin = shared.admin.clone();
+
+        // the running phase is interrupted while it waits for requests
+        let state_machine: StateMachine<_, _> = PhaseState::<Sum, _, _>::new(shared).into();
+        let abort = async {
+            delay_for(Duration::from_millis(10)).await;
+            admin.abort();
+        };
+        let (state_machine, _) = tokio::join!(state_machine.next(), abort);
+        let state_machine = state_machine.unwrap();
+        assert!(state_machine.is_error());
+        let PhaseState { private, .. } = state_machine.into_error_phase_state();
+        assert!(matches!(private, PhaseStateError::Aborted));
+    }
+
+    #[tokio::test]
+    async fn test_admin_shutdown_while_paused() {
+        let coordinator_state = utils::coordinator_state();
+        let (shared, _request_tx, _events) =
+            utils::init_shared(coordinator_state, in_memory_store());
+        let admin = shared.admin.clone();
+        admin.pause();
+
+        let state_machine: StateMachine<_, _> = PhaseState::<Idle, _, _>::new(shared).into();
+        let shutdown = async {
+            delay_for(Duration::from_millis(10)).await;
+            admin.shutdown();
+        };
+        let (state_machine, _) = tokio::join!(state_machine.next(), shutdown);
+        assert!(state_machine.unwrap().is_shutdown());
+    }
 }
@@ -1,26 +1,183 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
 use async_trait::async_trait;
+use thiserror::Error;
 use tokio::time::{timeout, Duration};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::{
     state_machine::{
+        events::DictionaryUpdate,
         phases::{Handler, Phase, PhaseName, PhaseState, PhaseStateError, Shared, Unmask},
-        requests::{StateMachineRequest, Sum2Request},
+        requests::{StateMachineRequest, Sum2Request, Sum2SharesRequest},
         RequestError,
         StateMachine,
     },
-    storage::{CoordinatorStorage, ModelStorage},
+    storage::{CoordinatorStorage, MaskScoreIncrError, ModelStorage, StorageError},
 };
 use xaynet_core::{
-    mask::{Aggregation, MaskObject},
+    mask::{share_indices, sharing_threshold, Aggregation, MaskObject, MaskSeed, SharingError},
+    SeedShareDict,
+    SumDict,
     SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
 };
 
+/// Error that occurs during the sum2 phase.
+#[derive(Error, Debug)]
+pub enum Sum2StateError {
+    #[error("sum dictionary does not exists")]
+    NoSumDict,
+    #[error("fetching sum dictionary failed: {0}")]
+    FetchSumDict(StorageError),
+    #[error("seed dictionary does not exists")]
+    NoSeedDict,
+    #[error("fetching seed dictionary failed: {0}")]
+    FetchSeedDict(StorageError),
+    #[error("assigning the share indices failed: {0}")]
+    ShareIndices(#[from] SharingError),
+}
+
+/// The masks and the shares of the mask seeds which are collected during the sum2 phase if the
+/// mask seeds are secret-shared.
+///
+/// First, every sum participant submits the aggregation of the masks derived from its own mask
+/// seeds. Then, the sum participants who didn't submit their masks are considered dropped out and
+/// the others submit their shares of the mask seeds of the dropouts. The mask seeds of the
+/// remaining sum participants are never revealed to the coordinator.
+#[derive(Debug)]
+pub struct SeedShares {
+    /// The number of shares which are required to reconstruct a mask seed.
+    pub threshold: usize,
+    /// The sum dictionary.
+    sum_dict: SumDict,
+    /// The share index of every sum participant.
+    indices: HashMap<SumParticipantPublicKey, u8>,
+    /// The update participants of the round.
+    update_pks: HashSet<UpdateParticipantPublicKey>,
+    /// The aggregated masks of the sum participants who submitted their masks.
+    pub masks: Aggregation,
+    /// The sum participants who submitted their masks.
+    masked: HashSet<SumParticipantPublicKey>,
+    /// The sum participants who dropped out, once the masks have been collected.
+    dropouts: Option<HashSet<SumParticipantPublicKey>>,
+    /// The sum participants who submitted their shares.
+    submitted: HashSet<SumParticipantPublicKey>,
+    /// The indexed shares of the mask seeds of every sum participant who dropped out for every
+    /// update participant.
+    pub shares:
+        HashMap<SumParticipantPublicKey, HashMap<UpdateParticipantPublicKey, Vec<(u8, MaskSeed)>>>,
+}
+
+impl SeedShares {
+    /// Adds the aggregated mask of a sum participant.
+    fn add_mask(
+        &mut self,
+        participant_pk: SumParticipantPublicKey,
+        mask: MaskObject,
+    ) -> Result<(), RequestError> {
+        if !self.indices.contains_key(&participant_pk) {
+            return Err(MaskScoreIncrError::UnknownSumPk.into());
+        }
+        if self.masked.contains(&participant_pk) {
+            return Err(MaskScoreIncrError::MaskAlreadySubmitted.into());
+        }
+        self.masks
+            .validate_aggregation(&mask)
+            .map_err(|_| RequestError::AggregationFailed)?;
+
+        self.masks.aggregate(mask);
+        self.masked.insert(participant_pk);
+        Ok(())
+    }
+
+    /// Considers the sum participants who didn't submit their masks as dropped out and returns
+    /// them.
+    fn drop_out(&mut self) -> SumDict {
+        let dropouts = self
+            .sum_dict
+            .iter()
+            .filter(|(pk, _)| !self.masked.contains(pk))
+            .map(|(pk, ephm_pk)| (*pk, *ephm_pk))
+            .collect::<SumDict>();
+        self.shares = dropouts
+            .keys()
+            .map(|pk| {
+                let shares = self
+                    .update_pks
+                    .iter()
+                    .map(|update_pk| (*update_pk, Vec::with_capacity(self.threshold)))
+                    .collect();
+                (*pk, shares)
+            })
+            .collect();
+        self.dropouts = Some(dropouts.keys().copied().collect());
+        dropouts
+    }
+
+    /// Adds the shares of a sum participant.
+    ///
+    /// The shares must cover exactly the mask seeds of the update participants for the sum
+    /// participants who dropped out.
+    fn add_shares(
+        &mut self,
+        participant_pk: SumParticipantPublicKey,
+        seed_shares: SeedShareDict,
+    ) -> Result<(), RequestError> {
+        let dropouts = self
+            .dropouts
+            .as_ref()
+            .ok_or(RequestError::MessageRejected)?;
+        if !self.masked.contains(&participant_pk) {
+            return Err(MaskScoreIncrError::UnknownSumPk.into());
+        }
+        if self.submitted.contains(&participant_pk) {
+            return Err(MaskScoreIncrError::MaskAlreadySubmitted.into());
+        }
+        if seed_shares.len() != dropouts.len()
+            || !seed_shares.iter().all(|(pk, update_seed_shares)| {
+                dropouts.contains(pk)
+                    && update_seed_shares.len() == self.update_pks.len()
+                    && update_seed_shares
+                        .keys()
+                        .all(|update_pk| self.update_pks.contains(update_pk))
+            })
+        {
+            return Err(RequestError::MessageRejected);
+        }
+
+        // safe unwrap: the participant has an index, since it submitted its mask
+        let index = self.indices[&participant_pk];
+        for (pk, update_seed_shares) in seed_shares {
+            // safe unwraps: the keys have been checked above
+            let shares = self.shares.get_mut(&pk).unwrap();
+            for (update_pk, share) in update_seed_shares {
+                shares.get_mut(&update_pk).unwrap().push((index, share));
+            }
+        }
+        self.submitted.insert(participant_pk);
+        Ok(())
+    }
+
+    /// Checks whether enough masks or shares have been collected to unmask the global model.
+    fn is_complete(&self) -> bool {
+        match self.dropouts {
+            None => self.masked.len() >= self.threshold,
+            Some(ref dropouts) => dropouts.is_empty() || self.submitted.len() >= self.threshold,
+        }
+    }
+}
+
 /// The sum2 state.
 #[derive(Debug)]
 pub struct Sum2 {
     /// The aggregator for masked models.
     model_agg: Aggregation,
+    /// The masks and the shares of the mask seeds if the mask seeds are secret-shared.
+    seed_shares: Option<SeedShares>,
     /// The number of sum2 messages successfully processed.
     accepted: u64,
     /// The number of sum2 messages failed to processed.
@@ -39,6 +196,10 @@ where
     const NAME: PhaseName = PhaseName::Sum2;
 
     async fn run(&mut self) -> Result<(), PhaseStateError> {
+        if let Some(fraction) = self.shared.state.round_params.seed_sharing_threshold {
+            self.private.seed_shares = Some(self.init_seed_shares(fraction).await?);
+        }
+
         let min_time = self.shared.state.min_sum_time;
         let max_time = self.shared.state.max_sum_time;
         debug!(
@@ -50,6 +211,10 @@ where
         let time_left = max_time - min_time;
         timeout(Duration::from_secs(time_left), self.process_until_enough()).await??;
 
+        if self.private.seed_shares.is_some() {
+            self.collect_dropout_shares(min_time, time_left).await?;
+        }
+
         info!(
             "in total {} sum2 messages accepted (min {} and max {} required)",
             self.private.accepted, self.shared.state.min_sum_count, self.shared.state.max_sum_count,
@@ -67,7 +232,22 @@ where
     ///
     /// See the [module level documentation](../index.html) for more details.
     fn next(self) -> Option<StateMachine<C, M>> {
-        Some(PhaseState::<Unmask, _, _>::new(self.shared, self.private.model_agg).into())
+        let Sum2 {
+            model_agg,
+            seed_shares,
+            ..
+        } = self.private;
+        Some(
+            match seed_shares {
+                Some(seed_shares) => PhaseState::<Unmask, _, _>::new_with_seed_shares(
+                    self.shared,
+                    model_agg,
+                    seed_shares,
+                ),
+                None => PhaseState::<Unmask, _, _>::new(self.shared, model_agg),
+            }
+            .into(),
+        )
     }
 }
 
@@ -78,23 +258,46 @@ where
     M: ModelStorage,
 {
     async fn handle_request(&mut self, req: StateMachineRequest) -> Result<(), RequestError> {
-        if let StateMachineRequest::Sum2(Sum2Request {
-            participant_pk,
-            model_mask,
-        }) = req
-        {
-            self.update_mask_dict(participant_pk, model_mask).await
-        } else {
-            Err(RequestError::MessageRejected)
+        match (req, self.private.seed_shares.as_mut()) {
+            (
+                StateMachineRequest::Sum2(Sum2Request {
+                    participant_pk,
+                    model_mask,
+                }),
+                None,
+            ) => self.update_mask_dict(participant_pk, model_mask).await,
+            (
+                StateMachineRequest::Sum2(Sum2Request {
+                    participant_pk,
+                    model_mask,
+                }),
+                Some(seed_shares),
+            ) if seed_shares.dropouts.is_none() => seed_shares.add_mask(participant_pk, model_mask),
+            (
+                StateMachineRequest::Sum2Shares(Sum2SharesRequest {
+                    participant_pk,
+                    seed_shares: shares,
+                }),
+                Some(seed_shares),
+            ) => seed_shares.add_shares(participant_pk, shares),
+            _ => Err(RequestError::MessageRejected),
         }
     }
 
     fn has_enough_messages(&self) -> bool {
-        self.private.accepted >= self.shared.state.min_sum_count
+        // if the mask seeds are secret-shared, the sum participants above the threshold may drop
+        // out without breaking the round
+        match self.private.seed_shares {
+            Some(ref seed_shares) => seed_shares.is_complete(),
+            None => self.private.accepted >= self.shared.state.min_sum_count,
+        }
     }
 
     fn has_overmuch_messages(&self) -> bool {
-        self.private.accepted >= self.shared.state.max_sum_count
+        // if the mask seeds are secret-shared, the sum participants submit a mask and possibly
+        // their shares, which are limited by the sum dictionary
+        self.private.seed_shares.is_none()
+            && self.private.accepted >= self.shared.state.max_sum_count
     }
 
     fn increment_accepted(&mut self) {
@@ -126,6 +329,7 @@ where
         Self {
             private: Sum2 {
                 model_agg,
+                seed_shares: None,
                 accepted: 0,
                 rejected: 0,
                 discarded: 0,
@@ -147,6 +351,83 @@ where
             .into_inner()
             .map_err(RequestError::from)
     }
+
+    /// Prepares the collection of the masks and seed shares from the sum and seed dictionaries.
+    async fn init_seed_shares(&mut self, fraction: f64) -> Result<SeedShares, Sum2StateError> {
+        let sum_dict = self
+            .shared
+            .store
+            .sum_dict()
+            .await
+            .map_err(Sum2StateError::FetchSumDict)?
+            .ok_or(Sum2StateError::NoSumDict)?;
+        let seed_dict = self
+            .shared
+            .store
+            .seed_dict()
+            .await
+            .map_err(Sum2StateError::FetchSeedDict)?
+            .ok_or(Sum2StateError::NoSeedDict)?;
+
+        let indices = share_indices(&sum_dict)?;
+        let threshold = sharing_threshold(fraction, sum_dict.len());
+        // every sum participant holds a mask seed of every update participant
+        let update_pks = seed_dict
+            .values()
+            .next()
+            .map(|update_seed_dict| update_seed_dict.keys().copied().collect())
+            .unwrap_or_default();
+        let round_params = &self.shared.state.round_params;
+        let masks = Aggregation::new(round_params.mask_config, round_params.mask_length());
+        info!(
+            "collecting masks of {} sum participants (min {} required)",
+            indices.len(),
+            threshold,
+        );
+
+        Ok(SeedShares {
+            threshold,
+            sum_dict,
+            indices,
+            update_pks,
+            masks,
+            masked: HashSet::new(),
+            dropouts: None,
+            submitted: HashSet::new(),
+            shares: HashMap::new(),
+        })
+    }
+
+    /// Broadcasts the sum participants who dropped out and collects the shares of their mask
+    /// seeds from the remaining sum participants.
+    async fn collect_dropout_shares(
+        &mut self,
+        min_time: u64,
+        time_left: u64,
+    ) -> Result<(), PhaseStateError> {
+        // safe unwrap: the seed shares are initialized if the mask seeds are secret-shared
+        let dropouts = self.private.seed_shares.as_mut().unwrap().drop_out();
+        let is_complete = dropouts.is_empty();
+        if is_complete {
+            info!("no sum participants dropped out");
+        } else {
+            warn!(
+                "{} sum participants dropped out, collecting their seed shares",
+                dropouts.len()
+            );
+        }
+        info!("broadcasting the dropout dictionary");
+        self.shared
+            .events
+            .broadcast_dropout_dict(DictionaryUpdate::New(Arc::new(dropouts)));
+        if is_complete {
+            return Ok(());
+        }
+
+        self.process_during(Duration::from_secs(min_time)).await?;
+        timeout(Duration::from_secs(time_left), self.process_until_enough()).await??;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -191,6 +472,8 @@ mod tests {
             seed: RoundSeed::generate(),
             mask_config: utils::mask_config(),
             model_length,
+            seed_sharing_threshold: None,
+            model_delta: false,
         };
 
         let n_updaters = 1;
@@ -223,6 +506,7 @@ mod tests {
             .with_seed(round_params.seed.clone())
             .with_phase(Sum2 {
                 model_agg: agg,
+                seed_shares: None,
                 accepted: 0,
                 rejected: 0,
                 discarded: 0,
@@ -1,20 +1,34 @@
-use std::{cmp::Ordering, sync::Arc};
+use std::{cmp::Ordering, iter, sync::Arc};
 
 use async_trait::async_trait;
 use thiserror::Error;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
     metric,
     metrics::{GlobalRecorder, Measurement},
     state_machine::{
+        aggregation::{self, AggregationError, AggregationState},
         events::ModelUpdate,
-        phases::{Idle, Phase, PhaseName, PhaseState, PhaseStateError, Shared},
+        phases::{Idle, Phase, PhaseName, PhaseState, PhaseStateError, SeedShares, Shared},
+        privacy::{self, PrivacyError},
         StateMachine,
     },
     storage::{CoordinatorStorage, ModelStorage, StorageError},
 };
-use xaynet_core::mask::{Aggregation, MaskObject, Model, UnmaskingError};
+use xaynet_core::{
+    mask::{
+        Aggregation,
+        FromPrimitives,
+        MaskObject,
+        MaskSeed,
+        MaskSelection,
+        Model,
+        SharingError,
+        UnmaskingError,
+    },
+    UpdateParticipantPublicKey,
+};
 
 /// Error that occurs during the unmask phase.
 #[derive(Error, Debug)]
@@ -23,11 +37,18 @@ pub enum UnmaskStateError {
     AmbiguousMasks,
     #[error("no mask found")]
     NoMask,
+    #[error("the seed shares of the mask seed of the update participant {0:?} are inconsistent")]
+    InconsistentSeedShares(UpdateParticipantPublicKey),
+    #[error("reconstructing a mask seed failed: {0}")]
+    Sharing(#[from] SharingError),
     #[error("unmasking global model failed: {0}")]
     Unmasking(#[from] UnmaskingError),
     #[error("fetching best masks failed: {0}")]
     FetchBestMasks(#[from] StorageError),
-    #[cfg(feature = "model-persistence")]
+    #[error("applying the aggregation strategy failed: {0}")]
+    Aggregation(#[from] AggregationError),
+    #[error("adding differential privacy noise failed: {0}")]
+    Privacy(#[from] PrivacyError),
     #[error("saving the global model failed: {0}")]
     SaveGlobalModel(crate::storage::StorageError),
 }
@@ -37,6 +58,8 @@ pub enum UnmaskStateError {
 pub struct Unmask {
     /// The aggregator for masked models.
     model_agg: Option<Aggregation>,
+    /// The shares of the mask seeds if the mask seeds are secret-shared.
+    seed_shares: Option<SeedShares>,
 }
 
 #[async_trait]
@@ -48,25 +71,33 @@ where
     const NAME: PhaseName = PhaseName::Unmask;
 
     async fn run(&mut self) -> Result<(), PhaseStateError> {
-        self.emit_number_of_unique_masks_metrics();
+        let mask = match self.private.seed_shares.take() {
+            Some(seed_shares) => self.reconstruct_mask(seed_shares)?,
+            None => {
+                self.emit_number_of_unique_masks_metrics();
 
-        let best_masks = self
-            .shared
-            .store
-            .best_masks()
-            .await
-            .map_err(UnmaskStateError::FetchBestMasks)?
-            .ok_or(UnmaskStateError::NoMask)?;
+                let best_masks = self
+                    .shared
+                    .store
+                    .best_masks()
+                    .await
+                    .map_err(UnmaskStateError::FetchBestMasks)?
+                    .ok_or(UnmaskStateError::NoMask)?;
+                self.freeze_mask_dict(best_masks).await?
+            }
+        };
 
-        let global_model = self.end_round(best_masks).await?;
+        let (global_model, aggregation_state) = self.end_round(mask)?;
 
-        #[cfg(feature = "model-persistence")]
-        self.save_global_model(&global_model).await?;
+        let global_model_id = self.save_global_model(&global_model).await?;
+        self.save_aggregation_state(aggregation_state, &global_model_id)
+            .await;
 
         info!("broadcasting the new global model");
-        self.shared
-            .events
-            .broadcast_model(ModelUpdate::New(Arc::new(global_model)));
+        self.shared.events.broadcast_model(ModelUpdate::New {
+            id: global_model_id,
+            model: Arc::new(global_model),
+        });
 
         Ok(())
     }
@@ -86,6 +117,23 @@ where
         Self {
             private: Unmask {
                 model_agg: Some(model_agg),
+                seed_shares: None,
+            },
+            shared,
+        }
+    }
+
+    /// Creates a new unmask state which completes the mask with the masks reconstructed from the
+    /// seed shares.
+    pub fn new_with_seed_shares(
+        shared: Shared<C, M>,
+        model_agg: Aggregation,
+        seed_shares: SeedShares,
+    ) -> Self {
+        Self {
+            private: Unmask {
+                model_agg: Some(model_agg),
+                seed_shares: Some(seed_shares),
             },
             shared,
         }
@@ -112,12 +160,48 @@ where
         Ok(mask)
     }
 
-    async fn end_round(
-        &mut self,
-        best_masks: Vec<(MaskObject, u64)>,
-    ) -> Result<Model, UnmaskStateError> {
-        let mask = self.freeze_mask_dict(best_masks).await?;
+    /// Completes the aggregated masks of the sum participants with the masks of the sum
+    /// participants who dropped out.
+    ///
+    /// The mask seeds of the dropouts are reconstructed from the seed shares of the remaining sum
+    /// participants, where every further share beyond the threshold must be consistent with the
+    /// reconstructed mask seed.
+    fn reconstruct_mask(&self, seed_shares: SeedShares) -> Result<MaskObject, UnmaskStateError> {
+        let SeedShares {
+            threshold,
+            masks: mut mask_agg,
+            shares,
+            ..
+        } = seed_shares;
+        info!(
+            "reconstructing the mask seeds of {} sum participants who dropped out",
+            shares.len()
+        );
+        let config = self.shared.state.round_params.mask_config;
+        let mask_len = self.shared.state.round_params.mask_length();
+        for (update_pk, shares) in shares.values().flatten() {
+            let seed =
+                MaskSeed::reconstruct_checked(shares, threshold).map_err(|err| match err {
+                    SharingError::InconsistentShares => {
+                        UnmaskStateError::InconsistentSeedShares(*update_pk)
+                    }
+                    err => UnmaskStateError::from(err),
+                })?;
+            // the derived masks are valid by construction and their number doesn't matter for
+            // the unmasking
+            mask_agg.aggregate(seed.derive_mask(mask_len, config));
+        }
+        Ok(mask_agg.into())
+    }
 
+    /// Unmasks the aggregated model and applies the aggregation strategy.
+    ///
+    /// The new state of the aggregation strategy is returned along with the new global model
+    /// instead of being applied right away, so that it can't get ahead of the saved global models.
+    fn end_round(
+        &mut self,
+        mask: MaskObject,
+    ) -> Result<(Model, AggregationState), UnmaskStateError> {
         // Safe unwrap: State::<Unmask>::new always creates Some(aggregation)
         let model_agg = self.private.model_agg.take().unwrap();
 
@@ -125,13 +209,87 @@ where
             .validate_unmasking(&mask)
             .map_err(UnmaskStateError::from)?;
 
-        Ok(model_agg.unmask(mask))
+        let model = model_agg.unmask(mask);
+        let model = self.add_privacy_noise(model)?;
+        let round_params = &self.shared.state.round_params;
+        let model = match round_params.mask_selection() {
+            Some(selection) => {
+                info!(
+                    "scattering {} unmasked weights into the model",
+                    selection.len()
+                );
+                // the weights which weren't masked are unchanged, i.e. zero in delta mode
+                let base = if round_params.model_delta {
+                    None
+                } else {
+                    self.shared.events.latest_model()
+                };
+                scatter_weights(
+                    &selection,
+                    model,
+                    base.as_deref(),
+                    round_params.model_length,
+                )
+            }
+            None => model,
+        };
+        let model = if self.shared.state.round_params.model_delta {
+            info!("adding the aggregated delta to the previous global model");
+            add_global_model(model, self.shared.events.latest_model().as_deref())
+        } else {
+            model
+        };
+        let strategy = aggregation::from_settings(&self.shared.state.aggregation_settings);
+        strategy
+            .aggregate(
+                &self.shared.state.aggregation,
+                self.shared.events.latest_model().as_deref(),
+                model,
+            )
+            .map_err(UnmaskStateError::from)
     }
 
-    #[cfg(feature = "model-persistence")]
-    async fn save_global_model(&mut self, global_model: &Model) -> Result<(), UnmaskStateError> {
-        use tracing::warn;
+    /// Adds the differential privacy noise to the unmasked model and spends the privacy budget
+    /// of the round.
+    fn add_privacy_noise(&mut self, model: Model) -> Result<Model, UnmaskStateError> {
+        let settings = &self.shared.state.privacy_settings;
+        if !settings.enable {
+            return Ok(model);
+        }
+
+        let model = privacy::add_noise(settings, model)?;
+        self.shared.state.privacy_accountant.spend(settings);
 
+        let accountant = &self.shared.state.privacy_accountant;
+        info!(
+            "spent privacy budget: epsilon = {}, delta = {}",
+            accountant.spent_epsilon, accountant.spent_delta
+        );
+        metric!(
+            Measurement::PrivacyBudgetEpsilon,
+            accountant.spent_epsilon,
+            ("round_id", self.shared.state.round_id),
+            ("phase", PhaseName::Unmask as u8),
+            ("task", self.shared.task.as_str())
+        );
+        metric!(
+            Measurement::PrivacyBudgetDelta,
+            accountant.spent_delta,
+            ("round_id", self.shared.state.round_id),
+            ("phase", PhaseName::Unmask as u8),
+            ("task", self.shared.task.as_str())
+        );
+        Ok(model)
+    }
+
+    /// Saves the global model and returns its global model id.
+    ///
+    /// The id is only recorded as the latest global model id if the model storage keeps the
+    /// global models, otherwise the coordinator is restored without a global model.
+    async fn save_global_model(
+        &mut self,
+        global_model: &Model,
+    ) -> Result<String, UnmaskStateError> {
         let round_seed = &self.shared.state.round_params.seed;
         let global_model_id = self
             .shared
@@ -139,16 +297,69 @@ where
             .set_global_model(self.shared.state.round_id, &round_seed, global_model)
             .await
             .map_err(UnmaskStateError::SaveGlobalModel)?;
+        if !self.shared.store.is_persistent() {
+            return Ok(global_model_id);
+        }
         let _ = self
             .shared
             .store
             .set_latest_global_model_id(&global_model_id)
             .await
             .map_err(|err| warn!("failed to update latest global model id: {}", err));
-        Ok(())
+        Ok(global_model_id)
+    }
+
+    /// Applies the new state of the aggregation strategy which belongs to the saved global model.
+    ///
+    /// The state is only stored if the model storage keeps the global models, since it is
+    /// discarded without the global model when the coordinator is restored.
+    async fn save_aggregation_state(&mut self, mut state: AggregationState, global_model_id: &str) {
+        state.global_model_id = Some(global_model_id.to_string());
+        if self.shared.store.is_persistent() {
+            let _ = self
+                .shared
+                .store
+                .set_aggregation_state(&state)
+                .await
+                .map_err(|err| warn!("failed to update aggregation state: {}", err));
+        }
+        self.shared.state.aggregation = state;
+    }
+}
+
+/// Adds the previous global model to the aggregated delta of the local models.
+///
+/// A missing global model or one of another length counts as a model of zeros, just like for the
+/// participants.
+fn add_global_model(delta: Model, global_model: Option<&Model>) -> Model {
+    match global_model {
+        Some(global_model) if global_model.len() == delta.len() => delta
+            .into_iter()
+            .zip(global_model.iter())
+            .map(|(delta, weight)| delta + weight)
+            .collect(),
+        _ => delta,
     }
 }
 
+/// Scatters the unmasked weights of sparse models into a model of `model_length` weights.
+///
+/// The weights which weren't masked are taken from the `base` model, where a missing base model or
+/// one of another length counts as a model of zeros.
+fn scatter_weights(
+    selection: &MaskSelection,
+    weights: Model,
+    base: Option<&Model>,
+    model_length: usize,
+) -> Model {
+    let base = match base {
+        Some(base) if base.len() == model_length => base.clone(),
+        // UNWRAP_SAFE: zeros are always representable
+        _ => Model::from_primitives(iter::repeat(0_i32).take(model_length)).unwrap(),
+    };
+    selection.scatter(weights, base)
+}
+
 impl<C, M> PhaseState<Unmask, C, M>
 where
     Self: Phase<C, M>,
@@ -162,6 +373,7 @@ where
 
         let mut store = self.shared.store.clone();
         let (round_id, phase_name) = (self.shared.state.round_id, Self::NAME);
+        let task = self.shared.task.clone();
 
         tokio::spawn(async move {
             match store.number_of_unique_masks().await {
@@ -169,7 +381,8 @@ where
                     Measurement::MasksTotalNumber,
                     number_of_masks,
                     ("round_id", round_id),
-                    ("phase", phase_name as u8)
+                    ("phase", phase_name as u8),
+                    ("task", task.as_str())
                 ),
                 Err(err) => error!("failed to fetch total number of masks: {}", err),
             };
@@ -180,10 +393,51 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use xaynet_core::{
+        common::RoundSeed,
+        crypto::ByteObject,
+        mask::{IntoPrimitives, SparsityType},
+    };
 
     impl Unmask {
         pub fn aggregation(&self) -> Option<&Aggregation> {
             self.model_agg.as_ref()
         }
     }
+
+    #[test]
+    fn test_add_global_model() {
+        let delta = Model::from_primitives(vec![1_i32, -2, 3].into_iter()).unwrap();
+        let global_model = Model::from_primitives(vec![4_i32, 5, 6].into_iter()).unwrap();
+        let model = add_global_model(delta.clone(), Some(&global_model));
+        let weights: Vec<i32> = model.into_primitives_unchecked().collect();
+        assert_eq!(weights, vec![5, 3, 9]);
+
+        // without a matching global model the delta is the model
+        assert_eq!(add_global_model(delta.clone(), None), delta);
+        let global_model = Model::from_primitives(vec![4_i32, 5].into_iter()).unwrap();
+        assert_eq!(add_global_model(delta.clone(), Some(&global_model)), delta);
+    }
+
+    #[test]
+    fn test_scatter_weights() {
+        let selection = MaskSelection::new(&RoundSeed::generate(), 20, SparsityType::S1).unwrap();
+        let indices = selection.indices().to_vec();
+        let weights = Model::from_primitives(vec![7_i32, 8].into_iter()).unwrap();
+        let base = Model::from_primitives(1_i32..=20).unwrap();
+
+        let model = scatter_weights(&selection, weights.clone(), Some(&base), 20);
+        let scattered: Vec<i32> = model.into_primitives_unchecked().collect();
+        for (i, weight) in scattered.into_iter().enumerate() {
+            match indices.iter().position(|index| *index == i) {
+                Some(position) => assert_eq!(weight, 7 + position as i32),
+                None => assert_eq!(weight, i as i32 + 1),
+            }
+        }
+
+        // without a matching base model the other weights are zero
+        let model = scatter_weights(&selection, weights, None, 20);
+        let scattered: Vec<i32> = model.into_primitives_unchecked().collect();
+        assert_eq!(scattered.iter().filter(|weight| **weight == 0).count(), 18);
+    }
 }
@@ -10,6 +10,7 @@ use crate::{
         events::DictionaryUpdate,
         phases::{Handler, Phase, PhaseName, PhaseState, PhaseStateError, Shared, Sum2},
         requests::{StateMachineRequest, UpdateRequest},
+        sharding::{ShardedAggregation, ShardedAggregationError},
         RequestError,
         StateMachine,
     },
@@ -28,13 +29,15 @@ pub enum UpdateStateError {
     NoSeedDict,
     #[error("fetching seed dictionary failed: {0}")]
     FetchSeedDict(StorageError),
+    #[error("{0}")]
+    Aggregation(#[from] ShardedAggregationError),
 }
 
 /// The update state.
 #[derive(Debug)]
 pub struct Update {
-    /// The aggregator for masked models.
-    model_agg: Aggregation,
+    /// The aggregator for masked models, which aggregates them in parallel.
+    model_agg: ShardedAggregation,
     /// The number of update messages successfully processed.
     accepted: u64,
     /// The number of update messages failed to processed.
@@ -79,6 +82,12 @@ where
             self.private.discarded,
         );
 
+        debug!("waiting for the aggregation of the masked models");
+        self.private.model_agg.flush().await.map_err(|err| {
+            warn!("aggregating the masked models failed");
+            UpdateStateError::from(err)
+        })?;
+
         let seed_dict = self
             .shared
             .store
@@ -96,7 +105,8 @@ where
     }
 
     fn next(self) -> Option<StateMachine<C, M>> {
-        Some(PhaseState::<Sum2, _, _>::new(self.shared, self.private.model_agg).into())
+        let model_agg = self.private.model_agg.into_aggregation();
+        Some(PhaseState::<Sum2, _, _>::new(self.shared, model_agg).into())
     }
 }
 
@@ -162,9 +172,12 @@ where
     pub fn new(shared: Shared<C, M>) -> Self {
         Self {
             private: Update {
-                model_agg: Aggregation::new(
-                    shared.state.round_params.mask_config,
-                    shared.state.round_params.model_length,
+                model_agg: ShardedAggregation::new(
+                    Aggregation::new(
+                        shared.state.round_params.mask_config,
+                        shared.state.round_params.mask_length(),
+                    ),
+                    shared.thread_pool.clone(),
                 ),
                 accepted: 0,
                 rejected: 0,
@@ -194,6 +207,29 @@ where
                 RequestError::AggregationFailed
             })?;
 
+        // If the mask seeds are secret-shared, every sum participant must get a mask seed and the
+        // seed shares of the mask seeds of all the others, otherwise the masked model couldn't be
+        // unmasked if sum participants drop out. The coordinator checks that the keys of the
+        // local seed dict match the sum dict when adding it.
+        let seeds_per_participant = if self
+            .shared
+            .state
+            .round_params
+            .seed_sharing_threshold
+            .is_some()
+        {
+            local_seed_dict.len()
+        } else {
+            1
+        };
+        if local_seed_dict
+            .values()
+            .any(|seeds| seeds.count() != seeds_per_participant)
+        {
+            warn!("incomplete local seed dictionary, ignoring update message");
+            return Err(RequestError::MessageRejected);
+        }
+
         // Try to update local seed dict first. If this fail, we do
         // not want to aggregate the model.
         info!("updating the global seed dictionary");
@@ -204,7 +240,7 @@ where
                 err
             })?;
 
-        info!("aggregating the masked model and scalar");
+        info!("aggregating the masked model and scalar in the background");
         self.private.model_agg.aggregate(mask_object);
         Ok(())
     }
@@ -253,7 +289,7 @@ mod tests {
     };
 
     impl Update {
-        pub fn aggregation(&self) -> &Aggregation {
+        pub fn aggregation(&self) -> &ShardedAggregation {
             &self.model_agg
         }
     }
@@ -270,6 +306,8 @@ mod tests {
             seed: RoundSeed::generate(),
             mask_config: utils::mask_config(),
             model_length,
+            seed_sharing_threshold: None,
+            model_delta: false,
         };
         let n_updaters = 1;
         let n_summers = 1;
@@ -290,7 +328,7 @@ mod tests {
         let (state_machine, request_tx, events) = StateMachineBuilder::new(store.clone())
             .with_seed(round_params.seed.clone())
             .with_phase(Update {
-                model_agg: aggregation.clone(),
+                model_agg: ShardedAggregation::new(aggregation, utils::thread_pool()),
                 accepted: 0,
                 rejected: 0,
                 discarded: 0,
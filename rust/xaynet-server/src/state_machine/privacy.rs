@@ -0,0 +1,232 @@
+//! Central differential privacy for the global models.
+//!
+//! If enabled in the [`DifferentialPrivacySettings`], the [`Unmask`] phase adds calibrated noise
+//! to the unmasked aggregated model before the aggregation strategy is applied. Every noisy
+//! global model spends a part of the privacy budget, which is tracked by the
+//! [`PrivacyAccountant`] of the [`CoordinatorState`] via sequential composition. The [`Idle`]
+//! phase refuses to start a new round once the remaining budget doesn't suffice for another
+//! round.
+//!
+//! [`Unmask`]: crate::state_machine::phases::Unmask
+//! [`Idle`]: crate::state_machine::phases::Idle
+//! [`CoordinatorState`]: crate::state_machine::coordinator::CoordinatorState
+
+use rand::Rng;
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::settings::{DifferentialPrivacySettings, NoiseMechanism};
+use xaynet_core::mask::{
+    sample_gaussian,
+    sample_laplace,
+    FromPrimitives,
+    IntoPrimitives,
+    Model,
+    ModelCastError,
+};
+
+/// Tolerance for rounding errors when comparing the accumulated budget with the total budget.
+const BUDGET_TOLERANCE: f64 = 1e-9;
+
+/// Error that occurs while adding noise to a model.
+#[derive(Debug, Error)]
+pub enum PrivacyError {
+    #[error("converting the model failed: {0}")]
+    ModelCast(#[from] ModelCastError),
+    #[error("the noisy model contains non-finite weights")]
+    NonFiniteWeights,
+}
+
+/// A privacy accountant which tracks the spent privacy budget across rounds.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivacyAccountant {
+    /// The spent privacy budget for `epsilon`.
+    pub spent_epsilon: f64,
+    /// The spent privacy budget for `delta`.
+    pub spent_delta: f64,
+}
+
+impl PrivacyAccountant {
+    /// Checks whether the remaining privacy budget suffices for another round.
+    pub fn can_spend(&self, settings: &DifferentialPrivacySettings) -> bool {
+        if !settings.enable {
+            return true;
+        }
+
+        let (epsilon, delta) = round_budget(settings);
+        self.spent_epsilon + epsilon <= settings.epsilon_budget + BUDGET_TOLERANCE
+            && self.spent_delta + delta <= settings.delta_budget + BUDGET_TOLERANCE
+    }
+
+    /// Spends the privacy budget of one round.
+    pub fn spend(&mut self, settings: &DifferentialPrivacySettings) {
+        if !settings.enable {
+            return;
+        }
+
+        let (epsilon, delta) = round_budget(settings);
+        self.spent_epsilon += epsilon;
+        self.spent_delta += delta;
+    }
+}
+
+/// Returns the privacy budget `(epsilon, delta)` which is spent per round.
+fn round_budget(settings: &DifferentialPrivacySettings) -> (f64, f64) {
+    match settings.mechanism {
+        NoiseMechanism::Gaussian => (settings.epsilon, settings.delta),
+        NoiseMechanism::Laplace => (settings.epsilon, 0.),
+    }
+}
+
+/// Returns the scale of the noise, i.e. the standard deviation for the Gaussian mechanism and the
+/// diversity for the Laplace mechanism.
+fn noise_scale(settings: &DifferentialPrivacySettings) -> f64 {
+    match settings.mechanism {
+        NoiseMechanism::Gaussian => {
+            settings.sensitivity * (2. * (1.25 / settings.delta).ln()).sqrt() / settings.epsilon
+        }
+        NoiseMechanism::Laplace => settings.sensitivity / settings.epsilon,
+    }
+}
+
+/// Adds noise to the model according to the settings.
+///
+/// The model is returned unchanged if differential privacy is disabled.
+///
+/// # Errors
+/// Fails if the model can't be converted or if the noisy model isn't finite.
+pub fn add_noise(
+    settings: &DifferentialPrivacySettings,
+    model: Model,
+) -> Result<Model, PrivacyError> {
+    if !settings.enable {
+        return Ok(model);
+    }
+    add_noise_with_rng(settings, model, &mut ChaCha20Rng::from_entropy())
+}
+
+fn add_noise_with_rng<R: Rng>(
+    settings: &DifferentialPrivacySettings,
+    model: Model,
+    rng: &mut R,
+) -> Result<Model, PrivacyError> {
+    let scale = noise_scale(settings);
+    let noisy_model = model
+        .to_primitives()
+        .map(|weight| {
+            let noise = match settings.mechanism {
+                NoiseMechanism::Gaussian => sample_gaussian(rng, scale),
+                NoiseMechanism::Laplace => sample_laplace(rng, scale),
+            };
+            weight.map(|weight: f64| weight + noise)
+        })
+        .collect::<Result<Vec<f64>, _>>()?;
+
+    Model::from_primitives(noisy_model.into_iter()).map_err(|_| PrivacyError::NonFiniteWeights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gaussian_settings() -> DifferentialPrivacySettings {
+        DifferentialPrivacySettings {
+            enable: true,
+            ..DifferentialPrivacySettings::default()
+        }
+    }
+
+    fn laplace_settings() -> DifferentialPrivacySettings {
+        DifferentialPrivacySettings {
+            enable: true,
+            mechanism: NoiseMechanism::Laplace,
+            epsilon: 1.,
+            ..DifferentialPrivacySettings::default()
+        }
+    }
+
+    // Returns the mean and the variance of the noise added to a zero model.
+    fn noise_moments(settings: &DifferentialPrivacySettings) -> (f64, f64) {
+        let len = 100_000;
+        let model = Model::from_primitives(vec![0_f64; len].into_iter()).unwrap();
+        let mut rng = ChaCha20Rng::from_seed([0; 32]);
+        let noise: Vec<f64> = add_noise_with_rng(settings, model, &mut rng)
+            .unwrap()
+            .into_primitives_unchecked()
+            .collect();
+
+        let mean = noise.iter().sum::<f64>() / len as f64;
+        let variance = noise.iter().map(|n| (n - mean).powi(2)).sum::<f64>() / len as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_disabled() {
+        let settings = DifferentialPrivacySettings::default();
+        let model = Model::from_primitives(vec![1_f64, 2., 3.].into_iter()).unwrap();
+        assert_eq!(add_noise(&settings, model.clone()).unwrap(), model);
+
+        let mut accountant = PrivacyAccountant::default();
+        accountant.spend(&settings);
+        assert_eq!(accountant, PrivacyAccountant::default());
+        assert!(accountant.can_spend(&settings));
+    }
+
+    #[test]
+    fn test_gaussian_noise() {
+        let settings = gaussian_settings();
+        let std_dev = (2. * (1.25 / settings.delta).ln()).sqrt() / settings.epsilon;
+        let (mean, variance) = noise_moments(&settings);
+        assert!(mean.abs() < 0.05 * std_dev);
+        assert!((variance / (std_dev * std_dev) - 1.).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_laplace_noise() {
+        let settings = laplace_settings();
+        let diversity = settings.sensitivity / settings.epsilon;
+        let (mean, variance) = noise_moments(&settings);
+        assert!(mean.abs() < 0.05 * diversity);
+        assert!((variance / (2. * diversity * diversity) - 1.).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_accountant_gaussian() {
+        let settings = DifferentialPrivacySettings {
+            epsilon: 0.5,
+            delta: 0.0001,
+            epsilon_budget: 10.,
+            delta_budget: 0.0003,
+            ..gaussian_settings()
+        };
+        let mut accountant = PrivacyAccountant::default();
+
+        // the delta budget is exhausted after three rounds
+        for _ in 0..3 {
+            assert!(accountant.can_spend(&settings));
+            accountant.spend(&settings);
+        }
+        assert!(!accountant.can_spend(&settings));
+        assert!((accountant.spent_epsilon - 1.5).abs() < BUDGET_TOLERANCE);
+        assert!((accountant.spent_delta - 0.0003).abs() < BUDGET_TOLERANCE);
+    }
+
+    #[test]
+    fn test_accountant_laplace() {
+        let settings = DifferentialPrivacySettings {
+            epsilon: 0.1,
+            epsilon_budget: 1.,
+            ..laplace_settings()
+        };
+        let mut accountant = PrivacyAccountant::default();
+
+        // ten rounds fit into the budget despite the rounding errors of the accumulated epsilon
+        for _ in 0..10 {
+            assert!(accountant.can_spend(&settings));
+            accountant.spend(&settings);
+        }
+        assert!(!accountant.can_spend(&settings));
+        assert_eq!(accountant.spent_delta, 0.);
+    }
+}
@@ -20,6 +20,7 @@ use xaynet_core::{
     message::{Message, Payload, Update},
     LocalSeedDict,
     ParticipantPublicKey,
+    SeedShareDict,
     SumParticipantEphemeralPublicKey,
     SumParticipantPublicKey,
     UpdateParticipantPublicKey,
@@ -59,6 +60,15 @@ pub struct Sum2Request {
     pub model_mask: MaskObject,
 }
 
+/// A sum2 request with secret-shared mask seeds.
+#[derive(Debug)]
+pub struct Sum2SharesRequest {
+    /// The public key of the participant.
+    pub participant_pk: ParticipantPublicKey,
+    /// The decrypted shares of the mask seeds of the update participants.
+    pub seed_shares: SeedShareDict,
+}
+
 /// A [`StateMachine`] request.
 ///
 /// [`StateMachine`]: crate::state_machine
@@ -67,6 +77,7 @@ pub enum StateMachineRequest {
     Sum(SumRequest),
     Update(UpdateRequest),
     Sum2(Sum2Request),
+    Sum2Shares(Sum2SharesRequest),
 }
 
 impl From<Message> for StateMachineRequest {
@@ -93,6 +104,12 @@ impl From<Message> for StateMachineRequest {
                 participant_pk,
                 model_mask: sum2.model_mask,
             }),
+            Payload::Sum2Shares(sum2_shares) => {
+                StateMachineRequest::Sum2Shares(Sum2SharesRequest {
+                    participant_pk,
+                    seed_shares: sum2_shares.seed_shares,
+                })
+            }
             Payload::Chunk(_) => unimplemented!(),
         }
     }
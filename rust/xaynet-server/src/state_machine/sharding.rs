@@ -0,0 +1,227 @@
+//! Parallel aggregation of masked models.
+//!
+//! The [`Update`] phase aggregates the masked models with a [`ShardedAggregation`], which splits
+//! the aggregated vector into consecutive ranges, one per thread of the `rayon` thread-pool that
+//! is shared with the message processing services. Each accepted masked model is split accordingly
+//! and its ranges are aggregated into the shards in parallel on the thread-pool, while the state
+//! machine continues to process requests. The shards are merged into a single [`Aggregation`] at
+//! the end of the phase.
+//!
+//! [`Update`]: crate::state_machine::phases::Update
+
+use std::{
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use rayon::{prelude::*, ThreadPool};
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+use xaynet_core::mask::{Aggregation, AggregationError, MaskConfigPair, MaskObject};
+
+/// Error that occurs if a masked model could not be aggregated on the thread-pool.
+#[derive(Debug, Error)]
+#[error("aggregating a masked model on the thread-pool failed")]
+pub struct ShardedAggregationError;
+
+/// An aggregator for masked models which aggregates the ranges of the models in parallel.
+#[derive(Debug)]
+pub struct ShardedAggregation {
+    /// The masking configurations of the aggregated models.
+    config: MaskConfigPair,
+    /// The length of the aggregated models.
+    object_size: usize,
+    /// The number of accepted models, including the ones which are still being aggregated.
+    nb_models: usize,
+    /// The lengths of the ranges of the shards.
+    lengths: Vec<usize>,
+    /// The shards of the aggregator.
+    shards: Arc<Vec<Mutex<Aggregation>>>,
+    /// The receivers which are notified once the aggregation of a model has finished.
+    pending: Vec<oneshot::Receiver<()>>,
+    /// The thread-pool for the aggregation.
+    thread_pool: Arc<ThreadPool>,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl ShardedAggregation {
+    /// Creates a new parallel aggregator from an `aggregation` with one shard per thread of the
+    /// `thread_pool`.
+    pub fn new(aggregation: Aggregation, thread_pool: Arc<ThreadPool>) -> Self {
+        let config = aggregation.config();
+        let object_size = aggregation.len();
+        let nb_models = aggregation.nb_models();
+        let shards = aggregation.into_shards(thread_pool.current_num_threads());
+        let lengths = shards.iter().map(Aggregation::len).collect();
+        let shards = Arc::new(shards.into_iter().map(Mutex::new).collect());
+        Self {
+            config,
+            object_size,
+            nb_models,
+            lengths,
+            shards,
+            pending: Vec::new(),
+            thread_pool,
+        }
+    }
+
+    /// Gets the length of the aggregated masked models.
+    pub fn len(&self) -> usize {
+        self.object_size
+    }
+
+    /// Gets the number of shards.
+    pub fn nb_shards(&self) -> usize {
+        self.lengths.len()
+    }
+
+    /// Validates if aggregation of the given masked model may be safely performed.
+    ///
+    /// This performs the same checks as [`Aggregation::validate_aggregation()`] wrt all accepted
+    /// models, including the ones which are still being aggregated.
+    pub fn validate_aggregation(&self, object: &MaskObject) -> Result<(), AggregationError> {
+        if self.config.vect != object.vect.config {
+            return Err(AggregationError::ModelMismatch);
+        }
+
+        if self.config.unit != object.unit.config {
+            return Err(AggregationError::ScalarMismatch);
+        }
+
+        if self.object_size != object.vect.data.len() {
+            return Err(AggregationError::ModelMismatch);
+        }
+
+        if self.nb_models >= self.config.vect.model_type.max_nb_models() {
+            return Err(AggregationError::TooManyModels);
+        }
+
+        if self.nb_models >= self.config.unit.model_type.max_nb_models() {
+            return Err(AggregationError::TooManyScalars);
+        }
+
+        if !object.is_valid() {
+            return Err(AggregationError::InvalidObject);
+        }
+
+        Ok(())
+    }
+
+    /// Aggregates the given masked model in the background.
+    ///
+    /// It should be checked that [`validate_aggregation()`] succeeds before calling this, since
+    /// aggregation may return garbage values otherwise. If the aggregation panics, the aggregator
+    /// fails to [`flush()`].
+    ///
+    /// [`validate_aggregation()`]: #method.validate_aggregation
+    /// [`flush()`]: #method.flush
+    pub fn aggregate(&mut self, object: MaskObject) {
+        let objects = object.split(&self.lengths);
+        let shards = self.shards.clone();
+        let (tx, rx) = oneshot::channel();
+        self.thread_pool.spawn(move || {
+            // a panic would abort the coordinator, instead the sender is dropped and flushing
+            // the aggregator fails
+            let aggregated = panic::catch_unwind(AssertUnwindSafe(|| {
+                shards.par_iter().zip(objects).for_each(|(shard, object)| {
+                    shard
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .aggregate(object)
+                })
+            }));
+            if aggregated.is_ok() {
+                let _ = tx.send(());
+            }
+        });
+        self.pending.push(rx);
+        self.nb_models += 1;
+    }
+
+    /// Waits until all accepted masked models are aggregated.
+    ///
+    /// # Errors
+    /// Fails if the aggregation of a masked model was aborted.
+    pub async fn flush(&mut self) -> Result<(), ShardedAggregationError> {
+        for rx in self.pending.drain(..) {
+            rx.await.map_err(|_| ShardedAggregationError)?;
+        }
+        Ok(())
+    }
+
+    /// Merges the shards into a single aggregator.
+    ///
+    /// The aggregator should be [`flush()`]ed before calling this, since the aggregation of
+    /// masked models which is still in progress may be lost otherwise.
+    ///
+    /// [`flush()`]: #method.flush
+    pub fn into_aggregation(self) -> Aggregation {
+        let config = self.config;
+        let shards = self
+            .shards
+            .iter()
+            .map(|shard| {
+                let mut shard = shard.lock().unwrap_or_else(PoisonError::into_inner);
+                mem::replace(&mut *shard, Aggregation::new(config, 0))
+            })
+            .collect();
+        // UNWRAP_SAFE: an aggregator has at least one shard
+        Aggregation::merge(shards).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::ThreadPoolBuilder;
+
+    use super::*;
+    use crate::state_machine::tests::utils;
+    use xaynet_core::{
+        crypto::ByteObject,
+        mask::{FromPrimitives, MaskSeed, Masker, Model},
+    };
+
+    fn masked_model(weight: i32) -> MaskObject {
+        let model = Model::from_primitives(vec![weight; 10].into_iter()).unwrap();
+        Masker::with_seed(utils::mask_config(), MaskSeed::generate())
+            .mask(1.0, &model)
+            .1
+    }
+
+    #[tokio::test]
+    async fn test_sharded_aggregation() {
+        let thread_pool = Arc::new(ThreadPoolBuilder::new().num_threads(4).build().unwrap());
+        let mut aggregation = Aggregation::new(utils::mask_config(), 10);
+        let mut sharded = ShardedAggregation::new(aggregation.clone(), thread_pool);
+        assert_eq!(sharded.nb_shards(), 4);
+
+        for weight in 0..5 {
+            let object = masked_model(weight);
+            assert!(aggregation.validate_aggregation(&object).is_ok());
+            assert!(sharded.validate_aggregation(&object).is_ok());
+            aggregation.aggregate(object.clone());
+            sharded.aggregate(object);
+        }
+        sharded.flush().await.unwrap();
+
+        let merged = sharded.into_aggregation();
+        assert_eq!(merged.nb_models(), 5);
+        assert_eq!(
+            <Aggregation as Into<MaskObject>>::into(merged),
+            aggregation.into(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_sharded_aggregation() {
+        let thread_pool = Arc::new(ThreadPoolBuilder::new().num_threads(2).build().unwrap());
+        let sharded =
+            ShardedAggregation::new(Aggregation::new(utils::mask_config(), 5), thread_pool);
+        assert!(matches!(
+            sharded.validate_aggregation(&masked_model(0)),
+            Err(AggregationError::ModelMismatch),
+        ));
+    }
+}
@@ -66,6 +66,8 @@ where
         events.broadcast_sum_dict(sum_dict);
         let seed_dict = event_subscriber.seed_dict_listener().get_latest().event;
         events.broadcast_seed_dict(seed_dict);
+        let dropout_dict = event_subscriber.dropout_dict_listener().get_latest().event;
+        events.broadcast_dropout_dict(dropout_dict);
 
         let state = PhaseState {
             private: phase_state,
@@ -133,6 +135,11 @@ where
         self
     }
 
+    pub fn with_seed_sharing_threshold(mut self, threshold: Option<f64>) -> Self {
+        self.coordinator_state.round_params.seed_sharing_threshold = threshold;
+        self
+    }
+
     pub fn with_min_sum_time(mut self, in_secs: u64) -> Self {
         self.coordinator_state.min_sum_time = in_secs;
         self
@@ -1,23 +1,34 @@
 use serial_test::serial;
 
-use super::utils::{mask_settings, model_settings, pet_settings};
+use super::utils::{
+    aggregation_settings,
+    mask_settings,
+    model_settings,
+    pet_settings,
+    privacy_settings,
+    thread_pool,
+};
 #[cfg(feature = "model-persistence")]
+use crate::state_machine::initializer::StateMachineInitializationError;
 use crate::{
     settings::RestoreSettings,
     state_machine::{
+        aggregation::AggregationState,
+        coordinator::CoordinatorState,
         events::{DictionaryUpdate, ModelUpdate},
-        initializer::StateMachineInitializationError,
         phases::PhaseName,
+        StateMachineInitializer,
+    },
+    storage::{
+        coordinator_storage::in_memory::InMemory,
+        model_storage::file::FileStorage,
+        tests::{init_store, utils::create_global_model},
+        CoordinatorStorage,
+        ModelStorage,
+        Store,
     },
-    storage::tests::utils::create_global_model,
-    storage::ModelStorage,
-};
-use crate::{
-    state_machine::{coordinator::CoordinatorState, StateMachineInitializer},
-    storage::{tests::init_store, CoordinatorStorage},
 };
 
-#[cfg(feature = "model-persistence")]
 #[tokio::test]
 #[serial]
 async fn integration_state_machine_initializer_no_restore() {
@@ -26,8 +37,11 @@ async fn integration_state_machine_initializer_no_restore() {
         pet_settings(),
         mask_settings(),
         model_settings(),
+        aggregation_settings(),
+        privacy_settings(),
         RestoreSettings { enable: false },
         store,
+        thread_pool(),
     );
 
     let (state_machine, _request_sender, event_subscriber) = smi.init().await.unwrap();
@@ -50,7 +64,6 @@ async fn integration_state_machine_initializer_no_restore() {
     assert_eq!(round_id, 0);
 }
 
-#[cfg(feature = "model-persistence")]
 #[tokio::test]
 #[serial]
 async fn integration_state_machine_initializer_no_state() {
@@ -59,8 +72,11 @@ async fn integration_state_machine_initializer_no_state() {
         pet_settings(),
         mask_settings(),
         model_settings(),
+        aggregation_settings(),
+        privacy_settings(),
         RestoreSettings { enable: true },
         store,
+        thread_pool(),
     );
 
     let (state_machine, _request_sender, event_subscriber) = smi.init().await.unwrap();
@@ -83,7 +99,6 @@ async fn integration_state_machine_initializer_no_state() {
     assert_eq!(round_id, 0);
 }
 
-#[cfg(feature = "model-persistence")]
 #[tokio::test]
 #[serial]
 async fn integration_state_machine_initializer_without_global_model() {
@@ -96,7 +111,13 @@ async fn integration_state_machine_initializer_without_global_model() {
     // if we don't update the round_id we can't check if the state in the store was used or if the state was reset
     // because in both cases the round id will be 0
     let mut store = init_store().await;
-    let mut state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let mut state = CoordinatorState::new(
+        pet_settings,
+        mask_settings,
+        model_settings.clone(),
+        aggregation_settings(),
+        privacy_settings(),
+    );
     let new_round_id = 5;
     state.round_id = new_round_id;
     store.set_coordinator_state(&state).await.unwrap();
@@ -105,8 +126,11 @@ async fn integration_state_machine_initializer_without_global_model() {
         pet_settings,
         mask_settings,
         model_settings,
+        aggregation_settings(),
+        privacy_settings(),
         RestoreSettings { enable: true },
         store,
+        thread_pool(),
     );
 
     let (state_machine, _request_sender, event_subscriber) = smi.init().await.unwrap();
@@ -138,7 +162,13 @@ async fn integration_state_machine_initializer_with_global_model() {
     let model_settings = model_settings();
 
     let mut store = init_store().await;
-    let mut state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let mut state = CoordinatorState::new(
+        pet_settings,
+        mask_settings,
+        model_settings.clone(),
+        aggregation_settings(),
+        privacy_settings(),
+    );
     let new_round_id = 7;
     state.round_id = new_round_id;
     store.set_coordinator_state(&state).await.unwrap();
@@ -162,8 +192,11 @@ async fn integration_state_machine_initializer_with_global_model() {
         pet_settings,
         mask_settings,
         model_settings,
+        aggregation_settings(),
+        privacy_settings(),
         RestoreSettings { enable: true },
         store,
+        thread_pool(),
     );
 
     let (state_machine, _request_sender, event_subscriber) = smi.init().await.unwrap();
@@ -181,7 +214,7 @@ async fn integration_state_machine_initializer_with_global_model() {
 
     let global_model = event_subscriber.model_listener().get_latest().event;
     assert!(
-        matches!(global_model, ModelUpdate::New(broadcasted_model) if uploaded_global_model == *broadcasted_model)
+        matches!(global_model, ModelUpdate::New { model: broadcasted_model, .. } if uploaded_global_model == *broadcasted_model)
     );
 
     let round_id = event_subscriber.params_listener().get_latest().round_id;
@@ -197,7 +230,13 @@ async fn integration_state_machine_initializer_failed_because_of_wrong_size() {
     let model_settings = model_settings();
 
     let mut store = init_store().await;
-    let mut state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let mut state = CoordinatorState::new(
+        pet_settings,
+        mask_settings,
+        model_settings.clone(),
+        aggregation_settings(),
+        privacy_settings(),
+    );
     let new_round_id = 9;
     state.round_id = new_round_id;
     store.set_coordinator_state(&state).await.unwrap();
@@ -221,8 +260,11 @@ async fn integration_state_machine_initializer_failed_because_of_wrong_size() {
         pet_settings,
         mask_settings,
         model_settings,
+        aggregation_settings(),
+        privacy_settings(),
         RestoreSettings { enable: true },
         store,
+        thread_pool(),
     );
 
     let result = smi.init().await;
@@ -242,7 +284,13 @@ async fn integration_state_machine_initializer_failed_to_find_global_model() {
     let model_settings = model_settings();
 
     let mut store = init_store().await;
-    let mut state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let mut state = CoordinatorState::new(
+        pet_settings,
+        mask_settings,
+        model_settings.clone(),
+        aggregation_settings(),
+        privacy_settings(),
+    );
     let new_round_id = 11;
     state.round_id = new_round_id;
     store.set_coordinator_state(&state).await.unwrap();
@@ -258,8 +306,11 @@ async fn integration_state_machine_initializer_failed_to_find_global_model() {
         pet_settings,
         mask_settings,
         model_settings,
+        aggregation_settings(),
+        privacy_settings(),
         RestoreSettings { enable: true },
         store,
+        thread_pool(),
     );
 
     let result = smi.init().await;
@@ -278,16 +329,24 @@ async fn integration_state_machine_initializer_reset_state() {
     let model_settings = model_settings();
 
     let mut store = init_store().await;
-    let state = CoordinatorState::new(pet_settings, mask_settings, model_settings.clone());
+    let state = CoordinatorState::new(
+        pet_settings,
+        mask_settings,
+        model_settings.clone(),
+        aggregation_settings(),
+        privacy_settings(),
+    );
     store.set_coordinator_state(&state).await.unwrap();
 
     let mut smi = StateMachineInitializer::new(
         pet_settings,
         mask_settings,
         model_settings,
-        #[cfg(feature = "model-persistence")]
+        aggregation_settings(),
+        privacy_settings(),
         RestoreSettings { enable: true },
         store.clone(),
+        thread_pool(),
     );
 
     smi.from_settings().await.unwrap();
@@ -299,3 +358,127 @@ async fn integration_state_machine_initializer_reset_state() {
     assert!(store.latest_global_model_id().await.unwrap().is_none());
     assert_eq!(store.number_of_unique_masks().await.unwrap(), 0);
 }
+
+#[tokio::test]
+async fn test_state_machine_initializer_with_file_model_storage() {
+    let pet_settings = pet_settings();
+    let mask_settings = mask_settings();
+    let model_settings = model_settings();
+
+    let dir = tempfile::tempdir().unwrap();
+    let model_store = FileStorage::new(dir.path(), None).await.unwrap();
+    let mut store = Store::new(InMemory::new(), model_store);
+    let mut state = CoordinatorState::new(
+        pet_settings,
+        mask_settings,
+        model_settings.clone(),
+        aggregation_settings(),
+        privacy_settings(),
+    );
+    let new_round_id = 13;
+    state.round_id = new_round_id;
+    store.set_coordinator_state(&state).await.unwrap();
+
+    // store a global model and set the id
+    let stored_global_model = create_global_model(state.round_params.model_length);
+    let global_model_id = store
+        .set_global_model(
+            state.round_id,
+            &state.round_params.seed,
+            &stored_global_model,
+        )
+        .await
+        .unwrap();
+    store
+        .set_latest_global_model_id(&global_model_id)
+        .await
+        .unwrap();
+
+    let smi = StateMachineInitializer::new(
+        pet_settings,
+        mask_settings,
+        model_settings,
+        aggregation_settings(),
+        privacy_settings(),
+        RestoreSettings { enable: true },
+        store,
+        thread_pool(),
+    );
+
+    let (state_machine, _request_sender, event_subscriber) = smi.init().await.unwrap();
+
+    assert!(state_machine.is_idle());
+
+    let global_model = event_subscriber.model_listener().get_latest().event;
+    assert!(
+        matches!(global_model, ModelUpdate::New { id, model: broadcasted_model } if id == global_model_id && stored_global_model == *broadcasted_model)
+    );
+
+    let round_id = event_subscriber.params_listener().get_latest().round_id;
+    assert_eq!(round_id, new_round_id);
+}
+
+#[tokio::test]
+async fn test_state_machine_initializer_restores_aggregation_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let model_store = FileStorage::new(dir.path(), None).await.unwrap();
+    let mut store = Store::new(InMemory::new(), model_store);
+    let state = CoordinatorState::new(
+        pet_settings(),
+        mask_settings(),
+        model_settings(),
+        aggregation_settings(),
+        privacy_settings(),
+    );
+    store.set_coordinator_state(&state).await.unwrap();
+
+    let global_model = create_global_model(state.round_params.model_length);
+    let global_model_id = store
+        .set_global_model(1, &state.round_params.seed, &global_model)
+        .await
+        .unwrap();
+    store
+        .set_latest_global_model_id(&global_model_id)
+        .await
+        .unwrap();
+    let aggregation_state = AggregationState {
+        global_model_id: Some(global_model_id.clone()),
+        first_moment: Some(vec![0.; state.round_params.model_length]),
+        second_moment: Some(vec![1.; state.round_params.model_length]),
+    };
+    store
+        .set_aggregation_state(&aggregation_state)
+        .await
+        .unwrap();
+
+    let init = |store| {
+        StateMachineInitializer::new(
+            pet_settings(),
+            mask_settings(),
+            model_settings(),
+            aggregation_settings(),
+            privacy_settings(),
+            RestoreSettings { enable: true },
+            store,
+            thread_pool(),
+        )
+    };
+
+    // the aggregation state belongs to the latest global model
+    let (state_machine, _, _) = init(store.clone()).init().await.unwrap();
+    let restored = state_machine.into_idle_phase_state().shared.state;
+    assert_eq!(restored.aggregation, aggregation_state);
+
+    // the aggregation state belongs to an older global model
+    let newer_global_model_id = store
+        .set_global_model(2, &state.round_params.seed, &global_model)
+        .await
+        .unwrap();
+    store
+        .set_latest_global_model_id(&newer_global_model_id)
+        .await
+        .unwrap();
+    let (state_machine, _, _) = init(store).init().await.unwrap();
+    let restored = state_machine.into_idle_phase_state().shared.state;
+    assert_eq!(restored.aggregation, AggregationState::default());
+}
@@ -4,22 +4,25 @@ pub mod initializer;
 pub mod utils;
 
 use serial_test::serial;
+use tokio::time::{delay_for, Duration};
 
 use crate::{
     state_machine::{
-        events::Event,
+        events::{DictionaryUpdate, Event},
         phases::PhaseName,
         tests::{
             builder::StateMachineBuilder,
             utils::{enable_logging, generate_summer, generate_updater, mask_config, Participant},
         },
+        RequestError,
     },
     storage::{tests::init_store, CoordinatorStorage},
 };
 use xaynet_core::{
     common::{RoundParameters, RoundSeed},
     crypto::{ByteObject, EncryptKeyPair},
-    mask::{FromPrimitives, Model},
+    mask::{share_indices, FromPrimitives, Model},
+    SeedShareDict,
 };
 
 #[tokio::test]
@@ -34,6 +37,8 @@ async fn integration_full_round() {
         seed: RoundSeed::generate(),
         mask_config: mask_config(),
         model_length,
+        seed_sharing_threshold: None,
+        model_delta: false,
     };
     let n_updaters = 3;
     let n_summers = 2;
@@ -116,12 +121,15 @@ async fn integration_full_round() {
         let global_model_id = store.latest_global_model_id().await.unwrap().unwrap();
         let store_model = store.global_model(&global_model_id).await.unwrap().unwrap();
         assert!(
-            matches!(events.model_listener().get_latest().event, super::events::ModelUpdate::New(broadcasted_model) if store_model == *broadcasted_model)
+            matches!(events.model_listener().get_latest().event, super::events::ModelUpdate::New { model: broadcasted_model, .. } if store_model == *broadcasted_model)
         );
 
         let get_global_model_id = store.latest_global_model_id().await.unwrap().unwrap();
         assert_eq!(global_model_id, get_global_model_id);
     }
+    // the no-op model storage doesn't keep the global model, so there is no model to restore
+    #[cfg(not(feature = "model-persistence"))]
+    assert!(store.latest_global_model_id().await.unwrap().is_none());
 
     assert!(state_machine.is_idle());
 
@@ -153,3 +161,124 @@ async fn integration_full_round() {
     assert!(state_machine.is_shutdown());
     assert!(state_machine.next().await.is_none())
 }
+
+#[tokio::test]
+#[serial]
+async fn integration_full_round_with_seed_sharing() {
+    enable_logging();
+    let model_length = 4;
+    let round_params = RoundParameters {
+        pk: EncryptKeyPair::generate().public,
+        sum: 0.5,
+        update: 1.0,
+        seed: RoundSeed::generate(),
+        mask_config: mask_config(),
+        model_length,
+        seed_sharing_threshold: Some(0.5),
+        model_delta: false,
+    };
+    let n_updaters = 3;
+    let n_summers = 3;
+
+    let store = init_store().await;
+    let (state_machine, requests, events) = StateMachineBuilder::new(store.clone())
+        .with_round_id(42)
+        .with_seed(round_params.seed.clone())
+        .with_seed_sharing_threshold(round_params.seed_sharing_threshold)
+        .with_sum_ratio(round_params.sum)
+        .with_update_ratio(round_params.update)
+        .with_min_sum_count(n_summers)
+        .with_max_sum_count(n_summers + 10)
+        .with_min_update_count(n_updaters)
+        .with_max_update_count(n_updaters + 10)
+        .with_min_sum_time(1)
+        .with_max_sum_time(2)
+        .with_min_update_time(1)
+        .with_max_update_time(2)
+        .with_model_length(model_length)
+        .build();
+
+    // Idle phase
+    let state_machine = state_machine.next().await.unwrap();
+    assert!(state_machine.is_sum());
+
+    // Sum phase
+    let summers = (0..n_summers)
+        .map(|_| generate_summer(round_params.clone()))
+        .collect::<Vec<_>>();
+    let transition_task = tokio::spawn(async { state_machine.next().await.unwrap() });
+    for summer in summers.iter() {
+        requests.msg(&summer.compose_sum_message()).await.unwrap();
+    }
+    let state_machine = transition_task.await.unwrap();
+    assert!(state_machine.is_update());
+
+    // Update phase
+    let transition_task = tokio::spawn(async { state_machine.next().await.unwrap() });
+    let sum_dict = events.sum_dict_listener().get_latest().event.unwrap();
+    let scalar = 1.0 / (n_updaters as f64 * round_params.update);
+    let model = Model::from_primitives(vec![0; model_length].into_iter()).unwrap();
+    // an update without the mask seeds for every sum participant is rejected
+    let updater = generate_updater(round_params.clone());
+    let (mask_seed, masked_model) = updater.compute_masked_model(&model, scalar);
+    let local_seed_dict = Participant::build_seed_dict(&sum_dict, &mask_seed);
+    let msg = updater.compose_update_message(masked_model, local_seed_dict);
+    assert!(matches!(
+        requests.msg(&msg).await,
+        Err(RequestError::MessageRejected)
+    ));
+    for _ in 0..n_updaters {
+        let updater = generate_updater(round_params.clone());
+        let (mask_seed, masked_model) = updater.compute_masked_model(&model, scalar);
+        let (masked_model, local_seed_dict) =
+            updater.build_shared_seed_dict(&sum_dict, mask_seed, masked_model, 0.5);
+        let msg = updater.compose_update_message(masked_model, local_seed_dict);
+        requests.msg(&msg).await.unwrap();
+    }
+    let state_machine = transition_task.await.unwrap();
+    assert!(state_machine.is_sum2());
+
+    // Sum2 phase: two out of three sum participants suffice, the third one drops out
+    let seed_dict = events.seed_dict_listener().get_latest().event.unwrap();
+    let indices = share_indices(&sum_dict).unwrap();
+    let seeds = summers
+        .iter()
+        .map(|summer| summer.decrypt_shared_seeds(&seed_dict[&summer.keys.public]))
+        .collect::<Vec<_>>();
+    let transition_task = tokio::spawn(async { state_machine.next().await.unwrap() });
+    for (summer, seeds) in summers.iter().zip(seeds.iter()).take(2) {
+        let own_seeds = Participant::pick_seeds(seeds, indices[&summer.keys.public]);
+        let own_seeds = own_seeds.values().cloned().collect::<Vec<_>>();
+        let mask = summer.aggregate_masks(model_length, &own_seeds);
+        let msg = summer.compose_sum2_message(mask.into());
+        requests.msg(&msg).await.unwrap();
+    }
+
+    // the remaining sum participants provide their shares of the dropout's mask seeds
+    let dropout_dict = loop {
+        if let DictionaryUpdate::New(dict) = events.dropout_dict_listener().get_latest().event {
+            break dict;
+        }
+        delay_for(Duration::from_millis(10)).await;
+    };
+    let dropout_pk = summers[2].keys.public;
+    assert_eq!(dropout_dict.keys().collect::<Vec<_>>(), vec![&dropout_pk]);
+    for (summer, seeds) in summers.iter().zip(seeds.iter()).take(2) {
+        let mut seed_shares = SeedShareDict::new();
+        seed_shares.insert(
+            dropout_pk,
+            Participant::pick_seeds(seeds, indices[&dropout_pk]),
+        );
+        let msg = summer.compose_sum2_shares_message(seed_shares);
+        requests.msg(&msg).await.unwrap();
+    }
+    let state_machine = transition_task.await.unwrap();
+    assert!(state_machine.is_unmask());
+
+    // Unmask phase
+    let state_machine = state_machine.next().await.unwrap();
+    assert!(state_machine.is_idle());
+    assert!(
+        matches!(events.model_listener().get_latest().event, super::events::ModelUpdate::New { model: global_model, .. } if *global_model == model)
+    );
+}
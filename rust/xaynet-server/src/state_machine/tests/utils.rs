@@ -1,7 +1,16 @@
+use std::{collections::HashMap, sync::Arc};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 use crate::{
-    settings::{MaskSettings, ModelSettings, PetSettings},
+    settings::{
+        AggregationSettings,
+        DifferentialPrivacySettings,
+        MaskSettings,
+        ModelSettings,
+        PetSettings,
+    },
     state_machine::{
         coordinator::CoordinatorState,
         events::{EventPublisher, EventSubscriber, ModelUpdate},
@@ -14,9 +23,13 @@ use xaynet_core::{
     common::RoundParameters,
     crypto::{ByteObject, EncryptKeyPair, Signature, SigningKeyPair},
     mask::{
+        share_indices,
+        share_mask_seed,
+        sharing_threshold,
         Aggregation,
         BoundType,
         DataType,
+        EncryptedMaskSeed,
         GroupType,
         MaskConfig,
         MaskConfigPair,
@@ -25,12 +38,17 @@ use xaynet_core::{
         Masker,
         Model,
         ModelType,
+        QuantizationType,
+        SparsityType,
     },
-    message::{Message, Payload, Sum, Sum2, Update},
+    message::{Message, Payload, Sum, Sum2, Sum2Shares, Update},
     LocalSeedDict,
+    SeedShareDict,
     SumDict,
     SumParticipantEphemeralPublicKey,
+    UpdateParticipantPublicKey,
     UpdateSeedDict,
+    UpdateSeedShareDict,
 };
 
 pub fn enable_logging() {
@@ -109,6 +127,33 @@ impl Participant {
             .collect()
     }
 
+    pub fn build_shared_seed_dict(
+        &self,
+        sum_dict: &SumDict,
+        mask_seed: MaskSeed,
+        masked_model: MaskObject,
+        fraction: f64,
+    ) -> (MaskObject, LocalSeedDict) {
+        let indices = share_indices(sum_dict).unwrap();
+        let threshold = sharing_threshold(fraction, sum_dict.len());
+        let seeds = share_mask_seed(mask_seed, threshold, sum_dict.len()).unwrap();
+        let len = masked_model.vect.data.len();
+        let mut masked_model = Aggregation::from(masked_model);
+        for (i, seeds) in seeds.iter().enumerate().skip(1) {
+            masked_model.aggregate(seeds[i].derive_mask(len, self.mask_settings));
+        }
+        let local_seed_dict = sum_dict
+            .iter()
+            .map(|(pk, ephm_pk)| {
+                let seeds = &seeds[indices[pk] as usize - 1];
+                let encrypted =
+                    EncryptedMaskSeed::concat(seeds.iter().map(|seed| seed.encrypt(&ephm_pk)));
+                (*pk, encrypted)
+            })
+            .collect();
+        (masked_model.into(), local_seed_dict)
+    }
+
     pub fn compose_update_message(
         &self,
         masked_model: MaskObject,
@@ -132,6 +177,29 @@ impl Participant {
             .collect()
     }
 
+    /// Decrypts the mask seeds and seed shares, which are ordered by the share indices.
+    pub fn decrypt_shared_seeds(
+        &self,
+        seed_dict: &UpdateSeedDict,
+    ) -> HashMap<UpdateParticipantPublicKey, Vec<MaskSeed>> {
+        let (pk, sk) = (self.ephm_keys.public, self.ephm_keys.secret.clone());
+        seed_dict
+            .iter()
+            .map(|(update_pk, seed)| (*update_pk, seed.decrypt_all(&pk, &sk).unwrap()))
+            .collect()
+    }
+
+    /// Picks the mask seeds of the sum participant with the given share index.
+    pub fn pick_seeds(
+        seeds: &HashMap<UpdateParticipantPublicKey, Vec<MaskSeed>>,
+        index: u8,
+    ) -> UpdateSeedShareDict {
+        seeds
+            .iter()
+            .map(|(update_pk, seeds)| (*update_pk, seeds[index as usize - 1].clone()))
+            .collect()
+    }
+
     pub fn aggregate_masks(&self, mask_length: usize, seeds: &[MaskSeed]) -> Aggregation {
         let mut aggregation = Aggregation::new(self.mask_settings, mask_length);
         for seed in seeds {
@@ -149,6 +217,14 @@ impl Participant {
         };
         Message::new_sum2(self.keys.public, self.round_params.pk, payload)
     }
+
+    pub fn compose_sum2_shares_message(&self, seed_shares: SeedShareDict) -> Message {
+        let payload = Sum2Shares {
+            sum_signature: self.sum_signature(),
+            seed_shares,
+        };
+        Message::new_sum2_shares(self.keys.public, self.round_params.pk, payload)
+    }
 }
 
 pub fn generate_summer(round_params: RoundParameters) -> Participant {
@@ -175,6 +251,8 @@ pub fn mask_settings() -> MaskSettings {
         data_type: DataType::F32,
         bound_type: BoundType::B0,
         model_type: ModelType::M3,
+        sparsity_type: SparsityType::S0,
+        quantization_type: QuantizationType::Qmax,
     }
 }
 
@@ -194,11 +272,23 @@ pub fn pet_settings() -> PetSettings {
         max_sum_time: 2,
         min_update_time: 1,
         max_update_time: 2,
+        seed_sharing_threshold: None,
     }
 }
 
 pub fn model_settings() -> ModelSettings {
-    ModelSettings { length: 1 }
+    ModelSettings {
+        length: 1,
+        delta: false,
+    }
+}
+
+pub fn aggregation_settings() -> AggregationSettings {
+    AggregationSettings::default()
+}
+
+pub fn privacy_settings() -> DifferentialPrivacySettings {
+    DifferentialPrivacySettings::default()
 }
 
 pub fn init_shared<C, M>(
@@ -219,14 +309,30 @@ where
 
     let (request_rx, request_tx) = RequestReceiver::new();
     (
-        Shared::new(coordinator_state, event_publisher, request_rx, store),
+        Shared::new(
+            coordinator_state,
+            event_publisher,
+            request_rx,
+            store,
+            thread_pool(),
+        ),
         request_tx,
         event_subscriber,
     )
 }
 
+pub fn thread_pool() -> Arc<ThreadPool> {
+    Arc::new(ThreadPoolBuilder::new().num_threads(2).build().unwrap())
+}
+
 pub fn coordinator_state() -> CoordinatorState {
-    CoordinatorState::new(pet_settings(), mask_settings(), model_settings())
+    CoordinatorState::new(
+        pet_settings(),
+        mask_settings(),
+        model_settings(),
+        aggregation_settings(),
+        privacy_settings(),
+    )
 }
 
 /// Extract the ephemeral public key from a sum message.
@@ -0,0 +1,718 @@
+//! A file-backed [`CoordinatorStorage`].
+//!
+//! The [`FileStorage`] keeps the coordinator data in memory, like the [in-memory storage], and
+//! persists it in two files on the local disk: a snapshot of the complete data and a log of the
+//! changes which have been applied since the snapshot was taken. Every change is appended to the
+//! log and synced to the disk before it is applied to the data in memory, so that the cost of a
+//! change doesn't grow with the amount of stored data. Once the log outgrows the snapshot, the
+//! log is compacted into a new snapshot, which is first written to a temporary file next to the
+//! storage file and then renamed, so that the storage file always contains a consistent state,
+//! even if the coordinator crashes while writing.
+//!
+//! When the coordinator restarts, the snapshot is loaded and the changes of the log are replayed,
+//! which allows the [`StateMachineInitializer`] to restore the state of the coordinator the same
+//! way it does with the [Redis storage]. A change which was only partially appended to the log
+//! when the coordinator crashed is discarded.
+//!
+//! [in-memory storage]: crate::storage::coordinator_storage::in_memory
+//! [Redis storage]: crate::storage::coordinator_storage::redis
+//! [`StateMachineInitializer`]: crate::state_machine::initializer::StateMachineInitializer
+
+use std::{
+    convert::TryInto,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::{sync::Mutex, task};
+use tracing::{debug, warn};
+
+use crate::{
+    state_machine::{aggregation::AggregationState, coordinator::CoordinatorState},
+    storage::{
+        coordinator_storage::in_memory::Data,
+        CoordinatorStorage,
+        LocalSeedDictAdd,
+        MaskScoreIncr,
+        StorageResult,
+        SumPartAdd,
+    },
+};
+use xaynet_core::{
+    mask::MaskObject,
+    LocalSeedDict,
+    SeedDict,
+    SumDict,
+    SumParticipantEphemeralPublicKey,
+    SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
+};
+
+/// The minimum size in bytes of the log before it is compacted into a new snapshot.
+const MIN_COMPACTION_SIZE: u64 = 1 << 20;
+
+/// The length in bytes of the generation header of the log and of the length prefix of a change.
+const HEADER_LEN: usize = 8;
+
+/// A change of the coordinator data, as it is recorded in the log.
+#[derive(Serialize, Deserialize)]
+enum Change {
+    SetCoordinatorState(Box<CoordinatorState>),
+    AddSumParticipant(SumParticipantPublicKey, SumParticipantEphemeralPublicKey),
+    AddLocalSeedDict(UpdateParticipantPublicKey, LocalSeedDict),
+    IncrMaskScore(SumParticipantPublicKey, MaskObject),
+    DeleteCoordinatorData,
+    DeleteDicts,
+    SetLatestGlobalModelId(String),
+    SetAggregationState(Box<AggregationState>),
+}
+
+impl Change {
+    /// Applies the change to the data.
+    fn apply(&self, data: &mut Data) {
+        match self {
+            Change::SetCoordinatorState(state) => {
+                data.coordinator_state = Some(state.as_ref().clone())
+            }
+            Change::AddSumParticipant(pk, ephm_pk) => {
+                data.add_sum_participant(pk, ephm_pk);
+            }
+            Change::AddLocalSeedDict(update_pk, local_seed_dict) => {
+                data.add_local_seed_dict(update_pk, local_seed_dict);
+            }
+            Change::IncrMaskScore(sum_pk, mask) => {
+                data.incr_mask_score(sum_pk, mask);
+            }
+            Change::DeleteCoordinatorData => data.delete_coordinator_data(),
+            Change::DeleteDicts => data.delete_dicts(),
+            Change::SetLatestGlobalModelId(id) => data.latest_global_model_id = Some(id.clone()),
+            Change::SetAggregationState(state) => {
+                data.aggregation_state = Some(state.as_ref().clone())
+            }
+        }
+    }
+}
+
+/// A snapshot of the coordinator data.
+///
+/// The generation of the snapshot is increased with every compaction. The log starts with the
+/// generation of the snapshot it belongs to, which allows to discard a stale log if the
+/// coordinator crashed after a new snapshot was written but before the log was reset.
+#[derive(Default, Deserialize)]
+struct Snapshot {
+    generation: u64,
+    data: Data,
+}
+
+/// The coordinator data and the state of the files.
+struct Inner {
+    data: Data,
+    /// The generation of the snapshot.
+    generation: u64,
+    /// The size of the snapshot file in bytes.
+    snapshot_len: u64,
+    /// The log file, which is created on the first change after opening the storage or after a
+    /// compaction.
+    log: Option<Arc<fs::File>>,
+    /// The size of the log file in bytes.
+    log_len: u64,
+}
+
+/// A coordinator storage that persists its data in a file.
+///
+/// Clones of a [`FileStorage`] share the same data.
+#[derive(Clone)]
+pub struct FileStorage {
+    path: Arc<PathBuf>,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl FileStorage {
+    /// Opens the storage file at the given path.
+    ///
+    /// The log of the storage file is kept next to it, with an additional `.log` extension. If
+    /// the files don't exist yet, the storage starts empty and the files are created on the
+    /// first change.
+    ///
+    /// # Errors
+    /// Fails if the files exist but cannot be read or don't contain valid coordinator data.
+    pub async fn open(path: impl AsRef<Path>) -> StorageResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let (path, inner) = task::spawn_blocking(move || -> StorageResult<_> {
+            let inner = load(&path)?;
+            Ok((path, inner))
+        })
+        .await??;
+
+        Ok(Self {
+            path: Arc::new(path),
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    /// Persists a change and applies it to the data.
+    ///
+    /// The `apply` function must have the same effect on the data as [`Change::apply()`], which
+    /// replays the change when the storage is opened again. The change is only applied to the
+    /// data once it has been appended to the log. If the write fails, the data remains unchanged.
+    async fn update<F, T>(&self, change: Change, apply: F) -> StorageResult<T>
+    where
+        F: FnOnce(&mut Data) -> T + Send,
+    {
+        let mut inner = self.inner.lock().await;
+
+        let mut record = vec![0; HEADER_LEN];
+        bincode::serialize_into(&mut record, &change)?;
+        let record_len = (record.len() - HEADER_LEN) as u64;
+        record[..HEADER_LEN].copy_from_slice(&record_len.to_le_bytes());
+
+        let path = self.path.clone();
+        let generation = inner.generation;
+        let log = inner.log.clone();
+        let log_len = inner.log_len;
+        let (log, log_len) = task::spawn_blocking(move || -> StorageResult<_> {
+            let (log, log_len) = match log {
+                Some(log) => (log, log_len),
+                None => create_log(&path, generation)?,
+            };
+            append(&log, log_len, &record)
+                .with_context(|| format!("failed to write storage log {}", path.display()))?;
+            Ok((log, log_len + record.len() as u64))
+        })
+        .await??;
+        inner.log = Some(log);
+        inner.log_len = log_len;
+
+        let output = apply(&mut inner.data);
+        if inner.log_len > inner.snapshot_len.max(MIN_COMPACTION_SIZE) {
+            // the change is already persisted in the log, hence a failed compaction is retried
+            // with the next change
+            if let Err(err) = self.compact(&mut inner).await {
+                warn!("failed to compact the coordinator storage: {:?}", err);
+            }
+        }
+        Ok(output)
+    }
+
+    /// Compacts the log into a new snapshot.
+    async fn compact(&self, inner: &mut Inner) -> StorageResult<()> {
+        debug!("compact coordinator storage log");
+        let generation = inner.generation + 1;
+        let snapshot = SnapshotRef {
+            generation,
+            data: &inner.data,
+        };
+        let bytes = bincode::serialize(&snapshot)?;
+
+        let path = self.path.clone();
+        let (snapshot_len, log, log_len) = task::spawn_blocking(move || -> StorageResult<_> {
+            write_atomically(&path, &bytes)
+                .with_context(|| format!("failed to write storage file {}", path.display()))?;
+            let (log, log_len) = create_log(&path, generation)?;
+            Ok((bytes.len() as u64, log, log_len))
+        })
+        .await??;
+
+        inner.generation = generation;
+        inner.snapshot_len = snapshot_len;
+        inner.log = Some(log);
+        inner.log_len = log_len;
+        Ok(())
+    }
+}
+
+/// A borrowed [`Snapshot`], which avoids cloning the data for a compaction.
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    generation: u64,
+    data: &'a Data,
+}
+
+/// Gets the path of the log of the storage file.
+fn log_path(path: &Path) -> PathBuf {
+    let mut log_path = path.as_os_str().to_owned();
+    log_path.push(".log");
+    PathBuf::from(log_path)
+}
+
+/// Loads the snapshot and replays the changes of the log.
+fn load(path: &Path) -> StorageResult<Inner> {
+    let (snapshot, snapshot_len) = match fs::read(path) {
+        Ok(bytes) => (
+            bincode::deserialize::<Snapshot>(&bytes)
+                .with_context(|| format!("invalid storage file {}", path.display()))?,
+            bytes.len() as u64,
+        ),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => (Snapshot::default(), 0),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read storage file {}", path.display()))
+        }
+    };
+    let mut inner = Inner {
+        data: snapshot.data,
+        generation: snapshot.generation,
+        snapshot_len,
+        log: None,
+        log_len: 0,
+    };
+
+    let log_path = log_path(path);
+    let bytes = match fs::read(&log_path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(inner),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read storage log {}", log_path.display()))
+        }
+    };
+    match read_u64(&bytes) {
+        Some(generation) if generation == inner.generation => {}
+        // the log is stale or its header is incomplete, hence it is replaced on the first change
+        _ => return Ok(inner),
+    }
+
+    let mut offset = HEADER_LEN;
+    while let Some(record_len) = read_u64(&bytes[offset..]) {
+        let start = offset + HEADER_LEN;
+        let end = match (record_len as usize).checked_add(start) {
+            Some(end) if end <= bytes.len() => end,
+            _ => break,
+        };
+        let change: Change = bincode::deserialize(&bytes[start..end])
+            .with_context(|| format!("invalid storage log {}", log_path.display()))?;
+        change.apply(&mut inner.data);
+        offset = end;
+    }
+
+    if offset < bytes.len() {
+        warn!(
+            "discarding {} bytes of an incomplete change at the end of the storage log {}",
+            bytes.len() - offset,
+            log_path.display(),
+        );
+    }
+    let log = fs::OpenOptions::new()
+        .write(true)
+        .open(&log_path)
+        .with_context(|| format!("failed to open storage log {}", log_path.display()))?;
+    log.set_len(offset as u64)?;
+    log.sync_all()?;
+    inner.log = Some(Arc::new(log));
+    inner.log_len = offset as u64;
+    Ok(inner)
+}
+
+/// Reads a little endian `u64` from the start of the bytes, if there are enough bytes.
+fn read_u64(bytes: &[u8]) -> Option<u64> {
+    bytes
+        .get(..HEADER_LEN)
+        // UNWRAP_SAFE: the slice has the length of an u64
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Creates an empty log for the given generation of the snapshot, replacing any previous log.
+fn create_log(path: &Path, generation: u64) -> StorageResult<(Arc<fs::File>, u64)> {
+    let log_path = log_path(path);
+    let mut log = fs::File::create(&log_path)
+        .with_context(|| format!("failed to create storage log {}", log_path.display()))?;
+    log.write_all(&generation.to_le_bytes())?;
+    log.sync_all()?;
+    sync_dir(path)?;
+    Ok((Arc::new(log), HEADER_LEN as u64))
+}
+
+/// Appends a record to the log and syncs it to the disk.
+///
+/// If the record cannot be written completely, the log is truncated to its previous length, so
+/// that following records are not appended to an incomplete one.
+fn append(mut log: &fs::File, log_len: u64, record: &[u8]) -> io::Result<()> {
+    let written = io::Seek::seek(&mut log, io::SeekFrom::Start(log_len))
+        .and_then(|_| log.write_all(record))
+        .and_then(|_| log.sync_data());
+    if written.is_err() {
+        let _ = log.set_len(log_len);
+    }
+    written
+}
+
+/// Writes the bytes to a temporary file and renames it to the given path afterwards.
+fn write_atomically(path: &Path, bytes: &[u8]) -> StorageResult<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp_path, path)?;
+    sync_dir(path)?;
+    Ok(())
+}
+
+/// Syncs the directory of the given path, which persists renamed and created files in it.
+#[cfg(unix)]
+pub(in crate::storage) fn sync_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir)?.sync_all()
+}
+
+/// Syncs the directory of the given path, which is not supported on this platform.
+#[cfg(not(unix))]
+pub(in crate::storage) fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[async_trait]
+impl CoordinatorStorage for FileStorage {
+    async fn set_coordinator_state(&mut self, state: &CoordinatorState) -> StorageResult<()> {
+        debug!("set coordinator state");
+        let change = Change::SetCoordinatorState(Box::new(state.clone()));
+        self.update(change, |data| data.coordinator_state = Some(state.clone()))
+            .await
+    }
+
+    async fn coordinator_state(&mut self) -> StorageResult<Option<CoordinatorState>> {
+        Ok(self.inner.lock().await.data.coordinator_state.clone())
+    }
+
+    async fn add_sum_participant(
+        &mut self,
+        pk: &SumParticipantPublicKey,
+        ephm_pk: &SumParticipantEphemeralPublicKey,
+    ) -> StorageResult<SumPartAdd> {
+        debug!("add sum participant with pk {:?}", pk);
+        let change = Change::AddSumParticipant(*pk, *ephm_pk);
+        self.update(change, |data| data.add_sum_participant(pk, ephm_pk))
+            .await
+    }
+
+    async fn sum_dict(&mut self) -> StorageResult<Option<SumDict>> {
+        debug!("get sum dictionary");
+        Ok(self.inner.lock().await.data.sum_dict())
+    }
+
+    async fn add_local_seed_dict(
+        &mut self,
+        update_pk: &UpdateParticipantPublicKey,
+        local_seed_dict: &LocalSeedDict,
+    ) -> StorageResult<LocalSeedDictAdd> {
+        debug!(
+            "update seed dictionary for update participant with pk {:?}",
+            update_pk
+        );
+        let change = Change::AddLocalSeedDict(*update_pk, local_seed_dict.clone());
+        self.update(change, |data| {
+            data.add_local_seed_dict(update_pk, local_seed_dict)
+        })
+        .await
+    }
+
+    async fn seed_dict(&mut self) -> StorageResult<Option<SeedDict>> {
+        debug!("get seed dictionary");
+        Ok(self.inner.lock().await.data.seed_dict())
+    }
+
+    async fn incr_mask_score(
+        &mut self,
+        sum_pk: &SumParticipantPublicKey,
+        mask: &MaskObject,
+    ) -> StorageResult<MaskScoreIncr> {
+        debug!("increment mask count");
+        let change = Change::IncrMaskScore(*sum_pk, mask.clone());
+        self.update(change, |data| data.incr_mask_score(sum_pk, mask))
+            .await
+    }
+
+    async fn best_masks(&mut self) -> StorageResult<Option<Vec<(MaskObject, u64)>>> {
+        debug!("get best masks");
+        Ok(self.inner.lock().await.data.best_masks())
+    }
+
+    async fn number_of_unique_masks(&mut self) -> StorageResult<u64> {
+        debug!("get number of unique masks");
+        Ok(self.inner.lock().await.data.number_of_unique_masks())
+    }
+
+    async fn delete_coordinator_data(&mut self) -> StorageResult<()> {
+        debug!("flush coordinator data");
+        self.update(Change::DeleteCoordinatorData, |data| {
+            data.delete_coordinator_data()
+        })
+        .await
+    }
+
+    async fn delete_dicts(&mut self) -> StorageResult<()> {
+        debug!("flush all dictionaries");
+        self.update(Change::DeleteDicts, |data| data.delete_dicts())
+            .await
+    }
+
+    async fn set_latest_global_model_id(&mut self, global_model_id: &str) -> StorageResult<()> {
+        debug!("set latest global model with id {}", global_model_id);
+        let change = Change::SetLatestGlobalModelId(global_model_id.to_string());
+        self.update(change, |data| {
+            data.latest_global_model_id = Some(global_model_id.to_string())
+        })
+        .await
+    }
+
+    async fn latest_global_model_id(&mut self) -> StorageResult<Option<String>> {
+        debug!("get latest global model id");
+        Ok(self.inner.lock().await.data.latest_global_model_id.clone())
+    }
+
+    async fn set_aggregation_state(&mut self, state: &AggregationState) -> StorageResult<()> {
+        debug!("set aggregation state");
+        let change = Change::SetAggregationState(Box::new(state.clone()));
+        self.update(change, |data| data.aggregation_state = Some(state.clone()))
+            .await
+    }
+
+    async fn aggregation_state(&mut self) -> StorageResult<Option<AggregationState>> {
+        debug!("get aggregation state");
+        Ok(self.inner.lock().await.data.aggregation_state.clone())
+    }
+
+    async fn is_ready(&mut self) -> StorageResult<()> {
+        let path = self.path.clone();
+        task::spawn_blocking(move || -> StorageResult<()> {
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            let metadata = fs::metadata(dir)
+                .with_context(|| format!("storage directory {} unavailable", dir.display()))?;
+            if metadata.permissions().readonly() {
+                anyhow::bail!("storage directory {} is read-only", dir.display());
+            }
+            Ok(())
+        })
+        .await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state_machine::tests::utils::{
+            aggregation_settings,
+            mask_settings,
+            model_settings,
+            pet_settings,
+            privacy_settings,
+        },
+        storage::tests::utils::*,
+    };
+    use tempfile::TempDir;
+
+    async fn open(dir: &TempDir) -> FileStorage {
+        FileStorage::open(dir.path().join("coordinator.db"))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_open_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = open(&dir).await;
+
+        assert!(storage.coordinator_state().await.unwrap().is_none());
+        assert!(storage.sum_dict().await.unwrap().is_none());
+        assert!(storage.latest_global_model_id().await.unwrap().is_none());
+        storage.is_ready().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_open_invalid_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("coordinator.db");
+        fs::write(&path, b"invalid").unwrap();
+
+        assert!(FileStorage::open(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_restore_after_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = open(&dir).await;
+
+        let state = CoordinatorState::new(
+            pet_settings(),
+            mask_settings(),
+            model_settings(),
+            aggregation_settings(),
+            privacy_settings(),
+        );
+        storage.set_coordinator_state(&state).await.unwrap();
+        let sum_pks = create_and_add_sum_participant_entries(&mut storage, 3).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+        let mask = create_mask_zeroed(10);
+        storage
+            .incr_mask_score(&sum_pks[0], &mask)
+            .await
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        storage.set_latest_global_model_id("id").await.unwrap();
+        let aggregation_state = AggregationState {
+            global_model_id: Some("id".to_string()),
+            first_moment: Some(vec![0.5, -1.]),
+            second_moment: Some(vec![0.25, 1.]),
+        };
+        storage
+            .set_aggregation_state(&aggregation_state)
+            .await
+            .unwrap();
+
+        let sum_dict = storage.sum_dict().await.unwrap();
+        let seed_dict = storage.seed_dict().await.unwrap();
+        drop(storage);
+
+        let mut restored = open(&dir).await;
+        assert_eq!(restored.coordinator_state().await.unwrap(), Some(state));
+        assert_eq!(restored.sum_dict().await.unwrap(), sum_dict);
+        assert_eq!(restored.seed_dict().await.unwrap(), seed_dict);
+        assert_eq!(
+            restored.best_masks().await.unwrap(),
+            Some(vec![(mask.clone(), 1)])
+        );
+        assert_eq!(
+            restored.latest_global_model_id().await.unwrap(),
+            Some("id".to_string())
+        );
+        assert_eq!(
+            restored.aggregation_state().await.unwrap(),
+            Some(aggregation_state)
+        );
+
+        // the restored storage still rejects resubmissions
+        let mask_already_submitted = restored.incr_mask_score(&sum_pks[0], &mask).await.unwrap();
+        assert!(mask_already_submitted.into_inner().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_delete_coordinator_data_is_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = open(&dir).await;
+
+        let state = CoordinatorState::new(
+            pet_settings(),
+            mask_settings(),
+            model_settings(),
+            aggregation_settings(),
+            privacy_settings(),
+        );
+        storage.set_coordinator_state(&state).await.unwrap();
+        storage
+            .set_aggregation_state(&AggregationState::default())
+            .await
+            .unwrap();
+        create_and_add_sum_participant_entries(&mut storage, 2).await;
+        storage.delete_coordinator_data().await.unwrap();
+        drop(storage);
+
+        let mut restored = open(&dir).await;
+        assert!(restored.coordinator_state().await.unwrap().is_none());
+        assert!(restored.aggregation_state().await.unwrap().is_none());
+        assert!(restored.sum_dict().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_restore_after_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("coordinator.db");
+        let mut storage = open(&dir).await;
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut storage, 2).await;
+        {
+            let mut inner = storage.inner.lock().await;
+            storage.compact(&mut inner).await.unwrap();
+            assert_eq!(inner.generation, 1);
+            assert_eq!(inner.log_len, HEADER_LEN as u64);
+        }
+        storage.set_latest_global_model_id("id").await.unwrap();
+        let sum_dict = storage.sum_dict().await.unwrap();
+        assert_eq!(
+            sum_dict.as_ref().map(|dict| dict.len()),
+            Some(sum_pks.len())
+        );
+        drop(storage);
+
+        let mut restored = open(&dir).await;
+        assert_eq!(restored.sum_dict().await.unwrap(), sum_dict);
+        assert_eq!(
+            restored.latest_global_model_id().await.unwrap(),
+            Some("id".to_string())
+        );
+
+        // a log of a previous generation is discarded
+        fs::write(log_path(&path), 0_u64.to_le_bytes()).unwrap();
+        let mut restored = open(&dir).await;
+        assert_eq!(restored.sum_dict().await.unwrap(), sum_dict);
+        assert!(restored.latest_global_model_id().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_discard_incomplete_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("coordinator.db");
+        let mut storage = open(&dir).await;
+        storage.set_latest_global_model_id("id").await.unwrap();
+        drop(storage);
+
+        let complete_len = fs::metadata(log_path(&path)).unwrap().len();
+        let mut log = fs::OpenOptions::new()
+            .append(true)
+            .open(log_path(&path))
+            .unwrap();
+        log.write_all(&[100, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3]).unwrap();
+        drop(log);
+
+        let mut restored = open(&dir).await;
+        assert_eq!(fs::metadata(log_path(&path)).unwrap().len(), complete_len);
+        assert_eq!(
+            restored.latest_global_model_id().await.unwrap(),
+            Some("id".to_string())
+        );
+
+        // changes are appended after the last complete change
+        restored.set_latest_global_model_id("id2").await.unwrap();
+        drop(restored);
+        let mut restored = open(&dir).await;
+        assert_eq!(
+            restored.latest_global_model_id().await.unwrap(),
+            Some("id2".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_keeps_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = FileStorage::open(dir.path().join("missing").join("coordinator.db"))
+            .await
+            .unwrap();
+
+        let state = CoordinatorState::new(
+            pet_settings(),
+            mask_settings(),
+            model_settings(),
+            aggregation_settings(),
+            privacy_settings(),
+        );
+        assert!(storage.set_coordinator_state(&state).await.is_err());
+        assert!(storage.coordinator_state().await.unwrap().is_none());
+        assert!(storage.is_ready().await.is_err());
+    }
+}
@@ -0,0 +1,551 @@
+//! An in-memory [`CoordinatorStorage`].
+//!
+//! The [`InMemory`] storage keeps all coordinator data in the memory of the coordinator process.
+//! It mirrors the behavior of the [Redis storage] but does not require a running Redis server,
+//! which makes it suitable for single-node deployments and tests. All data is lost when the
+//! coordinator shuts down, therefore a coordinator that uses this storage always starts from
+//! the settings.
+//!
+//! [Redis storage]: crate::storage::coordinator_storage::redis
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    state_machine::{aggregation::AggregationState, coordinator::CoordinatorState},
+    storage::{
+        CoordinatorStorage,
+        LocalSeedDictAdd,
+        LocalSeedDictAddError,
+        MaskScoreIncr,
+        MaskScoreIncrError,
+        StorageResult,
+        SumPartAdd,
+        SumPartAddError,
+    },
+};
+use xaynet_core::{
+    mask::MaskObject,
+    LocalSeedDict,
+    SeedDict,
+    SumDict,
+    SumParticipantEphemeralPublicKey,
+    SumParticipantPublicKey,
+    UpdateParticipantPublicKey,
+};
+
+/// The coordinator data held by the [`InMemory`] storage.
+///
+/// The operations on the data implement the semantics of the [`CoordinatorStorage`] methods, so
+/// that they can be shared with other storages which keep their data in memory.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Data {
+    pub(crate) coordinator_state: Option<CoordinatorState>,
+    sum_dict: SumDict,
+    update_participants: HashSet<UpdateParticipantPublicKey>,
+    seed_dict: SeedDict,
+    mask_submitted: HashSet<SumParticipantPublicKey>,
+    mask_dict: HashMap<MaskObject, u64>,
+    pub(crate) latest_global_model_id: Option<String>,
+    pub(crate) aggregation_state: Option<AggregationState>,
+}
+
+impl Data {
+    pub(crate) fn add_sum_participant(
+        &mut self,
+        pk: &SumParticipantPublicKey,
+        ephm_pk: &SumParticipantEphemeralPublicKey,
+    ) -> SumPartAdd {
+        if self.sum_dict.contains_key(pk) {
+            return SumPartAdd(Err(SumPartAddError::AlreadyExists));
+        }
+        self.sum_dict.insert(*pk, *ephm_pk);
+        SumPartAdd(Ok(()))
+    }
+
+    pub(crate) fn sum_dict(&self) -> Option<SumDict> {
+        if self.sum_dict.is_empty() {
+            return None;
+        }
+        Some(self.sum_dict.clone())
+    }
+
+    pub(crate) fn add_local_seed_dict(
+        &mut self,
+        update_pk: &UpdateParticipantPublicKey,
+        local_seed_dict: &LocalSeedDict,
+    ) -> LocalSeedDictAdd {
+        // the checks are performed in the same order as in the Redis storage, so that both
+        // storages return the same error for the same invalid local seed dict
+        if local_seed_dict.len() != self.sum_dict.len() {
+            return LocalSeedDictAdd(Err(LocalSeedDictAddError::LengthMisMatch));
+        }
+
+        if local_seed_dict
+            .keys()
+            .any(|sum_pk| !self.sum_dict.contains_key(sum_pk))
+        {
+            return LocalSeedDictAdd(Err(LocalSeedDictAddError::UnknownSumParticipant));
+        }
+
+        if !self.update_participants.insert(*update_pk) {
+            return LocalSeedDictAdd(Err(LocalSeedDictAddError::UpdatePkAlreadySubmitted));
+        }
+
+        if local_seed_dict.keys().any(|sum_pk| {
+            self.seed_dict
+                .get(sum_pk)
+                .map(|update_seed_dict| update_seed_dict.contains_key(update_pk))
+                .unwrap_or(false)
+        }) {
+            return LocalSeedDictAdd(Err(
+                LocalSeedDictAddError::UpdatePkAlreadyExistsInUpdateSeedDict,
+            ));
+        }
+
+        for (sum_pk, seed) in local_seed_dict {
+            self.seed_dict
+                .entry(*sum_pk)
+                .or_default()
+                .insert(*update_pk, seed.clone());
+        }
+        LocalSeedDictAdd(Ok(()))
+    }
+
+    pub(crate) fn seed_dict(&self) -> Option<SeedDict> {
+        if self.sum_dict.is_empty() {
+            return None;
+        }
+
+        // like in the Redis storage, every sum participant has an entry in the seed dict,
+        // even if no update participant has submitted a seed for it yet
+        let seed_dict = self
+            .sum_dict
+            .keys()
+            .map(|sum_pk| {
+                let update_seed_dict = self.seed_dict.get(sum_pk).cloned().unwrap_or_default();
+                (*sum_pk, update_seed_dict)
+            })
+            .collect();
+        Some(seed_dict)
+    }
+
+    pub(crate) fn incr_mask_score(
+        &mut self,
+        sum_pk: &SumParticipantPublicKey,
+        mask: &MaskObject,
+    ) -> MaskScoreIncr {
+        if !self.sum_dict.contains_key(sum_pk) {
+            return MaskScoreIncr(Err(MaskScoreIncrError::UnknownSumPk));
+        }
+
+        if !self.mask_submitted.insert(*sum_pk) {
+            return MaskScoreIncr(Err(MaskScoreIncrError::MaskAlreadySubmitted));
+        }
+
+        *self.mask_dict.entry(mask.clone()).or_insert(0) += 1;
+        MaskScoreIncr(Ok(()))
+    }
+
+    pub(crate) fn best_masks(&self) -> Option<Vec<(MaskObject, u64)>> {
+        if self.mask_dict.is_empty() {
+            return None;
+        }
+
+        let mut masks: Vec<(&MaskObject, u64)> = self
+            .mask_dict
+            .iter()
+            .map(|(mask, count)| (mask, *count))
+            .collect();
+        masks.sort_by(|(_, count_a), (_, count_b)| count_b.cmp(count_a));
+
+        let best_masks = masks
+            .into_iter()
+            .take(2)
+            .map(|(mask, count)| (mask.clone(), count))
+            .collect();
+        Some(best_masks)
+    }
+
+    pub(crate) fn number_of_unique_masks(&self) -> u64 {
+        self.mask_dict.len() as u64
+    }
+
+    pub(crate) fn delete_coordinator_data(&mut self) {
+        self.delete_dicts();
+        self.coordinator_state = None;
+        self.latest_global_model_id = None;
+        self.aggregation_state = None;
+    }
+
+    pub(crate) fn delete_dicts(&mut self) {
+        self.sum_dict.clear();
+        self.update_participants.clear();
+        self.seed_dict.clear();
+        self.mask_submitted.clear();
+        self.mask_dict.clear();
+    }
+}
+
+/// An in-memory coordinator storage.
+///
+/// Clones of an [`InMemory`] storage share the same data.
+#[derive(Clone, Default)]
+pub struct InMemory {
+    data: Arc<Mutex<Data>>,
+}
+
+impl InMemory {
+    /// Creates a new, empty in-memory storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CoordinatorStorage for InMemory {
+    async fn set_coordinator_state(&mut self, state: &CoordinatorState) -> StorageResult<()> {
+        debug!("set coordinator state");
+        self.data.lock().await.coordinator_state = Some(state.clone());
+        Ok(())
+    }
+
+    async fn coordinator_state(&mut self) -> StorageResult<Option<CoordinatorState>> {
+        Ok(self.data.lock().await.coordinator_state.clone())
+    }
+
+    async fn add_sum_participant(
+        &mut self,
+        pk: &SumParticipantPublicKey,
+        ephm_pk: &SumParticipantEphemeralPublicKey,
+    ) -> StorageResult<SumPartAdd> {
+        debug!("add sum participant with pk {:?}", pk);
+        Ok(self.data.lock().await.add_sum_participant(pk, ephm_pk))
+    }
+
+    async fn sum_dict(&mut self) -> StorageResult<Option<SumDict>> {
+        debug!("get sum dictionary");
+        Ok(self.data.lock().await.sum_dict())
+    }
+
+    async fn add_local_seed_dict(
+        &mut self,
+        update_pk: &UpdateParticipantPublicKey,
+        local_seed_dict: &LocalSeedDict,
+    ) -> StorageResult<LocalSeedDictAdd> {
+        debug!(
+            "update seed dictionary for update participant with pk {:?}",
+            update_pk
+        );
+        Ok(self
+            .data
+            .lock()
+            .await
+            .add_local_seed_dict(update_pk, local_seed_dict))
+    }
+
+    async fn seed_dict(&mut self) -> StorageResult<Option<SeedDict>> {
+        debug!("get seed dictionary");
+        Ok(self.data.lock().await.seed_dict())
+    }
+
+    async fn incr_mask_score(
+        &mut self,
+        sum_pk: &SumParticipantPublicKey,
+        mask: &MaskObject,
+    ) -> StorageResult<MaskScoreIncr> {
+        debug!("increment mask count");
+        Ok(self.data.lock().await.incr_mask_score(sum_pk, mask))
+    }
+
+    async fn best_masks(&mut self) -> StorageResult<Option<Vec<(MaskObject, u64)>>> {
+        debug!("get best masks");
+        Ok(self.data.lock().await.best_masks())
+    }
+
+    async fn number_of_unique_masks(&mut self) -> StorageResult<u64> {
+        debug!("get number of unique masks");
+        Ok(self.data.lock().await.number_of_unique_masks())
+    }
+
+    async fn delete_coordinator_data(&mut self) -> StorageResult<()> {
+        debug!("flush coordinator data");
+        self.data.lock().await.delete_coordinator_data();
+        Ok(())
+    }
+
+    async fn delete_dicts(&mut self) -> StorageResult<()> {
+        debug!("flush all dictionaries");
+        self.data.lock().await.delete_dicts();
+        Ok(())
+    }
+
+    async fn set_latest_global_model_id(&mut self, global_model_id: &str) -> StorageResult<()> {
+        debug!("set latest global model with id {}", global_model_id);
+        self.data.lock().await.latest_global_model_id = Some(global_model_id.to_string());
+        Ok(())
+    }
+
+    async fn latest_global_model_id(&mut self) -> StorageResult<Option<String>> {
+        debug!("get latest global model id");
+        Ok(self.data.lock().await.latest_global_model_id.clone())
+    }
+
+    async fn set_aggregation_state(&mut self, state: &AggregationState) -> StorageResult<()> {
+        debug!("set aggregation state");
+        self.data.lock().await.aggregation_state = Some(state.clone());
+        Ok(())
+    }
+
+    async fn aggregation_state(&mut self) -> StorageResult<Option<AggregationState>> {
+        debug!("get aggregation state");
+        Ok(self.data.lock().await.aggregation_state.clone())
+    }
+
+    async fn is_ready(&mut self) -> StorageResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state_machine::tests::utils::{
+            aggregation_settings,
+            mask_settings,
+            model_settings,
+            pet_settings,
+            privacy_settings,
+        },
+        storage::tests::utils::*,
+    };
+
+    #[tokio::test]
+    async fn test_set_and_get_coordinator_state() {
+        let mut storage = InMemory::new();
+
+        assert!(storage.coordinator_state().await.unwrap().is_none());
+
+        let set_state = CoordinatorState::new(
+            pet_settings(),
+            mask_settings(),
+            model_settings(),
+            aggregation_settings(),
+            privacy_settings(),
+        );
+        storage.set_coordinator_state(&set_state).await.unwrap();
+
+        let get_state = storage.coordinator_state().await.unwrap().unwrap();
+        assert_eq!(set_state, get_state)
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_data() {
+        let mut storage = InMemory::new();
+        let mut clone = storage.clone();
+
+        storage.set_latest_global_model_id("id").await.unwrap();
+        assert_eq!(
+            clone.latest_global_model_id().await.unwrap(),
+            Some("id".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sum_dict() {
+        let mut storage = InMemory::new();
+        assert!(storage.sum_dict().await.unwrap().is_none());
+
+        let (pk, epk) = create_sum_participant_entry();
+        let add_new_key = storage.add_sum_participant(&pk, &epk).await.unwrap();
+        assert!(add_new_key.is_ok());
+
+        let key_already_exist = storage.add_sum_participant(&pk, &epk).await.unwrap();
+        assert!(matches!(
+            key_already_exist.into_inner().unwrap_err(),
+            SumPartAddError::AlreadyExists
+        ));
+
+        let sum_dict = storage.sum_dict().await.unwrap().unwrap();
+        assert_eq!(sum_dict.len(), 1);
+        assert_eq!(sum_dict.get(&pk), Some(&epk));
+    }
+
+    #[tokio::test]
+    async fn test_seed_dict() {
+        let mut storage = InMemory::new();
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut storage, 2).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+
+        let update_result = add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        let sum_dict = storage.sum_dict().await.unwrap().unwrap();
+        let seed_dict = create_seed_dict(sum_dict, &local_seed_dicts);
+
+        assert_eq!(seed_dict, storage.seed_dict().await.unwrap().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_seed_dict_len_mis_match() {
+        let mut storage = InMemory::new();
+
+        let mut sum_pks = create_and_add_sum_participant_entries(&mut storage, 2).await;
+        sum_pks.pop();
+
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+        update_result.into_iter().for_each(|res| {
+            assert!(matches!(
+                res.into_inner().unwrap_err(),
+                LocalSeedDictAddError::LengthMisMatch
+            ))
+        });
+    }
+
+    #[tokio::test]
+    async fn test_seed_dict_unknown_sum_participant() {
+        let mut storage = InMemory::new();
+
+        let mut sum_pks = create_and_add_sum_participant_entries(&mut storage, 2).await;
+        sum_pks.pop();
+        let (pk, _) = create_sum_participant_entry();
+        sum_pks.push(pk);
+
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+        update_result.into_iter().for_each(|res| {
+            assert!(matches!(
+                res.into_inner().unwrap_err(),
+                LocalSeedDictAddError::UnknownSumParticipant
+            ))
+        });
+    }
+
+    #[tokio::test]
+    async fn test_seed_dict_update_pk_already_submitted() {
+        let mut storage = InMemory::new();
+        let sum_pks = create_and_add_sum_participant_entries(&mut storage, 2).await;
+
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+
+        let update_result = add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+        update_result.into_iter().for_each(|res| {
+            assert!(matches!(
+                res.into_inner().unwrap_err(),
+                LocalSeedDictAddError::UpdatePkAlreadySubmitted
+            ))
+        });
+    }
+
+    #[tokio::test]
+    async fn test_incr_mask_score() {
+        let mut storage = InMemory::new();
+        assert!(storage.best_masks().await.unwrap().is_none());
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut storage, 3).await;
+        let mask = create_mask_zeroed(10);
+        for sum_pk in sum_pks.iter() {
+            let res = storage.incr_mask_score(sum_pk, &mask).await.unwrap();
+            assert!(res.is_ok());
+        }
+
+        let already_submitted = storage.incr_mask_score(&sum_pks[0], &mask).await.unwrap();
+        assert!(matches!(
+            already_submitted.into_inner().unwrap_err(),
+            MaskScoreIncrError::MaskAlreadySubmitted
+        ));
+
+        let (unknown_sum_pk, _) = create_sum_participant_entry();
+        let unknown = storage
+            .incr_mask_score(&unknown_sum_pk, &mask)
+            .await
+            .unwrap();
+        assert!(matches!(
+            unknown.into_inner().unwrap_err(),
+            MaskScoreIncrError::UnknownSumPk
+        ));
+
+        let best_masks = storage.best_masks().await.unwrap().unwrap();
+        assert_eq!(best_masks, vec![(mask, 3)]);
+    }
+
+    #[tokio::test]
+    async fn test_best_masks() {
+        let mut storage = InMemory::new();
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut storage, 6).await;
+        let masks = vec![
+            create_mask(10, 1),
+            create_mask(10, 2),
+            create_mask(10, 2),
+            create_mask(10, 3),
+            create_mask(10, 3),
+            create_mask(10, 3),
+        ];
+        for (sum_pk, mask) in sum_pks.iter().zip(masks.iter()) {
+            let res = storage.incr_mask_score(sum_pk, mask).await.unwrap();
+            assert!(res.is_ok());
+        }
+
+        assert_eq!(storage.number_of_unique_masks().await.unwrap(), 3);
+        let best_masks = storage.best_masks().await.unwrap().unwrap();
+        assert_eq!(
+            best_masks,
+            vec![(create_mask(10, 3), 3), (create_mask(10, 2), 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_dicts_and_coordinator_data() {
+        let mut storage = InMemory::new();
+
+        let set_state = CoordinatorState::new(
+            pet_settings(),
+            mask_settings(),
+            model_settings(),
+            aggregation_settings(),
+            privacy_settings(),
+        );
+        storage.set_coordinator_state(&set_state).await.unwrap();
+        storage.set_latest_global_model_id("id").await.unwrap();
+        storage
+            .set_aggregation_state(&AggregationState::default())
+            .await
+            .unwrap();
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut storage, 2).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        add_local_seed_entries(&mut storage, &local_seed_dicts).await;
+        storage
+            .incr_mask_score(&sum_pks[0], &create_mask_zeroed(10))
+            .await
+            .unwrap();
+
+        storage.delete_dicts().await.unwrap();
+        assert!(storage.coordinator_state().await.unwrap().is_some());
+        assert!(storage.latest_global_model_id().await.unwrap().is_some());
+        assert!(storage.aggregation_state().await.unwrap().is_some());
+        assert!(storage.sum_dict().await.unwrap().is_none());
+        assert!(storage.seed_dict().await.unwrap().is_none());
+        assert!(storage.best_masks().await.unwrap().is_none());
+        assert_eq!(storage.number_of_unique_masks().await.unwrap(), 0);
+
+        storage.delete_coordinator_data().await.unwrap();
+        assert!(storage.coordinator_state().await.unwrap().is_none());
+        assert!(storage.latest_global_model_id().await.unwrap().is_none());
+        assert!(storage.aggregation_state().await.unwrap().is_none());
+        assert!(storage.sum_dict().await.unwrap().is_none());
+    }
+}
@@ -1 +1,3 @@
+pub mod file;
+pub mod in_memory;
 pub mod redis;
@@ -6,7 +6,7 @@ use redis::{ErrorKind, FromRedisValue, RedisError, RedisResult, RedisWrite, ToRe
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    state_machine::coordinator::CoordinatorState,
+    state_machine::{aggregation::AggregationState, coordinator::CoordinatorState},
     storage::{
         LocalSeedDictAdd,
         LocalSeedDictAddError,
@@ -169,6 +169,9 @@ macro_rules! impl_bincode_redis_traits {
 // so bincode will not panic.
 impl_bincode_redis_traits!(CoordinatorState);
 
+// AggregationState only consists of options of strings and sequences.
+impl_bincode_redis_traits!(AggregationState);
+
 #[derive(From, Into, Serialize, Deserialize)]
 pub(crate) struct MaskObjectRead(MaskObject);
 
@@ -33,9 +33,13 @@
 //!         (mask_object_1, 2), // (mask: bincode encoded string, score/counter: number)
 //!         (mask_object_2, 1)
 //!     ],
-//!     "latest_global_model_id": global_model_id
+//!     "latest_global_model_id": global_model_id,
+//!     "aggregation_state": "..." // bincode encoded string
 //! }
 //! ```
+//!
+//! A [`Client`] with a namespace (see [`Client::with_namespace`]) prefixes all of the keys above
+//! with `{namespace}:`, which allows several federated learning tasks to share a Redis database.
 
 mod impls;
 
@@ -57,7 +61,7 @@ use self::impls::{
     PublicSigningKeyWrite,
 };
 use crate::{
-    state_machine::coordinator::CoordinatorState,
+    state_machine::{aggregation::AggregationState, coordinator::CoordinatorState},
     storage::{
         CoordinatorStorage,
         LocalSeedDictAdd,
@@ -68,6 +72,7 @@ use crate::{
     },
 };
 use xaynet_core::{
+    crypto::ByteObject,
     mask::MaskObject,
     LocalSeedDict,
     SeedDict,
@@ -81,6 +86,7 @@ use xaynet_core::{
 #[derive(Clone)]
 pub struct Client {
     connection: ConnectionManager,
+    prefix: String,
 }
 
 fn to_storage_err(e: RedisError) -> StorageError {
@@ -98,14 +104,36 @@ impl Client {
     pub async fn new<T: IntoConnectionInfo>(url: T) -> Result<Self, RedisError> {
         let client = redis::Client::open(url)?;
         let connection = client.get_tokio_connection_manager().await?;
-        Ok(Self { connection })
+        Ok(Self {
+            connection,
+            prefix: String::new(),
+        })
+    }
+
+    /// Scopes the keys of the client to the given namespace.
+    ///
+    /// The client shares its connection with the client from which it was created.
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.prefix = format!("{}:", namespace);
+        self
+    }
+
+    /// Returns the namespaced name of a key.
+    fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// Returns the namespaced key of the seed dict entry of a sum participant.
+    fn seed_dict_key(&self, sum_pk: &SumParticipantPublicKey) -> Vec<u8> {
+        [self.prefix.as_bytes(), sum_pk.as_slice()].concat()
     }
 
     async fn create_flush_dicts_pipeline(&mut self) -> RedisResult<Pipeline> {
         // https://redis.io/commands/hkeys
         // > Return value:
         //   Array reply: list of fields in the hash, or an empty list when key does not exist.
-        let sum_pks: Vec<PublicSigningKeyRead> = self.connection.hkeys("sum_dict").await?;
+        let sum_pks: Vec<PublicSigningKeyRead> =
+            self.connection.hkeys(self.key("sum_dict")).await?;
         let mut pipe = redis::pipe();
 
         // https://redis.io/commands/del
@@ -116,17 +144,17 @@ impl Client {
         // We ignore the return value because we are not interested in it.
 
         // delete sum dict
-        pipe.del("sum_dict").ignore();
+        pipe.del(self.key("sum_dict")).ignore();
 
         // delete seed dict
-        pipe.del("update_participants").ignore();
+        pipe.del(self.key("update_participants")).ignore();
         for sum_pk in sum_pks {
-            pipe.del(sum_pk).ignore();
+            pipe.del(self.seed_dict_key(&sum_pk.into())).ignore();
         }
 
         // delete mask dict
-        pipe.del("mask_submitted").ignore();
-        pipe.del("mask_dict").ignore();
+        pipe.del(self.key("mask_submitted")).ignore();
+        pipe.del(self.key("mask_dict")).ignore();
         Ok(pipe)
     }
 }
@@ -141,7 +169,7 @@ impl CoordinatorStorage for Client {
         // Possible return value in our case:
         // > Simple string reply: OK if SET was executed correctly.
         self.connection
-            .set("coordinator_state", state)
+            .set(self.key("coordinator_state"), state)
             .await
             .map_err(to_storage_err)
     }
@@ -154,7 +182,7 @@ impl CoordinatorStorage for Client {
         // > Return value
         //   Bulk string reply: the value of key, or nil when key does not exist.
         self.connection
-            .get("coordinator_state")
+            .get(self.key("coordinator_state"))
             .await
             .map_err(to_storage_err)
     }
@@ -173,7 +201,7 @@ impl CoordinatorStorage for Client {
         //   0 if field already exists in the hash and no operation was performed.
         self.connection
             .hset_nx(
-                "sum_dict",
+                self.key("sum_dict"),
                 PublicSigningKeyWrite::from(pk),
                 PublicEncryptKeyWrite::from(ephm_pk),
             )
@@ -189,7 +217,7 @@ impl CoordinatorStorage for Client {
         //   list when key does not exist.
         let reply: Vec<(PublicSigningKeyRead, PublicEncryptKeyRead)> = self
             .connection
-            .hgetall("sum_dict")
+            .hgetall(self.key("sum_dict"))
             .await
             .map_err(to_storage_err)?;
 
@@ -218,26 +246,28 @@ impl CoordinatorStorage for Client {
             r#"
                 -- lua lists (tables) start at 1
                 local update_pk = ARGV[1]
+                local prefix = ARGV[2]
+                local sum_dict = prefix .. "sum_dict"
 
                 -- check if the local seed dict has the same length as the sum_dict
 
                 -- KEYS is a list (table) of key value pairs ([update_pk_1, seed_1, update_pk_2, seed_2])
                 local seed_dict_len = #KEYS / 2
-                local sum_dict_len = redis.call("HLEN", "sum_dict")
+                local sum_dict_len = redis.call("HLEN", sum_dict)
                 if seed_dict_len ~= sum_dict_len then
                     return -1
                 end
 
                 -- check if all pks of the local seed dict exists in sum_dict
                 for i = 1, #KEYS, 2 do
-                    local exist_in_sum_dict = redis.call("HEXISTS", "sum_dict", KEYS[i])
+                    local exist_in_sum_dict = redis.call("HEXISTS", sum_dict, KEYS[i])
                     if exist_in_sum_dict == 0 then
                         return -2
                     end
                 end
 
                 -- check if one pk of the local seed dict already exists in seed_dict
-                local exist_in_seed_dict = redis.call("SADD", "update_participants", update_pk)
+                local exist_in_seed_dict = redis.call("SADD", prefix .. "update_participants", update_pk)
                 -- SADD returns 0 if the key already exists
                 if exist_in_seed_dict == 0 then
                     return -3
@@ -245,7 +275,7 @@ impl CoordinatorStorage for Client {
 
                 -- update the seed dict
                 for i = 1, #KEYS, 2 do
-                    local exist_in_update_seed_dict = redis.call("HSETNX", KEYS[i], update_pk, KEYS[i + 1])
+                    local exist_in_update_seed_dict = redis.call("HSETNX", prefix .. KEYS[i], update_pk, KEYS[i + 1])
                     -- HSETNX returns 0 if the key already exists
                     if exist_in_update_seed_dict == 0 then
                         -- This condition should never apply.
@@ -261,6 +291,7 @@ impl CoordinatorStorage for Client {
         script
             .key(LocalSeedDictWrite::from(local_seed_dict))
             .arg(PublicSigningKeyWrite::from(update_pk))
+            .arg(&self.prefix)
             .invoke_async(&mut self.connection)
             .await
             .map_err(to_storage_err)
@@ -273,7 +304,8 @@ impl CoordinatorStorage for Client {
         // https://redis.io/commands/hkeys
         // > Return value:
         //   Array reply: list of fields in the hash, or an empty list when key does not exist.
-        let sum_pks: Vec<PublicSigningKeyRead> = self.connection.hkeys("sum_dict").await?;
+        let sum_pks: Vec<PublicSigningKeyRead> =
+            self.connection.hkeys(self.key("sum_dict")).await?;
 
         if sum_pks.is_empty() {
             return Ok(None);
@@ -281,14 +313,15 @@ impl CoordinatorStorage for Client {
 
         let mut seed_dict: SeedDict = SeedDict::new();
         for sum_pk in sum_pks {
+            let sum_pk = sum_pk.into();
             // https://redis.io/commands/hgetall
             // > Return value
             //   Array reply: list of fields and their values stored in the hash, or an empty
             //   list when key does not exist.
             let sum_pk_seed_dict: HashMap<PublicSigningKeyRead, EncryptedMaskSeedRead> =
-                self.connection.hgetall(&sum_pk).await?;
+                self.connection.hgetall(self.seed_dict_key(&sum_pk)).await?;
             seed_dict.insert(
-                sum_pk.into(),
+                sum_pk,
                 sum_pk_seed_dict
                     .into_iter()
                     .map(|(pk, seed)| (pk.into(), seed.into()))
@@ -310,24 +343,25 @@ impl CoordinatorStorage for Client {
             r#"
                 -- lua lists (tables) start at 1
                 local sum_pk = ARGV[1]
+                local prefix = ARGV[2]
 
                 -- check if the client participated in sum phase
                 --
                 -- Note: we cannot delete the sum_pk in the sum_dict because we
                 -- need the sum_dict later to delete the seed_dict
-                local sum_pk_exist = redis.call("HEXISTS", "sum_dict", sum_pk)
+                local sum_pk_exist = redis.call("HEXISTS", prefix .. "sum_dict", sum_pk)
                 if sum_pk_exist == 0 then
                     return -1
                 end
 
                 -- check if sum participant has not already submitted a mask
-                local mask_already_submitted = redis.call("SADD", "mask_submitted", sum_pk)
+                local mask_already_submitted = redis.call("SADD", prefix .. "mask_submitted", sum_pk)
                 -- SADD returns 0 if the key already exists
                 if mask_already_submitted == 0 then
                     return -2
                 end
 
-                redis.call("ZINCRBY", "mask_dict", 1, KEYS[1])
+                redis.call("ZINCRBY", prefix .. "mask_dict", 1, KEYS[1])
 
                 return 0
             "#,
@@ -336,6 +370,7 @@ impl CoordinatorStorage for Client {
         script
             .key(MaskObjectWrite::from(mask))
             .arg(PublicSigningKeyWrite::from(sum_pk))
+            .arg(&self.prefix)
             .invoke_async(&mut self.connection)
             .await
             .map_err(to_storage_err)
@@ -349,7 +384,7 @@ impl CoordinatorStorage for Client {
         //   in case the WITHSCORES option is given).
         let reply: Vec<(MaskObjectRead, u64)> = self
             .connection
-            .zrevrange_withscores("mask_dict", 0, 1)
+            .zrevrange_withscores(self.key("mask_dict"), 0, 1)
             .await?;
 
         let result = match reply.is_empty() {
@@ -373,7 +408,7 @@ impl CoordinatorStorage for Client {
         // > Return value:
         //   Integer reply: the number of elements in the specified score range.
         self.connection
-            .zcount("mask_dict", "-inf", "+inf")
+            .zcount(self.key("mask_dict"), "-inf", "+inf")
             .await
             .map_err(to_storage_err)
     }
@@ -383,8 +418,9 @@ impl CoordinatorStorage for Client {
     async fn delete_coordinator_data(&mut self) -> StorageResult<()> {
         debug!("flush coordinator data");
         let mut pipe = self.create_flush_dicts_pipeline().await?;
-        pipe.del("coordinator_state").ignore();
-        pipe.del("latest_global_model_id").ignore();
+        pipe.del(self.key("coordinator_state")).ignore();
+        pipe.del(self.key("latest_global_model_id")).ignore();
+        pipe.del(self.key("aggregation_state")).ignore();
         pipe.atomic()
             .query_async(&mut self.connection)
             .await
@@ -410,7 +446,7 @@ impl CoordinatorStorage for Client {
         // Possible return value in our case:
         // > Simple string reply: OK if SET was executed correctly.
         self.connection
-            .set("latest_global_model_id", global_model_id)
+            .set(self.key("latest_global_model_id"), global_model_id)
             .await
             .map_err(to_storage_err)
     }
@@ -424,7 +460,28 @@ impl CoordinatorStorage for Client {
         // > Return value
         //   Bulk string reply: the value of key, or nil when key does not exist.
         self.connection
-            .get("latest_global_model_id")
+            .get(self.key("latest_global_model_id"))
+            .await
+            .map_err(to_storage_err)
+    }
+
+    async fn set_aggregation_state(&mut self, state: &AggregationState) -> StorageResult<()> {
+        debug!("set aggregation state");
+        // https://redis.io/commands/set
+        // > Set key to hold the string value. If key already holds a value,
+        //   it is overwritten, regardless of its type.
+        self.connection
+            .set(self.key("aggregation_state"), state)
+            .await
+            .map_err(to_storage_err)
+    }
+
+    async fn aggregation_state(&mut self) -> StorageResult<Option<AggregationState>> {
+        debug!("get aggregation state");
+        // https://redis.io/commands/get
+        // > Get the value of key. If the key does not exist the special value nil is returned.
+        self.connection
+            .get(self.key("aggregation_state"))
             .await
             .map_err(to_storage_err)
     }
@@ -454,7 +511,7 @@ impl Client {
         //   Integer reply: the number of fields that were removed from the hash,
         //   not including specified but non existing fields.
         self.connection
-            .hdel("sum_dict", PublicSigningKeyWrite::from(pk))
+            .hdel(self.key("sum_dict"), PublicSigningKeyWrite::from(pk))
             .await
     }
 
@@ -463,7 +520,7 @@ impl Client {
         // https://redis.io/commands/hlen
         // > Return value
         //   Integer reply: number of fields in the hash, or 0 when key does not exist.
-        self.connection.hlen("sum_dict").await
+        self.connection.hlen(self.key("sum_dict")).await
     }
 
     // Returns the [`SumParticipantPublicKey`] of the [`SumDict`] or an empty list when the
@@ -475,7 +532,7 @@ impl Client {
         // > Return value:
         //   Array reply: list of fields in the hash, or an empty list when key does not exist.
         let result: std::collections::HashSet<PublicSigningKeyRead> =
-            self.connection.hkeys("sum_dict").await?;
+            self.connection.hkeys(self.key("sum_dict")).await?;
         let sum_pks = result.into_iter().map(|pk| pk.into()).collect();
 
         Ok(sum_pks)
@@ -488,15 +545,17 @@ impl Client {
     ) -> RedisResult<u64> {
         self.connection
             .srem(
-                "update_participants",
+                self.key("update_participants"),
                 PublicSigningKeyWrite::from(update_pk),
             )
             .await
     }
 
     pub async fn mask_submitted_set(&mut self) -> RedisResult<Vec<SumParticipantPublicKey>> {
-        let result: Vec<PublicSigningKeyRead> =
-            self.connection.smembers("update_submitted").await?;
+        let result: Vec<PublicSigningKeyRead> = self
+            .connection
+            .smembers(self.key("update_submitted"))
+            .await?;
         let sum_pks = result.into_iter().map(|pk| pk.into()).collect();
         Ok(sum_pks)
     }
@@ -521,10 +580,8 @@ impl Client {
         // > Return value
         //   Array reply: list of fields and their values stored in the hash, or an empty
         //   list when key does not exist.
-        let result: Vec<(PublicSigningKeyRead, EncryptedMaskSeedRead)> = self
-            .connection
-            .hgetall(PublicSigningKeyWrite::from(sum_pk))
-            .await?;
+        let result: Vec<(PublicSigningKeyRead, EncryptedMaskSeedRead)> =
+            self.connection.hgetall(self.seed_dict_key(sum_pk)).await?;
         let seed_dict = result
             .into_iter()
             .map(|(pk, seed)| (pk.into(), seed.into()))
@@ -546,11 +603,17 @@ impl Client {
 }
 
 #[cfg(test)]
-pub(in crate) mod tests {
+pub(crate) mod tests {
     use self::impls::SumDictDeleteError;
     use super::*;
     use crate::{
-        state_machine::tests::utils::{mask_settings, model_settings, pet_settings},
+        state_machine::tests::utils::{
+            aggregation_settings,
+            mask_settings,
+            model_settings,
+            pet_settings,
+            privacy_settings,
+        },
         storage::{tests::utils::*, LocalSeedDictAddError, MaskScoreIncrError, SumPartAddError},
     };
     use serial_test::serial;
@@ -571,7 +634,13 @@ pub(in crate) mod tests {
         // test the writing and reading of the coordinator state
         let mut client = init_client().await;
 
-        let set_state = CoordinatorState::new(pet_settings(), mask_settings(), model_settings());
+        let set_state = CoordinatorState::new(
+            pet_settings(),
+            mask_settings(),
+            model_settings(),
+            aggregation_settings(),
+            privacy_settings(),
+        );
         client.set_coordinator_state(&set_state).await.unwrap();
 
         let get_state = client.coordinator_state().await.unwrap().unwrap();
@@ -951,7 +1020,13 @@ pub(in crate) mod tests {
         let mut client = init_client().await;
 
         // write some data into redis
-        let set_state = CoordinatorState::new(pet_settings(), mask_settings(), model_settings());
+        let set_state = CoordinatorState::new(
+            pet_settings(),
+            mask_settings(),
+            model_settings(),
+            aggregation_settings(),
+            privacy_settings(),
+        );
         let res = client.set_coordinator_state(&set_state).await;
         assert!(res.is_ok());
 
@@ -1000,13 +1075,24 @@ pub(in crate) mod tests {
         let mut client = init_client().await;
 
         // write some data into redis
-        let set_state = CoordinatorState::new(pet_settings(), mask_settings(), model_settings());
+        let set_state = CoordinatorState::new(
+            pet_settings(),
+            mask_settings(),
+            model_settings(),
+            aggregation_settings(),
+            privacy_settings(),
+        );
         let res = client.set_coordinator_state(&set_state).await;
         assert!(res.is_ok());
 
         let res = client.set_latest_global_model_id("global_model_id").await;
         assert!(res.is_ok());
 
+        let res = client
+            .set_aggregation_state(&AggregationState::default())
+            .await;
+        assert!(res.is_ok());
+
         let sum_pks = create_and_add_sum_participant_entries(&mut client, 2).await;
 
         let local_seed_dicts = create_local_seed_entries(&sum_pks);
@@ -1041,6 +1127,25 @@ pub(in crate) mod tests {
         assert_eq!(set_id, get_id)
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn integration_set_and_get_aggregation_state() {
+        // test the writing and reading of the aggregation state
+        let mut client = init_client().await;
+        assert!(client.aggregation_state().await.unwrap().is_none());
+
+        let set_state = AggregationState {
+            global_model_id: Some("global_model_id".to_string()),
+            first_moment: Some(vec![0.5, -1.]),
+            second_moment: Some(vec![0.25, 1.]),
+        };
+        client.set_aggregation_state(&set_state).await.unwrap();
+
+        let get_state = client.aggregation_state().await.unwrap().unwrap();
+
+        assert_eq!(set_state, get_state)
+    }
+
     #[tokio::test]
     #[serial]
     async fn integration_is_ready_ok() {
@@ -1061,4 +1166,43 @@ pub(in crate) mod tests {
 
         assert_eq!(None, get_id)
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn integration_namespaces_are_isolated() {
+        // test that the data of clients with different namespaces doesn't interfere
+        let mut client = init_client().await;
+        let mut task_client = client.clone().with_namespace("task");
+
+        let sum_pks = create_and_add_sum_participant_entries(&mut task_client, 2).await;
+        let local_seed_dicts = create_local_seed_entries(&sum_pks);
+        let update_result = add_local_seed_entries(&mut task_client, &local_seed_dicts).await;
+        update_result.iter().for_each(|res| assert!(res.is_ok()));
+        let mask = create_mask_zeroed(10);
+        task_client
+            .incr_mask_score(sum_pks.get(0).unwrap(), &mask)
+            .await
+            .unwrap();
+        task_client
+            .set_latest_global_model_id("global_model_id")
+            .await
+            .unwrap();
+
+        assert!(client.sum_dict().await.unwrap().is_none());
+        assert!(client.seed_dict().await.unwrap().is_none());
+        assert!(client.best_masks().await.unwrap().is_none());
+        assert!(client.latest_global_model_id().await.unwrap().is_none());
+
+        assert_eq!(task_client.sum_dict().await.unwrap().unwrap().len(), 2);
+        assert_eq!(task_client.seed_dict().await.unwrap().unwrap().len(), 2);
+        assert!(task_client.best_masks().await.unwrap().is_some());
+
+        // all keys of the namespaced client are prefixed
+        let keys: Vec<Vec<u8>> = client.connection.keys("*").await.unwrap();
+        assert!(!keys.is_empty());
+        assert!(keys.iter().all(|key| key.starts_with(b"task:")));
+
+        task_client.delete_coordinator_data().await.unwrap();
+        assert!(client.keys().await.unwrap().is_empty());
+    }
 }
@@ -0,0 +1,363 @@
+//! A filesystem [`ModelStorage`].
+//!
+//! The [`FileStorage`] writes every global model into its own file in a directory. The file name
+//! is the global model id, see [`ModelStorage::create_global_model_id`]. A model is first
+//! written to a temporary file and then renamed, so that a model file is either complete or
+//! doesn't exist at all. Optionally, only the most recently written global models are retained.
+
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use thiserror::Error;
+use tokio::task;
+use tracing::{debug, warn};
+
+use crate::storage::{coordinator_storage::file::sync_dir, ModelStorage, StorageResult};
+use xaynet_core::{common::RoundSeed, mask::Model};
+
+/// The extension of the temporary files which are written before they are renamed.
+const TMP_EXTENSION: &str = "tmp";
+
+#[derive(Debug, Error)]
+pub enum FileStorageError {
+    #[error("global model {0} already exists")]
+    ModelAlreadyExists(String),
+    #[error("invalid global model id {0}")]
+    InvalidModelId(String),
+    #[error("model directory {0} is not a directory")]
+    NotADirectory(PathBuf),
+}
+
+#[derive(Clone)]
+pub struct FileStorage {
+    dir: Arc<PathBuf>,
+    retention: Option<usize>,
+    /// The paths of the stored global models in the order in which they were written.
+    models: Arc<Mutex<VecDeque<PathBuf>>>,
+}
+
+impl FileStorage {
+    /// Creates a new filesystem model storage which stores the global models in the given
+    /// directory. The directory is created if it doesn't exist yet.
+    ///
+    /// If a `retention` is given, only the `retention` most recently written global models are
+    /// kept and older global models are deleted whenever a new global model is set. The global
+    /// models which already exist in the directory are ranked by their modification times, they
+    /// are older than all global models which are written afterwards. The round ids don't
+    /// determine the rank, since they start again at `1` if the coordinator state isn't restored.
+    ///
+    /// # Errors
+    /// Fails if the directory cannot be created or read.
+    pub async fn new(dir: impl AsRef<Path>, retention: Option<usize>) -> StorageResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let (dir, models) = task::spawn_blocking(move || -> StorageResult<_> {
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create model directory {}", dir.display()))?;
+            let models = Self::read_models(&dir)
+                .with_context(|| format!("failed to read model directory {}", dir.display()))?;
+            Ok((dir, models))
+        })
+        .await??;
+
+        Ok(Self {
+            dir: Arc::new(dir),
+            retention,
+            models: Arc::new(Mutex::new(models)),
+        })
+    }
+
+    // Returns the path of the global model with the given id.
+    fn model_path(dir: &Path, id: &str) -> StorageResult<PathBuf> {
+        // the id must not be able to escape the model directory
+        if id.is_empty() || id.contains(|c: char| matches!(c, '/' | '\\' | '.')) {
+            return Err(FileStorageError::InvalidModelId(id.to_string()).into());
+        }
+        Ok(dir.join(id))
+    }
+
+    // Returns the round id of a global model file, if the file is a global model file.
+    fn round_id(path: &Path) -> Option<u64> {
+        if path.extension().is_some() {
+            return None;
+        }
+        let file_name = path.file_name()?.to_str()?;
+        let (round_id, _round_seed) = file_name.split_at(file_name.find('_')?);
+        round_id.parse().ok()
+    }
+
+    // Returns the paths of the existing global models ordered by their modification times.
+    fn read_models(dir: &Path) -> io::Result<VecDeque<PathBuf>> {
+        let mut models = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if let Some(round_id) = Self::round_id(&path) {
+                let modified = entry
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                models.push((modified, round_id, path));
+            }
+        }
+        models.sort_unstable();
+        Ok(models.into_iter().map(|(_, _, path)| path).collect())
+    }
+
+    // Deletes all global models except for the `retention` most recently written ones.
+    fn apply_retention(models: &mut VecDeque<PathBuf>, retention: usize) {
+        while models.len() > retention {
+            // UNWRAP_SAFE: there are more models than retained
+            let path = models.pop_front().unwrap();
+            debug!("delete outdated global model {}", path.display());
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => warn!(
+                    "failed to delete outdated global model {}: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+    }
+}
+
+// Writes the data to a temporary file and renames it to the given path afterwards.
+fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let tmp_path = path.with_extension(TMP_EXTENSION);
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp_path, path)?;
+    sync_dir(path)
+}
+
+#[async_trait]
+impl ModelStorage for FileStorage {
+    async fn set_global_model(
+        &mut self,
+        round_id: u64,
+        round_seed: &RoundSeed,
+        global_model: &Model,
+    ) -> StorageResult<String> {
+        let id = Self::create_global_model_id(round_id, round_seed);
+        debug!("write global model: {}", id);
+
+        let dir = self.dir.clone();
+        let retention = self.retention;
+        let models = self.models.clone();
+        let data = bincode::serialize(global_model)?;
+        task::spawn_blocking(move || -> StorageResult<_> {
+            let path = Self::model_path(&dir, &id)?;
+            // the lock keeps the order of the models consistent with the order of the writes
+            let mut models = models.lock().unwrap_or_else(PoisonError::into_inner);
+            if path.exists() {
+                return Err(FileStorageError::ModelAlreadyExists(id).into());
+            }
+            write_atomically(&path, &data)
+                .with_context(|| format!("failed to write global model {}", path.display()))?;
+
+            models.push_back(path);
+            if let Some(retention) = retention {
+                Self::apply_retention(&mut models, retention);
+            }
+            Ok(id)
+        })
+        .await?
+    }
+
+    async fn global_model(&mut self, id: &str) -> StorageResult<Option<Model>> {
+        debug!("read global model {}", id);
+        let path = Self::model_path(&self.dir, id)?;
+        task::spawn_blocking(move || -> StorageResult<_> {
+            let data = match fs::read(&path) {
+                Ok(data) => data,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("failed to read global model {}", path.display()))
+                }
+            };
+            let model = bincode::deserialize(&data)?;
+            Ok(Some(model))
+        })
+        .await?
+    }
+
+    async fn is_ready(&mut self) -> StorageResult<()> {
+        let dir = self.dir.clone();
+        task::spawn_blocking(move || -> StorageResult<_> {
+            let metadata = fs::metadata(&*dir)
+                .with_context(|| format!("model directory {} unavailable", dir.display()))?;
+            if !metadata.is_dir() {
+                return Err(FileStorageError::NotADirectory(dir.to_path_buf()).into());
+            }
+            Ok(())
+        })
+        .await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::utils::create_global_model;
+    use tempfile::TempDir;
+    use xaynet_core::crypto::ByteObject;
+
+    async fn create_storage(dir: &TempDir, retention: Option<usize>) -> FileStorage {
+        FileStorage::new(dir.path().join("models"), retention)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_set_and_get_global_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = create_storage(&dir, None).await;
+        storage.is_ready().await.unwrap();
+
+        let global_model = create_global_model(10);
+        let round_seed = RoundSeed::generate();
+        let id = storage
+            .set_global_model(1, &round_seed, &global_model)
+            .await
+            .unwrap();
+        assert_eq!(id, FileStorage::create_global_model_id(1, &round_seed));
+
+        let downloaded = storage.global_model(&id).await.unwrap().unwrap();
+        assert_eq!(global_model, downloaded);
+
+        // no temporary files are left behind
+        let files = fs::read_dir(dir.path().join("models")).unwrap().count();
+        assert_eq!(files, 1);
+    }
+
+    #[tokio::test]
+    async fn test_set_global_model_already_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = create_storage(&dir, None).await;
+
+        let global_model = create_global_model(10);
+        let round_seed = RoundSeed::generate();
+        storage
+            .set_global_model(1, &round_seed, &global_model)
+            .await
+            .unwrap();
+        assert!(storage
+            .set_global_model(1, &round_seed, &global_model)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_global_model_non_existent() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = create_storage(&dir, None).await;
+
+        let id = FileStorage::create_global_model_id(1, &RoundSeed::generate());
+        assert!(storage.global_model(&id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_global_model_invalid_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = create_storage(&dir, None).await;
+
+        assert!(storage.global_model("../1_00").await.is_err());
+        assert!(storage.global_model("").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = create_storage(&dir, Some(2)).await;
+
+        let global_model = create_global_model(10);
+        let mut ids = Vec::new();
+        for round_id in 1..=4 {
+            let id = storage
+                .set_global_model(round_id, &RoundSeed::generate(), &global_model)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        assert!(storage.global_model(&ids[0]).await.unwrap().is_none());
+        assert!(storage.global_model(&ids[1]).await.unwrap().is_none());
+        assert!(storage.global_model(&ids[2]).await.unwrap().is_some());
+        assert!(storage.global_model(&ids[3]).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_retention_after_round_id_reset() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = create_storage(&dir, Some(2)).await;
+
+        let global_model = create_global_model(10);
+        let mut ids = Vec::new();
+        // the round ids start again at 1 if the coordinator state isn't restored
+        for round_id in [5, 6, 7, 1].iter() {
+            let id = storage
+                .set_global_model(*round_id, &RoundSeed::generate(), &global_model)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        assert!(storage.global_model(&ids[0]).await.unwrap().is_none());
+        assert!(storage.global_model(&ids[1]).await.unwrap().is_none());
+        assert!(storage.global_model(&ids[2]).await.unwrap().is_some());
+        assert!(storage.global_model(&ids[3]).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_retention_of_existing_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = create_storage(&dir, None).await;
+
+        let global_model = create_global_model(10);
+        let mut ids = Vec::new();
+        for round_id in 5..=7 {
+            let id = storage
+                .set_global_model(round_id, &RoundSeed::generate(), &global_model)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        // the existing models are older than the models written after a restart
+        let mut storage = create_storage(&dir, Some(2)).await;
+        let id = storage
+            .set_global_model(1, &RoundSeed::generate(), &global_model)
+            .await
+            .unwrap();
+
+        assert!(storage.global_model(&id).await.unwrap().is_some());
+        assert!(storage.global_model(&ids[2]).await.unwrap().is_some());
+        assert!(storage.global_model(&ids[0]).await.unwrap().is_none());
+        assert!(storage.global_model(&ids[1]).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_is_ready_not_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut storage = create_storage(&dir, None).await;
+
+        let models_dir = dir.path().join("models");
+        fs::remove_dir(&models_dir).unwrap();
+        assert!(storage.is_ready().await.is_err());
+
+        fs::write(&models_dir, b"").unwrap();
+        assert!(storage.is_ready().await.is_err());
+    }
+}
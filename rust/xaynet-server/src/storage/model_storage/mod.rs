@@ -1,3 +1,4 @@
+pub mod file;
 pub mod noop;
 #[cfg(feature = "model-persistence")]
 pub mod s3;
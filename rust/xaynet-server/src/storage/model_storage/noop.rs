@@ -20,6 +20,10 @@ impl ModelStorage for NoOp {
         Err(anyhow::anyhow!("No-op model store"))
     }
 
+    fn is_persistent(&self) -> bool {
+        false
+    }
+
     async fn is_ready(&mut self) -> StorageResult<()> {
         Ok(())
     }
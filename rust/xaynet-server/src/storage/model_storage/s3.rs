@@ -67,6 +67,7 @@ pub enum ClientError {
 pub struct Client {
     buckets: Arc<S3BucketsSettings>,
     client: S3Client,
+    prefix: String,
 }
 
 impl Client {
@@ -104,9 +105,24 @@ impl Client {
         Ok(Self {
             buckets: Arc::new(settings.buckets),
             client: S3Client::new_with(dispatcher, credentials_provider, settings.region),
+            prefix: String::new(),
         })
     }
 
+    /// Scopes the global models of the client to the given namespace.
+    ///
+    /// The global models are stored in the same bucket under the object key prefix
+    /// `{namespace}/`.
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.prefix = format!("{}/", namespace);
+        self
+    }
+
+    // Returns the namespaced object key of a global model.
+    fn object_key(&self, id: &str) -> String {
+        format!("{}{}", self.prefix, id)
+    }
+
     /// Creates the `global models` bucket.
     /// This method does not fail if the bucket already exists or is already owned by you.
     pub async fn create_global_models_bucket(&self) -> ClientResult<()> {
@@ -188,7 +204,7 @@ impl ModelStorage for Client {
 
         debug!("upload global model: {}", id);
         let output = self
-            .fetch_object_meta(&self.buckets.global_models, &id)
+            .fetch_object_meta(&self.buckets.global_models, &self.object_key(&id))
             .await;
         if output.is_ok() {
             return Err(anyhow::anyhow!(ClientError::ObjectAlreadyExists(
@@ -197,7 +213,7 @@ impl ModelStorage for Client {
         };
 
         let data = bincode::serialize(global_model).map_err(ClientError::Serialization)?;
-        self.upload_object(&self.buckets.global_models, &id, data)
+        self.upload_object(&self.buckets.global_models, &self.object_key(&id), data)
             .await
             .map(|_| Ok(id))?
     }
@@ -205,7 +221,7 @@ impl ModelStorage for Client {
     async fn global_model(&mut self, id: &str) -> StorageResult<Option<Model>> {
         debug!("download global model {}", id);
         let output = self
-            .fetch_object_meta(&self.buckets.global_models, id)
+            .fetch_object_meta(&self.buckets.global_models, &self.object_key(id))
             .await;
         let object_meta = match output {
             Err(RusotoError::Service(GetObjectError::NoSuchKey(_))) => return Ok(None),
@@ -441,6 +457,23 @@ pub(in crate) mod tests {
         assert_eq!(global_model, downloaded_global_model)
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn integration_test_namespaces_are_isolated() {
+        let mut client = init_client().await;
+        let mut task_client = client.clone().with_namespace("task");
+
+        let global_model = create_global_model(10);
+        let id = task_client
+            .set_global_model(1, &RoundSeed::generate(), &global_model)
+            .await
+            .unwrap();
+
+        assert!(client.global_model(&id).await.unwrap().is_none());
+        let downloaded_global_model = task_client.global_model(&id).await.unwrap().unwrap();
+        assert_eq!(global_model, downloaded_global_model)
+    }
+
     #[tokio::test]
     #[serial]
     async fn integration_test_is_ready_ok() {
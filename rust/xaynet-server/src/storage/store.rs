@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 
 use crate::{
-    state_machine::coordinator::CoordinatorState,
+    state_machine::{aggregation::AggregationState, coordinator::CoordinatorState},
     storage::{
         CoordinatorStorage,
         LocalSeedDictAdd,
@@ -135,6 +135,14 @@ where
         self.coordinator.latest_global_model_id().await
     }
 
+    async fn set_aggregation_state(&mut self, state: &AggregationState) -> StorageResult<()> {
+        self.coordinator.set_aggregation_state(state).await
+    }
+
+    async fn aggregation_state(&mut self) -> StorageResult<Option<AggregationState>> {
+        self.coordinator.aggregation_state().await
+    }
+
     async fn is_ready(&mut self) -> StorageResult<()> {
         self.coordinator.is_ready().await
     }
@@ -161,6 +169,10 @@ where
         self.model.global_model(id).await
     }
 
+    fn is_persistent(&self) -> bool {
+        self.model.is_persistent()
+    }
+
     async fn is_ready(&mut self) -> StorageResult<()> {
         self.model.is_ready().await
     }
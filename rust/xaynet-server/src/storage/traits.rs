@@ -6,7 +6,7 @@ use displaydoc::Display;
 use num_enum::TryFromPrimitive;
 use thiserror::Error;
 
-use crate::state_machine::coordinator::CoordinatorState;
+use crate::state_machine::{aggregation::AggregationState, coordinator::CoordinatorState};
 use xaynet_core::{
     common::RoundSeed,
     crypto::ByteObject,
@@ -125,7 +125,7 @@ where
     /// Returns the number of unique masks.
     async fn number_of_unique_masks(&mut self) -> StorageResult<u64>;
 
-    /// Deletes all coordinator data. This includes the coordinator
+    /// Deletes all coordinator data. This includes the coordinator state and the aggregation
     /// state as well as the [`SumDict`], [`SeedDict`] and `mask` dictionary.
     async fn delete_coordinator_data(&mut self) -> StorageResult<()>;
 
@@ -149,6 +149,22 @@ where
     /// - If the global model id exists, return `StorageResult::Ok(Some(String)))`.
     async fn latest_global_model_id(&mut self) -> StorageResult<Option<String>>;
 
+    /// Sets the [`AggregationState`].
+    ///
+    /// # Behavior
+    ///
+    /// - If no state has been set yet, set the state and return `StorageResult::Ok(())`.
+    /// - If a state already exists, override the state and return `StorageResult::Ok(())`.
+    async fn set_aggregation_state(&mut self, state: &AggregationState) -> StorageResult<()>;
+
+    /// Returns the [`AggregationState`].
+    ///
+    /// # Behavior
+    ///
+    /// - If no state has been set yet, return `StorageResult::Ok(Option::None)`.
+    /// - If a state exists, return `StorageResult::Ok(Some(AggregationState))`.
+    async fn aggregation_state(&mut self) -> StorageResult<Option<AggregationState>>;
+
     /// Checks if the [`CoordinatorStorage`] is ready to process requests.
     ///
     /// # Behavior
@@ -197,6 +213,15 @@ where
         format!("{}_{}", round_id, round_seed)
     }
 
+    /// Checks if the [`ModelStorage`] keeps the global models.
+    ///
+    /// The id of the latest global model is only stored for persistent model storages, because
+    /// the global model couldn't be restored with it otherwise. The default implementation
+    /// returns `true`.
+    fn is_persistent(&self) -> bool {
+        true
+    }
+
     /// Checks if the [`ModelStorage`] is ready to process requests.
     ///
     /// # Behavior